@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use aws_lc_rs::digest;
+use bytes::Bytes;
+use dashmap::DashMap;
+use hyper::{
+    Method, Response, StatusCode, body::Incoming, header, header::HeaderValue,
+};
+use once_cell::sync::Lazy;
+use rcgen::{CertificateParams, CustomExtension, KeyPair};
+use rustls::{
+    crypto::aws_lc_rs::sign::any_supported_type,
+    pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+    sign::CertifiedKey,
+};
+
+use super::error::AcmeError;
+use crate::{
+    GATEWAY_LOGGER_DOMAIN, debug_log,
+    gateway::{
+        chain::{Middleware, Next},
+        context::Context,
+        response::{BoxBodyType, ResponseBuilder},
+    },
+    info_log,
+};
+
+pub const HTTP01_PATH_PREFIX: &str = "/.well-known/acme-challenge/";
+pub const ACME_TLS_ALPN_PROTOCOL: &[u8] = b"acme-tls/1";
+
+/// Pending challenge answers, shared by the issuer and every listener.
+#[derive(Default)]
+pub struct ChallengeStore {
+    http01: DashMap<String, String>,
+    tls_alpn01: DashMap<String, Arc<CertifiedKey>>,
+}
+
+static CHALLENGES: Lazy<ChallengeStore> = Lazy::new(ChallengeStore::default);
+
+pub fn acme_challenges() -> &'static ChallengeStore {
+    &CHALLENGES
+}
+
+impl ChallengeStore {
+    pub fn add_http01(&self, token: &str, key_authorization: &str) {
+        self.http01
+            .insert(token.to_string(), key_authorization.to_string());
+    }
+
+    pub fn remove_http01(&self, token: &str) {
+        self.http01.remove(token);
+    }
+
+    pub fn http01_response(&self, path: &str) -> Option<String> {
+        let token = path.strip_prefix(HTTP01_PATH_PREFIX)?;
+        self.http01.get(token).map(|entry| entry.value().clone())
+    }
+
+    /// Builds the RFC 8737 validation certificate for `domain`.
+    pub fn add_tls_alpn01(
+        &self,
+        domain: &str,
+        key_authorization: &str,
+    ) -> Result<(), AcmeError> {
+        let key_error = |e: rcgen::Error| AcmeError::Key(e.to_string());
+        let digest =
+            digest::digest(&digest::SHA256, key_authorization.as_bytes());
+        let mut params = CertificateParams::new(vec![domain.to_string()])
+            .map_err(key_error)?;
+        params.custom_extensions =
+            vec![CustomExtension::new_acme_identifier(digest.as_ref())];
+        let key_pair = KeyPair::generate().map_err(key_error)?;
+        let cert = params.self_signed(&key_pair).map_err(key_error)?;
+
+        let private_key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            key_pair.serialize_der(),
+        ));
+        let signing_key = any_supported_type(&private_key)
+            .map_err(|e| AcmeError::Key(e.to_string()))?;
+        self.tls_alpn01.insert(
+            domain.to_ascii_lowercase(),
+            Arc::new(CertifiedKey::new(vec![cert.der().clone()], signing_key)),
+        );
+        Ok(())
+    }
+
+    pub fn remove_tls_alpn01(&self, domain: &str) {
+        self.tls_alpn01.remove(&domain.to_ascii_lowercase());
+    }
+
+    pub fn tls_alpn01_certificate(
+        &self,
+        server_name: Option<&str>,
+    ) -> Option<Arc<CertifiedKey>> {
+        let name = server_name?.to_ascii_lowercase();
+        self.tls_alpn01
+            .get(&name)
+            .map(|entry| entry.value().clone())
+    }
+}
+
+/// Serves pending HTTP-01 tokens ahead of every other route.
+#[derive(Clone)]
+pub struct AcmeChallengeMiddleware;
+
+#[async_trait]
+impl Middleware for AcmeChallengeMiddleware {
+    async fn handle(
+        &self,
+        ctx: Context,
+        body: Option<Incoming>,
+        next: Next,
+    ) -> Response<BoxBodyType> {
+        if ctx.method != Method::GET
+            || !ctx.path.starts_with(HTTP01_PATH_PREFIX)
+        {
+            return next(ctx, body).await;
+        }
+
+        debug_log!(
+            GATEWAY_LOGGER_DOMAIN,
+            "Starting acme challenge middleware..."
+        );
+
+        match acme_challenges().http01_response(&ctx.path) {
+            Some(key_authorization) => {
+                info_log!(
+                    GATEWAY_LOGGER_DOMAIN,
+                    "acme_http01_challenge_served path={}",
+                    ctx.path
+                );
+                ResponseBuilder::with_bytes(
+                    StatusCode::OK,
+                    vec![(
+                        header::CONTENT_TYPE,
+                        HeaderValue::from_static("text/plain"),
+                    )],
+                    Bytes::from(key_authorization),
+                )
+            }
+            None => next(ctx, body).await,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_parser::prelude::{FromDer, X509Certificate};
+
+    use super::{ChallengeStore, HTTP01_PATH_PREFIX};
+
+    #[test]
+    fn http01_token_is_served_until_removed() {
+        let store = ChallengeStore::default();
+        store.add_http01("tok", "tok.thumb");
+        let path = format!("{HTTP01_PATH_PREFIX}tok");
+
+        assert_eq!(store.http01_response(&path).as_deref(), Some("tok.thumb"));
+        assert_eq!(store.http01_response("/tok"), None);
+
+        store.remove_http01("tok");
+        assert_eq!(store.http01_response(&path), None);
+    }
+
+    #[test]
+    fn tls_alpn01_certificate_carries_acme_identifier() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let store = ChallengeStore::default();
+        store
+            .add_tls_alpn01("Stream.Example.com", "tok.thumb")
+            .expect("build challenge cert");
+
+        let certified = store
+            .tls_alpn01_certificate(Some("stream.example.com"))
+            .expect("challenge cert");
+        let der = certified.cert.first().expect("leaf").as_ref().to_vec();
+        let (_, cert) = X509Certificate::from_der(&der).expect("parse cert");
+        let acme_oid = "1.3.6.1.5.5.7.1.31";
+
+        assert!(
+            cert.extensions()
+                .iter()
+                .any(|ext| ext.critical && ext.oid.to_id_string() == acme_oid)
+        );
+        assert!(store.tls_alpn01_certificate(Some("other.test")).is_none());
+    }
+}
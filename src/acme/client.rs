@@ -0,0 +1,462 @@
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use bytes::Bytes;
+use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair};
+use reqwest::{
+    Client,
+    header::{ACCEPT, CONTENT_TYPE, HeaderMap, LOCATION},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{challenge::ChallengeStore, error::AcmeError, jws::AccountKey};
+use crate::config::http2::AcmeChallengeType;
+use crate::{ACME_LOGGER_DOMAIN, debug_log, info_log};
+
+const REPLAY_NONCE: &str = "replay-nonce";
+const BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_POLL_ATTEMPTS: u32 = 30;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Directory {
+    pub new_nonce: String,
+    pub new_account: String,
+    pub new_order: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Order {
+    pub status: String,
+    #[serde(default)]
+    pub authorizations: Vec<String>,
+    pub finalize: String,
+    #[serde(default)]
+    pub certificate: Option<String>,
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Identifier {
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Authorization {
+    pub identifier: Identifier,
+    pub status: String,
+    #[serde(default)]
+    pub challenges: Vec<Challenge>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Challenge {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+struct AcmeResponse {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl AcmeResponse {
+    fn json<T: for<'de> Deserialize<'de>>(
+        &self,
+        url: &str,
+    ) -> Result<T, AcmeError> {
+        serde_json::from_slice(&self.body).map_err(|e| AcmeError::Http {
+            url: url.to_string(),
+            error: format!("invalid JSON: {e}"),
+        })
+    }
+
+    fn location(&self, url: &str) -> Result<String, AcmeError> {
+        self.headers
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| AcmeError::MissingField {
+                url: url.to_string(),
+                field: "Location header",
+            })
+    }
+}
+
+/// RFC 8555 client: one account key, one order at a time.
+pub struct AcmeClient {
+    http: Client,
+    directory: Directory,
+    key: AccountKey,
+    kid: Option<String>,
+    nonce: Option<String>,
+    poll_interval: Duration,
+    poll_attempts: u32,
+}
+
+impl AcmeClient {
+    pub async fn connect(
+        http: Client,
+        directory_url: &str,
+        key: AccountKey,
+    ) -> Result<Self, AcmeError> {
+        let response = http
+            .get(directory_url)
+            .send()
+            .await
+            .map_err(|e| http_error(directory_url, e))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| http_error(directory_url, e))?;
+        if !status.is_success() {
+            return Err(AcmeError::Problem {
+                url: directory_url.to_string(),
+                status: status.as_u16(),
+                problem: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+        let directory =
+            serde_json::from_slice(&body).map_err(|e| AcmeError::Http {
+                url: directory_url.to_string(),
+                error: format!("invalid directory: {e}"),
+            })?;
+
+        Ok(Self {
+            http,
+            directory,
+            key,
+            kid: None,
+            nonce: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            poll_attempts: DEFAULT_POLL_ATTEMPTS,
+        })
+    }
+
+    pub fn with_poll(mut self, interval: Duration, attempts: u32) -> Self {
+        self.poll_interval = interval;
+        self.poll_attempts = attempts;
+        self
+    }
+
+    pub fn with_account(mut self, kid: Option<String>) -> Self {
+        self.kid = kid;
+        self
+    }
+
+    pub fn account_key(&self) -> &AccountKey {
+        &self.key
+    }
+
+    /// Registers the key (or looks up its existing account) and returns
+    /// the account URL used as `kid` for later requests.
+    pub async fn ensure_account(
+        &mut self,
+        contact_email: &str,
+    ) -> Result<String, AcmeError> {
+        if let Some(kid) = &self.kid {
+            return Ok(kid.clone());
+        }
+
+        let mut payload = json!({ "termsOfServiceAgreed": true });
+        if !contact_email.trim().is_empty() {
+            payload["contact"] =
+                json!([format!("mailto:{}", contact_email.trim())]);
+        }
+        let url = self.directory.new_account.clone();
+        let response = self.post(&url, Some(&payload)).await?;
+        let kid = response.location(&url)?;
+        info_log!(ACME_LOGGER_DOMAIN, "acme_account_ready kid={}", kid);
+        self.kid = Some(kid.clone());
+        Ok(kid)
+    }
+
+    /// Runs a full order for `domains` and returns the PEM chain signed for
+    /// `cert_key`. Challenge answers are published in `challenges` only for
+    /// the lifetime of each authorization.
+    pub async fn issue(
+        &mut self,
+        domains: &[String],
+        challenge_type: AcmeChallengeType,
+        cert_key: &KeyPair,
+        challenges: &ChallengeStore,
+    ) -> Result<String, AcmeError> {
+        let identifiers: Vec<Value> = domains
+            .iter()
+            .map(|domain| json!({ "type": "dns", "value": domain }))
+            .collect();
+        let new_order = self.directory.new_order.clone();
+        let response = self
+            .post(&new_order, Some(&json!({ "identifiers": identifiers })))
+            .await?;
+        let order_url = response.location(&new_order)?;
+        let order: Order = response.json(&new_order)?;
+        info_log!(
+            ACME_LOGGER_DOMAIN,
+            "acme_order_created url={} status={} authorizations={}",
+            order_url,
+            order.status,
+            order.authorizations.len()
+        );
+
+        for authz_url in &order.authorizations {
+            self.authorize(authz_url, challenge_type, challenges)
+                .await?;
+        }
+
+        let mut params = CertificateParams::new(domains.to_vec())
+            .map_err(|e| AcmeError::Key(e.to_string()))?;
+        params.distinguished_name = DistinguishedName::new();
+        if let Some(first) = domains.first() {
+            params
+                .distinguished_name
+                .push(DnType::CommonName, first.clone());
+        }
+        let csr = params
+            .serialize_request(cert_key)
+            .map_err(|e| AcmeError::Key(e.to_string()))?;
+        let payload = json!({ "csr": URL_SAFE_NO_PAD.encode(csr.der()) });
+        self.post(&order.finalize, Some(&payload)).await?;
+
+        let order = self
+            .poll_order(&order_url, |order| order.status == "valid")
+            .await?;
+        let certificate_url =
+            order.certificate.ok_or_else(|| AcmeError::MissingField {
+                url: order_url.clone(),
+                field: "certificate",
+            })?;
+        let response = self.post(&certificate_url, None).await?;
+        let chain = String::from_utf8_lossy(&response.body).into_owned();
+        info_log!(
+            ACME_LOGGER_DOMAIN,
+            "acme_certificate_downloaded url={} domains={:?}",
+            certificate_url,
+            domains
+        );
+        Ok(chain)
+    }
+
+    async fn authorize(
+        &mut self,
+        authz_url: &str,
+        challenge_type: AcmeChallengeType,
+        challenges: &ChallengeStore,
+    ) -> Result<(), AcmeError> {
+        let authz: Authorization =
+            self.post(authz_url, None).await?.json(authz_url)?;
+        if authz.status == "valid" {
+            debug_log!(
+                ACME_LOGGER_DOMAIN,
+                "acme_authorization_already_valid identifier={}",
+                authz.identifier.value
+            );
+            return Ok(());
+        }
+
+        let challenge = authz
+            .challenges
+            .iter()
+            .find(|challenge| challenge.kind == challenge_type.as_str())
+            .cloned()
+            .ok_or_else(|| AcmeError::ChallengeUnavailable {
+                challenge: challenge_type.as_str(),
+                identifier: authz.identifier.value.clone(),
+            })?;
+        let domain = authz.identifier.value.clone();
+        let key_authorization = self.key.key_authorization(&challenge.token);
+
+        match challenge_type {
+            AcmeChallengeType::Http01 => {
+                challenges.add_http01(&challenge.token, &key_authorization);
+            }
+            AcmeChallengeType::TlsAlpn01 => {
+                challenges.add_tls_alpn01(&domain, &key_authorization)?;
+            }
+        }
+
+        let result = self.complete_challenge(authz_url, &challenge).await;
+
+        match challenge_type {
+            AcmeChallengeType::Http01 => {
+                challenges.remove_http01(&challenge.token)
+            }
+            AcmeChallengeType::TlsAlpn01 => {
+                challenges.remove_tls_alpn01(&domain)
+            }
+        }
+
+        let authz = result?;
+        if authz.status != "valid" {
+            let detail = authz
+                .challenges
+                .iter()
+                .find_map(|challenge| challenge.error.clone())
+                .map(|error| error.to_string())
+                .unwrap_or_default();
+            return Err(AcmeError::Validation {
+                kind: "authorization",
+                identifier: domain,
+                status: authz.status,
+                detail,
+            });
+        }
+        info_log!(
+            ACME_LOGGER_DOMAIN,
+            "acme_authorization_valid identifier={} challenge={}",
+            domain,
+            challenge_type.as_str()
+        );
+        Ok(())
+    }
+
+    async fn complete_challenge(
+        &mut self,
+        authz_url: &str,
+        challenge: &Challenge,
+    ) -> Result<Authorization, AcmeError> {
+        self.post(&challenge.url, Some(&json!({}))).await?;
+
+        for _ in 0..self.poll_attempts {
+            let authz: Authorization =
+                self.post(authz_url, None).await?.json(authz_url)?;
+            if authz.status != "pending" {
+                return Ok(authz);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+        Err(AcmeError::Timeout {
+            kind: "authorization",
+            url: authz_url.to_string(),
+            status: "pending".to_string(),
+        })
+    }
+
+    async fn poll_order(
+        &mut self,
+        order_url: &str,
+        done: impl Fn(&Order) -> bool,
+    ) -> Result<Order, AcmeError> {
+        let mut status = String::new();
+        for _ in 0..self.poll_attempts {
+            let order: Order =
+                self.post(order_url, None).await?.json(order_url)?;
+            if done(&order) {
+                return Ok(order);
+            }
+            if order.status == "invalid" {
+                return Err(AcmeError::Validation {
+                    kind: "order",
+                    identifier: order_url.to_string(),
+                    status: order.status,
+                    detail: order
+                        .error
+                        .map(|error| error.to_string())
+                        .unwrap_or_default(),
+                });
+            }
+            status = order.status;
+            tokio::time::sleep(self.poll_interval).await;
+        }
+        Err(AcmeError::Timeout {
+            kind: "order",
+            url: order_url.to_string(),
+            status,
+        })
+    }
+
+    async fn fresh_nonce(&mut self) -> Result<String, AcmeError> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let url = self.directory.new_nonce.clone();
+        let response = self
+            .http
+            .head(&url)
+            .send()
+            .await
+            .map_err(|e| http_error(&url, e))?;
+        response
+            .headers()
+            .get(REPLAY_NONCE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or(AcmeError::MissingField {
+                url,
+                field: "Replay-Nonce header",
+            })
+    }
+
+    /// Signed POST; `None` is POST-as-GET. A `badNonce` rejection is retried
+    /// once with the nonce from the error response.
+    async fn post(
+        &mut self,
+        url: &str,
+        payload: Option<&Value>,
+    ) -> Result<AcmeResponse, AcmeError> {
+        let mut retried = false;
+        loop {
+            let nonce = self.fresh_nonce().await?;
+            let body =
+                self.key.sign(url, &nonce, self.kid.as_deref(), payload)?;
+            let response = self
+                .http
+                .post(url)
+                .header(CONTENT_TYPE, "application/jose+json")
+                .header(
+                    ACCEPT,
+                    "application/pem-certificate-chain, application/json",
+                )
+                .body(body.to_string())
+                .send()
+                .await
+                .map_err(|e| http_error(url, e))?;
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            self.nonce = headers
+                .get(REPLAY_NONCE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let body =
+                response.bytes().await.map_err(|e| http_error(url, e))?;
+
+            if status.is_success() {
+                return Ok(AcmeResponse { headers, body });
+            }
+
+            let problem: Value =
+                serde_json::from_slice(&body).unwrap_or_default();
+            if !retried && problem["type"] == BAD_NONCE {
+                debug_log!(
+                    ACME_LOGGER_DOMAIN,
+                    "acme_bad_nonce_retry url={}",
+                    url
+                );
+                retried = true;
+                continue;
+            }
+            return Err(AcmeError::Problem {
+                url: url.to_string(),
+                status: status.as_u16(),
+                problem: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+    }
+}
+
+fn http_error(url: &str, error: reqwest::Error) -> AcmeError {
+    AcmeError::Http {
+        url: url.to_string(),
+        error: error.to_string(),
+    }
+}
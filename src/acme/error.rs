@@ -0,0 +1,37 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AcmeError {
+    #[error("ACME request to {url} failed: {error}")]
+    Http { url: String, error: String },
+    #[error("ACME server rejected {url} ({status}): {problem}")]
+    Problem {
+        url: String,
+        status: u16,
+        problem: String,
+    },
+    #[error("ACME response from {url} is missing {field}")]
+    MissingField { url: String, field: &'static str },
+    #[error("ACME {kind} for {identifier} ended as '{status}': {detail}")]
+    Validation {
+        kind: &'static str,
+        identifier: String,
+        status: String,
+        detail: String,
+    },
+    #[error("ACME {kind} still '{status}' after polling {url}")]
+    Timeout {
+        kind: &'static str,
+        url: String,
+        status: String,
+    },
+    #[error("ACME server offers no {challenge} challenge for {identifier}")]
+    ChallengeUnavailable {
+        challenge: &'static str,
+        identifier: String,
+    },
+    #[error("ACME key error: {0}")]
+    Key(String),
+    #[error("ACME state I/O failed for {path}: {error}")]
+    Io { path: String, error: String },
+}
@@ -0,0 +1,156 @@
+use aws_lc_rs::{
+    digest,
+    rand::SystemRandom,
+    signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use rcgen::{KeyPair as PemKeyPair, PKCS_ECDSA_P256_SHA256};
+use serde_json::{Value, json};
+
+use super::error::AcmeError;
+
+/// ES256 account key; requests are signed as flattened JWS (RFC 8555 §6.2).
+pub struct AccountKey {
+    key_pair: EcdsaKeyPair,
+    pem: String,
+}
+
+impl AccountKey {
+    pub fn generate() -> Result<Self, AcmeError> {
+        let generated = PemKeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)
+            .map_err(|e| AcmeError::Key(e.to_string()))?;
+        Self::from_pem(&generated.serialize_pem())
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self, AcmeError> {
+        let parsed = PemKeyPair::from_pem(pem)
+            .map_err(|e| AcmeError::Key(e.to_string()))?;
+        let key_pair = EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            &parsed.serialize_der(),
+        )
+        .map_err(|e| AcmeError::Key(e.to_string()))?;
+        Ok(Self {
+            key_pair,
+            pem: pem.to_string(),
+        })
+    }
+
+    pub fn to_pem(&self) -> &str {
+        &self.pem
+    }
+
+    pub fn jwk(&self) -> Value {
+        // Uncompressed SEC1 point: 0x04 || X || Y.
+        let point = self.key_pair.public_key().as_ref();
+        json!({
+            "crv": "P-256",
+            "kty": "EC",
+            "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+            "y": URL_SAFE_NO_PAD.encode(&point[33..65]),
+        })
+    }
+
+    /// RFC 7638 thumbprint; `serde_json` keeps the required key order.
+    pub fn thumbprint(&self) -> String {
+        let canonical = self.jwk().to_string();
+        let hash = digest::digest(&digest::SHA256, canonical.as_bytes());
+        URL_SAFE_NO_PAD.encode(hash.as_ref())
+    }
+
+    pub fn key_authorization(&self, token: &str) -> String {
+        format!("{token}.{}", self.thumbprint())
+    }
+
+    /// Signs `payload` for `url`. `None` yields the empty POST-as-GET body;
+    /// without `kid` the full JWK is embedded (account creation).
+    pub fn sign(
+        &self,
+        url: &str,
+        nonce: &str,
+        kid: Option<&str>,
+        payload: Option<&Value>,
+    ) -> Result<Value, AcmeError> {
+        let mut protected = json!({
+            "alg": "ES256",
+            "nonce": nonce,
+            "url": url,
+        });
+        match kid {
+            Some(kid) => protected["kid"] = json!(kid),
+            None => protected["jwk"] = self.jwk(),
+        }
+
+        let protected = URL_SAFE_NO_PAD.encode(protected.to_string());
+        let payload = payload
+            .map(|payload| URL_SAFE_NO_PAD.encode(payload.to_string()))
+            .unwrap_or_default();
+        let signing_input = format!("{protected}.{payload}");
+        let signature = self
+            .key_pair
+            .sign(&SystemRandom::new(), signing_input.as_bytes())
+            .map_err(|e| AcmeError::Key(e.to_string()))?;
+
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_lc_rs::signature::{ECDSA_P256_SHA256_FIXED, UnparsedPublicKey};
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use serde_json::json;
+
+    use super::AccountKey;
+
+    #[test]
+    fn signed_request_verifies_against_embedded_jwk() {
+        let key = AccountKey::generate().expect("generate key");
+        let restored = AccountKey::from_pem(key.to_pem()).expect("load key");
+        assert_eq!(key.thumbprint(), restored.thumbprint());
+
+        let jws = restored
+            .sign(
+                "https://acme.test/new-acct",
+                "nonce-1",
+                None,
+                Some(&json!({"termsOfServiceAgreed": true})),
+            )
+            .expect("sign");
+
+        let protected = jws["protected"].as_str().unwrap_or_default();
+        let header: serde_json::Value = serde_json::from_slice(
+            &URL_SAFE_NO_PAD.decode(protected).unwrap_or_default(),
+        )
+        .unwrap_or_default();
+        assert_eq!(header["nonce"], "nonce-1");
+        assert_eq!(header["jwk"], key.jwk());
+
+        let x = URL_SAFE_NO_PAD
+            .decode(header["jwk"]["x"].as_str().unwrap_or_default())
+            .unwrap_or_default();
+        let y = URL_SAFE_NO_PAD
+            .decode(header["jwk"]["y"].as_str().unwrap_or_default())
+            .unwrap_or_default();
+        let point = [&[4_u8][..], &x, &y].concat();
+        let message = format!(
+            "{}.{}",
+            protected,
+            jws["payload"].as_str().unwrap_or_default()
+        );
+        let signature = URL_SAFE_NO_PAD
+            .decode(jws["signature"].as_str().unwrap_or_default())
+            .unwrap_or_default();
+
+        assert!(
+            UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, point)
+                .verify(message.as_bytes(), &signature)
+                .is_ok()
+        );
+        assert!(key.key_authorization("tok").starts_with("tok."));
+    }
+}
@@ -0,0 +1,665 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use rcgen::{CertificateParams, KeyPair};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use x509_parser::{extensions::GeneralName, pem::parse_x509_pem};
+
+use super::{
+    challenge::{ChallengeStore, acme_challenges},
+    client::AcmeClient,
+    error::AcmeError,
+    jws::AccountKey,
+};
+use crate::{
+    ACME_LOGGER_DOMAIN,
+    cli_wizard::persist::write_atomic,
+    config::{core::Config, http2::AcmeConfig},
+    error_log, info_log, warn_log,
+};
+
+const ACCOUNT_KEY_FILE: &str = "account.key";
+const ACCOUNT_STATE_FILE: &str = "account.json";
+const RENEW_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AccountState {
+    directory_url: String,
+    kid: String,
+}
+
+/// Keeps the `[Http2]` certificate pair issued and renewed. The listeners
+/// pick up new files through their certificate watcher.
+pub struct AcmeManager {
+    acme: AcmeConfig,
+    state_dir: PathBuf,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    http: Client,
+    poll_interval: Option<Duration>,
+}
+
+impl AcmeManager {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let acme = config.http2.acme.clone().filter(|acme| acme.enable)?;
+        Some(Self::new(
+            acme,
+            config.get_acme_state_dir()?,
+            config.get_ssl_cert_path()?,
+            config.get_ssl_key_path()?,
+        ))
+    }
+
+    pub fn new(
+        acme: AcmeConfig,
+        state_dir: PathBuf,
+        cert_path: PathBuf,
+        key_path: PathBuf,
+    ) -> Self {
+        let http = Client::builder()
+            .use_rustls_tls()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            acme,
+            state_dir,
+            cert_path,
+            key_path,
+            http,
+            poll_interval: None,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = Some(interval);
+        self
+    }
+
+    /// Writes a short-lived self-signed pair when no certificate exists yet,
+    /// so the TLS listener can start and answer `tls-alpn-01`. It is always
+    /// inside the renewal window and gets replaced on the first run.
+    pub fn ensure_placeholder_certificate(&self) -> Result<bool, AcmeError> {
+        if self.cert_path.exists() && self.key_path.exists() {
+            return Ok(false);
+        }
+
+        let key_error = |e: rcgen::Error| AcmeError::Key(e.to_string());
+        let mut params = CertificateParams::new(self.acme.domains.clone())
+            .map_err(key_error)?;
+        let now = OffsetDateTime::now_utc();
+        params.not_before = now - time::Duration::hours(1);
+        params.not_after = now + time::Duration::days(1);
+        let key_pair = KeyPair::generate().map_err(key_error)?;
+        let cert = params.self_signed(&key_pair).map_err(key_error)?;
+
+        self.write_pair(&cert.pem(), &key_pair.serialize_pem())?;
+        warn_log!(
+            ACME_LOGGER_DOMAIN,
+            "acme_placeholder_certificate_written cert_path={:?}",
+            self.cert_path
+        );
+        Ok(true)
+    }
+
+    /// True when the certificate is missing, unreadable, close to expiry or
+    /// does not cover every configured domain.
+    pub fn needs_renewal(&self) -> bool {
+        let Ok(pem) = fs::read(&self.cert_path) else {
+            return true;
+        };
+        let Ok((_, pem)) = parse_x509_pem(&pem) else {
+            return true;
+        };
+        let Ok(cert) = pem.parse_x509() else {
+            return true;
+        };
+
+        let renew_before =
+            i64::from(self.acme.renew_before_days) * 24 * 60 * 60;
+        let remaining = cert.validity().not_after.timestamp()
+            - OffsetDateTime::now_utc().unix_timestamp();
+        if remaining <= renew_before {
+            return true;
+        }
+
+        let names: Vec<String> = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|san| {
+                san.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(dns) => {
+                            Some(dns.to_ascii_lowercase())
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.acme
+            .domains
+            .iter()
+            .any(|domain| !names.contains(&domain.to_ascii_lowercase()))
+    }
+
+    pub async fn renew_if_needed(&self) -> Result<bool, AcmeError> {
+        if !self.needs_renewal() {
+            return Ok(false);
+        }
+        self.issue(acme_challenges()).await?;
+        Ok(true)
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                let wait = match self.renew_if_needed().await {
+                    Ok(_) => RENEW_CHECK_INTERVAL,
+                    Err(e) => {
+                        error_log!(
+                            ACME_LOGGER_DOMAIN,
+                            "acme_renewal_failed domains={:?} error={}",
+                            self.acme.domains,
+                            e
+                        );
+                        RETRY_INTERVAL
+                    }
+                };
+                tokio::time::sleep(wait).await;
+            }
+        });
+    }
+
+    async fn issue(
+        &self,
+        challenges: &ChallengeStore,
+    ) -> Result<(), AcmeError> {
+        let key = self.load_or_create_account_key()?;
+        let state = self.read_account_state();
+        let kid = (state.directory_url == self.acme.directory_url
+            && !state.kid.is_empty())
+        .then_some(state.kid);
+
+        let mut client = AcmeClient::connect(
+            self.http.clone(),
+            &self.acme.directory_url,
+            key,
+        )
+        .await?
+        .with_account(kid);
+        if let Some(interval) = self.poll_interval {
+            client = client.with_poll(interval, 30);
+        }
+
+        let kid = client.ensure_account(&self.acme.contact_email).await?;
+        self.write_account_state(&AccountState {
+            directory_url: self.acme.directory_url.clone(),
+            kid,
+        })?;
+
+        let cert_key =
+            KeyPair::generate().map_err(|e| AcmeError::Key(e.to_string()))?;
+        let chain = client
+            .issue(
+                &self.acme.domains,
+                self.acme.challenge,
+                &cert_key,
+                challenges,
+            )
+            .await?;
+
+        self.write_pair(&chain, &cert_key.serialize_pem())?;
+        info_log!(
+            ACME_LOGGER_DOMAIN,
+            "acme_certificate_installed domains={:?} cert_path={:?}",
+            self.acme.domains,
+            self.cert_path
+        );
+        Ok(())
+    }
+
+    fn load_or_create_account_key(&self) -> Result<AccountKey, AcmeError> {
+        let path = self.state_dir.join(ACCOUNT_KEY_FILE);
+        if let Ok(pem) = fs::read_to_string(&path) {
+            return AccountKey::from_pem(&pem);
+        }
+        let key = AccountKey::generate()?;
+        write_file(&path, key.to_pem())?;
+        info_log!(
+            ACME_LOGGER_DOMAIN,
+            "acme_account_key_created path={:?}",
+            path
+        );
+        Ok(key)
+    }
+
+    fn read_account_state(&self) -> AccountState {
+        fs::read_to_string(self.state_dir.join(ACCOUNT_STATE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_account_state(
+        &self,
+        state: &AccountState,
+    ) -> Result<(), AcmeError> {
+        let content = serde_json::to_string_pretty(state).unwrap_or_default();
+        write_file(&self.state_dir.join(ACCOUNT_STATE_FILE), &content)
+    }
+
+    /// A watcher tick between the two writes sees a new key next to the old
+    /// certificate; `load_certified_key` rejects the mismatch, so the
+    /// previous pair keeps serving until the certificate lands.
+    fn write_pair(
+        &self,
+        cert_pem: &str,
+        key_pem: &str,
+    ) -> Result<(), AcmeError> {
+        write_file(&self.key_path, key_pem)?;
+        write_file(&self.cert_path, cert_pem)
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), AcmeError> {
+    write_atomic(path, contents).map_err(|e| AcmeError::Io {
+        path: path.display().to_string(),
+        error: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use aws_lc_rs::signature::{ECDSA_P256_SHA256_FIXED, UnparsedPublicKey};
+    use axum::{
+        Json, Router,
+        extract::{Path as RoutePath, State},
+        http::{HeaderMap, StatusCode, header},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+    };
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use rcgen::{CertificateParams, KeyPair};
+    use serde_json::{Value, json};
+
+    use super::AcmeManager;
+    use crate::{
+        acme::challenge::{ChallengeStore, HTTP01_PATH_PREFIX},
+        config::http2::{AcmeChallengeType, AcmeConfig},
+    };
+
+    const DOMAIN: &str = "stream.example.test";
+
+    /// Minimal Pebble-like server: checks nonces and signatures, validates
+    /// HTTP-01 against the shared store and signs a real certificate.
+    #[derive(Default)]
+    struct Pebble {
+        base: String,
+        nonces: HashSet<String>,
+        next_nonce: u64,
+        reject_first_nonce: bool,
+        jwk: Option<Value>,
+        authz_status: String,
+        order_status: String,
+        challenges: Arc<ChallengeStore>,
+    }
+
+    type Shared = Arc<Mutex<Pebble>>;
+
+    impl Pebble {
+        fn issue_nonce(&mut self) -> String {
+            self.next_nonce += 1;
+            let nonce = format!("nonce-{}", self.next_nonce);
+            self.nonces.insert(nonce.clone());
+            nonce
+        }
+
+        fn thumbprint(&self) -> String {
+            let canonical = self.jwk.clone().unwrap_or_default().to_string();
+            let hash = aws_lc_rs::digest::digest(
+                &aws_lc_rs::digest::SHA256,
+                canonical.as_bytes(),
+            );
+            URL_SAFE_NO_PAD.encode(hash.as_ref())
+        }
+
+        /// Returns the decoded payload, or the ACME problem type.
+        fn verify(&mut self, jws: &Value) -> Result<Value, &'static str> {
+            let decode = |field: &str| {
+                URL_SAFE_NO_PAD
+                    .decode(jws[field].as_str().unwrap_or_default())
+                    .unwrap_or_default()
+            };
+            let header: Value = serde_json::from_slice(&decode("protected"))
+                .unwrap_or_default();
+            let nonce = header["nonce"].as_str().unwrap_or_default();
+            if !self.nonces.remove(nonce) || self.reject_first_nonce {
+                self.reject_first_nonce = false;
+                return Err("urn:ietf:params:acme:error:badNonce");
+            }
+
+            let jwk = match header.get("jwk") {
+                Some(jwk) => {
+                    self.jwk = Some(jwk.clone());
+                    jwk.clone()
+                }
+                None => self.jwk.clone().unwrap_or_default(),
+            };
+            let point = [
+                &[4_u8][..],
+                &URL_SAFE_NO_PAD
+                    .decode(jwk["x"].as_str().unwrap_or_default())
+                    .unwrap_or_default(),
+                &URL_SAFE_NO_PAD
+                    .decode(jwk["y"].as_str().unwrap_or_default())
+                    .unwrap_or_default(),
+            ]
+            .concat();
+            let message = format!(
+                "{}.{}",
+                jws["protected"].as_str().unwrap_or_default(),
+                jws["payload"].as_str().unwrap_or_default()
+            );
+            if UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, point)
+                .verify(message.as_bytes(), &decode("signature"))
+                .is_err()
+            {
+                return Err("urn:ietf:params:acme:error:malformed");
+            }
+
+            let payload = decode("payload");
+            if payload.is_empty() {
+                return Ok(Value::Null);
+            }
+            Ok(serde_json::from_slice(&payload).unwrap_or_default())
+        }
+
+        fn problem(&mut self, kind: &str, status: StatusCode) -> Response {
+            self.reply(status, None, json!({ "type": kind }))
+        }
+
+        fn reply(
+            &mut self,
+            status: StatusCode,
+            location: Option<&str>,
+            body: Value,
+        ) -> Response {
+            let mut headers = HeaderMap::new();
+            let nonce = self.issue_nonce();
+            headers.insert("replay-nonce", nonce.parse().expect("nonce"));
+            if let Some(location) = location {
+                let location = format!("{}{}", self.base, location);
+                headers.insert(
+                    header::LOCATION,
+                    location.parse().expect("location"),
+                );
+            }
+            (status, headers, Json(body)).into_response()
+        }
+
+        fn order(&self) -> Value {
+            let mut order = json!({
+                "status": self.order_status,
+                "authorizations": [format!("{}/authz/1", self.base)],
+                "finalize": format!("{}/finalize/1", self.base),
+            });
+            if self.order_status == "valid" {
+                order["certificate"] = json!(format!("{}/cert/1", self.base));
+            }
+            order
+        }
+    }
+
+    async fn directory(State(state): State<Shared>) -> Json<Value> {
+        let base = state.lock().expect("pebble").base.clone();
+        Json(json!({
+            "newNonce": format!("{base}/nonce"),
+            "newAccount": format!("{base}/acct"),
+            "newOrder": format!("{base}/order"),
+        }))
+    }
+
+    async fn nonce(State(state): State<Shared>) -> Response {
+        state
+            .lock()
+            .expect("pebble")
+            .reply(StatusCode::OK, None, Value::Null)
+    }
+
+    async fn acme_post(
+        State(state): State<Shared>,
+        RoutePath((kind, _id)): RoutePath<(String, String)>,
+        Json(jws): Json<Value>,
+    ) -> Response {
+        let mut pebble = state.lock().expect("pebble");
+        let payload = match pebble.verify(&jws) {
+            Ok(payload) => payload,
+            Err(kind) => {
+                return pebble.problem(kind, StatusCode::BAD_REQUEST);
+            }
+        };
+
+        match kind.as_str() {
+            "authz" => {
+                let body = json!({
+                    "identifier": { "type": "dns", "value": DOMAIN },
+                    "status": pebble.authz_status,
+                    "challenges": [
+                        {
+                            "type": "http-01",
+                            "url": format!("{}/chall/1", pebble.base),
+                            "token": "tok-1",
+                        },
+                        {
+                            "type": "tls-alpn-01",
+                            "url": format!("{}/chall/2", pebble.base),
+                            "token": "tok-2",
+                        },
+                    ],
+                });
+                pebble.reply(StatusCode::OK, None, body)
+            }
+            "chall" => {
+                let expected = format!("tok-1.{}", pebble.thumbprint());
+                let served = pebble
+                    .challenges
+                    .http01_response(&format!("{}tok-1", HTTP01_PATH_PREFIX));
+                pebble.authz_status = if served == Some(expected) {
+                    "valid".to_string()
+                } else {
+                    "invalid".to_string()
+                };
+                pebble.reply(
+                    StatusCode::OK,
+                    None,
+                    json!({ "status": "processing" }),
+                )
+            }
+            "finalize" => {
+                let csr = payload["csr"].as_str().unwrap_or_default();
+                pebble.order_status = if csr.is_empty() {
+                    "invalid".to_string()
+                } else {
+                    "processing".to_string()
+                };
+                let order = pebble.order();
+                pebble.reply(StatusCode::OK, None, order)
+            }
+            "orders" => {
+                if pebble.order_status == "processing" {
+                    pebble.order_status = "valid".to_string();
+                }
+                let order = pebble.order();
+                pebble.reply(StatusCode::OK, None, order)
+            }
+            "cert" => {
+                let key = KeyPair::generate().expect("issuer key");
+                let params = CertificateParams::new(vec![DOMAIN.to_string()])
+                    .expect("params");
+                let pem = params.self_signed(&key).expect("cert").pem();
+                let nonce = pebble.issue_nonce();
+                (
+                    StatusCode::OK,
+                    [
+                        ("replay-nonce", nonce),
+                        (
+                            "content-type",
+                            "application/pem-certificate-chain".to_string(),
+                        ),
+                    ],
+                    pem,
+                )
+                    .into_response()
+            }
+            _ => pebble.problem(
+                "urn:ietf:params:acme:error:malformed",
+                StatusCode::NOT_FOUND,
+            ),
+        }
+    }
+
+    async fn new_account(
+        State(state): State<Shared>,
+        Json(jws): Json<Value>,
+    ) -> Response {
+        let mut pebble = state.lock().expect("pebble");
+        match pebble.verify(&jws) {
+            Ok(payload) if payload["termsOfServiceAgreed"] == true => {
+                pebble.reply(StatusCode::CREATED, Some("/acct/1"), json!({}))
+            }
+            Ok(_) => pebble.problem(
+                "urn:ietf:params:acme:error:userActionRequired",
+                StatusCode::FORBIDDEN,
+            ),
+            Err(kind) => pebble.problem(kind, StatusCode::BAD_REQUEST),
+        }
+    }
+
+    async fn new_order(
+        State(state): State<Shared>,
+        Json(jws): Json<Value>,
+    ) -> Response {
+        let mut pebble = state.lock().expect("pebble");
+        match pebble.verify(&jws) {
+            Ok(payload) => {
+                assert_eq!(payload["identifiers"][0]["value"], DOMAIN);
+                pebble.order_status = "pending".to_string();
+                let order = pebble.order();
+                pebble.reply(StatusCode::CREATED, Some("/orders/1"), order)
+            }
+            Err(kind) => pebble.problem(kind, StatusCode::BAD_REQUEST),
+        }
+    }
+
+    async fn spawn_pebble(challenges: Arc<ChallengeStore>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind pebble");
+        let base = format!("http://{}", listener.local_addr().expect("addr"));
+        let state = Arc::new(Mutex::new(Pebble {
+            base: base.clone(),
+            reject_first_nonce: true,
+            authz_status: "pending".to_string(),
+            challenges,
+            ..Pebble::default()
+        }));
+        let app = Router::new()
+            .route("/dir", get(directory))
+            .route("/nonce", get(nonce).head(nonce))
+            .route("/acct", post(new_account))
+            .route("/order", post(new_order))
+            .route("/{kind}/{id}", post(acme_post))
+            .with_state(state);
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        format!("{base}/dir")
+    }
+
+    fn manager(dir: &std::path::Path, directory_url: String) -> AcmeManager {
+        AcmeManager::new(
+            AcmeConfig {
+                enable: true,
+                directory_url,
+                contact_email: "ops@example.test".to_string(),
+                domains: vec![DOMAIN.to_string()],
+                challenge: AcmeChallengeType::Http01,
+                ..AcmeConfig::default()
+            },
+            dir.join("acme"),
+            dir.join("ssl").join("ssl-cert"),
+            dir.join("ssl").join("ssl-key"),
+        )
+        .with_poll_interval(Duration::from_millis(10))
+    }
+
+    #[test]
+    fn placeholder_certificate_stays_inside_renewal_window() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let manager = manager(dir.path(), "http://127.0.0.1:1/dir".into());
+
+        assert!(manager.needs_renewal());
+        assert!(
+            manager
+                .ensure_placeholder_certificate()
+                .expect("placeholder")
+        );
+        assert!(
+            !manager
+                .ensure_placeholder_certificate()
+                .expect("placeholder exists")
+        );
+        assert!(manager.needs_renewal());
+    }
+
+    #[tokio::test]
+    async fn issues_certificate_against_pebble_stand_in() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let challenges = Arc::new(ChallengeStore::default());
+        let directory_url = spawn_pebble(challenges.clone()).await;
+        let manager = manager(dir.path(), directory_url.clone());
+        manager
+            .ensure_placeholder_certificate()
+            .expect("placeholder");
+
+        manager.issue(&challenges).await.expect("issue certificate");
+
+        assert!(!manager.needs_renewal());
+        let key = std::fs::read_to_string(dir.path().join("ssl/ssl-key"))
+            .expect("key written");
+        assert!(key.contains("PRIVATE KEY"));
+        let account = std::fs::read_to_string(
+            dir.path().join("acme").join(super::ACCOUNT_STATE_FILE),
+        )
+        .expect("account state");
+        assert!(account.contains("/acct/1"));
+        assert!(account.contains(&directory_url));
+        assert!(
+            dir.path()
+                .join("acme")
+                .join(super::ACCOUNT_KEY_FILE)
+                .exists()
+        );
+        assert_eq!(
+            challenges.http01_response(&format!("{}tok-1", HTTP01_PATH_PREFIX)),
+            None
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! Built-in ACME (RFC 8555) issuance for the `[Http2]` certificate pair.
+//!
+//! `http-01` answers are served by [`challenge::AcmeChallengeMiddleware`] on
+//! every listener; `tls-alpn-01` certificates are handed out by the gateway's
+//! certificate resolver when a client offers ALPN `acme-tls/1`.
+
+pub mod challenge;
+pub mod client;
+pub mod error;
+pub mod jws;
+pub mod manager;
+
+pub use challenge::{AcmeChallengeMiddleware, acme_challenges};
+pub use error::AcmeError;
+pub use manager::AcmeManager;
@@ -45,6 +45,25 @@ impl API {
             operation: Operation::FsLink { path: path.into() },
         }
     }
+
+    pub fn auth_login(
+        url: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        otp_code: impl Into<String>,
+        user_agent: impl Into<String>,
+    ) -> Self {
+        API {
+            url: url.into(),
+            token: String::new(),
+            user_agent: user_agent.into(),
+            operation: Operation::AuthLogin {
+                username: username.into(),
+                password: password.into(),
+                otp_code: otp_code.into(),
+            },
+        }
+    }
 }
 
 impl NetworkTarget for API {
@@ -64,10 +83,11 @@ impl NetworkTarget for API {
         match &self.operation {
             Operation::FsGet { .. } => "api/fs/get".to_string(),
             Operation::FsLink { .. } => "api/fs/link".to_string(),
+            Operation::AuthLogin { .. } => "api/auth/login".to_string(),
         }
     }
 
-    /// Gets the HTTP method for the request (always POST for OpenList fs/get).
+    /// Gets the HTTP method for the request (always POST for OpenList).
     fn method(&self) -> HttpMethod {
         HttpMethod::Post
     }
@@ -91,6 +111,18 @@ impl NetworkTarget for API {
                 });
                 NetworkTask::RequestJson(json)
             }
+            Operation::AuthLogin {
+                username,
+                password,
+                otp_code,
+            } => {
+                let json = serde_json::json!({
+                    "username": username,
+                    "password": password,
+                    "otp_code": otp_code
+                });
+                NetworkTask::RequestJson(json)
+            }
         }
     }
 
@@ -98,18 +130,21 @@ impl NetworkTarget for API {
     ///
     /// Includes:
     /// - Standard JSON content type headers
-    /// - Authentication token
+    /// - Authentication token (omitted for login)
     /// - User agent string
     fn headers(&self) -> Vec<(String, String)> {
-        vec![
+        let mut headers = vec![
             ("accept".into(), "application/json, text/plain, */*".into()),
-            ("authorization".into(), self.token.clone()),
             ("cache-control".into(), "no-cache".into()),
             (
                 "content-type".into(),
                 "application/json;charset=UTF-8".into(),
             ),
             ("user-agent".into(), self.user_agent.clone()),
-        ]
+        ];
+        if !self.token.is_empty() {
+            headers.push(("authorization".into(), self.token.clone()));
+        }
+        headers
     }
 }
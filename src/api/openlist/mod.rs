@@ -4,4 +4,6 @@ pub mod response;
 
 pub use api::API;
 pub use operation::Operation;
-pub use response::{FileData, FileResponse, LinkData, LinkResponse};
+pub use response::{
+    FileData, FileResponse, LinkData, LinkResponse, LoginResponse,
+};
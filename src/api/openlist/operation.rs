@@ -5,4 +5,10 @@ pub enum Operation {
     FsGet { path: String },
     /// Fetch link information for a given path.
     FsLink { path: String },
+    /// Exchange username/password (and optional TOTP code) for a token.
+    AuthLogin {
+        username: String,
+        password: String,
+        otp_code: String,
+    },
 }
@@ -1,3 +1,5 @@
 pub mod types;
 
-pub use types::{FileData, FileResponse, LinkData, LinkResponse};
+pub use types::{
+    FileData, FileResponse, LinkData, LinkResponse, LoginResponse,
+};
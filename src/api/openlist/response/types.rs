@@ -1,45 +1,137 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, time::Duration};
 
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone)]
 pub struct FileResponse {
-    data: FileData,
+    #[serde(default)]
+    code: i64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    data: Option<FileData>,
 }
 
 impl FileResponse {
-    pub fn get_data(&self) -> FileData {
+    pub fn get_code(&self) -> i64 {
+        self.code
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_data(&self) -> Option<FileData> {
         self.data.clone()
     }
+
+    /// OpenList answers a missing path with a non-200 `code`, no `data` and
+    /// an "object not found" style message.
+    pub fn is_not_found(&self) -> bool {
+        self.data.is_none()
+            && self.message.to_ascii_lowercase().contains("not found")
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        self.code == UNAUTHORIZED_CODE
+    }
 }
 
-#[derive(Deserialize, Clone)]
+/// OpenList reports auth failures in the body `code`, usually with HTTP 200.
+const UNAUTHORIZED_CODE: i64 = 401;
+
+#[derive(Deserialize, Clone, Default)]
 pub struct FileData {
     raw_url: Option<String>,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    is_dir: bool,
 }
 
 impl FileData {
     pub fn get_raw_url(&self) -> Option<String> {
         self.raw_url.clone()
     }
+
+    pub fn get_size(&self) -> Option<u64> {
+        self.size
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
 }
 
 #[derive(Deserialize)]
 pub struct LinkResponse {
-    data: LinkData,
+    #[serde(default)]
+    code: i64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    data: Option<LinkData>,
 }
 
 impl LinkResponse {
+    pub fn get_code(&self) -> i64 {
+        self.code
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
     pub fn get_data(&self) -> LinkData {
-        self.data.clone()
+        self.data.clone().unwrap_or_default()
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        self.code == UNAUTHORIZED_CODE
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LoginResponse {
+    #[serde(default)]
+    code: i64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    data: Option<LoginData>,
+}
+
+impl LoginResponse {
+    pub fn get_code(&self) -> i64 {
+        self.code
+    }
+
+    pub fn get_message(&self) -> String {
+        self.message.clone()
+    }
+
+    pub fn get_token(&self) -> Option<String> {
+        self.data
+            .as_ref()
+            .map(|data| data.token.clone())
+            .filter(|token| !token.is_empty())
     }
 }
 
+#[derive(Deserialize, Clone, Default)]
+struct LoginData {
+    #[serde(default)]
+    token: String,
+}
+
 #[derive(Deserialize, Clone, Default)]
 pub struct LinkData {
     url: String,
     #[serde(default)]
     header: Option<HashMap<String, Vec<String>>>,
+    /// Link lifetime as a Go `time.Duration`, in nanoseconds.
+    #[serde(default, alias = "Expiration")]
+    expiration: Option<i64>,
 }
 
 impl LinkData {
@@ -50,6 +142,13 @@ impl LinkData {
     pub fn get_header(&self) -> Option<HashMap<String, Vec<String>>> {
         self.header.clone()
     }
+
+    /// How long the storage driver promises the link stays valid, or `None`
+    /// when it did not say.
+    pub fn get_expiration(&self) -> Option<Duration> {
+        self.expiration
+            .map(|nanos| Duration::from_nanos(nanos.max(0) as u64))
+    }
 }
 
 impl fmt::Display for LinkData {
@@ -1,14 +1,25 @@
-use std::{collections::HashSet, ops::Deref as DerefTrait, sync::Arc};
+use std::{
+    collections::HashSet,
+    ops::Deref as DerefTrait,
+    sync::{Arc, OnceLock},
+};
 
 use dashmap::DashMap;
 use tokio::sync::{Mutex as TokioMutex, OnceCell, RwLock as TokioRwLock};
 
 use crate::{
-    INIT_LOGGER_DOMAIN,
+    CONFIG_LOGGER_DOMAIN, INIT_LOGGER_DOMAIN,
     cache::{GeneralCache, RateLimiterCache},
     client::{ClientBuilder, EmbyClient, GoogleDriveClient, OpenListClient},
-    config::core::Config,
-    core::backend::{constants::DISK_BACKEND_TYPE, upstream_proxy, webdav},
+    config::{backend::BackendNode, core::Config},
+    core::{
+        backend::{
+            constants::DISK_BACKEND_TYPE,
+            google_drive_index::{self, GoogleDriveFolderIndexes},
+            resilience, smb, upstream_proxy, webdav, webdav_auth,
+        },
+        frontend::types::ForwardConfig,
+    },
     info_log,
     oauthutil::OAuthToken,
     util::path_rewriter::PathRewriter,
@@ -19,11 +30,15 @@ use crate::{
 const PROBLEMATIC_CLIENTS: &[&str] =
     &["yamby", "hills", "embytolocalplayer", "Emby/"];
 const GOOGLE_DRIVE_FILE_ID_CACHE_TTL_SECS: u64 = 20 * 60;
+const FILE_PROBE_CACHE_TTL_SECS: u64 = 5 * 60;
+const WEBDAV_STAT_CACHE_TTL_SECS: u64 = 5 * 60;
 
 pub struct AppState {
     pub(crate) config: TokioRwLock<Config>,
-    frontend_path_rewrite_cache: OnceCell<Vec<PathRewriter>>,
-    problematic_clients_cache: OnceCell<Vec<String>>,
+    frontend_path_rewrite_cache: TokioRwLock<Option<Arc<Vec<PathRewriter>>>>,
+    backend_nodes_cache: TokioRwLock<Option<Arc<Vec<BackendNode>>>>,
+    forward_config_cache: TokioRwLock<Option<Arc<ForwardConfig>>>,
+    problematic_clients_cache: TokioRwLock<Option<Arc<Vec<String>>>>,
     encrypt_cache: OnceCell<GeneralCache>,
     decrypt_cache: OnceCell<GeneralCache>,
     playback_info_cache: OnceCell<GeneralCache>,
@@ -32,10 +47,12 @@ pub struct AppState {
     local_metadata_cache: OnceCell<GeneralCache>,
     api_response_cache: OnceCell<GeneralCache>,
     google_drive_file_id_cache: OnceCell<GeneralCache>,
+    file_probe_cache: OnceCell<GeneralCache>,
+    webdav_stat_cache: OnceCell<GeneralCache>,
     emby_client: OnceCell<Arc<EmbyClient>>,
     google_drive_client: OnceCell<Arc<GoogleDriveClient>>,
     open_list_client: OnceCell<Arc<OpenListClient>>,
-    rate_limiter_cache: OnceCell<DashMap<String, RateLimiterCache>>,
+    rate_limiter_cache: TokioRwLock<Option<RateLimiterMap>>,
     pub(crate) api_request_locks: DashMap<String, Arc<TokioMutex<()>>>,
     pub(crate) open_list_request_locks: DashMap<String, Arc<TokioMutex<()>>>,
     pub(crate) playback_info_request_locks:
@@ -45,20 +62,31 @@ pub struct AppState {
         DashMap<String, Arc<TokioMutex<()>>>,
     pub(crate) google_drive_file_id_request_locks:
         DashMap<String, Arc<TokioMutex<()>>>,
+    pub(crate) file_probe_request_locks: DashMap<String, Arc<TokioMutex<()>>>,
+    pub(crate) webdav_stat_request_locks: DashMap<String, Arc<TokioMutex<()>>>,
     pub(crate) google_drive_token_cache: DashMap<String, OAuthToken>,
     pub(crate) google_drive_refresh_locks: DashMap<String, Arc<TokioMutex<()>>>,
     pub(crate) google_drive_refresh_backoff_until:
         DashMap<String, chrono::DateTime<chrono::Utc>>,
-    pub(crate) webdav_auth_cache: DashMap<String, String>,
+    pub(crate) google_drive_credential_backoff_until:
+        DashMap<String, chrono::DateTime<chrono::Utc>>,
+    pub(crate) google_drive_folder_indexes: GoogleDriveFolderIndexes,
+    pub(crate) webdav_auth_cache: webdav_auth::WebDavAuthCache,
     pub(crate) webdav_auth_probe_locks: DashMap<String, Arc<TokioMutex<()>>>,
+    pub(crate) openlist_token_cache: DashMap<String, String>,
+    pub(crate) openlist_login_locks: DashMap<String, Arc<TokioMutex<()>>>,
+    pub(crate) smb_sessions: Arc<smb::SmbSessionPool>,
+    pub(crate) circuit_breakers: Arc<resilience::CircuitBreakers>,
 }
 
 impl AppState {
     pub async fn new(config: Config) -> Self {
         Self {
             config: TokioRwLock::new(config),
-            frontend_path_rewrite_cache: OnceCell::new(),
-            problematic_clients_cache: OnceCell::new(),
+            frontend_path_rewrite_cache: TokioRwLock::new(None),
+            backend_nodes_cache: TokioRwLock::new(None),
+            forward_config_cache: TokioRwLock::new(None),
+            problematic_clients_cache: TokioRwLock::new(None),
             encrypt_cache: OnceCell::new(),
             decrypt_cache: OnceCell::new(),
             playback_info_cache: OnceCell::new(),
@@ -67,21 +95,31 @@ impl AppState {
             local_metadata_cache: OnceCell::new(),
             api_response_cache: OnceCell::new(),
             google_drive_file_id_cache: OnceCell::new(),
+            file_probe_cache: OnceCell::new(),
+            webdav_stat_cache: OnceCell::new(),
             emby_client: OnceCell::new(),
             google_drive_client: OnceCell::new(),
             open_list_client: OnceCell::new(),
-            rate_limiter_cache: OnceCell::new(),
+            rate_limiter_cache: TokioRwLock::new(None),
             api_request_locks: DashMap::new(),
             open_list_request_locks: DashMap::new(),
             playback_info_request_locks: DashMap::new(),
             strm_request_locks: DashMap::new(),
             local_metadata_request_locks: DashMap::new(),
             google_drive_file_id_request_locks: DashMap::new(),
+            file_probe_request_locks: DashMap::new(),
+            webdav_stat_request_locks: DashMap::new(),
             google_drive_token_cache: DashMap::new(),
             google_drive_refresh_locks: DashMap::new(),
             google_drive_refresh_backoff_until: DashMap::new(),
+            google_drive_credential_backoff_until: DashMap::new(),
+            google_drive_folder_indexes: DashMap::new(),
             webdav_auth_cache: DashMap::new(),
             webdav_auth_probe_locks: DashMap::new(),
+            openlist_token_cache: DashMap::new(),
+            openlist_login_locks: DashMap::new(),
+            smb_sessions: Arc::new(smb::SmbSessionPool::new()),
+            circuit_breakers: Arc::new(resilience::CircuitBreakers::new()),
         }
     }
 
@@ -91,11 +129,7 @@ impl AppState {
 
     pub async fn get_cache_settings(&self) -> (u64, u64) {
         let config = self.get_config().await;
-        match config.general.memory_mode.as_str() {
-            "low" => (256, 60 * 60 * 4),
-            "high" => (2048, 60 * 60 * 12),
-            _ => (512, 60 * 60 * 8),
-        }
+        cache_settings(&config)
     }
 
     pub async fn get_api_cache_settings(&self) -> (u64, u64) {
@@ -107,51 +141,118 @@ impl AppState {
         }
     }
 
-    pub async fn get_frontend_path_rewrite_cache(&self) -> &Vec<PathRewriter> {
+    pub async fn get_frontend_path_rewrite_cache(
+        &self,
+    ) -> Arc<Vec<PathRewriter>> {
+        // The config guard is held until the cache is filled so a concurrent
+        // reload cannot leave rewriters from the previous config behind.
         let config = self.get_config().await;
+        if let Some(cached) =
+            self.frontend_path_rewrite_cache.read().await.as_ref()
+        {
+            return cached.clone();
+        }
+
+        let rewriters = match &config.frontend {
+            Some(frontend) => frontend
+                .path_rewrites
+                .iter()
+                .map(|path_rewrite| {
+                    PathRewriter::new(
+                        path_rewrite.enable,
+                        &path_rewrite.pattern,
+                        &path_rewrite.replacement,
+                    )
+                })
+                .collect(),
+            None => vec![],
+        };
         self.frontend_path_rewrite_cache
-            .get_or_init(|| async move {
-                let frontend_config = match &config.frontend {
-                    Some(config) => config,
-                    None => return vec![],
-                };
-                frontend_config
-                    .clone()
-                    .path_rewrites
-                    .into_iter()
-                    .map(|path_rewrite| {
-                        PathRewriter::new(
-                            path_rewrite.enable,
-                            &path_rewrite.pattern,
-                            &path_rewrite.replacement,
-                        )
-                    })
-                    .collect()
-            })
+            .write()
             .await
+            .get_or_insert_with(|| Arc::new(rewriters))
+            .clone()
     }
 
-    pub async fn get_problematic_clients(&self) -> &Vec<String> {
+    /// Backend nodes in routing order (lowest `priority` first).
+    pub async fn get_backend_nodes(&self) -> Arc<Vec<BackendNode>> {
         let config = self.get_config().await;
-        self.problematic_clients_cache
-            .get_or_init(|| async move {
-                let mut clients: HashSet<String> = PROBLEMATIC_CLIENTS
+        if let Some(cached) = self.backend_nodes_cache.read().await.as_ref() {
+            return cached.clone();
+        }
+
+        let mut nodes = config.backend_nodes.clone();
+        nodes.sort_by_key(|node| node.priority);
+        self.backend_nodes_cache
+            .write()
+            .await
+            .get_or_insert_with(|| Arc::new(nodes))
+            .clone()
+    }
+
+    /// Settings the frontend signs redirects with; `None` without a
+    /// `Backend` section to point them at.
+    pub async fn get_forward_config(&self) -> Option<Arc<ForwardConfig>> {
+        let config = self.get_config().await;
+        if let Some(cached) = self.forward_config_cache.read().await.as_ref() {
+            return Some(cached.clone());
+        }
+
+        let backend = config.backend.as_ref()?;
+        let (_, ttl) = cache_settings(&config);
+        let forward_config = ForwardConfig {
+            expired_seconds: ttl,
+            backend_url: backend.uri().to_string(),
+            crypto_key: config.general.encipher_key.clone(),
+            crypto_iv: config.general.encipher_iv.clone(),
+            emby_api_key: config.emby.token.to_string(),
+            check_file_existence: config
+                .frontend
+                .as_ref()
+                .is_some_and(|frontend| frontend.check_file_existence),
+        };
+        Some(
+            self.forward_config_cache
+                .write()
+                .await
+                .get_or_insert_with(|| Arc::new(forward_config))
+                .clone(),
+        )
+    }
+
+    /// Shared per-node circuit breakers for upstream calls.
+    pub fn circuit_breakers(&self) -> Arc<resilience::CircuitBreakers> {
+        self.circuit_breakers.clone()
+    }
+
+    pub async fn get_problematic_clients(&self) -> Arc<Vec<String>> {
+        let config = self.get_config().await;
+        if let Some(cached) =
+            self.problematic_clients_cache.read().await.as_ref()
+        {
+            return cached.clone();
+        }
+
+        let mut clients: HashSet<String> = PROBLEMATIC_CLIENTS
+            .iter()
+            .map(|s| s.to_lowercase())
+            .collect();
+
+        if let Some(backend_config) = config.backend.as_ref() {
+            clients.extend(
+                backend_config
+                    .problematic_clients
                     .iter()
-                    .map(|s| s.to_lowercase())
-                    .collect();
-
-                if let Some(backend_config) = config.backend.as_ref() {
-                    clients.extend(
-                        backend_config
-                            .problematic_clients
-                            .iter()
-                            .map(|s| s.to_lowercase()),
-                    );
-                }
-
-                clients.into_iter().filter(|s| !s.is_empty()).collect()
-            })
+                    .map(|s| s.to_lowercase()),
+            );
+        }
+
+        let clients = clients.into_iter().filter(|s| !s.is_empty()).collect();
+        self.problematic_clients_cache
+            .write()
             .await
+            .get_or_insert_with(|| Arc::new(clients))
+            .clone()
     }
 
     pub async fn get_encrypt_cache(&self) -> &GeneralCache {
@@ -216,6 +317,24 @@ impl AppState {
             .await
     }
 
+    pub async fn get_file_probe_cache(&self) -> &GeneralCache {
+        let (capacity, _) = self.get_cache_settings().await;
+        self.file_probe_cache
+            .get_or_init(|| async move {
+                GeneralCache::new(capacity, FILE_PROBE_CACHE_TTL_SECS)
+            })
+            .await
+    }
+
+    pub async fn get_webdav_stat_cache(&self) -> &GeneralCache {
+        let (capacity, _) = self.get_cache_settings().await;
+        self.webdav_stat_cache
+            .get_or_init(|| async move {
+                GeneralCache::new(capacity, WEBDAV_STAT_CACHE_TTL_SECS)
+            })
+            .await
+    }
+
     pub async fn get_emby_client(&self) -> &Arc<EmbyClient> {
         self.emby_client
             .get_or_init(|| async move {
@@ -252,43 +371,85 @@ impl AppState {
         &self,
         node_uuid: &str,
     ) -> Option<RateLimiterCache> {
-        let cache_map = self
-            .rate_limiter_cache
-            .get_or_init(|| async move {
-                let config = self.get_config().await;
-                let (capacity, ttl) = self.get_cache_settings().await;
-                let map = DashMap::new();
-
-                // Per-client byte limiting is only applied in `LocalStreamer` (Disk → local file).
-                // WebDAV / OpenList / DirectLink / StreamRelay proxy paths do not use this cache.
-                for node in &config.backend_nodes {
-                    if !node
-                        .backend_type
-                        .eq_ignore_ascii_case(DISK_BACKEND_TYPE)
-                    {
-                        continue;
-                    }
-                    let cache = RateLimiterCache::new(
-                        capacity * 2,
-                        ttl,
-                        node.client_speed_limit_kbs,
-                        node.client_burst_speed_kbs,
-                    );
-                    cache.start_refill_task();
-                    map.insert(node.uuid.clone(), cache);
-                }
-
-                map
-            })
-            .await;
+        let config = self.get_config().await;
+        if let Some(map) = self.rate_limiter_cache.read().await.as_ref() {
+            return map.get(node_uuid).map(|r| r.value().clone());
+        }
 
-        cache_map.get(node_uuid).map(|r| r.value().clone())
+        let mut cache = self.rate_limiter_cache.write().await;
+        let map =
+            cache.get_or_insert_with(|| build_rate_limiters(&config, None));
+        map.get(node_uuid).map(|r| r.value().clone())
     }
 
     pub async fn init_rate_limiters(&self) {
         self.get_rate_limiter_cache("").await;
     }
 
+    /// Swaps in a freshly loaded config without restarting the listeners.
+    ///
+    /// Nodes that keep their name and type keep their UUID, so per-node
+    /// caches and rate limiters carry over. Returns the config sections whose
+    /// changes only apply after a restart; `Http2` keeps its running value.
+    pub async fn reload_config(
+        self: &Arc<Self>,
+        mut config: Config,
+    ) -> Vec<&'static str> {
+        let mut current = self.config.write().await;
+        carry_over_node_uuids(
+            &current.backend_nodes,
+            &mut config.backend_nodes,
+        );
+        let restart_required = restart_required_sections(&current, &config);
+        config.http2 = current.http2.clone();
+
+        let previous = std::mem::replace(&mut *current, config);
+        *self.frontend_path_rewrite_cache.write().await = None;
+        *self.backend_nodes_cache.write().await = None;
+        *self.forward_config_cache.write().await = None;
+        *self.problematic_clients_cache.write().await = None;
+        // Cached signs carry the old backend URL, and links signed with a
+        // rotated key must stop resolving.
+        if let Some(cache) = self.encrypt_cache.get() {
+            cache.clear();
+        }
+        if let Some(cache) = self.decrypt_cache.get() {
+            cache.clear();
+        }
+        // Logins, probed auth schemes and pooled sessions were made with
+        // the old credentials; links and file ids with the old roots.
+        self.webdav_auth_cache.clear();
+        self.openlist_token_cache.clear();
+        self.google_drive_token_cache.clear();
+        self.google_drive_refresh_backoff_until.clear();
+        self.smb_sessions.clear();
+        if let Some(cache) = self.open_list_cache.get() {
+            cache.clear();
+        }
+        if let Some(cache) = self.google_drive_file_id_cache.get() {
+            cache.clear();
+        }
+        let mut limiters = self.rate_limiter_cache.write().await;
+        let reused = limiters.take().map(|map| (&previous, map));
+        *limiters = Some(build_rate_limiters(&current, reused));
+
+        info_log!(
+            CONFIG_LOGGER_DOMAIN,
+            "config_reloaded path={} nodes={} restart_required={:?}",
+            current.path.display(),
+            current.backend_nodes.len(),
+            restart_required
+        );
+        drop(limiters);
+        drop(current);
+        // Indexer tasks hold the node they were started with.
+        google_drive_index::reconcile_google_drive_folder_indexers(
+            self.clone(),
+        )
+        .await;
+        restart_required
+    }
+
     pub(crate) fn request_lock(
         locks: &DashMap<String, Arc<TokioMutex<()>>>,
         cache_key: &str,
@@ -357,6 +518,134 @@ impl AppState {
     }
 }
 
+type RateLimiterMap = Arc<DashMap<String, RateLimiterCache>>;
+
+static RUNTIME_STATE: OnceLock<Arc<AppState>> = OnceLock::new();
+
+/// Makes the stream runtime reachable from the in-process Web Studio
+/// (`embystream run --web`), e.g. for live config reloads.
+pub fn publish_runtime_state(state: Arc<AppState>) {
+    let _ = RUNTIME_STATE.set(state);
+}
+
+pub fn runtime_state() -> Option<Arc<AppState>> {
+    RUNTIME_STATE.get().cloned()
+}
+
+fn cache_settings(config: &Config) -> (u64, u64) {
+    match config.general.memory_mode.as_str() {
+        "low" => (256, 60 * 60 * 4),
+        "high" => (2048, 60 * 60 * 12),
+        _ => (512, 60 * 60 * 8),
+    }
+}
+
+/// Builds per-node limiters. With `previous`, a node whose limits did not
+/// change keeps its limiter (and the clients' remaining budget).
+fn build_rate_limiters(
+    config: &Config,
+    previous: Option<(&Config, RateLimiterMap)>,
+) -> RateLimiterMap {
+    let (capacity, ttl) = cache_settings(config);
+    let map = DashMap::new();
+
+    // Per-client byte limiting is only applied where EmbyStream reads
+    // the bytes itself: `LocalStreamer` (Disk) and `SmbStreamer` (Smb).
+    // WebDAV / OpenList / DirectLink / StreamRelay proxy paths do not use this cache.
+    for node in &config.backend_nodes {
+        if !node.backend_type.eq_ignore_ascii_case(DISK_BACKEND_TYPE)
+            && !node.backend_type.eq_ignore_ascii_case(smb::BACKEND_TYPE)
+        {
+            continue;
+        }
+        let reused = previous.as_ref().and_then(|(old_config, old_map)| {
+            let unchanged = old_config.backend_nodes.iter().any(|old| {
+                old.uuid == node.uuid
+                    && old.client_speed_limit_kbs == node.client_speed_limit_kbs
+                    && old.client_burst_speed_kbs == node.client_burst_speed_kbs
+            });
+            unchanged
+                .then(|| old_map.get(&node.uuid).map(|r| r.value().clone()))
+                .flatten()
+        });
+        let cache = reused.unwrap_or_else(|| {
+            let cache = RateLimiterCache::new(
+                capacity * 2,
+                ttl,
+                node.client_speed_limit_kbs,
+                node.client_burst_speed_kbs,
+            );
+            cache.start_refill_task();
+            cache
+        });
+        map.insert(node.uuid.clone(), cache);
+    }
+
+    Arc::new(map)
+}
+
+fn carry_over_node_uuids(
+    previous: &[crate::config::backend::BackendNode],
+    next: &mut [crate::config::backend::BackendNode],
+) {
+    for node in next {
+        if let Some(old) = previous.iter().find(|old| {
+            old.name == node.name
+                && old.backend_type.eq_ignore_ascii_case(&node.backend_type)
+        }) {
+            node.uuid = old.uuid.clone();
+        }
+    }
+}
+
+fn restart_required_sections(
+    current: &Config,
+    next: &Config,
+) -> Vec<&'static str> {
+    fn differs<T: serde::Serialize>(a: &T, b: &T) -> bool {
+        serde_json::to_value(a).ok() != serde_json::to_value(b).ok()
+    }
+
+    let listen_port = |config: &Config| {
+        (
+            config
+                .frontend
+                .as_ref()
+                .map(|frontend| frontend.listen_port),
+            config.backend.as_ref().map(|backend| backend.listen_port),
+        )
+    };
+
+    let mut sections = Vec::new();
+    if differs(&current.log, &next.log) {
+        sections.push("Log");
+    }
+    if current.general.stream_mode != next.general.stream_mode
+        || current.general.memory_mode != next.general.memory_mode
+    {
+        sections.push("General");
+    }
+    if current.emby.get_uri() != next.emby.get_uri() {
+        sections.push("Emby");
+    }
+    if listen_port(current) != listen_port(next) {
+        sections.push("listen_port");
+    }
+    let anti_reverse_proxy = |config: &Config| {
+        config
+            .frontend
+            .as_ref()
+            .map(|frontend| frontend.anti_reverse_proxy.clone())
+    };
+    if differs(&anti_reverse_proxy(current), &anti_reverse_proxy(next)) {
+        sections.push("Frontend.AntiReverseProxy");
+    }
+    if differs(&current.http2, &next.http2) {
+        sections.push("Http2");
+    }
+    sections
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, sync::Arc};
@@ -365,7 +654,12 @@ mod tests {
     use dashmap::DashMap;
     use tokio::sync::Mutex as TokioMutex;
 
-    use crate::config::core::{finish_raw_config, parse_raw_config_str};
+    use crate::{
+        config::core::{finish_raw_config, parse_raw_config_str},
+        core::backend::webdav_auth::WebDavAuth,
+        oauthutil::OAuthToken,
+        test_support::TestConfig,
+    };
 
     const MIN_FRONTEND_CONFIG: &str = r#"
 [Log]
@@ -431,6 +725,98 @@ host = ""
         assert_eq!(locks.len(), 1);
     }
 
+    #[tokio::test]
+    async fn reload_config_refreshes_derived_caches() {
+        let state = Arc::new(test_state().await);
+        assert!(state.get_frontend_path_rewrite_cache().await.is_empty());
+        state.webdav_auth_cache.insert(
+            "dav|http://nas".to_string(),
+            WebDavAuth::Basic("Basic b2xkOnNlY3JldA==".to_string()),
+        );
+        state
+            .openlist_token_cache
+            .insert("list|http://list|admin".to_string(), "old".to_string());
+        state.google_drive_token_cache.insert(
+            "google-drive-token:gd-node".to_string(),
+            OAuthToken::default(),
+        );
+        state
+            .get_google_drive_file_id_cache()
+            .await
+            .insert("gd-node:/movie.mkv".to_string(), "file-id".to_string());
+
+        let updated = MIN_FRONTEND_CONFIG.replace(
+            "[Frontend]\nlisten_port = 60001\n",
+            "[Frontend]\nlisten_port = 60002\n\n\
+             [[Frontend.PathRewrite]]\nenable = true\n\
+             pattern = \"^/a\"\nreplacement = \"/b\"\n",
+        );
+        let raw = parse_raw_config_str(&updated).expect("parse");
+        let config =
+            finish_raw_config(PathBuf::from("test.toml"), raw).expect("finish");
+
+        let restart_required = state.reload_config(config).await;
+
+        assert_eq!(restart_required, vec!["listen_port"]);
+        assert_eq!(state.get_frontend_path_rewrite_cache().await.len(), 1);
+        assert!(state.webdav_auth_cache.is_empty());
+        assert!(state.openlist_token_cache.is_empty());
+        assert!(state.google_drive_token_cache.is_empty());
+        assert!(
+            state
+                .get_google_drive_file_id_cache()
+                .await
+                .get::<String>("gd-node:/movie.mkv")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn reload_config_swaps_routing_nodes_and_forward_config() {
+        let config = |base_url: &str, nodes: &[(&str, i32)]| {
+            let mut config =
+                TestConfig::new().stream_mode("dual").section(&format!(
+                    "[Backend]\nlisten_port = 60002\n\
+                     base_url = \"{base_url}\"\nport = \"443\"\n\
+                     path = \"\"\nproblematic_clients = []\n"
+                ));
+            for (name, priority) in nodes {
+                config = config.section(&format!(
+                    "[[BackendNode]]\nname = \"{name}\"\ntype = \"Disk\"\n\
+                     pattern = \"/mnt/.*\"\npriority = {priority}\n"
+                ));
+            }
+            config.build()
+        };
+        let names = |nodes: &[crate::config::backend::BackendNode]| {
+            nodes
+                .iter()
+                .map(|node| node.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let state = Arc::new(
+            AppState::new(config("https://old.example.com", &[("Movies", 0)]))
+                .await,
+        );
+        assert_eq!(names(&state.get_backend_nodes().await), ["Movies"]);
+        let forward = state.get_forward_config().await.expect("forward");
+        assert!(forward.backend_url.starts_with("https://old.example.com"));
+
+        state
+            .reload_config(config(
+                "https://new.example.com",
+                &[("Movies", 10), ("Shows", 5)],
+            ))
+            .await;
+
+        assert_eq!(
+            names(&state.get_backend_nodes().await),
+            ["Shows", "Movies"]
+        );
+        let forward = state.get_forward_config().await.expect("forward");
+        assert!(forward.backend_url.starts_with("https://new.example.com"));
+    }
+
     #[tokio::test]
     async fn get_emby_client_reuses_single_instance() {
         let state = test_state().await;
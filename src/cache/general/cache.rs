@@ -55,6 +55,11 @@ impl Cache {
         self.inner.invalidate(key);
     }
 
+    /// Drops every entry, e.g. after the settings they derive from change.
+    pub fn clear(&self) {
+        self.inner.invalidate_all();
+    }
+
     /// Returns the current number of entries in the cache.
     pub fn len(&self) -> u64 {
         self.inner.entry_count()
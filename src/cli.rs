@@ -70,6 +70,8 @@ pub enum Commands {
     /// Interactive TOML configuration wizard.
     /// Prompt language follows `--lang`.
     Config(ConfigArgs),
+    /// Manage the `[Secrets]` store behind `${secret:NAME}` references.
+    Secrets(SecretsArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -116,6 +118,33 @@ pub enum ConfigSubcommand {
     Template,
 }
 
+#[derive(Parser, Debug)]
+pub struct SecretsArgs {
+    /// Config file whose `[Secrets]` table selects the store.
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub sub: SecretsSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretsSubcommand {
+    /// Print stored secret names; values are never printed.
+    List,
+    /// Store a secret. Reads the value from stdin unless `--value` is given.
+    Set {
+        #[arg(value_name = "NAME")]
+        name: String,
+        #[arg(long, value_name = "VALUE")]
+        value: Option<String>,
+    },
+    /// Delete a secret.
+    Remove {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
 #[derive(Parser, Debug)]
 pub struct RunArgs {
     #[arg(short, long, value_name = "FILE")]
@@ -195,6 +224,9 @@ pub struct WebAdminArgs {
 pub enum WebAdminSubcommand {
     /// Reset an administrator password and print the new random password.
     ResetPassword(WebAdminResetPasswordArgs),
+    /// Turn off two-factor authentication for an account that lost its
+    /// authenticator and recovery codes.
+    DisableTwoFactor(WebAdminDisableTwoFactorArgs),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -207,3 +239,14 @@ pub struct WebAdminResetPasswordArgs {
     #[arg(long, value_name = "DIR", default_value = "web_data")]
     pub data_dir: PathBuf,
 }
+
+#[derive(Parser, Debug, Clone)]
+pub struct WebAdminDisableTwoFactorArgs {
+    /// Username whose two-factor authentication should be turned off.
+    #[arg(long, value_name = "NAME")]
+    pub username: String,
+
+    /// Data directory for SQLite, sessions, artifacts, and audit logs.
+    #[arg(long, value_name = "DIR", default_value = "web_data")]
+    pub data_dir: PathBuf,
+}
@@ -0,0 +1,45 @@
+//! `embystream secrets`: manage the store behind `${secret:NAME}`.
+
+use std::io::Read as _;
+
+use crate::{
+    cli::{SecretsArgs, SecretsSubcommand},
+    config::{core::Config, error::ConfigError, secrets::SecretStore},
+};
+
+pub fn run(args: &SecretsArgs) -> Result<(), ConfigError> {
+    let config_path = Config::resolve_config_path(args.config.as_deref())?;
+    let store = SecretStore::from_config_file(&config_path)?;
+
+    match &args.sub {
+        SecretsSubcommand::List => {
+            for name in store.names()? {
+                println!("{name}");
+            }
+        }
+        SecretsSubcommand::Set { name, value } => {
+            let value = match value {
+                Some(value) => value.clone(),
+                None => {
+                    let mut value = String::new();
+                    std::io::stdin().read_to_string(&mut value)?;
+                    value.trim_end_matches(['\r', '\n']).to_string()
+                }
+            };
+            store.set(name, &value)?;
+            println!(
+                "Stored secret '{}' in the {} provider",
+                name,
+                store.provider().as_str()
+            );
+        }
+        SecretsSubcommand::Remove { name } => {
+            if store.remove(name)? {
+                println!("Removed secret '{name}'");
+            } else {
+                println!("Secret '{name}' was not stored");
+            }
+        }
+    }
+    Ok(())
+}
@@ -3,10 +3,13 @@
 use serde::Serialize;
 
 use crate::config::{
-    backend::{Backend, BackendNode, GoogleDriveConfig, WebDavConfig},
+    backend::{
+        Backend, BackendNode, GoogleDriveConfig, GoogleDriveRoot, WebDavConfig,
+    },
     frontend::Frontend,
     general::StreamMode,
-    http2::Http2,
+    http2::{AcmeConfig, FrontendTls, Http2, SniCertificate},
+    secrets::SecretsConfig,
     types::{AntiReverseProxyConfig, PathRewriteConfig, RawConfig},
 };
 use crate::core::backend::webdav::{DEFAULT_QUERY_PARAM, MODE_PATH_JOIN};
@@ -17,6 +20,12 @@ struct EmitHttp2 {
     ssl_cert_file: String,
     #[serde(skip_serializing_if = "str::is_empty")]
     ssl_key_file: String,
+    #[serde(rename = "Sni", skip_serializing_if = "Vec::is_empty")]
+    sni: Vec<SniCertificate>,
+    #[serde(rename = "Frontend", skip_serializing_if = "Option::is_none")]
+    frontend: Option<FrontendTls>,
+    #[serde(rename = "Acme", skip_serializing_if = "Option::is_none")]
+    acme: Option<AcmeConfig>,
 }
 
 #[derive(Serialize)]
@@ -75,6 +84,10 @@ struct EmitBackendNode {
     google_drive: Option<EmitGoogleDrive>,
     #[serde(rename = "WebDav", skip_serializing_if = "Option::is_none")]
     webdav: Option<EmitWebDav>,
+    #[serde(rename = "Smb", skip_serializing_if = "Option::is_none")]
+    smb: Option<crate::config::backend::SmbConfig>,
+    #[serde(rename = "Resilience", skip_serializing_if = "Option::is_none")]
+    resilience: Option<crate::config::backend::ResilienceConfig>,
 }
 
 fn is_zero_i32(n: &i32) -> bool {
@@ -146,6 +159,12 @@ fn should_omit_google_drive_table(g: &GoogleDriveConfig) -> bool {
         && g.client_secret.trim().is_empty()
         && g.drive_id.trim().is_empty()
         && g.drive_name.trim().is_empty()
+        && g.root.is_shared_drive()
+        && g.root_folder_id.trim().is_empty()
+        && g.service_account_files().next().is_none()
+        && g.service_account_subject.trim().is_empty()
+        && !g.folder_index
+        && g.token_secret.trim().is_empty()
         && g.access_token.trim().is_empty()
         && g.refresh_token.trim().is_empty()
 }
@@ -162,6 +181,18 @@ struct EmitGoogleDrive {
     drive_id: String,
     #[serde(skip_serializing_if = "str::is_empty")]
     drive_name: String,
+    #[serde(skip_serializing_if = "GoogleDriveRoot::is_shared_drive")]
+    root: GoogleDriveRoot,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    root_folder_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    service_account_files: Vec<String>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    service_account_subject: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    folder_index: bool,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    token_secret: String,
     #[serde(skip_serializing_if = "str::is_empty")]
     access_token: String,
     #[serde(skip_serializing_if = "str::is_empty")]
@@ -178,6 +209,15 @@ fn map_google_drive_emit(g: &GoogleDriveConfig) -> Option<EmitGoogleDrive> {
         client_secret: g.client_secret.clone(),
         drive_id: g.drive_id.clone(),
         drive_name: g.drive_name.clone(),
+        root: g.root,
+        root_folder_id: g.root_folder_id.clone(),
+        service_account_files: g
+            .service_account_files()
+            .map(str::to_string)
+            .collect(),
+        service_account_subject: g.service_account_subject.clone(),
+        folder_index: g.folder_index,
+        token_secret: g.token_secret.trim().to_string(),
         access_token: g.access_token.clone(),
         refresh_token: g.refresh_token.clone(),
     })
@@ -245,16 +285,26 @@ fn map_node(n: &BackendNode) -> EmitBackendNode {
         direct_link: n.direct_link.clone(),
         google_drive: n.google_drive.as_ref().and_then(map_google_drive_emit),
         webdav: n.webdav.as_ref().and_then(map_webdav_emit),
+        smb: n.smb.clone(),
+        resilience: n.resilience.clone(),
     }
 }
 
 fn map_http2(h: &Http2) -> Option<EmitHttp2> {
-    if h.ssl_cert_file.is_empty() && h.ssl_key_file.is_empty() {
+    if h.ssl_cert_file.is_empty()
+        && h.ssl_key_file.is_empty()
+        && h.sni.is_empty()
+        && h.frontend.is_none()
+        && h.acme.is_none()
+    {
         return None;
     }
     Some(EmitHttp2 {
         ssl_cert_file: h.ssl_cert_file.clone(),
         ssl_key_file: h.ssl_key_file.clone(),
+        sni: h.sni.clone(),
+        frontend: h.frontend.clone(),
+        acme: h.acme.clone(),
     })
 }
 
@@ -322,6 +372,14 @@ struct WizardEmitBackend {
 #[derive(Serialize)]
 struct WizardEmitFallback {
     video_missing_path: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    upstream_down_path: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    quota_exceeded_path: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    sign_expired_path: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    concurrent_limit_path: String,
 }
 
 #[derive(Serialize)]
@@ -344,6 +402,8 @@ struct WizardEmitDoc {
     backend_nodes: Vec<EmitBackendNode>,
     #[serde(rename = "Fallback")]
     fallback: WizardEmitFallback,
+    #[serde(rename = "Secrets", skip_serializing_if = "Option::is_none")]
+    secrets: Option<SecretsConfig>,
 }
 
 fn map_anti_wizard_opt(
@@ -425,7 +485,12 @@ pub fn emit_wizard_config_toml(
         backend_nodes,
         fallback: WizardEmitFallback {
             video_missing_path: raw.fallback.video_missing_path.clone(),
+            upstream_down_path: raw.fallback.upstream_down_path.clone(),
+            quota_exceeded_path: raw.fallback.quota_exceeded_path.clone(),
+            sign_expired_path: raw.fallback.sign_expired_path.clone(),
+            concurrent_limit_path: raw.fallback.concurrent_limit_path.clone(),
         },
+        secrets: raw.secrets.clone(),
     };
     toml::to_string_pretty(&doc)
 }
@@ -485,6 +550,14 @@ pub(crate) mod compact_emit_test {
     struct EmitFallback {
         #[serde(skip_serializing_if = "str::is_empty")]
         video_missing_path: String,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        upstream_down_path: String,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        quota_exceeded_path: String,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        sign_expired_path: String,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        concurrent_limit_path: String,
     }
 
     #[derive(Serialize)]
@@ -615,6 +688,13 @@ pub(crate) mod compact_emit_test {
             backend_nodes,
             fallback: EmitFallback {
                 video_missing_path: raw.fallback.video_missing_path.clone(),
+                upstream_down_path: raw.fallback.upstream_down_path.clone(),
+                quota_exceeded_path: raw.fallback.quota_exceeded_path.clone(),
+                sign_expired_path: raw.fallback.sign_expired_path.clone(),
+                concurrent_limit_path: raw
+                    .fallback
+                    .concurrent_limit_path
+                    .clone(),
             },
         };
         toml::to_string_pretty(&doc)
@@ -4,7 +4,7 @@ mod discover;
 pub(crate) mod emit;
 mod l10n;
 mod mask;
-mod persist;
+pub(crate) mod persist;
 mod regex_lab;
 pub(crate) mod template_payload;
 mod terminal;
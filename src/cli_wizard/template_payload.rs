@@ -65,6 +65,7 @@ fn user_agent_template() -> UserAgent {
 fn fallback_template() -> FallbackConfig {
     FallbackConfig {
         video_missing_path: "/mnt/anime/fallback/video_missing.mp4".into(),
+        ..FallbackConfig::default()
     }
 }
 
@@ -136,6 +137,7 @@ pub(crate) fn build_template_raw(mode: StreamMode) -> RawConfig {
         open_list: None,
         direct_link: None,
         fallback: fallback_template(),
+        secrets: None,
         frontend: None,
         backend: None,
         backend_nodes: None,
@@ -221,10 +223,13 @@ fn openlist_example_node() -> BackendNode {
             base_url: "http://alist.example.com".into(),
             port: String::new(),
             token: "replace_openlist_token".into(),
+            ..OpenList::default()
         }),
         direct_link: None,
         google_drive: None,
         webdav: None,
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -256,6 +261,8 @@ fn directlink_example_node() -> BackendNode {
         }),
         google_drive: None,
         webdav: None,
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -288,6 +295,8 @@ fn webdav_accel_example_node() -> BackendNode {
             node_uuid: "webdav_node_a".into(),
             ..Default::default()
         }),
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -326,6 +335,8 @@ fn disk_example_node() -> BackendNode {
         direct_link: None,
         google_drive: None,
         webdav: None,
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -363,6 +374,8 @@ fn google_drive_example_node() -> BackendNode {
             ..Default::default()
         }),
         webdav: None,
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -392,10 +405,13 @@ fn openlist_node_dual() -> BackendNode {
             base_url: "http://alist.example.com".into(),
             port: String::new(),
             token: "replace_openlist_token".into(),
+            ..OpenList::default()
         }),
         direct_link: None,
         google_drive: None,
         webdav: None,
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -427,6 +443,8 @@ fn directlink_node_dual() -> BackendNode {
         }),
         google_drive: None,
         webdav: None,
+        smb: None,
+        resilience: None,
     }
 }
 
@@ -213,6 +213,8 @@ fn invalid_regex_in_node_rejected() {
         direct_link: None,
         google_drive: None,
         webdav: None,
+        smb: None,
+        resilience: None,
     }]);
     assert!(validate_raw_regexes(&raw).is_err());
 }
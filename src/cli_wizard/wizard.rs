@@ -12,8 +12,11 @@ use crate::{
     cli::ConfigArgs,
     config::{
         backend::{
-            Backend, BackendNode, direct::DirectLink, disk::Disk,
-            google_drive::GoogleDriveConfig, openlist::OpenList,
+            Backend, BackendNode,
+            direct::DirectLink,
+            disk::Disk,
+            google_drive::{GoogleDriveConfig, GoogleDriveRoot},
+            openlist::OpenList,
             webdav::WebDavConfig,
         },
         core::{finish_raw_config, parse_raw_config_str},
@@ -748,6 +751,7 @@ fn build_new_raw_skeleton(mode: StreamMode) -> Result<RawConfig> {
         open_list: None,
         direct_link: None,
         fallback: FallbackConfig::default(),
+        secrets: None,
     })
 }
 
@@ -923,6 +927,7 @@ fn prompt_shared_sections(raw: &mut RawConfig) -> Result<()> {
         raw.http2 = Some(Http2 {
             ssl_cert_file: cert,
             ssl_key_file: key,
+            ..Http2::default()
         });
     }
 
@@ -1439,6 +1444,7 @@ fn prompt_one_backend_node() -> Result<BackendNode> {
                         base_url: b,
                         port: p,
                         token: tok,
+                        ..OpenList::default()
                     }),
                     None,
                     None,
@@ -1467,12 +1473,52 @@ fn prompt_one_backend_node() -> Result<BackendNode> {
                 );
                 let node_uuid: String = input_text_w_echo(None, false)?;
                 intro(
-                    tr("wizard.field.drive_id"),
-                    tr("wizard.prompt.google_drive_drive_id"),
+                    tr("wizard.field.root"),
+                    tr("wizard.prompt.google_drive_root"),
                     None,
                     None,
                 );
-                let drive_id: String = input_text_w_echo(None, true)?;
+                let root_items = vec![
+                    tr("wizard.option.google_drive_root.shared_drive"),
+                    tr("wizard.option.google_drive_root.my_drive"),
+                    tr("wizard.option.google_drive_root.folder"),
+                ];
+                let ridx = Select::with_theme(&theme())
+                    .with_prompt("")
+                    .items(&root_items)
+                    .default(0)
+                    .report(false)
+                    .interact()
+                    .map_err(|e| anyhow!(e.to_string()))?;
+                let root = match ridx {
+                    1 => GoogleDriveRoot::MyDrive,
+                    2 => GoogleDriveRoot::Folder,
+                    _ => GoogleDriveRoot::SharedDrive,
+                };
+                print_field_value_line(root.as_str());
+                let drive_id: String = if root.is_shared_drive() {
+                    intro(
+                        tr("wizard.field.drive_id"),
+                        tr("wizard.prompt.google_drive_drive_id"),
+                        None,
+                        None,
+                    );
+                    input_text_w_echo(None, true)?
+                } else {
+                    String::new()
+                };
+                let root_folder_id: String = if root == GoogleDriveRoot::Folder
+                {
+                    intro(
+                        tr("wizard.field.root_folder_id"),
+                        tr("wizard.prompt.google_drive_root_folder_id"),
+                        None,
+                        None,
+                    );
+                    input_text_w_echo(None, false)?
+                } else {
+                    String::new()
+                };
                 intro(
                     tr("wizard.field.drive_name"),
                     tr("wizard.prompt.google_drive_drive_name"),
@@ -1518,9 +1564,11 @@ fn prompt_one_backend_node() -> Result<BackendNode> {
                         client_secret,
                         drive_id,
                         drive_name,
+                        root,
+                        root_folder_id,
                         access_token,
                         refresh_token,
-                        token: None,
+                        ..Default::default()
                     }),
                     None,
                 )
@@ -1620,6 +1668,8 @@ fn prompt_one_backend_node() -> Result<BackendNode> {
         direct_link,
         google_drive,
         webdav,
+        smb: None,
+        resilience: None,
     })
 }
 
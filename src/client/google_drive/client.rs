@@ -14,9 +14,18 @@ use crate::{
 
 const GOOGLE_DRIVE_API_BASE: &str = "https://www.googleapis.com/drive/v3";
 const GOOGLE_OAUTH_TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
+const GOOGLE_OAUTH_AUTHORIZE_ENDPOINT: &str =
+    "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_DRIVE_READONLY_SCOPE: &str =
+    "https://www.googleapis.com/auth/drive.readonly";
 const GOOGLE_DRIVE_FOLDER_MIME: &str = "application/vnd.google-apps.folder";
+const JWT_BEARER_GRANT_TYPE: &str =
+    "urn:ietf:params:oauth:grant-type:jwt-bearer";
+/// Drive's alias for the root folder of the account's own My Drive.
+const GOOGLE_DRIVE_MY_DRIVE_ROOT: &str = "root";
+const INDEX_PAGE_SIZE: &str = "1000";
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Client {
     http: HttpClient,
     api_base: Arc<str>,
@@ -59,6 +68,16 @@ pub struct GoogleTokenRefreshResponse {
     pub expires_in: Option<u64>,
 }
 
+/// Tokens returned for an authorization code; `refresh_token` is only
+/// present when consent was granted for offline access.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoogleAuthorizationCodeResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: Option<u64>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SharedDriveRef {
     pub id: String,
@@ -67,8 +86,44 @@ pub struct SharedDriveRef {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GoogleDriveResolvedFile {
+    /// Shared Drive holding the file; empty for My Drive and folder roots.
     pub drive_id: String,
     pub file_id: String,
+    pub size: Option<u64>,
+}
+
+/// One file or folder as reported by `files.list` and `changes.list`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DriveFileEntry {
+    pub id: String,
+    pub name: String,
+    pub parents: Vec<String>,
+    pub is_folder: bool,
+    pub size: Option<u64>,
+    pub trashed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DriveFilesPage {
+    pub files: Vec<DriveFileEntry>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DriveChange {
+    pub file_id: String,
+    /// The file left the corpus or the account lost access to it.
+    pub removed: bool,
+    pub file: Option<DriveFileEntry>,
+}
+
+/// A page of `changes.list`; `new_start_page_token` is only set on the last
+/// page and is where the next poll resumes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DriveChangesPage {
+    pub changes: Vec<DriveChange>,
+    pub next_page_token: Option<String>,
+    pub new_start_page_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -79,6 +134,16 @@ struct TokenRefreshPayload {
     expires_in: Option<u64>,
 }
 
+#[derive(Debug, Deserialize)]
+struct AuthorizationCodePayload {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: String,
+    #[serde(default)]
+    token_type: String,
+    expires_in: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct DrivesListResponse {
     #[serde(default)]
@@ -97,9 +162,86 @@ struct FilesListResponse {
     files: Vec<FileEntryDto>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileMetadataDto {
+    id: String,
+    #[serde(default)]
+    drive_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartPageTokenResponse {
+    start_page_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexFilesResponse {
+    #[serde(default)]
+    files: Vec<IndexFileDto>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangesListResponse {
+    #[serde(default)]
+    changes: Vec<ChangeDto>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+    #[serde(default)]
+    new_start_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeDto {
+    #[serde(default)]
+    file_id: String,
+    #[serde(default)]
+    removed: bool,
+    #[serde(default)]
+    file: Option<IndexFileDto>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexFileDto {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    parents: Vec<String>,
+    #[serde(default)]
+    size: Option<String>,
+    #[serde(default)]
+    trashed: bool,
+}
+
+impl From<IndexFileDto> for DriveFileEntry {
+    fn from(dto: IndexFileDto) -> Self {
+        Self {
+            is_folder: dto.mime_type == GOOGLE_DRIVE_FOLDER_MIME,
+            size: dto.size.as_deref().and_then(|size| size.parse().ok()),
+            id: dto.id,
+            name: dto.name,
+            parents: dto.parents,
+            trashed: dto.trashed,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct FileEntryDto {
     id: String,
+    /// Drive reports `size` as a decimal string; absent for folders.
+    #[serde(default)]
+    size: Option<String>,
 }
 
 impl Default for Client {
@@ -182,6 +324,100 @@ impl Client {
         })
     }
 
+    /// Consent URL for the authorization-code flow. Offline access and a
+    /// forced consent prompt make Google return a refresh token every time.
+    pub fn authorization_url(
+        &self,
+        client_id: &str,
+        redirect_uri: &str,
+        state: &str,
+    ) -> String {
+        let query = serde_urlencoded::to_string([
+            ("client_id", client_id.trim()),
+            ("redirect_uri", redirect_uri),
+            ("response_type", "code"),
+            ("scope", GOOGLE_DRIVE_READONLY_SCOPE),
+            ("access_type", "offline"),
+            ("prompt", "consent"),
+            ("include_granted_scopes", "true"),
+            ("state", state),
+        ])
+        .unwrap_or_default();
+        format!("{GOOGLE_OAUTH_AUTHORIZE_ENDPOINT}?{query}")
+    }
+
+    pub async fn exchange_authorization_code(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<GoogleAuthorizationCodeResponse, GoogleDriveApiError> {
+        let client_id = client_id.trim();
+        if client_id.is_empty() {
+            return Err(GoogleDriveApiError::EmptyClientId);
+        }
+
+        let response = self
+            .http
+            .post(self.oauth_token_endpoint.as_ref())
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .form(&[
+                ("client_id", client_id),
+                ("client_secret", client_secret.trim()),
+                ("code", code.trim()),
+                ("redirect_uri", redirect_uri),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveApiError::ApiStatus { status, body });
+        }
+
+        let payload: AuthorizationCodePayload = response.json().await?;
+        Ok(GoogleAuthorizationCodeResponse {
+            access_token: payload.access_token,
+            refresh_token: payload.refresh_token,
+            token_type: payload.token_type,
+            expires_in: payload.expires_in,
+        })
+    }
+
+    /// Exchanges a signed service-account JWT for an access token through
+    /// the `jwt-bearer` grant.
+    pub async fn exchange_jwt_assertion(
+        &self,
+        assertion: &str,
+    ) -> Result<GoogleTokenRefreshResponse, GoogleDriveApiError> {
+        let response = self
+            .http
+            .post(self.oauth_token_endpoint.as_ref())
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .form(&[
+                ("grant_type", JWT_BEARER_GRANT_TYPE),
+                ("assertion", assertion),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveApiError::ApiStatus { status, body });
+        }
+
+        let payload: TokenRefreshPayload = response.json().await?;
+        Ok(GoogleTokenRefreshResponse {
+            access_token: payload.access_token,
+            token_type: payload.token_type,
+            expires_in: payload.expires_in,
+        })
+    }
+
     pub async fn find_shared_drive_by_name(
         &self,
         access_token: &str,
@@ -250,21 +486,29 @@ impl Client {
             return Err(GoogleDriveApiError::EmptyPath);
         }
 
-        let drive = match lookup {
-            DriveLookup::DriveId(id) => SharedDriveRef {
-                id: id.trim().to_string(),
-                name: String::new(),
-            },
-            DriveLookup::DriveName(name) => self
-                .find_shared_drive_by_name(access_token, name)
-                .await?
-                .ok_or_else(|| {
-                    GoogleDriveApiError::DriveNotFound(name.to_string())
-                })?,
+        let (drive_id, root_id) = match lookup {
+            DriveLookup::DriveId(id) => {
+                (id.trim().to_string(), id.trim().to_string())
+            }
+            DriveLookup::DriveName(name) => {
+                let drive = self
+                    .find_shared_drive_by_name(access_token, name)
+                    .await?
+                    .ok_or_else(|| {
+                        GoogleDriveApiError::DriveNotFound(name.to_string())
+                    })?;
+                (drive.id.clone(), drive.id)
+            }
+            DriveLookup::MyDrive => {
+                (String::new(), GOOGLE_DRIVE_MY_DRIVE_ROOT.to_string())
+            }
+            DriveLookup::Folder(id) => (String::new(), id.trim().to_string()),
         };
+        let scope = files_scope_params(lookup, &drive_id);
 
-        let mut parent_id = drive.id.clone();
-        let mut current_file_id = drive.id.clone();
+        let mut parent_id = root_id.clone();
+        let mut current_file_id = root_id;
+        let mut current_size = None;
         let mut logical_segments = VecDeque::with_capacity(segments.len());
 
         while let Some(segment) = segments.pop_front() {
@@ -275,15 +519,9 @@ impl Client {
                 .http
                 .get(format!("{}/files", self.api_base))
                 .header(AUTHORIZATION, bearer_token(access_token))
-                .query(&[
-                    ("pageSize", "2"),
-                    ("driveId", drive.id.as_str()),
-                    ("corpora", "drive"),
-                    ("includeItemsFromAllDrives", "true"),
-                    ("supportsAllDrives", "true"),
-                    ("fields", "files(id)"),
-                    ("q", q.as_str()),
-                ])
+                .query(&[("pageSize", "2")])
+                .query(&scope)
+                .query(&[("fields", "files(id,size)"), ("q", q.as_str())])
                 .send()
                 .await?;
 
@@ -303,6 +541,10 @@ impl Client {
                 }
                 1 => {
                     current_file_id = payload.files[0].id.clone();
+                    current_size = payload.files[0]
+                        .size
+                        .as_deref()
+                        .and_then(|size| size.parse::<u64>().ok());
                     parent_id = current_file_id.clone();
                 }
                 _ => {
@@ -318,8 +560,153 @@ impl Client {
         }
 
         Ok(GoogleDriveResolvedFile {
-            drive_id: drive.id,
+            drive_id,
             file_id: current_file_id,
+            size: current_size,
+        })
+    }
+
+    /// Resolves a file ID or alias (such as `root`) to its canonical ID and
+    /// the Shared Drive holding it, empty outside Shared Drives.
+    pub async fn get_file_drive(
+        &self,
+        access_token: &str,
+        file_id: &str,
+    ) -> Result<(String, String), GoogleDriveApiError> {
+        let response = self
+            .http
+            .get(format!("{}/files/{}", self.api_base, file_id.trim()))
+            .header(AUTHORIZATION, bearer_token(access_token))
+            .query(&[("fields", "id,driveId"), ("supportsAllDrives", "true")])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveApiError::ApiStatus { status, body });
+        }
+
+        let payload: FileMetadataDto = response.json().await?;
+        Ok((payload.id, payload.drive_id.unwrap_or_default()))
+    }
+
+    /// Token marking "now" in the change feed of one Shared Drive, or of
+    /// the user's corpus when `drive_id` is empty.
+    pub async fn get_start_page_token(
+        &self,
+        access_token: &str,
+        drive_id: &str,
+    ) -> Result<String, GoogleDriveApiError> {
+        let response = self
+            .http
+            .get(format!("{}/changes/startPageToken", self.api_base))
+            .header(AUTHORIZATION, bearer_token(access_token))
+            .query(&[("supportsAllDrives", "true")])
+            .query(&drive_id_param(drive_id))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveApiError::ApiStatus { status, body });
+        }
+
+        let payload: StartPageTokenResponse = response.json().await?;
+        Ok(payload.start_page_token)
+    }
+
+    /// One page of every non-trashed file in a Shared Drive, or in the
+    /// user's corpus when `drive_id` is empty.
+    pub async fn list_files_page(
+        &self,
+        access_token: &str,
+        drive_id: &str,
+        page_token: Option<&str>,
+    ) -> Result<DriveFilesPage, GoogleDriveApiError> {
+        let scope = if drive_id.is_empty() {
+            files_scope_params(&DriveLookup::MyDrive, drive_id)
+        } else {
+            files_scope_params(
+                &DriveLookup::DriveId(drive_id.to_string()),
+                drive_id,
+            )
+        };
+        let response = self
+            .http
+            .get(format!("{}/files", self.api_base))
+            .header(AUTHORIZATION, bearer_token(access_token))
+            .query(&scope)
+            .query(&[
+                ("pageSize", INDEX_PAGE_SIZE),
+                ("q", "trashed = false"),
+                (
+                    "fields",
+                    "nextPageToken,files(id,name,mimeType,parents,size,trashed)",
+                ),
+            ])
+            .query(&page_token.map(|token| [("pageToken", token)]))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveApiError::ApiStatus { status, body });
+        }
+
+        let payload: IndexFilesResponse = response.json().await?;
+        Ok(DriveFilesPage {
+            files: payload.files.into_iter().map(Into::into).collect(),
+            next_page_token: payload.next_page_token,
+        })
+    }
+
+    pub async fn list_changes_page(
+        &self,
+        access_token: &str,
+        drive_id: &str,
+        page_token: &str,
+    ) -> Result<DriveChangesPage, GoogleDriveApiError> {
+        let response = self
+            .http
+            .get(format!("{}/changes", self.api_base))
+            .header(AUTHORIZATION, bearer_token(access_token))
+            .query(&[
+                ("pageToken", page_token),
+                ("pageSize", INDEX_PAGE_SIZE),
+                ("includeRemoved", "true"),
+                ("includeItemsFromAllDrives", "true"),
+                ("supportsAllDrives", "true"),
+                (
+                    "fields",
+                    "nextPageToken,newStartPageToken,changes(fileId,removed,file(id,name,mimeType,parents,size,trashed))",
+                ),
+            ])
+            .query(&drive_id_param(drive_id))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveApiError::ApiStatus { status, body });
+        }
+
+        let payload: ChangesListResponse = response.json().await?;
+        Ok(DriveChangesPage {
+            changes: payload
+                .changes
+                .into_iter()
+                .map(|change| DriveChange {
+                    file_id: change.file_id,
+                    removed: change.removed,
+                    file: change.file.map(Into::into),
+                })
+                .collect(),
+            next_page_token: payload.next_page_token,
+            new_start_page_token: payload.new_start_page_token,
         })
     }
 
@@ -359,6 +746,32 @@ fn bearer_token(access_token: &str) -> String {
     format!("Bearer {}", access_token.trim())
 }
 
+fn drive_id_param(drive_id: &str) -> Option<[(&'static str, &str); 1]> {
+    (!drive_id.is_empty()).then_some([("driveId", drive_id)])
+}
+
+/// `files.list` search scope: one Shared Drive, the user's own My Drive, or
+/// every drive for folder roots, which may live in a Shared Drive too.
+fn files_scope_params(
+    lookup: &DriveLookup,
+    drive_id: &str,
+) -> Vec<(&'static str, String)> {
+    match lookup {
+        DriveLookup::DriveId(_) | DriveLookup::DriveName(_) => vec![
+            ("driveId", drive_id.to_string()),
+            ("corpora", "drive".to_string()),
+            ("includeItemsFromAllDrives", "true".to_string()),
+            ("supportsAllDrives", "true".to_string()),
+        ],
+        DriveLookup::MyDrive => vec![("corpora", "user".to_string())],
+        DriveLookup::Folder(_) => vec![
+            ("corpora", "allDrives".to_string()),
+            ("includeItemsFromAllDrives", "true".to_string()),
+            ("supportsAllDrives", "true".to_string()),
+        ],
+    }
+}
+
 fn build_files_query(segment: &str, parent_id: &str, is_last: bool) -> String {
     let escaped_segment = escape_query_literal(segment);
     let escaped_parent_id = escape_query_literal(parent_id);
@@ -475,6 +888,90 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn list_changes_page_parses_removals_and_moves() {
+        ensure_rustls_crypto_provider();
+
+        let seen = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        let base = spawn_mock_server(vec![Box::new(move |request| {
+            let seen_clone = seen_clone.clone();
+            Box::pin(async move {
+                seen_clone.lock().await.push(request.clone());
+                http_response(
+                    200,
+                    "application/json",
+                    r#"{"newStartPageToken":"42","changes":[{"fileId":"gone","removed":true},{"fileId":"ep1","removed":false,"file":{"id":"ep1","name":"E01.mkv","mimeType":"video/x-matroska","parents":["season"],"size":"1024"}}]}"#,
+                )
+            })
+        })])
+        .await;
+
+        let client = Client::with_endpoints(&base, "http://unused");
+        let page = client
+            .list_changes_page("token-1", "drive-1", "41")
+            .await
+            .expect("changes page");
+
+        assert_eq!(page.new_start_page_token.as_deref(), Some("42"));
+        assert_eq!(page.next_page_token, None);
+        assert!(page.changes[0].removed);
+        assert_eq!(page.changes[0].file, None);
+        assert_eq!(
+            page.changes[1].file,
+            Some(DriveFileEntry {
+                id: "ep1".to_string(),
+                name: "E01.mkv".to_string(),
+                parents: vec!["season".to_string()],
+                is_folder: false,
+                size: Some(1024),
+                trashed: false,
+            })
+        );
+
+        let requests = seen.lock().await;
+        let req = requests.first().expect("captured request");
+        assert!(req.starts_with("GET /changes?pageToken=41"));
+        assert!(req.contains("driveId=drive-1"));
+    }
+
+    #[tokio::test]
+    async fn exchange_jwt_assertion_posts_jwt_bearer_grant() {
+        ensure_rustls_crypto_provider();
+
+        let seen = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        let base = spawn_mock_server(vec![Box::new(move |request| {
+            let seen_clone = seen_clone.clone();
+            Box::pin(async move {
+                seen_clone.lock().await.push(request.clone());
+                http_response(
+                    200,
+                    "application/json",
+                    r#"{"access_token":"sa-token","token_type":"Bearer","expires_in":3599}"#,
+                )
+            })
+        })])
+        .await;
+
+        let client =
+            Client::with_endpoints("http://unused", &format!("{base}/token"));
+        let exchanged = client
+            .exchange_jwt_assertion("header.claims.signature")
+            .await
+            .expect("exchange assertion");
+
+        assert_eq!(exchanged.access_token, "sa-token");
+        assert_eq!(exchanged.expires_in, Some(3599));
+
+        let requests = seen.lock().await;
+        let req = requests.first().expect("captured request");
+        assert!(req.starts_with("POST /token HTTP/1.1"));
+        assert!(req.contains(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer&assertion=header.claims.signature"
+        ));
+    }
+
     #[tokio::test]
     async fn find_shared_drive_by_name_uses_exact_query() {
         ensure_rustls_crypto_provider();
@@ -585,6 +1082,65 @@ mod tests {
         assert_eq!(resolved.file_id, "file-789");
     }
 
+    #[tokio::test]
+    async fn resolve_file_id_by_path_walks_from_my_drive_and_folder_roots() {
+        ensure_rustls_crypto_provider();
+
+        let base = spawn_mock_server(vec![
+            Box::new(|request: String| -> Pin<Box<dyn Future<Output = String> + Send>> {
+                Box::pin(async move {
+                    assert!(request.contains("corpora=user"));
+                    assert!(!request.contains("driveId="));
+                    assert!(request.contains("%27root%27+in+parents"));
+                    http_response(
+                        200,
+                        "application/json",
+                        r#"{"files":[{"id":"file-1","size":"42"}]}"#,
+                    )
+                })
+            }) as Handler,
+            Box::new(|request: String| -> Pin<Box<dyn Future<Output = String> + Send>> {
+                Box::pin(async move {
+                    assert!(request.contains("corpora=allDrives"));
+                    assert!(request.contains("supportsAllDrives=true"));
+                    assert!(request.contains("%27folder-abc%27+in+parents"));
+                    http_response(
+                        200,
+                        "application/json",
+                        r#"{"files":[{"id":"file-2"}]}"#,
+                    )
+                })
+            }) as Handler,
+        ])
+        .await;
+
+        let client = Client::with_endpoints(
+            &format!("{base}/drive/v3"),
+            "http://unused",
+        );
+        let my_drive = client
+            .resolve_file_id_by_path(
+                "access-token",
+                &DriveLookup::MyDrive,
+                "/test.mkv",
+            )
+            .await
+            .expect("resolve in my drive");
+        let folder = client
+            .resolve_file_id_by_path(
+                "access-token",
+                &DriveLookup::Folder("folder-abc".into()),
+                "/test.mkv",
+            )
+            .await
+            .expect("resolve in folder");
+
+        assert_eq!(my_drive.file_id, "file-1");
+        assert_eq!(my_drive.size, Some(42));
+        assert!(my_drive.drive_id.is_empty());
+        assert_eq!(folder.file_id, "file-2");
+    }
+
     type Handler = Box<
         dyn Fn(String) -> Pin<Box<dyn Future<Output = String> + Send>>
             + Send
@@ -1,6 +1,7 @@
 mod client;
 
 pub use client::{
-    Client, GoogleDriveApiError, GoogleDriveResolvedFile,
-    GoogleTokenRefreshResponse, SharedDriveRef,
+    Client, DriveChange, DriveChangesPage, DriveFileEntry, DriveFilesPage,
+    GoogleAuthorizationCodeResponse, GoogleDriveApiError,
+    GoogleDriveResolvedFile, GoogleTokenRefreshResponse, SharedDriveRef,
 };
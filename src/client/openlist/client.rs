@@ -1,9 +1,26 @@
+use reqwest::{Response, StatusCode};
+
 use crate::{
-    api::openlist::{API, FileResponse, LinkData, LinkResponse},
+    api::openlist::{
+        API, FileData, FileResponse, LinkData, LinkResponse, LoginResponse,
+    },
     client::BuildableClient,
     network::{NetworkPlugin, NetworkProvider},
 };
 
+/// Carried inside the `anyhow::Error` when OpenList rejects the token, so
+/// callers can log in again and retry.
+#[derive(Debug, thiserror::Error)]
+#[error("openlist token rejected code={code} message={message}")]
+pub struct Unauthorized {
+    pub code: i64,
+    pub message: String,
+}
+
+pub fn is_unauthorized(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<Unauthorized>().is_some()
+}
+
 pub struct Client {
     provider: NetworkProvider,
 }
@@ -30,8 +47,56 @@ impl Client {
 
         let request = API::fs_get(url, token, emby_path, user_agent);
         let response = self.provider.send_request(&request).await?;
-        let json: FileResponse = response.json().await?;
-        Ok(json.get_data().get_raw_url().unwrap_or_default())
+        let json: FileResponse = Self::json_or_unauthorized(response).await?;
+        if json.is_unauthorized() {
+            return Err(Self::unauthorized(
+                json.get_code(),
+                json.get_message(),
+            ));
+        }
+        let data = json.get_data().ok_or_else(|| {
+            anyhow::anyhow!(
+                "openlist fs/get failed code={} message={}",
+                json.get_code(),
+                json.get_message()
+            )
+        })?;
+        Ok(data.get_raw_url().unwrap_or_default())
+    }
+
+    /// Looks up `emby_path` through `fs/get`; `Ok(None)` when OpenList
+    /// reports that the object does not exist.
+    pub async fn fetch_file_info(
+        &self,
+        url: impl Into<String>,
+        token: impl Into<String>,
+        emby_path: impl Into<String>,
+        user_agent: impl Into<String>,
+    ) -> Result<Option<FileData>, anyhow::Error> {
+        let emby_path = emby_path.into();
+        if emby_path.is_empty() {
+            return Ok(None);
+        }
+
+        let request = API::fs_get(url, token, emby_path, user_agent);
+        let response = self.provider.send_request(&request).await?;
+        let json: FileResponse = Self::json_or_unauthorized(response).await?;
+        if json.is_unauthorized() {
+            return Err(Self::unauthorized(
+                json.get_code(),
+                json.get_message(),
+            ));
+        }
+        if json.is_not_found() {
+            return Ok(None);
+        }
+        json.get_data().map(Some).ok_or_else(|| {
+            anyhow::anyhow!(
+                "openlist fs/get failed code={} message={}",
+                json.get_code(),
+                json.get_message()
+            )
+        })
     }
 
     pub async fn fetch_file_link(
@@ -48,7 +113,49 @@ impl Client {
 
         let request = API::fs_link(url, token, emby_path, user_agent);
         let response = self.provider.send_request(&request).await?;
-        let json: LinkResponse = response.json().await?;
+        let json: LinkResponse = Self::json_or_unauthorized(response).await?;
+        if json.is_unauthorized() {
+            return Err(Self::unauthorized(
+                json.get_code(),
+                json.get_message(),
+            ));
+        }
         Ok(json.get_data())
     }
+
+    /// Logs in with `api/auth/login` and returns the session token.
+    pub async fn login(
+        &self,
+        url: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        otp_code: impl Into<String>,
+        user_agent: impl Into<String>,
+    ) -> Result<String, anyhow::Error> {
+        let request =
+            API::auth_login(url, username, password, otp_code, user_agent);
+        let response = self.provider.send_request(&request).await?;
+        let json: LoginResponse = response.json().await?;
+        json.get_token().ok_or_else(|| {
+            anyhow::anyhow!(
+                "openlist login failed code={} message={}",
+                json.get_code(),
+                json.get_message()
+            )
+        })
+    }
+
+    async fn json_or_unauthorized<T: serde::de::DeserializeOwned>(
+        response: Response,
+    ) -> Result<T, anyhow::Error> {
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let message = response.text().await.unwrap_or_default();
+            return Err(Self::unauthorized(401, message));
+        }
+        Ok(response.json().await?)
+    }
+
+    fn unauthorized(code: i64, message: String) -> anyhow::Error {
+        Unauthorized { code, message }.into()
+    }
 }
@@ -1,6 +1,36 @@
 use crate::oauthutil::OAuthToken;
 use serde::{Deserialize, Serialize};
 
+/// Starting point for resolving node paths to Drive file IDs.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GoogleDriveRoot {
+    /// Shared Drive picked by `drive_id` or `drive_name`.
+    #[default]
+    SharedDrive,
+    /// The authenticated account's personal My Drive.
+    MyDrive,
+    /// An arbitrary folder given by `root_folder_id`, for example one
+    /// shared with the account.
+    Folder,
+}
+
+impl GoogleDriveRoot {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SharedDrive => "shared_drive",
+            Self::MyDrive => "my_drive",
+            Self::Folder => "folder",
+        }
+    }
+
+    pub fn is_shared_drive(&self) -> bool {
+        *self == Self::SharedDrive
+    }
+}
+
 /// Sub-table `[BackendNode.GoogleDrive]`.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct GoogleDriveConfig {
@@ -19,6 +49,12 @@ pub struct GoogleDriveConfig {
     /// Shared drive name fallback when `drive_id` is absent.
     #[serde(default)]
     pub drive_name: String,
+    /// Where path lookups start; defaults to the shared drive.
+    #[serde(default)]
+    pub root: GoogleDriveRoot,
+    /// Folder ID that paths are resolved under when `root = "folder"`.
+    #[serde(default)]
+    pub root_folder_id: String,
     /// Persisted OAuth access token; may be refreshed at runtime.
     #[serde(default)]
     pub access_token: String,
@@ -28,6 +64,22 @@ pub struct GoogleDriveConfig {
     /// Preferred persisted OAuth token blob.
     #[serde(default)]
     pub token: Option<OAuthToken>,
+    /// Service-account JSON key files pooled after the OAuth account; the
+    /// node moves to the next one when a credential runs out of quota.
+    #[serde(default)]
+    pub service_account_files: Vec<String>,
+    /// Workspace user the service accounts impersonate through domain-wide
+    /// delegation; empty to act as the service accounts themselves.
+    #[serde(default)]
+    pub service_account_subject: String,
+    /// Keep a persisted path index fed by the Drive Changes API so lookups
+    /// are answered locally instead of walking `files.list` per segment.
+    #[serde(default)]
+    pub folder_index: bool,
+    /// Secret holding the OAuth token blob as JSON. When set, refreshed
+    /// tokens go to the `[Secrets]` store instead of this file.
+    #[serde(default)]
+    pub token_secret: String,
 }
 
 impl GoogleDriveConfig {
@@ -58,6 +110,13 @@ impl GoogleDriveConfig {
             .filter(|token| !token.is_empty())
     }
 
+    pub fn service_account_files(&self) -> impl Iterator<Item = &str> {
+        self.service_account_files
+            .iter()
+            .map(|path| path.trim())
+            .filter(|path| !path.is_empty())
+    }
+
     pub fn apply_token(&mut self, token: OAuthToken) {
         self.access_token = token.access_token.clone();
         self.refresh_token = token.refresh_token.clone();
@@ -3,12 +3,16 @@ pub mod disk;
 #[path = "google_drive.rs"]
 pub mod google_drive;
 pub mod openlist;
+pub mod resilience;
+pub mod smb;
 pub mod types;
 pub mod webdav;
 
 pub use direct::DirectLink;
 pub use disk::Disk;
-pub use google_drive::GoogleDriveConfig;
+pub use google_drive::{GoogleDriveConfig, GoogleDriveRoot};
 pub use openlist::OpenList;
+pub use resilience::ResilienceConfig;
+pub use smb::SmbConfig;
 pub use types::{Backend, BackendConfig, BackendNode};
 pub use webdav::WebDavConfig;
@@ -1,7 +1,7 @@
 use hyper::Uri;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct OpenList {
     #[serde(default)]
     pub base_url: String,
@@ -9,9 +9,23 @@ pub struct OpenList {
     pub port: String,
     #[serde(default)]
     pub token: String,
+    /// With `username` / `password` set, the node logs in through
+    /// `api/auth/login` and renews its token when OpenList rejects it;
+    /// `token` is then ignored.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+    /// Base32 TOTP secret for accounts with two-factor login enabled.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub otp_secret: String,
 }
 
 impl OpenList {
+    pub fn credentials_configured(&self) -> bool {
+        !self.username.trim().is_empty() && !self.password.is_empty()
+    }
+
     pub fn uri(&self) -> Uri {
         let should_show_port = !(self.port == "443" || self.port == "80");
         let clean_url = self.base_url.trim_end_matches('/');
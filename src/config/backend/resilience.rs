@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+fn default_connect_timeout_secs() -> u64 {
+    5
+}
+
+fn default_first_byte_timeout_secs() -> u64 {
+    15
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    30
+}
+
+fn default_retry_attempts() -> u32 {
+    2
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    200
+}
+
+fn default_breaker_failure_threshold() -> u32 {
+    5
+}
+
+fn default_breaker_open_secs() -> u64 {
+    30
+}
+
+/// Sub-table `[BackendNode.Resilience]`: how long EmbyStream waits on the
+/// node's upstream, how often idempotent metadata calls are retried and
+/// when the node's circuit breaker opens. Every key has a default, so the
+/// table is optional.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ResilienceConfig {
+    /// TCP (and TLS) connect bound.
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Bound from sending a request to receiving response headers.
+    #[serde(default = "default_first_byte_timeout_secs")]
+    pub first_byte_timeout_secs: u64,
+    /// Longest gap between two body chunks of a proxied stream.
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// Extra attempts for idempotent metadata calls (OpenList link/info,
+    /// WebDAV `PROPFIND`). `0` disables retries.
+    #[serde(default = "default_retry_attempts")]
+    pub retry_attempts: u32,
+    /// Upper bound of the first retry delay; doubles per attempt and is
+    /// drawn with full jitter.
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+    /// Consecutive upstream failures that open the breaker. `0` disables it.
+    #[serde(default = "default_breaker_failure_threshold")]
+    pub breaker_failure_threshold: u32,
+    /// How long an open breaker short-circuits before one trial request.
+    #[serde(default = "default_breaker_open_secs")]
+    pub breaker_open_secs: u64,
+}
+
+impl Default for ResilienceConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_connect_timeout_secs(),
+            first_byte_timeout_secs: default_first_byte_timeout_secs(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            retry_attempts: default_retry_attempts(),
+            retry_base_delay_ms: default_retry_base_delay_ms(),
+            breaker_failure_threshold: default_breaker_failure_threshold(),
+            breaker_open_secs: default_breaker_open_secs(),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+fn default_timeout_secs() -> u64 {
+    15
+}
+
+fn default_reconnect_attempts() -> u32 {
+    2
+}
+
+/// Sub-table `[BackendNode.Smb]`. The server comes from the node's
+/// `base_url` (`smb://host` or a bare host) and `port` (default `445`).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SmbConfig {
+    /// Share name, e.g. `media` for `\\nas\media`.
+    pub share: String,
+    /// Empty for guest access.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// NTLM domain or workgroup; may stay empty for most NAS boxes.
+    #[serde(default)]
+    pub domain: String,
+    /// Bound on connecting and on every single SMB request, so a sleeping
+    /// NAS cannot stall a stream forever.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Fresh connections tried after a request fails or times out.
+    #[serde(default = "default_reconnect_attempts")]
+    pub reconnect_attempts: u32,
+}
+
+impl Default for SmbConfig {
+    fn default() -> Self {
+        Self {
+            share: String::new(),
+            username: String::new(),
+            password: String::new(),
+            domain: String::new(),
+            timeout_secs: default_timeout_secs(),
+            reconnect_attempts: default_reconnect_attempts(),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use super::{
     direct::types::DirectLink, disk::types::Disk,
     google_drive::GoogleDriveConfig, openlist::types::OpenList,
-    webdav::WebDavConfig,
+    resilience::ResilienceConfig, smb::SmbConfig, webdav::WebDavConfig,
 };
 use crate::{
     config::types::{AntiReverseProxyConfig, PathRewriteConfig},
@@ -74,6 +74,10 @@ pub struct BackendNode {
     pub google_drive: Option<GoogleDriveConfig>,
     #[serde(rename = "webdav", alias = "WebDav")]
     pub webdav: Option<WebDavConfig>,
+    #[serde(rename = "smb", alias = "Smb")]
+    pub smb: Option<SmbConfig>,
+    #[serde(rename = "resilience", alias = "Resilience")]
+    pub resilience: Option<ResilienceConfig>,
 }
 
 macro_rules! impl_uri {
@@ -120,5 +124,5 @@ pub enum BackendConfig {
     Disk(Disk),
     OpenList(OpenList),
     DirectLink(DirectLink),
-    GoogleDrive(GoogleDriveConfig),
+    GoogleDrive(Box<GoogleDriveConfig>),
 }
@@ -12,13 +12,18 @@ use tempfile::NamedTempFile;
 use uuid::Uuid;
 
 use super::{
-    backend::{Backend, BackendNode},
+    backend::{Backend, BackendNode, GoogleDriveRoot},
     error::ConfigError,
     frontend::Frontend,
     general::{General, StreamMode, UserAgent},
     http2::Http2,
+    secrets::{
+        SecretStore, SecretsProvider, config_dir_of, resolve_secret_references,
+        secrets_config,
+    },
     types::{FallbackConfig, PathRewriteConfig, RawConfig},
 };
+use crate::core::backend::smb::{BACKEND_TYPE as SMB_BACKEND_TYPE, SmbTarget};
 use crate::core::backend::webdav::{
     BACKEND_TYPE as WEBDAV_BACKEND_TYPE, PROXY_MODE_ACCEL_REDIRECT,
 };
@@ -31,7 +36,7 @@ use crate::{
         STREAM_RELAY_BACKEND_TYPE, backend_base_url_is_empty,
         backend_base_url_is_local_host,
     },
-    oauthutil::OAuthToken,
+    oauthutil::{OAuthToken, ServiceAccountKey},
     util::path_rewriter::PathRewriter,
 };
 
@@ -40,6 +45,8 @@ const GOOGLE_DRIVE_BACKEND_TYPE: &str = "googleDrive";
 const CONFIG_DIR_NAME: &str = "embystream";
 const CONFIG_FILE_NAME: &str = "config.toml";
 const SSL_DIR_NAME: &str = "ssl";
+const ACME_DIR_NAME: &str = "acme";
+const GOOGLE_DRIVE_INDEX_DIR_NAME: &str = "google-drive-index";
 const SSL_CER_FILE_NAME: &str = "ssl-cert";
 const SSL_KEY_FILE_NAME: &str = "ssl-key";
 const DOCKER_CONFIG_PATH: &str = "/config/embystream/config.toml";
@@ -66,10 +73,7 @@ impl Config {
     pub fn load_or_init(
         args: &RunArgs,
     ) -> Result<LoadConfigOutcome, ConfigError> {
-        let config_path = match &args.config {
-            Some(path) => path.clone(),
-            None => Self::get_default_config_path()?.join(CONFIG_FILE_NAME),
-        };
+        let config_path = Self::resolve_config_path(args.config.as_deref())?;
 
         if !config_path.exists() {
             Self::handle_missing_config(&config_path)?;
@@ -95,15 +99,57 @@ impl Config {
         Ok(LoadConfigOutcome::Loaded(Box::new(config)))
     }
 
+    /// `--config` when given, otherwise the platform default location.
+    pub fn resolve_config_path(
+        config: Option<&Path>,
+    ) -> Result<PathBuf, ConfigError> {
+        match config {
+            Some(path) => Ok(path.to_path_buf()),
+            None => Ok(Self::get_default_config_path()?.join(CONFIG_FILE_NAME)),
+        }
+    }
+
     pub fn get_ssl_cert_path(&self) -> Option<PathBuf> {
-        let config_dir = self.path.parent()?;
-        let path_str = &self.http2.ssl_cert_file;
+        self.resolve_ssl_path(&self.http2.ssl_cert_file, SSL_CER_FILE_NAME)
+    }
 
-        if path_str.is_empty() {
-            return Some(config_dir.join(SSL_DIR_NAME).join(SSL_CER_FILE_NAME));
+    pub fn get_ssl_key_path(&self) -> Option<PathBuf> {
+        self.resolve_ssl_path(&self.http2.ssl_key_file, SSL_KEY_FILE_NAME)
+    }
+
+    /// Frontend certificate; `None` unless `[Http2.Frontend]` is enabled.
+    /// Empty paths fall back to the backend certificate.
+    pub fn get_frontend_ssl_cert_path(&self) -> Option<PathBuf> {
+        let frontend = self.http2.frontend.as_ref().filter(|f| f.enable)?;
+        if frontend.ssl_cert_file.trim().is_empty() {
+            return self.get_ssl_cert_path();
+        }
+        self.resolve_ssl_path(&frontend.ssl_cert_file, SSL_CER_FILE_NAME)
+    }
+
+    /// Frontend private key; see [`Config::get_frontend_ssl_cert_path`].
+    pub fn get_frontend_ssl_key_path(&self) -> Option<PathBuf> {
+        let frontend = self.http2.frontend.as_ref().filter(|f| f.enable)?;
+        if frontend.ssl_key_file.trim().is_empty() {
+            return self.get_ssl_key_path();
         }
+        self.resolve_ssl_path(&frontend.ssl_key_file, SSL_KEY_FILE_NAME)
+    }
 
-        let path = PathBuf::from(path_str);
+    /// Persisted googleDrive folder indexes, one JSON file per node.
+    pub fn get_google_drive_index_dir(&self) -> Option<PathBuf> {
+        Some(self.path.parent()?.join(GOOGLE_DRIVE_INDEX_DIR_NAME))
+    }
+
+    /// ACME account and order state; `None` unless `[Http2.Acme]` is enabled.
+    pub fn get_acme_state_dir(&self) -> Option<PathBuf> {
+        let acme = self.http2.acme.as_ref().filter(|acme| acme.enable)?;
+        let config_dir = self.path.parent()?;
+        let state_dir = acme.state_dir.trim();
+        if state_dir.is_empty() {
+            return Some(config_dir.join(ACME_DIR_NAME));
+        }
+        let path = PathBuf::from(state_dir);
         if path.is_absolute() {
             Some(path)
         } else {
@@ -111,12 +157,17 @@ impl Config {
         }
     }
 
-    pub fn get_ssl_key_path(&self) -> Option<PathBuf> {
+    /// Empty values resolve to `ssl/<default_name>` next to the config file,
+    /// relative values to the config directory.
+    pub fn resolve_ssl_path(
+        &self,
+        path_str: &str,
+        default_name: &str,
+    ) -> Option<PathBuf> {
         let config_dir = self.path.parent()?;
-        let path_str = &self.http2.ssl_key_file;
 
         if path_str.is_empty() {
-            return Some(config_dir.join(SSL_DIR_NAME).join(SSL_KEY_FILE_NAME));
+            return Some(config_dir.join(SSL_DIR_NAME).join(default_name));
         }
 
         let path = PathBuf::from(path_str);
@@ -127,9 +178,21 @@ impl Config {
         }
     }
 
-    fn load_from_path(path: &Path) -> Result<Self, ConfigError> {
-        let content = fs::read_to_string(path)?;
-        let raw_config: RawConfig = toml::from_str(&content)?;
+    pub fn load_from_path(path: &Path) -> Result<Self, ConfigError> {
+        Self::load_from_content(path, &fs::read_to_string(path)?)
+    }
+
+    /// Builds a runtime config from `content` as if it were stored at
+    /// `path`, so secret references resolve against that directory.
+    pub fn load_from_content(
+        path: &Path,
+        content: &str,
+    ) -> Result<Self, ConfigError> {
+        let mut doc: toml::Value = toml::from_str(content)?;
+        let config_dir = config_dir_of(path);
+        resolve_secret_references(&mut doc, config_dir)?;
+        load_google_drive_token_secrets(&mut doc, config_dir)?;
+        let raw_config: RawConfig = doc.try_into()?;
         finish_raw_config(path.to_path_buf(), raw_config)
     }
 
@@ -318,8 +381,101 @@ fn validate_webdav_accel_redirect_nodes(
     Ok(())
 }
 
+fn validate_fallback_config(
+    fallback: &FallbackConfig,
+) -> Result<(), ConfigError> {
+    for (key, value) in fallback.entries() {
+        let value = value.trim();
+        let lower = value.to_ascii_lowercase();
+        if !lower.starts_with("http://") && !lower.starts_with("https://") {
+            continue;
+        }
+
+        let valid = value
+            .parse::<hyper::Uri>()
+            .map(|uri| uri.host().is_some_and(|host| !host.is_empty()))
+            .unwrap_or(false);
+        if !valid {
+            return Err(ConfigError::InvalidValue(format!(
+                "Fallback.{key} '{value}' is not a valid redirect URL"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_http2_config(http2: &Http2) -> Result<(), ConfigError> {
+    let frontend_sni = http2.frontend.iter().flat_map(|frontend| {
+        frontend.sni.iter().map(|sni| ("Frontend.Sni", sni))
+    });
+    for (table, sni) in
+        http2.sni.iter().map(|sni| ("Sni", sni)).chain(frontend_sni)
+    {
+        if sni.server_names.iter().all(|name| name.trim().is_empty()) {
+            return Err(ConfigError::InvalidValue(format!(
+                "Http2.{table} entry requires at least one server_names value"
+            )));
+        }
+        if sni.ssl_cert_file.trim().is_empty()
+            || sni.ssl_key_file.trim().is_empty()
+        {
+            return Err(ConfigError::MissingConfig(format!(
+                "Http2.{table} '{}' requires ssl_cert_file and ssl_key_file",
+                sni.server_names.join(",")
+            )));
+        }
+    }
+
+    if let Some(acme) = http2.acme.as_ref().filter(|acme| acme.enable) {
+        if acme.domains.iter().all(|domain| domain.trim().is_empty()) {
+            return Err(ConfigError::MissingConfig(
+                "Http2.Acme requires at least one domains value".to_string(),
+            ));
+        }
+        if let Some(domain) =
+            acme.domains.iter().find(|domain| domain.contains('*'))
+        {
+            return Err(ConfigError::InvalidValue(format!(
+                "Http2.Acme domain '{domain}' is a wildcard; http-01 and tls-alpn-01 cannot validate wildcards"
+            )));
+        }
+        if !acme.directory_url.starts_with("https://")
+            && !acme.directory_url.starts_with("http://")
+        {
+            return Err(ConfigError::InvalidValue(format!(
+                "Http2.Acme directory_url must be an http(s) URL, got '{}'",
+                acme.directory_url
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_smb_nodes(
+    backend_nodes: &[BackendNode],
+) -> Result<(), ConfigError> {
+    for node in backend_nodes {
+        if !node.backend_type.eq_ignore_ascii_case(SMB_BACKEND_TYPE) {
+            continue;
+        }
+        if node.smb.is_none() {
+            return Err(ConfigError::MissingConfig(format!(
+                "BackendNode.Smb for node '{}'",
+                node.name
+            )));
+        }
+        SmbTarget::from_node(node)
+            .map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
 fn validate_google_drive_nodes(
     backend_nodes: &[BackendNode],
+    secrets_provider: SecretsProvider,
 ) -> Result<(), ConfigError> {
     let node_uuid_pattern =
         Regex::new(r"^[A-Za-z0-9_-]+$").map_err(ConfigError::InvalidRegex)?;
@@ -348,14 +504,20 @@ fn validate_google_drive_nodes(
             )));
         }
 
-        if cfg.client_id.trim().is_empty() {
+        // A node backed only by service accounts needs no OAuth client.
+        let token_secret = cfg.token_secret.trim();
+        let requires_oauth = cfg.service_account_files().next().is_none()
+            || cfg.effective_refresh_token().is_some()
+            || !token_secret.is_empty();
+
+        if requires_oauth && cfg.client_id.trim().is_empty() {
             return Err(ConfigError::MissingConfig(format!(
                 "BackendNode.GoogleDrive.client_id for node '{}'",
                 node.name
             )));
         }
 
-        if cfg.client_secret.trim().is_empty() {
+        if requires_oauth && cfg.client_secret.trim().is_empty() {
             return Err(ConfigError::MissingConfig(format!(
                 "BackendNode.GoogleDrive.client_secret for node '{}'",
                 node.name
@@ -376,7 +538,56 @@ fn validate_google_drive_nodes(
             )));
         }
 
-        if cfg.effective_refresh_token().is_none() {
+        if cfg.root == GoogleDriveRoot::Folder
+            && cfg.root_folder_id.trim().is_empty()
+        {
+            return Err(ConfigError::MissingConfig(format!(
+                "BackendNode.GoogleDrive.root_folder_id for node '{}'",
+                node.name
+            )));
+        }
+
+        // A service account's own My Drive is not the OAuth account's, so
+        // rotating onto one would resolve paths in a different drive.
+        if cfg.root == GoogleDriveRoot::MyDrive
+            && cfg.service_account_files().next().is_some()
+            && cfg.service_account_subject.trim().is_empty()
+        {
+            return Err(ConfigError::InvalidValue(format!(
+                "BackendNode.GoogleDrive on node '{}' cannot use root = \
+                 \"my_drive\" with service_account_files unless \
+                 service_account_subject names the drive's owner; use a \
+                 shared drive or root = \"folder\" instead",
+                node.name
+            )));
+        }
+
+        for key_path in cfg.service_account_files() {
+            ServiceAccountKey::from_file(Path::new(key_path)).map_err(
+                |error| {
+                    ConfigError::InvalidValue(format!(
+                        "BackendNode.GoogleDrive.service_account_files on node '{}': {}",
+                        node.name, error
+                    ))
+                },
+            )?;
+        }
+
+        if !token_secret.is_empty() && secrets_provider == SecretsProvider::Env
+        {
+            return Err(ConfigError::InvalidValue(format!(
+                "BackendNode.GoogleDrive.token_secret on node '{}' needs a \
+                 writable [Secrets] provider; env is read-only",
+                node.name
+            )));
+        }
+
+        // With `token_secret` the token may live only in the store, which
+        // drafts and other unresolved configs cannot see.
+        if requires_oauth
+            && token_secret.is_empty()
+            && cfg.effective_refresh_token().is_none()
+        {
             return Err(ConfigError::MissingConfig(format!(
                 "BackendNode.GoogleDrive.refresh_token for node '{}'",
                 node.name
@@ -403,10 +614,18 @@ pub fn finish_raw_config(
 ) -> Result<Config, ConfigError> {
     validate_raw_structure(&raw_config)?;
     validate_raw_regexes(&raw_config)?;
+    validate_fallback_config(&raw_config.fallback)?;
+    if let Some(http2) = &raw_config.http2 {
+        validate_http2_config(http2)?;
+    }
 
     let mut backend_nodes = raw_config.backend_nodes.unwrap_or_default();
     validate_webdav_accel_redirect_nodes(&backend_nodes)?;
-    validate_google_drive_nodes(&backend_nodes)?;
+    validate_smb_nodes(&backend_nodes)?;
+    validate_google_drive_nodes(
+        &backend_nodes,
+        raw_config.secrets.clone().unwrap_or_default().provider,
+    )?;
     for node in &mut backend_nodes {
         node.uuid = Uuid::new_v4().to_string();
 
@@ -456,7 +675,10 @@ pub fn finish_raw_config(
     })
 }
 
-fn write_atomic_config(dest: &Path, contents: &str) -> Result<(), ConfigError> {
+pub(crate) fn write_atomic_config(
+    dest: &Path,
+    contents: &str,
+) -> Result<(), ConfigError> {
     let dest_dir = dest
         .parent()
         .filter(|p| !p.as_os_str().is_empty())
@@ -476,7 +698,10 @@ pub fn read_google_drive_token(
     node_uuid: &str,
 ) -> Result<Option<OAuthToken>, ConfigError> {
     let content = fs::read_to_string(config_path)?;
-    let doc: toml::Value = toml::from_str(&content)?;
+    let mut doc: toml::Value = toml::from_str(&content)?;
+    let config_dir = config_dir_of(config_path);
+    resolve_secret_references(&mut doc, config_dir)?;
+    load_google_drive_token_secrets(&mut doc, config_dir)?;
     let backend_nodes = doc
         .get("BackendNode")
         .and_then(toml::Value::as_array)
@@ -508,11 +733,95 @@ pub fn persist_google_drive_token(
     token: &OAuthToken,
 ) -> Result<(), ConfigError> {
     let content = fs::read_to_string(config_path)?;
+    let doc: toml::Value = toml::from_str(&content)?;
+    if let Some(name) = google_drive_token_secret(&doc, node_uuid) {
+        let value = serde_json::to_string(token).map_err(|error| {
+            ConfigError::Io(IoError::other(format!(
+                "serialize googleDrive token blob: {error}"
+            )))
+        })?;
+        return SecretStore::open(
+            &secrets_config(&doc)?,
+            config_dir_of(config_path),
+        )?
+        .set(&name, &value);
+    }
+
     let updated =
         rewrite_google_drive_token_in_raw_config(&content, node_uuid, token)?;
     write_atomic_config(config_path, &updated)
 }
 
+fn google_drive_tables_mut(
+    doc: &mut toml::Value,
+) -> impl Iterator<Item = &mut toml::map::Map<String, toml::Value>> {
+    doc.get_mut("BackendNode")
+        .and_then(toml::Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(|node| {
+            node.get_mut("GoogleDrive")
+                .and_then(toml::Value::as_table_mut)
+        })
+}
+
+fn table_token_secret(
+    google_drive: &toml::map::Map<String, toml::Value>,
+) -> Option<String> {
+    google_drive
+        .get("token_secret")
+        .and_then(toml::Value::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+fn google_drive_token_secret(
+    doc: &toml::Value,
+    node_uuid: &str,
+) -> Option<String> {
+    doc.get("BackendNode")?
+        .as_array()?
+        .iter()
+        .filter_map(|node| node.get("GoogleDrive")?.as_table())
+        .find(|google_drive| {
+            google_drive.get("node_uuid").and_then(toml::Value::as_str)
+                == Some(node_uuid)
+        })
+        .and_then(table_token_secret)
+}
+
+/// Replace the inline `token` of googleDrive nodes that set `token_secret`
+/// with the stored blob. Inline values stay as the seed until the first
+/// refresh writes the secret.
+fn load_google_drive_token_secrets(
+    doc: &mut toml::Value,
+    config_dir: &Path,
+) -> Result<(), ConfigError> {
+    let secrets = secrets_config(doc)?;
+    let mut store = None;
+    for table in google_drive_tables_mut(doc) {
+        let Some(name) = table_token_secret(table) else {
+            continue;
+        };
+        let store = match store.as_mut() {
+            Some(store) => store,
+            None => store.insert(SecretStore::open(&secrets, config_dir)?),
+        };
+        let Some(value) = store.get(&name)? else {
+            continue;
+        };
+        let token: OAuthToken =
+            serde_json::from_str(&value).map_err(|error| {
+                ConfigError::InvalidValue(format!(
+                    "secret '{name}' is not a googleDrive token blob: {error}"
+                ))
+            })?;
+        table.insert("token".to_string(), oauth_token_to_toml_value(&token)?);
+    }
+    Ok(())
+}
+
 fn parse_google_drive_token_table(
     google_drive: &toml::map::Map<String, toml::Value>,
 ) -> Result<OAuthToken, ConfigError> {
@@ -734,8 +1043,15 @@ mod tests {
 
     use chrono::{TimeZone, Utc};
 
-    use super::{persist_google_drive_token, read_google_drive_token};
+    use serde::Deserialize;
+
+    use super::{
+        BackendNode, SecretsProvider, persist_google_drive_token,
+        read_google_drive_token, validate_google_drive_nodes,
+        validate_http2_config, validate_smb_nodes,
+    };
     use crate::config::error::ConfigError;
+    use crate::config::http2::Http2;
     use crate::oauthutil::OAuthToken;
 
     #[test]
@@ -808,6 +1124,55 @@ access_token = "old-token-2"
         assert_eq!(second_blob.expiry, Some(expiry));
     }
 
+    #[test]
+    fn google_drive_token_secret_keeps_refreshes_out_of_config() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let config_path = temp_dir.path().join("config.toml");
+        let content = r#"
+[Secrets]
+provider = "directory"
+directory = "secrets"
+
+[[BackendNode]]
+name = "GD-1"
+type = "googleDrive"
+
+[BackendNode.GoogleDrive]
+node_uuid = "node-1"
+token_secret = "gd-token"
+refresh_token = "seed-refresh"
+"#;
+        fs::write(&config_path, content).expect("write config");
+
+        let seed = read_google_drive_token(&config_path, "node-1")
+            .expect("read seed")
+            .expect("seed token");
+        assert_eq!(seed.refresh_token, "seed-refresh");
+
+        let token = OAuthToken {
+            access_token: "new-access".to_string(),
+            refresh_token: "new-refresh".to_string(),
+            token_type: "Bearer".to_string(),
+            expiry: None,
+        };
+        persist_google_drive_token(&config_path, "node-1", &token)
+            .expect("persist");
+
+        assert_eq!(
+            fs::read_to_string(&config_path).expect("read config"),
+            content
+        );
+        let stored =
+            fs::read_to_string(temp_dir.path().join("secrets/gd-token"))
+                .expect("secret file");
+        assert!(stored.contains("new-refresh"));
+        assert_eq!(
+            read_google_drive_token(&config_path, "node-1")
+                .expect("read secret"),
+            Some(token)
+        );
+    }
+
     #[test]
     fn persist_google_drive_token_errors_when_node_is_missing() {
         let temp_dir = tempfile::tempdir().expect("temp dir");
@@ -1005,4 +1370,150 @@ replacement = "/pilipili/pilipili/$1"
 
         assert_eq!(persisted, expected);
     }
+
+    #[test]
+    fn validate_http2_config_requires_complete_sni_entries() {
+        let parse = |content: &str| -> Http2 {
+            toml::from_str(content).expect("parse http2")
+        };
+
+        let valid = parse(
+            r#"
+[[Sni]]
+server_names = ["*.example.com"]
+ssl_cert_file = "ssl/example.crt"
+ssl_key_file = "ssl/example.key"
+
+[Frontend]
+enable = true
+"#,
+        );
+        assert!(validate_http2_config(&valid).is_ok());
+
+        let unnamed = parse(
+            r#"
+[[Frontend.Sni]]
+ssl_cert_file = "ssl/example.crt"
+ssl_key_file = "ssl/example.key"
+"#,
+        );
+        assert!(matches!(
+            validate_http2_config(&unnamed),
+            Err(ConfigError::InvalidValue(_))
+        ));
+
+        let keyless = parse(
+            r#"
+[[Sni]]
+server_names = ["stream.example.com"]
+ssl_cert_file = "ssl/example.crt"
+"#,
+        );
+        assert!(matches!(
+            validate_http2_config(&keyless),
+            Err(ConfigError::MissingConfig(_))
+        ));
+    }
+
+    #[test]
+    fn validate_google_drive_nodes_rejects_service_accounts_on_my_drive() {
+        let validate = |drive: &str| {
+            #[derive(Deserialize)]
+            struct Nodes {
+                #[serde(rename = "BackendNode")]
+                nodes: Vec<BackendNode>,
+            }
+            let content = format!(
+                r#"
+[[BackendNode]]
+name = "Drive"
+type = "googleDrive"
+pattern = "/gdrive/.*"
+base_url = ""
+port = ""
+
+[BackendNode.GoogleDrive]
+node_uuid = "drive"
+service_account_files = ["/missing/sa.json"]
+{drive}
+"#
+            );
+            let nodes = toml::from_str::<Nodes>(&content)
+                .expect("parse nodes")
+                .nodes;
+            validate_google_drive_nodes(&nodes, SecretsProvider::EncryptedFile)
+                .expect_err("invalid node")
+                .to_string()
+        };
+
+        assert!(validate(r#"root = "my_drive""#).contains("my_drive"));
+        // Past the root check, the missing key file is what fails.
+        for drive in [
+            "root = \"my_drive\"\nservice_account_subject = \"me@example.com\"",
+            "root = \"folder\"\nroot_folder_id = \"folder-id\"",
+            "drive_name = \"Media\"",
+        ] {
+            assert!(validate(drive).contains("service_account_files"));
+        }
+    }
+
+    #[test]
+    fn validate_smb_nodes_requires_share_and_host() {
+        let parse = |content: &str| -> Vec<BackendNode> {
+            #[derive(Deserialize)]
+            struct Nodes {
+                #[serde(rename = "BackendNode")]
+                nodes: Vec<BackendNode>,
+            }
+            toml::from_str::<Nodes>(content).expect("parse nodes").nodes
+        };
+
+        let valid = parse(
+            r#"
+[[BackendNode]]
+name = "NAS"
+type = "Smb"
+pattern = "/nas/.*"
+base_url = "smb://nas.local"
+port = ""
+
+[BackendNode.Smb]
+share = "media"
+"#,
+        );
+        assert!(validate_smb_nodes(&valid).is_ok());
+
+        let tableless = parse(
+            r#"
+[[BackendNode]]
+name = "NAS"
+type = "Smb"
+pattern = "/nas/.*"
+base_url = "smb://nas.local"
+port = ""
+"#,
+        );
+        assert!(matches!(
+            validate_smb_nodes(&tableless),
+            Err(ConfigError::MissingConfig(_))
+        ));
+
+        let hostless = parse(
+            r#"
+[[BackendNode]]
+name = "NAS"
+type = "Smb"
+pattern = "/nas/.*"
+base_url = ""
+port = ""
+
+[BackendNode.Smb]
+share = "media"
+"#,
+        );
+        assert!(matches!(
+            validate_smb_nodes(&hostless),
+            Err(ConfigError::InvalidValue(_))
+        ));
+    }
 }
@@ -6,4 +6,115 @@ pub struct Http2 {
     pub ssl_cert_file: String,
     #[serde(default)]
     pub ssl_key_file: String,
+    /// Extra backend certificates selected by the TLS SNI host name.
+    #[serde(default, rename = "Sni", skip_serializing_if = "Vec::is_empty")]
+    pub sni: Vec<SniCertificate>,
+    /// TLS for the frontend listener; plain HTTP when absent or disabled.
+    #[serde(
+        default,
+        rename = "Frontend",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub frontend: Option<FrontendTls>,
+    /// Automatic certificate issuance into `ssl_cert_file` / `ssl_key_file`.
+    #[serde(default, rename = "Acme", skip_serializing_if = "Option::is_none")]
+    pub acme: Option<AcmeConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct FrontendTls {
+    #[serde(default)]
+    pub enable: bool,
+    /// Empty values reuse the backend `ssl_cert_file` / `ssl_key_file`.
+    #[serde(default)]
+    pub ssl_cert_file: String,
+    #[serde(default)]
+    pub ssl_key_file: String,
+    #[serde(default, rename = "Sni", skip_serializing_if = "Vec::is_empty")]
+    pub sni: Vec<SniCertificate>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AcmeConfig {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default = "default_acme_directory_url")]
+    pub directory_url: String,
+    #[serde(default)]
+    pub contact_email: String,
+    /// DNS names on the issued certificate; the first is the subject.
+    #[serde(default)]
+    pub domains: Vec<String>,
+    #[serde(default)]
+    pub challenge: AcmeChallengeType,
+    #[serde(default = "default_acme_renew_before_days")]
+    pub renew_before_days: u32,
+    /// Account key and order state; empty resolves to `acme/` next to the
+    /// config file.
+    #[serde(default)]
+    pub state_dir: String,
+}
+
+impl Default for AcmeConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            directory_url: default_acme_directory_url(),
+            contact_email: String::new(),
+            domains: Vec::new(),
+            challenge: AcmeChallengeType::default(),
+            renew_before_days: default_acme_renew_before_days(),
+            state_dir: String::new(),
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize,
+)]
+pub enum AcmeChallengeType {
+    /// Token served at `/.well-known/acme-challenge/` on plain HTTP.
+    #[default]
+    #[serde(rename = "http-01")]
+    Http01,
+    /// Self-signed validation certificate offered for ALPN `acme-tls/1`.
+    #[serde(rename = "tls-alpn-01")]
+    TlsAlpn01,
+}
+
+impl AcmeChallengeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Http01 => "http-01",
+            Self::TlsAlpn01 => "tls-alpn-01",
+        }
+    }
+}
+
+fn default_acme_directory_url() -> String {
+    "https://acme-v02.api.letsencrypt.org/directory".to_string()
+}
+
+fn default_acme_renew_before_days() -> u32 {
+    30
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct SniCertificate {
+    /// Host names served by this certificate; `*.example.com` matches one
+    /// extra label.
+    #[serde(default)]
+    pub server_names: Vec<String>,
+    #[serde(default)]
+    pub ssl_cert_file: String,
+    #[serde(default)]
+    pub ssl_key_file: String,
+}
+
+impl Http2 {
+    pub fn frontend_enabled(&self) -> bool {
+        self.frontend
+            .as_ref()
+            .is_some_and(|frontend| frontend.enable)
+    }
 }
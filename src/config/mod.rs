@@ -5,4 +5,5 @@ pub mod frontend;
 pub mod general;
 pub mod http2;
 pub mod macros;
+pub mod secrets;
 pub mod types;
@@ -8,6 +8,7 @@ use crate::config::{
     frontend::Frontend,
     general::{Emby, General, Log, UserAgent},
     http2::Http2,
+    secrets::SecretsConfig,
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
@@ -68,10 +69,40 @@ impl AntiReverseProxyConfig {
     }
 }
 
+/// Fallback media per failure reason. Each entry is either a local file
+/// path (relative paths resolve against the config directory) or an
+/// `http(s)://` URL the client is redirected to. Empty entries disable the
+/// fallback and the bare status code is returned.
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct FallbackConfig {
+    /// Served when the requested file does not exist.
     #[serde(default)]
     pub video_missing_path: String,
+    /// Served when the upstream backend is unreachable or returns 5xx.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub upstream_down_path: String,
+    /// Served when Google Drive reports a download quota or rate limit.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub quota_exceeded_path: String,
+    /// Served when the signed stream URL has expired.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub sign_expired_path: String,
+    /// Served when the upstream rejects the stream for too many
+    /// concurrent connections.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub concurrent_limit_path: String,
+}
+
+impl FallbackConfig {
+    pub fn entries(&self) -> [(&'static str, &str); 5] {
+        [
+            ("video_missing_path", self.video_missing_path.as_str()),
+            ("upstream_down_path", self.upstream_down_path.as_str()),
+            ("quota_exceeded_path", self.quota_exceeded_path.as_str()),
+            ("sign_expired_path", self.sign_expired_path.as_str()),
+            ("concurrent_limit_path", self.concurrent_limit_path.as_str()),
+        ]
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -100,4 +131,10 @@ pub struct RawConfig {
     pub direct_link: Option<DirectLink>,
     #[serde(rename = "Fallback", default)]
     pub fallback: FallbackConfig,
+    #[serde(
+        rename = "Secrets",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub secrets: Option<SecretsConfig>,
 }
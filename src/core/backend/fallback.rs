@@ -0,0 +1,394 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use hyper::{HeaderMap, StatusCode, Uri, header};
+
+use super::{
+    google_drive_auth, local_streamer::LocalStreamer,
+    result::Result as AppStreamResult,
+};
+use crate::{
+    AppState, STREAM_LOGGER_DOMAIN,
+    config::{backend::BackendNode, core::Config},
+    core::redirect_info::RedirectInfo,
+    info_log, warn_log,
+};
+
+/// Google Drive error reasons that mean the file is temporarily not
+/// downloadable rather than missing or unauthorized.
+const GOOGLE_DRIVE_QUOTA_REASONS: &[&str] = &[
+    "downloadQuotaExceeded",
+    "quotaExceeded",
+    "userRateLimitExceeded",
+    "rateLimitExceeded",
+];
+
+/// Why a stream could not be served from its primary source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FallbackReason {
+    FileMissing,
+    UpstreamDown,
+    QuotaExceeded,
+    SignExpired,
+    ConcurrentLimit,
+}
+
+impl FallbackReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FallbackReason::FileMissing => "file_missing",
+            FallbackReason::UpstreamDown => "upstream_down",
+            FallbackReason::QuotaExceeded => "quota_exceeded",
+            FallbackReason::SignExpired => "sign_expired",
+            FallbackReason::ConcurrentLimit => "concurrent_limit",
+        }
+    }
+
+    fn configured_value(self, config: &Config) -> &str {
+        let fallback = &config.fallback;
+        match self {
+            FallbackReason::FileMissing => &fallback.video_missing_path,
+            FallbackReason::UpstreamDown => &fallback.upstream_down_path,
+            FallbackReason::QuotaExceeded => &fallback.quota_exceeded_path,
+            FallbackReason::SignExpired => &fallback.sign_expired_path,
+            FallbackReason::ConcurrentLimit => &fallback.concurrent_limit_path,
+        }
+    }
+
+    /// Classifies a non-success upstream response.
+    pub fn from_upstream_status(
+        node: &BackendNode,
+        status: StatusCode,
+        body: &[u8],
+    ) -> Self {
+        if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+            return FallbackReason::FileMissing;
+        }
+
+        let is_limit_status = status == StatusCode::FORBIDDEN
+            || status == StatusCode::TOO_MANY_REQUESTS;
+        if is_limit_status && google_drive_auth::is_google_drive_node(node) {
+            let body = String::from_utf8_lossy(body);
+            if GOOGLE_DRIVE_QUOTA_REASONS
+                .iter()
+                .any(|reason| body.contains(reason))
+            {
+                return FallbackReason::QuotaExceeded;
+            }
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return FallbackReason::ConcurrentLimit;
+        }
+
+        FallbackReason::UpstreamDown
+    }
+}
+
+impl fmt::Display for FallbackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A failed stream attempt: the reason drives fallback selection and the
+/// status is returned when no fallback is configured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamFailure {
+    pub reason: FallbackReason,
+    pub status: StatusCode,
+}
+
+impl StreamFailure {
+    pub fn new(reason: FallbackReason, status: StatusCode) -> Self {
+        Self { reason, status }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FallbackTarget {
+    LocalFile(PathBuf),
+    Redirect(Uri),
+}
+
+impl FallbackTarget {
+    pub fn resolve(config: &Config, reason: FallbackReason) -> Option<Self> {
+        let value = reason.configured_value(config).trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        let lower = value.to_ascii_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            return value.parse().ok().map(FallbackTarget::Redirect);
+        }
+
+        let path = PathBuf::from(value);
+        if path.is_absolute() {
+            Some(FallbackTarget::LocalFile(path))
+        } else {
+            Some(FallbackTarget::LocalFile(
+                config
+                    .path
+                    .parent()
+                    .unwrap_or_else(|| Path::new(""))
+                    .join(path),
+            ))
+        }
+    }
+}
+
+pub(crate) struct FallbackResponder;
+
+impl FallbackResponder {
+    /// Serves the fallback configured for `failure.reason`, or returns
+    /// `failure.status` when none is configured or it cannot be served.
+    pub async fn respond(
+        state: &Arc<AppState>,
+        failure: StreamFailure,
+        client_headers: &HeaderMap,
+    ) -> Result<AppStreamResult, StatusCode> {
+        let target = {
+            let config = state.get_config().await;
+            FallbackTarget::resolve(&config, failure.reason)
+        };
+        let Some(target) = target else {
+            return Err(failure.status);
+        };
+
+        match target {
+            FallbackTarget::Redirect(target_url) => {
+                info_log!(
+                    STREAM_LOGGER_DOMAIN,
+                    "fallback_redirect reason={} status={} target={}",
+                    failure.reason,
+                    failure.status.as_u16(),
+                    target_url
+                );
+                let mut final_headers = HeaderMap::new();
+                final_headers.insert(
+                    header::CACHE_CONTROL,
+                    header::HeaderValue::from_static("no-store"),
+                );
+                Ok(AppStreamResult::Redirect(RedirectInfo {
+                    target_url,
+                    final_headers,
+                }))
+            }
+            FallbackTarget::LocalFile(path) => {
+                info_log!(
+                    STREAM_LOGGER_DOMAIN,
+                    "fallback_local_file reason={} status={} path={:?}",
+                    failure.reason,
+                    failure.status.as_u16(),
+                    path
+                );
+                let range = client_headers
+                    .get(header::RANGE)
+                    .and_then(|value| value.to_str().ok());
+                LocalStreamer::stream_fallback(state.clone(), &path, range)
+                    .await
+                    .map_err(|status| {
+                        warn_log!(
+                            STREAM_LOGGER_DOMAIN,
+                            "fallback_local_file_failed reason={} path={:?} \
+                             status={}",
+                            failure.reason,
+                            path,
+                            status.as_u16()
+                        );
+                        failure.status
+                    })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::Arc};
+
+    use hyper::{HeaderMap, StatusCode, header};
+    use tempfile::TempDir;
+
+    use super::{
+        FallbackReason, FallbackResponder, FallbackTarget, StreamFailure,
+    };
+    use crate::{
+        AppState, config::backend::BackendNode,
+        core::backend::result::Result as AppStreamResult,
+        test_support::TestConfig,
+    };
+
+    fn config_with_fallback(
+        fallback_section: &str,
+    ) -> crate::config::core::Config {
+        TestConfig::new()
+            .path("/etc/embystream/config.toml")
+            .section(&format!("[Fallback]\n{fallback_section}"))
+            .build()
+    }
+
+    fn node(backend_type: &str) -> BackendNode {
+        let raw = format!(
+            r#"
+name = "node"
+type = "{backend_type}"
+pattern = ""
+base_url = "http://127.0.0.1"
+port = "8080"
+path = ""
+"#
+        );
+        toml::from_str(&raw).expect("backend node")
+    }
+
+    #[test]
+    fn resolve_distinguishes_urls_and_relative_paths() {
+        let config = config_with_fallback(
+            r#"video_missing_path = "fallback/missing.mp4"
+upstream_down_path = "https://cdn.example.com/down.mp4"
+"#,
+        );
+
+        assert_eq!(
+            FallbackTarget::resolve(&config, FallbackReason::FileMissing),
+            Some(FallbackTarget::LocalFile(PathBuf::from(
+                "/etc/embystream/fallback/missing.mp4"
+            )))
+        );
+        assert_eq!(
+            FallbackTarget::resolve(&config, FallbackReason::UpstreamDown),
+            Some(FallbackTarget::Redirect(
+                "https://cdn.example.com/down.mp4".parse().expect("uri")
+            ))
+        );
+        assert_eq!(
+            FallbackTarget::resolve(&config, FallbackReason::SignExpired),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_fallback_url_is_rejected() {
+        let result = TestConfig::new()
+            .section("[Fallback]\nsign_expired_path = \"https://\"")
+            .try_build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn upstream_status_classification() {
+        let drive = node("googleDrive");
+        let relay = node("StreamRelay");
+        let quota_body =
+            br#"{"error":{"errors":[{"reason":"downloadQuotaExceeded"}]}}"#;
+
+        assert_eq!(
+            FallbackReason::from_upstream_status(
+                &drive,
+                StatusCode::FORBIDDEN,
+                quota_body
+            ),
+            FallbackReason::QuotaExceeded
+        );
+        assert_eq!(
+            FallbackReason::from_upstream_status(
+                &relay,
+                StatusCode::FORBIDDEN,
+                quota_body
+            ),
+            FallbackReason::UpstreamDown
+        );
+        assert_eq!(
+            FallbackReason::from_upstream_status(
+                &relay,
+                StatusCode::TOO_MANY_REQUESTS,
+                b""
+            ),
+            FallbackReason::ConcurrentLimit
+        );
+        assert_eq!(
+            FallbackReason::from_upstream_status(
+                &relay,
+                StatusCode::NOT_FOUND,
+                b""
+            ),
+            FallbackReason::FileMissing
+        );
+    }
+
+    #[tokio::test]
+    async fn respond_redirects_without_caching() {
+        let config = config_with_fallback(
+            r#"concurrent_limit_path = "https://cdn.example.com/busy.mp4""#,
+        );
+        let state = Arc::new(AppState::new(config).await);
+
+        let result = FallbackResponder::respond(
+            &state,
+            StreamFailure::new(
+                FallbackReason::ConcurrentLimit,
+                StatusCode::BAD_GATEWAY,
+            ),
+            &HeaderMap::new(),
+        )
+        .await;
+
+        match result {
+            Ok(AppStreamResult::Redirect(info)) => {
+                assert_eq!(
+                    info.target_url.to_string(),
+                    "https://cdn.example.com/busy.mp4"
+                );
+                assert_eq!(
+                    info.final_headers.get(header::CACHE_CONTROL),
+                    Some(&header::HeaderValue::from_static("no-store"))
+                );
+            }
+            _ => panic!("expected fallback redirect"),
+        }
+    }
+
+    #[tokio::test]
+    async fn respond_streams_local_file_and_keeps_status_when_unset() {
+        let dir = TempDir::new().expect("temp dir");
+        let path = dir.path().join("expired.mp4");
+        fs::write(&path, b"hello world").expect("write fallback");
+        let config = config_with_fallback(&format!(
+            r#"sign_expired_path = "{}""#,
+            path.to_string_lossy().replace('\\', "\\\\")
+        ));
+        let state = Arc::new(AppState::new(config).await);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, "bytes=0-4".parse().expect("range"));
+
+        let result = FallbackResponder::respond(
+            &state,
+            StreamFailure::new(FallbackReason::SignExpired, StatusCode::GONE),
+            &headers,
+        )
+        .await;
+        match result {
+            Ok(AppStreamResult::Stream(response)) => {
+                assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+            }
+            _ => panic!("expected fallback stream"),
+        }
+
+        let unset = FallbackResponder::respond(
+            &state,
+            StreamFailure::new(
+                FallbackReason::UpstreamDown,
+                StatusCode::BAD_GATEWAY,
+            ),
+            &headers,
+        )
+        .await;
+        assert!(matches!(unset, Err(StatusCode::BAD_GATEWAY)));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::backend::GoogleDriveConfig;
+use crate::config::backend::{GoogleDriveConfig, GoogleDriveRoot};
 
 pub const BACKEND_TYPE: &str = "googleDrive";
 pub const ACCEL_REDIRECT_PREFIX: &str = "/_origin/google-drive";
@@ -7,6 +7,10 @@ pub const ACCEL_REDIRECT_PREFIX: &str = "/_origin/google-drive";
 pub enum DriveLookup {
     DriveId(String),
     DriveName(String),
+    /// The account's personal My Drive (`root` alias).
+    MyDrive,
+    /// Any folder the account can read, addressed by its file ID.
+    Folder(String),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -31,6 +35,41 @@ fn join_segments(segments: &[&str]) -> String {
     }
 }
 
+fn drive_lookup(
+    cfg: &GoogleDriveConfig,
+    drive_name: &str,
+) -> Result<DriveLookup, &'static str> {
+    match cfg.root {
+        GoogleDriveRoot::MyDrive => Ok(DriveLookup::MyDrive),
+        GoogleDriveRoot::Folder => {
+            let folder_id = cfg.root_folder_id.trim();
+            if folder_id.is_empty() {
+                return Err("googleDrive root_folder_id is empty");
+            }
+            Ok(DriveLookup::Folder(folder_id.to_string()))
+        }
+        GoogleDriveRoot::SharedDrive if cfg.drive_id.trim().is_empty() => {
+            Ok(DriveLookup::DriveName(drive_name.to_string()))
+        }
+        GoogleDriveRoot::SharedDrive => {
+            Ok(DriveLookup::DriveId(cfg.drive_id.trim().to_string()))
+        }
+    }
+}
+
+/// The lookup every path of the node resolves through, or `None` when the
+/// shared drive name is inferred per request from the path.
+pub fn configured_drive_lookup(cfg: &GoogleDriveConfig) -> Option<DriveLookup> {
+    let drive_name = cfg.drive_name.trim();
+    if cfg.root.is_shared_drive()
+        && cfg.drive_id.trim().is_empty()
+        && drive_name.is_empty()
+    {
+        return None;
+    }
+    drive_lookup(cfg, drive_name).ok()
+}
+
 pub fn resolve_google_drive_path(
     raw_path: &str,
     cfg: &GoogleDriveConfig,
@@ -76,11 +115,7 @@ pub fn resolve_google_drive_path(
         &logical_segments
     };
 
-    let lookup = if cfg.drive_id.trim().is_empty() {
-        DriveLookup::DriveName(drive_name.clone())
-    } else {
-        DriveLookup::DriveId(cfg.drive_id.trim().to_string())
-    };
+    let lookup = drive_lookup(cfg, &drive_name)?;
 
     Ok(ResolvedGoogleDrivePath {
         lookup,
@@ -170,4 +205,36 @@ mod tests {
         assert_eq!(resolved.logical_path, "/pilipili/电影/2026/test/test.mkv");
         assert_eq!(resolved.relative_path, "/电影/2026/test/test.mkv");
     }
+
+    #[test]
+    fn my_drive_and_folder_roots_override_shared_drive_lookup() {
+        let my_drive = GoogleDriveConfig {
+            drive_id: "drive-123".into(),
+            drive_name: "gdrive".into(),
+            root: GoogleDriveRoot::MyDrive,
+            ..Default::default()
+        };
+        let resolved =
+            resolve_google_drive_path("/mnt/gdrive/电影/test.mkv", &my_drive)
+                .expect("resolve");
+        assert_eq!(resolved.lookup, DriveLookup::MyDrive);
+        assert_eq!(resolved.relative_path, "/电影/test.mkv");
+
+        let folder = GoogleDriveConfig {
+            root: GoogleDriveRoot::Folder,
+            root_folder_id: " folder-abc ".into(),
+            drive_name: "shared".into(),
+            ..Default::default()
+        };
+        let resolved = resolve_google_drive_path("/shared/a.mkv", &folder)
+            .expect("resolve");
+        assert_eq!(resolved.lookup, DriveLookup::Folder("folder-abc".into()));
+        assert_eq!(resolved.relative_path, "/a.mkv");
+
+        let missing_folder = GoogleDriveConfig {
+            root: GoogleDriveRoot::Folder,
+            ..Default::default()
+        };
+        assert!(resolve_google_drive_path("/a.mkv", &missing_folder).is_err());
+    }
 }
@@ -10,8 +10,11 @@ use crate::{
     config::backend::{BackendNode, GoogleDriveConfig},
     debug_log, error_log, info_log,
     oauthutil::{
-        GoogleDriveTokenSource, OAuthToken, TokenRequest, TokenSourceError,
+        GoogleDriveTokenSource, OAuthToken, ServiceAccountTokenSource,
+        TokenRequest, TokenSnapshot, TokenSourceError,
     },
+    util::StringUtil,
+    warn_log,
 };
 
 use super::google_drive::BACKEND_TYPE as GOOGLE_DRIVE_BACKEND_TYPE;
@@ -24,6 +27,12 @@ pub const ACCEL_REDIRECT_MIN_VALID_SECS: i64 = 600;
 const PRE_REFRESH_LEAD_SECS: i64 = ACCEL_REDIRECT_MIN_VALID_SECS;
 const REFRESH_SCHEDULER_IDLE_SECS: i64 = 300;
 const REFRESH_SCHEDULER_MIN_SLEEP_SECS: i64 = 1;
+/// Download quotas reset daily, so an exhausted credential sits out longer
+/// than one that only hit a per-user rate limit.
+const QUOTA_BACKOFF_SECS: i64 = 3600;
+const RATE_LIMIT_BACKOFF_SECS: i64 = 60;
+const RATE_LIMIT_REASONS: &[&str] =
+    &["userRateLimitExceeded", "rateLimitExceeded"];
 
 pub type GoogleDriveRefreshLocks = DashMap<String, Arc<AsyncMutex<()>>>;
 
@@ -43,21 +52,155 @@ pub fn extra_headers_from_auth_line(
     Ok(map)
 }
 
+/// One identity a googleDrive node can fetch with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GoogleDriveCredential {
+    /// The node's OAuth refresh token.
+    OAuth,
+    /// A service-account JSON key file.
+    ServiceAccount(String),
+}
+
+impl GoogleDriveCredential {
+    fn label(&self) -> String {
+        match self {
+            Self::OAuth => "oauth".to_string(),
+            Self::ServiceAccount(path) => {
+                format!("service-account:{}", StringUtil::hash_hex(path))
+            }
+        }
+    }
+}
+
+/// Credentials in rotation order: the OAuth account first when it can be
+/// refreshed (or is the only option), then every service-account key.
+pub fn credential_pool(cfg: &GoogleDriveConfig) -> Vec<GoogleDriveCredential> {
+    let mut pool = Vec::new();
+    if cfg.effective_refresh_token().is_some()
+        || cfg.service_account_files().next().is_none()
+    {
+        pool.push(GoogleDriveCredential::OAuth);
+    }
+    pool.extend(
+        cfg.service_account_files().map(|path| {
+            GoogleDriveCredential::ServiceAccount(path.to_string())
+        }),
+    );
+    pool
+}
+
+/// First credential not benched for quota; when every one is benched, the
+/// one whose backoff ends soonest.
+pub fn select_credential(
+    state: &AppState,
+    node: &BackendNode,
+) -> GoogleDriveCredential {
+    let Some(cfg) = node.google_drive.as_ref() else {
+        return GoogleDriveCredential::OAuth;
+    };
+    let now = Utc::now();
+    let mut soonest: Option<(DateTime<Utc>, GoogleDriveCredential)> = None;
+    for credential in credential_pool(cfg) {
+        let benched_until = state
+            .google_drive_credential_backoff_until
+            .get(&credential_backoff_key(&cfg.node_uuid, &credential))
+            .map(|value| *value.value())
+            .filter(|until| *until > now);
+        let Some(until) = benched_until else {
+            return credential;
+        };
+        if soonest.as_ref().is_none_or(|(current, _)| until < *current) {
+            soonest = Some((until, credential));
+        }
+    }
+    soonest
+        .map(|(_, credential)| credential)
+        .unwrap_or(GoogleDriveCredential::OAuth)
+}
+
+fn is_benched(
+    state: &AppState,
+    node: &BackendNode,
+    credential: &GoogleDriveCredential,
+) -> bool {
+    let Some(cfg) = node.google_drive.as_ref() else {
+        return false;
+    };
+    state
+        .google_drive_credential_backoff_until
+        .get(&credential_backoff_key(&cfg.node_uuid, credential))
+        .is_some_and(|until| *until.value() > Utc::now())
+}
+
+fn credential_backoff_key(
+    node_uuid: &str,
+    credential: &GoogleDriveCredential,
+) -> String {
+    format!(
+        "google-drive-credential:{}:{}",
+        node_uuid.trim().to_ascii_lowercase(),
+        credential.label()
+    )
+}
+
+async fn credential_token(
+    state: Arc<AppState>,
+    node: BackendNode,
+    credential: &GoogleDriveCredential,
+    request: TokenRequest,
+) -> Result<TokenSnapshot, TokenSourceError> {
+    match credential {
+        GoogleDriveCredential::OAuth => {
+            GoogleDriveTokenSource::new(state, node)
+                .token(request)
+                .await
+        }
+        GoogleDriveCredential::ServiceAccount(path) => {
+            ServiceAccountTokenSource::new(state, node, path)
+                .token(request)
+                .await
+        }
+    }
+}
+
+fn cached_access_token(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    credential: &GoogleDriveCredential,
+) -> Option<String> {
+    match credential {
+        GoogleDriveCredential::OAuth => {
+            GoogleDriveTokenSource::new(state.clone(), node.clone())
+                .cached_access_token()
+        }
+        GoogleDriveCredential::ServiceAccount(path) => {
+            ServiceAccountTokenSource::new(state.clone(), node.clone(), path)
+                .cached_access_token()
+        }
+    }
+}
+
 pub async fn token_for_request(
     state: Arc<AppState>,
     node: BackendNode,
     reason: &'static str,
     min_valid_for: Duration,
 ) -> Result<OAuthToken, TokenSourceError> {
-    let snapshot = GoogleDriveTokenSource::new(state, node)
-        .token(TokenRequest::new(reason, min_valid_for))
-        .await?;
+    let credential = select_credential(&state, &node);
+    let snapshot = credential_token(
+        state,
+        node,
+        &credential,
+        TokenRequest::new(reason, min_valid_for),
+    )
+    .await?;
 
     debug_log!(
         GOOGLE_DRIVE_AUTH_LOGGER_DOMAIN,
-        "google_drive_token_selected reason={} source={}",
+        "google_drive_token_selected reason={} source={} credential={}",
         reason,
-        snapshot.source
+        snapshot.source,
+        credential.label()
     );
 
     Ok(snapshot.token)
@@ -83,14 +226,120 @@ pub async fn force_refresh(
     node: BackendNode,
     reason: &'static str,
 ) -> Result<OAuthToken, TokenSourceError> {
-    GoogleDriveTokenSource::new(state, node)
-        .token(TokenRequest::force_refresh(reason))
-        .await
-        .map(|snapshot| snapshot.token)
+    let credential = select_credential(&state, &node);
+    credential_token(
+        state,
+        node,
+        &credential,
+        TokenRequest::force_refresh(reason),
+    )
+    .await
+    .map(|snapshot| snapshot.token)
 }
 
+/// Drops the cached token of the credential currently in use, e.g. after
+/// Drive answered 401.
 pub fn invalidate(state: &Arc<AppState>, node: &BackendNode) {
-    GoogleDriveTokenSource::new(state.clone(), node.clone()).invalidate();
+    match select_credential(state, node) {
+        GoogleDriveCredential::OAuth => {
+            GoogleDriveTokenSource::new(state.clone(), node.clone())
+                .invalidate();
+        }
+        GoogleDriveCredential::ServiceAccount(path) => {
+            ServiceAccountTokenSource::new(state.clone(), node.clone(), &path)
+                .invalidate();
+        }
+    }
+}
+
+/// Benches the credential that sent `used_headers` after Drive refused it
+/// with a quota or rate-limit error (`body` is the error payload, empty for
+/// HEAD probes). Returns the benched credential.
+pub fn bench_after_quota_error(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    used_headers: Option<&HeaderMap>,
+    body: &[u8],
+) -> Option<GoogleDriveCredential> {
+    let cfg = google_drive_config(node)?;
+    let used_token = used_headers
+        .and_then(|headers| headers.get(header::AUTHORIZATION))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .strip_prefix("Bearer ")
+                .or_else(|| value.strip_prefix("bearer "))
+                .unwrap_or(value)
+                .trim()
+                .to_string()
+        });
+    let credential = used_token
+        .and_then(|token| {
+            credential_pool(cfg).into_iter().find(|credential| {
+                cached_access_token(state, node, credential).as_deref()
+                    == Some(token.as_str())
+            })
+        })
+        .unwrap_or_else(|| select_credential(state, node));
+
+    let body = String::from_utf8_lossy(body);
+    let backoff_secs = if RATE_LIMIT_REASONS
+        .iter()
+        .any(|reason| body.contains(reason))
+    {
+        RATE_LIMIT_BACKOFF_SECS
+    } else {
+        QUOTA_BACKOFF_SECS
+    };
+    state.google_drive_credential_backoff_until.insert(
+        credential_backoff_key(&cfg.node_uuid, &credential),
+        Utc::now() + Duration::seconds(backoff_secs),
+    );
+    warn_log!(
+        GOOGLE_DRIVE_AUTH_LOGGER_DOMAIN,
+        "google_drive_credential_benched node={} credential={} backoff_secs={}",
+        node.name,
+        credential.label(),
+        backoff_secs
+    );
+    Some(credential)
+}
+
+/// Benches the refused credential and returns auth headers for the next
+/// one in the pool, or `None` when no other credential is available.
+pub async fn rotate_after_quota_error(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    used_headers: Option<&HeaderMap>,
+    body: &[u8],
+) -> Option<HeaderMap> {
+    let benched = bench_after_quota_error(state, node, used_headers, body)?;
+    let next = select_credential(state, node);
+    if next == benched || is_benched(state, node, &next) {
+        return None;
+    }
+
+    let snapshot = credential_token(
+        state.clone(),
+        node.clone(),
+        &next,
+        TokenRequest::new(
+            "quota_rotation",
+            Duration::seconds(PROXY_MIN_VALID_SECS),
+        ),
+    )
+    .await
+    .map_err(|error| log_token_error("quota_rotation", node, &error))
+    .ok()?;
+    info_log!(
+        GOOGLE_DRIVE_AUTH_LOGGER_DOMAIN,
+        "google_drive_credential_rotated node={} from={} to={}",
+        node.name,
+        benched.label(),
+        next.label()
+    );
+    let auth_line = snapshot.token.authorization_header_value()?;
+    extra_headers_from_auth_line(&auth_line).ok()
 }
 
 fn collect_refreshable_google_drive_nodes(
@@ -313,13 +562,17 @@ fn std_duration_from_chrono(duration: Duration) -> StdDuration {
 mod tests {
     use chrono::{Duration, TimeZone, Utc};
 
+    use std::sync::Arc;
+
     use super::{
-        ACCEL_REDIRECT_MIN_VALID_SECS, collect_refreshable_google_drive_nodes,
-        scheduled_refresh_delay,
+        ACCEL_REDIRECT_MIN_VALID_SECS, GoogleDriveCredential,
+        RATE_LIMIT_BACKOFF_SECS, bench_after_quota_error,
+        collect_refreshable_google_drive_nodes, credential_pool,
+        scheduled_refresh_delay, select_credential,
     };
     use crate::{
         AppState,
-        config::backend::{BackendNode, GoogleDriveConfig},
+        config::backend::{BackendNode, GoogleDriveConfig, GoogleDriveRoot},
         config::core::{finish_raw_config, parse_raw_config_str},
         oauthutil::OAuthToken,
     };
@@ -386,11 +639,19 @@ host = ""
                 client_secret: "client-secret".to_string(),
                 drive_id: String::new(),
                 drive_name: String::new(),
+                root: GoogleDriveRoot::SharedDrive,
+                root_folder_id: String::new(),
+                service_account_files: vec![],
+                service_account_subject: String::new(),
+                folder_index: false,
+                token_secret: String::new(),
                 access_token: String::new(),
                 refresh_token: refresh_token.to_string(),
                 token: None,
             }),
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
@@ -441,6 +702,8 @@ host = ""
                 direct_link: None,
                 google_drive: None,
                 webdav: None,
+                smb: None,
+                resilience: None,
             },
         ];
 
@@ -512,4 +775,97 @@ host = ""
             Duration::seconds(42)
         );
     }
+
+    fn pooled_node() -> BackendNode {
+        let mut node = google_drive_node("google-1", "node-1", "refresh-1");
+        node.google_drive
+            .as_mut()
+            .expect("google config")
+            .service_account_files =
+            vec!["/keys/a.json".to_string(), " ".to_string()];
+        node
+    }
+
+    #[test]
+    fn credential_pool_lists_oauth_before_service_accounts() {
+        let node = pooled_node();
+        let cfg = node.google_drive.as_ref().expect("google config");
+        assert_eq!(
+            credential_pool(cfg),
+            vec![
+                GoogleDriveCredential::OAuth,
+                GoogleDriveCredential::ServiceAccount(
+                    "/keys/a.json".to_string()
+                ),
+            ]
+        );
+
+        let mut service_account_only = node.clone();
+        service_account_only
+            .google_drive
+            .as_mut()
+            .expect("google config")
+            .refresh_token
+            .clear();
+        assert_eq!(
+            credential_pool(
+                service_account_only.google_drive.as_ref().expect("config")
+            ),
+            vec![GoogleDriveCredential::ServiceAccount(
+                "/keys/a.json".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn quota_error_benches_credential_and_rotates_to_next() {
+        let node = pooled_node();
+        let state = Arc::new(test_state_with_node(node.clone()).await);
+        assert_eq!(
+            select_credential(&state, &node),
+            GoogleDriveCredential::OAuth
+        );
+
+        let benched = bench_after_quota_error(
+            &state,
+            &node,
+            None,
+            b"downloadQuotaExceeded",
+        );
+        assert_eq!(benched, Some(GoogleDriveCredential::OAuth));
+        assert_eq!(
+            select_credential(&state, &node),
+            GoogleDriveCredential::ServiceAccount("/keys/a.json".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limit_bench_is_short_and_all_benched_picks_soonest() {
+        let node = pooled_node();
+        let state = Arc::new(test_state_with_node(node.clone()).await);
+
+        bench_after_quota_error(&state, &node, None, b"downloadQuotaExceeded");
+        let before = Utc::now();
+        bench_after_quota_error(
+            &state,
+            &node,
+            None,
+            br#"{"error":{"errors":[{"reason":"userRateLimitExceeded"}]}}"#,
+        );
+
+        let rate_limited = state
+            .google_drive_credential_backoff_until
+            .iter()
+            .map(|entry| *entry.value())
+            .min()
+            .expect("benched credential");
+        assert!(
+            rate_limited
+                <= before + Duration::seconds(RATE_LIMIT_BACKOFF_SECS + 1)
+        );
+        assert_eq!(
+            select_credential(&state, &node),
+            GoogleDriveCredential::ServiceAccount("/keys/a.json".to_string())
+        );
+    }
 }
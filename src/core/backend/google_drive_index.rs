@@ -0,0 +1,761 @@
+//! Local path index for googleDrive nodes with `folder_index = true`.
+//!
+//! The index is built once from `files.list`, then kept current from the
+//! Drive Changes API and persisted next to the config, so a restart resumes
+//! from the saved change token instead of re-walking the drive. Lookups
+//! that miss the index still fall back to the live per-segment walk.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration as StdDuration,
+};
+
+use chrono::Duration;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::task::AbortHandle;
+
+use crate::{
+    AppState,
+    client::google_drive::{
+        DriveChange, DriveFileEntry, GoogleDriveApiError,
+        GoogleDriveResolvedFile,
+    },
+    config::{backend::BackendNode, core::write_atomic_config},
+    debug_log, info_log,
+    oauthutil::TokenSourceError,
+    warn_log,
+};
+
+use super::{
+    google_drive::{
+        DriveLookup, ResolvedGoogleDrivePath, configured_drive_lookup,
+    },
+    google_drive_auth,
+};
+
+const GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN: &str = "GOOGLE-DRIVE-INDEX";
+
+const INDEX_FORMAT_VERSION: u32 = 1;
+const INDEX_POLL_SECS: u64 = 60;
+const INDEX_RETRY_SECS: u64 = 300;
+/// Drive answers these when a saved change token is no longer usable.
+const EXPIRED_PAGE_TOKEN_STATUSES: &[u16] = &[400, 404, 410];
+
+pub type GoogleDriveFolderIndexes = DashMap<String, FolderIndexer>;
+
+/// A running indexer task and the index it keeps current. Dropping it
+/// stops the task.
+#[derive(Debug)]
+pub struct FolderIndexer {
+    index: Arc<RwLock<GoogleDriveFolderIndex>>,
+    /// Node settings and index path the task was started with; see
+    /// [`indexer_fingerprint`].
+    fingerprint: String,
+    task: AbortHandle,
+}
+
+impl Drop for FolderIndexer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Debug, Error)]
+enum IndexError {
+    #[error(transparent)]
+    Token(#[from] TokenSourceError),
+    #[error(transparent)]
+    Api(#[from] GoogleDriveApiError),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct IndexedFile {
+    name: String,
+    #[serde(default)]
+    parents: Vec<String>,
+    #[serde(default)]
+    folder: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+/// Every file of one drive corpus, addressable by parent and name.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct GoogleDriveFolderIndex {
+    version: u32,
+    /// The node lookup the index was built for; see [`lookup_key`].
+    lookup: String,
+    root_id: String,
+    /// Shared Drive whose change feed is followed; empty for the user's
+    /// own corpus.
+    drive_id: String,
+    start_page_token: String,
+    files: HashMap<String, IndexedFile>,
+    #[serde(skip)]
+    children: HashMap<String, HashMap<String, Vec<String>>>,
+}
+
+impl GoogleDriveFolderIndex {
+    fn new(
+        lookup: String,
+        root_id: String,
+        drive_id: String,
+        start_page_token: String,
+    ) -> Self {
+        Self {
+            version: INDEX_FORMAT_VERSION,
+            lookup,
+            root_id,
+            drive_id,
+            start_page_token,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.root_id.is_empty() && !self.start_page_token.is_empty()
+    }
+
+    /// Walks `relative_path` from the root; `None` when a segment is
+    /// missing, ambiguous, or a non-final segment is not a folder.
+    pub fn resolve(
+        &self,
+        relative_path: &str,
+    ) -> Option<GoogleDriveResolvedFile> {
+        let segments: Vec<&str> = relative_path
+            .split(['/', '\\'])
+            .filter(|segment| !segment.trim().is_empty())
+            .collect();
+        if segments.is_empty() || !self.is_ready() {
+            return None;
+        }
+
+        let mut current_id = self.root_id.as_str();
+        for (idx, segment) in segments.iter().enumerate() {
+            let [file_id] =
+                self.children.get(current_id)?.get(*segment)?.as_slice()
+            else {
+                return None;
+            };
+            let is_last = idx + 1 == segments.len();
+            if !is_last && !self.files.get(file_id)?.folder {
+                return None;
+            }
+            current_id = file_id;
+        }
+
+        // A Shared Drive's root folder ID is the drive ID itself.
+        let drive_id = if self.root_id == self.drive_id {
+            self.drive_id.clone()
+        } else {
+            String::new()
+        };
+        Some(GoogleDriveResolvedFile {
+            drive_id,
+            file_id: current_id.to_string(),
+            size: self.files.get(current_id).and_then(|file| file.size),
+        })
+    }
+
+    fn insert(&mut self, entry: DriveFileEntry) {
+        self.remove(&entry.id);
+        if entry.trashed {
+            return;
+        }
+        for parent in &entry.parents {
+            self.children
+                .entry(parent.clone())
+                .or_default()
+                .entry(entry.name.clone())
+                .or_default()
+                .push(entry.id.clone());
+        }
+        self.files.insert(
+            entry.id,
+            IndexedFile {
+                name: entry.name,
+                parents: entry.parents,
+                folder: entry.is_folder,
+                size: entry.size,
+            },
+        );
+    }
+
+    fn remove(&mut self, file_id: &str) {
+        let Some(file) = self.files.remove(file_id) else {
+            return;
+        };
+        for parent in &file.parents {
+            let Some(names) = self.children.get_mut(parent) else {
+                continue;
+            };
+            if let Some(ids) = names.get_mut(&file.name) {
+                ids.retain(|id| id != file_id);
+                if ids.is_empty() {
+                    names.remove(&file.name);
+                }
+            }
+            if names.is_empty() {
+                self.children.remove(parent);
+            }
+        }
+    }
+
+    fn apply_change(&mut self, change: DriveChange) {
+        match change.file {
+            Some(file) if !change.removed => self.insert(file),
+            _ => self.remove(&change.file_id),
+        }
+    }
+
+    fn rebuild_children(&mut self) {
+        self.children.clear();
+        for (id, file) in &self.files {
+            for parent in &file.parents {
+                self.children
+                    .entry(parent.clone())
+                    .or_default()
+                    .entry(file.name.clone())
+                    .or_default()
+                    .push(id.clone());
+            }
+        }
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut index: Self = serde_json::from_str(&content).ok()?;
+        if index.version != INDEX_FORMAT_VERSION {
+            return None;
+        }
+        index.rebuild_children();
+        Some(index)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let content =
+            serde_json::to_string(self).map_err(|error| error.to_string())?;
+        write_atomic_config(path, &content).map_err(|error| error.to_string())
+    }
+}
+
+/// Stable text form of a [`DriveLookup`], stored with the index so a
+/// config change invalidates it.
+fn lookup_key(lookup: &DriveLookup) -> String {
+    match lookup {
+        DriveLookup::DriveId(id) => format!("drive_id:{id}"),
+        DriveLookup::DriveName(name) => format!("drive_name:{name}"),
+        DriveLookup::MyDrive => "my_drive".to_string(),
+        DriveLookup::Folder(id) => format!("folder:{id}"),
+    }
+}
+
+fn index_key(node_uuid: &str) -> String {
+    node_uuid.trim().to_ascii_lowercase()
+}
+
+fn index_file_path(dir: &Path, node_uuid: &str) -> PathBuf {
+    dir.join(format!("{}.json", index_key(node_uuid)))
+}
+
+/// The task captures the node and index path, so any change to either
+/// needs a fresh task.
+fn indexer_fingerprint(
+    node: &BackendNode,
+    index_path: Option<&Path>,
+) -> String {
+    format!(
+        "{}|{}|{}",
+        node.name,
+        serde_json::to_string(&node.google_drive).unwrap_or_default(),
+        index_path
+            .map(Path::display)
+            .map(|path| path.to_string())
+            .unwrap_or_default()
+    )
+}
+
+/// Answers a lookup from the node's index when it is built for the same
+/// drive lookup.
+pub fn lookup(
+    state: &AppState,
+    node: &BackendNode,
+    resolved_path: &ResolvedGoogleDrivePath,
+) -> Option<GoogleDriveResolvedFile> {
+    let cfg = node.google_drive.as_ref()?;
+    let index = state
+        .google_drive_folder_indexes
+        .get(&index_key(&cfg.node_uuid))?
+        .index
+        .clone();
+    let index = index.read().unwrap_or_else(PoisonError::into_inner);
+    if index.lookup != lookup_key(&resolved_path.lookup) {
+        return None;
+    }
+    index.resolve(&resolved_path.relative_path)
+}
+
+/// Brings the indexer tasks in line with the current config: one task per
+/// googleDrive node with `folder_index` enabled. Tasks whose node is gone
+/// or changed are stopped, unchanged ones keep running. Returns how many
+/// indexers are running.
+pub async fn reconcile_google_drive_folder_indexers(
+    state: Arc<AppState>,
+) -> usize {
+    let (nodes, index_dir) = {
+        let config = state.get_config().await;
+        let nodes: Vec<BackendNode> = config
+            .backend_nodes
+            .iter()
+            .filter(|node| google_drive_auth::is_google_drive_node(node))
+            .filter(|node| {
+                node.google_drive
+                    .as_ref()
+                    .is_some_and(|cfg| cfg.folder_index)
+            })
+            .cloned()
+            .collect();
+        (nodes, config.get_google_drive_index_dir())
+    };
+
+    let mut wanted = HashMap::new();
+    for node in nodes {
+        let Some(cfg) = node.google_drive.as_ref() else {
+            continue;
+        };
+        let Some(drive_lookup) = configured_drive_lookup(cfg) else {
+            warn_log!(
+                GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+                "google_drive_index_skipped node={} reason=drive_name_inferred_from_path",
+                node.name
+            );
+            continue;
+        };
+        let index_path = index_dir
+            .as_deref()
+            .map(|dir| index_file_path(dir, &cfg.node_uuid));
+        let fingerprint = indexer_fingerprint(&node, index_path.as_deref());
+        wanted.insert(
+            index_key(&cfg.node_uuid),
+            (node, drive_lookup, index_path, fingerprint),
+        );
+    }
+
+    state.google_drive_folder_indexes.retain(|key, indexer| {
+        wanted.get(key).is_some_and(|(_, _, _, fingerprint)| {
+            *fingerprint == indexer.fingerprint
+        })
+    });
+
+    for (key, (node, drive_lookup, index_path, fingerprint)) in wanted {
+        if state.google_drive_folder_indexes.contains_key(&key) {
+            continue;
+        }
+        let index = Arc::new(RwLock::new(GoogleDriveFolderIndex::default()));
+        let task = tokio::spawn(run_folder_indexer(
+            state.clone(),
+            node,
+            drive_lookup,
+            index_path,
+            index.clone(),
+        ))
+        .abort_handle();
+        state.google_drive_folder_indexes.insert(
+            key,
+            FolderIndexer {
+                index,
+                fingerprint,
+                task,
+            },
+        );
+    }
+    state.google_drive_folder_indexes.len()
+}
+
+async fn run_folder_indexer(
+    state: Arc<AppState>,
+    node: BackendNode,
+    drive_lookup: DriveLookup,
+    index_path: Option<PathBuf>,
+    shared: Arc<RwLock<GoogleDriveFolderIndex>>,
+) {
+    let expected_lookup = lookup_key(&drive_lookup);
+    let persisted = index_path
+        .as_deref()
+        .and_then(GoogleDriveFolderIndex::load)
+        .filter(|index| index.lookup == expected_lookup && index.is_ready());
+    let mut ready = persisted.is_some();
+    if let Some(persisted) = persisted {
+        info_log!(
+            GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+            "google_drive_index_loaded node={} files={}",
+            node.name,
+            persisted.len()
+        );
+        *shared.write().unwrap_or_else(PoisonError::into_inner) = persisted;
+    }
+
+    loop {
+        let outcome = if ready {
+            sync_changes(&state, &node, &shared).await
+        } else {
+            build_index(&state, &node, &drive_lookup)
+                .await
+                .map(|fresh| {
+                    info_log!(
+                        GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+                        "google_drive_index_built node={} files={}",
+                        node.name,
+                        fresh.len()
+                    );
+                    *shared.write().unwrap_or_else(PoisonError::into_inner) =
+                        fresh;
+                    true
+                })
+        };
+
+        let sleep_secs = match outcome {
+            Ok(changed) => {
+                ready = true;
+                if changed && let Some(path) = index_path.as_deref() {
+                    let saved = shared
+                        .read()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .save(path);
+                    if let Err(error) = saved {
+                        warn_log!(
+                            GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+                            "google_drive_index_persist_failed node={} path={} error={}",
+                            node.name,
+                            path.display(),
+                            error
+                        );
+                    }
+                }
+                INDEX_POLL_SECS
+            }
+            Err(IndexError::Api(GoogleDriveApiError::ApiStatus {
+                status: 401,
+                ..
+            })) => {
+                google_drive_auth::invalidate(&state, &node);
+                INDEX_POLL_SECS
+            }
+            Err(IndexError::Api(GoogleDriveApiError::ApiStatus {
+                status,
+                body,
+            })) if ready && EXPIRED_PAGE_TOKEN_STATUSES.contains(&status) => {
+                warn_log!(
+                    GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+                    "google_drive_index_rebuild node={} status={} body={}",
+                    node.name,
+                    status,
+                    body
+                );
+                ready = false;
+                continue;
+            }
+            Err(error) => {
+                warn_log!(
+                    GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+                    "google_drive_index_sync_failed node={} error={}",
+                    node.name,
+                    error
+                );
+                INDEX_RETRY_SECS
+            }
+        };
+        tokio::time::sleep(StdDuration::from_secs(sleep_secs)).await;
+    }
+}
+
+async fn index_access_token(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+) -> Result<String, IndexError> {
+    let token = google_drive_auth::token_for_request(
+        state.clone(),
+        node.clone(),
+        "folder_index",
+        Duration::seconds(google_drive_auth::LOOKUP_MIN_VALID_SECS),
+    )
+    .await?;
+    Ok(token.access_token)
+}
+
+async fn build_index(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    drive_lookup: &DriveLookup,
+) -> Result<GoogleDriveFolderIndex, IndexError> {
+    let client = state.get_google_drive_client().await.clone();
+    let access_token = index_access_token(state, node).await?;
+    let (root_id, drive_id) = match drive_lookup {
+        DriveLookup::DriveId(id) => (id.clone(), id.clone()),
+        DriveLookup::DriveName(name) => {
+            let drive = client
+                .find_shared_drive_by_name(&access_token, name)
+                .await?
+                .ok_or_else(|| {
+                    GoogleDriveApiError::DriveNotFound(name.clone())
+                })?;
+            (drive.id.clone(), drive.id)
+        }
+        DriveLookup::MyDrive => {
+            client.get_file_drive(&access_token, "root").await?
+        }
+        DriveLookup::Folder(id) => {
+            client.get_file_drive(&access_token, id).await?
+        }
+    };
+
+    // Taken before listing so edits made during the walk are replayed.
+    let start_page_token = client
+        .get_start_page_token(&access_token, &drive_id)
+        .await?;
+    let mut index = GoogleDriveFolderIndex::new(
+        lookup_key(drive_lookup),
+        root_id,
+        drive_id,
+        start_page_token,
+    );
+
+    let mut page_token: Option<String> = None;
+    loop {
+        let access_token = index_access_token(state, node).await?;
+        let page = client
+            .list_files_page(
+                &access_token,
+                &index.drive_id,
+                page_token.as_deref(),
+            )
+            .await?;
+        for file in page.files {
+            index.insert(file);
+        }
+        match page.next_page_token {
+            Some(next) => page_token = Some(next),
+            None => break,
+        }
+    }
+    Ok(index)
+}
+
+/// Replays the change feed since the saved token; returns whether the
+/// index moved.
+async fn sync_changes(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    shared: &RwLock<GoogleDriveFolderIndex>,
+) -> Result<bool, IndexError> {
+    let client = state.get_google_drive_client().await.clone();
+    let (drive_id, start_page_token) = {
+        let index = shared.read().unwrap_or_else(PoisonError::into_inner);
+        (index.drive_id.clone(), index.start_page_token.clone())
+    };
+
+    let mut changes = Vec::new();
+    let mut page_token = start_page_token.clone();
+    let next_start_page_token = loop {
+        let access_token = index_access_token(state, node).await?;
+        let page = client
+            .list_changes_page(&access_token, &drive_id, &page_token)
+            .await?;
+        changes.extend(page.changes);
+        if let Some(next) = page.next_page_token {
+            page_token = next;
+            continue;
+        }
+        break page.new_start_page_token.unwrap_or(page_token);
+    };
+
+    let changed =
+        !changes.is_empty() || next_start_page_token != start_page_token;
+    debug_log!(
+        GOOGLE_DRIVE_INDEX_LOGGER_DOMAIN,
+        "google_drive_index_synced node={} changes={}",
+        node.name,
+        changes.len()
+    );
+    let mut index = shared.write().unwrap_or_else(PoisonError::into_inner);
+    for change in changes {
+        index.apply_change(change);
+    }
+    index.start_page_token = next_start_page_token;
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{GoogleDriveFolderIndex, index_file_path};
+    use crate::{
+        AppState,
+        client::google_drive::{DriveChange, DriveFileEntry},
+        test_support::TestConfig,
+    };
+
+    fn entry(
+        id: &str,
+        name: &str,
+        parent: &str,
+        folder: bool,
+    ) -> DriveFileEntry {
+        DriveFileEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            parents: vec![parent.to_string()],
+            is_folder: folder,
+            size: (!folder).then_some(42),
+            trashed: false,
+        }
+    }
+
+    fn sample_index() -> GoogleDriveFolderIndex {
+        let mut index = GoogleDriveFolderIndex::new(
+            "drive_id:drive-1".to_string(),
+            "drive-1".to_string(),
+            "drive-1".to_string(),
+            "token-1".to_string(),
+        );
+        index.insert(entry("shows", "Shows", "drive-1", true));
+        index.insert(entry("season", "Season 01", "shows", true));
+        index.insert(entry("ep1", "E01.mkv", "season", false));
+        index
+    }
+
+    #[test]
+    fn resolve_walks_folders_from_root() {
+        let index = sample_index();
+
+        let resolved = index
+            .resolve("/Shows/Season 01/E01.mkv")
+            .expect("indexed file");
+        assert_eq!(resolved.file_id, "ep1");
+        assert_eq!(resolved.drive_id, "drive-1");
+        assert_eq!(resolved.size, Some(42));
+        assert!(index.resolve("/Shows/Season 02/E01.mkv").is_none());
+        assert!(index.resolve("/Shows/Season 01/E01.mkv/x").is_none());
+    }
+
+    #[test]
+    fn changes_move_rename_and_remove_entries() {
+        let mut index = sample_index();
+
+        index.apply_change(DriveChange {
+            file_id: "ep1".to_string(),
+            removed: false,
+            file: Some(entry("ep1", "S01E01.mkv", "shows", false)),
+        });
+        assert!(index.resolve("/Shows/Season 01/E01.mkv").is_none());
+        assert_eq!(
+            index.resolve("/Shows/S01E01.mkv").expect("moved").file_id,
+            "ep1"
+        );
+
+        let mut trashed = entry("season", "Season 01", "shows", true);
+        trashed.trashed = true;
+        index.apply_change(DriveChange {
+            file_id: "season".to_string(),
+            removed: false,
+            file: Some(trashed),
+        });
+        index.apply_change(DriveChange {
+            file_id: "ep1".to_string(),
+            removed: true,
+            file: None,
+        });
+        assert!(index.resolve("/Shows/S01E01.mkv").is_none());
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_names_are_left_to_the_live_lookup() {
+        let mut index = sample_index();
+        index.insert(entry("ep1-copy", "E01.mkv", "season", false));
+
+        assert!(index.resolve("/Shows/Season 01/E01.mkv").is_none());
+    }
+
+    #[test]
+    fn persisted_index_round_trips() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let path = index_file_path(temp_dir.path(), "Node-1");
+        sample_index().save(&path).expect("save");
+
+        let loaded = GoogleDriveFolderIndex::load(&path).expect("load");
+        assert!(path.ends_with("node-1.json"));
+        assert_eq!(loaded.start_page_token, "token-1");
+        assert_eq!(
+            loaded
+                .resolve("/Shows/Season 01/E01.mkv")
+                .expect("indexed file")
+                .file_id,
+            "ep1"
+        );
+    }
+
+    #[tokio::test]
+    async fn reload_restarts_only_changed_indexers() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let config = |drive_id: &str, folder_index: bool| {
+            TestConfig::new()
+                .path(temp_dir.path().join("config.toml"))
+                .stream_mode("dual")
+                .section(
+                    "[Backend]\nlisten_port = 60002\n\
+                     base_url = \"https://emby.example.com\"\n\
+                     port = \"443\"\npath = \"\"\n\
+                     problematic_clients = []\n",
+                )
+                .section(&format!(
+                    "[[BackendNode]]\nname = \"Drive\"\n\
+                     type = \"googleDrive\"\npattern = \"/gd/.*\"\n\n\
+                     [BackendNode.GoogleDrive]\nnode_uuid = \"gd-node\"\n\
+                     client_id = \"client-id\"\n\
+                     client_secret = \"client-secret\"\n\
+                     refresh_token = \"refresh-token\"\n\
+                     drive_id = \"{drive_id}\"\n\
+                     folder_index = {folder_index}\n"
+                ))
+                .build()
+        };
+        let running = |state: &AppState| {
+            state
+                .google_drive_folder_indexes
+                .get("gd-node")
+                .map(|indexer| (indexer.index.clone(), indexer.task.clone()))
+        };
+        let state = Arc::new(AppState::new(config("drive-1", false)).await);
+
+        state.reload_config(config("drive-1", true)).await;
+        let (first, first_task) = running(&state).expect("indexer started");
+
+        state.reload_config(config("drive-1", true)).await;
+        let (same, _) = running(&state).expect("indexer kept");
+        assert!(Arc::ptr_eq(&first, &same));
+
+        state.reload_config(config("drive-2", true)).await;
+        let (second, _) = running(&state).expect("indexer restarted");
+        assert!(!Arc::ptr_eq(&first, &second));
+        tokio::task::yield_now().await;
+        assert!(first_task.is_finished());
+
+        state.reload_config(config("drive-2", false)).await;
+        assert!(running(&state).is_none());
+    }
+}
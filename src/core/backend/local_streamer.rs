@@ -16,6 +16,9 @@ use hyper::{HeaderMap, StatusCode, header};
 use lazy_static::lazy_static;
 
 use super::{
+    fallback::{
+        FallbackReason, FallbackResponder, FallbackTarget, StreamFailure,
+    },
     read_stream::ReaderStream,
     response::Response,
     result::Result as AppStreamResult,
@@ -24,7 +27,7 @@ use super::{
     },
 };
 use crate::cache::{FileMetadata, RateLimiter};
-use crate::gateway::error::Error as GatewayError;
+use crate::gateway::{error::Error as GatewayError, response::BoxBodyType};
 use crate::util::string_util::StringUtil;
 use crate::{
     AppState, LOCAL_STREAMER_LOGGER_DOMAIN, debug_log, error_log, info_log,
@@ -91,7 +94,7 @@ impl LocalStreamer {
         Self::fix_range_header_if_needed(
             &mut range_header,
             &client_info.user_agent,
-            problematic_clients,
+            &problematic_clients,
         )
         .await;
 
@@ -117,7 +120,13 @@ impl LocalStreamer {
         );
 
         let prepared_target =
-            Self::prepare_stream_target(state.clone(), path).await?;
+            match Self::prepare_stream_target(state.clone(), path).await {
+                Ok(target) => target,
+                Err(status) => {
+                    return Self::redirect_if_fallback_url(&state, status)
+                        .await;
+                }
+            };
 
         let content_range = match Self::parse_content_range(
             range_value,
@@ -379,22 +388,100 @@ impl LocalStreamer {
 
     async fn fallback_path(state: Arc<AppState>) -> Option<PathBuf> {
         let config = state.get_config().await;
-        let fallback_path_str = &config.fallback.video_missing_path;
-        if fallback_path_str.is_empty() {
-            return None;
+        match FallbackTarget::resolve(&config, FallbackReason::FileMissing)? {
+            FallbackTarget::LocalFile(path) => Some(path),
+            FallbackTarget::Redirect(_) => None,
         }
+    }
 
-        let fallback_path = PathBuf::from(fallback_path_str);
-        if fallback_path.is_absolute() {
-            Some(fallback_path)
-        } else {
-            Some(
-                config
-                    .path
-                    .parent()
-                    .unwrap_or_else(|| Path::new(""))
-                    .join(fallback_path),
-            )
+    /// A missing file with a URL fallback configured redirects instead of
+    /// returning the bare status; local fallbacks are already handled by
+    /// `prepare_stream_target`.
+    async fn redirect_if_fallback_url(
+        state: &Arc<AppState>,
+        status: StatusCode,
+    ) -> Result<AppStreamResult, StatusCode> {
+        let redirect = {
+            let config = state.get_config().await;
+            match FallbackTarget::resolve(&config, FallbackReason::FileMissing)
+            {
+                Some(FallbackTarget::Redirect(_)) => {
+                    status == StatusCode::NOT_FOUND
+                }
+                _ => false,
+            }
+        };
+        if !redirect {
+            return Err(status);
+        }
+
+        FallbackResponder::respond(
+            state,
+            StreamFailure::new(FallbackReason::FileMissing, status),
+            &HeaderMap::new(),
+        )
+        .await
+    }
+
+    /// Streams a fallback file outside of the rate limiter. Requests without
+    /// a `Range` header receive the whole file.
+    pub(crate) async fn stream_fallback(
+        state: Arc<AppState>,
+        path: &Path,
+        range_header: Option<&str>,
+    ) -> Result<AppStreamResult, StatusCode> {
+        let target = Self::prepare_direct_target(state, path)
+            .await
+            .map_err(|err| {
+                error_log!(
+                    LOCAL_STREAMER_LOGGER_DOMAIN,
+                    "fallback_stream_target_unavailable fallback={:?} error={}",
+                    path,
+                    err
+                );
+                StatusCode::NOT_FOUND
+            })?
+            .with_fallback(true);
+        let total_size = target.file_metadata.file_size;
+
+        let (content_range, status_code) =
+            Self::optional_content_range(range_header, total_size)?;
+
+        Self::stream_file(
+            target,
+            content_range,
+            status_code,
+            RateLimiter::unlimited(),
+        )
+        .await
+    }
+
+    /// Range to serve for an optional `Range` header: the whole file with
+    /// `200` when absent, the first requested range with `206` otherwise.
+    pub(crate) fn optional_content_range(
+        range_header: Option<&str>,
+        total_size: u64,
+    ) -> Result<(ContentRange, StatusCode), StatusCode> {
+        match range_header.filter(|value| !value.trim().is_empty()) {
+            Some(range_value) => {
+                match Self::parse_content_range(range_value, total_size) {
+                    Ok(range) => Ok((range, StatusCode::PARTIAL_CONTENT)),
+                    Err(RangeParseError::Malformed) => {
+                        Err(StatusCode::BAD_REQUEST)
+                    }
+                    Err(RangeParseError::Unsatisfiable) => {
+                        Err(StatusCode::RANGE_NOT_SATISFIABLE)
+                    }
+                }
+            }
+            None => Ok((
+                ContentRange {
+                    start: 0,
+                    end: total_size.saturating_sub(1),
+                    total_size,
+                },
+                StatusCode::OK,
+            )),
         }
     }
 
@@ -418,15 +505,6 @@ impl LocalStreamer {
             content_range,
         );
 
-        type Framed = Pin<
-            Box<
-                dyn futures_util::Stream<
-                        Item = Result<Frame<Bytes>, GatewayError>,
-                    > + Send
-                    + Sync,
-            >,
-        >;
-
         let reader_stream = match opened_file {
             Some(opened_file) => ReaderStream::from_opened_file(
                 path.clone(),
@@ -435,16 +513,49 @@ impl LocalStreamer {
             ),
             None => ReaderStream::new(path.clone(), content_range),
         };
+        let headers = Self::stream_headers(
+            &file_metadata.format,
+            &content_range,
+            status_code,
+        );
+
+        let response = Response {
+            status: status_code,
+            headers,
+            body: Self::rate_limited_body(reader_stream.into_stream(), limiter),
+        };
+
+        Ok(AppStreamResult::Stream(response))
+    }
+
+    /// Wraps a byte stream into a response body, spending `limiter`
+    /// permits per chunk unless the client is unlimited.
+    pub(crate) fn rate_limited_body<S>(
+        stream: S,
+        limiter: Arc<RateLimiter>,
+    ) -> BoxBodyType
+    where
+        S: futures_util::Stream<Item = Result<Bytes, IoError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        type Framed = Pin<
+            Box<
+                dyn futures_util::Stream<
+                        Item = Result<Frame<Bytes>, GatewayError>,
+                    > + Send
+                    + Sync,
+            >,
+        >;
 
         let stream: Framed = if limiter.skip_semaphore {
-            let s = reader_stream
-                .into_stream()
+            let s = stream
                 .map(|res| res.map(Frame::data).map_err(GatewayError::from));
             Box::pin(s)
         } else {
             let sem = limiter.semaphore.clone();
-            let s = reader_stream
-                .into_stream()
+            let s = stream
                 .and_then(move |chunk| {
                     let sem = sem.clone();
                     async move {
@@ -464,11 +575,17 @@ impl LocalStreamer {
                 .map_err(GatewayError::from);
             Box::pin(s)
         };
+        BodyExt::boxed(StreamBody::new(stream))
+    }
 
+    /// Content type, `Accept-Ranges` and length headers for a file body.
+    pub(crate) fn stream_headers(
+        format: &str,
+        content_range: &ContentRange,
+        status_code: StatusCode,
+    ) -> HeaderMap {
         let mut headers = HeaderMap::new();
-        if let Ok(content_type) =
-            get_content_type(&file_metadata.format).parse()
-        {
+        if let Ok(content_type) = get_content_type(format).parse() {
             headers.insert(header::CONTENT_TYPE, content_type);
         }
         if let Ok(accept_ranges) = "bytes".parse() {
@@ -493,14 +610,7 @@ impl LocalStreamer {
                 content_range.total_size.into(),
             );
         }
-
-        let response = Response {
-            status: status_code,
-            headers,
-            body: BodyExt::boxed(StreamBody::new(stream)),
-        };
-
-        Ok(AppStreamResult::Stream(response))
+        headers
     }
 
     /// Handles requests from specific clients that do not send a Range header by applying a default.
@@ -540,7 +650,7 @@ impl LocalStreamer {
         }
     }
 
-    fn parse_content_range(
+    pub(crate) fn parse_content_range(
         range_value: &str,
         total_size: u64,
     ) -> Result<ContentRange, RangeParseError> {
@@ -1,14 +1,21 @@
 pub mod constants;
+pub mod fallback;
 pub mod google_drive;
 pub mod google_drive_auth;
+pub mod google_drive_index;
 pub mod local_streamer;
+pub mod openlist_auth;
+pub mod probe;
 pub mod proxy_mode;
 pub mod read_stream;
 pub mod remote_streamer;
+pub mod resilience;
 pub mod response;
 pub mod result;
 pub mod service;
 pub mod session_id;
+pub mod smb;
+pub mod smb_streamer;
 pub mod source;
 pub mod stream;
 pub mod stream_relay;
@@ -0,0 +1,268 @@
+use std::{future::Future, sync::Arc, time::Instant};
+
+use crate::{
+    AppState, OPENLIST_AUTH_LOGGER_DOMAIN,
+    client::openlist::client::is_unauthorized,
+    config::backend::{BackendNode, openlist::OpenList},
+    debug_log, info_log,
+    system::SystemInfo,
+    util::Totp,
+    warn_log,
+};
+
+fn cache_key(node: &BackendNode, cfg: &OpenList) -> String {
+    let base = cfg.base_url.trim_end_matches('/').to_lowercase();
+    format!("{}|{}|{}", node.name, base, cfg.username.trim())
+}
+
+/// Token for `node`: the static `token`, or a cached login session when
+/// credentials are configured. Concurrent cold starts share one login.
+pub async fn token_for_node(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    cfg: &OpenList,
+) -> Result<String, anyhow::Error> {
+    if !cfg.credentials_configured() {
+        return Ok(cfg.token.clone());
+    }
+
+    let key = cache_key(node, cfg);
+    if let Some(token) = state.openlist_token_cache.get(&key) {
+        return Ok(token.clone());
+    }
+
+    let login_mutex = AppState::request_lock(&state.openlist_login_locks, &key);
+    let result = {
+        let wait_start = Instant::now();
+        let _login_guard = login_mutex.lock().await;
+        let lock_wait_ms = wait_start.elapsed().as_millis();
+
+        if let Some(token) = state.openlist_token_cache.get(&key) {
+            debug_log!(
+                OPENLIST_AUTH_LOGGER_DOMAIN,
+                "openlist_login_wait_hit lock_wait_ms={} node={}",
+                lock_wait_ms,
+                node.name
+            );
+            Ok(token.clone())
+        } else {
+            login(state, node, cfg).await.inspect(|token| {
+                state
+                    .openlist_token_cache
+                    .insert(key.clone(), token.clone());
+            })
+        }
+    };
+
+    AppState::cleanup_request_lock(
+        &state.openlist_login_locks,
+        &key,
+        &login_mutex,
+    );
+    result
+}
+
+async fn login(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    cfg: &OpenList,
+) -> Result<String, anyhow::Error> {
+    let otp_code = if cfg.otp_secret.trim().is_empty() {
+        String::new()
+    } else {
+        Totp::code_now(&cfg.otp_secret).ok_or_else(|| {
+            anyhow::anyhow!(
+                "openlist otp_secret for node '{}' is not valid base32",
+                node.name
+            )
+        })?
+    };
+
+    let client = state.get_open_list_client().await.clone();
+    let token = client
+        .login(
+            &cfg.base_url,
+            cfg.username.trim(),
+            &cfg.password,
+            otp_code,
+            SystemInfo::new().get_user_agent(),
+        )
+        .await?;
+    info_log!(
+        OPENLIST_AUTH_LOGGER_DOMAIN,
+        "openlist_login_success node={} username={}",
+        node.name,
+        cfg.username.trim()
+    );
+    Ok(token)
+}
+
+/// Drops `stale` from the cache unless another request already replaced it.
+pub fn invalidate(
+    state: &AppState,
+    node: &BackendNode,
+    cfg: &OpenList,
+    stale: &str,
+) {
+    let key = cache_key(node, cfg);
+    state
+        .openlist_token_cache
+        .remove_if(&key, |_, token| token == stale);
+}
+
+/// Runs `op` with the node token; on an auth rejection the session is
+/// renewed and `op` retried once. Static tokens are never retried.
+pub async fn with_token<T, F, Fut>(
+    state: &Arc<AppState>,
+    node: &BackendNode,
+    cfg: &OpenList,
+    op: F,
+) -> Result<T, anyhow::Error>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, anyhow::Error>>,
+{
+    let token = token_for_node(state, node, cfg).await?;
+    match op(token.clone()).await {
+        Err(error)
+            if is_unauthorized(&error) && cfg.credentials_configured() =>
+        {
+            warn_log!(
+                OPENLIST_AUTH_LOGGER_DOMAIN,
+                "openlist_token_rejected node={} error={}",
+                node.name,
+                error
+            );
+            invalidate(state, node, cfg, &token);
+            let token = token_for_node(state, node, cfg).await?;
+            op(token).await
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use rustls::crypto::aws_lc_rs;
+
+    use super::with_token;
+    use crate::{
+        config::backend::{BackendNode, openlist::OpenList},
+        test_support::{
+            HttpMockHandler, TestConfig, http_response, spawn_http_mock_server,
+        },
+    };
+
+    static RUSTLS_CRYPTO_INIT: Once = Once::new();
+
+    fn ensure_rustls_crypto_provider() {
+        RUSTLS_CRYPTO_INIT.call_once(|| {
+            let _ = aws_lc_rs::default_provider().install_default();
+        });
+    }
+
+    fn openlist_node(open_list: OpenList) -> BackendNode {
+        BackendNode {
+            name: "alist".into(),
+            backend_type: "OpenList".into(),
+            pattern: String::new(),
+            pattern_regex: None,
+            base_url: String::new(),
+            port: String::new(),
+            path: String::new(),
+            priority: 0,
+            proxy_mode: "redirect".into(),
+            client_speed_limit_kbs: 0,
+            client_burst_speed_kbs: 0,
+            path_rewrites: vec![],
+            anti_reverse_proxy: Default::default(),
+            path_rewriter_cache: vec![],
+            uuid: String::new(),
+            disk: None,
+            open_list: Some(open_list),
+            direct_link: None,
+            google_drive: None,
+            webdav: None,
+            smb: None,
+            resilience: None,
+        }
+    }
+
+    fn login_handler(token: &'static str) -> HttpMockHandler {
+        Box::new(move |request| {
+            Box::pin(async move {
+                assert!(request.starts_with("POST /api/auth/login "));
+                assert!(request.contains(r#""username":"admin""#));
+                http_response(
+                    200,
+                    "application/json",
+                    &format!(
+                        r#"{{"code":200,"message":"success","data":{{"token":"{token}"}}}}"#
+                    ),
+                )
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn relogs_in_once_when_token_is_rejected() {
+        ensure_rustls_crypto_provider();
+        let handlers: Vec<HttpMockHandler> = vec![
+            login_handler("expired-token"),
+            Box::new(|request| {
+                Box::pin(async move {
+                    assert!(request.contains("authorization: expired-token"));
+                    http_response(
+                        200,
+                        "application/json",
+                        r#"{"code":401,"message":"token is expired","data":null}"#,
+                    )
+                })
+            }),
+            login_handler("fresh-token"),
+            Box::new(|request| {
+                Box::pin(async move {
+                    assert!(request.contains("authorization: fresh-token"));
+                    http_response(
+                        200,
+                        "application/json",
+                        r#"{"code":200,"message":"success","data":{"raw_url":"https://cdn.example.com/a.mkv"}}"#,
+                    )
+                })
+            }),
+        ];
+        let base_url = spawn_http_mock_server(handlers).await;
+        let open_list = OpenList {
+            base_url,
+            username: "admin".into(),
+            password: "secret".into(),
+            ..OpenList::default()
+        };
+        let node = openlist_node(open_list.clone());
+        let state = TestConfig::new().app_state().await;
+        let client = state.get_open_list_client().await.clone();
+
+        let raw_url = with_token(&state, &node, &open_list, |token| {
+            client.fetch_file_path(
+                &open_list.base_url,
+                token,
+                "/movies/a.mkv",
+                "test-agent",
+            )
+        })
+        .await
+        .expect("fetch after relogin");
+
+        assert_eq!(raw_url, "https://cdn.example.com/a.mkv");
+        assert_eq!(
+            state
+                .openlist_token_cache
+                .iter()
+                .map(|entry| entry.value().clone())
+                .collect::<Vec<_>>(),
+            vec!["fresh-token".to_string()]
+        );
+    }
+}
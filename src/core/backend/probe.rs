@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use hyper::{Method, Response, StatusCode, Uri, body::Incoming, header};
+use serde::{Deserialize, Serialize};
+
+use super::{service::StreamService, stream::StreamMiddleware};
+use crate::{
+    AppState, GATEWAY_LOGGER_DOMAIN, debug_log, error_log, info_log, warn_log,
+};
+use crate::{
+    core::{
+        request::Request as AppStreamRequest, sign_decryptor::SignDecryptor,
+    },
+    gateway::{
+        chain::{Middleware, Next},
+        context::Context,
+        response::{BoxBodyType, ResponseBuilder},
+    },
+    sign::SignParams,
+    util::UriExt,
+};
+
+/// Appended to the signed stream path: `<backend_url>/probe?sign=…`.
+pub const PROBE_PATH_SUFFIX: &str = "/probe";
+
+/// Existence answer for a signed path, as resolved by the matched node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub exists: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+impl ProbeResult {
+    pub fn found(size: Option<u64>) -> Self {
+        Self { exists: true, size }
+    }
+
+    pub fn missing() -> Self {
+        Self {
+            exists: false,
+            size: None,
+        }
+    }
+}
+
+/// Builds the probe URL for a signed stream URL, keeping its query.
+pub fn probe_uri(signed_uri: &Uri) -> Option<Uri> {
+    let mut parts = signed_uri.clone().into_parts();
+    let path = signed_uri.path().trim_end_matches('/');
+    let path_and_query = match signed_uri.query() {
+        Some(query) => format!("{path}{PROBE_PATH_SUFFIX}?{query}"),
+        None => format!("{path}{PROBE_PATH_SUFFIX}"),
+    };
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// Answers `GET <stream path>/probe?sign=…` with a [`ProbeResult`] JSON body
+/// so a split frontend can check a file before handing out a redirect.
+#[derive(Clone)]
+pub struct ProbeMiddleware {
+    stream_service: Arc<dyn StreamService>,
+    state: Arc<AppState>,
+}
+
+impl ProbeMiddleware {
+    pub fn new(
+        stream_service: Arc<dyn StreamService>,
+        state: Arc<AppState>,
+    ) -> Self {
+        Self {
+            stream_service,
+            state,
+        }
+    }
+
+    fn json_response(result: &ProbeResult) -> Response<BoxBodyType> {
+        match serde_json::to_string(result) {
+            Ok(json) => ResponseBuilder::with_json(StatusCode::OK, &json),
+            Err(_) => ResponseBuilder::with_status_code(
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for ProbeMiddleware {
+    async fn handle(
+        &self,
+        ctx: Context,
+        body: Option<Incoming>,
+        next: Next,
+    ) -> Response<BoxBodyType> {
+        if !ctx.path.ends_with(PROBE_PATH_SUFFIX) {
+            return next(ctx, body).await;
+        }
+
+        let params = ctx
+            .uri
+            .query()
+            .and_then(|query| {
+                serde_urlencoded::from_str::<SignParams>(query).ok()
+            })
+            .unwrap_or_default();
+
+        if params.sign.is_empty() {
+            return next(ctx, body).await;
+        }
+
+        debug_log!(GATEWAY_LOGGER_DOMAIN, "Starting probe middleware...");
+
+        if ctx.method != Method::GET {
+            warn_log!(
+                GATEWAY_LOGGER_DOMAIN,
+                "Signed probe rejected method {:?} (only GET allowed)",
+                ctx.method,
+            );
+            return ResponseBuilder::with_status_code(
+                StatusCode::METHOD_NOT_ALLOWED,
+            );
+        }
+
+        let sign =
+            match SignDecryptor::decrypt(&params.sign, &params, &self.state)
+                .await
+            {
+                Ok(sign) => sign,
+                Err(e) => {
+                    error_log!(
+                        GATEWAY_LOGGER_DOMAIN,
+                        "probe_sign_decrypt_failed error={:?}",
+                        e
+                    );
+                    return ResponseBuilder::with_status_code(
+                        StatusCode::BAD_REQUEST,
+                    );
+                }
+            };
+
+        if !sign.is_valid() {
+            return ResponseBuilder::with_status_code(StatusCode::GONE);
+        }
+
+        let Some(sign_uri) = sign.uri.clone() else {
+            return ResponseBuilder::with_status_code(StatusCode::BAD_REQUEST);
+        };
+        let file_path = Uri::to_path_or_url_string(&sign_uri);
+
+        let backend_nodes = self.state.get_backend_nodes().await;
+        let Some(node) =
+            StreamMiddleware::find_matching_node(&backend_nodes, &file_path)
+        else {
+            info_log!(
+                GATEWAY_LOGGER_DOMAIN,
+                "probe_no_matching_node path={}",
+                file_path
+            );
+            return Self::json_response(&ProbeResult::missing());
+        };
+
+        let host = ctx
+            .headers
+            .get(header::HOST)
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("");
+        if node.anti_reverse_proxy.is_need_anti(host) {
+            return ResponseBuilder::with_status_code(StatusCode::FORBIDDEN);
+        }
+
+        let request = AppStreamRequest {
+            uri: ctx.uri,
+            original_headers: ctx.headers,
+            request_start_time: ctx.start_time,
+            node: Some(node.clone()),
+            sign: Some(sign),
+        };
+
+        match self.stream_service.probe(request).await {
+            Ok(result) => {
+                info_log!(
+                    GATEWAY_LOGGER_DOMAIN,
+                    "probe_complete node={} exists={} size={:?} path={}",
+                    node.name,
+                    result.exists,
+                    result.size,
+                    file_path
+                );
+                Self::json_response(&result)
+            }
+            Err(status_code) => ResponseBuilder::with_status_code(status_code),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Middleware> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::Uri;
+
+    use super::{ProbeResult, probe_uri};
+
+    #[test]
+    fn probe_uri_appends_suffix_and_keeps_query() {
+        let signed: Uri =
+            "https://stream.example.com/stream?sign=abc&session_id=s"
+                .parse()
+                .unwrap_or_default();
+
+        let probe = probe_uri(&signed).map(|uri| uri.to_string());
+
+        assert_eq!(
+            probe.as_deref(),
+            Some(
+                "https://stream.example.com/stream/probe?sign=abc&session_id=s"
+            )
+        );
+    }
+
+    #[test]
+    fn probe_result_omits_unknown_size() {
+        let json = serde_json::to_string(&ProbeResult::found(None))
+            .unwrap_or_default();
+
+        assert_eq!(json, r#"{"exists":true}"#);
+        let parsed: Option<ProbeResult> =
+            serde_json::from_str(r#"{"exists":false}"#).ok();
+        assert_eq!(parsed, Some(ProbeResult::missing()));
+    }
+}
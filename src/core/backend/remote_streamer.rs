@@ -1,15 +1,22 @@
 use std::sync::Arc;
 
+use bytes::Bytes;
 use chrono::Duration;
 use http_body_util::BodyExt;
 use hyper::{
-    HeaderMap, Response as HyperResponse, StatusCode, Uri, body::Incoming,
-    header,
+    HeaderMap, Method, Response as HyperResponse, StatusCode, Uri,
+    body::Incoming, header,
 };
 
 use super::{
-    google_drive_auth, response::Response, result::Result as AppStreamResult,
-    upstream_proxy, webdav::BACKEND_TYPE as WEBDAV_BACKEND_TYPE, webdav_auth,
+    fallback::{FallbackReason, FallbackResponder, StreamFailure},
+    google_drive_auth,
+    resilience::UpstreamPolicy,
+    response::Response,
+    result::Result as AppStreamResult,
+    upstream_proxy,
+    webdav::BACKEND_TYPE as WEBDAV_BACKEND_TYPE,
+    webdav_auth,
 };
 use crate::{
     AppState, REMOTE_STREAMER_LOGGER_DOMAIN, config::backend::BackendNode,
@@ -50,9 +57,31 @@ fn webdav_needs_auth_retry(node: &BackendNode, status: StatusCode) -> bool {
             .unwrap_or(false)
 }
 
+/// Error bodies are only read for a reason code; anything past this is
+/// left unread and the connection dropped with it.
+const ERROR_BODY_LIMIT: usize = 16 * 1024;
+
 async fn drain_incoming(body: Incoming) -> Result<(), GatewayError> {
-    let _ = BodyExt::collect(body).await?;
-    Ok(())
+    collect_incoming(body).await.map(|_| ())
+}
+
+/// First [`ERROR_BODY_LIMIT`] bytes of an upstream error body.
+async fn collect_incoming<B>(mut body: B) -> Result<Bytes, GatewayError>
+where
+    B: hyper::body::Body<Data = Bytes> + Unpin,
+    GatewayError: From<B::Error>,
+{
+    let mut collected = Vec::new();
+    while collected.len() < ERROR_BODY_LIMIT {
+        let Some(frame) = body.frame().await else {
+            break;
+        };
+        if let Ok(data) = frame?.into_data() {
+            let room = ERROR_BODY_LIMIT - collected.len();
+            collected.extend_from_slice(&data[..data.len().min(room)]);
+        }
+    }
+    Ok(Bytes::from(collected))
 }
 
 pub(crate) struct RemoteStreamer;
@@ -72,6 +101,7 @@ impl RemoteStreamer {
             node,
             stream_session_id,
         } = params;
+        let policy = UpstreamPolicy::for_node(node);
 
         if !client_headers.contains_key(header::RANGE) {
             error_log!(
@@ -86,22 +116,37 @@ impl RemoteStreamer {
 
         let extra_ref = extra_upstream_headers.as_ref();
 
-        let upstream_resp = upstream_proxy::forward_get(
+        let upstream_resp = match upstream_proxy::forward_get(
             url.clone(),
             client_headers,
             &user_agent,
             extra_ref,
             Some(stream_session_id.as_str()),
+            &policy,
         )
         .await
-        .map_err(|e| {
-            error_log!(
-                REMOTE_STREAMER_LOGGER_DOMAIN,
-                "Upstream forward failed: {}",
-                e
-            );
-            StatusCode::BAD_GATEWAY
-        })?;
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                error_log!(
+                    REMOTE_STREAMER_LOGGER_DOMAIN,
+                    "Upstream forward failed: {}",
+                    e
+                );
+                state
+                    .circuit_breakers
+                    .record_failure(&node.name, &policy, &e);
+                return FallbackResponder::respond(
+                    &state,
+                    StreamFailure::new(
+                        FallbackReason::UpstreamDown,
+                        StatusCode::BAD_GATEWAY,
+                    ),
+                    client_headers,
+                )
+                .await;
+            }
+        };
 
         let upstream_resp = Self::maybe_retry_webdav_401(
             state.clone(),
@@ -113,45 +158,117 @@ impl RemoteStreamer {
             stream_session_id.as_str(),
         )
         .await?;
-        let upstream_resp = Self::maybe_retry_google_drive_401(
+        let mut upstream_resp = Self::maybe_retry_google_drive_401(
             state.clone(),
             node,
             upstream_resp,
-            url,
+            url.clone(),
             client_headers,
             &user_agent,
             stream_session_id.as_str(),
         )
         .await?;
+        let mut used_headers = extra_upstream_headers.clone();
 
-        let status = upstream_resp.status();
-        if !status.is_success() {
+        while !upstream_resp.status().is_success() {
+            let status = upstream_resp.status();
             error_log!(
                 REMOTE_STREAMER_LOGGER_DOMAIN,
                 "Upstream returned error status: {}",
                 status
             );
             let (_, body) = upstream_resp.into_parts();
-            let _ = drain_incoming(body).await;
+            let body = collect_incoming(body).await.unwrap_or_default();
             if status == StatusCode::UNAUTHORIZED && is_webdav_node(node) {
                 return Err(StatusCode::UNAUTHORIZED);
             }
-            if is_google_drive_node(node) {
-                return Err(StatusCode::SERVICE_UNAVAILABLE);
+            if status.is_server_error() {
+                state.circuit_breakers.record_failure(
+                    &node.name,
+                    &policy,
+                    format_args!("upstream status {}", status.as_u16()),
+                );
+            } else {
+                state.circuit_breakers.record_success(&node.name);
+            }
+            let reason =
+                FallbackReason::from_upstream_status(node, status, &body);
+            if reason == FallbackReason::QuotaExceeded {
+                if let Some(rotated) =
+                    google_drive_auth::rotate_after_quota_error(
+                        &state,
+                        node,
+                        used_headers.as_ref(),
+                        &body,
+                    )
+                    .await
+                {
+                    info_log!(
+                        REMOTE_STREAMER_LOGGER_DOMAIN,
+                        "google_drive_quota_retry node={}{}",
+                        node.name,
+                        upstream_proxy::stream_session_log_suffix(Some(
+                            stream_session_id.as_str()
+                        )),
+                    );
+                    upstream_resp = upstream_proxy::forward_get(
+                        url.clone(),
+                        client_headers,
+                        &user_agent,
+                        Some(&rotated),
+                        Some(stream_session_id.as_str()),
+                        &policy,
+                    )
+                    .await
+                    .map_err(|e| {
+                        error_log!(
+                            REMOTE_STREAMER_LOGGER_DOMAIN,
+                            "googleDrive quota retry failed: {}",
+                            e
+                        );
+                        StatusCode::BAD_GATEWAY
+                    })?;
+                    used_headers = Some(rotated);
+                    continue;
+                }
             }
-            return Err(StatusCode::BAD_GATEWAY);
+            let status_code = if is_google_drive_node(node) {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::BAD_GATEWAY
+            };
+            info_log!(
+                REMOTE_STREAMER_LOGGER_DOMAIN,
+                "upstream_failure_classified node={} status={} reason={}{}",
+                node.name,
+                status.as_u16(),
+                reason,
+                upstream_proxy::stream_session_log_suffix(Some(
+                    stream_session_id.as_str()
+                )),
+            );
+            return FallbackResponder::respond(
+                &state,
+                StreamFailure::new(reason, status_code),
+                client_headers,
+            )
+            .await;
         }
 
+        state.circuit_breakers.record_success(&node.name);
         let (response_status, response_headers, body) =
-            upstream_proxy::map_upstream_to_stream_response(upstream_resp)
-                .map_err(|e| {
-                    error_log!(
-                        REMOTE_STREAMER_LOGGER_DOMAIN,
-                        "Map upstream response failed: {}",
-                        e
-                    );
-                    StatusCode::BAD_GATEWAY
-                })?;
+            upstream_proxy::map_upstream_to_stream_response(
+                upstream_resp,
+                policy.idle_timeout,
+            )
+            .map_err(|e| {
+                error_log!(
+                    REMOTE_STREAMER_LOGGER_DOMAIN,
+                    "Map upstream response failed: {}",
+                    e
+                );
+                StatusCode::BAD_GATEWAY
+            })?;
 
         Ok(AppStreamResult::Stream(Response {
             status: response_status,
@@ -207,6 +324,7 @@ impl RemoteStreamer {
             &state.webdav_auth_cache,
             &state.webdav_auth_probe_locks,
             node,
+            &Method::GET,
             &url,
             cfg,
             Some(headers),
@@ -233,6 +351,7 @@ impl RemoteStreamer {
             user_agent,
             Some(&refreshed),
             Some(stream_session_id),
+            &UpstreamPolicy::for_node(node),
         )
         .await
         .map_err(|e| {
@@ -306,6 +425,7 @@ impl RemoteStreamer {
             user_agent,
             Some(&refreshed),
             Some(stream_session_id),
+            &UpstreamPolicy::for_node(node),
         )
         .await
         .map_err(|e| {
@@ -326,18 +446,23 @@ mod tests {
         sync::{Arc, Once},
     };
 
-    use hyper::{HeaderMap, StatusCode, Uri, header};
+    use bytes::Bytes;
+    use http_body_util::StreamBody;
+    use hyper::{HeaderMap, StatusCode, Uri, body::Frame, header};
     use rustls::crypto::aws_lc_rs;
 
-    use super::{RemoteStreamParams, RemoteStreamer};
+    use super::{
+        ERROR_BODY_LIMIT, RemoteStreamParams, RemoteStreamer, collect_incoming,
+    };
     use crate::{
         AppState,
         client::GoogleDriveClient,
         config::{
-            backend::{BackendNode, GoogleDriveConfig},
+            backend::{BackendNode, GoogleDriveConfig, GoogleDriveRoot},
             core::{finish_raw_config, parse_raw_config_str},
         },
         core::backend::result::Result as AppStreamResult,
+        gateway::error::Error as GatewayError,
         oauthutil::OAuthToken,
         test_support::{
             HttpMockHandler, http_response, spawn_http_mock_server,
@@ -410,6 +535,12 @@ host = ""
                 client_secret: "client-secret".to_string(),
                 drive_id: "drive-id".to_string(),
                 drive_name: "pilipili".to_string(),
+                root: GoogleDriveRoot::SharedDrive,
+                root_folder_id: String::new(),
+                service_account_files: vec![],
+                service_account_subject: String::new(),
+                folder_index: false,
+                token_secret: String::new(),
                 access_token: "access-token".to_string(),
                 refresh_token: "refresh-token".to_string(),
                 token: Some(OAuthToken {
@@ -422,6 +553,8 @@ host = ""
                 }),
             }),
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
@@ -442,6 +575,21 @@ host = ""
         ))
     }
 
+    #[tokio::test]
+    async fn error_body_read_stops_at_the_limit() {
+        let endless = futures_util::stream::repeat_with(|| {
+            Ok::<_, GatewayError>(Frame::data(Bytes::from_static(
+                &[b'x'; 1000],
+            )))
+        });
+
+        let body = collect_incoming(StreamBody::new(endless))
+            .await
+            .expect("body");
+
+        assert_eq!(body.len(), ERROR_BODY_LIMIT);
+    }
+
     #[tokio::test]
     async fn stream_retries_google_drive_proxy_after_401() {
         ensure_rustls_crypto_provider();
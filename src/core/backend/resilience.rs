@@ -0,0 +1,474 @@
+//! Per-node upstream timeouts, jittered retries for idempotent metadata
+//! calls and a circuit breaker per backend node.
+
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    RESILIENCE_LOGGER_DOMAIN,
+    config::backend::{BackendNode, ResilienceConfig},
+    gateway::error::Error as GatewayError,
+    info_log, warn_log,
+};
+
+/// Runtime form of [`ResilienceConfig`] for one node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpstreamPolicy {
+    pub connect_timeout: Duration,
+    pub first_byte_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub retry_attempts: u32,
+    pub retry_base_delay: Duration,
+    pub breaker_failure_threshold: u32,
+    pub breaker_open: Duration,
+}
+
+impl UpstreamPolicy {
+    pub fn for_node(node: &BackendNode) -> Self {
+        node.resilience
+            .as_ref()
+            .map(Self::from_config)
+            .unwrap_or_default()
+    }
+
+    pub fn from_config(cfg: &ResilienceConfig) -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(
+                cfg.connect_timeout_secs.max(1),
+            ),
+            first_byte_timeout: Duration::from_secs(
+                cfg.first_byte_timeout_secs.max(1),
+            ),
+            idle_timeout: Duration::from_secs(cfg.idle_timeout_secs.max(1)),
+            retry_attempts: cfg.retry_attempts,
+            retry_base_delay: Duration::from_millis(cfg.retry_base_delay_ms),
+            breaker_failure_threshold: cfg.breaker_failure_threshold,
+            breaker_open: Duration::from_secs(cfg.breaker_open_secs.max(1)),
+        }
+    }
+
+    /// Bound on one request until its response headers arrive.
+    pub fn request_timeout(&self) -> Duration {
+        self.connect_timeout + self.first_byte_timeout
+    }
+
+    /// Full jitter: uniform in `[0, base * 2^attempt]`, capped at 10s.
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .retry_base_delay
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(Duration::from_secs(10));
+        if ceiling.is_zero() {
+            return ceiling;
+        }
+        let millis = rand::thread_rng().gen_range(0..=ceiling.as_millis());
+        Duration::from_millis(millis as u64)
+    }
+}
+
+impl Default for UpstreamPolicy {
+    fn default() -> Self {
+        Self::from_config(&ResilienceConfig::default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("upstream did not answer within {0:?}")]
+pub struct UpstreamTimeout(pub Duration);
+
+impl From<UpstreamTimeout> for GatewayError {
+    fn from(timeout: UpstreamTimeout) -> Self {
+        GatewayError::IoError(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            timeout,
+        ))
+    }
+}
+
+/// Runs an idempotent call with the node's request timeout, retrying
+/// transient failures up to `retry_attempts` times with jittered backoff.
+pub async fn retry_idempotent<T, E, F, Fut>(
+    policy: &UpstreamPolicy,
+    label: &str,
+    node_name: &str,
+    is_transient: impl Fn(&E) -> bool,
+    mut op: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: From<UpstreamTimeout> + Display,
+{
+    let mut attempt = 0;
+    loop {
+        let outcome = match tokio::time::timeout(policy.request_timeout(), op())
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(_) => Err(E::from(UpstreamTimeout(policy.request_timeout()))),
+        };
+        match outcome {
+            Ok(value) => return Ok(value),
+            Err(error)
+                if attempt < policy.retry_attempts && is_transient(&error) =>
+            {
+                let delay = policy.retry_delay(attempt);
+                attempt += 1;
+                warn_log!(
+                    RESILIENCE_LOGGER_DOMAIN,
+                    "upstream_retry call={} node={} attempt={} delay_ms={} error={}",
+                    label,
+                    node_name,
+                    attempt,
+                    delay.as_millis(),
+                    error
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Timeouts and transport failures from the reqwest-based API clients.
+pub fn is_transient_anyhow(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if cause.is::<UpstreamTimeout>() || cause.is::<std::io::Error>() {
+            return true;
+        }
+        cause.downcast_ref::<reqwest::Error>().is_some_and(|e| {
+            e.is_timeout()
+                || e.is_connect()
+                || e.is_request()
+                || e.status().is_some_and(|s| s.is_server_error())
+        })
+    })
+}
+
+/// Everything but malformed requests is worth another try.
+pub fn is_transient_gateway(error: &GatewayError) -> bool {
+    !matches!(
+        error,
+        GatewayError::InvalidUri(_) | GatewayError::HttpError(_)
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct BreakerEntry {
+    state: BreakerState,
+    consecutive_failures: u32,
+    total_failures: u64,
+    short_circuits: u64,
+    opened_at: Option<Instant>,
+    opened_at_utc: Option<DateTime<Utc>>,
+    open_for: Duration,
+    trial_started: Option<Instant>,
+    last_failure: Option<String>,
+}
+
+impl Default for BreakerEntry {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            total_failures: 0,
+            short_circuits: 0,
+            opened_at: None,
+            opened_at_utc: None,
+            open_for: Duration::ZERO,
+            trial_started: None,
+            last_failure: None,
+        }
+    }
+}
+
+/// Breaker state of one node as shown in the admin UI.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakerSnapshot {
+    pub node: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub total_failures: u64,
+    pub short_circuits: u64,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub retry_at: Option<DateTime<Utc>>,
+    pub last_failure: Option<String>,
+}
+
+/// Circuit breakers keyed by node name. A node gets an entry on its first
+/// upstream failure; nodes that never fail are never short-circuited.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    nodes: DashMap<String, BreakerEntry>,
+}
+
+impl CircuitBreakers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `false` while the node's breaker is open. Once `breaker_open` has
+    /// passed, a single trial request goes through in half-open state.
+    pub fn try_acquire(&self, node: &str, policy: &UpstreamPolicy) -> bool {
+        if policy.breaker_failure_threshold == 0 {
+            return true;
+        }
+        let Some(mut entry) = self.nodes.get_mut(node) else {
+            return true;
+        };
+        let now = Instant::now();
+        let allowed = match entry.state {
+            BreakerState::Closed => true,
+            BreakerState::Open => entry.opened_at.is_none_or(|opened| {
+                now.duration_since(opened) >= entry.open_for
+            }),
+            // A trial that never reported back must not pin the breaker.
+            BreakerState::HalfOpen => {
+                entry.trial_started.is_none_or(|started| {
+                    now.duration_since(started) >= entry.open_for
+                })
+            }
+        };
+        if !allowed {
+            entry.short_circuits += 1;
+            return false;
+        }
+        if entry.state != BreakerState::Closed {
+            entry.state = BreakerState::HalfOpen;
+            entry.trial_started = Some(now);
+        }
+        true
+    }
+
+    pub fn record_success(&self, node: &str) {
+        let Some(mut entry) = self.nodes.get_mut(node) else {
+            return;
+        };
+        if entry.state != BreakerState::Closed {
+            info_log!(
+                RESILIENCE_LOGGER_DOMAIN,
+                "circuit_closed node={} after_failures={}",
+                node,
+                entry.consecutive_failures
+            );
+        }
+        entry.state = BreakerState::Closed;
+        entry.consecutive_failures = 0;
+        entry.opened_at = None;
+        entry.opened_at_utc = None;
+        entry.trial_started = None;
+    }
+
+    pub fn record_failure(
+        &self,
+        node: &str,
+        policy: &UpstreamPolicy,
+        reason: impl Display,
+    ) {
+        if policy.breaker_failure_threshold == 0 {
+            return;
+        }
+        let mut entry = self.nodes.entry(node.to_string()).or_default();
+        entry.consecutive_failures += 1;
+        entry.total_failures += 1;
+        entry.last_failure = Some(reason.to_string());
+        let trips = match entry.state {
+            BreakerState::HalfOpen => true,
+            BreakerState::Closed => {
+                entry.consecutive_failures >= policy.breaker_failure_threshold
+            }
+            BreakerState::Open => false,
+        };
+        if trips {
+            warn_log!(
+                RESILIENCE_LOGGER_DOMAIN,
+                "circuit_opened node={} consecutive_failures={} open_secs={} reason={}",
+                node,
+                entry.consecutive_failures,
+                policy.breaker_open.as_secs(),
+                reason
+            );
+            entry.state = BreakerState::Open;
+            entry.opened_at = Some(Instant::now());
+            entry.opened_at_utc = Some(Utc::now());
+            entry.open_for = policy.breaker_open;
+            entry.trial_started = None;
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<BreakerSnapshot> {
+        let mut nodes: Vec<BreakerSnapshot> = self
+            .nodes
+            .iter()
+            .map(|item| {
+                let entry = item.value();
+                let retry_at = entry.opened_at_utc.and_then(|opened| {
+                    chrono::Duration::from_std(entry.open_for)
+                        .ok()
+                        .map(|open_for| opened + open_for)
+                });
+                BreakerSnapshot {
+                    node: item.key().clone(),
+                    state: entry.state,
+                    consecutive_failures: entry.consecutive_failures,
+                    total_failures: entry.total_failures,
+                    short_circuits: entry.short_circuits,
+                    opened_at: entry.opened_at_utc,
+                    retry_at,
+                    last_failure: entry.last_failure.clone(),
+                }
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        nodes
+    }
+}
+
+static RUNTIME_BREAKERS: OnceLock<Arc<CircuitBreakers>> = OnceLock::new();
+
+/// Makes the stream runtime's breakers readable by the web studio when
+/// both run in one process (`embystream run --web`).
+pub fn publish_runtime_breakers(breakers: Arc<CircuitBreakers>) {
+    let _ = RUNTIME_BREAKERS.set(breakers);
+}
+
+pub fn runtime_breakers() -> Option<Arc<CircuitBreakers>> {
+    RUNTIME_BREAKERS.get().cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn policy(threshold: u32, open_ms: u64) -> UpstreamPolicy {
+        UpstreamPolicy {
+            retry_base_delay: Duration::from_millis(1),
+            breaker_failure_threshold: threshold,
+            breaker_open: Duration::from_millis(open_ms),
+            ..UpstreamPolicy::default()
+        }
+    }
+
+    #[test]
+    fn breaker_opens_after_threshold_and_half_opens_after_cooldown() {
+        let breakers = CircuitBreakers::new();
+        let policy = policy(2, 40);
+
+        assert!(breakers.try_acquire("nas", &policy));
+        breakers.record_failure("nas", &policy, "connect refused");
+        assert!(breakers.try_acquire("nas", &policy));
+        breakers.record_failure("nas", &policy, "connect refused");
+        assert!(!breakers.try_acquire("nas", &policy));
+
+        let snapshot = breakers.snapshot();
+        assert_eq!(snapshot[0].state, BreakerState::Open);
+        assert_eq!(snapshot[0].short_circuits, 1);
+        assert!(snapshot[0].retry_at.is_some());
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(breakers.try_acquire("nas", &policy));
+        assert!(!breakers.try_acquire("nas", &policy));
+        breakers.record_failure("nas", &policy, "timeout");
+        assert_eq!(breakers.snapshot()[0].state, BreakerState::Open);
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(breakers.try_acquire("nas", &policy));
+        breakers.record_success("nas");
+        assert_eq!(breakers.snapshot()[0].state, BreakerState::Closed);
+        assert!(breakers.try_acquire("nas", &policy));
+    }
+
+    #[test]
+    fn zero_threshold_disables_breaker() {
+        let breakers = CircuitBreakers::new();
+        let policy = policy(0, 1000);
+        for _ in 0..10 {
+            breakers.record_failure("nas", &policy, "down");
+        }
+        assert!(breakers.try_acquire("nas", &policy));
+        assert!(breakers.snapshot().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_stops_on_success_and_skips_permanent_errors() {
+        let policy = policy(5, 1000);
+        let calls = AtomicU32::new(0);
+        let result: Result<u32, anyhow::Error> = retry_idempotent(
+            &policy,
+            "test",
+            "nas",
+            is_transient_anyhow,
+            || {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if call == 0 {
+                        Err(anyhow::Error::new(std::io::Error::other("reset")))
+                    } else {
+                        Ok(call)
+                    }
+                }
+            },
+        )
+        .await;
+        assert_eq!(result.expect("second attempt"), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<u32, anyhow::Error> = retry_idempotent(
+            &policy,
+            "test",
+            "nas",
+            is_transient_anyhow,
+            || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(anyhow::anyhow!("object not found")) }
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retry_times_out_hung_calls() {
+        let policy = UpstreamPolicy {
+            connect_timeout: Duration::from_millis(10),
+            first_byte_timeout: Duration::from_millis(10),
+            retry_attempts: 1,
+            ..policy(5, 1000)
+        };
+        let calls = AtomicU32::new(0);
+        let result: Result<(), anyhow::Error> = retry_idempotent(
+            &policy,
+            "test",
+            "nas",
+            is_transient_anyhow,
+            || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                std::future::pending()
+            },
+        )
+        .await;
+        let error = result.expect_err("timed out");
+        assert!(error.is::<UpstreamTimeout>());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
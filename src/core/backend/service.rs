@@ -1,8 +1,12 @@
-use std::{borrow::Cow, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    borrow::Cow, collections::HashMap, path::PathBuf, sync::Arc, time::Instant,
+};
 
 use async_trait::async_trait;
 use chrono::Duration;
-use hyper::{HeaderMap, StatusCode, Uri, header};
+use hyper::{
+    HeaderMap, Method, StatusCode, Uri, header, http::uri::PathAndQuery,
+};
 
 use tokio::sync::Mutex as TokioMutex;
 
@@ -11,17 +15,28 @@ use super::{
         DISK_BACKEND_TYPE, STREAM_RELAY_BACKEND_TYPE,
         backend_base_url_is_empty, backend_base_url_is_local_host,
     },
-    google_drive, google_drive_auth,
+    fallback::{FallbackReason, FallbackResponder, StreamFailure},
+    google_drive, google_drive_auth, google_drive_index,
     local_streamer::LocalStreamer,
+    openlist_auth,
+    probe::ProbeResult,
     proxy_mode::ProxyMode,
     remote_streamer::{RemoteStreamParams, RemoteStreamer},
+    resilience::{self, UpstreamPolicy},
     result::Result as AppStreamResult,
     session_id::generate_stream_session_id,
+    smb::{self, SmbTarget},
+    smb_streamer::SmbStreamer,
     source::Source,
-    upstream_proxy, webdav, webdav_auth,
+    upstream_proxy,
+    webdav::{self, WebDavEntry},
+    webdav_auth,
 };
 use crate::backend::types::ClientInfo;
-use crate::client::google_drive::GoogleDriveApiError;
+use crate::cache::GeneralCache;
+use crate::client::google_drive::{
+    GoogleDriveApiError, GoogleDriveResolvedFile,
+};
 use crate::config::backend::BackendNode;
 use crate::core::redirect_info::{AccelRedirectInfo, RedirectInfo};
 use crate::{
@@ -29,13 +44,37 @@ use crate::{
 };
 use crate::{
     core::{
-        error::Error as AppStreamError, request::Request as AppStreamRequest,
+        error::Error as AppStreamError, frontend::strm,
+        request::Request as AppStreamRequest, sign::Sign,
     },
     sign::SignParams,
     system::SystemInfo,
     util::{Privacy, StringUtil, UriExt},
 };
 
+/// Where OpenList resolved a signed path to, plus the request headers its
+/// `fs/link` asked for when the node proxies the stream.
+#[derive(Debug, Clone)]
+struct OpenListLink {
+    uri: Uri,
+    headers: Option<HeaderMap>,
+    expires_at: Option<Instant>,
+}
+
+impl OpenListLink {
+    fn passthrough(uri: &Uri) -> Self {
+        Self {
+            uri: uri.clone(),
+            headers: None,
+            expires_at: None,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| Instant::now() >= at)
+    }
+}
+
 /// Trait for handling streaming requests
 ///
 /// Implementations of this trait process incoming streaming requests,
@@ -58,6 +97,18 @@ pub trait StreamService: Send + Sync {
         &self,
         request: AppStreamRequest,
     ) -> Result<AppStreamResult, StatusCode>;
+
+    /// Check whether the signed path exists on the matched node without
+    /// streaming it (Disk stat, WebDav PROPFIND, OpenList fs/get or
+    /// googleDrive lookup).
+    ///
+    /// # Returns
+    /// - `Ok(ProbeResult)` when the node answered, found or not
+    /// - `Err(StatusCode)` when the node could not be asked
+    async fn probe(
+        &self,
+        request: AppStreamRequest,
+    ) -> Result<ProbeResult, StatusCode>;
 }
 
 /// Main streaming service implementation
@@ -103,7 +154,10 @@ impl AppStreamService {
         let rewrite_ms = rewrite_start.elapsed().as_millis();
 
         let openlist_start = Instant::now();
-        uri = self.fetch_remote_uri_if_openlist(&uri, request).await?;
+        let openlist_link =
+            self.fetch_remote_uri_if_openlist(&uri, request).await?;
+        uri = openlist_link.uri;
+        let openlist_headers = openlist_link.headers;
         let openlist_ms = openlist_start.elapsed().as_millis();
 
         let device_id = params.device_id;
@@ -119,6 +173,7 @@ impl AppStreamService {
         let is_local_uri = Uri::is_local(&uri);
         let is_webdav_node = Self::is_webdav_node(node);
         let is_google_drive_node = Self::is_google_drive_node(node);
+        let is_smb_node = Self::is_smb_node(node);
 
         debug_log!(
             STREAM_LOGGER_DOMAIN,
@@ -140,6 +195,7 @@ impl AppStreamService {
 
         let result = if is_webdav_node && is_local_uri {
             let path_str = Uri::to_path_or_url_string(&uri);
+            self.ensure_webdav_file_exists(node, &path_str).await?;
             if proxy_mode == ProxyMode::AccelRedirect {
                 let node_uuid = Self::webdav_accel_redirect_node_uuid(node)?;
                 let info = Self::build_webdav_accel_redirect_info(
@@ -180,15 +236,27 @@ impl AppStreamService {
                     AppStreamError::InvalidUri
                 })?;
             Ok(resolved)
+        } else if is_smb_node && is_local_uri {
+            let path = Uri::to_path_or_url_string(&uri);
+            debug_log!(
+                STREAM_LOGGER_DOMAIN,
+                "Routing to SMB share path {}",
+                path
+            );
+            Ok(Source::Smb { path, device_id })
         } else if !is_local_uri {
             debug_log!(STREAM_LOGGER_DOMAIN, "URI is already remote: {}", uri);
             Ok(Source::Remote {
                 uri,
                 mode: proxy_mode,
-                extra_upstream_headers: None,
+                extra_upstream_headers: Self::remote_upstream_headers(
+                    sign,
+                    proxy_mode,
+                    openlist_headers,
+                ),
             })
         } else {
-            if is_webdav_node || is_google_drive_node {
+            if is_webdav_node || is_google_drive_node || is_smb_node {
                 error_log!(
                     STREAM_LOGGER_DOMAIN,
                     "special_backend_local_fallback_blocked node={} backend_type={} \
@@ -429,6 +497,11 @@ impl AppStreamService {
             || node.backend_type.eq_ignore_ascii_case(webdav::BACKEND_TYPE)
     }
 
+    fn is_smb_node(node: &BackendNode) -> bool {
+        node.smb.is_some()
+            || node.backend_type.eq_ignore_ascii_case(smb::BACKEND_TYPE)
+    }
+
     fn is_google_drive_node(node: &BackendNode) -> bool {
         node.google_drive.is_some()
             || node
@@ -440,7 +513,7 @@ impl AppStreamService {
         &self,
         uri: &Uri,
         request: &AppStreamRequest,
-    ) -> Result<Uri, AppStreamError> {
+    ) -> Result<OpenListLink, AppStreamError> {
         let timer = Instant::now();
 
         if !Uri::is_local(uri) {
@@ -450,7 +523,7 @@ impl AppStreamService {
                 skipping backend processing for remote URI: {:?}",
                 uri
             );
-            return Ok(uri.clone());
+            return Ok(OpenListLink::passthrough(uri));
         }
 
         let user_agent = request.user_agent();
@@ -464,7 +537,9 @@ impl AppStreamService {
             Self::open_list_cache_key(&node.uuid, uri, &openlist_ua);
 
         let cache = self.state.get_open_list_cache().await;
-        if let Some(cached_uri) = cache.get(&open_list_cache_key) {
+        if let Some(cached) =
+            Self::cached_open_list_link(cache, &open_list_cache_key)
+        {
             let elapsed_ms = timer.elapsed().as_millis();
             debug_log!(
                 STREAM_LOGGER_DOMAIN,
@@ -472,9 +547,9 @@ impl AppStreamService {
                 elapsed_ms,
                 open_list_cache_key,
                 node.name,
-                cached_uri
+                cached.uri
             );
-            return Ok(cached_uri);
+            return Ok(cached);
         }
 
         let openlist_config = match &node.open_list {
@@ -486,7 +561,7 @@ impl AppStreamService {
                     "openlist_skip_no_config elapsed_ms={}",
                     elapsed_ms
                 );
-                return Ok(uri.clone());
+                return Ok(OpenListLink::passthrough(uri));
             }
         };
 
@@ -496,7 +571,9 @@ impl AppStreamService {
             let _probe_guard = probe_mutex.lock().await;
             let lock_wait_ms = wait_start.elapsed().as_millis();
 
-            if let Some(cached_uri) = cache.get(&open_list_cache_key) {
+            if let Some(cached) =
+                Self::cached_open_list_link(cache, &open_list_cache_key)
+            {
                 info_log!(
                     STREAM_LOGGER_DOMAIN,
                     "openlist_inflight_wait_hit lock_wait_ms={} key={} node={} \
@@ -504,9 +581,9 @@ impl AppStreamService {
                     lock_wait_ms,
                     open_list_cache_key,
                     node.name,
-                    cached_uri
+                    cached.uri
                 );
-                Ok(cached_uri)
+                Ok(cached)
             } else {
                 debug_log!(
                     STREAM_LOGGER_DOMAIN,
@@ -527,19 +604,71 @@ impl AppStreamService {
                 let openlist_client =
                     self.state.get_open_list_client().await.clone();
 
-                let result = openlist_client
-                    .fetch_file_path(
-                        &openlist_config.base_url,
-                        &openlist_config.token,
-                        path,
-                        openlist_ua.clone(),
+                // Proxying needs the storage driver's own request headers,
+                // which only `fs/link` returns; redirects keep `fs/get`.
+                let use_fs_link =
+                    Self::effective_proxy_mode(node) == ProxyMode::Proxy;
+                let policy = UpstreamPolicy::for_node(node);
+                let result = if use_fs_link {
+                    resilience::retry_idempotent(
+                        &policy,
+                        "openlist_fs_link",
+                        &node.name,
+                        resilience::is_transient_anyhow,
+                        || {
+                            openlist_auth::with_token(
+                                &self.state,
+                                node,
+                                openlist_config,
+                                |token| {
+                                    openlist_client.fetch_file_link(
+                                        &openlist_config.base_url,
+                                        token,
+                                        path.clone(),
+                                        openlist_ua.clone(),
+                                    )
+                                },
+                            )
+                        },
+                    )
+                    .await
+                    .and_then(|link| {
+                        let url = link.get_url();
+                        if url.is_empty() {
+                            anyhow::bail!("openlist fs/link returned no url");
+                        }
+                        Ok((url, link.get_header(), link.get_expiration()))
+                    })
+                } else {
+                    resilience::retry_idempotent(
+                        &policy,
+                        "openlist_fs_get",
+                        &node.name,
+                        resilience::is_transient_anyhow,
+                        || {
+                            openlist_auth::with_token(
+                                &self.state,
+                                node,
+                                openlist_config,
+                                |token| {
+                                    openlist_client.fetch_file_path(
+                                        &openlist_config.base_url,
+                                        token,
+                                        path.clone(),
+                                        openlist_ua.clone(),
+                                    )
+                                },
+                            )
+                        },
                     )
-                    .await;
+                    .await
+                    .map(|url| (url, None, None))
+                };
 
                 let elapsed_ms = timer.elapsed().as_millis();
 
                 match result {
-                    Ok(new_url) => {
+                    Ok((new_url, link_headers, expiration)) => {
                         if elapsed_ms >= 500 {
                             warn_log!(
                                 STREAM_LOGGER_DOMAIN,
@@ -575,19 +704,39 @@ impl AppStreamService {
                                 )
                             })?;
 
-                        cache.insert(
-                            open_list_cache_key.clone(),
-                            new_uri.clone(),
-                        );
-                        info_log!(
-                            STREAM_LOGGER_DOMAIN,
-                            "openlist_cache_store key={} node={} uri={}",
-                            open_list_cache_key,
-                            node.name,
-                            new_uri
-                        );
+                        let link = OpenListLink {
+                            uri: new_uri,
+                            headers: Self::open_list_link_headers(link_headers),
+                            expires_at: Self::open_list_link_expires_at(
+                                expiration,
+                            ),
+                        };
+                        if link.is_expired() {
+                            info_log!(
+                                STREAM_LOGGER_DOMAIN,
+                                "openlist_cache_skip_short_lived key={} node={} \
+                                 expiration={:?}",
+                                open_list_cache_key,
+                                node.name,
+                                expiration
+                            );
+                        } else {
+                            cache.insert(
+                                open_list_cache_key.clone(),
+                                link.clone(),
+                            );
+                            info_log!(
+                                STREAM_LOGGER_DOMAIN,
+                                "openlist_cache_store key={} node={} uri={} \
+                                 expiration={:?}",
+                                open_list_cache_key,
+                                node.name,
+                                link.uri,
+                                expiration
+                            );
+                        }
 
-                        Ok(new_uri)
+                        Ok(link)
                     }
                     Err(e) => {
                         error_log!(
@@ -781,6 +930,45 @@ impl AppStreamService {
         )
     }
 
+    fn cached_open_list_link(
+        cache: &GeneralCache,
+        cache_key: &str,
+    ) -> Option<OpenListLink> {
+        cache
+            .get::<OpenListLink>(cache_key)
+            .filter(|link| !link.is_expired())
+    }
+
+    fn open_list_link_headers(
+        headers: Option<HashMap<String, Vec<String>>>,
+    ) -> Option<HeaderMap> {
+        let mut map = HeaderMap::new();
+        for (name, values) in headers.unwrap_or_default() {
+            let Ok(name) = name.parse::<header::HeaderName>() else {
+                continue;
+            };
+            for value in values {
+                if let Ok(value) = value.parse::<header::HeaderValue>() {
+                    map.append(name.clone(), value);
+                }
+            }
+        }
+        (!map.is_empty()).then_some(map)
+    }
+
+    /// Links stop being served from `open_list_cache` a little before the
+    /// storage driver says they expire, so a cached link is never handed to
+    /// a stream that starts just as it dies.
+    fn open_list_link_expires_at(
+        expiration: Option<std::time::Duration>,
+    ) -> Option<Instant> {
+        const EXPIRY_MARGIN: std::time::Duration =
+            std::time::Duration::from_secs(30);
+        expiration.map(|lifetime| {
+            Instant::now() + lifetime.saturating_sub(EXPIRY_MARGIN)
+        })
+    }
+
     fn open_list_request_lock(&self, cache_key: &str) -> Arc<TokioMutex<()>> {
         AppState::request_lock(&self.state.open_list_request_locks, cache_key)
     }
@@ -802,6 +990,30 @@ impl AppStreamService {
         node: &BackendNode,
         resolved_path: &google_drive::ResolvedGoogleDrivePath,
     ) -> Result<String, String> {
+        self.lookup_google_drive_file_with_retry(node, resolved_path)
+            .await?
+            .map(|resolved| resolved.file_id)
+            .ok_or_else(|| {
+                format!(
+                    "googleDrive file not found: {}",
+                    resolved_path.relative_path
+                )
+            })
+    }
+
+    /// Walks the Drive path with one token refresh on 401; `Ok(None)` when a
+    /// path component does not exist.
+    async fn lookup_google_drive_file_with_retry(
+        &self,
+        node: &BackendNode,
+        resolved_path: &google_drive::ResolvedGoogleDrivePath,
+    ) -> Result<Option<GoogleDriveResolvedFile>, String> {
+        if let Some(indexed) =
+            google_drive_index::lookup(&self.state, node, resolved_path)
+        {
+            return Ok(Some(indexed));
+        }
+
         let client = self.state.get_google_drive_client().await.clone();
         let access_token = google_drive_auth::token_for_request(
             self.state.clone(),
@@ -819,8 +1031,7 @@ impl AppStreamService {
                 &resolved_path.relative_path,
             )
             .await;
-        match attempt {
-            Ok(resolved) => Ok(resolved.file_id),
+        let attempt = match attempt {
             Err(GoogleDriveApiError::ApiStatus { status: 401, .. }) => {
                 google_drive_auth::invalidate(&self.state, node);
                 let refreshed = google_drive_auth::token_for_request(
@@ -838,8 +1049,17 @@ impl AppStreamService {
                         &resolved_path.relative_path,
                     )
                     .await
-                    .map(|resolved| resolved.file_id)
-                    .map_err(|error| error.to_string())
+            }
+            other => other,
+        };
+        match attempt {
+            Ok(resolved) => Ok(Some(resolved)),
+            // Path misses are reported with the logical path ("/a/b");
+            // a missing shared drive name stays a configuration error.
+            Err(GoogleDriveApiError::DriveNotFound(path))
+                if path.starts_with('/') =>
+            {
+                Ok(None)
             }
             Err(error) => Err(error.to_string()),
         }
@@ -1004,6 +1224,7 @@ impl AppStreamService {
     async fn webdav_proxy_auth_headers(
         &self,
         node: &BackendNode,
+        method: &Method,
         uri: &Uri,
         client_headers: &hyper::HeaderMap,
         stream_session_id: Option<&str>,
@@ -1022,6 +1243,7 @@ impl AppStreamService {
             &self.state.webdav_auth_cache,
             &self.state.webdav_auth_probe_locks,
             node,
+            method,
             uri,
             cfg,
             Some(client_headers),
@@ -1056,6 +1278,7 @@ impl AppStreamService {
         }
         self.webdav_proxy_auth_headers(
             node,
+            &Method::GET,
             uri,
             client_headers,
             stream_session_id,
@@ -1063,61 +1286,507 @@ impl AppStreamService {
         .await
     }
 
+    /// Checks that Drive serves `uri` before the client is redirected to
+    /// it. A rejected token is refreshed and a credential out of quota is
+    /// rotated, so the returned target may carry a different token than
+    /// the one passed in.
     async fn probe_google_drive_redirect_target(
         &self,
         node: &BackendNode,
-        uri: &Uri,
-        extra_headers: Option<&HeaderMap>,
+        uri: Uri,
+        extra_headers: Option<HeaderMap>,
         user_agent: &str,
         stream_session_id: &str,
-    ) -> Result<(), StatusCode> {
+    ) -> Result<(Uri, Option<HeaderMap>), StreamFailure> {
         if !google_drive_auth::is_google_drive_node(node) {
-            return Ok(());
+            return Ok((uri, extra_headers));
         }
-        let auth_value = extra_headers
-            .and_then(|headers| headers.get(header::AUTHORIZATION))
-            .and_then(|value| value.to_str().ok())
-            .unwrap_or_default();
-        if auth_value.is_empty() {
-            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        let upstream_down = StreamFailure::new(
+            FallbackReason::UpstreamDown,
+            StatusCode::SERVICE_UNAVAILABLE,
+        );
+        let Some(mut headers) = extra_headers
+            .filter(|headers| headers.contains_key(header::AUTHORIZATION))
+        else {
+            return Err(upstream_down);
+        };
+        let probe = async |headers: &HeaderMap| {
+            let auth_value = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            upstream_proxy::probe_media_range(
+                Self::with_google_drive_access_token(&uri, auth_value),
+                auth_value,
+                user_agent,
+                Some(stream_session_id),
+            )
+            .await
+            .map_err(|_| {
+                StreamFailure::new(
+                    FallbackReason::UpstreamDown,
+                    StatusCode::BAD_GATEWAY,
+                )
+            })
+        };
+
+        let mut result = probe(&headers).await?;
+        if result.status == StatusCode::UNAUTHORIZED {
+            google_drive_auth::invalidate(&self.state, node);
+            let (_, refreshed) = self
+                .google_drive_auth_headers(
+                    node,
+                    "probe_redirect_retry_401",
+                    Duration::seconds(google_drive_auth::PROXY_MIN_VALID_SECS),
+                )
+                .await
+                .map_err(|_| upstream_down)?;
+            headers = refreshed;
+            result = probe(&headers).await?;
         }
 
-        let status = upstream_proxy::probe_authorization(
-            uri.clone(),
-            auth_value,
-            user_agent,
-            Some(stream_session_id),
-        )
-        .await
-        .map_err(|_| StatusCode::BAD_GATEWAY)?;
-        if status.is_success() {
-            return Ok(());
+        loop {
+            if result.status.is_success() {
+                let auth_value = headers
+                    .get(header::AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default();
+                let uri =
+                    Self::with_google_drive_access_token(&uri, auth_value);
+                return Ok((uri, Some(headers)));
+            }
+            let reason = FallbackReason::from_upstream_status(
+                node,
+                result.status,
+                &result.body,
+            );
+            if reason != FallbackReason::QuotaExceeded {
+                return Err(StreamFailure::new(
+                    reason,
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ));
+            }
+            // Benches the refused credential; every credential is tried at
+            // most once because benched ones are never handed out again.
+            let Some(next) = google_drive_auth::rotate_after_quota_error(
+                &self.state,
+                node,
+                Some(&headers),
+                &result.body,
+            )
+            .await
+            else {
+                return Err(StreamFailure::new(
+                    reason,
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ));
+            };
+            headers = next;
+            result = probe(&headers).await?;
         }
+    }
 
-        if status == StatusCode::UNAUTHORIZED {
-            google_drive_auth::invalidate(&self.state, node);
-            let auth_line = google_drive_auth::authorization_line_for_remote(
-                self.state.clone(),
-                node.clone(),
-                "probe_redirect_retry_401",
-                Duration::seconds(google_drive_auth::PROXY_MIN_VALID_SECS),
+    /// Swaps the `access_token` query parameter of a Drive redirect URL for
+    /// the token in `auth_value`; URLs without one are returned unchanged.
+    fn with_google_drive_access_token(uri: &Uri, auth_value: &str) -> Uri {
+        let Some(query) = uri.query() else {
+            return uri.clone();
+        };
+        if !query
+            .split('&')
+            .any(|pair| pair.starts_with("access_token="))
+        {
+            return uri.clone();
+        }
+        let token = auth_value
+            .strip_prefix("Bearer ")
+            .or_else(|| auth_value.strip_prefix("bearer "))
+            .unwrap_or(auth_value)
+            .trim();
+        let query = query
+            .split('&')
+            .map(|pair| {
+                if pair.starts_with("access_token=") {
+                    format!("access_token={token}")
+                } else {
+                    pair.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = format!("{}?{query}", uri.path())
+            .parse::<PathAndQuery>()
+            .ok();
+        Uri::from_parts(parts).unwrap_or_else(|_| uri.clone())
+    }
+
+    /// Upstream headers for an already remote target: whatever OpenList's
+    /// `fs/link` demanded, overlaid with the STRM file's own directives.
+    fn remote_upstream_headers(
+        sign: &Sign,
+        proxy_mode: ProxyMode,
+        openlist_headers: Option<HeaderMap>,
+    ) -> Option<HeaderMap> {
+        let strm_headers = Self::strm_upstream_headers(sign, proxy_mode);
+        match (openlist_headers, strm_headers) {
+            (Some(mut headers), Some(strm_headers)) => {
+                for (name, value) in &strm_headers {
+                    headers.insert(name, value.clone());
+                }
+                Some(headers)
+            }
+            (openlist_headers, strm_headers) => {
+                openlist_headers.or(strm_headers)
+            }
+        }
+    }
+
+    /// Headers declared by the STRM file travel inside the sign and are
+    /// injected only when this backend proxies the remote target; redirects
+    /// cannot carry request headers for the client.
+    fn strm_upstream_headers(
+        sign: &Sign,
+        proxy_mode: ProxyMode,
+    ) -> Option<HeaderMap> {
+        if proxy_mode != ProxyMode::Proxy || sign.headers.is_empty() {
+            return None;
+        }
+        let headers = strm::headers_to_map(&sign.headers);
+        (!headers.is_empty()).then_some(headers)
+    }
+
+    async fn probe_source(
+        &self,
+        request: &AppStreamRequest,
+    ) -> Result<ProbeResult, AppStreamError> {
+        let sign = request
+            .sign
+            .as_ref()
+            .ok_or(AppStreamError::EmptySignature)?;
+        let node = request
+            .node
+            .as_ref()
+            .ok_or(AppStreamError::BackendNodeNotFound)?;
+        let uri = sign.uri.clone().ok_or(AppStreamError::InvalidUri)?;
+        let uri = self.rewrite_uri_if_needed(uri, request).await?;
+
+        if !Uri::is_local(&uri) {
+            debug_log!(
+                STREAM_LOGGER_DOMAIN,
+                "probe_skip_remote_uri node={} uri={}",
+                node.name,
+                uri
+            );
+            return Ok(ProbeResult::found(None));
+        }
+
+        let path = Uri::to_path_or_url_string(&uri);
+        if let Some(openlist_config) = &node.open_list {
+            let user_agent = request
+                .user_agent()
+                .unwrap_or_else(|| SystemInfo::new().get_user_agent());
+            let client = self.state.get_open_list_client().await.clone();
+            let info = resilience::retry_idempotent(
+                &UpstreamPolicy::for_node(node),
+                "openlist_fs_get_info",
+                &node.name,
+                resilience::is_transient_anyhow,
+                || {
+                    openlist_auth::with_token(
+                        &self.state,
+                        node,
+                        openlist_config,
+                        |token| {
+                            client.fetch_file_info(
+                                &openlist_config.base_url,
+                                token,
+                                path.clone(),
+                                user_agent.clone(),
+                            )
+                        },
+                    )
+                },
             )
             .await
-            .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
-            let retry_status = upstream_proxy::probe_authorization(
-                uri.clone(),
-                &auth_line,
-                user_agent,
-                Some(stream_session_id),
+            .map_err(|e| {
+                AppStreamError::UnexpectedOpenListError(e.to_string())
+            })?;
+            return Ok(match info {
+                Some(data) if !data.is_dir() => {
+                    ProbeResult::found(data.get_size())
+                }
+                _ => ProbeResult::missing(),
+            });
+        }
+
+        if Self::is_webdav_node(node) {
+            return self.probe_webdav(node, &path).await;
+        }
+
+        if Self::is_smb_node(node) {
+            return self.probe_smb(node, &path).await;
+        }
+
+        if Self::is_google_drive_node(node) {
+            let cfg = node
+                .google_drive
+                .as_ref()
+                .ok_or(AppStreamError::InvalidUri)?;
+            let resolved_path = google_drive::resolve_google_drive_path(
+                &path, cfg,
             )
+            .map_err(|e| AppStreamError::FileProbeError(e.to_string()))?;
+            let resolved = self
+                .lookup_google_drive_file_with_retry(node, &resolved_path)
+                .await
+                .map_err(AppStreamError::FileProbeError)?;
+            let Some(resolved) = resolved else {
+                return Ok(ProbeResult::missing());
+            };
+            let cache_key =
+                Self::google_drive_file_id_cache_key(&cfg.node_uuid, &path);
+            self.state
+                .get_google_drive_file_id_cache()
+                .await
+                .insert(cache_key, resolved.file_id.clone());
+            return Ok(ProbeResult::found(resolved.size));
+        }
+
+        if Self::node_has_remote_stream_base(node)
+            || node
+                .backend_type
+                .eq_ignore_ascii_case(STREAM_RELAY_BACKEND_TYPE)
+        {
+            return Ok(ProbeResult::found(None));
+        }
+
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => {
+                Ok(ProbeResult::found(Some(metadata.len())))
+            }
+            Ok(_) => Ok(ProbeResult::missing()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(ProbeResult::missing())
+            }
+            Err(e) => Err(AppStreamError::IoError(e)),
+        }
+    }
+
+    /// With `check_file_existence`, a PROPFIND that finds nothing at `path`
+    /// routes to the fallback video instead of a 404 at stream time. A failed
+    /// PROPFIND lets the request through.
+    async fn ensure_webdav_file_exists(
+        &self,
+        node: &BackendNode,
+        path: &str,
+    ) -> Result<(), AppStreamError> {
+        let check_file_existence = self
+            .state
+            .get_config()
             .await
-            .map_err(|_| StatusCode::BAD_GATEWAY)?;
-            if retry_status.is_success() {
-                return Ok(());
+            .backend
+            .as_ref()
+            .is_some_and(|backend| backend.check_file_existence);
+        if !check_file_existence {
+            return Ok(());
+        }
+
+        match self.stat_webdav(node, path).await {
+            Ok(entry) if !entry.exists => {
+                warn_log!(
+                    STREAM_LOGGER_DOMAIN,
+                    "webdav_file_missing node={} path={}",
+                    node.name,
+                    path
+                );
+                Err(AppStreamError::FileNotFound(path.to_string()))
             }
+            Ok(_) => Ok(()),
+            Err(error) => {
+                warn_log!(
+                    STREAM_LOGGER_DOMAIN,
+                    "webdav_stat_unavailable node={} path={} error={:?}",
+                    node.name,
+                    path,
+                    error
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn probe_webdav(
+        &self,
+        node: &BackendNode,
+        path: &str,
+    ) -> Result<ProbeResult, AppStreamError> {
+        let entry = self.stat_webdav(node, path).await?;
+        Ok(if entry.exists {
+            ProbeResult::found(entry.size)
+        } else {
+            ProbeResult::missing()
+        })
+    }
+
+    async fn probe_smb(
+        &self,
+        node: &BackendNode,
+        path: &str,
+    ) -> Result<ProbeResult, AppStreamError> {
+        let target = SmbTarget::from_node(node)
+            .map_err(|e| AppStreamError::FileProbeError(e.to_string()))?;
+        let file_path = target.file_path(path);
+        match self
+            .state
+            .smb_sessions
+            .stat(Arc::new(target), &file_path)
+            .await
+        {
+            Ok(file) if !file.is_directory => {
+                Ok(ProbeResult::found(Some(file.size)))
+            }
+            Ok(_) => Ok(ProbeResult::missing()),
+            Err(e) if e.is_not_found() => Ok(ProbeResult::missing()),
+            Err(e) => Err(AppStreamError::FileProbeError(e.to_string())),
+        }
+    }
+
+    fn webdav_stat_cache_key(node: &BackendNode, path: &str) -> String {
+        let path_hash = StringUtil::hash_hex(path);
+        format!(
+            "backend:webdav:stat:node:{}:path_hash:{}",
+            node.name, path_hash
+        )
+    }
+
+    /// PROPFIND answer for `path`, cached per node for a few minutes with a
+    /// single in-flight request per key.
+    async fn stat_webdav(
+        &self,
+        node: &BackendNode,
+        path: &str,
+    ) -> Result<WebDavEntry, AppStreamError> {
+        let cache = self.state.get_webdav_stat_cache().await;
+        let cache_key = Self::webdav_stat_cache_key(node, path);
+        if let Some(entry) = cache.get::<WebDavEntry>(&cache_key) {
+            debug_log!(
+                STREAM_LOGGER_DOMAIN,
+                "webdav_stat_cache_hit key={} exists={}",
+                cache_key,
+                entry.exists
+            );
+            return Ok(entry);
         }
 
-        Err(StatusCode::SERVICE_UNAVAILABLE)
+        let stat_mutex = AppState::request_lock(
+            &self.state.webdav_stat_request_locks,
+            &cache_key,
+        );
+        let result = {
+            let _stat_guard = stat_mutex.lock().await;
+            match cache.get::<WebDavEntry>(&cache_key) {
+                Some(entry) => Ok(entry),
+                None => {
+                    let fetched = self.fetch_webdav_stat(node, path).await;
+                    if let Ok(entry) = &fetched {
+                        debug_log!(
+                            STREAM_LOGGER_DOMAIN,
+                            "webdav_stat_complete key={} exists={} size={:?} \
+                             etag={:?}",
+                            cache_key,
+                            entry.exists,
+                            entry.size,
+                            entry.etag
+                        );
+                        cache.insert(cache_key.clone(), entry.clone());
+                    }
+                    fetched
+                }
+            }
+        };
+
+        AppState::cleanup_request_lock(
+            &self.state.webdav_stat_request_locks,
+            &cache_key,
+            &stat_mutex,
+        );
+        result
+    }
+
+    async fn fetch_webdav_stat(
+        &self,
+        node: &BackendNode,
+        path: &str,
+    ) -> Result<WebDavEntry, AppStreamError> {
+        let upstream =
+            webdav::build_upstream_uri(node, path, node.webdav.as_ref())
+                .map_err(|e| AppStreamError::WebDavUrl(e.to_string()))?;
+        let propfind =
+            Method::from_bytes(webdav::PROPFIND_METHOD.as_bytes())
+                .map_err(|e| AppStreamError::FileProbeError(e.to_string()))?;
+        let auth_headers = self
+            .webdav_proxy_auth_headers(
+                node,
+                &propfind,
+                &upstream,
+                &HeaderMap::new(),
+                None,
+            )
+            .await
+            .map_err(|status| {
+                AppStreamError::FileProbeError(format!(
+                    "webdav authorization failed status={status}"
+                ))
+            })?;
+        let user_agent = node
+            .webdav
+            .as_ref()
+            .map(|cfg| cfg.user_agent.trim())
+            .filter(|ua| !ua.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| SystemInfo::new().get_user_agent());
+
+        let policy = UpstreamPolicy::for_node(node);
+        let stat = resilience::retry_idempotent(
+            &policy,
+            "webdav_propfind",
+            &node.name,
+            resilience::is_transient_gateway,
+            || {
+                upstream_proxy::propfind(
+                    upstream.clone(),
+                    &user_agent,
+                    auth_headers.as_ref(),
+                    &policy,
+                )
+            },
+        )
+        .await
+        .map_err(|e| AppStreamError::FileProbeError(e.to_string()))?;
+        let status = stat.status;
+        WebDavEntry::from_propfind(stat).ok_or_else(|| {
+            AppStreamError::FileProbeError(format!(
+                "webdav propfind status {}",
+                status.as_u16()
+            ))
+        })
+    }
+
+    fn route_failure(error: &AppStreamError) -> Option<StreamFailure> {
+        match error {
+            AppStreamError::FileNotFound(_) => Some(StreamFailure::new(
+                FallbackReason::FileMissing,
+                StatusCode::BAD_REQUEST,
+            )),
+            AppStreamError::UnexpectedOpenListError(_) => {
+                Some(StreamFailure::new(
+                    FallbackReason::UpstreamDown,
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+            _ => None,
+        }
     }
 }
 
@@ -1138,12 +1807,16 @@ mod tests {
     use std::sync::Once;
     use tokio::sync::Mutex as TokioMutex;
 
-    use super::AppStreamService;
+    use super::{AppStreamService, OpenListLink};
+    use crate::core::backend::{fallback::FallbackReason, probe::ProbeResult};
     use crate::{
         AppState,
         client::GoogleDriveClient,
         config::{
-            backend::{BackendNode, GoogleDriveConfig},
+            backend::{
+                BackendNode, GoogleDriveConfig, GoogleDriveRoot,
+                openlist::OpenList,
+            },
             core::{finish_raw_config, parse_raw_config_str},
         },
         core::backend::google_drive::{DriveLookup, ResolvedGoogleDrivePath},
@@ -1265,6 +1938,12 @@ refresh_token = "refresh-token"
                 client_secret: "client-secret".to_string(),
                 drive_id: String::new(),
                 drive_name: "pilipili".to_string(),
+                root: GoogleDriveRoot::SharedDrive,
+                root_folder_id: String::new(),
+                service_account_files: vec![],
+                service_account_subject: String::new(),
+                folder_index: false,
+                token_secret: String::new(),
                 access_token: "access-token".to_string(),
                 refresh_token: "refresh-token".to_string(),
                 token: Some(OAuthToken {
@@ -1277,6 +1956,8 @@ refresh_token = "refresh-token"
                 }),
             }),
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
@@ -1516,7 +2197,7 @@ token=access-token"
         let media_handlers: Vec<HttpMockHandler> = vec![
             Box::new(move |request| {
                 Box::pin(async move {
-                    assert!(request.starts_with("HEAD /media HTTP/1.1"));
+                    assert!(request.starts_with("GET /media HTTP/1.1"));
                     assert!(
                         request.contains("authorization: Bearer access-token")
                     );
@@ -1525,11 +2206,11 @@ token=access-token"
             }),
             Box::new(move |request| {
                 Box::pin(async move {
-                    assert!(request.starts_with("HEAD /media HTTP/1.1"));
+                    assert!(request.starts_with("GET /media HTTP/1.1"));
                     assert!(
                         request.contains("authorization: Bearer probe-token")
                     );
-                    http_response(200, "text/plain", "")
+                    http_response(206, "text/plain", "x")
                 })
             }),
         ];
@@ -1560,17 +2241,152 @@ token=access-token"
             "Bearer access-token".parse().expect("authorization"),
         );
 
-        let result = service
+        let (uri, headers) = service
             .probe_google_drive_redirect_target(
                 &node,
-                &format!("{media_base}/media").parse().expect("uri"),
-                Some(&headers),
+                format!("{media_base}/media").parse().expect("uri"),
+                Some(headers),
                 "UnitTest/1.0",
                 "session-1",
             )
-            .await;
+            .await
+            .expect("probe");
 
-        assert_eq!(result, Ok(()));
+        assert_eq!(uri.path(), "/media");
+        assert_eq!(
+            headers.as_ref().and_then(|h| h.get(header::AUTHORIZATION)),
+            Some(&"Bearer probe-token".parse().expect("authorization"))
+        );
+    }
+
+    #[tokio::test]
+    async fn probe_google_drive_redirect_target_rotates_after_quota_error() {
+        ensure_rustls_crypto_provider();
+        let media_handlers: Vec<HttpMockHandler> = vec![
+            Box::new(move |request| {
+                Box::pin(async move {
+                    assert!(request.starts_with("GET /media?"));
+                    assert!(
+                        request.contains("authorization: Bearer access-token")
+                    );
+                    http_response(
+                        403,
+                        "application/json",
+                        r#"{"error":{"errors":[{"reason":"downloadQuotaExceeded"}]}}"#,
+                    )
+                })
+            }),
+            Box::new(move |request| {
+                Box::pin(async move {
+                    assert!(request.contains("access_token=sa-token"));
+                    assert!(request.contains("authorization: Bearer sa-token"));
+                    http_response(206, "application/octet-stream", "x")
+                })
+            }),
+        ];
+        let token_handlers: Vec<HttpMockHandler> = vec![Box::new(
+            move |request| {
+                Box::pin(async move {
+                    assert!(request.contains("jwt-bearer"));
+                    http_response(
+                        200,
+                        "application/json",
+                        r#"{"access_token":"sa-token","token_type":"Bearer","expires_in":3600}"#,
+                    )
+                })
+            },
+        )];
+        let media_base = spawn_http_mock_server(media_handlers).await;
+        let token_base = spawn_http_mock_server(token_handlers).await;
+        let key_path = std::env::temp_dir()
+            .join(format!("embystream-sa-{}.json", uuid::Uuid::new_v4()));
+        let key_pair = rcgen::KeyPair::generate_for(&rcgen::PKCS_RSA_SHA256)
+            .expect("rsa key");
+        let key_json = serde_json::json!({
+            "type": "service_account",
+            "client_email": "pool-1@project.iam.gserviceaccount.com",
+            "private_key": key_pair.serialize_pem(),
+        });
+        std::fs::write(&key_path, key_json.to_string()).expect("write key");
+        let mut node = google_drive_node();
+        node.google_drive
+            .as_mut()
+            .expect("google config")
+            .service_account_files =
+            vec![key_path.to_string_lossy().into_owned()];
+        let state = test_state_with_google_node(node.clone()).await;
+        state.set_google_drive_client_for_test(google_drive_client_for_test(
+            &media_base,
+            &token_base,
+        ));
+        let service = AppStreamService::new(state);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            "Bearer access-token".parse().expect("authorization"),
+        );
+
+        let (uri, headers) = service
+            .probe_google_drive_redirect_target(
+                &node,
+                format!(
+                    "{media_base}/media?alt=media&access_token=access-token"
+                )
+                .parse()
+                .expect("uri"),
+                Some(headers),
+                "UnitTest/1.0",
+                "session-1",
+            )
+            .await
+            .expect("probe");
+
+        assert_eq!(uri.query(), Some("alt=media&access_token=sa-token"));
+        assert_eq!(
+            headers.as_ref().and_then(|h| h.get(header::AUTHORIZATION)),
+            Some(&"Bearer sa-token".parse().expect("authorization"))
+        );
+        let _ = std::fs::remove_file(key_path);
+    }
+
+    #[tokio::test]
+    async fn probe_google_drive_redirect_target_keeps_credential_on_plain_403()
+    {
+        ensure_rustls_crypto_provider();
+        let media_handlers: Vec<HttpMockHandler> = vec![Box::new(
+            move |_request| {
+                Box::pin(async move {
+                    http_response(
+                        403,
+                        "application/json",
+                        r#"{"error":{"errors":[{"reason":"insufficientFilePermissions"}]}}"#,
+                    )
+                })
+            },
+        )];
+        let media_base = spawn_http_mock_server(media_handlers).await;
+        let node = google_drive_node();
+        let state = test_state_with_google_node(node.clone()).await;
+        let service = AppStreamService::new(state.clone());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            "Bearer access-token".parse().expect("authorization"),
+        );
+
+        let failure = service
+            .probe_google_drive_redirect_target(
+                &node,
+                format!("{media_base}/media").parse().expect("uri"),
+                Some(headers),
+                "UnitTest/1.0",
+                "session-1",
+            )
+            .await
+            .expect_err("refused");
+
+        assert_eq!(failure.reason, FallbackReason::UpstreamDown);
+        assert!(state.google_drive_credential_backoff_until.is_empty());
     }
 
     #[test]
@@ -1614,6 +2430,131 @@ token=access-token"
 
         assert!(matches!(result, Err(AppStreamError::InvalidUri)));
     }
+
+    #[tokio::test]
+    async fn probe_source_stats_local_disk_files() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let file = dir.path().join("movie.mkv");
+        std::fs::write(&file, b"0123456789").expect("write media");
+        let mut node = google_drive_node();
+        node.backend_type = "Disk".to_string();
+        node.google_drive = None;
+
+        let service = AppStreamService::new(Arc::new(test_state().await));
+        let found = service
+            .probe_source(&google_drive_request(
+                node.clone(),
+                Uri::force_from_path_or_url(file.to_string_lossy().as_ref())
+                    .expect("sign uri"),
+            ))
+            .await
+            .expect("probe existing");
+        let missing = service
+            .probe_source(&google_drive_request(
+                node,
+                Uri::force_from_path_or_url("/definitely/missing/movie.mkv")
+                    .expect("sign uri"),
+            ))
+            .await
+            .expect("probe missing");
+
+        assert_eq!(found, ProbeResult::found(Some(10)));
+        assert_eq!(missing, ProbeResult::missing());
+    }
+
+    #[tokio::test]
+    async fn openlist_proxy_uses_fs_link_headers_and_expiry() {
+        ensure_rustls_crypto_provider();
+        let handlers: Vec<HttpMockHandler> = vec![Box::new(|request| {
+            Box::pin(async move {
+                assert!(request.starts_with("POST /api/fs/link "));
+                http_response(
+                    200,
+                    "application/json",
+                    r#"{"code":200,"message":"success","data":{"url":"https://cdn.example/movie.mkv","header":{"Referer":["https://pan.example/"]},"expiration":60000000000}}"#,
+                )
+            })
+        })];
+        let base_url = spawn_http_mock_server(handlers).await;
+
+        let mut node = google_drive_node();
+        node.backend_type = "OpenList".to_string();
+        node.proxy_mode = "proxy".to_string();
+        node.google_drive = None;
+        node.open_list = Some(OpenList {
+            base_url,
+            token: "tok".to_string(),
+            ..OpenList::default()
+        });
+        let service = AppStreamService::new(Arc::new(test_state().await));
+        let sign_uri =
+            Uri::force_from_path_or_url("/media/movie.mkv").expect("sign uri");
+        let request = google_drive_request(node, sign_uri.clone());
+
+        let first = service
+            .fetch_remote_uri_if_openlist(&sign_uri, &request)
+            .await
+            .expect("fs/link");
+        let cached = service
+            .fetch_remote_uri_if_openlist(&sign_uri, &request)
+            .await
+            .expect("cached link");
+
+        assert_eq!(first.uri.to_string(), "https://cdn.example/movie.mkv");
+        assert_eq!(
+            first
+                .headers
+                .as_ref()
+                .and_then(|headers| headers.get(header::REFERER))
+                .and_then(|v| v.to_str().ok()),
+            Some("https://pan.example/")
+        );
+        let remaining = first
+            .expires_at
+            .expect("expiry")
+            .saturating_duration_since(Instant::now());
+        assert!(remaining <= std::time::Duration::from_secs(30));
+        assert_eq!(cached.uri, first.uri);
+    }
+
+    #[test]
+    fn open_list_link_expiry_within_margin_is_not_cached() {
+        let link = OpenListLink {
+            expires_at: AppStreamService::open_list_link_expires_at(Some(
+                std::time::Duration::from_secs(10),
+            )),
+            ..OpenListLink::passthrough(&Uri::from_static("https://x/"))
+        };
+
+        assert!(link.is_expired());
+        assert!(
+            !OpenListLink::passthrough(&Uri::from_static("https://x/"))
+                .is_expired()
+        );
+    }
+
+    #[test]
+    fn strm_upstream_headers_apply_only_when_proxying() {
+        use crate::core::{backend::proxy_mode::ProxyMode, sign::Sign};
+
+        let sign = Sign::new(None, None).with_headers(vec![
+            ("Referer".to_string(), "https://site.example/".to_string()),
+            ("bad header".to_string(), "ignored".to_string()),
+        ]);
+
+        let proxied =
+            AppStreamService::strm_upstream_headers(&sign, ProxyMode::Proxy)
+                .expect("proxy headers");
+        assert_eq!(
+            proxied.get(header::REFERER).and_then(|v| v.to_str().ok()),
+            Some("https://site.example/")
+        );
+        assert_eq!(proxied.len(), 1);
+        assert!(
+            AppStreamService::strm_upstream_headers(&sign, ProxyMode::Redirect)
+                .is_none()
+        );
+    }
 }
 
 #[async_trait]
@@ -1622,10 +2563,59 @@ impl StreamService for AppStreamService {
         &self,
         request: AppStreamRequest,
     ) -> Result<AppStreamResult, StatusCode> {
-        let source = self.route_with_sign(&request).await.map_err(|e| {
-            error_log!(STREAM_LOGGER_DOMAIN, "Routing stream error: {:?}", e);
-            StatusCode::BAD_REQUEST
-        })?;
+        if let Some(node) = request.node.as_ref() {
+            let policy = UpstreamPolicy::for_node(node);
+            if !self.state.circuit_breakers.try_acquire(&node.name, &policy) {
+                warn_log!(
+                    STREAM_LOGGER_DOMAIN,
+                    "circuit_open_short_circuit node={}",
+                    node.name
+                );
+                return FallbackResponder::respond(
+                    &self.state,
+                    StreamFailure::new(
+                        FallbackReason::UpstreamDown,
+                        StatusCode::SERVICE_UNAVAILABLE,
+                    ),
+                    &request.original_headers,
+                )
+                .await;
+            }
+        }
+
+        let source = match self.route_with_sign(&request).await {
+            Ok(source) => source,
+            Err(e) => {
+                error_log!(
+                    STREAM_LOGGER_DOMAIN,
+                    "Routing stream error: {:?}",
+                    e
+                );
+                let failure = Self::route_failure(&e);
+                if let (Some(node), Some(failure)) =
+                    (request.node.as_ref(), failure)
+                {
+                    if failure.reason == FallbackReason::UpstreamDown {
+                        self.state.circuit_breakers.record_failure(
+                            &node.name,
+                            &UpstreamPolicy::for_node(node),
+                            &e,
+                        );
+                    }
+                }
+                return match failure {
+                    Some(failure) => {
+                        FallbackResponder::respond(
+                            &self.state,
+                            failure,
+                            &request.original_headers,
+                        )
+                        .await
+                    }
+                    None => Err(StatusCode::BAD_REQUEST),
+                };
+            }
+        };
 
         let node = request.node.as_ref().ok_or_else(|| {
             error_log!(
@@ -1645,6 +2635,7 @@ impl StreamService for AppStreamService {
                 Source::Local { .. } => "Local",
                 Source::Remote { .. } => "Remote",
                 Source::AccelRedirect { .. } => "AccelRedirect",
+                Source::Smb { .. } => "Smb",
             }
         );
         info_log!(STREAM_LOGGER_DOMAIN, "Routing stream source: {:?}", source);
@@ -1678,8 +2669,26 @@ impl StreamService for AppStreamService {
                 .await
             }
             Source::AccelRedirect { info } => {
+                self.state.circuit_breakers.record_success(&node.name);
                 Ok(AppStreamResult::AccelRedirect(info))
             }
+            Source::Smb { path, device_id } => {
+                let client_info = ClientInfo::new(
+                    Some(device_id),
+                    None,
+                    request.client(),
+                    request.client_ip(),
+                );
+                SmbStreamer::stream(
+                    self.state.clone(),
+                    node,
+                    &path,
+                    request.content_range(),
+                    client_info,
+                    &request.original_headers,
+                )
+                .await
+            }
             Source::Remote {
                 uri,
                 mode,
@@ -1689,14 +2698,26 @@ impl StreamService for AppStreamService {
                     let stream_session_id = generate_stream_session_id();
                     let user_agent =
                         Self::resolve_upstream_user_agent(node, &request);
-                    self.probe_google_drive_redirect_target(
-                        node,
-                        &uri,
-                        extra_upstream_headers.as_ref(),
-                        &user_agent,
-                        stream_session_id.as_str(),
-                    )
-                    .await?;
+                    let (uri, extra_upstream_headers) = match self
+                        .probe_google_drive_redirect_target(
+                            node,
+                            uri,
+                            extra_upstream_headers,
+                            &user_agent,
+                            stream_session_id.as_str(),
+                        )
+                        .await
+                    {
+                        Ok(target) => target,
+                        Err(failure) => {
+                            return FallbackResponder::respond(
+                                &self.state,
+                                failure,
+                                &request.original_headers,
+                            )
+                            .await;
+                        }
+                    };
                     let redirect_info = self
                         .build_redirect_info(
                             uri,
@@ -1712,11 +2733,18 @@ impl StreamService for AppStreamService {
                             );
                             StatusCode::INTERNAL_SERVER_ERROR
                         })?;
+                    self.state.circuit_breakers.record_success(&node.name);
                     Ok(AppStreamResult::Redirect(redirect_info))
                 }
                 ProxyMode::Proxy => {
-                    let user_agent =
-                        Self::resolve_upstream_user_agent(node, &request);
+                    let user_agent = extra_upstream_headers
+                        .as_ref()
+                        .and_then(|headers| headers.get(header::USER_AGENT))
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| {
+                            Self::resolve_upstream_user_agent(node, &request)
+                        });
                     let stream_session_id = generate_stream_session_id();
                     info_log!(
                         STREAM_LOGGER_DOMAIN,
@@ -1754,4 +2782,19 @@ impl StreamService for AppStreamService {
             },
         }
     }
+
+    async fn probe(
+        &self,
+        request: AppStreamRequest,
+    ) -> Result<ProbeResult, StatusCode> {
+        self.probe_source(&request).await.map_err(|e| {
+            error_log!(STREAM_LOGGER_DOMAIN, "probe_failed error={:?}", e);
+            match e {
+                AppStreamError::WebDavUrl(_) | AppStreamError::InvalidUri => {
+                    StatusCode::BAD_REQUEST
+                }
+                _ => StatusCode::BAD_GATEWAY,
+            }
+        })
+    }
 }
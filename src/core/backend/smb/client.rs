@@ -0,0 +1,794 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use super::{
+    constants::{DEFAULT_PORT, MAX_READ_SIZE},
+    ntlm,
+    proto::{
+        self, CAP_LARGE_MTU, CREDIT_UNIT, Command, DIALECT_202, DIALECT_210,
+        DIALECTS, RequestHeader, ResponseHeader,
+        SECURITY_MODE_SIGNING_REQUIRED, SESSION_FLAG_ENCRYPT_DATA,
+        SESSION_FLAG_IS_GUEST, SESSION_FLAG_IS_NULL, SHARE_FLAG_ENCRYPT_DATA,
+        STATUS_END_OF_FILE, STATUS_MORE_PROCESSING_REQUIRED, STATUS_PENDING,
+        STATUS_SUCCESS, Signer,
+    },
+};
+use crate::config::backend::{BackendNode, SmbConfig};
+
+/// Credits asked for with every request; plenty for one in-flight READ.
+const CREDIT_REQUEST: u16 = 64;
+/// Upper bound for a single NetBIOS frame accepted from the server.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum SmbError {
+    #[error("smb io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("smb request timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("smb {command} failed with status 0x{status:08x}")]
+    Status { command: &'static str, status: u32 },
+    #[error("smb path not found: {0}")]
+    NotFound(String),
+    #[error("smb protocol error: {0}")]
+    Protocol(&'static str),
+    #[error("smb unsupported: {0}")]
+    Unsupported(&'static str),
+    #[error("invalid smb node: {0}")]
+    InvalidNode(String),
+}
+
+impl SmbError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound(_))
+    }
+}
+
+/// Where and as whom an `Smb` node connects, resolved once per request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmbTarget {
+    pub host: String,
+    pub port: u16,
+    pub share: String,
+    pub username: String,
+    pub password: String,
+    pub domain: String,
+    /// Folder inside the share that node paths are relative to.
+    pub root: String,
+    pub timeout: Duration,
+    pub reconnect_attempts: u32,
+}
+
+impl SmbTarget {
+    /// Host from `base_url` (`smb://nas`, `//nas` or `nas`, optionally
+    /// with `:port`), port from `port`, root folder from `path`.
+    pub fn from_node(node: &BackendNode) -> Result<Self, SmbError> {
+        let cfg = node.smb.as_ref().ok_or_else(|| {
+            SmbError::InvalidNode(format!(
+                "node '{}' has no [BackendNode.Smb] table",
+                node.name
+            ))
+        })?;
+        Self::new(node, cfg)
+    }
+
+    fn new(node: &BackendNode, cfg: &SmbConfig) -> Result<Self, SmbError> {
+        let raw = node.base_url.trim();
+        let without_scheme = ["smb://", "cifs://", "//", "\\\\"]
+            .iter()
+            .find_map(|prefix| {
+                raw.get(..prefix.len())
+                    .filter(|head| head.eq_ignore_ascii_case(prefix))
+                    .map(|_| &raw[prefix.len()..])
+            })
+            .unwrap_or(raw);
+        let authority =
+            without_scheme.split(['/', '\\']).next().unwrap_or_default();
+        let (host, port_in_url) = match authority.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => {
+                (host, port.parse::<u16>().ok())
+            }
+            _ => (authority, None),
+        };
+        let host = host.trim_matches(['[', ']']);
+        if host.is_empty() {
+            return Err(SmbError::InvalidNode(format!(
+                "node '{}' needs an SMB host in base_url",
+                node.name
+            )));
+        }
+        let port = match node.port.trim() {
+            "" => port_in_url.unwrap_or(DEFAULT_PORT),
+            value => value.parse::<u16>().map_err(|_| {
+                SmbError::InvalidNode(format!(
+                    "node '{}' has invalid port '{}'",
+                    node.name, value
+                ))
+            })?,
+        };
+        let share = cfg.share.trim().trim_matches(['/', '\\']);
+        if share.is_empty() {
+            return Err(SmbError::InvalidNode(format!(
+                "node '{}' needs BackendNode.Smb.share",
+                node.name
+            )));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            share: share.to_string(),
+            username: cfg.username.trim().to_string(),
+            password: cfg.password.clone(),
+            domain: cfg.domain.trim().to_string(),
+            root: share_path("", &node.path),
+            timeout: Duration::from_secs(cfg.timeout_secs.max(1)),
+            reconnect_attempts: cfg.reconnect_attempts,
+        })
+    }
+
+    /// Pool key: one set of sessions per server, share and identity.
+    pub fn key(&self) -> String {
+        format!(
+            "{}:{}|{}|{}\\{}",
+            self.host.to_lowercase(),
+            self.port,
+            self.share.to_lowercase(),
+            self.domain,
+            self.username
+        )
+    }
+
+    /// Backslash-separated path inside the share for a node path.
+    pub fn file_path(&self, logical_path: &str) -> String {
+        share_path(&self.root, logical_path)
+    }
+}
+
+fn share_path(root: &str, logical_path: &str) -> String {
+    root.split(['/', '\\'])
+        .chain(logical_path.split(['/', '\\']))
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("\\")
+}
+
+/// An opened file on the share.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmbFile {
+    file_id: [u8; 16],
+    pub size: u64,
+    pub is_directory: bool,
+}
+
+/// One authenticated TCP connection with the share already connected.
+/// Requests run one at a time; callers own the session while they use it.
+pub struct SmbSession {
+    stream: TcpStream,
+    timeout: Duration,
+    message_id: u64,
+    credits: u32,
+    session_id: u64,
+    tree_id: u32,
+    dialect: u16,
+    large_mtu: bool,
+    max_read: u32,
+    signing_required: bool,
+    signer: Option<Signer>,
+}
+
+impl SmbSession {
+    pub async fn connect(target: &SmbTarget) -> Result<Self, SmbError> {
+        let address = format!("{}:{}", target.host, target.port);
+        let stream =
+            tokio::time::timeout(target.timeout, TcpStream::connect(address))
+                .await
+                .map_err(|_| SmbError::Timeout(target.timeout))??;
+        stream.set_nodelay(true)?;
+
+        let mut session = Self {
+            stream,
+            timeout: target.timeout,
+            message_id: 0,
+            credits: 1,
+            session_id: 0,
+            tree_id: 0,
+            dialect: 0,
+            large_mtu: false,
+            max_read: CREDIT_UNIT,
+            signing_required: false,
+            signer: None,
+        };
+        session.negotiate().await?;
+        session.session_setup(target).await?;
+        session.tree_connect(target).await?;
+        Ok(session)
+    }
+
+    pub fn dialect(&self) -> u16 {
+        self.dialect
+    }
+
+    /// Opens `path` (relative to the share) for reading.
+    pub async fn open(&mut self, path: &str) -> Result<SmbFile, SmbError> {
+        let reply = self
+            .call(Command::Create, &proto::create_body(path), 1)
+            .await?;
+        let header = Self::header(&reply)?;
+        if proto::is_not_found(header.status) {
+            return Err(SmbError::NotFound(path.to_string()));
+        }
+        Self::expect_success(&header, "create")?;
+        let created = proto::parse_create(&reply)
+            .ok_or(SmbError::Protocol("malformed create response"))?;
+        Ok(SmbFile {
+            file_id: created.file_id,
+            size: created.end_of_file,
+            is_directory: created.is_directory,
+        })
+    }
+
+    /// Reads up to `length` bytes at `offset`; an empty result means end of
+    /// file. Large reads are capped to what one request may carry.
+    pub async fn read(
+        &mut self,
+        file: &SmbFile,
+        offset: u64,
+        length: u32,
+    ) -> Result<Bytes, SmbError> {
+        let mut length = length.min(self.max_read);
+        if self.large_mtu {
+            let affordable = self.credits.max(1) * CREDIT_UNIT;
+            length = length.min(affordable);
+        }
+        let charge = length.div_ceil(CREDIT_UNIT).max(1) as u16;
+        let reply = self
+            .call(
+                Command::Read,
+                &proto::read_body(&file.file_id, offset, length),
+                charge,
+            )
+            .await?;
+        let header = Self::header(&reply)?;
+        if header.status == STATUS_END_OF_FILE {
+            return Ok(Bytes::new());
+        }
+        Self::expect_success(&header, "read")?;
+        let range = proto::parse_read(&reply)
+            .ok_or(SmbError::Protocol("malformed read response"))?;
+        Ok(Bytes::from(reply).slice(range))
+    }
+
+    pub async fn close(&mut self, file: &SmbFile) -> Result<(), SmbError> {
+        let reply = self
+            .call(Command::Close, &proto::close_body(&file.file_id), 1)
+            .await?;
+        Self::expect_success(&Self::header(&reply)?, "close")
+    }
+
+    /// Ends the session politely; errors are irrelevant at this point.
+    pub async fn logoff(mut self) {
+        let mut body = Vec::with_capacity(4);
+        proto::write_u16(&mut body, 4);
+        proto::write_u16(&mut body, 0);
+        let _ = self.call(Command::Logoff, &body, 1).await;
+    }
+
+    async fn negotiate(&mut self) -> Result<(), SmbError> {
+        let client_guid = rand::random::<[u8; 16]>();
+        let reply = self
+            .call(Command::Negotiate, &proto::negotiate_body(client_guid), 0)
+            .await?;
+        Self::expect_success(&Self::header(&reply)?, "negotiate")?;
+        let negotiated = proto::parse_negotiate(&reply)
+            .ok_or(SmbError::Protocol("malformed negotiate response"))?;
+        if !DIALECTS.contains(&negotiated.dialect) {
+            return Err(SmbError::Unsupported(
+                "server picked an unknown dialect",
+            ));
+        }
+
+        self.dialect = negotiated.dialect;
+        self.large_mtu = negotiated.dialect >= DIALECT_210
+            && negotiated.capabilities & CAP_LARGE_MTU != 0;
+        let cap = if self.large_mtu {
+            MAX_READ_SIZE
+        } else {
+            CREDIT_UNIT
+        };
+        self.max_read = negotiated.max_read_size.clamp(1, cap);
+        self.signing_required =
+            negotiated.security_mode & SECURITY_MODE_SIGNING_REQUIRED != 0;
+        Ok(())
+    }
+
+    async fn session_setup(
+        &mut self,
+        target: &SmbTarget,
+    ) -> Result<(), SmbError> {
+        let init = ntlm::spnego_init(&ntlm::negotiate_message());
+        let reply = self
+            .call(Command::SessionSetup, &proto::session_setup_body(&init), 1)
+            .await?;
+        let header = Self::header(&reply)?;
+        if header.status != STATUS_MORE_PROCESSING_REQUIRED {
+            return Err(SmbError::Status {
+                command: "session_setup",
+                status: header.status,
+            });
+        }
+        self.session_id = header.session_id;
+        let challenge = proto::parse_session_setup(&reply)
+            .and_then(|(_, blob)| ntlm::parse_challenge(blob))
+            .ok_or(SmbError::Protocol("missing NTLM challenge"))?;
+
+        let auth = ntlm::authenticate_message(
+            &challenge,
+            &target.username,
+            &target.password,
+            &target.domain,
+        );
+        let token = ntlm::spnego_response(&auth.message);
+        let reply = self
+            .call(Command::SessionSetup, &proto::session_setup_body(&token), 1)
+            .await?;
+        Self::expect_success(&Self::header(&reply)?, "session_setup")?;
+        let (flags, _) = proto::parse_session_setup(&reply)
+            .ok_or(SmbError::Protocol("malformed session setup response"))?;
+        if flags & SESSION_FLAG_ENCRYPT_DATA != 0 {
+            return Err(SmbError::Unsupported("session requires encryption"));
+        }
+
+        let anonymous =
+            flags & (SESSION_FLAG_IS_GUEST | SESSION_FLAG_IS_NULL) != 0;
+        if self.signing_required && !anonymous {
+            let session_key = auth
+                .session_key
+                .ok_or(SmbError::Unsupported("signing without credentials"))?;
+            self.signer = Some(Signer::new(self.dialect, &session_key));
+        }
+        Ok(())
+    }
+
+    async fn tree_connect(
+        &mut self,
+        target: &SmbTarget,
+    ) -> Result<(), SmbError> {
+        let unc = format!("\\\\{}\\{}", target.host, target.share);
+        let reply = self
+            .call(Command::TreeConnect, &proto::tree_connect_body(&unc), 1)
+            .await?;
+        let header = Self::header(&reply)?;
+        if proto::is_not_found(header.status) {
+            return Err(SmbError::NotFound(unc));
+        }
+        Self::expect_success(&header, "tree_connect")?;
+        let share_flags = proto::parse_tree_connect(&reply)
+            .ok_or(SmbError::Protocol("malformed tree connect response"))?;
+        if share_flags & SHARE_FLAG_ENCRYPT_DATA != 0 {
+            return Err(SmbError::Unsupported("share requires encryption"));
+        }
+        self.tree_id = header.tree_id;
+        Ok(())
+    }
+
+    fn header(reply: &[u8]) -> Result<ResponseHeader, SmbError> {
+        ResponseHeader::parse(reply)
+            .ok_or(SmbError::Protocol("malformed response header"))
+    }
+
+    fn expect_success(
+        header: &ResponseHeader,
+        command: &'static str,
+    ) -> Result<(), SmbError> {
+        if header.status == STATUS_SUCCESS {
+            Ok(())
+        } else {
+            Err(SmbError::Status {
+                command,
+                status: header.status,
+            })
+        }
+    }
+
+    /// Sends one request and waits for its final response, within the
+    /// session timeout.
+    async fn call(
+        &mut self,
+        command: Command,
+        body: &[u8],
+        credit_charge: u16,
+    ) -> Result<Vec<u8>, SmbError> {
+        let timeout = self.timeout;
+        tokio::time::timeout(
+            timeout,
+            self.call_inner(command, body, credit_charge),
+        )
+        .await
+        .map_err(|_| SmbError::Timeout(timeout))?
+    }
+
+    async fn call_inner(
+        &mut self,
+        command: Command,
+        body: &[u8],
+        credit_charge: u16,
+    ) -> Result<Vec<u8>, SmbError> {
+        // SMB 2.0.2 has no multi-credit requests and sends a zero charge.
+        let charge = if self.dialect <= DIALECT_202 {
+            0
+        } else {
+            credit_charge
+        };
+        let consumed = u64::from(charge.max(1));
+        let message_id = self.message_id;
+        self.message_id += consumed;
+        self.credits = self.credits.saturating_sub(consumed as u32);
+
+        let mut message = proto::request(
+            command,
+            RequestHeader {
+                credit_charge: charge,
+                credit_request: CREDIT_REQUEST,
+                message_id,
+                tree_id: self.tree_id,
+                session_id: self.session_id,
+            },
+            body,
+        );
+        if let Some(signer) = &self.signer {
+            signer.sign(&mut message);
+        }
+        self.write_frame(&message).await?;
+
+        loop {
+            let reply = self.read_frame().await?;
+            let header = Self::header(&reply)?;
+            // Oplock breaks and stray replies carry other message IDs.
+            if header.message_id != message_id {
+                continue;
+            }
+            self.credits += u32::from(header.credit_response);
+            if header.status == STATUS_PENDING && header.is_async() {
+                continue;
+            }
+            return Ok(reply);
+        }
+    }
+
+    async fn write_frame(&mut self, message: &[u8]) -> Result<(), SmbError> {
+        let len = u32::try_from(message.len())
+            .ok()
+            .filter(|len| *len < 1 << 24)
+            .ok_or(SmbError::Protocol("request too large"))?;
+        let mut frame = Vec::with_capacity(4 + message.len());
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(message);
+        self.stream.write_all(&frame).await?;
+        Ok(())
+    }
+
+    async fn read_frame(&mut self) -> Result<Vec<u8>, SmbError> {
+        let mut prefix = [0u8; 4];
+        self.stream.read_exact(&mut prefix).await?;
+        let len = (u32::from_be_bytes(prefix) & 0x00ff_ffff) as usize;
+        if prefix[0] != 0 || len > MAX_FRAME_LEN {
+            return Err(SmbError::Protocol("bad session frame"));
+        }
+        let mut message = vec![0u8; len];
+        self.stream.read_exact(&mut message).await?;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    pub(crate) fn smb_node(base_url: &str, port: &str) -> BackendNode {
+        BackendNode {
+            name: "nas".into(),
+            backend_type: super::super::BACKEND_TYPE.into(),
+            pattern: String::new(),
+            pattern_regex: None,
+            base_url: base_url.into(),
+            port: port.into(),
+            path: "/movies".into(),
+            priority: 0,
+            proxy_mode: "proxy".into(),
+            client_speed_limit_kbs: 0,
+            client_burst_speed_kbs: 0,
+            path_rewrites: vec![],
+            anti_reverse_proxy: Default::default(),
+            path_rewriter_cache: vec![],
+            uuid: String::new(),
+            disk: None,
+            open_list: None,
+            direct_link: None,
+            google_drive: None,
+            webdav: None,
+            smb: Some(SmbConfig {
+                share: "media".into(),
+                username: "alice".into(),
+                password: "secret".into(),
+                timeout_secs: 2,
+                reconnect_attempts: 1,
+                ..Default::default()
+            }),
+            resilience: None,
+        }
+    }
+
+    #[test]
+    fn target_parses_host_port_and_root() {
+        let target = SmbTarget::from_node(&smb_node("smb://NAS.local/", ""))
+            .expect("target");
+        assert_eq!(target.host, "NAS.local");
+        assert_eq!(target.port, DEFAULT_PORT);
+        assert_eq!(target.share, "media");
+        assert_eq!(target.file_path("/2024/a b.mkv"), "movies\\2024\\a b.mkv");
+
+        let target = SmbTarget::from_node(&smb_node("//10.0.0.2:1445", ""))
+            .expect("target");
+        assert_eq!((target.host.as_str(), target.port), ("10.0.0.2", 1445));
+        let target = SmbTarget::from_node(&smb_node("10.0.0.2", "4455"))
+            .expect("target");
+        assert_eq!(target.port, 4455);
+
+        assert!(SmbTarget::from_node(&smb_node("smb://", "")).is_err());
+        let mut node = smb_node("nas", "");
+        node.smb = None;
+        assert!(SmbTarget::from_node(&node).is_err());
+    }
+
+    /// Minimal SMB 2.1 server: accepts any NTLM logon and serves `files`
+    /// from memory, so the client can be exercised without Samba.
+    pub(crate) async fn spawn_fake_server(
+        files: HashMap<String, Vec<u8>>,
+    ) -> (u16, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("addr").port();
+        let handle = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    break;
+                };
+                let files = files.clone();
+                tokio::spawn(fake_connection(stream, files));
+            }
+        });
+        (port, handle)
+    }
+
+    async fn fake_connection(
+        mut stream: TcpStream,
+        files: HashMap<String, Vec<u8>>,
+    ) {
+        let mut open: HashMap<[u8; 16], Vec<u8>> = HashMap::new();
+        let mut next_handle = 1u8;
+        loop {
+            let mut prefix = [0u8; 4];
+            if stream.read_exact(&mut prefix).await.is_err() {
+                return;
+            }
+            let len = (u32::from_be_bytes(prefix) & 0x00ff_ffff) as usize;
+            let mut request = vec![0u8; len];
+            if stream.read_exact(&mut request).await.is_err() {
+                return;
+            }
+            let command = proto::u16_at(&request, 12).unwrap_or_default();
+            let body = &request[proto::HEADER_LEN..];
+            let (status, reply_body): (u32, Vec<u8>) = match command {
+                0x0000 => {
+                    let mut out = vec![0u8; 64];
+                    out[..2].copy_from_slice(&65u16.to_le_bytes());
+                    out[4..6].copy_from_slice(&DIALECT_210.to_le_bytes());
+                    out[24..28].copy_from_slice(&CAP_LARGE_MTU.to_le_bytes());
+                    out[32..36].copy_from_slice(&(256 * 1024u32).to_le_bytes());
+                    (STATUS_SUCCESS, out)
+                }
+                0x0001 => {
+                    let is_auth = request
+                        .windows(8)
+                        .position(|w| w == b"NTLMSSP\0")
+                        .and_then(|at| proto::u32_at(&request, at + 8))
+                        == Some(3);
+                    if is_auth {
+                        let mut out = vec![0u8; 8];
+                        out[..2].copy_from_slice(&9u16.to_le_bytes());
+                        (STATUS_SUCCESS, out)
+                    } else {
+                        let challenge = fake_challenge();
+                        let mut out = vec![0u8; 8];
+                        out[..2].copy_from_slice(&9u16.to_le_bytes());
+                        out[4..6].copy_from_slice(&72u16.to_le_bytes());
+                        out[6..8].copy_from_slice(
+                            &(challenge.len() as u16).to_le_bytes(),
+                        );
+                        out.extend_from_slice(&challenge);
+                        (STATUS_MORE_PROCESSING_REQUIRED, out)
+                    }
+                }
+                0x0002 => (STATUS_SUCCESS, 4u16.to_le_bytes().to_vec()),
+                0x0003 => {
+                    let mut out = vec![0u8; 16];
+                    out[..2].copy_from_slice(&16u16.to_le_bytes());
+                    (STATUS_SUCCESS, out)
+                }
+                0x0005 => {
+                    let offset = usize::from(
+                        proto::u16_at(body, 44).unwrap_or_default(),
+                    );
+                    let name_len = usize::from(
+                        proto::u16_at(body, 46).unwrap_or_default(),
+                    );
+                    let units: Vec<u16> = request[offset..offset + name_len]
+                        .chunks(2)
+                        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                        .collect();
+                    let name = String::from_utf16_lossy(&units);
+                    match files.get(&name) {
+                        Some(data) => {
+                            let handle = [next_handle; 16];
+                            next_handle += 1;
+                            open.insert(handle, data.clone());
+                            let mut out = vec![0u8; 88];
+                            out[..2].copy_from_slice(&89u16.to_le_bytes());
+                            out[48..56].copy_from_slice(
+                                &(data.len() as u64).to_le_bytes(),
+                            );
+                            out[64..80].copy_from_slice(&handle);
+                            (STATUS_SUCCESS, out)
+                        }
+                        None => (0xc000_0034, vec![9, 0, 0, 0, 0, 0, 0, 0]),
+                    }
+                }
+                0x0006 => {
+                    let mut out = vec![0u8; 60];
+                    out[..2].copy_from_slice(&60u16.to_le_bytes());
+                    (STATUS_SUCCESS, out)
+                }
+                0x0008 => {
+                    let length =
+                        proto::u32_at(body, 4).unwrap_or_default() as usize;
+                    let offset =
+                        proto::u64_at(body, 8).unwrap_or_default() as usize;
+                    let handle: [u8; 16] =
+                        body[16..32].try_into().unwrap_or_default();
+                    let data = open.get(&handle).cloned().unwrap_or_default();
+                    if offset >= data.len() {
+                        (STATUS_END_OF_FILE, vec![9, 0, 0, 0, 0, 0, 0, 0])
+                    } else {
+                        let end = (offset + length).min(data.len());
+                        let mut out = vec![0u8; 16];
+                        out[..2].copy_from_slice(&17u16.to_le_bytes());
+                        out[2] = 80;
+                        out[4..8].copy_from_slice(
+                            &((end - offset) as u32).to_le_bytes(),
+                        );
+                        out.extend_from_slice(&data[offset..end]);
+                        (STATUS_SUCCESS, out)
+                    }
+                }
+                _ => (0xc000_0002, vec![9, 0, 0, 0, 0, 0, 0, 0]),
+            };
+
+            let mut reply = request[..proto::HEADER_LEN].to_vec();
+            reply[8..12].copy_from_slice(&status.to_le_bytes());
+            reply[14..16].copy_from_slice(&64u16.to_le_bytes());
+            reply[16] |= 0x01;
+            reply[36..40].copy_from_slice(&7u32.to_le_bytes());
+            reply[40..48].copy_from_slice(&0x42u64.to_le_bytes());
+            reply.extend_from_slice(&reply_body);
+            let mut frame = (reply.len() as u32).to_be_bytes().to_vec();
+            frame.extend_from_slice(&reply);
+            if stream.write_all(&frame).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn fake_challenge() -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"NTLMSSP\0");
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&[0; 8]);
+        out.extend_from_slice(&0xe28a_8235u32.to_le_bytes());
+        out.extend_from_slice(&[0x11; 8]);
+        out.extend_from_slice(&[0; 8]);
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&56u32.to_le_bytes());
+        out.extend_from_slice(&[0; 8]);
+        out.extend_from_slice(&[0; 4]);
+        out
+    }
+
+    #[tokio::test]
+    async fn session_reads_ranges_from_fake_server() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let files =
+            HashMap::from([("movies\\film.mkv".to_string(), data.clone())]);
+        let (port, server) = spawn_fake_server(files).await;
+        let target =
+            SmbTarget::from_node(&smb_node("127.0.0.1", &port.to_string()))
+                .expect("target");
+
+        let mut session = SmbSession::connect(&target).await.expect("connect");
+        assert_eq!(session.dialect(), DIALECT_210);
+        let file = session
+            .open(&target.file_path("/film.mkv"))
+            .await
+            .expect("open");
+        assert_eq!(file.size, data.len() as u64);
+        assert!(!file.is_directory);
+
+        let chunk = session.read(&file, 70_000, 100_000).await.expect("read");
+        assert_eq!(&chunk[..], &data[70_000..170_000]);
+        let tail = session.read(&file, 199_990, 100).await.expect("tail");
+        assert_eq!(&tail[..], &data[199_990..]);
+        let eof = session.read(&file, 200_000, 10).await.expect("eof");
+        assert!(eof.is_empty());
+        session.close(&file).await.expect("close");
+
+        let missing = session.open(&target.file_path("/gone.mkv")).await;
+        assert!(matches!(missing, Err(SmbError::NotFound(_))));
+        session.logoff().await;
+        server.abort();
+    }
+
+    /// Against a real server, e.g.
+    /// `docker run -p 1445:445 dperson/samba -u "alice;secret" -s "media;/share;yes;no;no;alice"`
+    /// with `EMBYSTREAM_SMB_TEST=127.0.0.1:1445/media/alice/secret/path/to/file`.
+    #[tokio::test]
+    #[ignore = "needs a Samba server, see EMBYSTREAM_SMB_TEST"]
+    async fn session_reads_from_samba() {
+        let Ok(spec) = std::env::var("EMBYSTREAM_SMB_TEST") else {
+            return;
+        };
+        let mut parts = spec.splitn(5, '/');
+        let (
+            Some(address),
+            Some(share),
+            Some(user),
+            Some(password),
+            Some(path),
+        ) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        )
+        else {
+            panic!(
+                "EMBYSTREAM_SMB_TEST must be host:port/share/user/password/path"
+            );
+        };
+        let (host, port) = address.split_once(':').unwrap_or((address, "445"));
+        let mut node = smb_node(host, port);
+        node.path = String::new();
+        if let Some(cfg) = node.smb.as_mut() {
+            cfg.share = share.into();
+            cfg.username = user.into();
+            cfg.password = password.into();
+            cfg.timeout_secs = 10;
+        }
+        let target = SmbTarget::from_node(&node).expect("target");
+        let mut session = SmbSession::connect(&target).await.expect("connect");
+        let file = session.open(&target.file_path(path)).await.expect("open");
+        let head = session.read(&file, 0, 4096).await.expect("read");
+        assert_eq!(head.len() as u64, file.size.min(4096));
+        session.close(&file).await.expect("close");
+    }
+}
@@ -0,0 +1,11 @@
+//! String and numeric constants for the Smb backend type.
+
+pub const BACKEND_TYPE: &str = "Smb";
+
+pub const DEFAULT_PORT: u16 = 445;
+
+/// Largest single READ requested even when the server allows more.
+pub const MAX_READ_SIZE: u32 = 1024 * 1024;
+
+/// Idle sessions kept per node for the next stream.
+pub const MAX_IDLE_SESSIONS: usize = 4;
@@ -0,0 +1,13 @@
+//! Userspace SMB2/3 client for `Smb` backend nodes: shares are read over
+//! TCP without a kernel cifs mount, so a sleeping NAS only times out the
+//! streams that touch it.
+
+pub mod client;
+pub mod constants;
+mod ntlm;
+pub mod pool;
+mod proto;
+
+pub use client::{SmbError, SmbFile, SmbSession, SmbTarget};
+pub use constants::{BACKEND_TYPE, DEFAULT_PORT};
+pub use pool::{SmbReader, SmbSessionPool};
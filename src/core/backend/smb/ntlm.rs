@@ -0,0 +1,340 @@
+//! NTLMv2 (MS-NLMP) wrapped in SPNEGO, just enough for an SMB2 session
+//! setup. No key exchange and no MIC: the session key is the NTLMv2
+//! session base key.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use md4::{Digest as _, Md4};
+
+use super::proto::{utf16le, write_u16, write_u32};
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_SIGN: u32 = 0x0000_0010;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ANONYMOUS: u32 = 0x0000_0800;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+const NEGOTIATE_TARGET_INFO: u32 = 0x0080_0000;
+const NEGOTIATE_VERSION: u32 = 0x0200_0000;
+const NEGOTIATE_128: u32 = 0x2000_0000;
+const NEGOTIATE_56: u32 = 0x8000_0000;
+
+const CLIENT_FLAGS: u32 = NEGOTIATE_UNICODE
+    | REQUEST_TARGET
+    | NEGOTIATE_SIGN
+    | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN
+    | NEGOTIATE_EXTENDED_SESSIONSECURITY
+    | NEGOTIATE_TARGET_INFO
+    | NEGOTIATE_VERSION
+    | NEGOTIATE_128
+    | NEGOTIATE_56;
+
+/// Windows 10 / NTLM revision 15, as every modern client sends.
+const VERSION: [u8; 8] = [10, 0, 0x61, 0x58, 0, 0, 0, 15];
+
+const AV_EOL: u16 = 0;
+const AV_TIMESTAMP: u16 = 7;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01.
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+const SPNEGO_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+const NTLMSSP_OID: &[u8] =
+    &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a];
+
+/// Fields of the server's CHALLENGE_MESSAGE used by the response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Challenge {
+    flags: u32,
+    server_challenge: [u8; 8],
+    target_info: Vec<u8>,
+}
+
+/// AUTHENTICATE_MESSAGE plus the key that signs the session, when there is
+/// one (anonymous logons have none).
+pub(super) struct Authenticate {
+    pub(super) message: Vec<u8>,
+    pub(super) session_key: Option<[u8; 16]>,
+}
+
+pub(super) fn negotiate_message() -> Vec<u8> {
+    let mut out = Vec::with_capacity(40);
+    out.extend_from_slice(SIGNATURE);
+    write_u32(&mut out, 1);
+    write_u32(&mut out, CLIENT_FLAGS);
+    out.extend_from_slice(&[0; 16]);
+    out.extend_from_slice(&VERSION);
+    out
+}
+
+/// Finds the NTLMSSP challenge inside a SPNEGO (or raw) security blob.
+pub(super) fn parse_challenge(blob: &[u8]) -> Option<Challenge> {
+    let start = blob
+        .windows(SIGNATURE.len())
+        .position(|window| window == SIGNATURE)?;
+    let msg = &blob[start..];
+    if msg.len() < 48 || u32_at(msg, 8)? != 2 {
+        return None;
+    }
+    let flags = u32_at(msg, 20)?;
+    let server_challenge = msg.get(24..32)?.try_into().ok()?;
+    let info_len = usize::from(u16_at(msg, 40)?);
+    let info_offset = u32_at(msg, 44)? as usize;
+    let target_info = msg
+        .get(info_offset..info_offset.checked_add(info_len)?)?
+        .to_vec();
+    Some(Challenge {
+        flags,
+        server_challenge,
+        target_info,
+    })
+}
+
+/// `NTOWFv2`: keyed MD5 of the upper-cased user and the domain, under the
+/// MD4 hash of the password.
+fn ntowf_v2(user: &str, password: &str, domain: &str) -> [u8; 16] {
+    let nt_hash: [u8; 16] = Md4::digest(utf16le(password)).into();
+    let identity = utf16le(&format!("{}{}", user.to_uppercase(), domain));
+    hmac_md5(&nt_hash, &[&identity])
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..16].copy_from_slice(&md5::compute(key).0);
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = md5::Context::new();
+    inner.consume(block.map(|b| b ^ 0x36));
+    for part in parts {
+        inner.consume(part);
+    }
+    let mut outer = md5::Context::new();
+    outer.consume(block.map(|b| b ^ 0x5c));
+    outer.consume(inner.finalize().0);
+    outer.finalize().0
+}
+
+fn find_av_pair(target_info: &[u8], id: u16) -> Option<&[u8]> {
+    let mut rest = target_info;
+    while rest.len() >= 4 {
+        let av_id = u16::from_le_bytes([rest[0], rest[1]]);
+        let len = usize::from(u16::from_le_bytes([rest[2], rest[3]]));
+        let value = rest.get(4..4 + len)?;
+        if av_id == AV_EOL {
+            return None;
+        }
+        if av_id == id {
+            return Some(value);
+        }
+        rest = &rest[4 + len..];
+    }
+    None
+}
+
+fn filetime_now() -> [u8; 8] {
+    let since_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let ticks = (since_unix.as_secs() + FILETIME_UNIX_OFFSET_SECS) * 10_000_000
+        + u64::from(since_unix.subsec_nanos() / 100);
+    ticks.to_le_bytes()
+}
+
+/// NTLMv2 response (`NTProofStr || temp`) and the session base key.
+fn ntlm_v2_response(
+    response_key: &[u8; 16],
+    challenge: &Challenge,
+    timestamp: [u8; 8],
+    client_challenge: [u8; 8],
+) -> (Vec<u8>, [u8; 16]) {
+    let mut temp = Vec::with_capacity(28 + challenge.target_info.len() + 4);
+    temp.extend_from_slice(&[1, 1, 0, 0, 0, 0, 0, 0]);
+    temp.extend_from_slice(&timestamp);
+    temp.extend_from_slice(&client_challenge);
+    temp.extend_from_slice(&[0; 4]);
+    temp.extend_from_slice(&challenge.target_info);
+    temp.extend_from_slice(&[0; 4]);
+
+    let proof = hmac_md5(response_key, &[&challenge.server_challenge, &temp]);
+    let session_base_key = hmac_md5(response_key, &[&proof]);
+    let mut response = proof.to_vec();
+    response.extend_from_slice(&temp);
+    (response, session_base_key)
+}
+
+/// Builds the AUTHENTICATE_MESSAGE. An empty `user` logs on anonymously.
+pub(super) fn authenticate_message(
+    challenge: &Challenge,
+    user: &str,
+    password: &str,
+    domain: &str,
+) -> Authenticate {
+    let anonymous = user.is_empty();
+    let (lm_response, nt_response, session_key) = if anonymous {
+        (vec![0u8], Vec::new(), None)
+    } else {
+        let key = ntowf_v2(user, password, domain);
+        let timestamp = find_av_pair(&challenge.target_info, AV_TIMESTAMP)
+            .and_then(|value| value.try_into().ok())
+            .unwrap_or_else(filetime_now);
+        let (nt, session_key) = ntlm_v2_response(
+            &key,
+            challenge,
+            timestamp,
+            rand::random::<[u8; 8]>(),
+        );
+        (vec![0u8; 24], nt, Some(session_key))
+    };
+
+    let mut flags = CLIENT_FLAGS & (challenge.flags | NEGOTIATE_VERSION);
+    if anonymous {
+        flags = (flags | NEGOTIATE_ANONYMOUS) & !NEGOTIATE_SIGN;
+    }
+
+    let domain = utf16le(domain);
+    let user = utf16le(user);
+    let workstation: Vec<u8> = Vec::new();
+    let payloads: [&[u8]; 6] = [
+        &lm_response,
+        &nt_response,
+        &domain,
+        &user,
+        &workstation,
+        &[],
+    ];
+
+    const HEADER_LEN: usize = 88;
+    let mut out = Vec::with_capacity(
+        HEADER_LEN + payloads.iter().map(|p| p.len()).sum::<usize>(),
+    );
+    out.extend_from_slice(SIGNATURE);
+    write_u32(&mut out, 3);
+    let mut offset = HEADER_LEN;
+    for payload in payloads {
+        write_u16(&mut out, payload.len() as u16);
+        write_u16(&mut out, payload.len() as u16);
+        write_u32(&mut out, offset as u32);
+        offset += payload.len();
+    }
+    write_u32(&mut out, flags);
+    out.extend_from_slice(&VERSION);
+    out.extend_from_slice(&[0; 16]);
+    for payload in payloads {
+        out.extend_from_slice(payload);
+    }
+
+    Authenticate {
+        message: out,
+        session_key,
+    }
+}
+
+fn der_length(out: &mut Vec<u8>, len: usize) {
+    match len {
+        0..=0x7f => out.push(len as u8),
+        0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+        _ => out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+}
+
+fn der(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 4);
+    out.push(tag);
+    der_length(&mut out, body.len());
+    out.extend_from_slice(body);
+    out
+}
+
+/// `NegTokenInit` offering NTLMSSP with the NEGOTIATE_MESSAGE attached.
+pub(super) fn spnego_init(token: &[u8]) -> Vec<u8> {
+    let mech_types = der(0xa0, &der(0x30, &der(0x06, NTLMSSP_OID)));
+    let mech_token = der(0xa2, &der(0x04, token));
+    let init = der(0xa0, &der(0x30, &[mech_types, mech_token].concat()));
+    der(0x60, &[der(0x06, SPNEGO_OID), init].concat())
+}
+
+/// `NegTokenResp` carrying the AUTHENTICATE_MESSAGE.
+pub(super) fn spnego_response(token: &[u8]) -> Vec<u8> {
+    der(0xa1, &der(0x30, &der(0xa2, &der(0x04, token))))
+}
+
+fn u16_at(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// MS-NLMP 4.2.4 NTLMv2 authentication example.
+    fn spec_challenge() -> Challenge {
+        let mut target_info = Vec::new();
+        for (id, value) in [(2u16, "Domain"), (1u16, "Server")] {
+            let value = utf16le(value);
+            write_u16(&mut target_info, id);
+            write_u16(&mut target_info, value.len() as u16);
+            target_info.extend_from_slice(&value);
+        }
+        target_info.extend_from_slice(&[0; 4]);
+        Challenge {
+            flags: 0xe28a_8233,
+            server_challenge: [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+            target_info,
+        }
+    }
+
+    #[test]
+    fn ntlm_v2_matches_ms_nlmp_example() {
+        let key = ntowf_v2("User", "Password", "Domain");
+        assert_eq!(hex(&key), "0c868a403bfd7a93a3001ef22ef02e3f");
+
+        let (response, session_key) =
+            ntlm_v2_response(&key, &spec_challenge(), [0; 8], [0xaa; 8]);
+        assert_eq!(hex(&response[..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+        assert_eq!(hex(&session_key), "8de40ccadbc14a82f15cb0ad0de95ca3");
+    }
+
+    #[test]
+    fn challenge_is_found_inside_spnego_blob() {
+        let spec = spec_challenge();
+        let mut message = Vec::new();
+        message.extend_from_slice(SIGNATURE);
+        write_u32(&mut message, 2);
+        message.extend_from_slice(&[0; 8]);
+        write_u32(&mut message, spec.flags);
+        message.extend_from_slice(&spec.server_challenge);
+        message.extend_from_slice(&[0; 8]);
+        write_u16(&mut message, spec.target_info.len() as u16);
+        write_u16(&mut message, spec.target_info.len() as u16);
+        write_u32(&mut message, 56);
+        message.extend_from_slice(&VERSION);
+        message.extend_from_slice(&spec.target_info);
+
+        let blob = der(0xa1, &der(0x30, &der(0xa2, &der(0x04, &message))));
+        assert_eq!(parse_challenge(&blob), Some(spec));
+        assert_eq!(parse_challenge(b"not ntlm"), None);
+    }
+
+    #[test]
+    fn anonymous_authenticate_has_no_session_key() {
+        let auth = authenticate_message(&spec_challenge(), "", "", "");
+        assert!(auth.session_key.is_none());
+        let flags = u32_at(&auth.message, 60).expect("flags");
+        assert_ne!(flags & NEGOTIATE_ANONYMOUS, 0);
+        assert_eq!(u16_at(&auth.message, 20), Some(0));
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use dashmap::DashMap;
+
+use super::{
+    client::{SmbError, SmbFile, SmbSession, SmbTarget},
+    constants::MAX_IDLE_SESSIONS,
+};
+use crate::{SMB_LOGGER_DOMAIN, debug_log, warn_log};
+
+/// Idle, already authenticated sessions per [`SmbTarget::key`].
+#[derive(Default)]
+pub struct SmbSessionPool {
+    idle: DashMap<String, Vec<SmbSession>>,
+}
+
+impl SmbSessionPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn checkout(
+        &self,
+        target: &SmbTarget,
+    ) -> Result<SmbSession, SmbError> {
+        let key = target.key();
+        let idle = self.idle.get_mut(&key).and_then(|mut list| list.pop());
+        if let Some(session) = idle {
+            return Ok(session);
+        }
+        debug_log!(
+            SMB_LOGGER_DOMAIN,
+            "smb_connect host={} port={} share={}",
+            target.host,
+            target.port,
+            target.share
+        );
+        SmbSession::connect(target).await
+    }
+
+    fn checkin(&self, target: &SmbTarget, session: SmbSession) {
+        let mut list = self.idle.entry(target.key()).or_default();
+        if list.len() < MAX_IDLE_SESSIONS {
+            list.push(session);
+        }
+    }
+
+    /// A failed request usually means the server went away, so every idle
+    /// session to it is stale too.
+    fn discard_idle(&self, target: &SmbTarget) {
+        self.idle.remove(&target.key());
+    }
+
+    /// Drops every idle session, e.g. after a reload changed the
+    /// credentials they were authenticated with.
+    pub fn clear(&self) {
+        self.idle.clear();
+    }
+
+    /// Size and kind of `path` without keeping it open.
+    pub async fn stat(
+        self: &Arc<Self>,
+        target: Arc<SmbTarget>,
+        path: &str,
+    ) -> Result<SmbFile, SmbError> {
+        let (reader, file) =
+            SmbReader::open(self.clone(), target, path.to_string()).await?;
+        reader.finish().await;
+        Ok(file)
+    }
+}
+
+/// Reads one file, reconnecting and reopening it when a request fails or
+/// times out, up to the node's `reconnect_attempts`.
+pub struct SmbReader {
+    pool: Arc<SmbSessionPool>,
+    target: Arc<SmbTarget>,
+    path: String,
+    current: Option<(SmbSession, SmbFile)>,
+}
+
+impl SmbReader {
+    pub async fn open(
+        pool: Arc<SmbSessionPool>,
+        target: Arc<SmbTarget>,
+        path: String,
+    ) -> Result<(Self, SmbFile), SmbError> {
+        let mut reader = Self {
+            pool,
+            target,
+            path,
+            current: None,
+        };
+        let file = reader
+            .retry(|reader| Box::pin(reader.ensure_open()))
+            .await?;
+        Ok((reader, file))
+    }
+
+    pub async fn read_at(
+        &mut self,
+        offset: u64,
+        length: u32,
+    ) -> Result<Bytes, SmbError> {
+        self.retry(|reader| {
+            Box::pin(async move {
+                let file = reader.ensure_open().await?;
+                match reader.current.as_mut() {
+                    Some((session, _)) => {
+                        session.read(&file, offset, length).await
+                    }
+                    None => Err(SmbError::Protocol("session lost")),
+                }
+            })
+        })
+        .await
+    }
+
+    /// Closes the file and returns a healthy session to the pool.
+    pub async fn finish(mut self) {
+        if let Some((mut session, file)) = self.current.take() {
+            if session.close(&file).await.is_ok() {
+                self.pool.checkin(&self.target, session);
+            }
+        }
+    }
+
+    async fn ensure_open(&mut self) -> Result<SmbFile, SmbError> {
+        if let Some((_, file)) = self.current.as_ref() {
+            return Ok(*file);
+        }
+        let mut session = self.pool.checkout(&self.target).await?;
+        match session.open(&self.path).await {
+            Ok(file) => {
+                self.current = Some((session, file));
+                Ok(file)
+            }
+            Err(error) if error.is_not_found() => {
+                self.pool.checkin(&self.target, session);
+                Err(error)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn retry<T>(
+        &mut self,
+        mut op: impl for<'a> FnMut(
+            &'a mut Self,
+        ) -> std::pin::Pin<
+            Box<dyn Future<Output = Result<T, SmbError>> + Send + 'a>,
+        >,
+    ) -> Result<T, SmbError> {
+        let mut attempt = 0;
+        loop {
+            match op(self).await {
+                Ok(value) => return Ok(value),
+                Err(error)
+                    if error.is_not_found()
+                        || attempt >= self.target.reconnect_attempts =>
+                {
+                    return Err(error);
+                }
+                Err(error) => {
+                    attempt += 1;
+                    warn_log!(
+                        SMB_LOGGER_DOMAIN,
+                        "smb_reconnect host={} share={} path={} attempt={} error={}",
+                        self.target.host,
+                        self.target.share,
+                        self.path,
+                        attempt,
+                        error
+                    );
+                    self.current = None;
+                    self.pool.discard_idle(&self.target);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::core::backend::smb::client::tests::{
+        smb_node, spawn_fake_server,
+    };
+
+    #[tokio::test]
+    async fn reader_reuses_pooled_sessions_and_reports_missing_files() {
+        let data = vec![7u8; 1000];
+        let files = HashMap::from([("movies\\a.mkv".to_string(), data)]);
+        let (port, server) = spawn_fake_server(files).await;
+        let target = Arc::new(
+            SmbTarget::from_node(&smb_node("127.0.0.1", &port.to_string()))
+                .expect("target"),
+        );
+        let pool = Arc::new(SmbSessionPool::new());
+
+        let file = pool
+            .stat(target.clone(), &target.file_path("a.mkv"))
+            .await
+            .expect("stat");
+        assert_eq!(file.size, 1000);
+        assert_eq!(pool.idle.get(&target.key()).map(|l| l.len()), Some(1));
+
+        let (mut reader, _) = SmbReader::open(
+            pool.clone(),
+            target.clone(),
+            target.file_path("a.mkv"),
+        )
+        .await
+        .expect("open");
+        assert_eq!(pool.idle.get(&target.key()).map(|l| l.len()), Some(0));
+        assert_eq!(reader.read_at(990, 64).await.expect("read").len(), 10);
+        reader.finish().await;
+
+        let missing = pool.stat(target.clone(), "movies\\b.mkv").await;
+        assert!(matches!(missing, Err(SmbError::NotFound(_))));
+
+        pool.clear();
+        assert!(pool.idle.is_empty());
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn reader_gives_up_after_reconnect_attempts() {
+        let (port, server) = spawn_fake_server(HashMap::new()).await;
+        server.abort();
+        let _ = server.await;
+        let target = Arc::new(
+            SmbTarget::from_node(&smb_node("127.0.0.1", &port.to_string()))
+                .expect("target"),
+        );
+        let result = SmbReader::open(
+            Arc::new(SmbSessionPool::new()),
+            target,
+            "movies\\a.mkv".to_string(),
+        )
+        .await;
+        assert!(matches!(result, Err(SmbError::Io(_))));
+    }
+}
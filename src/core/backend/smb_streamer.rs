@@ -0,0 +1,313 @@
+use std::{io::Error as IoError, path::Path, sync::Arc};
+
+use bytes::Bytes;
+use hyper::{HeaderMap, StatusCode};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use super::{
+    fallback::{FallbackReason, FallbackResponder, StreamFailure},
+    local_streamer::LocalStreamer,
+    resilience::{CircuitBreakers, UpstreamPolicy},
+    response::Response,
+    result::Result as AppStreamResult,
+    smb::{SmbError, SmbReader, SmbTarget, constants::MAX_READ_SIZE},
+    types::{ClientInfo, ContentRange},
+};
+use crate::cache::RateLimiter;
+use crate::config::backend::BackendNode;
+use crate::{AppState, SMB_LOGGER_DOMAIN, error_log, info_log, warn_log};
+
+/// Chunks buffered between the SMB reader task and the response body.
+const CHUNK_BUFFER: usize = 4;
+
+pub(crate) struct SmbStreamer;
+
+impl SmbStreamer {
+    /// Streams `path` from the node's share. Missing files use the
+    /// `FileMissing` fallback; an unreachable server uses `UpstreamDown`.
+    pub async fn stream(
+        state: Arc<AppState>,
+        node: &BackendNode,
+        path: &str,
+        range_header: Option<String>,
+        client_info: ClientInfo,
+        client_headers: &HeaderMap,
+    ) -> Result<AppStreamResult, StatusCode> {
+        let target = SmbTarget::from_node(node).map_err(|error| {
+            error_log!(
+                SMB_LOGGER_DOMAIN,
+                "smb_target_invalid node={} error={}",
+                node.name,
+                error
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        let file_path = target.file_path(path);
+
+        let limiter = match client_info.id.as_deref() {
+            Some(client_id) if !client_id.is_empty() => {
+                match state.get_rate_limiter_cache(&node.uuid).await {
+                    Some(cache) => cache.fetch_limiter(client_id).await,
+                    None => RateLimiter::unlimited(),
+                }
+            }
+            _ => RateLimiter::unlimited(),
+        };
+
+        let (reader, file) = match SmbReader::open(
+            state.smb_sessions.clone(),
+            Arc::new(target),
+            file_path.clone(),
+        )
+        .await
+        {
+            Ok(opened) => {
+                state.circuit_breakers.record_success(&node.name);
+                opened
+            }
+            Err(error) => {
+                return Self::respond_failure(
+                    &state,
+                    node,
+                    &file_path,
+                    &error,
+                    client_headers,
+                )
+                .await;
+            }
+        };
+        if file.is_directory {
+            reader.finish().await;
+            warn_log!(
+                SMB_LOGGER_DOMAIN,
+                "smb_stream_directory node={} path={}",
+                node.name,
+                file_path
+            );
+            return FallbackResponder::respond(
+                &state,
+                StreamFailure::new(
+                    FallbackReason::FileMissing,
+                    StatusCode::NOT_FOUND,
+                ),
+                client_headers,
+            )
+            .await;
+        }
+
+        let (content_range, status_code) =
+            match LocalStreamer::optional_content_range(
+                range_header.as_deref(),
+                file.size,
+            ) {
+                Ok(resolved) => resolved,
+                Err(status) => {
+                    reader.finish().await;
+                    return Err(status);
+                }
+            };
+
+        info_log!(
+            SMB_LOGGER_DOMAIN,
+            "smb_stream node={} path={} status={} range={:?} device_id={:?}",
+            node.name,
+            file_path,
+            status_code.as_u16(),
+            content_range,
+            client_info.id
+        );
+
+        let (sender, receiver) = mpsc::channel(CHUNK_BUFFER);
+        if file.size > 0 {
+            tokio::spawn(Self::pump(
+                reader,
+                content_range,
+                sender,
+                state.circuit_breakers.clone(),
+                node.clone(),
+            ));
+        } else {
+            reader.finish().await;
+        }
+
+        let format = Path::new(path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+        let headers =
+            LocalStreamer::stream_headers(format, &content_range, status_code);
+
+        Ok(AppStreamResult::Stream(Response {
+            status: status_code,
+            headers,
+            body: LocalStreamer::rate_limited_body(
+                ReceiverStream::new(receiver),
+                limiter,
+            ),
+        }))
+    }
+
+    async fn respond_failure(
+        state: &Arc<AppState>,
+        node: &BackendNode,
+        file_path: &str,
+        error: &SmbError,
+        client_headers: &HeaderMap,
+    ) -> Result<AppStreamResult, StatusCode> {
+        let failure = if error.is_not_found() {
+            StreamFailure::new(
+                FallbackReason::FileMissing,
+                StatusCode::NOT_FOUND,
+            )
+        } else {
+            StreamFailure::new(
+                FallbackReason::UpstreamDown,
+                StatusCode::BAD_GATEWAY,
+            )
+        };
+        if failure.reason == FallbackReason::UpstreamDown {
+            state.circuit_breakers.record_failure(
+                &node.name,
+                &UpstreamPolicy::for_node(node),
+                error,
+            );
+        }
+        error_log!(
+            SMB_LOGGER_DOMAIN,
+            "smb_open_failed node={} path={} reason={} error={}",
+            node.name,
+            file_path,
+            failure.reason,
+            error
+        );
+        FallbackResponder::respond(state, failure, client_headers).await
+    }
+
+    /// Reads `range` in `MAX_READ_SIZE` chunks until it is done, the client
+    /// goes away or the share fails for good. Read failures count against
+    /// the node's circuit breaker.
+    async fn pump(
+        mut reader: SmbReader,
+        range: ContentRange,
+        sender: mpsc::Sender<Result<Bytes, IoError>>,
+        breakers: Arc<CircuitBreakers>,
+        node: BackendNode,
+    ) {
+        let mut offset = range.start;
+        while offset <= range.end {
+            let wanted = (range.end - offset + 1).min(MAX_READ_SIZE as u64);
+            let chunk = match reader.read_at(offset, wanted as u32).await {
+                Ok(chunk) if chunk.is_empty() => {
+                    let _ = sender
+                        .send(Err(IoError::other("smb file ended early")))
+                        .await;
+                    break;
+                }
+                Ok(chunk) => chunk,
+                Err(error) => {
+                    warn_log!(
+                        SMB_LOGGER_DOMAIN,
+                        "smb_read_failed offset={} error={}",
+                        offset,
+                        error
+                    );
+                    breakers.record_failure(
+                        &node.name,
+                        &UpstreamPolicy::for_node(&node),
+                        &error,
+                    );
+                    let _ = sender.send(Err(IoError::other(error))).await;
+                    break;
+                }
+            };
+            offset += chunk.len() as u64;
+            if sender.send(Ok(chunk)).await.is_err() {
+                break;
+            }
+        }
+        reader.finish().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use http_body_util::BodyExt;
+    use hyper::header;
+
+    use super::*;
+    use crate::{
+        core::backend::smb::client::tests::{smb_node, spawn_fake_server},
+        test_support::TestConfig,
+    };
+
+    async fn test_state() -> Arc<AppState> {
+        TestConfig::new().app_state().await
+    }
+
+    fn client() -> ClientInfo {
+        ClientInfo::new(Some("client-1".into()), None, None, None)
+    }
+
+    #[tokio::test]
+    async fn stream_serves_requested_range_from_share() {
+        let data: Vec<u8> = (0..3_000_000u32).map(|i| i as u8).collect();
+        let files =
+            HashMap::from([("movies\\a.mkv".to_string(), data.clone())]);
+        let (port, server) = spawn_fake_server(files).await;
+        let node = smb_node("127.0.0.1", &port.to_string());
+
+        let result = SmbStreamer::stream(
+            test_state().await,
+            &node,
+            "/a.mkv",
+            Some("bytes=1000-2099999".into()),
+            client(),
+            &HeaderMap::new(),
+        )
+        .await
+        .expect("stream");
+        let AppStreamResult::Stream(response) = result else {
+            panic!("expected stream response");
+        };
+        assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers[header::CONTENT_RANGE],
+            "bytes 1000-2099999/3000000"
+        );
+        let body = response.body.collect().await.expect("body").to_bytes();
+        assert_eq!(body.as_ref(), &data[1000..2_100_000]);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn stream_maps_missing_file_and_dead_server() {
+        let (port, server) = spawn_fake_server(HashMap::new()).await;
+        let node = smb_node("127.0.0.1", &port.to_string());
+        let missing = SmbStreamer::stream(
+            test_state().await,
+            &node,
+            "/missing.mkv",
+            None,
+            client(),
+            &HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(missing, Err(StatusCode::NOT_FOUND)));
+
+        server.abort();
+        let _ = server.await;
+        let down = SmbStreamer::stream(
+            test_state().await,
+            &node,
+            "/missing.mkv",
+            None,
+            client(),
+            &HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(down, Err(StatusCode::BAD_GATEWAY)));
+    }
+}
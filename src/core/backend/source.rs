@@ -19,4 +19,8 @@ pub(crate) enum Source {
     AccelRedirect {
         info: crate::core::redirect_info::AccelRedirectInfo,
     },
+    Smb {
+        path: String,
+        device_id: String,
+    },
 }
@@ -3,7 +3,11 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use hyper::{Method, Response, StatusCode, Uri, body::Incoming, header};
 
-use super::{result::Result as AppStreamResult, service::StreamService};
+use super::{
+    fallback::{FallbackReason, FallbackResponder, StreamFailure},
+    result::Result as AppStreamResult,
+    service::StreamService,
+};
 use crate::core::backend::webdav::ACCEL_REDIRECT_HEADER;
 use crate::{
     AppState, GATEWAY_LOGGER_DOMAIN, REMOTE_STREAMER_LOGGER_DOMAIN, debug_log,
@@ -27,26 +31,22 @@ const PLAYBACK_SESSION_ID_QUERY_KEY: &str = "session_id";
 
 #[derive(Clone)]
 pub struct StreamMiddleware {
-    backend_nodes: Vec<BackendNode>,
     stream_service: Arc<dyn StreamService>,
     state: Arc<AppState>,
 }
 
 impl StreamMiddleware {
     pub fn new(
-        mut backend_nodes: Vec<BackendNode>,
         stream_service: Arc<dyn StreamService>,
         state: Arc<AppState>,
     ) -> Self {
-        backend_nodes.sort_by_key(|node| node.priority);
         Self {
-            backend_nodes,
             stream_service,
             state,
         }
     }
 
-    fn find_matching_node<'a>(
+    pub(crate) fn find_matching_node<'a>(
         nodes: &'a [BackendNode],
         file_path: &str,
     ) -> Option<&'a BackendNode> {
@@ -95,6 +95,73 @@ impl StreamMiddleware {
             }
         })
     }
+
+    fn into_response(
+        result: Result<AppStreamResult, StatusCode>,
+    ) -> Response<BoxBodyType> {
+        match result {
+            Ok(service_result) => match service_result {
+                AppStreamResult::Stream(stream_response) => {
+                    match Response::builder()
+                        .status(stream_response.status)
+                        .body(stream_response.body)
+                    {
+                        Ok(mut response) => {
+                            *response.headers_mut() = stream_response.headers;
+                            response
+                        }
+                        Err(_) => ResponseBuilder::with_status_code(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                        ),
+                    }
+                }
+                AppStreamResult::Redirect(redirect_info) => {
+                    info_log!(
+                        REMOTE_STREAMER_LOGGER_DOMAIN,
+                        "Redirecting backend to {:?}",
+                        redirect_info.target_url
+                    );
+                    debug_log!(
+                        REMOTE_STREAMER_LOGGER_DOMAIN,
+                        "Redirecting backend headers {:?}",
+                        redirect_info.final_headers.clone()
+                    );
+                    // Signed and fallback targets change between requests,
+                    // so clients must not cache the redirect.
+                    ResponseBuilder::with_redirect(
+                        redirect_info.target_url.to_string().as_str(),
+                        StatusCode::FOUND,
+                        Some(redirect_info.final_headers),
+                    )
+                }
+                AppStreamResult::AccelRedirect(accel_redirect_info) => {
+                    let mut headers = accel_redirect_info.internal_headers;
+                    debug_log!(
+                        REMOTE_STREAMER_LOGGER_DOMAIN,
+                        "google_drive_accel_redirect_emit internal_path={}",
+                        Privacy::sanitize_google_drive_internal_path_for_log(
+                            &accel_redirect_info.internal_path
+                        )
+                    );
+                    if let Ok(value) = accel_redirect_info.internal_path.parse()
+                    {
+                        headers.insert(ACCEL_REDIRECT_HEADER, value);
+                        ResponseBuilder::with_headers(StatusCode::OK, headers)
+                    } else {
+                        error_log!(
+                            REMOTE_STREAMER_LOGGER_DOMAIN,
+                            "Invalid accel redirect path {:?}",
+                            accel_redirect_info.internal_path
+                        );
+                        ResponseBuilder::with_status_code(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                        )
+                    }
+                }
+            },
+            Err(status_code) => ResponseBuilder::with_status_code(status_code),
+        }
+    }
 }
 
 #[async_trait]
@@ -169,7 +236,16 @@ impl Middleware for StreamMiddleware {
 
         if !sign.is_valid() {
             error_log!(GATEWAY_LOGGER_DOMAIN, "Sign is expired or invalid");
-            return ResponseBuilder::with_status_code(StatusCode::GONE);
+            let result = FallbackResponder::respond(
+                &self.state,
+                StreamFailure::new(
+                    FallbackReason::SignExpired,
+                    StatusCode::GONE,
+                ),
+                &ctx.headers,
+            )
+            .await;
+            return Self::into_response(result);
         }
 
         let sign_uri = match &sign.uri {
@@ -183,15 +259,15 @@ impl Middleware for StreamMiddleware {
         };
 
         let file_path = Uri::to_path_or_url_string(&sign_uri);
+        let backend_nodes = self.state.get_backend_nodes().await;
         debug_log!(
             GATEWAY_LOGGER_DOMAIN,
             "Decrypted file path: '{}', searching among {} nodes",
             file_path,
-            self.backend_nodes.len()
+            backend_nodes.len()
         );
 
-        let matched_node =
-            Self::find_matching_node(&self.backend_nodes, &file_path);
+        let matched_node = Self::find_matching_node(&backend_nodes, &file_path);
 
         if let Some(node) = matched_node {
             debug_log!(
@@ -232,73 +308,7 @@ impl Middleware for StreamMiddleware {
             let result =
                 self.stream_service.handle_request(stream_request).await;
 
-            match result {
-                Ok(service_result) => match service_result {
-                    AppStreamResult::Stream(stream_response) => {
-                        match Response::builder()
-                            .status(stream_response.status)
-                            .body(stream_response.body)
-                        {
-                            Ok(mut response) => {
-                                *response.headers_mut() =
-                                    stream_response.headers;
-                                response
-                            }
-                            Err(_) => ResponseBuilder::with_status_code(
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                            ),
-                        }
-                    }
-                    AppStreamResult::Redirect(redirect_info) => {
-                        info_log!(
-                            REMOTE_STREAMER_LOGGER_DOMAIN,
-                            "Redirecting backend to {:?}",
-                            redirect_info.target_url
-                        );
-                        debug_log!(
-                            REMOTE_STREAMER_LOGGER_DOMAIN,
-                            "Redirecting backend headers {:?}",
-                            redirect_info.final_headers.clone()
-                        );
-                        ResponseBuilder::with_redirect(
-                            redirect_info.target_url.to_string().as_str(),
-                            StatusCode::MOVED_PERMANENTLY,
-                            Some(redirect_info.final_headers),
-                        )
-                    }
-                    AppStreamResult::AccelRedirect(accel_redirect_info) => {
-                        let mut headers = accel_redirect_info.internal_headers;
-                        debug_log!(
-                            REMOTE_STREAMER_LOGGER_DOMAIN,
-                            "google_drive_accel_redirect_emit internal_path={}",
-                            Privacy::sanitize_google_drive_internal_path_for_log(
-                                &accel_redirect_info.internal_path
-                            )
-                        );
-                        if let Ok(value) =
-                            accel_redirect_info.internal_path.parse()
-                        {
-                            headers.insert(ACCEL_REDIRECT_HEADER, value);
-                            ResponseBuilder::with_headers(
-                                StatusCode::OK,
-                                headers,
-                            )
-                        } else {
-                            error_log!(
-                                REMOTE_STREAMER_LOGGER_DOMAIN,
-                                "Invalid accel redirect path {:?}",
-                                accel_redirect_info.internal_path
-                            );
-                            ResponseBuilder::with_status_code(
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                            )
-                        }
-                    }
-                },
-                Err(status_code) => {
-                    ResponseBuilder::with_status_code(status_code)
-                }
-            }
+            Self::into_response(result)
         } else {
             warn_log!(
                 GATEWAY_LOGGER_DOMAIN,
@@ -313,3 +323,35 @@ impl Middleware for StreamMiddleware {
         Box::new(self.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::{HeaderMap, StatusCode, header};
+
+    use super::StreamMiddleware;
+    use crate::core::{
+        backend::result::Result as AppStreamResult, redirect_info::RedirectInfo,
+    };
+
+    #[test]
+    fn redirects_are_temporary() {
+        let response = StreamMiddleware::into_response(Ok(
+            AppStreamResult::Redirect(RedirectInfo {
+                target_url: "https://cdn.example.com/busy.mp4"
+                    .parse()
+                    .expect("uri"),
+                final_headers: HeaderMap::new(),
+            }),
+        ));
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers().get(header::LOCATION),
+            Some(
+                &"https://cdn.example.com/busy.mp4"
+                    .parse()
+                    .expect("location")
+            )
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Signed stream relay: redirect GET requests to another backend without decrypting `sign`.
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use hyper::{Method, Response, StatusCode, Uri, body::Incoming, header};
 
@@ -8,7 +10,8 @@ use super::constants::{
     backend_base_url_is_local_host,
 };
 use crate::{
-    GATEWAY_LOGGER_DOMAIN, config::backend::BackendNode, debug_log, warn_log,
+    AppState, GATEWAY_LOGGER_DOMAIN, config::backend::BackendNode, debug_log,
+    warn_log,
 };
 use crate::{
     core::sign::SignParams,
@@ -24,8 +27,9 @@ fn is_stream_relay_node(node: &BackendNode) -> bool {
         .eq_ignore_ascii_case(STREAM_RELAY_BACKEND_TYPE)
 }
 
-fn relay_nodes_sorted(mut nodes: Vec<BackendNode>) -> Vec<BackendNode> {
-    nodes.retain(is_stream_relay_node);
+fn relay_nodes_sorted(nodes: &[BackendNode]) -> Vec<&BackendNode> {
+    let mut nodes: Vec<_> =
+        nodes.iter().filter(|n| is_stream_relay_node(n)).collect();
     nodes.sort_by_key(|n| n.priority);
     nodes
 }
@@ -91,18 +95,12 @@ fn build_redirect_location(
 
 #[derive(Clone)]
 pub struct StreamRelayMiddleware {
-    relay_nodes: Vec<BackendNode>,
+    state: Arc<AppState>,
 }
 
 impl StreamRelayMiddleware {
-    pub fn new(all_nodes: Vec<BackendNode>) -> Self {
-        Self {
-            relay_nodes: relay_nodes_sorted(all_nodes),
-        }
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.relay_nodes.is_empty()
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
     }
 }
 
@@ -114,7 +112,9 @@ impl Middleware for StreamRelayMiddleware {
         body: Option<Incoming>,
         next: Next,
     ) -> Response<BoxBodyType> {
-        if self.relay_nodes.is_empty() {
+        let backend_nodes = self.state.get_backend_nodes().await;
+        let relay_nodes = relay_nodes_sorted(&backend_nodes);
+        if relay_nodes.is_empty() {
             return next(ctx, body).await;
         }
 
@@ -132,7 +132,7 @@ impl Middleware for StreamRelayMiddleware {
             return next(ctx, body).await;
         }
 
-        for node in &self.relay_nodes {
+        for node in relay_nodes {
             if !http_path_matches_node(&ctx.path, node) {
                 continue;
             }
@@ -203,7 +203,7 @@ mod tests {
     use regex::Regex;
     use std::time::Instant;
 
-    use crate::config::backend::BackendNode;
+    use crate::{config::backend::BackendNode, test_support::TestConfig};
 
     fn sample_relay_node() -> BackendNode {
         BackendNode {
@@ -227,9 +227,17 @@ mod tests {
             direct_link: None,
             google_drive: None,
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
+    async fn relay_middleware() -> StreamRelayMiddleware {
+        let mut config = TestConfig::new().build();
+        config.backend_nodes = vec![sample_relay_node()];
+        StreamRelayMiddleware::new(Arc::new(AppState::new(config).await))
+    }
+
     #[test]
     fn loop_same_authority_and_path() {
         let t: Uri = "http://127.0.0.1:60010/stream".parse().unwrap();
@@ -280,7 +288,8 @@ mod tests {
             pattern_regex: Some(Regex::new(".*").unwrap()),
             ..sample_relay_node()
         };
-        let sorted = relay_nodes_sorted(vec![web, a.clone(), b.clone()]);
+        let nodes = [web, a, b];
+        let sorted = relay_nodes_sorted(&nodes);
         assert_eq!(sorted.len(), 2);
         assert_eq!(sorted[0].name, "first");
         assert_eq!(sorted[1].name, "second");
@@ -288,7 +297,7 @@ mod tests {
 
     #[tokio::test]
     async fn middleware_301_preserves_query() {
-        let mw = StreamRelayMiddleware::new(vec![sample_relay_node()]);
+        let mw = relay_middleware().await;
         let uri: Uri = "http://127.0.0.1:60010/stream?sign=dummy&device_id=x"
             .parse()
             .unwrap();
@@ -324,7 +333,7 @@ mod tests {
 
     #[tokio::test]
     async fn middleware_skips_when_no_sign() {
-        let mw = StreamRelayMiddleware::new(vec![sample_relay_node()]);
+        let mw = relay_middleware().await;
         let uri: Uri = "http://127.0.0.1:60010/stream?foo=1".parse().unwrap();
         let ctx = Context::new(
             uri,
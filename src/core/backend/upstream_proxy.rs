@@ -1,13 +1,19 @@
+use std::pin::Pin;
 use std::sync::OnceLock;
-use std::time::Instant;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
+use dashmap::DashMap;
 use http_body_util::{BodyExt, Full};
 use hyper::{
-    HeaderMap, Request, Response, StatusCode, Uri, body::Incoming, header,
+    HeaderMap, Method, Request, Response, StatusCode, Uri,
+    body::{Body, Frame, Incoming, SizeHint},
+    header,
 };
 use hyper_rustls::HttpsConnectorBuilder;
 use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use tokio::time::Sleep;
 
 use crate::{
     UPSTREAM_PROXY_LOGGER_DOMAIN, debug_log,
@@ -15,13 +21,19 @@ use crate::{
     info_log,
 };
 
+use super::{
+    resilience::{UpstreamPolicy, UpstreamTimeout},
+    webdav,
+};
+
 type HttpConnector = hyper_util::client::legacy::connect::HttpConnector;
 
 type UpstreamConnector = hyper_rustls::HttpsConnector<HttpConnector>;
 
 type UpstreamClient = Client<UpstreamConnector, Full<Bytes>>;
 
-static UPSTREAM_CLIENT: OnceLock<Result<UpstreamClient, String>> =
+/// One pooled client per distinct connect timeout (milliseconds).
+static UPSTREAM_CLIENTS: OnceLock<DashMap<u128, UpstreamClient>> =
     OnceLock::new();
 
 const HOP_BY_HOP_HEADERS: &[&str] = &[
@@ -42,33 +54,125 @@ fn hop_by_hop(name: &str) -> bool {
         .any(|h| name.eq_ignore_ascii_case(h))
 }
 
-fn build_upstream_client() -> Result<UpstreamClient, String> {
+fn build_upstream_client(
+    connect_timeout: Duration,
+) -> Result<UpstreamClient, String> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_connect_timeout(Some(connect_timeout));
     let connector = HttpsConnectorBuilder::new()
         .with_native_roots()
         .map_err(|e| format!("https native roots: {e}"))?
         .https_or_http()
         .enable_http1()
-        .build();
+        .wrap_connector(http);
 
     Ok(Client::builder(TokioExecutor::new()).build(connector))
 }
 
-fn shared_client() -> Result<&'static UpstreamClient, &'static str> {
-    let cell = UPSTREAM_CLIENT.get_or_init(build_upstream_client);
-    cell.as_ref().map_err(String::as_str)
+fn client_for(
+    connect_timeout: Duration,
+) -> Result<UpstreamClient, GatewayError> {
+    let clients = UPSTREAM_CLIENTS.get_or_init(DashMap::new);
+    let key = connect_timeout.as_millis();
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+    let client = build_upstream_client(connect_timeout)
+        .map_err(|msg| GatewayError::IoError(std::io::Error::other(msg)))?;
+    Ok(clients.entry(key).or_insert(client).clone())
+}
+
+fn shared_client() -> Result<UpstreamClient, GatewayError> {
+    client_for(UpstreamPolicy::default().connect_timeout)
+}
+
+/// Sends `req`, failing when no response headers arrive within the
+/// policy's connect plus first-byte budget.
+async fn request_with_policy(
+    client: &UpstreamClient,
+    req: Request<Full<Bytes>>,
+    policy: &UpstreamPolicy,
+) -> Result<Response<Incoming>, GatewayError> {
+    let timeout = policy.request_timeout();
+    match tokio::time::timeout(timeout, client.request(req)).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(UpstreamTimeout(timeout).into()),
+    }
+}
+
+/// Fails a proxied body when the upstream sends nothing for `idle`.
+///
+/// The timer starts at the first `Pending` after a frame, so a client that
+/// stops reading for a while is not charged for its own pause.
+struct IdleTimeoutBody {
+    inner: BoxBodyType,
+    idle: Duration,
+    deadline: Pin<Box<Sleep>>,
+    waiting: bool,
+}
+
+impl IdleTimeoutBody {
+    fn new(inner: BoxBodyType, idle: Duration) -> Self {
+        Self {
+            inner,
+            idle,
+            deadline: Box::pin(tokio::time::sleep(idle)),
+            waiting: false,
+        }
+    }
+}
+
+impl Body for IdleTimeoutBody {
+    type Data = Bytes;
+    type Error = GatewayError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match Pin::new(&mut self.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => {
+                self.waiting = false;
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Poll::Ready(other) => Poll::Ready(other),
+            Poll::Pending => {
+                if !self.waiting {
+                    self.waiting = true;
+                    let next = tokio::time::Instant::now() + self.idle;
+                    self.deadline.as_mut().reset(next);
+                }
+                match self.deadline.as_mut().poll(cx) {
+                    Poll::Ready(()) => Poll::Ready(Some(Err(UpstreamTimeout(
+                        self.idle,
+                    )
+                    .into()))),
+                    Poll::Pending => Poll::Pending,
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
 }
 
 /// Warms up connection pool by pre-establishing TCP/TLS connections.
 pub async fn warmup_connection(uri: Uri) -> Result<(), GatewayError> {
-    let client = shared_client()
-        .map_err(|msg| GatewayError::IoError(std::io::Error::other(msg)))?;
+    let client = shared_client()?;
 
     let req = Request::head(uri.clone())
         .body(Full::default())
         .map_err(GatewayError::from)?;
 
     let start = Instant::now();
-    match client.request(req).await {
+    match request_with_policy(&client, req, &UpstreamPolicy::default()).await {
         Ok(resp) => {
             let warmup_ms = start.elapsed().as_millis();
             info_log!(
@@ -87,7 +191,7 @@ pub async fn warmup_connection(uri: Uri) -> Result<(), GatewayError> {
                 upstream_uri_hint(&uri),
                 e
             );
-            Err(e.into())
+            Err(e)
         }
     }
 }
@@ -157,9 +261,11 @@ fn merge_request_headers(
     Ok(())
 }
 
-/// Copies upstream response metadata and pipes the body into `BoxBodyType`.
+/// Copies upstream response metadata and pipes the body into `BoxBodyType`,
+/// failing the body when the upstream stalls for `idle_timeout`.
 pub fn map_upstream_to_stream_response(
     upstream: Response<Incoming>,
+    idle_timeout: Duration,
 ) -> Result<(StatusCode, HeaderMap, BoxBodyType), GatewayError> {
     let (parts, body) = upstream.into_parts();
     let status = parts.status;
@@ -173,6 +279,7 @@ pub fn map_upstream_to_stream_response(
     }
 
     let boxed: BoxBodyType = body.map_err(GatewayError::from).boxed();
+    let boxed = IdleTimeoutBody::new(boxed, idle_timeout).boxed();
     Ok((status, out_headers, boxed))
 }
 
@@ -183,9 +290,9 @@ pub async fn forward_get(
     user_agent: &str,
     extra_upstream_headers: Option<&HeaderMap>,
     stream_session_id: Option<&str>,
+    policy: &UpstreamPolicy,
 ) -> Result<Response<Incoming>, GatewayError> {
-    let client = shared_client()
-        .map_err(|msg| GatewayError::IoError(std::io::Error::other(msg)))?;
+    let client = client_for(policy.connect_timeout)?;
 
     let mut headers = HeaderMap::new();
     merge_request_headers(
@@ -202,7 +309,7 @@ pub async fn forward_get(
     *req.headers_mut() = headers;
 
     let started = Instant::now();
-    let resp = client.request(req).await?;
+    let resp = request_with_policy(&client, req, policy).await?;
     let ttfb_ms = started.elapsed().as_millis();
     debug_log!(
         UPSTREAM_PROXY_LOGGER_DOMAIN,
@@ -215,17 +322,39 @@ pub async fn forward_get(
     Ok(resp)
 }
 
+/// Status of an authorization probe plus the `WWW-Authenticate` challenges
+/// the upstream sent with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthProbe {
+    pub status: StatusCode,
+    pub challenges: Vec<String>,
+}
+
+fn auth_probe(status: StatusCode, headers: &HeaderMap) -> AuthProbe {
+    AuthProbe {
+        status,
+        challenges: headers
+            .get_all(header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(str::to_string)
+            .collect(),
+    }
+}
+
 async fn probe_authorization_inner(
     uri: Uri,
-    authorization: &str,
+    authorization: Option<&str>,
     user_agent: &str,
-) -> Result<StatusCode, GatewayError> {
-    let client = shared_client()
-        .map_err(|msg| GatewayError::IoError(std::io::Error::other(msg)))?;
+) -> Result<AuthProbe, GatewayError> {
+    let client = shared_client()?;
+    let policy = UpstreamPolicy::default();
 
     let mut head_headers = HeaderMap::new();
-    head_headers
-        .insert(header::AUTHORIZATION, parse_header_value(authorization)?);
+    if let Some(authorization) = authorization {
+        head_headers
+            .insert(header::AUTHORIZATION, parse_header_value(authorization)?);
+    }
     head_headers.insert(header::USER_AGENT, parse_header_value(user_agent)?);
 
     let mut head_req = Request::head(uri.clone())
@@ -233,7 +362,7 @@ async fn probe_authorization_inner(
         .map_err(GatewayError::from)?;
     *head_req.headers_mut() = head_headers.clone();
 
-    let head_resp = client.request(head_req).await?;
+    let head_resp = request_with_policy(&client, head_req, &policy).await?;
     let (head_meta, head_body) = head_resp.into_parts();
     let _ = BodyExt::collect(head_body).await;
     let status = head_meta.status;
@@ -250,36 +379,37 @@ async fn probe_authorization_inner(
             .body(Full::default())
             .map_err(GatewayError::from)?;
         *get_req.headers_mut() = get_headers;
-        let get_resp = client.request(get_req).await?;
+        let get_resp = request_with_policy(&client, get_req, &policy).await?;
         let (get_meta, get_body) = get_resp.into_parts();
         let _ = BodyExt::collect(get_body).await;
-        return Ok(get_meta.status);
+        return Ok(auth_probe(get_meta.status, &get_meta.headers));
     }
 
-    Ok(status)
+    Ok(auth_probe(status, &head_meta.headers))
 }
 
 /// Lightweight probe: HEAD, or GET with `Range: bytes=0-0` if HEAD is not allowed.
+/// Without `authorization` it only asks the upstream for its challenges.
 ///
 /// Emits `webdav_auth_probe_ms` (PLAN-03) when logging so cold-start vs warm paths are comparable.
 pub async fn probe_authorization(
     uri: Uri,
-    authorization: &str,
+    authorization: Option<&str>,
     user_agent: &str,
     stream_session_id: Option<&str>,
-) -> Result<StatusCode, GatewayError> {
+) -> Result<AuthProbe, GatewayError> {
     let hint = upstream_uri_hint(&uri);
     let started = Instant::now();
     let result =
         probe_authorization_inner(uri, authorization, user_agent).await;
     let probe_ms = started.elapsed().as_millis();
     match &result {
-        Ok(status) => {
+        Ok(probe) => {
             info_log!(
                 UPSTREAM_PROXY_LOGGER_DOMAIN,
                 "webdav_auth_probe webdav_auth_probe_ms={} probe_status={} uri_hint={}{}",
                 probe_ms,
-                status.as_u16(),
+                probe.status.as_u16(),
                 hint,
                 stream_session_log_suffix(stream_session_id),
             );
@@ -298,10 +428,237 @@ pub async fn probe_authorization(
     result
 }
 
+/// Error bodies kept from a media probe; Drive's JSON errors are a few
+/// hundred bytes.
+const MEDIA_PROBE_BODY_LIMIT: usize = 16 * 1024;
+
+/// Status and body of a one-byte ranged GET.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaProbe {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+/// GET with `Range: bytes=0-0` that keeps the (capped) body, so a refusal
+/// can be told apart by the error reason it carries.
+pub async fn probe_media_range(
+    uri: Uri,
+    authorization: &str,
+    user_agent: &str,
+    stream_session_id: Option<&str>,
+) -> Result<MediaProbe, GatewayError> {
+    let client = shared_client()?;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::AUTHORIZATION, parse_header_value(authorization)?);
+    headers.insert(header::USER_AGENT, parse_header_value(user_agent)?);
+    headers
+        .insert(header::RANGE, header::HeaderValue::from_static("bytes=0-0"));
+
+    let hint = upstream_uri_hint(&uri);
+    let mut req = Request::get(uri)
+        .body(Full::default())
+        .map_err(GatewayError::from)?;
+    *req.headers_mut() = headers;
+
+    let started = Instant::now();
+    let resp =
+        request_with_policy(&client, req, &UpstreamPolicy::default()).await?;
+    let status = resp.status();
+    let body =
+        http_body_util::Limited::new(resp.into_body(), MEDIA_PROBE_BODY_LIMIT)
+            .collect()
+            .await
+            .map(|collected| collected.to_bytes())
+            .unwrap_or_default();
+    debug_log!(
+        UPSTREAM_PROXY_LOGGER_DOMAIN,
+        "media_range_probe probe_ms={} status={} uri_hint={}{}",
+        started.elapsed().as_millis(),
+        status.as_u16(),
+        hint,
+        stream_session_log_suffix(stream_session_id),
+    );
+    Ok(MediaProbe { status, body })
+}
+
+/// Result of a depth-0 WebDav `PROPFIND` for a single resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropfindStat {
+    pub status: StatusCode,
+    pub content_length: Option<u64>,
+    pub is_collection: bool,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+const PROPFIND_BODY: &str = concat!(
+    r#"<?xml version="1.0" encoding="utf-8"?>"#,
+    r#"<D:propfind xmlns:D="DAV:"><D:prop>"#,
+    r#"<D:getcontentlength/><D:resourcetype/>"#,
+    r#"<D:getetag/><D:getlastmodified/>"#,
+    r#"</D:prop></D:propfind>"#
+);
+
+/// Issues `PROPFIND` with `Depth: 0` and reads `getcontentlength`,
+/// `resourcetype`, `getetag` and `getlastmodified` from the multistatus
+/// body. Non-207 statuses are returned as-is with no properties.
+pub async fn propfind(
+    uri: Uri,
+    user_agent: &str,
+    extra_upstream_headers: Option<&HeaderMap>,
+    policy: &UpstreamPolicy,
+) -> Result<PropfindStat, GatewayError> {
+    let client = client_for(policy.connect_timeout)?;
+
+    let mut headers = HeaderMap::new();
+    merge_request_headers(
+        &HeaderMap::new(),
+        &mut headers,
+        user_agent,
+        extra_upstream_headers,
+    )?;
+    headers.insert("depth", header::HeaderValue::from_static("0"));
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/xml; charset=utf-8"),
+    );
+
+    let uri_hint = upstream_uri_hint(&uri);
+    let method = Method::from_bytes(webdav::PROPFIND_METHOD.as_bytes())
+        .map_err(|_| {
+            GatewayError::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid PROPFIND method",
+            ))
+        })?;
+    let mut req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Full::new(Bytes::from_static(PROPFIND_BODY.as_bytes())))
+        .map_err(GatewayError::from)?;
+    *req.headers_mut() = headers;
+
+    let started = Instant::now();
+    let resp = request_with_policy(&client, req, policy).await?;
+    let (meta, body) = resp.into_parts();
+    let body = BodyExt::collect(body).await?.to_bytes();
+    debug_log!(
+        UPSTREAM_PROXY_LOGGER_DOMAIN,
+        "upstream_propfind propfind_ms={} status={} uri_hint={}",
+        started.elapsed().as_millis(),
+        meta.status.as_u16(),
+        uri_hint,
+    );
+
+    if meta.status != StatusCode::MULTI_STATUS {
+        return Ok(PropfindStat {
+            status: meta.status,
+            content_length: None,
+            is_collection: false,
+            etag: None,
+            last_modified: None,
+        });
+    }
+
+    let text = String::from_utf8_lossy(&body);
+    let text_prop = |name: &str| {
+        xml_element_text(&text, name)
+            .map(|value| value.trim().replace("&quot;", "\""))
+            .filter(|value| !value.is_empty())
+    };
+    Ok(PropfindStat {
+        status: meta.status,
+        content_length: xml_element_text(&text, "getcontentlength")
+            .and_then(|value| value.trim().parse().ok()),
+        is_collection: xml_element_text(&text, "resourcetype")
+            .is_some_and(|value| value.contains("collection")),
+        etag: text_prop("getetag"),
+        last_modified: text_prop("getlastmodified"),
+    })
+}
+
+/// Text between the first `<prefix:local_name>` element and its closing tag.
+/// Namespace prefixes are matched case-insensitively; self-closing elements
+/// yield `None`.
+fn xml_element_text<'a>(xml: &'a str, local_name: &str) -> Option<&'a str> {
+    let lower = xml.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(offset) = lower[search_from..].find(local_name) {
+        let name_start = search_from + offset;
+        let name_end = name_start + local_name.len();
+        search_from = name_end;
+
+        let Some(tag_start) = lower[..name_start].rfind('<') else {
+            continue;
+        };
+        let prefix = &lower[tag_start + 1..name_start];
+        let is_open_tag = prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ':' || c == '-');
+        let boundary = lower[name_end..].chars().next();
+        if !is_open_tag
+            || !matches!(boundary, Some('>' | '/' | ' ' | '\t' | '\n'))
+        {
+            continue;
+        }
+
+        let tag_end = name_end + lower[name_end..].find('>')?;
+        if lower[..tag_end].ends_with('/') {
+            return None;
+        }
+        let closing_tag = format!("</{prefix}{local_name}");
+        let close_at = tag_end + lower[tag_end..].find(&closing_tag)?;
+        return Some(&xml[tag_end + 1..close_at]);
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn channel_body(
+        idle: Duration,
+    ) -> (tokio::sync::mpsc::Sender<Bytes>, IdleTimeoutBody) {
+        let (tx, rx) = tokio::sync::mpsc::channel::<Bytes>(4);
+        let stream = futures_util::StreamExt::map(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+            |chunk| Ok::<_, GatewayError>(Frame::data(chunk)),
+        );
+        let inner = http_body_util::StreamBody::new(stream).boxed();
+        (tx, IdleTimeoutBody::new(inner, idle))
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_ignores_pauses_on_the_reading_side() {
+        let idle = Duration::from_millis(100);
+        let (tx, mut body) = channel_body(idle);
+
+        tx.send(Bytes::from_static(b"first")).await.expect("send");
+        let first = body.frame().await.expect("frame").expect("first");
+        assert_eq!(first.into_data().ok(), Some(Bytes::from_static(b"first")));
+
+        // The client stops reading for longer than `idle`; the upstream
+        // answers promptly once it is polled again.
+        tokio::time::sleep(idle * 3).await;
+        let sender = tx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let _ = sender.send(Bytes::from_static(b"second")).await;
+        });
+        let second = body.frame().await.expect("frame").expect("second");
+        assert_eq!(
+            second.into_data().ok(),
+            Some(Bytes::from_static(b"second"))
+        );
+
+        // Real upstream silence still fails the body.
+        let started = Instant::now();
+        assert!(body.frame().await.expect("frame").is_err());
+        assert!(started.elapsed() >= idle);
+        drop(tx);
+    }
+
     #[test]
     fn upstream_uri_hint_joins_host_and_path() {
         let uri: Uri = "http://example.test:8080/foo/bar"
@@ -340,6 +697,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn xml_element_text_reads_prefixed_properties() {
+        let body = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:"><d:response><d:propstat><d:prop>
+<d:getcontentlength>1048576</d:getcontentlength>
+<d:resourcetype/>
+</d:prop></d:propstat></d:response></d:multistatus>"#;
+
+        assert_eq!(xml_element_text(body, "getcontentlength"), Some("1048576"));
+        assert_eq!(xml_element_text(body, "resourcetype"), None);
+    }
+
+    #[test]
+    fn xml_element_text_keeps_nested_collection_marker() {
+        let body = "<D:prop><D:resourcetype><D:collection/></D:resourcetype>\
+                    </D:prop>";
+
+        let value = xml_element_text(body, "resourcetype").unwrap_or_default();
+        assert!(value.contains("collection"));
+    }
+
     #[test]
     fn stream_session_log_suffix_uses_trimmed_id() {
         assert_eq!(
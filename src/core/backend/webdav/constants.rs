@@ -17,3 +17,5 @@ pub const DEFAULT_QUERY_PARAM: &str = "path";
 pub const ACCEL_REDIRECT_HEADER: &str = "x-accel-redirect";
 
 pub const ACCEL_REDIRECT_PREFIX: &str = "/_origin/webdav";
+
+pub const PROPFIND_METHOD: &str = "PROPFIND";
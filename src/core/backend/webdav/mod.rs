@@ -1,10 +1,12 @@
 pub mod constants;
+pub mod stat;
 pub mod url;
 
 pub use constants::{
     ACCEL_REDIRECT_HEADER, ACCEL_REDIRECT_PREFIX, BACKEND_TYPE,
     DEFAULT_QUERY_PARAM, MODE_PATH_JOIN, MODE_QUERY_PATH, MODE_URL_TEMPLATE,
-    PROXY_MODE_ACCEL_REDIRECT, TEMPLATE_PLACEHOLDER,
+    PROPFIND_METHOD, PROXY_MODE_ACCEL_REDIRECT, TEMPLATE_PLACEHOLDER,
 };
+pub use stat::WebDavEntry;
 pub(crate) use url::encode_path_segments;
 pub use url::{WebDavUrlError, build_upstream_uri};
@@ -0,0 +1,82 @@
+use hyper::StatusCode;
+
+use crate::core::backend::upstream_proxy::PropfindStat;
+
+/// Existence, size and validators of one WebDav path, as answered by a
+/// `Depth: 0` PROPFIND.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebDavEntry {
+    pub exists: bool,
+    pub size: Option<u64>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl WebDavEntry {
+    pub fn missing() -> Self {
+        Self {
+            exists: false,
+            size: None,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Collections count as missing: only files can be streamed. Returns
+    /// `None` when the status says nothing about the path (auth, 5xx, …).
+    pub fn from_propfind(stat: PropfindStat) -> Option<Self> {
+        match stat.status {
+            StatusCode::MULTI_STATUS | StatusCode::OK
+                if !stat.is_collection =>
+            {
+                Some(Self {
+                    exists: true,
+                    size: stat.content_length,
+                    etag: stat.etag,
+                    last_modified: stat.last_modified,
+                })
+            }
+            StatusCode::MULTI_STATUS | StatusCode::OK => Some(Self::missing()),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Some(Self::missing()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(status: StatusCode, is_collection: bool) -> PropfindStat {
+        PropfindStat {
+            status,
+            content_length: Some(42),
+            is_collection,
+            etag: Some("\"abc\"".into()),
+            last_modified: Some("Mon, 12 Jan 1998 09:25:56 GMT".into()),
+        }
+    }
+
+    #[test]
+    fn from_propfind_maps_files_collections_and_errors() {
+        let file =
+            WebDavEntry::from_propfind(stat(StatusCode::MULTI_STATUS, false))
+                .expect("file entry");
+        assert!(file.exists);
+        assert_eq!(file.size, Some(42));
+        assert_eq!(file.etag.as_deref(), Some("\"abc\""));
+
+        assert_eq!(
+            WebDavEntry::from_propfind(stat(StatusCode::MULTI_STATUS, true)),
+            Some(WebDavEntry::missing())
+        );
+        assert_eq!(
+            WebDavEntry::from_propfind(stat(StatusCode::NOT_FOUND, false)),
+            Some(WebDavEntry::missing())
+        );
+        assert_eq!(
+            WebDavEntry::from_propfind(stat(StatusCode::FORBIDDEN, false)),
+            None
+        );
+    }
+}
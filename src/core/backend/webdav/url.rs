@@ -184,6 +184,8 @@ mod tests {
             direct_link: None,
             google_drive: None,
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
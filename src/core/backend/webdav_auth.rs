@@ -1,8 +1,16 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    fmt::Write as _,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Instant,
+};
 
+use aws_lc_rs::digest;
 use base64::{Engine as _, engine::general_purpose::STANDARD as B64};
 use dashmap::DashMap;
-use hyper::{HeaderMap, StatusCode, Uri, header};
+use hyper::{HeaderMap, Method, Uri, header};
 use tokio::sync::Mutex as AsyncMutex;
 
 use crate::{
@@ -14,10 +22,275 @@ use crate::{
 use super::upstream_proxy;
 
 const BASIC_PREFIX: &str = "Basic ";
+const DIGEST_SCHEME: &str = "digest";
 
 /// Per-node mutex map: same key as `webdav_auth_cache` so only one probe runs at a time per node.
 pub type WebDavAuthProbeLocks = DashMap<String, Arc<AsyncMutex<()>>>;
 
+/// Per-node authentication learned from the first probe.
+pub type WebDavAuthCache = DashMap<String, WebDavAuth>;
+
+/// How a WebDav node authenticates once probed.
+#[derive(Clone, Debug)]
+pub enum WebDavAuth {
+    /// Ready-made `Basic …` line.
+    Basic(String),
+    /// Digest challenge; a fresh response is computed for every request.
+    Digest(Arc<DigestSession>),
+}
+
+impl WebDavAuth {
+    /// `Authorization` line for one request; Digest binds it to `method`
+    /// and the request target of `uri`.
+    pub fn header_for(&self, method: &Method, uri: &Uri) -> String {
+        match self {
+            WebDavAuth::Basic(line) => line.clone(),
+            WebDavAuth::Digest(session) => session.authorization(method, uri),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DigestAlgorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl DigestAlgorithm {
+    fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_ascii_uppercase).as_deref() {
+            None | Some("MD5") => Some(Self::Md5),
+            Some("MD5-SESS") => Some(Self::Md5Sess),
+            Some("SHA-256") => Some(Self::Sha256),
+            Some("SHA-256-SESS") => Some(Self::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Md5Sess => "MD5-sess",
+            Self::Sha256 => "SHA-256",
+            Self::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn is_session(self) -> bool {
+        matches!(self, Self::Md5Sess | Self::Sha256Sess)
+    }
+
+    fn hash(self, input: &str) -> String {
+        match self {
+            Self::Md5 | Self::Md5Sess => {
+                format!("{:x}", md5::compute(input.as_bytes()))
+            }
+            Self::Sha256 | Self::Sha256Sess => {
+                hex(digest::digest(&digest::SHA256, input.as_bytes()).as_ref())
+            }
+        }
+    }
+}
+
+/// Parsed `WWW-Authenticate: Digest …` challenge (RFC 7616).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: DigestAlgorithm,
+    qop_auth: bool,
+}
+
+impl DigestChallenge {
+    /// First supported Digest challenge among `WWW-Authenticate` values.
+    pub fn from_headers(challenges: &[String]) -> Option<Self> {
+        challenges.iter().find_map(|value| Self::parse(value))
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        parse_challenges(value)
+            .into_iter()
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(DIGEST_SCHEME))
+            .find_map(|(_, params)| Self::from_params(&params))
+    }
+
+    fn from_params(params: &[(String, String)]) -> Option<Self> {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        Some(Self {
+            realm: param("realm")?.to_string(),
+            nonce: param("nonce")?.to_string(),
+            opaque: param("opaque").map(str::to_string),
+            algorithm: DigestAlgorithm::parse(param("algorithm"))?,
+            qop_auth: param("qop").is_some_and(|qop| {
+                qop.split(',')
+                    .any(|item| item.trim().eq_ignore_ascii_case("auth"))
+            }),
+        })
+    }
+}
+
+/// Digest credentials plus the nonce count for the cached challenge.
+#[derive(Debug)]
+pub struct DigestSession {
+    username: String,
+    password: String,
+    challenge: DigestChallenge,
+    nonce_count: AtomicU32,
+}
+
+impl DigestSession {
+    pub fn new(
+        username: &str,
+        password: &str,
+        challenge: DigestChallenge,
+    ) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+            challenge,
+            nonce_count: AtomicU32::new(0),
+        }
+    }
+
+    fn authorization(&self, method: &Method, uri: &Uri) -> String {
+        let nonce_count = self.nonce_count.fetch_add(1, Ordering::Relaxed) + 1;
+        let cnonce = hex(&rand::random::<[u8; 8]>());
+        self.authorization_with(method, uri, nonce_count, &cnonce)
+    }
+
+    fn authorization_with(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        nonce_count: u32,
+        cnonce: &str,
+    ) -> String {
+        let challenge = &self.challenge;
+        let algorithm = challenge.algorithm;
+        let target = uri.path_and_query().map_or_else(
+            || uri.path().to_string(),
+            |pq| pq.as_str().to_string(),
+        );
+        let nc = format!("{nonce_count:08x}");
+
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            self.username, challenge.realm, self.password
+        ));
+        if algorithm.is_session() {
+            ha1 =
+                algorithm.hash(&format!("{ha1}:{}:{cnonce}", challenge.nonce));
+        }
+        let ha2 = algorithm.hash(&format!("{}:{target}", method.as_str()));
+        let response = if challenge.qop_auth {
+            algorithm.hash(&format!(
+                "{ha1}:{}:{nc}:{cnonce}:auth:{ha2}",
+                challenge.nonce
+            ))
+        } else {
+            algorithm.hash(&format!("{ha1}:{}:{ha2}", challenge.nonce))
+        };
+
+        let mut line = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", \
+             algorithm={}, response=\"{}\"",
+            quote_escape(&self.username),
+            quote_escape(&challenge.realm),
+            quote_escape(&challenge.nonce),
+            quote_escape(&target),
+            algorithm.as_str(),
+            response
+        );
+        if challenge.qop_auth {
+            let _ = write!(line, ", qop=auth, nc={nc}, cnonce=\"{cnonce}\"");
+        }
+        if let Some(opaque) = &challenge.opaque {
+            let _ = write!(line, ", opaque=\"{}\"", quote_escape(opaque));
+        }
+        line
+    }
+}
+
+/// `(auth-scheme, params)` of every challenge in one `WWW-Authenticate`
+/// value, which may list several: `Basic realm="a", Digest realm="b", …`.
+fn parse_challenges(value: &str) -> Vec<(String, Vec<(String, String)>)> {
+    const SEPARATORS: [char; 3] = [',', ' ', '\t'];
+    let mut challenges = Vec::new();
+    let mut rest = value.trim_start_matches(SEPARATORS);
+    while !rest.is_empty() {
+        let end = rest.find(SEPARATORS).unwrap_or(rest.len());
+        let (params, next) = parse_auth_params(&rest[end..]);
+        challenges.push((rest[..end].to_string(), params));
+        rest = next.trim_start_matches(SEPARATORS);
+    }
+    challenges
+}
+
+/// `key=value` / `key="quoted value"` pairs of one challenge, in order,
+/// plus the input left after them.
+fn parse_auth_params(input: &str) -> (Vec<(String, String)>, &str) {
+    let mut params = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().trim_start_matches(',').trim().to_string();
+        // Another scheme starts after the last parameter of this one.
+        if key.contains(char::is_whitespace) {
+            break;
+        }
+        rest = rest[eq + 1..].trim_start();
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut consumed = quoted.len();
+            while let Some((index, ch)) = chars.next() {
+                match ch {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        consumed = index + 1;
+                        break;
+                    }
+                    _ => value.push(ch),
+                }
+            }
+            rest = &quoted[consumed..];
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+            value
+        };
+        params.push((key, value));
+        rest = rest.trim_start().trim_start_matches(',').trim_start();
+    }
+    (params, rest)
+}
+
+fn quote_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
 fn cache_key(node: &BackendNode) -> String {
     let base = node.base_url.trim_end_matches('/').to_lowercase();
     format!("{}|{}", node.name, base)
@@ -36,7 +309,7 @@ fn basic_authorization_header(username: &str, password: &str) -> String {
     out
 }
 
-/// True when WebDAV Basic should be attached (non-empty username or password after trim).
+/// True when WebDAV auth should be attached (non-empty username or password after trim).
 pub fn credentials_configured(cfg: &WebDavConfig) -> bool {
     !cfg.username.trim().is_empty() || !cfg.password.trim().is_empty()
 }
@@ -62,55 +335,69 @@ fn probe_user_agent(
     crate::system::SystemInfo::new().get_user_agent()
 }
 
-/// Runs HEAD (or ranged GET) probe and stores `Basic` line in `cache` on success.
-async fn probe_and_cache_basic_line(
-    cache: &DashMap<String, String>,
+/// Runs a HEAD (or ranged GET) probe without credentials and picks the
+/// scheme from the challenge: Digest when offered, so the password never
+/// travels in the clear to a server that supports it, otherwise Basic.
+async fn probe_and_cache_auth(
+    cache: &WebDavAuthCache,
     key: &str,
     node: &BackendNode,
     upstream_uri: &Uri,
     cfg: &WebDavConfig,
     client_headers: Option<&HeaderMap>,
     stream_session_id: Option<&str>,
-) -> Result<String, ()> {
-    let auth_line = basic_authorization_header(&cfg.username, &cfg.password);
+) -> Result<WebDavAuth, ()> {
     let ua = probe_user_agent(client_headers, cfg);
 
     debug_log!(
         WEBDAV_AUTH_LOGGER_DOMAIN,
-        "Probing WebDav Basic auth for node='{}' uri={}",
+        "Probing WebDav auth for node='{}' uri={}",
         node.name,
         upstream_uri
     );
-    let status = upstream_proxy::probe_authorization(
+    let probe = upstream_proxy::probe_authorization(
         upstream_uri.clone(),
-        &auth_line,
+        None,
         &ua,
         stream_session_id,
     )
     .await
     .map_err(|_| ())?;
 
-    if status == StatusCode::UNAUTHORIZED {
-        return Err(());
-    }
+    let auth =
+        match DigestChallenge::from_headers(&probe.challenges) {
+            Some(challenge) => WebDavAuth::Digest(Arc::new(
+                DigestSession::new(&cfg.username, &cfg.password, challenge),
+            )),
+            None => WebDavAuth::Basic(basic_authorization_header(
+                &cfg.username,
+                &cfg.password,
+            )),
+        };
 
     info_log!(
         WEBDAV_AUTH_LOGGER_DOMAIN,
-        "WebDav Basic auth cached node='{}' probe_status={}",
+        "WebDav auth cached node='{}' scheme={} probe_status={}",
         node.name,
-        status
+        match auth {
+            WebDavAuth::Basic(_) => "basic",
+            WebDavAuth::Digest(_) => "digest",
+        },
+        probe.status
     );
-    cache.insert(key.to_string(), auth_line.clone());
-    Ok(auth_line)
+    cache.insert(key.to_string(), auth.clone());
+    Ok(auth)
 }
 
-/// Returns `Authorization` line (`Basic …`) for upstream requests when credentials are set.
+/// Returns the `Authorization` line (`Basic …` or `Digest …`) for an
+/// upstream `method` request to `upstream_uri` when credentials are set.
 /// When username and password are both empty, returns `Ok(None)` immediately (no probe, no lock).
-#[allow(clippy::result_unit_err)]
+#[allow(clippy::result_unit_err, clippy::too_many_arguments)]
 pub async fn authorization_header_for_proxy(
-    cache: &DashMap<String, String>,
+    cache: &WebDavAuthCache,
     probe_locks: &WebDavAuthProbeLocks,
     node: &BackendNode,
+    method: &Method,
     upstream_uri: &Uri,
     cfg: &WebDavConfig,
     client_headers: Option<&HeaderMap>,
@@ -125,11 +412,11 @@ pub async fn authorization_header_for_proxy(
     if let Some(cached) = cache.get(&key) {
         debug_log!(
             WEBDAV_AUTH_LOGGER_DOMAIN,
-            "Basic auth cache hit node='{}' key_prefix='{}'",
+            "Auth cache hit node='{}' key_prefix='{}'",
             node.name,
             key
         );
-        return Ok(Some(cached.clone()));
+        return Ok(Some(cached.header_for(method, upstream_uri)));
     }
 
     // Single-flight: concurrent cold-start requests wait here; one probe fills the cache.
@@ -151,10 +438,10 @@ pub async fn authorization_header_for_proxy(
             node.name,
             key
         );
-        return Ok(Some(cached.clone()));
+        return Ok(Some(cached.header_for(method, upstream_uri)));
     }
 
-    let line = probe_and_cache_basic_line(
+    let auth = probe_and_cache_auth(
         cache,
         &key,
         node,
@@ -164,11 +451,11 @@ pub async fn authorization_header_for_proxy(
         stream_session_id,
     )
     .await?;
-    Ok(Some(line))
+    Ok(Some(auth.header_for(method, upstream_uri)))
 }
 
 pub fn invalidate(
-    cache: &DashMap<String, String>,
+    cache: &WebDavAuthCache,
     probe_locks: &WebDavAuthProbeLocks,
     node: &BackendNode,
 ) {
@@ -235,6 +522,78 @@ mod tests {
         assert_eq!(s, "user:pass");
     }
 
+    #[test]
+    fn digest_challenge_parses_quoted_params() {
+        let challenge = DigestChallenge::from_headers(&[
+            "Basic realm=\"files\"".into(),
+            "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", \
+             nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", \
+             opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""
+                .into(),
+        ])
+        .expect("digest challenge");
+        assert_eq!(challenge.realm, "testrealm@host.com");
+        assert_eq!(challenge.nonce, "dcd98b7102dd2f0e8b11d0f600bfb0c093");
+        assert_eq!(
+            challenge.opaque.as_deref(),
+            Some("5ccc069c403ebaf9f0171e9517f40e41")
+        );
+        assert_eq!(challenge.algorithm, DigestAlgorithm::Md5);
+        assert!(challenge.qop_auth);
+
+        assert!(
+            DigestChallenge::from_headers(&[
+                "Digest realm=\"r\", nonce=\"n\", algorithm=SHA-512".into()
+            ])
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn digest_challenge_matches_the_auth_scheme_token() {
+        assert!(
+            DigestChallenge::from_headers(&[
+                "Basic realm=\"digest\", nonce=\"n\"".into()
+            ])
+            .is_none()
+        );
+
+        let challenge = DigestChallenge::from_headers(&[
+            "Basic realm=\"files\", Digest realm=\"dav\", nonce=\"n\"".into(),
+        ])
+        .expect("digest challenge");
+        assert_eq!(challenge.realm, "dav");
+        assert_eq!(challenge.nonce, "n");
+    }
+
+    #[test]
+    fn digest_response_matches_rfc_2617_example() {
+        let challenge = DigestChallenge::parse(
+            "Digest realm=\"testrealm@host.com\", qop=\"auth\", \
+             nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", \
+             opaque=\"5ccc069c403ebaf9f0171e9517f40e41\"",
+        )
+        .expect("digest challenge");
+        let session = DigestSession::new("Mufasa", "Circle Of Life", challenge);
+        let uri: Uri = "http://www.nowhere.org/dir/index.html"
+            .parse()
+            .expect("uri");
+
+        let line =
+            session.authorization_with(&Method::GET, &uri, 1, "0a4f113b");
+        assert!(line.starts_with("Digest username=\"Mufasa\""));
+        assert!(line.contains("uri=\"/dir/index.html\""));
+        assert!(line.contains("response=\"6629fae49393a05397450978507c4ef1\""));
+        assert!(line.contains("nc=00000001, cnonce=\"0a4f113b\""));
+        assert!(line.contains("opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""));
+
+        let first = WebDavAuth::Digest(Arc::new(session));
+        let a = first.header_for(&Method::HEAD, &uri);
+        let b = first.header_for(&Method::HEAD, &uri);
+        assert!(a.contains("nc=00000001"));
+        assert!(b.contains("nc=00000002"));
+    }
+
     #[test]
     fn cache_key_stable() {
         let node = BackendNode {
@@ -258,6 +617,8 @@ mod tests {
             direct_link: None,
             google_drive: None,
             webdav: None,
+            smb: None,
+            resilience: None,
         };
         assert_eq!(cache_key(&node), "n1|https://example.com");
     }
@@ -302,6 +663,8 @@ mod tests {
             direct_link: None,
             google_drive: None,
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
@@ -345,6 +708,83 @@ mod tests {
         (base, head_hits)
     }
 
+    /// Answers every request with `401` and `challenge`, recording whether
+    /// it carried an `Authorization` header.
+    async fn spawn_challenge_server(
+        challenge: &'static str,
+    ) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind listener");
+        let addr = listener.local_addr().expect("listener addr");
+        let authorized_hits = Arc::new(AtomicU32::new(0));
+        let hits = Arc::clone(&authorized_hits);
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let hits = Arc::clone(&hits);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 2048];
+                    let Ok(n) = stream.read(&mut buf).await else {
+                        return;
+                    };
+                    let req = String::from_utf8_lossy(&buf[..n]);
+                    if req.to_ascii_lowercase().contains("\r\nauthorization:") {
+                        hits.fetch_add(1, Ordering::SeqCst);
+                    }
+                    let resp = format!(
+                        "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: \
+                         {challenge}\r\nContent-Length: 0\r\n\
+                         Connection: close\r\n\r\n"
+                    );
+                    let _ = stream.write_all(resp.as_bytes()).await;
+                });
+            }
+        });
+
+        (format!("http://{addr}"), authorized_hits)
+    }
+
+    #[tokio::test]
+    async fn probe_sends_no_credentials_and_follows_the_challenge() {
+        ensure_rustls_crypto_provider();
+        let cfg = WebDavConfig {
+            username: "u".into(),
+            password: "p".into(),
+            ..Default::default()
+        };
+
+        for (challenge, scheme) in [
+            (
+                "Basic realm=\"dav\", Digest realm=\"dav\", nonce=\"abc\"",
+                "Digest ",
+            ),
+            ("Basic realm=\"dav\"", BASIC_PREFIX),
+        ] {
+            let (base, authorized_hits) =
+                spawn_challenge_server(challenge).await;
+            let uri: Uri =
+                format!("{base}/media/file.bin").parse().expect("uri");
+
+            let line = authorization_header_for_proxy(
+                &WebDavAuthCache::new(),
+                &WebDavAuthProbeLocks::new(),
+                &sample_webdav_node(&base),
+                &Method::GET,
+                &uri,
+                &cfg,
+                None,
+                None,
+            )
+            .await
+            .expect("probe")
+            .expect("authorization");
+
+            assert!(line.starts_with(scheme), "{challenge}: {line}");
+            assert_eq!(authorized_hits.load(Ordering::SeqCst), 0);
+        }
+    }
+
     /// Concurrent cache misses must perform only one upstream probe (single HEAD) per node key.
     #[tokio::test]
     async fn concurrent_cache_miss_single_probe() {
@@ -356,7 +796,7 @@ mod tests {
             Ok(u) => u,
             Err(e) => panic!("test uri: {e}"),
         };
-        let cache = Arc::new(DashMap::<String, String>::new());
+        let cache = Arc::new(WebDavAuthCache::new());
         let locks = Arc::new(WebDavAuthProbeLocks::new());
         let node = sample_webdav_node(&base);
         let cfg = WebDavConfig {
@@ -378,6 +818,7 @@ mod tests {
                     cache.as_ref(),
                     locks.as_ref(),
                     &node,
+                    &Method::GET,
                     &uri,
                     &cfg,
                     None,
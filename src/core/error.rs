@@ -30,6 +30,8 @@ pub enum Error {
     StrmFileTooLarge,
     #[error("Strm file IO error: {0}")]
     StrmFileIoError(String),
+    #[error("Strm remote fetch error: {0}")]
+    StrmRemoteFetchError(String),
     #[error("Empty emby token")]
     EmptyEmbyToken,
     #[error("Empty emby device id")]
@@ -46,6 +48,8 @@ pub enum Error {
     UnexpectedOpenListError(String),
     #[error("File not found: {0}")]
     FileNotFound(String),
+    #[error("File probe error: {0}")]
+    FileProbeError(String),
     #[error("IO error: {0}")]
     IoError(#[from] IoError),
     #[error("Backend node not found in request")]
@@ -1,3 +1,4 @@
 pub mod forward;
 pub mod service;
+pub mod strm;
 pub mod types;
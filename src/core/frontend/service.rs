@@ -1,22 +1,25 @@
 use std::{
     borrow::Cow,
+    io::ErrorKind,
     path::Path,
     sync::Arc,
-    time::{Instant, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use async_trait::async_trait;
 use form_urlencoded;
+use http_body_util::{BodyExt, Limited};
 use hyper::{
     StatusCode, Uri,
     header::{self, HeaderMap},
 };
 use reqwest::Url;
 use tokio::fs::{self as TokioFS, metadata as TokioMetadata};
-use tokio::sync::{Mutex as TokioMutex, OnceCell};
+use tokio::sync::Mutex as TokioMutex;
 
-use super::types::{
-    ForwardConfig, ForwardInfo, InfuseAuthorization, PathParams,
+use super::{
+    strm::{RemoteStrmReader, StrmEntry},
+    types::{ForwardConfig, ForwardInfo, InfuseAuthorization, PathParams},
 };
 use crate::{
     AppState, FORWARD_LOGGER_DOMAIN, debug_log, error_log, info_log, warn_log,
@@ -27,11 +30,20 @@ use crate::{
         PlaybackInfoRequest, PlaybackInfoService, PlaybackInfoServiceError,
     },
     core::{
-        backend::session_id::generate_playback_session_id,
-        error::Error as AppForwardError, redirect_info::RedirectInfo,
-        request::Request as AppForwardRequest, sign::Sign,
+        backend::{
+            fallback::{FallbackReason, FallbackTarget},
+            probe::{self, ProbeResult},
+            resilience::UpstreamPolicy,
+            session_id::generate_playback_session_id,
+            upstream_proxy,
+        },
+        error::Error as AppForwardError,
+        redirect_info::RedirectInfo,
+        request::Request as AppForwardRequest,
+        sign::Sign,
         sign_encryptor::SignEncryptor,
     },
+    system::SystemInfo,
     util::{StringUtil, UriExt, UriExtError},
 };
 
@@ -42,6 +54,9 @@ const DEVICE_ID_QUERY_KEY: &str = "device_id";
 const PLAYBACK_SESSION_ID_QUERY_KEY: &str = "session_id";
 const SIGN_ENCRYPT_CACHE_KEY_PREFIX: &str = "forward:sign_encrypt";
 const STRM_CACHE_KEY_PREFIX: &str = "frontend:strm";
+const FILE_PROBE_CACHE_KEY_PREFIX: &str = "frontend:probe";
+const FILE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_FILE_PROBE_RESPONSE_SIZE: usize = 4096;
 
 #[async_trait]
 pub trait ForwardService: Send + Sync {
@@ -54,15 +69,11 @@ pub trait ForwardService: Send + Sync {
 
 pub struct AppForwardService {
     state: Arc<AppState>,
-    config: OnceCell<Arc<ForwardConfig>>,
 }
 
 impl AppForwardService {
     pub fn new(state: Arc<AppState>) -> Self {
-        Self {
-            state,
-            config: OnceCell::new(),
-        }
+        Self { state }
     }
 
     async fn get_emby_api_token(
@@ -288,8 +299,8 @@ impl AppForwardService {
             return Ok(sign);
         }
 
-        let mut path = self.reparse_if_strm(params.path.as_str()).await?;
-        path = self.rewrite_if_needed(path.as_str()).await;
+        let strm_entry = self.reparse_if_strm(params.path.as_str()).await?;
+        let mut path = self.rewrite_if_needed(&strm_entry.target).await;
         debug_log!(FORWARD_LOGGER_DOMAIN, "Sign path: {:?}", path);
 
         let config = self.get_forward_config().await?;
@@ -297,10 +308,8 @@ impl AppForwardService {
 
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
         let expired_at = now + self.get_forward_config().await?.expired_seconds;
-        let sign = Sign {
-            uri: Some(uri.clone()),
-            expired_at: Some(expired_at),
-        };
+        let sign = Sign::new(Some(uri.clone()), Some(expired_at))
+            .with_headers(strm_entry.headers);
 
         debug_log!(
             FORWARD_LOGGER_DOMAIN,
@@ -342,9 +351,12 @@ impl AppForwardService {
     async fn reparse_if_strm(
         &self,
         path: &str,
-    ) -> Result<String, AppForwardError> {
+    ) -> Result<StrmEntry, AppForwardError> {
         if !path.ends_with(".strm") {
-            return Ok(path.to_string());
+            return Ok(StrmEntry {
+                target: path.to_string(),
+                headers: Vec::new(),
+            });
         }
 
         debug_log!(FORWARD_LOGGER_DOMAIN, "Detected strm file: {}", path);
@@ -352,15 +364,15 @@ impl AppForwardService {
         let strm_cache = self.state.get_strm_file_cache().await;
         let strm_cache_key = Self::strm_cache_key(path)?;
 
-        if let Some(cached_path) = strm_cache.get::<String>(&strm_cache_key) {
+        if let Some(cached) = strm_cache.get::<StrmEntry>(&strm_cache_key) {
             debug_log!(
                 FORWARD_LOGGER_DOMAIN,
                 "strm_cache_hit key={} resolved_path={:?} path={}",
                 strm_cache_key,
-                cached_path,
+                cached.target,
                 path
             );
-            return Ok(cached_path);
+            return Ok(cached);
         }
 
         let strm_mutex = self.strm_request_lock(&strm_cache_key);
@@ -369,88 +381,143 @@ impl AppForwardService {
             let _strm_guard = strm_mutex.lock().await;
             let lock_wait_ms = wait_start.elapsed().as_millis();
 
-            if let Some(cached_path) = strm_cache.get::<String>(&strm_cache_key)
-            {
+            if let Some(cached) = strm_cache.get::<StrmEntry>(&strm_cache_key) {
                 info_log!(
                     FORWARD_LOGGER_DOMAIN,
                     "strm_inflight_wait_hit key={} lock_wait_ms={} \
                      resolved_path={:?} path={}",
                     strm_cache_key,
                     lock_wait_ms,
-                    cached_path,
+                    cached.target,
                     path
                 );
-                Ok(cached_path)
+                Ok(cached)
             } else {
-                let file_path = Path::new(path);
-                let metadata = TokioMetadata(file_path).await.map_err(|e| {
+                self.read_strm_content(path)
+                    .await
+                    .and_then(|content| StrmEntry::parse(&content))
+                    .inspect(|entry| {
+                        debug_log!(
+                            FORWARD_LOGGER_DOMAIN,
+                            "strm_read_complete key={} path={} target={} \
+                             headers={}",
+                            strm_cache_key,
+                            path,
+                            entry.target,
+                            entry.headers.len()
+                        );
+                        strm_cache
+                            .insert(strm_cache_key.clone(), entry.clone());
+                        info_log!(
+                            FORWARD_LOGGER_DOMAIN,
+                            "strm_cache_store key={} path={}",
+                            strm_cache_key,
+                            path
+                        );
+                    })
+            }
+        };
+
+        AppState::cleanup_request_lock(
+            &self.state.strm_request_locks,
+            &strm_cache_key,
+            &strm_mutex,
+        );
+
+        result
+    }
+
+    /// Reads the STRM file locally, falling back to the matching WebDav or
+    /// OpenList backend node when the file is not mounted on this host.
+    async fn read_strm_content(
+        &self,
+        path: &str,
+    ) -> Result<String, AppForwardError> {
+        let file_path = Path::new(path);
+        let metadata = match TokioMetadata(file_path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let Some(node) =
+                    RemoteStrmReader::matching_node(&self.state, path).await
+                else {
                     error_log!(
                         FORWARD_LOGGER_DOMAIN,
                         "Failed to get metadata for strm file: {} (error: {})",
                         path,
                         e
                     );
-                    AppForwardError::StrmFileIoError(e.to_string())
-                })?;
-
-                if metadata.len() == 0 {
-                    error_log!(
-                        FORWARD_LOGGER_DOMAIN,
-                        "Empty strm file: {}",
-                        path
-                    );
-                    Err(AppForwardError::EmptyStrmFile)
-                } else if metadata.len() > MAX_STRM_FILE_SIZE {
+                    return Err(AppForwardError::StrmFileIoError(
+                        e.to_string(),
+                    ));
+                };
+                info_log!(
+                    FORWARD_LOGGER_DOMAIN,
+                    "strm_remote_read node={} path={}",
+                    node.name,
+                    path
+                );
+                let content = RemoteStrmReader::read(
+                    &self.state,
+                    &node,
+                    path,
+                    MAX_STRM_FILE_SIZE,
+                )
+                .await
+                .inspect_err(|e| {
                     error_log!(
                         FORWARD_LOGGER_DOMAIN,
-                        "Strm file too large ({} > {}): {}",
-                        metadata.len(),
-                        MAX_STRM_FILE_SIZE,
-                        path
-                    );
-                    Err(AppForwardError::StrmFileTooLarge)
-                } else {
-                    let content = TokioFS::read_to_string(file_path)
-                        .await
-                        .map_err(|e| {
-                            error_log!(
-                                FORWARD_LOGGER_DOMAIN,
-                                "Failed to read strm file: {} (error: {})",
-                                path,
-                                e
-                            );
-                            AppForwardError::StrmFileIoError(e.to_string())
-                        })?
-                        .trim()
-                        .to_string();
-
-                    debug_log!(
-                        FORWARD_LOGGER_DOMAIN,
-                        "strm_read_complete key={} path={} content={}",
-                        strm_cache_key,
+                        "strm_remote_read_failed node={} path={} error={}",
+                        node.name,
                         path,
-                        content
-                    );
-
-                    strm_cache.insert(strm_cache_key.clone(), content.clone());
-                    info_log!(
-                        FORWARD_LOGGER_DOMAIN,
-                        "strm_cache_store key={} path={}",
-                        strm_cache_key,
-                        path
+                        e
                     );
-                    Ok(content)
-                }
+                })?;
+                return Self::ensure_strm_content(path, content);
+            }
+            Err(e) => {
+                error_log!(
+                    FORWARD_LOGGER_DOMAIN,
+                    "Failed to get metadata for strm file: {} (error: {})",
+                    path,
+                    e
+                );
+                return Err(AppForwardError::StrmFileIoError(e.to_string()));
             }
         };
 
-        AppState::cleanup_request_lock(
-            &self.state.strm_request_locks,
-            &strm_cache_key,
-            &strm_mutex,
-        );
+        if metadata.len() > MAX_STRM_FILE_SIZE {
+            error_log!(
+                FORWARD_LOGGER_DOMAIN,
+                "Strm file too large ({} > {}): {}",
+                metadata.len(),
+                MAX_STRM_FILE_SIZE,
+                path
+            );
+            return Err(AppForwardError::StrmFileTooLarge);
+        }
 
-        result
+        let content =
+            TokioFS::read_to_string(file_path).await.map_err(|e| {
+                error_log!(
+                    FORWARD_LOGGER_DOMAIN,
+                    "Failed to read strm file: {} (error: {})",
+                    path,
+                    e
+                );
+                AppForwardError::StrmFileIoError(e.to_string())
+            })?;
+        Self::ensure_strm_content(path, content)
+    }
+
+    fn ensure_strm_content(
+        path: &str,
+        content: String,
+    ) -> Result<String, AppForwardError> {
+        if content.trim().is_empty() {
+            error_log!(FORWARD_LOGGER_DOMAIN, "Empty strm file: {}", path);
+            return Err(AppForwardError::EmptyStrmFile);
+        }
+        Ok(content)
     }
 
     async fn rewrite_if_needed(&self, path: &str) -> String {
@@ -467,7 +534,7 @@ impl AppForwardService {
         debug_log!(FORWARD_LOGGER_DOMAIN, "Starting frontend path rewrite.");
 
         let mut current_uri_str: Cow<str> = Cow::Borrowed(path);
-        for path_rewrite in path_rewrites {
+        for path_rewrite in path_rewrites.iter() {
             if !path_rewrite.enable {
                 continue;
             }
@@ -490,6 +557,134 @@ impl AppForwardService {
         }
     }
 
+    /// Asks the backend whether the signed file exists before the redirect is
+    /// handed out. A missing file resolves to the `video_missing_path` fallback
+    /// URL or 404; an unreachable probe lets the request through.
+    async fn ensure_file_exists(
+        &self,
+        forward_info: &ForwardInfo,
+        signed_uri: &Uri,
+    ) -> Result<Option<Uri>, StatusCode> {
+        let config = self
+            .get_forward_config()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if !config.check_file_existence {
+            return Ok(None);
+        }
+
+        if self.probe_file_exists(forward_info, signed_uri).await != Some(false)
+        {
+            return Ok(None);
+        }
+
+        warn_log!(
+            FORWARD_LOGGER_DOMAIN,
+            "frontend_probe_missing item_id={} media_source_id={} path={}",
+            forward_info.item_id,
+            forward_info.media_source_id,
+            forward_info.path
+        );
+        let config = self.state.get_config().await;
+        match FallbackTarget::resolve(&config, FallbackReason::FileMissing) {
+            Some(FallbackTarget::Redirect(uri)) => Ok(Some(uri)),
+            _ => Err(StatusCode::NOT_FOUND),
+        }
+    }
+
+    async fn probe_file_exists(
+        &self,
+        forward_info: &ForwardInfo,
+        signed_uri: &Uri,
+    ) -> Option<bool> {
+        let cache = self.state.get_file_probe_cache().await;
+        let cache_key = Self::file_probe_cache_key(&forward_info.path).ok()?;
+
+        if let Some(exists) = cache.get::<bool>(&cache_key) {
+            debug_log!(
+                FORWARD_LOGGER_DOMAIN,
+                "file_probe_cache_hit key={} exists={}",
+                cache_key,
+                exists
+            );
+            return Some(exists);
+        }
+
+        let probe_mutex = AppState::request_lock(
+            &self.state.file_probe_request_locks,
+            &cache_key,
+        );
+        let result = {
+            let _probe_guard = probe_mutex.lock().await;
+            if let Some(exists) = cache.get::<bool>(&cache_key) {
+                Some(exists)
+            } else {
+                match Self::fetch_probe(signed_uri).await {
+                    Ok(probe) => {
+                        info_log!(
+                            FORWARD_LOGGER_DOMAIN,
+                            "file_probe_complete key={} exists={} size={:?}",
+                            cache_key,
+                            probe.exists,
+                            probe.size
+                        );
+                        cache.insert(cache_key.clone(), probe.exists);
+                        Some(probe.exists)
+                    }
+                    Err(error) => {
+                        warn_log!(
+                            FORWARD_LOGGER_DOMAIN,
+                            "file_probe_unavailable key={} error={}",
+                            cache_key,
+                            error
+                        );
+                        None
+                    }
+                }
+            }
+        };
+
+        AppState::cleanup_request_lock(
+            &self.state.file_probe_request_locks,
+            &cache_key,
+            &probe_mutex,
+        );
+
+        result
+    }
+
+    async fn fetch_probe(signed_uri: &Uri) -> Result<ProbeResult, String> {
+        let uri = probe::probe_uri(signed_uri)
+            .ok_or_else(|| "invalid probe uri".to_string())?;
+        let user_agent = SystemInfo::new().get_user_agent();
+        let response = tokio::time::timeout(
+            FILE_PROBE_TIMEOUT,
+            upstream_proxy::forward_get(
+                uri,
+                &HeaderMap::new(),
+                &user_agent,
+                None,
+                None,
+                &UpstreamPolicy::default(),
+            ),
+        )
+        .await
+        .map_err(|_| "probe timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+
+        if response.status() != StatusCode::OK {
+            return Err(format!("probe status {}", response.status().as_u16()));
+        }
+
+        let body =
+            Limited::new(response.into_body(), MAX_FILE_PROBE_RESPONSE_SIZE)
+                .collect()
+                .await
+                .map_err(|e| e.to_string())?
+                .to_bytes();
+        serde_json::from_slice(&body).map_err(|e| e.to_string())
+    }
+
     fn build_redirect_info(
         &self,
         url: Uri,
@@ -526,6 +721,16 @@ impl AppForwardService {
         Ok(format!("{STRM_CACHE_KEY_PREFIX}:path_hash:{path_hash}"))
     }
 
+    fn file_probe_cache_key(path: &str) -> Result<String, AppForwardError> {
+        if path.is_empty() {
+            return Err(AppForwardError::InvalidUri);
+        }
+        let path_hash = StringUtil::hash_hex(&path.to_lowercase());
+        Ok(format!(
+            "{FILE_PROBE_CACHE_KEY_PREFIX}:path_hash:{path_hash}"
+        ))
+    }
+
     fn strm_request_lock(&self, cache_key: &str) -> Arc<TokioMutex<()>> {
         AppState::request_lock(&self.state.strm_request_locks, cache_key)
     }
@@ -533,24 +738,10 @@ impl AppForwardService {
     async fn get_forward_config(
         &self,
     ) -> Result<Arc<ForwardConfig>, AppForwardError> {
-        if let Some(config) = self.config.get() {
-            return Ok(config.clone());
-        }
-
-        let config = self.state.get_config().await;
-        let Some(backend) = config.backend.as_ref() else {
-            return Err(AppForwardError::InvalidUri);
-        };
-        let (_, ttl) = self.state.get_cache_settings().await;
-        let forward_config = Arc::new(ForwardConfig {
-            expired_seconds: ttl,
-            backend_url: backend.uri().to_string(),
-            crypto_key: config.general.encipher_key.clone(),
-            crypto_iv: config.general.encipher_iv.clone(),
-            emby_api_key: config.emby.token.to_string(),
-        });
-        let _ = self.config.set(forward_config.clone());
-        Ok(forward_config)
+        self.state
+            .get_forward_config()
+            .await
+            .ok_or(AppForwardError::InvalidUri)
     }
 }
 
@@ -559,12 +750,24 @@ impl AppForwardService {
 mod tests {
     use std::sync::Arc;
 
+    use std::sync::Once;
+
     use dashmap::DashMap;
+    use hyper::Uri;
     use reqwest::Url;
+    use rustls::crypto::aws_lc_rs;
     use tokio::sync::Mutex as TokioMutex;
 
     use super::AppForwardService;
-    use crate::AppState;
+    use crate::{
+        AppState,
+        core::backend::probe::ProbeResult,
+        test_support::{
+            HttpMockHandler, http_response, spawn_http_mock_server,
+        },
+    };
+
+    static RUSTLS_CRYPTO_INIT: Once = Once::new();
 
     #[test]
     fn strm_cache_key_is_structured() {
@@ -577,6 +780,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn file_probe_cache_key_is_structured() {
+        let key = AppForwardService::file_probe_cache_key("/mnt/media/a.mkv");
+
+        assert!(
+            key.unwrap_or_default()
+                .starts_with("frontend:probe:path_hash:")
+        );
+        assert!(AppForwardService::file_probe_cache_key("").is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_probe_reads_backend_answer() {
+        RUSTLS_CRYPTO_INIT.call_once(|| {
+            let _ = aws_lc_rs::default_provider().install_default();
+        });
+        let handlers: Vec<HttpMockHandler> =
+            vec![Box::new(|request: String| {
+                Box::pin(async move {
+                    assert!(request.starts_with("GET /stream/probe?sign=abc&"));
+                    http_response(
+                        200,
+                        "application/json",
+                        r#"{"exists":false}"#,
+                    )
+                })
+            })];
+        let base = spawn_http_mock_server(handlers).await;
+        let signed: Uri = format!("{base}/stream?sign=abc&session_id=s")
+            .parse()
+            .expect("signed uri");
+
+        let probe = AppForwardService::fetch_probe(&signed).await;
+
+        assert_eq!(probe, Ok(ProbeResult::missing()));
+    }
+
     #[test]
     fn strm_cache_key_rejects_empty_path() {
         let key = AppForwardService::strm_cache_key("");
@@ -697,6 +937,13 @@ impl ForwardService for AppForwardService {
                     }
                 })?;
 
+        if let Some(fallback_uri) =
+            self.ensure_file_exists(&forward_info, &remote_uri).await?
+        {
+            return Ok(self
+                .build_redirect_info(fallback_uri, &request.original_headers));
+        }
+
         let elapsed_ms = timer.elapsed().as_millis();
         if elapsed_ms >= SLOW_FRONTEND_ROUTING_THRESHOLD_MS {
             warn_log!(
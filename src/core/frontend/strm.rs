@@ -0,0 +1,426 @@
+use std::sync::Arc;
+
+use http_body_util::BodyExt;
+use hyper::{HeaderMap, Method, StatusCode, Uri, header};
+
+use crate::{
+    AppState, FORWARD_LOGGER_DOMAIN,
+    config::backend::BackendNode,
+    core::{
+        backend::{
+            openlist_auth, resilience::UpstreamPolicy,
+            stream::StreamMiddleware, upstream_proxy, webdav, webdav_auth,
+        },
+        error::Error as AppForwardError,
+    },
+    debug_log, info_log,
+    system::SystemInfo,
+};
+
+const EXTVLCOPT_PREFIX: &str = "#EXTVLCOPT:";
+const KODI_HEADER_SEPARATOR: char = '|';
+
+/// Parsed `.strm` content: the first playable line plus the request headers
+/// declared for it (`#EXTVLCOPT` directives or Kodi `url|Header=Value`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StrmEntry {
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl StrmEntry {
+    pub fn parse(content: &str) -> Result<Self, AppForwardError> {
+        let mut headers = Vec::new();
+
+        for line in content.lines() {
+            let line = line.trim().trim_start_matches('\u{feff}');
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(option) = line.strip_prefix(EXTVLCOPT_PREFIX) {
+                if let Some(header) = vlc_option_header(option) {
+                    headers.push(header);
+                }
+                continue;
+            }
+
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let (target, inline_headers) = split_kodi_headers(line);
+            headers.extend(inline_headers);
+            return Ok(Self {
+                target,
+                headers: dedup_headers(headers),
+            });
+        }
+
+        Err(AppForwardError::EmptyStrmFile)
+    }
+
+    pub fn is_remote(&self) -> bool {
+        is_http_url(&self.target)
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    let lower = value.trim().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+fn vlc_option_header(option: &str) -> Option<(String, String)> {
+    let (key, value) = option.split_once('=')?;
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let name = match key.trim().to_ascii_lowercase().as_str() {
+        "http-user-agent" => header::USER_AGENT.as_str(),
+        "http-referrer" | "http-referer" => header::REFERER.as_str(),
+        "http-cookie" => header::COOKIE.as_str(),
+        "http-origin" => header::ORIGIN.as_str(),
+        _ => return None,
+    };
+    Some((name.to_string(), value.to_string()))
+}
+
+fn split_kodi_headers(line: &str) -> (String, Vec<(String, String)>) {
+    if !is_http_url(line) {
+        return (line.to_string(), Vec::new());
+    }
+    let Some((target, raw_headers)) = line.split_once(KODI_HEADER_SEPARATOR)
+    else {
+        return (line.to_string(), Vec::new());
+    };
+
+    let headers = form_urlencoded::parse(raw_headers.as_bytes())
+        .filter(|(name, value)| {
+            !name.trim().is_empty() && !value.trim().is_empty()
+        })
+        .map(|(name, value)| {
+            (name.trim().to_string(), value.trim().to_string())
+        })
+        .collect();
+    (target.trim().to_string(), headers)
+}
+
+/// Later declarations win, matching how players apply repeated options.
+fn dedup_headers(headers: Vec<(String, String)>) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = Vec::with_capacity(headers.len());
+    for (name, value) in headers {
+        out.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+        out.push((name, value));
+    }
+    out
+}
+
+/// Converts STRM headers into a `HeaderMap`, skipping invalid entries.
+pub fn headers_to_map(headers: &[(String, String)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let (Ok(name), Ok(value)) = (
+            name.parse::<header::HeaderName>(),
+            value.parse::<header::HeaderValue>(),
+        ) else {
+            continue;
+        };
+        map.insert(name, value);
+    }
+    map
+}
+
+/// Reads `.strm` files that are not mounted locally through the backend node
+/// matching their path (WebDav or OpenList).
+pub(crate) struct RemoteStrmReader;
+
+impl RemoteStrmReader {
+    pub async fn matching_node(
+        state: &Arc<AppState>,
+        path: &str,
+    ) -> Option<BackendNode> {
+        let mut nodes = {
+            let config = state.get_config().await;
+            config.backend_nodes.clone()
+        };
+        nodes.sort_by_key(|node| node.priority);
+        StreamMiddleware::find_matching_node(&nodes, path)
+            .filter(|node| Self::supports(node))
+            .cloned()
+    }
+
+    fn supports(node: &BackendNode) -> bool {
+        node.open_list.is_some()
+            || node.backend_type.eq_ignore_ascii_case(webdav::BACKEND_TYPE)
+    }
+
+    pub async fn read(
+        state: &Arc<AppState>,
+        node: &BackendNode,
+        path: &str,
+        max_size: u64,
+    ) -> Result<String, AppForwardError> {
+        let user_agent = SystemInfo::new().get_user_agent();
+        let (uri, extra_headers) = if let Some(open_list) = &node.open_list {
+            let client = state.get_open_list_client().await.clone();
+            let raw_url =
+                openlist_auth::with_token(state, node, open_list, |token| {
+                    client.fetch_file_path(
+                        &open_list.base_url,
+                        token,
+                        path,
+                        user_agent.clone(),
+                    )
+                })
+                .await
+                .map_err(|e| {
+                    AppForwardError::StrmRemoteFetchError(e.to_string())
+                })?;
+            let uri = raw_url.parse::<Uri>().map_err(|_| {
+                AppForwardError::InvalidOpenListUri(raw_url.clone())
+            })?;
+            (uri, None)
+        } else {
+            let uri =
+                webdav::build_upstream_uri(node, path, node.webdav.as_ref())
+                    .map_err(|e| AppForwardError::WebDavUrl(e.to_string()))?;
+            let extra = Self::webdav_auth_headers(state, node, &uri).await?;
+            (uri, extra)
+        };
+
+        debug_log!(
+            FORWARD_LOGGER_DOMAIN,
+            "strm_remote_fetch_start node={} uri_hint={}",
+            node.name,
+            upstream_proxy::upstream_uri_hint(&uri)
+        );
+
+        let response = upstream_proxy::forward_get(
+            uri,
+            &HeaderMap::new(),
+            &user_agent,
+            extra_headers.as_ref(),
+            None,
+            &UpstreamPolicy::for_node(node),
+        )
+        .await
+        .map_err(|e| AppForwardError::StrmRemoteFetchError(e.to_string()))?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Err(AppForwardError::FileNotFound(path.to_string()));
+        }
+        if !status.is_success() {
+            return Err(AppForwardError::StrmRemoteFetchError(format!(
+                "upstream status {}",
+                status.as_u16()
+            )));
+        }
+
+        let declared_len = response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if declared_len.is_some_and(|len| len > max_size) {
+            return Err(AppForwardError::StrmFileTooLarge);
+        }
+
+        let body = http_body_util::Limited::new(
+            response.into_body(),
+            max_size as usize,
+        )
+        .collect()
+        .await
+        .map_err(|_| AppForwardError::StrmFileTooLarge)?
+        .to_bytes();
+
+        info_log!(
+            FORWARD_LOGGER_DOMAIN,
+            "strm_remote_fetch_complete node={} path={} bytes={}",
+            node.name,
+            path,
+            body.len()
+        );
+
+        String::from_utf8(body.to_vec())
+            .map_err(|e| AppForwardError::StrmFileIoError(e.to_string()))
+    }
+
+    async fn webdav_auth_headers(
+        state: &Arc<AppState>,
+        node: &BackendNode,
+        uri: &Uri,
+    ) -> Result<Option<HeaderMap>, AppForwardError> {
+        let Some(cfg) = node.webdav.as_ref() else {
+            return Ok(None);
+        };
+
+        let line = webdav_auth::authorization_header_for_proxy(
+            &state.webdav_auth_cache,
+            &state.webdav_auth_probe_locks,
+            node,
+            &Method::GET,
+            uri,
+            cfg,
+            None,
+            None,
+        )
+        .await
+        .map_err(|_| {
+            AppForwardError::StrmRemoteFetchError(
+                "webdav authorization failed".to_string(),
+            )
+        })?;
+
+        match line {
+            Some(line) => webdav_auth::extra_headers_from_auth_line(&line)
+                .map(Some)
+                .map_err(|e| {
+                    AppForwardError::StrmRemoteFetchError(e.to_string())
+                }),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use hyper::header;
+    use rustls::crypto::aws_lc_rs;
+
+    use super::{RemoteStrmReader, StrmEntry, headers_to_map};
+    use crate::{
+        config::backend::BackendNode,
+        core::error::Error as AppForwardError,
+        test_support::{
+            HttpMockHandler, TestConfig, http_response, spawn_http_mock_server,
+        },
+    };
+
+    static RUSTLS_CRYPTO_INIT: Once = Once::new();
+
+    fn ensure_rustls_crypto_provider() {
+        RUSTLS_CRYPTO_INIT.call_once(|| {
+            let _ = aws_lc_rs::default_provider().install_default();
+        });
+    }
+
+    fn webdav_node(base_url: &str, port: &str) -> BackendNode {
+        BackendNode {
+            name: "cloud".into(),
+            backend_type: crate::core::backend::webdav::BACKEND_TYPE.into(),
+            pattern: String::new(),
+            pattern_regex: None,
+            base_url: base_url.into(),
+            port: port.into(),
+            path: "dav".into(),
+            priority: 0,
+            proxy_mode: "proxy".into(),
+            client_speed_limit_kbs: 0,
+            client_burst_speed_kbs: 0,
+            path_rewrites: vec![],
+            anti_reverse_proxy: Default::default(),
+            path_rewriter_cache: vec![],
+            uuid: String::new(),
+            disk: None,
+            open_list: None,
+            direct_link: None,
+            google_drive: None,
+            webdav: None,
+            smb: None,
+            resilience: None,
+        }
+    }
+
+    #[test]
+    fn parse_plain_path_keeps_legacy_behaviour() {
+        let entry =
+            StrmEntry::parse("  /mnt/media/movie.mkv \n").expect("parse");
+
+        assert_eq!(entry.target, "/mnt/media/movie.mkv");
+        assert!(entry.headers.is_empty());
+        assert!(!entry.is_remote());
+    }
+
+    #[test]
+    fn parse_uses_first_playable_line_and_vlc_options() {
+        let content = "#EXTM3U\n\
+            #EXTINF:-1,Movie\n\
+            #EXTVLCOPT:http-user-agent=Player/1.0\n\
+            #EXTVLCOPT:http-referrer=https://site.example/\n\
+            #EXTVLCOPT:network-caching=1000\n\
+            https://cdn.example.com/movie.mkv\n\
+            https://cdn.example.com/backup.mkv\n";
+
+        let entry = StrmEntry::parse(content).expect("parse");
+
+        assert_eq!(entry.target, "https://cdn.example.com/movie.mkv");
+        assert!(entry.is_remote());
+        assert_eq!(
+            entry.headers,
+            vec![
+                ("user-agent".to_string(), "Player/1.0".to_string()),
+                ("referer".to_string(), "https://site.example/".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_kodi_inline_headers_override_directives() {
+        let content = "#EXTVLCOPT:http-user-agent=Old/1.0\n\
+            https://cdn.example.com/a.mkv|User-Agent=New%2F2.0&X-Token=abc\n";
+
+        let entry = StrmEntry::parse(content).expect("parse");
+        let map = headers_to_map(&entry.headers);
+
+        assert_eq!(entry.target, "https://cdn.example.com/a.mkv");
+        assert_eq!(
+            map.get(header::USER_AGENT).and_then(|v| v.to_str().ok()),
+            Some("New/2.0")
+        );
+        assert_eq!(
+            map.get("x-token").and_then(|v| v.to_str().ok()),
+            Some("abc")
+        );
+    }
+
+    #[test]
+    fn parse_rejects_comment_only_content() {
+        let result = StrmEntry::parse("#EXTM3U\n#EXTINF:-1,Nothing\n");
+
+        assert!(matches!(result, Err(AppForwardError::EmptyStrmFile)));
+    }
+
+    #[tokio::test]
+    async fn remote_reader_fetches_strm_through_webdav_node() {
+        ensure_rustls_crypto_provider();
+        let handlers: Vec<HttpMockHandler> = vec![Box::new(|request| {
+            Box::pin(async move {
+                assert!(request.starts_with("GET /dav/media/movie%2Estrm "));
+                http_response(
+                    200,
+                    "text/plain",
+                    "#EXTVLCOPT:http-user-agent=Player/1.0\nhttps://cdn.example.com/movie.mkv\n",
+                )
+            })
+        })];
+        let base = spawn_http_mock_server(handlers).await;
+        let (host, port) = base.rsplit_once(':').expect("mock base");
+        let node = webdav_node(host, port);
+        let state = TestConfig::new().app_state().await;
+
+        let content =
+            RemoteStrmReader::read(&state, &node, "/media/movie.strm", 1024)
+                .await
+                .expect("remote strm");
+        let entry = StrmEntry::parse(&content).expect("parse");
+
+        assert_eq!(entry.target, "https://cdn.example.com/movie.mkv");
+        assert_eq!(entry.headers.len(), 1);
+    }
+}
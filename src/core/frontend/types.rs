@@ -28,6 +28,7 @@ pub struct ForwardConfig {
     pub crypto_key: String,
     pub crypto_iv: String,
     pub emby_api_key: String,
+    pub check_file_existence: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,11 +34,23 @@ impl Default for SignParams {
 pub struct Sign {
     pub uri: Option<Uri>,
     pub expired_at: Option<u64>,
+    /// Upstream request headers declared by a STRM file; only applied when
+    /// the backend proxies a remote target.
+    pub headers: Vec<(String, String)>,
 }
 
 impl Sign {
     pub fn new(uri: Option<Uri>, expired_at: Option<u64>) -> Self {
-        Self { uri, expired_at }
+        Self {
+            uri,
+            expired_at,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
     }
 
     pub fn from_map(map: &HashMap<String, String>) -> Self {
@@ -53,6 +65,11 @@ impl Sign {
             sign.expired_at = expired_at_str.parse::<u64>().ok();
         }
 
+        if let Some(headers_str) = map.get("headers") {
+            sign.headers =
+                serde_json::from_str(headers_str).unwrap_or_default();
+        }
+
         sign
     }
 
@@ -73,6 +90,12 @@ impl Sign {
             map.insert("expired_at".to_string(), expired_at.to_string());
         }
 
+        if !self.headers.is_empty() {
+            if let Ok(headers) = serde_json::to_string(&self.headers) {
+                map.insert("headers".to_string(), headers);
+            }
+        }
+
         map
     }
 
@@ -97,3 +120,23 @@ impl Sign {
         !uri.to_string().is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Sign;
+
+    #[test]
+    fn map_round_trip_keeps_strm_headers() {
+        let sign = Sign::new(
+            Some("https://cdn.example.com/a.mkv".parse().expect("uri")),
+            Some(42),
+        )
+        .with_headers(vec![("Referer".into(), "https://site.example/".into())]);
+
+        let restored = Sign::from_map(&sign.to_map());
+
+        assert_eq!(restored.expired_at, Some(42));
+        assert_eq!(restored.headers, sign.headers);
+        assert!(!Sign::new(None, Some(1)).to_map().contains_key("headers"));
+    }
+}
@@ -1,3 +1,4 @@
+pub const ACME_LOGGER_DOMAIN: &str = "ACME";
 /// Default domain used for logging macros when no custom domain is provided.
 /// This helps categorize log messages consistently across the application.
 pub const API_CACHE_LOGGER_DOMAIN: &str = "API-CACHE";
@@ -13,13 +14,16 @@ pub const INIT_LOGGER_DOMAIN: &str = "INIT";
 pub const LOCAL_STREAMER_LOGGER_DOMAIN: &str = "LOCAL-STREAM";
 pub const METADATA_CACHE_LOGGER_DOMAIN: &str = "METADATA-CACHE";
 pub const NETWORK_LOGGER_DOMAIN: &str = "NETWORK";
+pub const OPENLIST_AUTH_LOGGER_DOMAIN: &str = "OPENLIST-AUTH";
 pub const PATH_REWRITER_LOGGER_DOMAIN: &str = "PATH-REWRITER";
 pub const PLAYBACK_INFO_LOGGER_DOMAIN: &str = "PLAYBACK-INFO";
 pub const PLAYLIST_MOCK_LOGGER_DOMAIN: &str = "PLAYLIST-MOCK";
 pub const READ_STREAM_LOGGER_DOMAIN: &str = "READ-STREAM";
 pub const REMOTE_STREAMER_LOGGER_DOMAIN: &str = "REMOTE-STREAM";
+pub const RESILIENCE_LOGGER_DOMAIN: &str = "RESILIENCE";
 pub const REVERSE_PROXY_FILTER_LOGGER_DOMAIN: &str = "REVERSE-PROXY-FILTER";
 pub const REVERSE_PROXY_LOGGER_DOMAIN: &str = "REVERSE-PROXY";
+pub const SMB_LOGGER_DOMAIN: &str = "SMB";
 pub const STREAM_LOGGER_DOMAIN: &str = "STREAM";
 pub const UPSTREAM_PROXY_LOGGER_DOMAIN: &str = "UPSTREAM-PROXY";
 pub const WEBDAV_AUTH_LOGGER_DOMAIN: &str = "WEBDAV-AUTH";
@@ -1,10 +1,8 @@
 use std::path::PathBuf;
 use std::{
     error::Error as StdError,
-    fs::File,
-    io::{BufReader, Error as IoError, ErrorKind as IoErrorKind},
+    io::{Error as IoError, ErrorKind as IoErrorKind},
     net::SocketAddr,
-    path::Path,
     str,
     sync::Arc,
 };
@@ -12,9 +10,12 @@ use std::{
 use super::{
     chain::{Handler, Middleware},
     svc::Svc,
+    tls::{ReloadingTlsConfig, TlsSettings},
 };
 use crate::{
-    GATEWAY_LOGGER_DOMAIN, debug_log, error_log,
+    GATEWAY_LOGGER_DOMAIN,
+    acme::challenge::ACME_TLS_ALPN_PROTOCOL,
+    debug_log, error_log,
     gateway::{
         context::Context,
         response::{BoxBodyType, ResponseBuilder},
@@ -26,16 +27,14 @@ use hyper_util::{
     rt::{TokioExecutor, TokioIo},
     server::conn::auto as hyper_conn_auto,
 };
-use rustls::{ServerConfig, crypto::aws_lc_rs};
+use rustls::crypto::aws_lc_rs;
 use tokio::net::TcpListener;
-use tokio_rustls::TlsAcceptor;
 
 pub struct Gateway {
     addr: String,
     handler: Option<Handler>,
     middlewares: Vec<Box<dyn Middleware>>,
-    cert_path: Option<String>,
-    key_path: Option<String>,
+    tls: Option<TlsSettings>,
 }
 
 impl Gateway {
@@ -44,34 +43,46 @@ impl Gateway {
             addr: addr.to_string(),
             handler: None,
             middlewares: Vec::new(),
-            cert_path: None,
-            key_path: None,
+            tls: None,
         }
     }
 
     pub fn with_tls(
-        mut self,
+        self,
         cert_path: Option<PathBuf>,
         key_path: Option<PathBuf>,
     ) -> Self {
-        if let (Some(cert), Some(key)) = (cert_path, key_path) {
-            if cert.exists() && key.exists() {
-                debug_log!(
-                    GATEWAY_LOGGER_DOMAIN,
-                    "SSL certificate exist, start loading cert_path={:?}, key_path={:?}",
-                    cert,
-                    key
-                );
-                self.cert_path = Some(cert.to_string_lossy().into_owned());
-                self.key_path = Some(key.to_string_lossy().into_owned());
-            } else {
-                warn_log!(
-                    GATEWAY_LOGGER_DOMAIN,
-                    "SSL certificate does not exist: cert_path={:?}, key_path={:?}",
-                    cert,
-                    key
-                );
+        match (cert_path, key_path) {
+            (Some(cert), Some(key)) => {
+                self.with_tls_settings(Some(TlsSettings::new(cert, key)))
             }
+            _ => self,
+        }
+    }
+
+    /// Enables TLS with SNI certificates; the files are watched and the
+    /// listener picks up renewed certificates without a restart.
+    pub fn with_tls_settings(mut self, settings: Option<TlsSettings>) -> Self {
+        let Some(settings) = settings else {
+            return self;
+        };
+        let default = &settings.default;
+        if default.exist() {
+            debug_log!(
+                GATEWAY_LOGGER_DOMAIN,
+                "SSL certificate exist, start loading cert_path={:?}, key_path={:?}, sni_entries={}",
+                default.cert_path,
+                default.key_path,
+                settings.sni.len()
+            );
+            self.tls = Some(settings);
+        } else {
+            warn_log!(
+                GATEWAY_LOGGER_DOMAIN,
+                "SSL certificate does not exist: cert_path={:?}, key_path={:?}",
+                default.cert_path,
+                default.key_path
+            );
         }
         self
     }
@@ -114,20 +125,16 @@ impl Gateway {
         middlewares: Arc<Vec<Box<dyn Middleware>>>,
     ) -> Result<(), Box<dyn StdError + Send + Sync>> {
         let addr = listener.local_addr()?;
-        if let (Some(cert_path), Some(key_path)) =
-            (&self.cert_path, &self.key_path)
-        {
-            match self
-                .load_tls_config(Path::new(cert_path), Path::new(key_path))
-            {
+        if let Some(settings) = &self.tls {
+            match ReloadingTlsConfig::load(settings.clone()) {
                 Ok(tls_config) => {
-                    let tls_acceptor = TlsAcceptor::from(Arc::new(tls_config));
+                    tls_config.spawn_watcher();
                     self.run_https_server(
                         &addr,
                         listener,
                         handler,
                         middlewares,
-                        tls_acceptor,
+                        tls_config,
                     )
                     .await
                 }
@@ -188,7 +195,7 @@ impl Gateway {
         listener: TcpListener,
         handler: Handler,
         middlewares: Arc<Vec<Box<dyn Middleware>>>,
-        tls_acceptor: TlsAcceptor,
+        tls_config: Arc<ReloadingTlsConfig>,
     ) -> Result<(), Box<dyn StdError + Send + Sync>> {
         info_log!(
             GATEWAY_LOGGER_DOMAIN,
@@ -203,7 +210,7 @@ impl Gateway {
                 peer_addr
             );
 
-            let tls_acceptor = tls_acceptor.clone();
+            let tls_acceptor = tls_config.acceptor();
             let service = Svc::new(handler.clone(), middlewares.clone());
 
             tokio::spawn(async move {
@@ -228,6 +235,15 @@ impl Gateway {
                             alpn_protocol
                         );
 
+                        if alpn_protocol.as_bytes() == ACME_TLS_ALPN_PROTOCOL {
+                            debug_log!(
+                                GATEWAY_LOGGER_DOMAIN,
+                                "ACME tls-alpn-01 validation handshake from {} done",
+                                peer_addr
+                            );
+                            return;
+                        }
+
                         let io = TokioIo::new(tls_stream);
                         debug_log!(
                             GATEWAY_LOGGER_DOMAIN,
@@ -265,30 +281,6 @@ impl Gateway {
         }
     }
 
-    fn load_tls_config(
-        &self,
-        cert_path: &Path,
-        key_path: &Path,
-    ) -> Result<ServerConfig, Box<dyn StdError + Send + Sync>> {
-        let cert_file = File::open(cert_path).map_err(|e| {
-            format!("failed to open cert file {cert_path:?}: {e}")
-        })?;
-        let certs = rustls_pemfile::certs(&mut BufReader::new(cert_file))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let key_file = File::open(key_path).map_err(|e| {
-            format!("failed to open key file {key_path:?}: {e}")
-        })?;
-        let key = rustls_pemfile::private_key(&mut BufReader::new(key_file))?
-            .ok_or("no private key found in file")?;
-
-        let mut config = ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(certs, key)?;
-        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
-        Ok(config)
-    }
-
     fn default_handler() -> Handler {
         Arc::new(
             |_ctx: Context, _body: Option<Incoming>| -> Response<BoxBodyType> {
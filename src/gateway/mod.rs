@@ -15,6 +15,7 @@ pub mod response;
 pub mod reverse_proxy;
 pub mod reverse_proxy_filter;
 pub mod svc;
+pub mod tls;
 
 #[cfg(test)]
 mod logger_test;
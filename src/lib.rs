@@ -1,9 +1,11 @@
+pub mod acme;
 pub mod api;
 pub mod app;
 pub mod auth;
 pub mod cache;
 pub mod cli;
 pub mod cli_lang;
+pub mod cli_secrets;
 pub mod cli_wizard;
 pub mod client;
 pub mod config;
@@ -13,19 +13,22 @@ use embystream::{
     i18n::lookup, info_log,
 };
 use embystream::{
+    acme::{AcmeChallengeMiddleware, AcmeManager},
     auth::google::{GoogleAuthArgs, run_google_auth},
     backend::{
-        google_drive_auth, service::AppStreamService, stream::StreamMiddleware,
+        google_drive_auth, google_drive_index, probe::ProbeMiddleware,
+        resilience, service::AppStreamService, stream::StreamMiddleware,
         stream_relay::StreamRelayMiddleware,
     },
     cli::{
         AuthSubcommand, Cli, Commands, RunArgs, WebAdminSubcommand, WebArgs,
     },
     cli_lang::{detect_lang_from_env_early, localize_cli_command},
-    cli_wizard,
+    cli_secrets, cli_wizard,
     config::{
         core::{Config, LoadConfigOutcome},
         general::StreamMode,
+        http2::SniCertificate,
     },
     frontend::{forward::ForwardMiddleware, service::AppForwardService},
     gateway::{
@@ -34,7 +37,7 @@ use embystream::{
         client_filter::ClientAgentFilterMiddleware, context::Context,
         core::Gateway, filtered_routes::COMPILED_UA_FILTERS,
         response::ResponseBuilder,
-        reverse_proxy_filter::ReverseProxyFilterMiddleware,
+        reverse_proxy_filter::ReverseProxyFilterMiddleware, tls::TlsSettings,
     },
     log_stream::global_log_stream,
     logger::{LogLevel, Logger, start_cleanup_task},
@@ -72,6 +75,28 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                         args.username, password
                     );
                 }
+                WebAdminSubcommand::DisableTwoFactor(args) => {
+                    let db = Database::new(args.data_dir);
+                    db.initialize().await?;
+                    let user_id = db
+                        .disable_totp_for_username(args.username.clone())
+                        .await?;
+                    db.write_audit_log(
+                        None,
+                        "disable_totp",
+                        "user",
+                        Some(user_id),
+                        serde_json::json!({
+                            "username": args.username,
+                            "source": "cli",
+                        }),
+                    )
+                    .await?;
+                    println!(
+                        "Two-factor authentication disabled for '{}'.",
+                        args.username
+                    );
+                }
             },
         },
         Some(Commands::Auth(auth_args)) => match auth_args.sub {
@@ -91,6 +116,12 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                 process::exit(1);
             }
         }
+        Some(Commands::Secrets(ref secrets_args)) => {
+            if let Err(e) = cli_secrets::run(secrets_args) {
+                eprintln!("secrets: {e}");
+                process::exit(1);
+            }
+        }
         None => {}
     }
     Ok(())
@@ -192,6 +223,7 @@ async fn initialize_stream_runtime(
         .map_err(|error| IoError::other(error.to_string()))?;
 
     setup_crypto_provider()?;
+    setup_acme(config);
 
     let app_state = setup_cache(config).await;
 
@@ -228,6 +260,20 @@ async fn initialize_stream_runtime(
     Ok(())
 }
 
+fn setup_acme(config: &Config) {
+    let Some(manager) = AcmeManager::from_config(config) else {
+        return;
+    };
+    if let Err(e) = manager.ensure_placeholder_certificate() {
+        error_log!(
+            INIT_LOGGER_DOMAIN,
+            "ACME placeholder certificate failed: {}",
+            e
+        );
+    }
+    manager.spawn();
+}
+
 fn setup_figlet() {
     if let Ok(standard_font) = FIGfont::standard() {
         if let Some(figure) = standard_font.convert("EMBYSTREAM") {
@@ -345,7 +391,9 @@ fn setup_logger(config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
 }
 
 async fn setup_cache(config: &Config) -> Arc<AppState> {
-    let app_state = AppState::new(config.clone()).await;
+    let app_state = Arc::new(AppState::new(config.clone()).await);
+    resilience::publish_runtime_breakers(app_state.circuit_breakers());
+    embystream::publish_runtime_state(app_state.clone());
 
     let problematic_clients = app_state.get_problematic_clients().await;
     info_log!(
@@ -354,7 +402,7 @@ async fn setup_cache(config: &Config) -> Arc<AppState> {
         problematic_clients
     );
 
-    Arc::new(app_state)
+    app_state
 }
 
 fn validate_dual_mode_ports(config: &Config) -> Result<(), String> {
@@ -410,6 +458,18 @@ async fn setup_google_drive_refresh(app_state: &Arc<AppState>) {
     tokio::spawn(google_drive_auth::schedule_google_drive_token_refreshes(
         app_state.clone(),
     ));
+    let indexed_node_count =
+        google_drive_index::reconcile_google_drive_folder_indexers(
+            app_state.clone(),
+        )
+        .await;
+    if indexed_node_count > 0 {
+        info_log!(
+            INIT_LOGGER_DOMAIN,
+            "googleDrive folder index enabled (nodes: {})",
+            indexed_node_count
+        );
+    }
     info_log!(
         INIT_LOGGER_DOMAIN,
         "googleDrive request-time token source enabled \
@@ -454,8 +514,21 @@ async fn setup_frontend_gateway(
         emby_base_url
     );
 
+    let frontend_sni = config
+        .http2
+        .frontend
+        .as_ref()
+        .map(|tls| tls.sni.as_slice())
+        .unwrap_or_default();
     let mut gateway = Gateway::new(&addr)
+        .with_tls_settings(tls_settings(
+            &config,
+            config.get_frontend_ssl_cert_path(),
+            config.get_frontend_ssl_key_path(),
+            frontend_sni,
+        ))
         .add_middleware(Box::new(LoggerMiddleware))
+        .add_middleware(Box::new(AcmeChallengeMiddleware))
         .add_middleware(Box::new(
             ClientAgentFilterMiddleware::new(app_state.clone())
                 .with_filter_paths(COMPILED_UA_FILTERS.clone()),
@@ -479,6 +552,28 @@ async fn setup_frontend_gateway(
     Ok(())
 }
 
+fn tls_settings(
+    config: &Config,
+    cert_path: Option<PathBuf>,
+    key_path: Option<PathBuf>,
+    sni: &[SniCertificate],
+) -> Option<TlsSettings> {
+    let (cert_path, key_path) = (cert_path?, key_path?);
+    let settings = sni.iter().fold(
+        TlsSettings::new(cert_path, key_path),
+        |settings, entry| match (
+            config.resolve_ssl_path(&entry.ssl_cert_file, ""),
+            config.resolve_ssl_path(&entry.ssl_key_file, ""),
+        ) {
+            (Some(cert), Some(key)) => {
+                settings.with_sni(entry.server_names.clone(), cert, key)
+            }
+            _ => settings,
+        },
+    );
+    Some(settings)
+}
+
 async fn setup_backend_gateway(
     app_state: &Arc<AppState>,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -507,18 +602,25 @@ async fn setup_backend_gateway(
     let service = Arc::new(AppStreamService::new(app_state.clone()));
 
     let mut gateway = Gateway::new(&addr)
-        .with_tls(config.get_ssl_cert_path(), config.get_ssl_key_path())
+        .with_tls_settings(tls_settings(
+            &config,
+            config.get_ssl_cert_path(),
+            config.get_ssl_key_path(),
+            &config.http2.sni,
+        ))
         .add_middleware(Box::new(LoggerMiddleware))
+        .add_middleware(Box::new(AcmeChallengeMiddleware))
         .add_middleware(Box::new(ClientAgentFilterMiddleware::new(
             app_state.clone(),
         )))
         .add_middleware(Box::new(CorsMiddleware))
         .add_middleware(Box::new(OptionsMiddleware))
-        .add_middleware(Box::new(StreamRelayMiddleware::new(
-            config.backend_nodes.clone(),
+        .add_middleware(Box::new(ProbeMiddleware::new(
+            service.clone(),
+            app_state.clone(),
         )))
+        .add_middleware(Box::new(StreamRelayMiddleware::new(app_state.clone())))
         .add_middleware(Box::new(StreamMiddleware::new(
-            config.backend_nodes.clone(),
             service,
             app_state.clone(),
         )));
@@ -3,6 +3,8 @@
 //! This module implements the core network provider that handles HTTP requests,
 //! including request building, sending, and plugin integration.
 
+use std::time::Duration;
+
 use once_cell::sync::Lazy;
 use reqwest::{
     Client, Error, Method, Response,
@@ -27,6 +29,7 @@ const NETWORK_LOGGER_DOMAIN: &str = "network";
 /// - Accept invalid certificates (for development)
 /// - Accept invalid hostnames (for development)
 /// - Use a standard browser user agent
+/// - Bound connect and read time so a dead upstream cannot hang a caller
 static CLIENT: Lazy<Client> = Lazy::new(|| {
     let sys_info = SystemInfo::new();
     match Client::builder()
@@ -34,6 +37,8 @@ static CLIENT: Lazy<Client> = Lazy::new(|| {
         .danger_accept_invalid_certs(true)
         .danger_accept_invalid_hostnames(true)
         .user_agent(sys_info.get_user_agent())
+        .connect_timeout(Duration::from_secs(10))
+        .read_timeout(Duration::from_secs(30))
         .build()
     {
         Ok(client) => client,
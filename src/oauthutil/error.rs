@@ -14,6 +14,10 @@ pub enum TokenSourceError {
     StoreRead { node: String, error: String },
     #[error("googleDrive token store write failed for node '{node}': {error}")]
     StoreWrite { node: String, error: String },
+    #[error(
+        "googleDrive service account key is unusable for node '{node}': {error}"
+    )]
+    ServiceAccountKey { node: String, error: String },
     #[error("googleDrive token refresh failed for node '{node}': {error}")]
     Refresh { node: String, error: String },
     #[error(
@@ -182,6 +182,15 @@ impl GoogleDriveTokenSource {
         })
     }
 
+    /// Access token currently cached for the node, if any.
+    pub fn cached_access_token(&self) -> Option<String> {
+        let (node_uuid, _) = self.local_token().ok()?;
+        self.state
+            .google_drive_token_cache
+            .get(&cache_key(&node_uuid))
+            .map(|entry| entry.value().access_token.clone())
+    }
+
     pub fn invalidate(&self) {
         if let Ok((node_uuid, _)) = self.local_token() {
             self.state
@@ -374,7 +383,7 @@ mod tests {
         AppState,
         client::google_drive::GoogleTokenRefreshResponse,
         config::{
-            backend::{BackendNode, GoogleDriveConfig},
+            backend::{BackendNode, GoogleDriveConfig, GoogleDriveRoot},
             core::{finish_raw_config, parse_raw_config_str},
         },
         oauthutil::{OAuthToken, TokenRequest, TokenSourceError},
@@ -443,6 +452,12 @@ host = ""
                 client_secret: "client-secret".to_string(),
                 drive_id: "drive-id".to_string(),
                 drive_name: "drive-name".to_string(),
+                root: GoogleDriveRoot::SharedDrive,
+                root_folder_id: String::new(),
+                service_account_files: vec![],
+                service_account_subject: String::new(),
+                folder_index: false,
+                token_secret: String::new(),
                 access_token: access_token.to_string(),
                 refresh_token: refresh_token.to_string(),
                 token: Some(OAuthToken {
@@ -453,6 +468,8 @@ host = ""
                 }),
             }),
             webdav: None,
+            smb: None,
+            resilience: None,
         }
     }
 
@@ -1,10 +1,12 @@
 mod error;
 mod google;
+mod service_account;
 mod source;
 mod store;
 mod token;
 
 pub use error::TokenSourceError;
 pub use google::GoogleDriveTokenSource;
+pub use service_account::{ServiceAccountKey, ServiceAccountTokenSource};
 pub use source::{TokenRequest, TokenSnapshot};
 pub use token::OAuthToken;
@@ -0,0 +1,464 @@
+use std::{future::Future, io::BufReader, path::Path, sync::Arc};
+
+use aws_lc_rs::{
+    rand::SystemRandom,
+    signature::{RSA_PKCS1_SHA256, RsaKeyPair},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use rustls::pki_types::PrivateKeyDer;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::{
+    AppState,
+    config::backend::BackendNode,
+    debug_log, error_log, info_log,
+    oauthutil::{
+        TokenSnapshot, TokenSourceError, source::TokenRequest,
+        token::OAuthToken,
+    },
+    util::StringUtil,
+};
+
+const LOGGER_DOMAIN: &str = "GOOGLE-DRIVE-AUTH";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const DRIVE_READONLY_SCOPE: &str =
+    "https://www.googleapis.com/auth/drive.readonly";
+const ASSERTION_LIFETIME_SECS: i64 = 3600;
+const MINT_FAILURE_BACKOFF_SECS: i64 = 30;
+
+/// The parts of a Google service-account JSON key needed to sign JWTs.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServiceAccountKey {
+    pub client_email: String,
+    private_key: String,
+    #[serde(default)]
+    private_key_id: String,
+    #[serde(default)]
+    token_uri: String,
+}
+
+impl ServiceAccountKey {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| format!("read {}: {error}", path.display()))?;
+        Self::from_json(&content)
+            .map_err(|error| format!("parse {}: {error}", path.display()))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let key: Self =
+            serde_json::from_str(content).map_err(|error| error.to_string())?;
+        if key.client_email.trim().is_empty() {
+            return Err("client_email is empty".to_string());
+        }
+        key.signing_key()?;
+        Ok(key)
+    }
+
+    fn signing_key(&self) -> Result<RsaKeyPair, String> {
+        let der = rustls_pemfile::private_key(&mut BufReader::new(
+            self.private_key.as_bytes(),
+        ))
+        .map_err(|error| format!("private_key: {error}"))?
+        .ok_or_else(|| "private_key holds no PEM key".to_string())?;
+        let PrivateKeyDer::Pkcs8(der) = der else {
+            return Err("private_key is not a PKCS#8 key".to_string());
+        };
+        RsaKeyPair::from_pkcs8(der.secret_pkcs8_der())
+            .map_err(|error| format!("private_key: {error}"))
+    }
+
+    fn token_uri(&self) -> &str {
+        match self.token_uri.trim() {
+            "" => DEFAULT_TOKEN_URI,
+            uri => uri,
+        }
+    }
+
+    /// Signs the RS256 assertion for the `jwt-bearer` grant, impersonating
+    /// `subject` when domain-wide delegation is configured.
+    pub fn assertion(
+        &self,
+        subject: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<String, String> {
+        let mut header = json!({ "alg": "RS256", "typ": "JWT" });
+        if !self.private_key_id.trim().is_empty() {
+            header["kid"] = json!(self.private_key_id.trim());
+        }
+        let issued_at = now.timestamp();
+        let mut claims = json!({
+            "iss": self.client_email.trim(),
+            "scope": DRIVE_READONLY_SCOPE,
+            "aud": self.token_uri(),
+            "iat": issued_at,
+            "exp": issued_at + ASSERTION_LIFETIME_SECS,
+        });
+        if let Some(subject) = subject.filter(|value| !value.trim().is_empty())
+        {
+            claims["sub"] = json!(subject.trim());
+        }
+
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let key_pair = self.signing_key()?;
+        let mut signature = vec![0; key_pair.public_modulus_len()];
+        key_pair
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                signing_input.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|error| format!("sign assertion: {error}"))?;
+
+        Ok(format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+}
+
+/// Mints and caches access tokens for one service-account key of a
+/// googleDrive node. Tokens are never persisted: a fresh one can always be
+/// signed from the key file.
+pub struct ServiceAccountTokenSource {
+    state: Arc<AppState>,
+    node: BackendNode,
+    key_path: String,
+}
+
+impl ServiceAccountTokenSource {
+    pub fn new(
+        state: Arc<AppState>,
+        node: BackendNode,
+        key_path: &str,
+    ) -> Self {
+        Self {
+            state,
+            node,
+            key_path: key_path.trim().to_string(),
+        }
+    }
+
+    pub async fn token(
+        &self,
+        request: TokenRequest,
+    ) -> Result<TokenSnapshot, TokenSourceError> {
+        self.token_with_mint(request, |key, subject| async move {
+            self.mint_from_google(&key, subject.as_deref()).await
+        })
+        .await
+    }
+
+    async fn token_with_mint<MintFn, MintFut>(
+        &self,
+        request: TokenRequest,
+        mint_fn: MintFn,
+    ) -> Result<TokenSnapshot, TokenSourceError>
+    where
+        MintFn: Fn(ServiceAccountKey, Option<String>) -> MintFut,
+        MintFut: Future<Output = Result<OAuthToken, TokenSourceError>>,
+    {
+        let cache_key = self.cache_key()?;
+
+        if !request.force_refresh {
+            if let Some(token) =
+                self.cached_token(&cache_key, request.min_valid_for)
+            {
+                debug_log!(
+                    LOGGER_DOMAIN,
+                    "google_drive_service_account_token_ready node={} \
+                     key_path={} reason={} source=cache",
+                    self.node.name,
+                    self.key_path,
+                    request.reason
+                );
+                return Ok(TokenSnapshot {
+                    token,
+                    source: "service_account_cache",
+                });
+            }
+        }
+
+        let lock = self
+            .state
+            .google_drive_refresh_locks
+            .entry(cache_key.clone())
+            .or_insert_with(|| Arc::new(AsyncMutex::new(())))
+            .clone();
+        let _guard = lock.lock().await;
+
+        if !request.force_refresh {
+            if let Some(token) =
+                self.cached_token(&cache_key, request.min_valid_for)
+            {
+                return Ok(TokenSnapshot {
+                    token,
+                    source: "service_account_cache_after_wait",
+                });
+            }
+        }
+
+        self.ensure_mint_not_in_backoff(&cache_key)?;
+        let (key, subject) = self.load_key()?;
+        info_log!(
+            LOGGER_DOMAIN,
+            "google_drive_service_account_mint_start node={} account={} \
+             reason={}",
+            self.node.name,
+            key.client_email,
+            request.reason
+        );
+
+        let minted = match mint_fn(key.clone(), subject).await {
+            Ok(token) => {
+                self.state
+                    .google_drive_refresh_backoff_until
+                    .remove(&cache_key);
+                token
+            }
+            Err(error) => {
+                self.state.google_drive_refresh_backoff_until.insert(
+                    cache_key,
+                    Utc::now() + Duration::seconds(MINT_FAILURE_BACKOFF_SECS),
+                );
+                return Err(error);
+            }
+        };
+        self.state
+            .google_drive_token_cache
+            .insert(cache_key, minted.clone());
+
+        info_log!(
+            LOGGER_DOMAIN,
+            "google_drive_service_account_mint_success node={} account={} \
+             remaining_secs={}",
+            self.node.name,
+            key.client_email,
+            minted
+                .remaining_lifetime(Utc::now())
+                .map(|value| value.num_seconds())
+                .unwrap_or_default()
+        );
+
+        Ok(TokenSnapshot {
+            token: minted,
+            source: "service_account",
+        })
+    }
+
+    /// Access token currently cached for this key, if any.
+    pub fn cached_access_token(&self) -> Option<String> {
+        let cache_key = self.cache_key().ok()?;
+        self.state
+            .google_drive_token_cache
+            .get(&cache_key)
+            .map(|entry| entry.value().access_token.clone())
+    }
+
+    pub fn invalidate(&self) {
+        if let Ok(cache_key) = self.cache_key() {
+            self.state.google_drive_token_cache.remove(&cache_key);
+            self.state
+                .google_drive_refresh_backoff_until
+                .remove(&cache_key);
+        }
+    }
+
+    fn load_key(
+        &self,
+    ) -> Result<(ServiceAccountKey, Option<String>), TokenSourceError> {
+        let cfg = self.node.google_drive.as_ref().ok_or_else(|| {
+            TokenSourceError::MissingGoogleDriveConfig {
+                node: self.node.name.clone(),
+            }
+        })?;
+        let key = ServiceAccountKey::from_file(Path::new(&self.key_path))
+            .map_err(|error| TokenSourceError::ServiceAccountKey {
+                node: self.node.name.clone(),
+                error,
+            })?;
+        let subject = Some(cfg.service_account_subject.trim().to_string())
+            .filter(|subject| !subject.is_empty());
+        Ok((key, subject))
+    }
+
+    fn cache_key(&self) -> Result<String, TokenSourceError> {
+        let cfg = self.node.google_drive.as_ref().ok_or_else(|| {
+            TokenSourceError::MissingGoogleDriveConfig {
+                node: self.node.name.clone(),
+            }
+        })?;
+        let node_uuid = cfg.node_uuid.trim();
+        if node_uuid.is_empty() {
+            return Err(TokenSourceError::MissingNodeUuid {
+                node: self.node.name.clone(),
+            });
+        }
+        Ok(cache_key(node_uuid, &self.key_path))
+    }
+
+    fn cached_token(
+        &self,
+        cache_key: &str,
+        min_valid_for: Duration,
+    ) -> Option<OAuthToken> {
+        self.state
+            .google_drive_token_cache
+            .get(cache_key)
+            .map(|entry| entry.value().clone())
+            .filter(|token| token.is_valid_for(min_valid_for, Utc::now()))
+    }
+
+    fn ensure_mint_not_in_backoff(
+        &self,
+        cache_key: &str,
+    ) -> Result<(), TokenSourceError> {
+        let Some(until) = self
+            .state
+            .google_drive_refresh_backoff_until
+            .get(cache_key)
+            .map(|value| *value.value())
+        else {
+            return Ok(());
+        };
+
+        let remaining = until - Utc::now();
+        if remaining > Duration::zero() {
+            return Err(TokenSourceError::RefreshBackoff {
+                node: self.node.name.clone(),
+                retry_after_secs: remaining.num_seconds(),
+                reason: "recent_service_account_failure".to_string(),
+            });
+        }
+
+        self.state
+            .google_drive_refresh_backoff_until
+            .remove(cache_key);
+        Ok(())
+    }
+
+    async fn mint_from_google(
+        &self,
+        key: &ServiceAccountKey,
+        subject: Option<&str>,
+    ) -> Result<OAuthToken, TokenSourceError> {
+        let assertion =
+            key.assertion(subject, Utc::now()).map_err(|error| {
+                TokenSourceError::ServiceAccountKey {
+                    node: self.node.name.clone(),
+                    error,
+                }
+            })?;
+        let client = self.state.get_google_drive_client().await.clone();
+        let minted = client.exchange_jwt_assertion(&assertion).await.map_err(
+            |error| {
+                error_log!(
+                    LOGGER_DOMAIN,
+                    "google_drive_service_account_mint_failed node={} \
+                         account={} error={}",
+                    self.node.name,
+                    key.client_email,
+                    error
+                );
+                TokenSourceError::Refresh {
+                    node: self.node.name.clone(),
+                    error: error.to_string(),
+                }
+            },
+        )?;
+
+        let expiry = minted
+            .expires_in
+            .map(|seconds| Utc::now() + Duration::seconds(seconds as i64));
+        Ok(OAuthToken::from_refresh_parts(
+            minted.access_token,
+            String::new(),
+            minted.token_type,
+            expiry,
+        ))
+    }
+}
+
+fn cache_key(node_uuid: &str, key_path: &str) -> String {
+    format!(
+        "google-drive-token:{}:service-account:{}",
+        node_uuid.trim().to_ascii_lowercase(),
+        StringUtil::hash_hex(key_path.trim())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use chrono::{TimeZone, Utc};
+    use rcgen::{KeyPair, PKCS_RSA_SHA256};
+    use serde_json::Value;
+
+    use super::ServiceAccountKey;
+
+    fn test_key_json() -> String {
+        let key_pair =
+            KeyPair::generate_for(&PKCS_RSA_SHA256).expect("rsa key");
+        serde_json::json!({
+            "type": "service_account",
+            "client_email": "pool-1@project.iam.gserviceaccount.com",
+            "private_key_id": "kid-1",
+            "private_key": key_pair.serialize_pem(),
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn assertion_carries_drive_scope_and_subject() {
+        let key = ServiceAccountKey::from_json(&test_key_json()).expect("key");
+        let now = Utc
+            .with_ymd_and_hms(2026, 10, 1, 12, 0, 0)
+            .single()
+            .expect("timestamp");
+
+        let assertion =
+            key.assertion(Some("media@example.com"), now).expect("sign");
+        let parts: Vec<&str> = assertion.split('.').collect();
+        assert_eq!(parts.len(), 3);
+        let header: Value = serde_json::from_slice(
+            &URL_SAFE_NO_PAD.decode(parts[0]).expect("header"),
+        )
+        .expect("header json");
+        let claims: Value = serde_json::from_slice(
+            &URL_SAFE_NO_PAD.decode(parts[1]).expect("claims"),
+        )
+        .expect("claims json");
+
+        assert_eq!(header["alg"], "RS256");
+        assert_eq!(header["kid"], "kid-1");
+        assert_eq!(claims["iss"], "pool-1@project.iam.gserviceaccount.com");
+        assert_eq!(claims["aud"], "https://oauth2.googleapis.com/token");
+        assert_eq!(claims["sub"], "media@example.com");
+        assert_eq!(claims["exp"], now.timestamp() + 3600);
+        assert!(
+            claims["scope"]
+                .as_str()
+                .is_some_and(|scope| scope.ends_with("/auth/drive.readonly"))
+        );
+        assert!(!URL_SAFE_NO_PAD.decode(parts[2]).expect("sig").is_empty());
+    }
+
+    #[test]
+    fn key_without_usable_private_key_is_rejected() {
+        let broken = serde_json::json!({
+            "client_email": "pool-1@project.iam.gserviceaccount.com",
+            "private_key": "not a pem",
+        })
+        .to_string();
+
+        assert!(ServiceAccountKey::from_json(&broken).is_err());
+    }
+}
@@ -1,10 +1,18 @@
-use std::{future::Future, pin::Pin};
+use std::{future::Future, path::PathBuf, pin::Pin, sync::Arc};
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpListener,
 };
 
+use crate::{
+    AppState,
+    config::{
+        core::{Config, finish_raw_config, parse_raw_config_str},
+        error::ConfigError,
+    },
+};
+
 pub type HttpMockHandler = Box<
     dyn Fn(String) -> Pin<Box<dyn Future<Output = String> + Send>>
         + Send
@@ -41,3 +49,96 @@ pub fn http_response(status: u16, content_type: &str, body: &str) -> String {
         body.len()
     )
 }
+
+/// Builds the smallest config that loads (frontend mode, default tables)
+/// so tests only spell out the sections they exercise.
+pub struct TestConfig {
+    path: PathBuf,
+    stream_mode: &'static str,
+    sections: Vec<String>,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("test.toml"),
+            stream_mode: "frontend",
+            sections: Vec::new(),
+        }
+    }
+}
+
+impl TestConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path the config is treated as loaded from; relative paths in it
+    /// resolve against its directory.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    pub fn stream_mode(mut self, stream_mode: &'static str) -> Self {
+        self.stream_mode = stream_mode;
+        self
+    }
+
+    /// Appends raw TOML, e.g. a `[Fallback]` table or `[[BackendNode]]`.
+    pub fn section(mut self, toml: &str) -> Self {
+        self.sections.push(toml.to_string());
+        self
+    }
+
+    pub fn toml(&self) -> String {
+        let mut raw = format!(
+            r#"[Log]
+level = "info"
+prefix = ""
+root_path = "./logs"
+
+[General]
+memory_mode = "middle"
+stream_mode = "{}"
+encipher_key = "1234567890123456"
+encipher_iv = "1234567890123456"
+
+[Emby]
+url = "http://127.0.0.1"
+port = "8096"
+token = "tok"
+
+[UserAgent]
+mode = "allow"
+allow_ua = []
+deny_ua = []
+
+[Frontend]
+listen_port = 60001
+"#,
+            self.stream_mode
+        );
+        for section in &self.sections {
+            raw.push('\n');
+            raw.push_str(section);
+            raw.push('\n');
+        }
+        raw
+    }
+
+    pub fn try_build(&self) -> Result<Config, ConfigError> {
+        finish_raw_config(
+            self.path.clone(),
+            parse_raw_config_str(&self.toml())?,
+        )
+    }
+
+    pub fn build(&self) -> Config {
+        self.try_build().expect("test config")
+    }
+
+    pub async fn app_state(&self) -> Arc<AppState> {
+        Arc::new(AppState::new(self.build()).await)
+    }
+}
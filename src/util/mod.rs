@@ -3,6 +3,7 @@ pub mod path_rewriter;
 pub mod privacy;
 pub mod string_util;
 pub mod timing;
+pub mod totp;
 pub mod uri_ext;
 
 pub use markdown::MarkdownV2Builder;
@@ -10,4 +11,5 @@ pub use path_rewriter::PathRewriter;
 pub use privacy::Privacy;
 pub use string_util::StringUtil;
 pub use timing::OperationTimer;
+pub use totp::Totp;
 pub use uri_ext::{UriExt, UriExtError};
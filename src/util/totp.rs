@@ -0,0 +1,143 @@
+use aws_lc_rs::hmac;
+
+const TOTP_STEP_SECS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+
+/// RFC 6238 codes (SHA-1, 30 s step, 6 digits) as used by authenticator apps.
+pub struct Totp;
+
+impl Totp {
+    /// Code for `secret` (base32, spaces and padding ignored) at `unix_secs`.
+    pub fn code(secret: &str, unix_secs: u64) -> Option<String> {
+        let key = Self::decode_base32(secret)?;
+        Some(Self::code_for_counter(&key, unix_secs / TOTP_STEP_SECS))
+    }
+
+    pub fn code_now(secret: &str) -> Option<String> {
+        Self::code(secret, Self::now_secs())
+    }
+
+    /// Time step `code` was generated for, accepting one step of clock
+    /// drift either way. Callers reject steps they have already seen.
+    pub fn verify(secret: &str, code: &str, unix_secs: u64) -> Option<u64> {
+        let code = code.trim();
+        if code.len() != TOTP_DIGITS as usize
+            || !code.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let key = Self::decode_base32(secret)?;
+        let current = unix_secs / TOTP_STEP_SECS;
+        [current.saturating_sub(1), current, current + 1]
+            .into_iter()
+            .find(|counter| Self::code_for_counter(&key, *counter) == code)
+    }
+
+    pub fn now_secs() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default()
+    }
+
+    fn code_for_counter(key: &[u8], counter: u64) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+        let tag = hmac::sign(&key, &counter.to_be_bytes());
+        let digest = tag.as_ref();
+        let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10_u32.pow(TOTP_DIGITS),
+            width = TOTP_DIGITS as usize
+        )
+    }
+
+    /// Unpadded RFC 4648 base32, the form authenticator apps expect.
+    pub fn encode_base32(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+        let mut out = String::new();
+        let mut bits: u64 = 0;
+        let mut bit_count = 0;
+        for byte in bytes {
+            bits = (bits << 8) | u64::from(*byte);
+            bit_count += 8;
+            while bit_count >= 5 {
+                bit_count -= 5;
+                out.push(char::from(
+                    ALPHABET[((bits >> bit_count) & 31) as usize],
+                ));
+            }
+        }
+        if bit_count > 0 {
+            out.push(char::from(
+                ALPHABET[((bits << (5 - bit_count)) & 31) as usize],
+            ));
+        }
+        out
+    }
+
+    pub fn decode_base32(input: &str) -> Option<Vec<u8>> {
+        let mut bits: u64 = 0;
+        let mut bit_count = 0;
+        let mut out = Vec::new();
+        for c in input.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+            let value = match c.to_ascii_uppercase() {
+                c @ 'A'..='Z' => c as u64 - 'A' as u64,
+                c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+                _ => return None,
+            };
+            bits = (bits << 5) | value;
+            bit_count += 5;
+            if bit_count >= 8 {
+                bit_count -= 8;
+                out.push((bits >> bit_count) as u8);
+                bits &= (1 << bit_count) - 1;
+            }
+        }
+        (!out.is_empty()).then_some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Totp;
+
+    #[test]
+    fn matches_rfc6238_sha1_vectors() {
+        // Base32 of the RFC test key "12345678901234567890".
+        let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+        assert_eq!(Totp::code(secret, 59).as_deref(), Some("287082"));
+        assert_eq!(
+            Totp::code(secret, 1_111_111_109).as_deref(),
+            Some("081804")
+        );
+        assert_eq!(
+            Totp::code(secret, 2_000_000_000).as_deref(),
+            Some("279037")
+        );
+        assert_eq!(Totp::code("not base32!", 59), None);
+    }
+
+    #[test]
+    fn verify_accepts_one_step_of_drift() {
+        let secret = Totp::encode_base32(b"12345678901234567890");
+        assert_eq!(secret, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(
+            Totp::decode_base32(&Totp::encode_base32(b"abc")).as_deref(),
+            Some(&b"abc"[..])
+        );
+
+        assert_eq!(Totp::verify(&secret, "287082", 59), Some(1));
+        assert_eq!(Totp::verify(&secret, " 287082 ", 89), Some(1));
+        assert_eq!(Totp::verify(&secret, "287082", 29), Some(1));
+        assert_eq!(Totp::verify(&secret, "287082", 120), None);
+        assert_eq!(Totp::verify(&secret, "28708", 59), None);
+    }
+}
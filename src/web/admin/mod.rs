@@ -14,14 +14,17 @@ use sysinfo::{
     ProcessesToUpdate, RefreshKind, System, get_current_pid,
 };
 
+use crate::core::backend::resilience;
 use crate::web::{
     api::WebAppState,
     auth::{hash_password, session_user_from_jar},
     contracts::{
-        LogoutResponse, RegistrationSettingsResponse, SystemMetricsResponse,
-        UpdateRegistrationSettingsRequest, UpdateUserDisabledRequest,
-        UpdateUserPasswordRequest, UpdateUserRoleRequest, UserEnvelope,
-        UserListResponse, UserRole,
+        AuditRetentionResponse, CircuitBreakerListResponse, LogoutResponse,
+        RegistrationSettingsResponse, SystemMetricsResponse,
+        TwoFactorPolicyResponse, UpdateAuditRetentionRequest,
+        UpdateRegistrationSettingsRequest, UpdateTwoFactorPolicyRequest,
+        UpdateUserDisabledRequest, UpdateUserPasswordRequest,
+        UpdateUserRoleRequest, UserEnvelope, UserListResponse, UserRole,
     },
     error::WebError,
 };
@@ -29,10 +32,19 @@ use crate::web::{
 pub fn routes() -> Router<WebAppState> {
     Router::new()
         .route("/system", get(get_system_metrics))
+        .route("/circuit-breakers", get(list_circuit_breakers))
         .route(
             "/settings/registration",
             get(get_registration_settings).patch(update_registration_settings),
         )
+        .route(
+            "/settings/two-factor",
+            get(get_two_factor_policy).patch(update_two_factor_policy),
+        )
+        .route(
+            "/settings/audit-retention",
+            get(get_audit_retention).patch(update_audit_retention),
+        )
         .route("/users", get(list_users))
         .route("/users/{user_id}/role", patch(update_user_role))
         .route("/users/{user_id}/disabled", patch(update_user_disabled))
@@ -76,7 +88,101 @@ async fn update_registration_settings(
     }))
 }
 
-async fn require_admin(
+async fn get_two_factor_policy(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<TwoFactorPolicyResponse>, WebError> {
+    let _admin = require_admin(&state, &jar).await?;
+    Ok(Json(TwoFactorPolicyResponse {
+        required_for_admins: state.db.totp_required_for_admins().await?,
+    }))
+}
+
+async fn update_two_factor_policy(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<UpdateTwoFactorPolicyRequest>,
+) -> Result<Json<TwoFactorPolicyResponse>, WebError> {
+    let admin = require_admin(&state, &jar).await?;
+    // Requiring TOTP would otherwise lock the acting admin into enrollment.
+    if payload.required_for_admins
+        && state
+            .db
+            .totp_state(admin.id.clone())
+            .await?
+            .secret
+            .is_none()
+    {
+        return Err(WebError::invalid_input(
+            "required_for_admins",
+            "Enable two-factor authentication on your own account first.",
+        ));
+    }
+    let required_for_admins = state
+        .db
+        .set_totp_required_for_admins(payload.required_for_admins)
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(admin.id),
+            "update_two_factor_policy",
+            "settings",
+            Some("two_factor".to_string()),
+            json!({ "required_for_admins": required_for_admins }),
+        )
+        .await?;
+
+    Ok(Json(TwoFactorPolicyResponse {
+        required_for_admins,
+    }))
+}
+
+async fn get_audit_retention(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<AuditRetentionResponse>, WebError> {
+    let _admin = require_admin(&state, &jar).await?;
+    Ok(Json(AuditRetentionResponse {
+        retention_days: state.db.audit_retention_days().await?,
+    }))
+}
+
+async fn update_audit_retention(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<UpdateAuditRetentionRequest>,
+) -> Result<Json<AuditRetentionResponse>, WebError> {
+    let admin = require_admin(&state, &jar).await?;
+    if payload
+        .retention_days
+        .is_some_and(|days| !(7..=3650).contains(&days))
+    {
+        return Err(WebError::invalid_input(
+            "retention_days",
+            "Retention must be between 7 and 3650 days.",
+        ));
+    }
+    let retention_days = state
+        .db
+        .set_audit_retention_days(payload.retention_days)
+        .await?;
+    let pruned = state.db.prune_audit_logs().await?;
+    state
+        .db
+        .write_audit_log(
+            Some(admin.id),
+            "update_audit_retention",
+            "settings",
+            Some("audit_retention".to_string()),
+            json!({ "retention_days": retention_days, "pruned": pruned }),
+        )
+        .await?;
+
+    Ok(Json(AuditRetentionResponse { retention_days }))
+}
+
+pub(crate) async fn require_admin(
     state: &WebAppState,
     jar: &CookieJar,
 ) -> Result<crate::web::contracts::SessionUser, WebError> {
@@ -87,6 +193,23 @@ async fn require_admin(
     Ok(user)
 }
 
+async fn list_circuit_breakers(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<CircuitBreakerListResponse>, WebError> {
+    let _user = session_user_from_jar(&state, &jar).await?;
+    Ok(Json(match resilience::runtime_breakers() {
+        Some(breakers) => CircuitBreakerListResponse {
+            available: true,
+            nodes: breakers.snapshot(),
+        },
+        None => CircuitBreakerListResponse {
+            available: false,
+            nodes: Vec::new(),
+        },
+    }))
+}
+
 async fn get_system_metrics(
     State(state): State<WebAppState>,
     jar: CookieJar,
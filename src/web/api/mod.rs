@@ -3,22 +3,32 @@ use std::{sync::Arc, time::Instant};
 use axum::http::{HeaderValue, header};
 use axum::middleware;
 use axum::{
-    Router, extract::Request, middleware::Next, response::Response,
-    routing::get,
+    Router,
+    extract::Request,
+    middleware::Next,
+    response::Response,
+    routing::{get, post},
 };
 use dashmap::DashMap;
 use reqwest::Client;
+use tokio::sync::Mutex as TokioMutex;
 
-use crate::log_stream::{LogStreamHub, global_log_stream};
+use crate::{
+    client::GoogleDriveClient,
+    log_stream::{LogStreamHub, global_log_stream},
+};
 
 use super::{
-    admin,
+    admin, api_tokens,
     app::WebRuntimeConfig,
     artifacts,
     assets::{FRONTEND_DIST_DIR, has_embedded_assets},
-    auth, backgrounds,
+    audit, auth, backgrounds,
     db::Database,
-    drafts, logs,
+    deployments, drafts,
+    google_oauth::{self, PendingGoogleOAuth},
+    logs,
+    oidc::PendingOidcLogin,
 };
 
 #[derive(Debug, Clone)]
@@ -28,7 +38,11 @@ pub struct WebAppState {
     pub http_client: Client,
     pub started_at: Arc<Instant>,
     pub login_attempts: Arc<DashMap<String, LoginThrottleState>>,
+    pub google_oauth_states: Arc<DashMap<String, PendingGoogleOAuth>>,
+    pub oidc_states: Arc<DashMap<String, PendingOidcLogin>>,
+    pub google_drive_client: GoogleDriveClient,
     pub live_logs: LogStreamHub,
+    pub deploy_lock: Arc<TokioMutex<()>>,
 }
 
 #[derive(Debug, Clone)]
@@ -68,7 +82,11 @@ impl WebAppState {
             http_client: Client::new(),
             started_at: Arc::new(Instant::now()),
             login_attempts: Arc::new(DashMap::new()),
+            google_oauth_states: Arc::new(DashMap::new()),
+            oidc_states: Arc::new(DashMap::new()),
+            google_drive_client: GoogleDriveClient::new(),
             live_logs,
+            deploy_lock: Arc::new(TokioMutex::new(())),
         }
     }
 }
@@ -76,12 +94,24 @@ impl WebAppState {
 pub fn build_router(state: WebAppState) -> Router {
     let api_router = Router::new()
         .nest("/api/auth", auth::routes())
+        .nest("/api/tokens", api_tokens::routes())
         .nest("/api/drafts", drafts::routes())
         .route("/api/config-sets", get(drafts::list_config_sets))
         .nest("/api/config-sets", artifacts::routes())
+        .route(
+            "/api/config-sets/{config_set_id}/deploy",
+            post(deployments::deploy_config_set),
+        )
+        .nest("/api/deployments", deployments::routes())
         .nest("/api/admin", admin::routes())
+        .nest("/api/audit", audit::routes())
         .nest("/api/logs", logs::routes())
         .nest("/api/backgrounds", backgrounds::routes())
+        .nest("/api/google-oauth", google_oauth::routes())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            api_tokens::authenticate_api_token,
+        ))
         .layer(middleware::from_fn(apply_security_headers))
         .layer(middleware::from_fn(auth::enforce_same_origin));
 
@@ -171,6 +201,7 @@ mod tests {
                 stream_log_dir: tempdir.path().join("stream-logs"),
                 executable_path: tempdir.path().join("embystream"),
                 main_config_path: Some(tempdir.path().join("config.toml")),
+                oidc: None,
             },
         ));
         (router, db, tempdir)
@@ -272,6 +303,7 @@ mod tests {
                 stream_log_dir: tempdir.path().join("stream-logs"),
                 executable_path: tempdir.path().join("embystream"),
                 main_config_path: Some(tempdir.path().join("config.toml")),
+                oidc: None,
             },
         ));
 
@@ -618,6 +650,40 @@ mod tests {
         assert_eq!(body["error"]["code"], "forbidden");
     }
 
+    #[tokio::test]
+    async fn circuit_breakers_require_session_and_report_availability() {
+        let (router, _, _tempdir) = build_test_router().await;
+
+        let anonymous = Request::builder()
+            .method("GET")
+            .uri("/api/admin/circuit-breakers")
+            .body(Body::empty())
+            .expect("request");
+        let anonymous_response =
+            router.clone().oneshot(anonymous).await.expect("anonymous");
+        assert_eq!(anonymous_response.status(), StatusCode::UNAUTHORIZED);
+
+        let cookie = login_cookie(
+            router.clone(),
+            "breaker-viewer",
+            "breaker@example.com",
+            "viewer-pass",
+        )
+        .await;
+        let request = Request::builder()
+            .method("GET")
+            .uri("/api/admin/circuit-breakers")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .expect("request");
+        let response = router.oneshot(request).await.expect("breakers");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert!(body["available"].is_boolean());
+        assert!(body["nodes"].is_array());
+    }
+
     #[tokio::test]
     async fn draft_generation_persists_config_sets_and_artifacts() {
         let (router, _, _tempdir) = build_test_router().await;
@@ -673,8 +739,9 @@ mod tests {
             .to_string();
         assert_eq!(
             generate_body["artifacts"].as_array().map(Vec::len),
-            Some(5)
+            Some(13)
         );
+        assert_eq!(generate_body["issues"], json!([]));
 
         let list_request = Request::builder()
             .method("GET")
@@ -701,6 +768,1575 @@ mod tests {
             router.oneshot(artifacts_request).await.expect("artifacts");
         assert_eq!(artifacts_response.status(), StatusCode::OK);
         let artifacts_body = json_body(artifacts_response).await;
-        assert_eq!(artifacts_body["items"].as_array().map(Vec::len), Some(5));
+        assert_eq!(artifacts_body["items"].as_array().map(Vec::len), Some(13));
+        assert_eq!(artifacts_body["issues"], json!([]));
+    }
+
+    async fn send_empty(
+        router: Router,
+        method: &str,
+        uri: &str,
+        cookie: &str,
+    ) -> axum::response::Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .expect("request");
+        router.oneshot(request).await.expect("response")
+    }
+
+    #[tokio::test]
+    async fn deploy_writes_config_with_backup_and_rolls_back() {
+        let (router, db, tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "deployer",
+            "deployer@example.com",
+            "deployer-pass",
+        )
+        .await;
+
+        let create_request = Request::builder()
+            .method("POST")
+            .uri("/api/drafts")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::COOKIE, cookie.clone())
+            .body(Body::from(
+                json!({ "name": "Deploy me", "stream_mode": "frontend" })
+                    .to_string(),
+            ))
+            .expect("request");
+        let create_body = json_body(
+            router
+                .clone()
+                .oneshot(create_request)
+                .await
+                .expect("create draft"),
+        )
+        .await;
+        let draft_id = create_body["draft"]["id"].as_str().expect("draft id");
+        let generate_body = json_body(
+            send_empty(
+                router.clone(),
+                "POST",
+                &format!("/api/drafts/{draft_id}/generate"),
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        let config_set_id = generate_body["config_set"]["id"]
+            .as_str()
+            .expect("config set id")
+            .to_string();
+        let deploy_uri = format!("/api/config-sets/{config_set_id}/deploy");
+
+        let forbidden =
+            send_empty(router.clone(), "POST", &deploy_uri, &cookie).await;
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+        let user = db
+            .find_user_by_login("deployer".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        db.update_user_role(&user.id, crate::web::contracts::UserRole::Admin)
+            .await
+            .expect("promote");
+
+        let artifacts_body = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                &format!("/api/config-sets/{config_set_id}/artifacts"),
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        let generated = artifacts_body["items"]
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .find(|item| item["artifact_type"] == "config_toml")
+            })
+            .and_then(|item| item["content"].as_str())
+            .expect("config.toml artifact")
+            .to_string();
+        let config_path = tempdir.path().join("config.toml");
+        let previous = format!("# previous\n{generated}");
+        std::fs::write(&config_path, &previous).expect("write previous");
+
+        let deploy_response =
+            send_empty(router.clone(), "POST", &deploy_uri, &cookie).await;
+        assert_eq!(deploy_response.status(), StatusCode::OK);
+        let deploy_body = json_body(deploy_response).await;
+        assert_eq!(deploy_body["deployment"]["kind"], "deploy");
+        assert_eq!(deploy_body["deployment"]["has_backup"], true);
+        assert_eq!(deploy_body["deployment"]["reloaded"], false);
+        assert_eq!(
+            std::fs::read_to_string(&config_path).expect("deployed"),
+            generated
+        );
+        let deployment_id = deploy_body["deployment"]["id"]
+            .as_str()
+            .expect("deployment id")
+            .to_string();
+
+        let list_body = json_body(
+            send_empty(router.clone(), "GET", "/api/deployments", &cookie)
+                .await,
+        )
+        .await;
+        assert_eq!(list_body["items"].as_array().map(Vec::len), Some(1));
+
+        let rollback_uri = format!("/api/deployments/{deployment_id}/rollback");
+        let rollback_response =
+            send_empty(router.clone(), "POST", &rollback_uri, &cookie).await;
+        assert_eq!(rollback_response.status(), StatusCode::OK);
+        let rollback_body = json_body(rollback_response).await;
+        assert_eq!(rollback_body["deployment"]["kind"], "rollback");
+        assert_eq!(rollback_body["deployment"]["rollback_of"], deployment_id);
+        assert_eq!(
+            std::fs::read_to_string(&config_path).expect("restored"),
+            previous
+        );
+
+        // Only the rollback's own backup survives a limit of one.
+        let backup = db.deployment_backup_path(&deployment_id);
+        assert!(backup.exists());
+        assert_eq!(db.prune_deployment_backups(1).await.expect("prune"), 1);
+        assert!(!backup.exists());
+        let pruned = db
+            .get_deployment(&deployment_id)
+            .await
+            .expect("get deployment")
+            .expect("deployment");
+        assert!(!pruned.has_backup);
+        let rollback_id = rollback_body["deployment"]["id"]
+            .as_str()
+            .expect("rollback id");
+        assert!(db.deployment_backup_path(rollback_id).exists());
+
+        let again = send_empty(router, "POST", &rollback_uri, &cookie).await;
+        assert_eq!(again.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn initialize_rebuilds_legacy_artifact_type_check() {
+        let (router, db, _tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "migrator",
+            "migrator@example.com",
+            "migrator-pass",
+        )
+        .await;
+        let draft = json_body(
+            send_json(
+                router.clone(),
+                "POST",
+                "/api/drafts",
+                &cookie,
+                json!({ "name": "Legacy", "stream_mode": "frontend" }),
+            )
+            .await,
+        )
+        .await;
+        let draft_id = draft["draft"]["id"].as_str().expect("draft id");
+        let generated = send_empty(
+            router.clone(),
+            "POST",
+            &format!("/api/drafts/{draft_id}/generate"),
+            &cookie,
+        )
+        .await;
+        assert_eq!(generated.status(), StatusCode::OK);
+
+        // Recreate the table the way releases before Kubernetes and
+        // Caddy/Traefik artifacts did, keeping only the types they knew.
+        let conn = rusqlite::Connection::open(db.db_path()).expect("open db");
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             ALTER TABLE artifacts RENAME TO artifacts_current;
+             CREATE TABLE artifacts (
+                id TEXT PRIMARY KEY,
+                config_set_id TEXT NOT NULL,
+                artifact_type TEXT NOT NULL CHECK (artifact_type IN (
+                    'config_toml', 'nginx_conf', 'docker_compose',
+                    'systemd_service', 'pm2_config'
+                )),
+                file_name TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY(config_set_id) REFERENCES config_sets(id)
+                    ON DELETE CASCADE
+             );
+             INSERT INTO artifacts
+             SELECT * FROM artifacts_current
+             WHERE artifact_type IN (
+                'config_toml', 'nginx_conf', 'docker_compose',
+                'systemd_service', 'pm2_config'
+             );
+             DROP TABLE artifacts_current;",
+        )
+        .expect("downgrade artifacts table");
+        drop(conn);
+
+        db.initialize().await.expect("reinitialize");
+        let conn = rusqlite::Connection::open(db.db_path()).expect("open db");
+        let (sql, rows): (String, i64) = conn
+            .query_row(
+                "SELECT sql, (SELECT COUNT(*) FROM artifacts)
+                 FROM sqlite_master
+                 WHERE type = 'table' AND name = 'artifacts'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("artifacts table");
+        assert!(sql.contains("'kubernetes_values'"));
+        assert!(sql.contains("'traefik_dynamic'"));
+        assert_eq!(rows, 5);
+
+        let regenerated = send_empty(
+            router,
+            "POST",
+            &format!("/api/drafts/{draft_id}/generate"),
+            &cookie,
+        )
+        .await;
+        assert_eq!(regenerated.status(), StatusCode::OK);
+    }
+
+    async fn send_json(
+        router: Router,
+        method: &str,
+        uri: &str,
+        cookie: &str,
+        body: Value,
+    ) -> axum::response::Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::COOKIE, cookie)
+            .body(Body::from(body.to_string()))
+            .expect("request");
+        router.oneshot(request).await.expect("response")
+    }
+
+    #[tokio::test]
+    async fn import_creates_drafts_from_uploaded_and_running_config() {
+        let (router, db, tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "importer",
+            "importer@example.com",
+            "importer-pass",
+        )
+        .await;
+        let content = crate::cli_wizard::emit::emit_wizard_config_toml(
+            &crate::cli_wizard::template_payload::build_template_raw(
+                crate::config::general::StreamMode::Frontend,
+            ),
+        )
+        .expect("template config");
+
+        let uploaded = send_json(
+            router.clone(),
+            "POST",
+            "/api/drafts/import",
+            &cookie,
+            json!({
+                "name": "Hand written",
+                "content": format!("{content}\n[OpenList]\nbase_url = \"x\"\n"),
+            }),
+        )
+        .await;
+        assert_eq!(uploaded.status(), StatusCode::OK);
+        let uploaded_body = json_body(uploaded).await;
+        assert_eq!(uploaded_body["draft"]["name"], "Hand written");
+        assert_eq!(uploaded_body["draft"]["stream_mode"], "frontend");
+        assert_eq!(uploaded_body["warnings"], json!(["OpenList"]));
+
+        let invalid = send_json(
+            router.clone(),
+            "POST",
+            "/api/drafts/import",
+            &cookie,
+            json!({ "content": "[General]\nstream_mode = 1" }),
+        )
+        .await;
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let forbidden = send_json(
+            router.clone(),
+            "POST",
+            "/api/drafts/import/running",
+            &cookie,
+            json!({}),
+        )
+        .await;
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+        let user = db
+            .find_user_by_login("importer".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        db.update_user_role(&user.id, crate::web::contracts::UserRole::Admin)
+            .await
+            .expect("promote");
+        std::fs::write(tempdir.path().join("config.toml"), &content)
+            .expect("write running config");
+
+        let running = send_json(
+            router.clone(),
+            "POST",
+            "/api/drafts/import/running",
+            &cookie,
+            json!({}),
+        )
+        .await;
+        assert_eq!(running.status(), StatusCode::OK);
+        let running_body = json_body(running).await;
+        assert_eq!(running_body["draft"]["name"], "Frontend setup");
+        assert_eq!(running_body["warnings"], json!([]));
+
+        let drafts_body =
+            json_body(send_empty(router, "GET", "/api/drafts", &cookie).await)
+                .await;
+        assert_eq!(drafts_body["items"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[tokio::test]
+    async fn draft_revisions_list_diff_and_restore() {
+        let (router, _, _tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "historian",
+            "historian@example.com",
+            "historian-pass",
+        )
+        .await;
+        let create_body = json_body(
+            send_json(
+                router.clone(),
+                "POST",
+                "/api/drafts",
+                &cookie,
+                json!({ "name": "History", "stream_mode": "frontend" }),
+            )
+            .await,
+        )
+        .await;
+        let draft_id = create_body["draft"]["id"].as_str().expect("draft id");
+        let draft_uri = format!("/api/drafts/{draft_id}");
+
+        let draft_body = json_body(
+            send_empty(router.clone(), "GET", &draft_uri, &cookie).await,
+        )
+        .await;
+        let original_level =
+            draft_body["draft"]["payload"]["shared"]["log"]["level"].clone();
+        let mut payload = draft_body["draft"]["payload"].clone();
+        payload["shared"]["log"]["level"] = json!("trace");
+        let saved = send_json(
+            router.clone(),
+            "PUT",
+            &draft_uri,
+            &cookie,
+            json!({ "name": "History", "payload": payload, "client_revision": 1 }),
+        )
+        .await;
+        assert_eq!(saved.status(), StatusCode::OK);
+
+        let revisions_body = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                &format!("{draft_uri}/revisions"),
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(revisions_body["current_revision"], 2);
+        assert_eq!(revisions_body["items"].as_array().map(Vec::len), Some(2));
+        assert_eq!(revisions_body["items"][0]["author"], "historian");
+
+        let diff_body = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                &format!("{draft_uri}/revisions/diff?from=1"),
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(diff_body["to_revision"], 2);
+        assert_eq!(
+            diff_body["sections"],
+            json!([{
+                "section": "Log",
+                "change": "modified",
+                "fields": [{
+                    "path": "level",
+                    "before": original_level,
+                    "after": "trace"
+                }]
+            }])
+        );
+
+        let restored = json_body(
+            send_empty(
+                router.clone(),
+                "POST",
+                &format!("{draft_uri}/revisions/1/restore"),
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(restored["draft"]["server_revision"], 3);
+        let restored_draft = json_body(
+            send_empty(router.clone(), "GET", &draft_uri, &cookie).await,
+        )
+        .await;
+        assert_eq!(
+            restored_draft["draft"]["payload"]["shared"]["log"]["level"],
+            original_level
+        );
+
+        let missing = send_empty(
+            router,
+            "POST",
+            &format!("{draft_uri}/revisions/9/restore"),
+            &cookie,
+        )
+        .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn google_oauth_start_issues_state_bound_consent_url() {
+        let (router, db, _tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "drive-owner",
+            "drive@example.com",
+            "drive-pass",
+        )
+        .await;
+        let user = db
+            .find_user_by_login("drive-owner".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+
+        let create_request = Request::builder()
+            .method("POST")
+            .uri("/api/drafts")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::COOKIE, cookie.clone())
+            .body(Body::from(
+                json!({ "name": "Drive", "stream_mode": "backend" })
+                    .to_string(),
+            ))
+            .expect("request");
+        let create_body = json_body(
+            router
+                .clone()
+                .oneshot(create_request)
+                .await
+                .expect("create draft"),
+        )
+        .await;
+        let draft_id = create_body["draft"]["id"]
+            .as_str()
+            .expect("draft id")
+            .to_string();
+        let draft = db
+            .get_draft(&user.id, &draft_id)
+            .await
+            .expect("get draft")
+            .expect("draft exists");
+        let mut payload = serde_json::to_value(&draft.payload).expect("json");
+        payload["backend_nodes"] = json!([{
+            "name": "GoogleDrive",
+            "backend_type": "googleDrive",
+            "google_drive": {
+                "node_uuid": "drive-node",
+                "client_id": "client-id",
+                "client_secret": "client-secret"
+            }
+        }]);
+        db.save_draft(
+            &user.id,
+            &draft_id,
+            draft.name,
+            serde_json::from_value(payload).expect("payload"),
+            0,
+        )
+        .await
+        .expect("save draft");
+
+        let start_request = Request::builder()
+            .method("POST")
+            .uri("/api/google-oauth/start")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::HOST, "studio.local:17172")
+            .header(header::COOKIE, cookie.clone())
+            .body(Body::from(
+                json!({ "draft_id": draft_id, "node_uuid": "drive-node" })
+                    .to_string(),
+            ))
+            .expect("request");
+        let start_response = router
+            .clone()
+            .oneshot(start_request)
+            .await
+            .expect("start oauth");
+        assert_eq!(start_response.status(), StatusCode::OK);
+        let start_body = json_body(start_response).await;
+        assert_eq!(
+            start_body["redirect_uri"],
+            "http://studio.local:17172/api/google-oauth/callback"
+        );
+        let authorize_url =
+            start_body["authorize_url"].as_str().expect("authorize url");
+        assert!(authorize_url.contains("client_id=client-id"));
+        assert!(authorize_url.contains("access_type=offline"));
+        assert!(authorize_url.contains("state="));
+
+        let callback_request = Request::builder()
+            .method("GET")
+            .uri("/api/google-oauth/callback?code=abc&state=forged")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .expect("request");
+        let callback_response =
+            router.oneshot(callback_request).await.expect("callback");
+        assert_eq!(callback_response.status(), StatusCode::FORBIDDEN);
+    }
+
+    async fn send_bearer(
+        router: Router,
+        method: &str,
+        uri: &str,
+        token: &str,
+        body: Value,
+    ) -> axum::response::Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ORIGIN, "https://ci.example")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::from(body.to_string()))
+            .expect("request");
+        router.oneshot(request).await.expect("response")
+    }
+
+    #[tokio::test]
+    async fn api_tokens_are_scoped_revocable_and_audited() {
+        let (router, db, _tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "scripter",
+            "scripter@example.com",
+            "scripter-pass",
+        )
+        .await;
+
+        let denied = send_json(
+            router.clone(),
+            "POST",
+            "/api/tokens",
+            &cookie,
+            json!({ "name": "logs", "scopes": ["logs:read"] }),
+        )
+        .await;
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+
+        let mut secrets = Vec::new();
+        for scopes in [json!(["drafts:write"]), json!(["drafts:read"])] {
+            let created = send_json(
+                router.clone(),
+                "POST",
+                "/api/tokens",
+                &cookie,
+                json!({ "name": "ci", "scopes": scopes, "expires_in_days": 30 }),
+            )
+            .await;
+            assert_eq!(created.status(), StatusCode::OK);
+            let body = json_body(created).await;
+            let secret = body["secret"].as_str().expect("secret").to_string();
+            assert_eq!(
+                body["token"]["token_prefix"].as_str(),
+                Some(&secret[..12])
+            );
+            secrets.push((body["token"]["id"].clone(), secret));
+        }
+        let (write_id, write_secret) = &secrets[0];
+        let (_, read_secret) = &secrets[1];
+
+        let created = send_bearer(
+            router.clone(),
+            "POST",
+            "/api/drafts",
+            write_secret,
+            json!({ "name": "From CI", "stream_mode": "frontend" }),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::OK);
+
+        let read_only = send_bearer(
+            router.clone(),
+            "POST",
+            "/api/drafts",
+            read_secret,
+            json!({ "name": "Nope", "stream_mode": "frontend" }),
+        )
+        .await;
+        assert_eq!(read_only.status(), StatusCode::FORBIDDEN);
+        let listed = send_bearer(
+            router.clone(),
+            "GET",
+            "/api/drafts",
+            read_secret,
+            json!({}),
+        )
+        .await;
+        assert_eq!(listed.status(), StatusCode::OK);
+        assert_eq!(json_body(listed).await["items"][0]["name"], "From CI");
+
+        let token_management = send_bearer(
+            router.clone(),
+            "GET",
+            "/api/tokens",
+            write_secret,
+            json!({}),
+        )
+        .await;
+        assert_eq!(token_management.status(), StatusCode::FORBIDDEN);
+
+        let revoked = send_empty(
+            router.clone(),
+            "DELETE",
+            &format!("/api/tokens/{}", write_id.as_str().expect("id")),
+            &cookie,
+        )
+        .await;
+        assert_eq!(revoked.status(), StatusCode::OK);
+        let after_revoke = send_bearer(
+            router.clone(),
+            "GET",
+            "/api/drafts",
+            write_secret,
+            json!({}),
+        )
+        .await;
+        assert_eq!(after_revoke.status(), StatusCode::UNAUTHORIZED);
+
+        let remaining =
+            json_body(send_empty(router, "GET", "/api/tokens", &cookie).await)
+                .await;
+        assert_eq!(remaining["items"].as_array().map(Vec::len), Some(1));
+
+        let conn = rusqlite::Connection::open(db.db_path()).expect("open db");
+        let uses: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM audit_logs WHERE action = 'use_api_token'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("count audit rows");
+        assert_eq!(uses, 4);
+    }
+
+    #[tokio::test]
+    async fn admin_api_tokens_follow_the_two_factor_policy() {
+        let (router, db, _tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "opsadmin",
+            "opsadmin@example.com",
+            "opsadmin-pass",
+        )
+        .await;
+        let admin = db
+            .find_user_by_login("opsadmin".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        db.update_user_role(&admin.id, crate::web::contracts::UserRole::Admin)
+            .await
+            .expect("promote");
+
+        let created = send_json(
+            router.clone(),
+            "POST",
+            "/api/tokens",
+            &cookie,
+            json!({ "name": "ops", "scopes": ["admin"] }),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::OK);
+        let secret = json_body(created).await["secret"]
+            .as_str()
+            .expect("secret")
+            .to_string();
+        let before = send_bearer(
+            router.clone(),
+            "GET",
+            "/api/audit",
+            &secret,
+            json!({}),
+        )
+        .await;
+        assert_eq!(before.status(), StatusCode::OK);
+
+        db.set_totp_required_for_admins(true)
+            .await
+            .expect("require two-factor");
+        let after = send_bearer(
+            router.clone(),
+            "GET",
+            "/api/audit",
+            &secret,
+            json!({}),
+        )
+        .await;
+        assert_eq!(after.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            json_body(after).await["error"]["code"],
+            "totp_enrollment_required"
+        );
+
+        let issued = send_json(
+            router,
+            "POST",
+            "/api/tokens",
+            &cookie,
+            json!({ "name": "again", "scopes": ["admin"] }),
+        )
+        .await;
+        assert_eq!(issued.status(), StatusCode::FORBIDDEN);
+    }
+
+    /// Minimal OIDC provider: discovery, a token endpoint that checks the
+    /// PKCE verifier, and userinfo carrying `groups`.
+    #[derive(Clone, Default)]
+    struct MockOidcProvider {
+        issuer: String,
+        /// `code_challenge` and `nonce` of the pending authorization.
+        authorization: Arc<std::sync::Mutex<Option<(String, String)>>>,
+        groups: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    async fn spawn_mock_oidc_provider() -> MockOidcProvider {
+        use axum::{Form, Json, extract::State};
+        use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+        use std::collections::HashMap;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock provider");
+        let provider = MockOidcProvider {
+            issuer: format!(
+                "http://{}",
+                listener.local_addr().expect("local addr")
+            ),
+            ..Default::default()
+        };
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(|State(provider): State<MockOidcProvider>| async move {
+                    Json(json!({
+                        "issuer": provider.issuer,
+                        "authorization_endpoint":
+                            format!("{}/authorize", provider.issuer),
+                        "token_endpoint": format!("{}/token", provider.issuer),
+                        "userinfo_endpoint":
+                            format!("{}/userinfo", provider.issuer),
+                    }))
+                }),
+            )
+            .route(
+                "/token",
+                post(
+                    |State(provider): State<MockOidcProvider>,
+                     Form(form): Form<HashMap<String, String>>| async move {
+                        let (challenge, nonce) = provider
+                            .authorization
+                            .lock()
+                            .expect("authorization")
+                            .clone()
+                            .expect("pending authorization");
+                        let verifier = URL_SAFE_NO_PAD.encode(
+                            aws_lc_rs::digest::digest(
+                                &aws_lc_rs::digest::SHA256,
+                                form["code_verifier"].as_bytes(),
+                            ),
+                        );
+                        if verifier != challenge || form["code"] != "mock-code"
+                        {
+                            return Err(StatusCode::BAD_REQUEST);
+                        }
+                        let claims = json!({
+                            "iss": provider.issuer,
+                            "aud": "studio",
+                            "sub": "jane-subject",
+                            "nonce": nonce,
+                            "exp": chrono::Utc::now().timestamp() + 300,
+                            "preferred_username": "jane",
+                        });
+                        let id_token = format!(
+                            "{}.{}.signature",
+                            URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256"}"#),
+                            URL_SAFE_NO_PAD.encode(claims.to_string()),
+                        );
+                        Ok(Json(json!({
+                            "access_token": "mock-access",
+                            "token_type": "Bearer",
+                            "id_token": id_token,
+                        })))
+                    },
+                ),
+            )
+            .route(
+                "/userinfo",
+                get(|State(provider): State<MockOidcProvider>| async move {
+                    let groups =
+                        provider.groups.lock().expect("groups").clone();
+                    Json(json!({
+                        "sub": "jane-subject",
+                        "email": "jane@example.org",
+                        "groups": groups,
+                    }))
+                }),
+            )
+            .with_state(provider.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("mock provider");
+        });
+        provider
+    }
+
+    /// Starts an SSO sign-in from `path` as a browser holding `cookie`, and
+    /// returns the `state` plus the state cookie the studio set for it.
+    async fn sso_start(
+        router: Router,
+        provider: &MockOidcProvider,
+        path: &str,
+        cookie: Option<&str>,
+    ) -> (String, String) {
+        let mut request = Request::builder()
+            .uri(path)
+            .header(header::HOST, "studio.local");
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        let response = router
+            .oneshot(request.body(Body::empty()).expect("request"))
+            .await
+            .expect("login");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = reqwest::Url::parse(
+            response.headers()[header::LOCATION]
+                .to_str()
+                .expect("location"),
+        )
+        .expect("authorize url");
+        assert!(location.as_str().starts_with(&provider.issuer));
+        let query = location
+            .query_pairs()
+            .into_owned()
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(
+            query["redirect_uri"],
+            "http://studio.local/api/auth/oidc/callback"
+        );
+        assert_eq!(query["code_challenge_method"], "S256");
+        *provider.authorization.lock().expect("authorization") =
+            Some((query["code_challenge"].clone(), query["nonce"].clone()));
+
+        let state_cookie = response_cookie(&response, "embystream_oidc_state")
+            .expect("state cookie");
+        (query["state"].clone(), state_cookie)
+    }
+
+    /// Replays the provider's redirect back to the studio in a browser
+    /// holding `cookie`.
+    async fn sso_callback(
+        router: Router,
+        state: &str,
+        cookie: &str,
+    ) -> axum::response::Response {
+        let request = Request::builder()
+            .uri(format!(
+                "/api/auth/oidc/callback?code=mock-code&state={state}"
+            ))
+            .header(header::HOST, "studio.local")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .expect("request");
+        router.oneshot(request).await.expect("callback")
+    }
+
+    /// Runs the browser side of an SSO sign-in and returns the callback
+    /// response.
+    async fn sso_sign_in(
+        router: Router,
+        provider: &MockOidcProvider,
+    ) -> axum::response::Response {
+        let (state, state_cookie) =
+            sso_start(router.clone(), provider, "/api/auth/oidc/login", None)
+                .await;
+        sso_callback(router, &state, &state_cookie).await
+    }
+
+    /// `name=value` of the cookie `name` set by `response`.
+    fn response_cookie(
+        response: &axum::response::Response,
+        name: &str,
+    ) -> Option<String> {
+        response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split(';').next())
+            .find(|pair| {
+                pair.starts_with(&format!("{name}=")) && !pair.ends_with('=')
+            })
+            .map(ToString::to_string)
+    }
+
+    async fn build_oidc_test_router(
+        provider: &MockOidcProvider,
+    ) -> (Router, Database, TempDir) {
+        let tempdir = tempdir().expect("tempdir");
+        let data_dir = tempdir.path().join("web-data");
+        let db = Database::new(data_dir.clone());
+        db.initialize().await.expect("initialize db");
+        let router = build_router(WebAppState::new(
+            db.clone(),
+            WebRuntimeConfig {
+                listen: "127.0.0.1:17172".parse().expect("socket addr"),
+                data_dir,
+                tmdb_api_key: None,
+                runtime_log_dir: tempdir.path().join("runtime-logs"),
+                stream_log_dir: tempdir.path().join("stream-logs"),
+                executable_path: tempdir.path().join("embystream"),
+                main_config_path: None,
+                oidc: Some(crate::web::oidc::OidcSettings {
+                    issuer: provider.issuer.clone(),
+                    client_id: "studio".into(),
+                    client_secret: Some("studio-secret".into()),
+                    scopes: "openid profile email groups".into(),
+                    redirect_uri: None,
+                    display_name: "Authelia".into(),
+                    role_claim: "groups".into(),
+                    admin_groups: vec!["studio-admins".into()],
+                    allowed_groups: vec!["studio-users".into()],
+                    auto_provision: true,
+                }),
+            },
+        ));
+        (router, db, tempdir)
+    }
+
+    #[tokio::test]
+    async fn oidc_sign_in_provisions_users_and_maps_groups_to_roles() {
+        let provider = spawn_mock_oidc_provider().await;
+        let (router, db, _tempdir) = build_oidc_test_router(&provider).await;
+
+        let provider_info = json_body(
+            router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/api/auth/oidc/provider")
+                        .body(Body::empty())
+                        .expect("request"),
+                )
+                .await
+                .expect("provider"),
+        )
+        .await;
+        assert_eq!(
+            provider_info,
+            json!({ "enabled": true, "display_name": "Authelia" })
+        );
+
+        *provider.groups.lock().expect("groups") = vec!["media".into()];
+        let denied = sso_sign_in(router.clone(), &provider).await;
+        assert_eq!(denied.headers()[header::LOCATION], "/login?sso=forbidden");
+
+        *provider.groups.lock().expect("groups") =
+            vec!["studio-users".into(), "studio-admins".into()];
+        let signed_in = sso_sign_in(router.clone(), &provider).await;
+        assert_eq!(signed_in.headers()[header::LOCATION], "/drafts");
+        let cookie =
+            response_cookie(&signed_in, crate::web::auth::SESSION_COOKIE_NAME)
+                .expect("session cookie");
+        let me = json_body(
+            send_empty(router.clone(), "GET", "/api/auth/me", &cookie).await,
+        )
+        .await;
+        assert_eq!(me["user"]["username"], "jane");
+        assert_eq!(me["user"]["email"], "jane@example.org");
+        assert_eq!(me["user"]["role"], "admin");
+
+        *provider.groups.lock().expect("groups") = vec!["studio-users".into()];
+        let again = sso_sign_in(router, &provider).await;
+        assert_eq!(again.headers()[header::LOCATION], "/drafts");
+        let user = db
+            .find_user_by_login("jane".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        assert_eq!(user.role, crate::web::contracts::UserRole::User);
+        assert!(
+            db.find_user_by_login("jane-2".to_string())
+                .await
+                .expect("find user")
+                .is_none(),
+            "returning identity must not provision a second account"
+        );
+    }
+
+    #[tokio::test]
+    async fn oidc_callback_only_completes_in_the_browser_that_started_it() {
+        use crate::web::auth::SESSION_COOKIE_NAME;
+
+        let provider = spawn_mock_oidc_provider().await;
+        let (router, db, _tempdir) = build_oidc_test_router(&provider).await;
+        db.set_registration_enabled(true)
+            .await
+            .expect("enable registration");
+        *provider.groups.lock().expect("groups") = vec!["studio-users".into()];
+        let location = |response: &axum::response::Response| {
+            response.headers()[header::LOCATION]
+                .to_str()
+                .expect("location")
+                .to_string()
+        };
+
+        let (state, _) =
+            sso_start(router.clone(), &provider, "/api/auth/oidc/login", None)
+                .await;
+        let forged = sso_callback(
+            router.clone(),
+            &state,
+            "embystream_oidc_state=another-browser",
+        )
+        .await;
+        assert_eq!(location(&forged), "/login?sso=expired");
+        assert!(response_cookie(&forged, SESSION_COOKIE_NAME).is_none());
+        assert!(
+            db.find_user_by_login("jane".to_string())
+                .await
+                .expect("find user")
+                .is_none()
+        );
+
+        let owner = login_cookie(
+            router.clone(),
+            "owner",
+            "owner@example.com",
+            "owner-pass",
+        )
+        .await;
+        let other = login_cookie(
+            router.clone(),
+            "other",
+            "other@example.com",
+            "other-pass",
+        )
+        .await;
+        let link_path = "/api/auth/oidc/login?link=true";
+
+        let (state, _) =
+            sso_start(router.clone(), &provider, link_path, Some(&owner)).await;
+        let handed_over = sso_callback(router.clone(), &state, &other).await;
+        assert_eq!(location(&handed_over), "/login?sso=expired");
+
+        let (state, state_cookie) =
+            sso_start(router.clone(), &provider, link_path, Some(&owner)).await;
+        let wrong_session = sso_callback(
+            router.clone(),
+            &state,
+            &format!("{other}; {state_cookie}"),
+        )
+        .await;
+        assert_eq!(location(&wrong_session), "/account?sso=expired");
+
+        let (state, state_cookie) =
+            sso_start(router.clone(), &provider, link_path, Some(&owner)).await;
+        let linked = sso_callback(
+            router.clone(),
+            &state,
+            &format!("{owner}; {state_cookie}"),
+        )
+        .await;
+        assert_eq!(location(&linked), "/account?sso=linked");
+
+        let owner_row = db
+            .find_user_by_login("owner".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        let linked_user = db
+            .find_user_by_oidc_identity(
+                provider.issuer.clone(),
+                "jane-subject".to_string(),
+            )
+            .await
+            .expect("find identity")
+            .expect("identity linked");
+        assert_eq!(linked_user.id, owner_row.id);
+    }
+
+    #[tokio::test]
+    async fn sso_admins_are_exempt_from_the_admin_two_factor_policy() {
+        let provider = spawn_mock_oidc_provider().await;
+        let (router, db, _tempdir) = build_oidc_test_router(&provider).await;
+        db.set_totp_required_for_admins(true)
+            .await
+            .expect("require two-factor");
+        *provider.groups.lock().expect("groups") =
+            vec!["studio-users".into(), "studio-admins".into()];
+
+        let signed_in = sso_sign_in(router.clone(), &provider).await;
+        assert_eq!(signed_in.headers()[header::LOCATION], "/drafts");
+        let cookie =
+            response_cookie(&signed_in, crate::web::auth::SESSION_COOKIE_NAME)
+                .expect("session cookie");
+        let me = json_body(
+            send_empty(router.clone(), "GET", "/api/auth/me", &cookie).await,
+        )
+        .await;
+        assert_eq!(me["user"]["role"], "admin");
+        assert_eq!(me["totp_enrollment_required"], false);
+        let drafts =
+            send_empty(router.clone(), "GET", "/api/drafts", &cookie).await;
+        assert_eq!(drafts.status(), StatusCode::OK);
+
+        let created = send_json(
+            router.clone(),
+            "POST",
+            "/api/tokens",
+            &cookie,
+            json!({ "name": "ops", "scopes": ["admin"] }),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::OK);
+        let secret = json_body(created).await["secret"]
+            .as_str()
+            .expect("secret")
+            .to_string();
+        let audit =
+            send_bearer(router, "GET", "/api/audit", &secret, json!({})).await;
+        assert_eq!(audit.status(), StatusCode::OK);
+    }
+
+    async fn totp_login(
+        router: Router,
+        login: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> axum::response::Response {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                json!({
+                    "login": login,
+                    "password": password,
+                    "totp_code": totp_code,
+                })
+                .to_string(),
+            ))
+            .expect("request");
+        router.oneshot(request).await.expect("login")
+    }
+
+    #[tokio::test]
+    async fn totp_guards_login_and_admin_policy_forces_enrollment() {
+        use crate::util::Totp;
+
+        let (router, db, _tempdir) = build_test_router().await;
+        let cookie = login_cookie(
+            router.clone(),
+            "guard",
+            "guard@example.com",
+            "guard-pass",
+        )
+        .await;
+        let guard = db
+            .find_user_by_login("guard".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        db.update_user_role(&guard.id, crate::web::contracts::UserRole::Admin)
+            .await
+            .expect("promote");
+
+        let policy_uri = "/api/admin/settings/two-factor";
+        let premature = send_json(
+            router.clone(),
+            "PATCH",
+            policy_uri,
+            &cookie,
+            json!({ "required_for_admins": true }),
+        )
+        .await;
+        assert_eq!(premature.status(), StatusCode::BAD_REQUEST);
+
+        let setup = json_body(
+            send_empty(router.clone(), "POST", "/api/auth/totp/setup", &cookie)
+                .await,
+        )
+        .await;
+        let secret = setup["secret"].as_str().expect("secret").to_string();
+        assert!(
+            setup["otpauth_uri"]
+                .as_str()
+                .expect("uri")
+                .starts_with("otpauth://totp/EmbyStream:guard?secret=")
+        );
+        assert!(setup["qr_svg"].as_str().expect("svg").contains("<svg"));
+
+        let wrong = send_json(
+            router.clone(),
+            "POST",
+            "/api/auth/totp/enable",
+            &cookie,
+            json!({ "code": "000000" }),
+        )
+        .await;
+        assert_eq!(wrong.status(), StatusCode::BAD_REQUEST);
+
+        let now = Totp::now_secs();
+        let enrolling_code = Totp::code(&secret, now).expect("code");
+        let enabled = send_json(
+            router.clone(),
+            "POST",
+            "/api/auth/totp/enable",
+            &cookie,
+            json!({ "code": enrolling_code }),
+        )
+        .await;
+        assert_eq!(enabled.status(), StatusCode::OK);
+        let recovery_codes = json_body(enabled).await["recovery_codes"]
+            .as_array()
+            .expect("recovery codes")
+            .iter()
+            .map(|code| code.as_str().expect("code").to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(recovery_codes.len(), 10);
+
+        let missing =
+            totp_login(router.clone(), "guard", "guard-pass", None).await;
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json_body(missing).await["error"]["code"], "totp_required");
+
+        let replayed = totp_login(
+            router.clone(),
+            "guard",
+            "guard-pass",
+            Some(&enrolling_code),
+        )
+        .await;
+        assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(json_body(replayed).await["error"]["code"], "unauthorized");
+
+        let recovery = recovery_codes[0].to_uppercase();
+        let with_recovery =
+            totp_login(router.clone(), "guard", "guard-pass", Some(&recovery))
+                .await;
+        assert_eq!(with_recovery.status(), StatusCode::OK);
+        let reused =
+            totp_login(router.clone(), "guard", "guard-pass", Some(&recovery))
+                .await;
+        assert_eq!(reused.status(), StatusCode::UNAUTHORIZED);
+
+        let next_code = Totp::code(&secret, now + 30).expect("code");
+        let with_totp =
+            totp_login(router.clone(), "guard", "guard-pass", Some(&next_code))
+                .await;
+        assert_eq!(with_totp.status(), StatusCode::OK);
+
+        let status = json_body(
+            send_empty(router.clone(), "GET", "/api/auth/totp", &cookie).await,
+        )
+        .await;
+        assert_eq!(status["enabled"], true);
+        assert_eq!(status["recovery_codes_remaining"], 9);
+
+        let required = send_json(
+            router.clone(),
+            "PATCH",
+            policy_uri,
+            &cookie,
+            json!({ "required_for_admins": true }),
+        )
+        .await;
+        assert_eq!(required.status(), StatusCode::OK);
+
+        let locked = send_json(
+            router.clone(),
+            "POST",
+            "/api/auth/totp/disable",
+            &cookie,
+            json!({ "password": "guard-pass", "code": recovery_codes[1] }),
+        )
+        .await;
+        assert_eq!(locked.status(), StatusCode::FORBIDDEN);
+
+        let other_cookie = login_cookie(
+            router.clone(),
+            "other",
+            "other@example.com",
+            "other-pass",
+        )
+        .await;
+        let other = db
+            .find_user_by_login("other".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        db.update_user_role(&other.id, crate::web::contracts::UserRole::Admin)
+            .await
+            .expect("promote");
+        let blocked =
+            send_empty(router.clone(), "GET", "/api/drafts", &other_cookie)
+                .await;
+        assert_eq!(blocked.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            json_body(blocked).await["error"]["code"],
+            "totp_enrollment_required"
+        );
+        let me = json_body(
+            send_empty(router.clone(), "GET", "/api/auth/me", &other_cookie)
+                .await,
+        )
+        .await;
+        assert_eq!(me["totp_enrollment_required"], true);
+        let other_status =
+            send_empty(router.clone(), "GET", "/api/auth/totp", &other_cookie)
+                .await;
+        assert_eq!(other_status.status(), StatusCode::OK);
+
+        db.disable_totp_for_username("guard".to_string())
+            .await
+            .expect("disable from cli");
+        let password_only =
+            totp_login(router, "guard", "guard-pass", None).await;
+        assert_eq!(password_only.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(password_only).await["totp_enrollment_required"],
+            true
+        );
+
+        let conn = rusqlite::Connection::open(db.db_path()).expect("open db");
+        let methods = conn
+            .prepare(
+                "SELECT json_extract(detail_json, '$.second_factor')
+                 FROM audit_logs WHERE action = 'login' ORDER BY rowid",
+            )
+            .expect("prepare")
+            .query_map([], |row| row.get::<_, Option<String>>(0))
+            .expect("query")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("rows");
+        assert_eq!(
+            methods.into_iter().flatten().collect::<Vec<_>>(),
+            ["recovery_code", "totp"]
+        );
+    }
+
+    #[tokio::test]
+    async fn audit_logs_filter_paginate_export_and_prune() {
+        let (router, db, _tempdir) = build_test_router().await;
+        let viewer = login_cookie(
+            router.clone(),
+            "viewer",
+            "viewer@example.com",
+            "viewer-pass",
+        )
+        .await;
+        let cookie = login_cookie(
+            router.clone(),
+            "auditor",
+            "auditor@example.com",
+            "auditor-pass",
+        )
+        .await;
+        let auditor = db
+            .find_user_by_login("auditor".to_string())
+            .await
+            .expect("find user")
+            .expect("user exists");
+        db.update_user_role(
+            &auditor.id,
+            crate::web::contracts::UserRole::Admin,
+        )
+        .await
+        .expect("promote");
+
+        let forbidden =
+            send_empty(router.clone(), "GET", "/api/audit", &viewer).await;
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+        let conn = rusqlite::Connection::open(db.db_path()).expect("open db");
+        conn.execute(
+            "INSERT INTO audit_logs (
+                id, user_id, action, target_type, target_id, detail_json, created_at
+             ) VALUES ('ancient', NULL, 'deploy', 'config_set', 'old-set', '{}', ?1)",
+            [(chrono::Utc::now() - chrono::Duration::days(400)).to_rfc3339()],
+        )
+        .expect("insert old row");
+
+        let registered = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                "/api/audit?action=register",
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(registered["total"], 2);
+        assert_eq!(registered["items"][0]["username"], "auditor");
+        assert!(
+            registered["actions"]
+                .as_array()
+                .expect("actions")
+                .contains(&json!("deploy"))
+        );
+
+        let page = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                "/api/audit?user=AUDITOR&page=2&page_size=1",
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["items"].as_array().map(Vec::len), Some(1));
+        assert_eq!(page["items"][0]["action"], "register");
+
+        let from = (chrono::Utc::now() - chrono::Duration::days(1))
+            .format("%Y-%m-%dT%H:%M:%SZ");
+        let recent = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                &format!("/api/audit?target_type=config_set&from={from}"),
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(recent["total"], 0);
+
+        let csv = send_empty(
+            router.clone(),
+            "GET",
+            "/api/audit/export?format=csv&action=login",
+            &cookie,
+        )
+        .await;
+        assert_eq!(csv.status(), StatusCode::OK);
+        assert!(
+            csv.headers()[header::CONTENT_DISPOSITION]
+                .to_str()
+                .expect("disposition")
+                .ends_with(".csv\"")
+        );
+        let csv = String::from_utf8(
+            to_bytes(csv.into_body(), usize::MAX)
+                .await
+                .expect("body")
+                .to_vec(),
+        )
+        .expect("utf8");
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some(
+                "created_at,user_id,username,action,target_type,target_id,detail"
+            )
+        );
+        assert_eq!(lines.count(), 2);
+
+        let exported = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                "/api/audit/export?format=json&user=viewer",
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(exported.as_array().map(Vec::len), Some(2));
+
+        let retention_uri = "/api/admin/settings/audit-retention";
+        let too_short = send_json(
+            router.clone(),
+            "PATCH",
+            retention_uri,
+            &cookie,
+            json!({ "retention_days": 3 }),
+        )
+        .await;
+        assert_eq!(too_short.status(), StatusCode::BAD_REQUEST);
+        let updated = send_json(
+            router.clone(),
+            "PATCH",
+            retention_uri,
+            &cookie,
+            json!({ "retention_days": 30 }),
+        )
+        .await;
+        assert_eq!(json_body(updated).await["retention_days"], 30);
+
+        let deploys = json_body(
+            send_empty(
+                router.clone(),
+                "GET",
+                "/api/audit?action=deploy",
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(deploys["total"], 0);
+        let pruned = json_body(
+            send_empty(
+                router,
+                "GET",
+                "/api/audit?action=update_audit_retention",
+                &cookie,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(pruned["items"][0]["detail"]["pruned"], 1);
     }
 }
@@ -0,0 +1,330 @@
+//! Personal API tokens. A request carrying `Authorization: Bearer <token>`
+//! acts as the token's owner, limited to the token's scopes, and skips the
+//! same-origin check that protects cookie sessions.
+
+use aws_lc_rs::digest;
+use axum::{
+    Json, Router,
+    extract::{Path, Request, State},
+    http::{HeaderMap, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use axum_extra::extract::CookieJar;
+use chrono::{Duration, Utc};
+use rand::{Rng, distributions::Alphanumeric};
+use serde_json::json;
+
+use crate::web::{
+    api::WebAppState,
+    auth::session_user_from_jar,
+    contracts::{
+        ApiTokenListResponse, ApiTokenScope, CreateApiTokenRequest,
+        CreateApiTokenResponse, LogoutResponse, SessionUser, UserRole,
+    },
+    db::CreateApiTokenInput,
+    error::WebError,
+};
+
+const TOKEN_SECRET_PREFIX: &str = "esk_";
+const TOKEN_SECRET_LENGTH: usize = 40;
+/// Characters of the secret kept in clear to tell tokens apart.
+const TOKEN_DISPLAY_PREFIX_LENGTH: usize = 12;
+const MAX_TOKEN_NAME_LENGTH: usize = 64;
+
+tokio::task_local! {
+    /// Owner of the API token authenticating the current request.
+    static API_TOKEN_USER: SessionUser;
+}
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/", get(list_api_tokens).post(create_api_token))
+        .route("/{token_id}", delete(revoke_api_token))
+}
+
+/// The user behind the bearer token of the request being handled, if any.
+pub(crate) fn api_token_user() -> Option<SessionUser> {
+    API_TOKEN_USER.try_with(SessionUser::clone).ok()
+}
+
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.trim().split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// Resolves bearer tokens before any handler runs. Requests without one
+/// pass through to the cookie session untouched.
+pub async fn authenticate_api_token(
+    State(state): State<WebAppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, WebError> {
+    let Some(secret) = bearer_token(request.headers()) else {
+        return Ok(next.run(request).await);
+    };
+    let token = state
+        .db
+        .find_api_token(sha256_hex(secret))
+        .await?
+        .ok_or(WebError::Unauthorized("API token is not valid."))?;
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    // Tokens cannot sidestep the admin two-factor policy; issuing one
+    // already needs an enrolled session, this covers older tokens.
+    let allowed = if token.totp_enrollment_required {
+        Err(WebError::EnrollmentRequired(
+            "Enable two-factor authentication to use API tokens.",
+        ))
+    } else {
+        required_scope(&method, &path).and_then(|scope| {
+            let granted = scope.is_none_or(|scope| {
+                token.scopes.contains(&scope)
+                    || token.scopes.contains(&ApiTokenScope::Admin)
+            });
+            granted.then_some(()).ok_or(WebError::Forbidden(
+                "API token does not grant this scope.",
+            ))
+        })
+    };
+    let response = match allowed {
+        Ok(()) => {
+            // The token alone decides who the caller is.
+            request.headers_mut().remove(header::COOKIE);
+            API_TOKEN_USER
+                .scope(token.user.clone(), next.run(request))
+                .await
+        }
+        Err(error) => error.into_response(),
+    };
+
+    state
+        .db
+        .write_audit_log(
+            Some(token.user.id),
+            "use_api_token",
+            "api_token",
+            Some(token.id),
+            json!({
+                "method": method.as_str(),
+                "path": path,
+                "status": response.status().as_u16(),
+            }),
+        )
+        .await?;
+    Ok(response)
+}
+
+/// Scope an API token needs for `path`; `None` means any valid token.
+/// Session, password and token management stay cookie-only.
+fn required_scope(
+    method: &Method,
+    path: &str,
+) -> Result<Option<ApiTokenScope>, WebError> {
+    let read = matches!(*method, Method::GET | Method::HEAD);
+    let segments = path
+        .trim_start_matches("/api/")
+        .split('/')
+        .collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["auth", "me"] if read => Ok(None),
+        ["auth" | "tokens", ..] => Err(WebError::Forbidden(
+            "API tokens cannot manage sessions, passwords or tokens.",
+        )),
+        ["backgrounds", ..] => Ok(None),
+        ["config-sets", _, "deploy"]
+        | ["deployments", ..]
+        | ["admin", ..]
+        | ["audit", ..] => Ok(Some(ApiTokenScope::Admin)),
+        ["drafts" | "config-sets" | "google-oauth", ..] => Ok(Some(if read {
+            ApiTokenScope::DraftsRead
+        } else {
+            ApiTokenScope::DraftsWrite
+        })),
+        ["logs", ..] if read => Ok(Some(ApiTokenScope::LogsRead)),
+        ["logs", ..] => Ok(Some(ApiTokenScope::Admin)),
+        _ => Err(WebError::Forbidden("API tokens cannot call this endpoint.")),
+    }
+}
+
+pub(crate) fn sha256_hex(input: &str) -> String {
+    digest::digest(&digest::SHA256, input.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn generate_token_secret() -> String {
+    let random = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_SECRET_LENGTH)
+        .map(char::from)
+        .collect::<String>();
+    format!("{TOKEN_SECRET_PREFIX}{random}")
+}
+
+async fn list_api_tokens(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<ApiTokenListResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    Ok(Json(ApiTokenListResponse {
+        items: state.db.list_api_tokens(&user.id).await?,
+    }))
+}
+
+async fn create_api_token(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<CreateApiTokenRequest>,
+) -> Result<Json<CreateApiTokenResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_TOKEN_NAME_LENGTH {
+        return Err(WebError::invalid_input(
+            "name",
+            "Token name must be between 1 and 64 characters.",
+        ));
+    }
+    let mut scopes = payload.scopes;
+    scopes.sort();
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(WebError::invalid_input(
+            "scopes",
+            "Select at least one scope.",
+        ));
+    }
+    if user.role != UserRole::Admin
+        && scopes.iter().any(|scope| {
+            matches!(scope, ApiTokenScope::LogsRead | ApiTokenScope::Admin)
+        })
+    {
+        return Err(WebError::Forbidden(
+            "Only administrators can grant logs:read or admin.",
+        ));
+    }
+    let expires_at = match payload.expires_in_days {
+        Some(0) => {
+            return Err(WebError::invalid_input(
+                "expires_in_days",
+                "Expiry must be at least one day.",
+            ));
+        }
+        Some(days) => Some(Utc::now() + Duration::days(i64::from(days))),
+        None => None,
+    };
+
+    let secret = generate_token_secret();
+    let token = state
+        .db
+        .create_api_token(CreateApiTokenInput {
+            user_id: user.id.clone(),
+            name,
+            token_prefix: secret[..TOKEN_DISPLAY_PREFIX_LENGTH].to_string(),
+            token_hash: sha256_hex(&secret),
+            scopes,
+            expires_at,
+        })
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id),
+            "create_api_token",
+            "api_token",
+            Some(token.id.clone()),
+            json!({
+                "name": token.name,
+                "scopes": token.scopes,
+                "expires_at": token.expires_at,
+            }),
+        )
+        .await?;
+
+    Ok(Json(CreateApiTokenResponse { token, secret }))
+}
+
+async fn revoke_api_token(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Path(token_id): Path<String>,
+) -> Result<Json<LogoutResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    if !state.db.revoke_api_token(&user.id, &token_id).await? {
+        return Err(WebError::NotFound("API token was not found."));
+    }
+    state
+        .db
+        .write_audit_log(
+            Some(user.id),
+            "revoke_api_token",
+            "api_token",
+            Some(token_id),
+            json!({}),
+        )
+        .await?;
+    Ok(Json(LogoutResponse { ok: true }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderMap, HeaderValue, Method, header};
+
+    use super::{bearer_token, required_scope};
+    use crate::web::contracts::ApiTokenScope;
+
+    #[test]
+    fn required_scope_follows_route_and_method() {
+        let scope = |method: Method, path: &str| {
+            required_scope(&method, path).map_err(|error| format!("{error:?}"))
+        };
+
+        assert_eq!(
+            scope(Method::GET, "/api/drafts/abc"),
+            Ok(Some(ApiTokenScope::DraftsRead))
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/drafts/abc/generate"),
+            Ok(Some(ApiTokenScope::DraftsWrite))
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/config-sets/abc/deploy"),
+            Ok(Some(ApiTokenScope::Admin))
+        );
+        assert_eq!(
+            scope(Method::GET, "/api/audit/export"),
+            Ok(Some(ApiTokenScope::Admin))
+        );
+        assert_eq!(
+            scope(Method::GET, "/api/logs/stream"),
+            Ok(Some(ApiTokenScope::LogsRead))
+        );
+        assert_eq!(scope(Method::GET, "/api/auth/me"), Ok(None));
+        assert!(scope(Method::POST, "/api/tokens").is_err());
+        assert!(scope(Method::PATCH, "/api/auth/password").is_err());
+    }
+
+    #[test]
+    fn bearer_token_reads_authorization_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("bearer esk_abc"),
+        );
+        assert_eq!(bearer_token(&headers), Some("esk_abc"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+        );
+        assert_eq!(bearer_token(&headers), None);
+    }
+}
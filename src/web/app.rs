@@ -5,6 +5,7 @@ use std::{
     net::SocketAddr,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
 use axum::Router;
@@ -19,8 +20,11 @@ use super::{
     api::{WebAppState, build_router},
     db::Database,
     error::WebError,
+    oidc::OidcSettings,
 };
 
+const AUDIT_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, Clone)]
 pub struct WebRuntimeConfig {
     pub listen: SocketAddr,
@@ -30,6 +34,7 @@ pub struct WebRuntimeConfig {
     pub stream_log_dir: PathBuf,
     pub executable_path: PathBuf,
     pub main_config_path: Option<PathBuf>,
+    pub oidc: Option<OidcSettings>,
 }
 
 pub fn to_runtime_config(
@@ -88,6 +93,7 @@ pub fn to_runtime_config(
         executable_path: std::env::current_exe()
             .unwrap_or_else(|_| PathBuf::from("embystream")),
         main_config_path: None,
+        oidc: OidcSettings::from_env()?,
     })
 }
 
@@ -114,6 +120,7 @@ pub async fn serve_web_app(config: WebRuntimeConfig) -> Result<(), WebError> {
     }
 
     let runtime_log_dir = config.runtime_log_dir.clone();
+    tokio::spawn(prune_audit_logs_periodically(db.clone()));
     let state = WebAppState::new(db, config);
     let router: Router = build_router(state);
 
@@ -135,6 +142,21 @@ pub async fn serve_web_app(config: WebRuntimeConfig) -> Result<(), WebError> {
     .map_err(WebError::from)
 }
 
+/// Applies the audit log retention setting at startup and then hourly.
+async fn prune_audit_logs_periodically(db: Database) {
+    let mut interval = tokio::time::interval(AUDIT_PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(error) = db.prune_audit_logs().await {
+            print_web_startup_line(
+                None,
+                "WARN",
+                format!("Audit log pruning failed: {error}"),
+            );
+        }
+    }
+}
+
 fn print_web_startup_line(
     persisted: Option<(&Path, String)>,
     level: &str,
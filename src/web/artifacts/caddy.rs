@@ -0,0 +1,254 @@
+//! Caddyfile counterpart of `nginx.conf`. Caddy proxies websocket upgrades
+//! and sets the `X-Forwarded-*` headers itself, and follows
+//! `X-Accel-Redirect` through `handle_response`.
+
+use crate::config::{general::StreamMode, types::RawConfig};
+
+use super::{AccelTarget, ProxySite, accel_targets};
+
+pub(super) fn render_caddyfile(raw: &RawConfig, site: &ProxySite) -> String {
+    let tls = if site.certificate.is_empty() || site.private_key.is_empty() {
+        "\t# Caddy obtains and renews a certificate for this host on its own;\n\t# use `tls <certificate> <private_key>` to serve your own files.".to_string()
+    } else {
+        format!("\ttls {} {}", site.certificate, site.private_key)
+    };
+    let request_body = match caddy_size(&site.client_max_body_size) {
+        Some(size) => {
+            format!("\n\n\trequest_body {{\n\t\tmax_size {size}\n\t}}")
+        }
+        None => String::new(),
+    };
+    let headers = match site.stream_mode {
+        StreamMode::Frontend => {
+            "\t\tReferrer-Policy \"origin-when-cross-origin\"\n\t\tStrict-Transport-Security \"max-age=15552000; preload\""
+        }
+        StreamMode::Backend => {
+            "\t\tStrict-Transport-Security \"max-age=31536000; includeSubDomains; preload\""
+        }
+        StreamMode::Dual => {
+            "\t\tReferrer-Policy \"origin-when-cross-origin\"\n\t\tStrict-Transport-Security \"max-age=31536000; includeSubDomains; preload\""
+        }
+    };
+    let log = match site.access_log.as_deref() {
+        Some(path) => {
+            format!(
+                "\n\n\tlog {{\n\t\toutput file {path}\n\t}}\n\n\tencode gzip"
+            )
+        }
+        None => String::new(),
+    };
+    let frontend_routes = format!(
+        r#"	@websocket path_regexp (?i){websocket_pattern}
+	handle @websocket {{
+		reverse_proxy 127.0.0.1:{emby_port}
+	}}
+
+	@static path_regexp (?i){static_pattern}
+	handle @static {{
+		header Cache-Control "public, max-age=315360000"
+		reverse_proxy 127.0.0.1:{frontend_port}
+	}}"#,
+        websocket_pattern = site.websocket_pattern,
+        emby_port = site.emby_port,
+        static_pattern = site.static_pattern,
+        frontend_port = site.frontend_port,
+    );
+    let frontend_fallback = format!(
+        r#"	handle {{
+		reverse_proxy 127.0.0.1:{frontend_port} {{
+			flush_interval -1
+		}}
+	}}"#,
+        frontend_port = site.frontend_port,
+    );
+    let stream_proxy = render_stream_proxy(raw, site.backend_port);
+    let routes = match site.stream_mode {
+        StreamMode::Frontend => {
+            format!("{frontend_routes}\n\n{frontend_fallback}")
+        }
+        StreamMode::Backend => format!("\thandle {{\n{stream_proxy}\n\t}}"),
+        StreamMode::Dual => format!(
+            "{frontend_routes}\n\n\thandle /{stream_path}* {{\n{stream_proxy}\n\t}}\n\n{frontend_fallback}",
+            stream_path = site.stream_path,
+        ),
+    };
+
+    format!(
+        r#"# Caddy redirects HTTP to HTTPS, upgrades websockets and adds the
+# X-Forwarded-* headers without extra directives.
+{server_name} {{
+{tls}{request_body}
+
+	header {{
+{headers}
+		X-Frame-Options "SAMEORIGIN"
+		X-Content-Type-Options "nosniff"
+		X-XSS-Protection "1; mode=block"
+		-X-Powered-By
+	}}{log}
+
+{routes}
+}}
+"#,
+        server_name = site.server_name,
+    )
+}
+
+/// `reverse_proxy` to the backend listener with the stream timeouts and,
+/// when a node uses `accel_redirect`, the offload routes it points at.
+fn render_stream_proxy(raw: &RawConfig, backend_port: u16) -> String {
+    let targets = accel_targets(raw);
+    let accel = if targets.is_empty() {
+        String::new()
+    } else {
+        let routes = targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| render_accel_route(index, target))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        format!(
+            r#"
+
+			@accel header X-Accel-Redirect *
+			handle_response @accel {{
+				rewrite * {{rp.header.X-Accel-Redirect}}
+
+{routes}
+			}}"#
+        )
+    };
+
+    format!(
+        r#"		reverse_proxy 127.0.0.1:{backend_port} {{
+			flush_interval -1
+			transport http {{
+				dial_timeout 120s
+				read_timeout 1200s
+				write_timeout 1200s
+			}}{accel}
+		}}"#
+    )
+}
+
+fn render_accel_route(index: usize, target: &AccelTarget) -> String {
+    match target {
+        AccelTarget::WebDav {
+            node_name,
+            node_uuid,
+            upstream,
+            upstream_path,
+            host_header,
+        } => {
+            let rewrite = if upstream_path.is_empty() {
+                String::new()
+            } else {
+                format!("\t\t\t\t\trewrite * {upstream_path}{{uri}}\n")
+            };
+            format!(
+                r#"				# webdav node '{node_name}'
+				handle_path /_origin/webdav/{node_uuid}/* {{
+{rewrite}					reverse_proxy {upstream} {{
+						header_up Host {host_header}
+						flush_interval -1
+					}}
+				}}"#
+            )
+        }
+        AccelTarget::GoogleDrive {
+            node_name,
+            root,
+            node_uuid,
+            query,
+        } => format!(
+            r#"				# googleDrive node '{node_name}' (root: {root})
+				@google_drive_{index} path_regexp google_drive_{index} ^/_origin/google-drive/{node_uuid}/([^/]+)$
+				handle @google_drive_{index} {{
+					vars google_token {{query.token}}
+					rewrite * /drive/v3/files/{{re.google_drive_{index}.1}}?{query}
+					reverse_proxy https://www.googleapis.com {{
+						header_up Host www.googleapis.com
+						header_up Authorization "Bearer {{vars.google_token}}"
+						flush_interval -1
+						transport http {{
+							dial_timeout 60s
+							read_timeout 3600s
+							write_timeout 3600s
+						}}
+					}}
+				}}"#
+        ),
+    }
+}
+
+/// nginx `client_max_body_size` (`100M`, `1g`, `512k`, bytes) as a Caddy
+/// size; `0` turns the limit off in both.
+fn caddy_size(nginx_size: &str) -> Option<String> {
+    let size = nginx_size.trim();
+    let (number, unit) = match size.char_indices().last() {
+        Some((index, unit)) if unit.is_ascii_alphabetic() => {
+            let unit = match unit.to_ascii_lowercase() {
+                'k' => "KB",
+                'm' => "MB",
+                'g' => "GB",
+                _ => return None,
+            };
+            (&size[..index], unit)
+        }
+        _ => (size, ""),
+    };
+    let number = number.parse::<u64>().ok()?;
+    (number > 0).then(|| format!("{number}{unit}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli_wizard::template_payload::build_template_raw,
+        config::general::StreamMode,
+        web::{artifacts::ProxySite, drafts::wizard_payload_from_raw},
+    };
+
+    use super::{caddy_size, render_caddyfile};
+
+    #[test]
+    fn caddyfile_follows_accel_redirect_for_webdav_and_google_drive() {
+        let mut raw = build_template_raw(StreamMode::Backend);
+        for node in raw.backend_nodes.iter_mut().flatten() {
+            if node.google_drive.is_some() {
+                node.proxy_mode = "accel_redirect".into();
+            }
+        }
+        let mut payload = wizard_payload_from_raw(raw.clone());
+        payload.nginx.backend.ssl_certificate = "/etc/ssl/stream.pem".into();
+        payload.nginx.backend.ssl_certificate_key =
+            "/etc/ssl/stream.key".into();
+
+        let caddyfile = render_caddyfile(&raw, &ProxySite::new(&raw, &payload));
+
+        assert!(
+            caddyfile.contains("\ttls /etc/ssl/stream.pem /etc/ssl/stream.key")
+        );
+        assert!(caddyfile.contains("\t\tmax_size 1GB"));
+        assert!(caddyfile.contains("rewrite * {rp.header.X-Accel-Redirect}"));
+        assert!(
+            caddyfile.contains("handle_path /_origin/webdav/webdav_node_a/* {")
+        );
+        assert!(
+            caddyfile.contains(
+                "^/_origin/google-drive/google_drive_node_a/([^/]+)$"
+            )
+        );
+        assert!(caddyfile.contains(
+            "header_up Authorization \"Bearer {vars.google_token}\""
+        ));
+    }
+
+    #[test]
+    fn caddy_size_converts_nginx_units() {
+        assert_eq!(caddy_size("100M").as_deref(), Some("100MB"));
+        assert_eq!(caddy_size("1g").as_deref(), Some("1GB"));
+        assert_eq!(caddy_size("4096").as_deref(), Some("4096"));
+        assert_eq!(caddy_size("0"), None);
+    }
+}
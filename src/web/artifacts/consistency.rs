@@ -0,0 +1,650 @@
+//! Cross-checks a rendered config set: the proxy and container artifacts
+//! must reach the listeners, accel locations and files `config.toml`
+//! names. Findings are reported rather than rejected so a draft can still
+//! be generated and fixed by hand.
+
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    config::{general::StreamMode, types::RawConfig},
+    core::backend::{
+        constants::{LOCAL_NODE_HOST_MARKERS, STREAM_RELAY_BACKEND_TYPE},
+        google_drive, webdav,
+    },
+    web::contracts::{
+        ArtifactIssue, ArtifactIssueCode, ArtifactIssueSeverity, ArtifactType,
+        WizardPayload,
+    },
+};
+
+use super::{AccelTarget, RenderedArtifact, accel_targets};
+
+/// Web Studio listener in `docker-compose.yaml`, `systemd.service` and
+/// `pm2.config.cjs`.
+const STUDIO_PORT: u16 = 6888;
+/// Upstream prefixes of the reverse proxy artifacts; the port follows.
+const PROXY_UPSTREAMS: &[(ArtifactType, &str)] = &[
+    (ArtifactType::NginxConf, "proxy_pass http://127.0.0.1:"),
+    (ArtifactType::Caddyfile, "reverse_proxy 127.0.0.1:"),
+    (ArtifactType::TraefikDynamic, "- url: 'http://127.0.0.1:"),
+];
+/// Artifacts that carry the internal `accel_redirect` locations.
+const ACCEL_ARTIFACTS: &[ArtifactType] = &[
+    ArtifactType::NginxConf,
+    ArtifactType::Caddyfile,
+    ArtifactType::KubernetesIngress,
+];
+
+pub fn check_artifacts(
+    raw: &RawConfig,
+    payload: &WizardPayload,
+    artifacts: &[RenderedArtifact],
+) -> Vec<ArtifactIssue> {
+    let listeners = listeners(raw);
+    let mut issues = Vec::new();
+    check_port_collisions(raw, &listeners, &mut issues);
+    check_compose(raw, &listeners, artifacts, &mut issues);
+    check_proxy_upstreams(raw, &listeners, artifacts, &mut issues);
+    if raw.general.stream_mode != StreamMode::Frontend {
+        check_accel_locations(raw, artifacts, &mut issues);
+    }
+    check_stream_relay_loops(raw, payload, &listeners, &mut issues);
+    issues
+}
+
+fn issue(
+    severity: ArtifactIssueSeverity,
+    code: ArtifactIssueCode,
+    artifact_type: ArtifactType,
+    message: String,
+) -> ArtifactIssue {
+    ArtifactIssue {
+        severity,
+        code,
+        artifact_type,
+        message,
+    }
+}
+
+/// Gateway listeners the draft's stream mode actually binds.
+fn listeners(raw: &RawConfig) -> Vec<(&'static str, u16)> {
+    let mode = raw.general.stream_mode;
+    let mut listeners = Vec::new();
+    if mode != StreamMode::Backend
+        && let Some(frontend) = raw.frontend.as_ref()
+    {
+        listeners.push(("frontend", frontend.listen_port));
+    }
+    if mode != StreamMode::Frontend
+        && let Some(backend) = raw.backend.as_ref()
+    {
+        listeners.push(("backend", backend.listen_port));
+    }
+    listeners
+}
+
+fn is_local_host(host: &str) -> bool {
+    LOCAL_NODE_HOST_MARKERS.contains(&host)
+}
+
+/// Lowercased host of `url`, with or without a scheme.
+fn url_host(url: &str) -> Option<String> {
+    let authority = url
+        .trim()
+        .split_once("://")
+        .map_or(url.trim(), |(_, rest)| rest)
+        .split(['/', '?'])
+        .next()?;
+    let host = authority
+        .rsplit_once(':')
+        .map_or(authority, |(host, _)| host);
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+fn emby_is_local(raw: &RawConfig) -> bool {
+    url_host(&raw.emby.url).is_none_or(|host| is_local_host(&host))
+}
+
+fn check_port_collisions(
+    raw: &RawConfig,
+    listeners: &[(&'static str, u16)],
+    issues: &mut Vec<ArtifactIssue>,
+) {
+    if let [(_, frontend), (_, backend)] = listeners
+        && frontend == backend
+    {
+        issues.push(issue(
+            ArtifactIssueSeverity::Error,
+            ArtifactIssueCode::PortCollision,
+            ArtifactType::ConfigToml,
+            format!(
+                "Frontend and backend both listen on port {frontend}; dual mode refuses to start."
+            ),
+        ));
+    }
+
+    let emby_port = raw.emby.port.trim().parse::<u16>().ok();
+    for (name, port) in listeners {
+        let clash = match *port {
+            STUDIO_PORT => Some((
+                ArtifactType::DockerCompose,
+                "the Web Studio".to_string(),
+            )),
+            80 | 443 => {
+                Some((ArtifactType::NginxConf, "the nginx.conf server".into()))
+            }
+            port if emby_port == Some(port) && emby_is_local(raw) => {
+                Some((ArtifactType::ConfigToml, "the local Emby server".into()))
+            }
+            _ => None,
+        };
+        if let Some((artifact_type, owner)) = clash {
+            issues.push(issue(
+                ArtifactIssueSeverity::Error,
+                ArtifactIssueCode::PortCollision,
+                artifact_type,
+                format!(
+                    "The {name} listen_port {port} is also used by {owner}."
+                ),
+            ));
+        }
+    }
+}
+
+fn check_compose(
+    raw: &RawConfig,
+    listeners: &[(&'static str, u16)],
+    artifacts: &[RenderedArtifact],
+    issues: &mut Vec<ArtifactIssue>,
+) {
+    let Some(compose) = artifacts
+        .iter()
+        .find(|artifact| artifact.artifact_type == ArtifactType::DockerCompose)
+    else {
+        return;
+    };
+
+    let mut published = HashSet::new();
+    let mut mounts = Vec::new();
+    for line in compose.content.lines() {
+        let Some(entry) = line.trim().strip_prefix("- ") else {
+            continue;
+        };
+        if let Some(port) = entry.strip_prefix('"') {
+            if let Some(port) = port
+                .trim_end_matches('"')
+                .rsplit(':')
+                .next()
+                .and_then(|port| port.parse::<u16>().ok())
+            {
+                published.insert(port);
+            }
+        } else if let Some(target) = entry.split(':').nth(1)
+            && target.starts_with('/')
+        {
+            mounts.push(target.trim_end_matches('/').to_string());
+        }
+    }
+
+    for (name, port) in listeners {
+        if !published.contains(port) {
+            issues.push(issue(
+                ArtifactIssueSeverity::Error,
+                ArtifactIssueCode::PortNotPublished,
+                ArtifactType::DockerCompose,
+                format!(
+                    "{} does not publish the {name} listen_port {port}.",
+                    compose.file_name
+                ),
+            ));
+        }
+    }
+
+    // Relative paths resolve next to config.toml inside the container.
+    let Some(config_dir) = mounts
+        .iter()
+        .find(|target| target.ends_with("/config.toml"))
+        .and_then(|target| Path::new(target).parent())
+    else {
+        return;
+    };
+    for (field, path) in tls_paths(raw) {
+        let resolved = config_dir.join(path.trim_start_matches("./"));
+        let resolved = resolved.to_string_lossy();
+        let mounted = mounts.iter().any(|mount| {
+            resolved == mount.as_str()
+                || resolved.starts_with(&format!("{mount}/"))
+        });
+        if !mounted {
+            issues.push(issue(
+                ArtifactIssueSeverity::Warning,
+                ArtifactIssueCode::TlsPathNotMounted,
+                ArtifactType::DockerCompose,
+                format!(
+                    "{field} resolves to {resolved} in the container, which {} does not mount.",
+                    compose.file_name
+                ),
+            ));
+        }
+    }
+}
+
+/// TLS files and ACME state the gateway reads or writes itself, keyed by
+/// the config field that names them.
+fn tls_paths(raw: &RawConfig) -> Vec<(String, String)> {
+    let Some(http2) = raw.http2.as_ref() else {
+        return Vec::new();
+    };
+    let acme = http2.acme.as_ref().filter(|acme| acme.enable);
+    let mut paths = Vec::new();
+    let mut push = |field: String, path: &str| {
+        let path = path.trim();
+        if !path.is_empty() {
+            paths.push((field, path.to_string()));
+        }
+    };
+
+    // ACME writes into the default `ssl/` directory when no path is set.
+    let default_ssl_dir = if acme.is_some() { "ssl" } else { "" };
+    push(
+        "Http2.ssl_cert_file".into(),
+        Some(http2.ssl_cert_file.as_str())
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or(default_ssl_dir),
+    );
+    push(
+        "Http2.ssl_key_file".into(),
+        Some(http2.ssl_key_file.as_str())
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or(default_ssl_dir),
+    );
+    let frontend = http2.frontend.as_ref().filter(|frontend| frontend.enable);
+    if let Some(frontend) = frontend {
+        push(
+            "Http2.Frontend.ssl_cert_file".into(),
+            &frontend.ssl_cert_file,
+        );
+        push("Http2.Frontend.ssl_key_file".into(), &frontend.ssl_key_file);
+    }
+    let sni = http2.sni.iter().map(|sni| ("Http2.Sni", sni)).chain(
+        frontend
+            .into_iter()
+            .flat_map(|frontend| &frontend.sni)
+            .map(|sni| ("Http2.Frontend.Sni", sni)),
+    );
+    for (table, sni) in sni {
+        let names = sni.server_names.join(",");
+        push(
+            format!("{table} '{names}' ssl_cert_file"),
+            &sni.ssl_cert_file,
+        );
+        push(format!("{table} '{names}' ssl_key_file"), &sni.ssl_key_file);
+    }
+    if let Some(acme) = acme {
+        push(
+            "Http2.Acme.state_dir".into(),
+            Some(acme.state_dir.as_str())
+                .filter(|path| !path.trim().is_empty())
+                .unwrap_or("acme"),
+        );
+    }
+    paths
+}
+
+fn check_proxy_upstreams(
+    raw: &RawConfig,
+    listeners: &[(&'static str, u16)],
+    artifacts: &[RenderedArtifact],
+    issues: &mut Vec<ArtifactIssue>,
+) {
+    // Websocket upgrades go straight to Emby in the frontend-facing modes.
+    let emby_port = (raw.general.stream_mode != StreamMode::Backend)
+        .then(|| raw.emby.port.trim().parse::<u16>().ok())
+        .flatten();
+    // Accel locations may proxy to a WebDAV server on the same host.
+    let accel_ports = accel_targets(raw)
+        .into_iter()
+        .filter_map(|target| match target {
+            AccelTarget::WebDav { upstream, .. } => upstream
+                .rsplit_once(':')
+                .and_then(|(_, port)| port.parse::<u16>().ok()),
+            AccelTarget::GoogleDrive { .. } => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (artifact_type, marker) in PROXY_UPSTREAMS {
+        let Some(artifact) = artifacts
+            .iter()
+            .find(|artifact| artifact.artifact_type == *artifact_type)
+        else {
+            continue;
+        };
+        let upstreams = ports_after(&artifact.content, marker);
+
+        for port in &upstreams {
+            if listeners.iter().all(|(_, listen)| listen != port)
+                && emby_port != Some(*port)
+                && !accel_ports.contains(port)
+            {
+                issues.push(issue(
+                    ArtifactIssueSeverity::Error,
+                    ArtifactIssueCode::UpstreamPortMismatch,
+                    *artifact_type,
+                    format!(
+                        "{} proxies to 127.0.0.1:{port}, which config.toml does not listen on.",
+                        artifact.file_name
+                    ),
+                ));
+            }
+        }
+        for (name, port) in listeners {
+            if !upstreams.contains(port) {
+                issues.push(issue(
+                    ArtifactIssueSeverity::Error,
+                    ArtifactIssueCode::UpstreamPortMismatch,
+                    *artifact_type,
+                    format!(
+                        "{} never proxies to the {name} listen_port {port}.",
+                        artifact.file_name
+                    ),
+                ));
+            }
+        }
+        if let Some(port) = emby_port
+            && upstreams.contains(&port)
+            && !emby_is_local(raw)
+        {
+            issues.push(issue(
+                ArtifactIssueSeverity::Warning,
+                ArtifactIssueCode::EmbyNotLocal,
+                *artifact_type,
+                format!(
+                    "{} sends websockets to 127.0.0.1:{port}, but Emby is configured at {}.",
+                    artifact.file_name,
+                    raw.emby.url.trim()
+                ),
+            ));
+        }
+    }
+}
+
+/// Distinct ports written right after each occurrence of `marker`.
+fn ports_after(content: &str, marker: &str) -> Vec<u16> {
+    let mut ports = Vec::new();
+    for (index, _) in content.match_indices(marker) {
+        let digits = content[index + marker.len()..]
+            .split(|char: char| !char.is_ascii_digit())
+            .next()
+            .unwrap_or_default();
+        if let Ok(port) = digits.parse::<u16>()
+            && !ports.contains(&port)
+        {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+fn accel_location(target: &AccelTarget) -> (&str, String) {
+    match target {
+        AccelTarget::WebDav {
+            node_name,
+            node_uuid,
+            ..
+        } => (
+            node_name,
+            format!("{}/{node_uuid}/", webdav::ACCEL_REDIRECT_PREFIX),
+        ),
+        AccelTarget::GoogleDrive {
+            node_name,
+            node_uuid,
+            ..
+        } => (
+            node_name,
+            format!("{}/{node_uuid}/", google_drive::ACCEL_REDIRECT_PREFIX),
+        ),
+    }
+}
+
+fn check_accel_locations(
+    raw: &RawConfig,
+    artifacts: &[RenderedArtifact],
+    issues: &mut Vec<ArtifactIssue>,
+) {
+    let targets = accel_targets(raw);
+    let locations = targets.iter().map(accel_location).collect::<Vec<_>>();
+
+    for artifact in artifacts {
+        if artifact.artifact_type == ArtifactType::TraefikDynamic {
+            for (node_name, _) in &locations {
+                issues.push(issue(
+                    ArtifactIssueSeverity::Warning,
+                    ArtifactIssueCode::AccelUnsupported,
+                    artifact.artifact_type,
+                    format!(
+                        "Traefik cannot follow X-Accel-Redirect from node '{node_name}'; serve it through nginx.conf or the Caddyfile, or switch the node to proxy_mode \"proxy\"."
+                    ),
+                ));
+            }
+            continue;
+        }
+        if !ACCEL_ARTIFACTS.contains(&artifact.artifact_type) {
+            continue;
+        }
+
+        for (node_name, location) in &locations {
+            if !artifact.content.contains(location.as_str()) {
+                issues.push(issue(
+                    ArtifactIssueSeverity::Error,
+                    ArtifactIssueCode::AccelLocationMissing,
+                    artifact.artifact_type,
+                    format!(
+                        "{} has no {location} location for accel_redirect node '{node_name}'.",
+                        artifact.file_name
+                    ),
+                ));
+            }
+        }
+        for prefix in [
+            webdav::ACCEL_REDIRECT_PREFIX,
+            google_drive::ACCEL_REDIRECT_PREFIX,
+        ] {
+            let marker = format!("{prefix}/");
+            let mut seen = HashSet::new();
+            for (index, _) in artifact.content.match_indices(&marker) {
+                let node_uuid = artifact.content[index + marker.len()..]
+                    .split(|char: char| {
+                        !char.is_ascii_alphanumeric()
+                            && char != '_'
+                            && char != '-'
+                    })
+                    .next()
+                    .unwrap_or_default();
+                let location = format!("{marker}{node_uuid}/");
+                if node_uuid.is_empty()
+                    || !seen.insert(node_uuid)
+                    || locations.iter().any(|(_, known)| *known == location)
+                {
+                    continue;
+                }
+                issues.push(issue(
+                    ArtifactIssueSeverity::Warning,
+                    ArtifactIssueCode::AccelLocationStale,
+                    artifact.artifact_type,
+                    format!(
+                        "{} routes {location}, but no accel_redirect node in config.toml uses node_uuid '{node_uuid}'.",
+                        artifact.file_name
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// StreamRelay nodes redirect clients to `base_url`; pointing one back at
+/// this gateway's own listener or public host redirects forever.
+fn check_stream_relay_loops(
+    raw: &RawConfig,
+    payload: &WizardPayload,
+    listeners: &[(&'static str, u16)],
+    issues: &mut Vec<ArtifactIssue>,
+) {
+    let own_hosts = [
+        &payload.nginx.frontend.server_name,
+        &payload.nginx.backend.server_name,
+    ]
+    .into_iter()
+    .flat_map(|names| names.split_whitespace())
+    .filter_map(url_host)
+    .collect::<HashSet<_>>();
+
+    for node in raw.backend_nodes.as_deref().unwrap_or(&[]) {
+        if !node
+            .backend_type
+            .eq_ignore_ascii_case(STREAM_RELAY_BACKEND_TYPE)
+        {
+            continue;
+        }
+        let uri = node.uri();
+        let Some(host) = uri.host().map(str::to_ascii_lowercase) else {
+            continue;
+        };
+        let port =
+            uri.port_u16()
+                .unwrap_or(if uri.scheme_str() == Some("https") {
+                    443
+                } else {
+                    80
+                });
+        let listener = listeners
+            .iter()
+            .find(|(_, listen)| *listen == port)
+            .map(|(name, _)| format!("the {name} listener"));
+        let target = if is_local_host(&host) {
+            listener
+        } else if own_hosts.contains(&host) {
+            listener.or_else(|| {
+                matches!(port, 80 | 443).then(|| "nginx.conf".to_string())
+            })
+        } else {
+            None
+        };
+        if let Some(target) = target {
+            issues.push(issue(
+                ArtifactIssueSeverity::Error,
+                ArtifactIssueCode::StreamRelayLoop,
+                ArtifactType::ConfigToml,
+                format!(
+                    "StreamRelay node '{}' redirects to {host}:{port}, which is {target} of this gateway.",
+                    node.name
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli_wizard::{
+            emit::emit_wizard_config_toml, template_payload::build_template_raw,
+        },
+        config::general::StreamMode,
+        web::{
+            artifacts::render_all,
+            contracts::{ArtifactIssueCode, ArtifactType},
+            drafts::wizard_payload_from_raw,
+        },
+    };
+
+    use super::check_artifacts;
+
+    #[test]
+    fn templates_render_consistent_artifacts() {
+        for mode in
+            [StreamMode::Frontend, StreamMode::Backend, StreamMode::Dual]
+        {
+            let raw = build_template_raw(mode);
+            let payload = wizard_payload_from_raw(raw.clone());
+            let config_toml = emit_wizard_config_toml(&raw).expect("toml");
+            let artifacts = render_all(&raw, &payload, config_toml);
+
+            let issues = check_artifacts(&raw, &payload, &artifacts)
+                .into_iter()
+                .filter(|issue| {
+                    issue.code != ArtifactIssueCode::AccelUnsupported
+                })
+                .collect::<Vec<_>>();
+            assert!(issues.is_empty(), "{mode:?}: {issues:#?}");
+        }
+    }
+
+    #[test]
+    fn reports_drifted_artifacts_and_relay_loops() {
+        let mut raw = build_template_raw(StreamMode::Backend);
+        let backend_port = raw.backend.as_ref().expect("backend").listen_port;
+        let nodes = raw.backend_nodes.as_mut().expect("nodes");
+        let mut relay = nodes[0].clone();
+        relay.name = "Relay".into();
+        relay.backend_type = "StreamRelay".into();
+        relay.base_url = "https://stream.example.com".into();
+        relay.port = "443".into();
+        nodes.push(relay);
+        raw.http2.get_or_insert_default().ssl_cert_file =
+            "/etc/ssl/gateway.pem".into();
+        let mut payload = wizard_payload_from_raw(raw.clone());
+        payload.nginx.backend.server_name = "stream.example.com".into();
+        let config_toml = emit_wizard_config_toml(&raw).expect("toml");
+        let mut artifacts = render_all(&raw, &payload, config_toml);
+        for artifact in &mut artifacts {
+            match artifact.artifact_type {
+                ArtifactType::NginxConf => {
+                    artifact.content = artifact.content.replace(
+                        "/_origin/webdav/webdav_node_a/",
+                        "/_origin/webdav/old_node/",
+                    );
+                }
+                ArtifactType::DockerCompose => {
+                    artifact.content = artifact.content.replace(
+                        &format!("\"{backend_port}:{backend_port}\""),
+                        "\"60099:60099\"",
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        let issues = check_artifacts(&raw, &payload, &artifacts);
+        let codes = |artifact_type: ArtifactType| {
+            issues
+                .iter()
+                .filter(|issue| issue.artifact_type == artifact_type)
+                .map(|issue| issue.code)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            codes(ArtifactType::NginxConf),
+            [
+                ArtifactIssueCode::AccelLocationMissing,
+                ArtifactIssueCode::AccelLocationStale
+            ]
+        );
+        assert_eq!(
+            codes(ArtifactType::DockerCompose),
+            [
+                ArtifactIssueCode::PortNotPublished,
+                ArtifactIssueCode::TlsPathNotMounted
+            ]
+        );
+        assert_eq!(
+            codes(ArtifactType::ConfigToml),
+            [ArtifactIssueCode::StreamRelayLoop]
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|issue| issue.message.contains("old_node"))
+        );
+    }
+}
@@ -0,0 +1,941 @@
+//! Kubernetes manifests rendered from the same draft as the other
+//! artifacts. Credentials move out of the ConfigMap into a Secret, which an
+//! init container copies onto the data volume for the `directory` secrets
+//! provider so refreshed Google Drive tokens can be written back.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    net::IpAddr,
+};
+
+use crate::{
+    cli_wizard::emit::emit_wizard_config_toml,
+    config::{
+        general::StreamMode,
+        secrets::{SecretsConfig, SecretsProvider, referenced_secret_names},
+        types::RawConfig,
+    },
+    web::contracts::{ArtifactType, WizardPayload},
+};
+
+use super::{
+    RenderedArtifact, normalize_or_default, normalize_server_name,
+    render_accel_blocks,
+};
+
+const APP_NAME: &str = "embystream";
+const CONFIG_MAP_NAME: &str = "embystream-config";
+const SECRET_NAME: &str = "embystream-secrets";
+const CONFIG_DIR: &str = "/config/embystream";
+/// Read-only mount of the Secret, used only to seed [`SECRETS_DIR`].
+const SECRET_MOUNT_DIR: &str = "/run/secrets/embystream";
+const DATA_DIR: &str = "/data";
+const SECRETS_DIR: &str = "/data/secrets";
+const STUDIO_PORT: u16 = 6888;
+
+/// Everything the manifests share, resolved once with defaults applied.
+struct KubernetesSettings {
+    namespace: String,
+    image: String,
+    ingress_class: String,
+    tls_secret_name: String,
+    ports: Vec<(&'static str, u16)>,
+    rules: Vec<IngressRule>,
+    client_max_body_size: String,
+    media_paths: Vec<String>,
+}
+
+struct IngressRule {
+    /// `None` when the server name is an IP address, which Ingress hosts
+    /// cannot be; the rule then matches every host.
+    host: Option<String>,
+    /// `(path, service port name)`, most specific first.
+    paths: Vec<(String, &'static str)>,
+}
+
+impl KubernetesSettings {
+    fn new(raw: &RawConfig, payload: &WizardPayload) -> Self {
+        let kubernetes = &payload.deployment.kubernetes;
+        let frontend_port = raw.frontend.as_ref().map(|f| f.listen_port);
+        let backend_port = raw.backend.as_ref().map(|b| b.listen_port);
+        let mut ports = Vec::new();
+        if let Some(port) = frontend_port {
+            ports.push(("frontend", port));
+        }
+        if let Some(port) = backend_port
+            && Some(port) != frontend_port
+        {
+            ports.push(("backend", port));
+        }
+        if ports.iter().all(|(_, port)| *port != STUDIO_PORT) {
+            ports.push(("studio", STUDIO_PORT));
+        }
+        let stream_port = if ports.iter().any(|(name, _)| *name == "backend") {
+            "backend"
+        } else {
+            ports[0].0
+        };
+
+        let frontend_host = normalize_server_name(
+            &payload.nginx.frontend.server_name,
+            "stream.example.com",
+        );
+        let backend_host = normalize_server_name(
+            &payload.nginx.backend.server_name,
+            &frontend_host,
+        );
+        let stream_path = raw
+            .backend
+            .as_ref()
+            .map(|backend| backend.path.trim_matches('/'))
+            .filter(|path| !path.is_empty())
+            .unwrap_or("stream");
+        let (rules, client_max_body_size) = match raw.general.stream_mode {
+            StreamMode::Frontend => (
+                vec![IngressRule {
+                    host: ingress_host(frontend_host),
+                    paths: vec![("/".to_string(), ports[0].0)],
+                }],
+                normalize_or_default(
+                    &payload.nginx.frontend.client_max_body_size,
+                    "100M",
+                ),
+            ),
+            StreamMode::Backend => (
+                vec![IngressRule {
+                    host: ingress_host(backend_host),
+                    paths: vec![("/".to_string(), stream_port)],
+                }],
+                normalize_or_default(
+                    &payload.nginx.backend.client_max_body_size,
+                    "1G",
+                ),
+            ),
+            StreamMode::Dual => (
+                vec![IngressRule {
+                    host: ingress_host(frontend_host),
+                    paths: vec![
+                        (format!("/{stream_path}"), stream_port),
+                        ("/".to_string(), ports[0].0),
+                    ],
+                }],
+                normalize_or_default(
+                    &payload.nginx.frontend.client_max_body_size,
+                    "100M",
+                ),
+            ),
+        };
+
+        let mut media_paths = Vec::new();
+        for node in raw.backend_nodes.as_deref().unwrap_or(&[]) {
+            if !node.backend_type.eq_ignore_ascii_case("disk") {
+                continue;
+            }
+            if let Some(path) = literal_directory(&node.pattern)
+                && !media_paths.contains(&path)
+            {
+                media_paths.push(path);
+            }
+        }
+
+        Self {
+            namespace: normalize_or_default(&kubernetes.namespace, APP_NAME),
+            image: normalize_or_default(
+                &kubernetes.image,
+                "openpilipili/embystream:latest",
+            ),
+            ingress_class: normalize_or_default(
+                &kubernetes.ingress_class,
+                "nginx",
+            ),
+            tls_secret_name: normalize_or_default(
+                &kubernetes.tls_secret_name,
+                "embystream-tls",
+            ),
+            ports,
+            rules,
+            client_max_body_size,
+            media_paths,
+        }
+    }
+
+    fn metadata(&self, name: &str) -> String {
+        format!(
+            "metadata:\n  name: {name}\n  namespace: {namespace}\n  labels:\n    app.kubernetes.io/name: {APP_NAME}",
+            namespace = self.namespace,
+        )
+    }
+
+    /// The single `tls` entry shared by the Ingress and the values file.
+    fn tls_entry(&self) -> String {
+        let mut hosts = Vec::new();
+        for host in self.rules.iter().filter_map(|rule| rule.host.as_ref()) {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+        let mut entry = format!("    - secretName: {}", self.tls_secret_name);
+        if !hosts.is_empty() {
+            entry.push_str("\n      hosts:");
+            for host in hosts {
+                entry.push_str(&format!("\n        - {}", yaml_string(host)));
+            }
+        }
+        entry
+    }
+}
+
+fn ingress_host(server_name: String) -> Option<String> {
+    server_name
+        .parse::<IpAddr>()
+        .is_err()
+        .then_some(server_name)
+}
+
+pub(super) fn render_kubernetes_artifacts(
+    raw: &RawConfig,
+    payload: &WizardPayload,
+) -> Vec<RenderedArtifact> {
+    let settings = KubernetesSettings::new(raw, payload);
+    let (config_raw, secrets) = split_secrets(raw);
+    let config_toml =
+        emit_wizard_config_toml(&config_raw).unwrap_or_else(|error| {
+            format!("# config.toml could not be rendered: {error}\n")
+        });
+    // Accel locations run inside the ingress controller, where the
+    // `google_drive_ext` log format of the standalone nginx.conf is absent.
+    let accel_blocks = render_accel_blocks(raw, None);
+    let token_secrets = google_drive_token_secrets(&config_raw);
+
+    vec![
+        yaml_artifact(
+            ArtifactType::KubernetesDeployment,
+            "k8s-deployment.yaml",
+            render_deployment(&settings, &token_secrets),
+        ),
+        yaml_artifact(
+            ArtifactType::KubernetesService,
+            "k8s-service.yaml",
+            render_service(&settings),
+        ),
+        yaml_artifact(
+            ArtifactType::KubernetesConfigMap,
+            "k8s-configmap.yaml",
+            render_config_map(&settings, &config_toml),
+        ),
+        yaml_artifact(
+            ArtifactType::KubernetesSecret,
+            "k8s-secret.yaml",
+            render_secret(&settings, &secrets),
+        ),
+        yaml_artifact(
+            ArtifactType::KubernetesIngress,
+            "k8s-ingress.yaml",
+            render_ingress(&settings, &accel_blocks),
+        ),
+        yaml_artifact(
+            ArtifactType::KubernetesValues,
+            "k8s-values.yaml",
+            render_values(&settings, &secrets),
+        ),
+    ]
+}
+
+fn yaml_artifact(
+    artifact_type: ArtifactType,
+    file_name: &str,
+    content: String,
+) -> RenderedArtifact {
+    RenderedArtifact {
+        artifact_type,
+        file_name: file_name.to_string(),
+        language: "yaml".to_string(),
+        content,
+    }
+}
+
+/// Replaces every credential in a copy of `raw` with a `${secret:NAME}`
+/// reference and returns the values by name. References that were already
+/// in the draft get an empty entry so the Secret lists every key the
+/// config needs.
+fn split_secrets(raw: &RawConfig) -> (RawConfig, BTreeMap<String, String>) {
+    let mut raw = raw.clone();
+    let mut secrets = BTreeMap::new();
+    move_to_secret(&mut raw.general.encipher_key, "encipher_key", &mut secrets);
+    move_to_secret(&mut raw.general.encipher_iv, "encipher_iv", &mut secrets);
+    move_to_secret(&mut raw.emby.token, "emby_token", &mut secrets);
+
+    let mut prefixes = HashSet::new();
+    for (index, node) in raw.backend_nodes.iter_mut().flatten().enumerate() {
+        let mut prefix = format!("node_{}", secret_slug(&node.name));
+        if !prefixes.insert(prefix.clone()) {
+            prefix = format!("{prefix}_{index}");
+        }
+        if let Some(webdav) = node.webdav.as_mut() {
+            move_to_secret(
+                &mut webdav.password,
+                &format!("{prefix}_webdav_password"),
+                &mut secrets,
+            );
+        }
+        if let Some(smb) = node.smb.as_mut() {
+            move_to_secret(
+                &mut smb.password,
+                &format!("{prefix}_smb_password"),
+                &mut secrets,
+            );
+        }
+        if let Some(open_list) = node.open_list.as_mut() {
+            move_to_secret(
+                &mut open_list.token,
+                &format!("{prefix}_openlist_token"),
+                &mut secrets,
+            );
+            move_to_secret(
+                &mut open_list.password,
+                &format!("{prefix}_openlist_password"),
+                &mut secrets,
+            );
+            move_to_secret(
+                &mut open_list.otp_secret,
+                &format!("{prefix}_openlist_otp_secret"),
+                &mut secrets,
+            );
+        }
+        if let Some(google_drive) = node.google_drive.as_mut() {
+            move_to_secret(
+                &mut google_drive.client_secret,
+                &format!("{prefix}_google_client_secret"),
+                &mut secrets,
+            );
+            if google_drive.token_secret.trim().is_empty()
+                && let Some(token) = google_drive.effective_token()
+                && let Ok(value) = serde_json::to_string(&token)
+            {
+                let name = format!("{prefix}_google_token");
+                secrets.insert(name.clone(), value);
+                google_drive.token_secret = name;
+                google_drive.token = None;
+                google_drive.access_token.clear();
+                google_drive.refresh_token.clear();
+            }
+        }
+    }
+
+    raw.secrets = Some(SecretsConfig {
+        provider: SecretsProvider::Directory,
+        directory: SECRETS_DIR.to_string(),
+        ..SecretsConfig::default()
+    });
+    if let Ok(doc) = toml::Value::try_from(&raw) {
+        for name in referenced_secret_names(&doc) {
+            secrets.entry(name).or_default();
+        }
+    }
+    (raw, secrets)
+}
+
+/// Secrets the gateway rewrites when it refreshes a Google Drive token.
+fn google_drive_token_secrets(raw: &RawConfig) -> Vec<String> {
+    raw.backend_nodes
+        .iter()
+        .flatten()
+        .filter_map(|node| node.google_drive.as_ref())
+        .map(|google_drive| google_drive.token_secret.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn move_to_secret(
+    value: &mut String,
+    name: &str,
+    secrets: &mut BTreeMap<String, String>,
+) {
+    if value.trim().is_empty() || value.contains("${secret:") {
+        return;
+    }
+    secrets.insert(name.to_string(), std::mem::take(value));
+    *value = format!("${{secret:{name}}}");
+}
+
+fn secret_slug(name: &str) -> String {
+    let slug = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let slug = slug.trim_matches('_');
+    if slug.is_empty() {
+        "unnamed".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Directory a disk node pattern such as `^/mnt/media(/.*)$` reads from:
+/// the literal prefix before the first regex construct, cut back to a
+/// whole path segment.
+fn literal_directory(pattern: &str) -> Option<String> {
+    let pattern = pattern.trim().trim_start_matches('^');
+    let literal_end = pattern
+        .find(|c: char| ".*+?()[]{}|\\$".contains(c))
+        .unwrap_or(pattern.len());
+    let literal = &pattern[..literal_end];
+    // `/mnt/media(/.*)` captures below a complete directory name.
+    let directory = if pattern[literal_end..].starts_with("(/") {
+        literal
+    } else {
+        &literal[..literal.rfind('/')?]
+    }
+    .trim_end_matches('/');
+    directory.starts_with('/').then(|| directory.to_string())
+}
+
+/// YAML double-quoted scalar; JSON string escapes are valid YAML.
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn indent_block(value: &str, indent: &str) -> String {
+    value
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{indent}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_deployment(
+    settings: &KubernetesSettings,
+    token_secrets: &[String],
+) -> String {
+    let container_ports = settings
+        .ports
+        .iter()
+        .map(|(name, port)| {
+            format!(
+                "            - name: {name}\n              containerPort: {port}"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let probe_port = settings.ports[0].0;
+    let mut volume_mounts = vec![
+        format!(
+            "            - name: config\n              mountPath: {CONFIG_DIR}"
+        ),
+        format!(
+            "            - name: data\n              mountPath: {DATA_DIR}"
+        ),
+    ];
+    let mut volumes = vec![
+        format!(
+            "        - name: config\n          configMap:\n            name: {CONFIG_MAP_NAME}"
+        ),
+        format!(
+            "        - name: secrets\n          secret:\n            secretName: {SECRET_NAME}"
+        ),
+        "        # Replace with a PersistentVolumeClaim to keep studio data and\n        # refreshed Google Drive tokens across restarts.\n        - name: data\n          emptyDir: {}".to_string(),
+    ];
+    for (index, path) in settings.media_paths.iter().enumerate() {
+        volume_mounts.push(format!(
+            "            - name: media-{index}\n              mountPath: {path}\n              readOnly: true"
+        ));
+        volumes.push(format!(
+            "        # Disk node media; swap for a PersistentVolumeClaim when the\n        # files are not on every node.\n        - name: media-{index}\n          hostPath:\n            path: {path}\n            type: Directory"
+        ));
+    }
+    // Tokens already on the data volume are newer than the Secret's seed.
+    let seed_script = format!(
+        r#"mkdir -p {SECRETS_DIR}
+chmod 700 {SECRETS_DIR}
+for file in {SECRET_MOUNT_DIR}/*; do
+  [ -e "$file" ] || continue
+  name="$(basename "$file")"
+  case " {keep} " in
+    *" $name "*) [ -s "{SECRETS_DIR}/$name" ] && continue ;;
+  esac
+  cp "$file" "{SECRETS_DIR}/$name"
+done"#,
+        keep = token_secrets.join(" "),
+    );
+
+    format!(
+        r#"apiVersion: apps/v1
+kind: Deployment
+{metadata}
+spec:
+  # The studio keeps its data in SQLite, so run a single replica.
+  replicas: 1
+  strategy:
+    type: Recreate
+  selector:
+    matchLabels:
+      app.kubernetes.io/name: {APP_NAME}
+  template:
+    metadata:
+      labels:
+        app.kubernetes.io/name: {APP_NAME}
+    spec:
+      initContainers:
+        # The Secret mount is read-only, so copy it onto the data volume
+        # where the gateway can write refreshed Google Drive tokens.
+        - name: seed-secrets
+          image: {image}
+          imagePullPolicy: IfNotPresent
+          command:
+            - sh
+            - -c
+            - |
+{seed_script}
+          volumeMounts:
+            - name: secrets
+              mountPath: {SECRET_MOUNT_DIR}
+              readOnly: true
+            - name: data
+              mountPath: {DATA_DIR}
+      containers:
+        - name: {APP_NAME}
+          image: {image}
+          imagePullPolicy: IfNotPresent
+          env:
+            - name: TZ
+              value: "Asia/Shanghai"
+            - name: PUID
+              value: "1000"
+            - name: PGID
+              value: "1000"
+            - name: UMASK
+              value: "022"
+          ports:
+{container_ports}
+          readinessProbe:
+            tcpSocket:
+              port: {probe_port}
+            initialDelaySeconds: 5
+            periodSeconds: 10
+          livenessProbe:
+            tcpSocket:
+              port: {probe_port}
+            initialDelaySeconds: 15
+            periodSeconds: 20
+          volumeMounts:
+{volume_mounts}
+      volumes:
+{volumes}
+"#,
+        metadata = settings.metadata(APP_NAME),
+        image = settings.image,
+        seed_script = indent_block(&seed_script, "              "),
+        volume_mounts = volume_mounts.join("\n"),
+        volumes = volumes.join("\n"),
+    )
+}
+
+fn render_service(settings: &KubernetesSettings) -> String {
+    let ports = settings
+        .ports
+        .iter()
+        .map(|(name, port)| {
+            format!(
+                "    - name: {name}\n      port: {port}\n      targetPort: {name}"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"apiVersion: v1
+kind: Service
+{metadata}
+spec:
+  type: ClusterIP
+  selector:
+    app.kubernetes.io/name: {APP_NAME}
+  ports:
+{ports}
+"#,
+        metadata = settings.metadata(APP_NAME),
+    )
+}
+
+fn render_config_map(
+    settings: &KubernetesSettings,
+    config_toml: &str,
+) -> String {
+    format!(
+        r#"# config.toml with every credential replaced by a ${{secret:NAME}}
+# reference; the values live in {SECRET_NAME}.
+apiVersion: v1
+kind: ConfigMap
+{metadata}
+data:
+  config.toml: |
+{config_toml}
+"#,
+        metadata = settings.metadata(CONFIG_MAP_NAME),
+        config_toml = indent_block(config_toml, "    "),
+    )
+}
+
+fn render_secret(
+    settings: &KubernetesSettings,
+    secrets: &BTreeMap<String, String>,
+) -> String {
+    let entries = if secrets.is_empty() {
+        "stringData: {}".to_string()
+    } else {
+        let lines = secrets
+            .iter()
+            .map(|(name, value)| {
+                if value.is_empty() {
+                    format!(
+                        "  # TODO: referenced by the draft; fill in its value.\n  {name}: \"\""
+                    )
+                } else {
+                    format!("  {name}: {}", yaml_string(value))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("stringData:\n{lines}")
+    };
+
+    format!(
+        r#"# Copied to {SECRETS_DIR} on the data volume by the seed-secrets
+# init container; config.toml reads it there through the `directory`
+# secrets provider. Keep this file out of version control.
+apiVersion: v1
+kind: Secret
+{metadata}
+type: Opaque
+{entries}
+"#,
+        metadata = settings.metadata(SECRET_NAME),
+    )
+}
+
+fn render_ingress(settings: &KubernetesSettings, accel_blocks: &str) -> String {
+    let mut annotations = vec![
+        format!(
+            "    nginx.ingress.kubernetes.io/proxy-body-size: {}",
+            yaml_string(&settings.client_max_body_size)
+        ),
+        "    nginx.ingress.kubernetes.io/proxy-buffering: \"off\"".to_string(),
+        "    nginx.ingress.kubernetes.io/proxy-request-buffering: \"off\""
+            .to_string(),
+        "    nginx.ingress.kubernetes.io/proxy-read-timeout: \"1200\""
+            .to_string(),
+        "    nginx.ingress.kubernetes.io/proxy-send-timeout: \"1200\""
+            .to_string(),
+    ];
+    let accel_blocks = accel_blocks.trim_matches('\n');
+    if !accel_blocks.is_empty() {
+        let dedented = accel_blocks
+            .lines()
+            .map(|line| line.strip_prefix("    ").unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        annotations.push(format!(
+            "    # accel_redirect locations; the controller must allow snippet\n    # annotations (allow-snippet-annotations: \"true\") and reach every\n    # node upstream below.\n    nginx.ingress.kubernetes.io/server-snippet: |\n{}",
+            indent_block(&dedented, "      ")
+        ));
+    }
+
+    let rules = settings
+        .rules
+        .iter()
+        .map(|rule| {
+            let paths = rule
+                .paths
+                .iter()
+                .map(|(path, port)| {
+                    format!(
+                        r#"          - path: {path}
+            pathType: Prefix
+            backend:
+              service:
+                name: {APP_NAME}
+                port:
+                  name: {port}"#
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            match &rule.host {
+                Some(host) => format!(
+                    "    - host: {}\n      http:\n        paths:\n{paths}",
+                    yaml_string(host)
+                ),
+                None => format!("    - http:\n        paths:\n{paths}"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"apiVersion: networking.k8s.io/v1
+kind: Ingress
+{metadata}
+  annotations:
+{annotations}
+spec:
+  ingressClassName: {ingress_class}
+  tls:
+{tls}
+  rules:
+{rules}
+"#,
+        metadata = settings.metadata(APP_NAME),
+        annotations = annotations.join("\n"),
+        ingress_class = settings.ingress_class,
+        tls = settings.tls_entry(),
+    )
+}
+
+/// Helm-style values describing the rendered manifests. Secret values are
+/// never repeated here, only their names.
+fn render_values(
+    settings: &KubernetesSettings,
+    secrets: &BTreeMap<String, String>,
+) -> String {
+    let (repository, tag) = match settings.image.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+        _ => (settings.image.as_str(), "latest"),
+    };
+    let ports = settings
+        .ports
+        .iter()
+        .map(|(name, port)| format!("    {name}: {port}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let hosts = settings
+        .rules
+        .iter()
+        .map(|rule| {
+            let paths = rule
+                .paths
+                .iter()
+                .map(|(path, port)| {
+                    format!("        - path: {path}\n          port: {port}")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "    - host: {}\n      paths:\n{paths}",
+                yaml_string(rule.host.as_deref().unwrap_or_default())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let media = if settings.media_paths.is_empty() {
+        "  media: []".to_string()
+    } else {
+        let entries = settings
+            .media_paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                format!("    - name: media-{index}\n      hostPath: {path}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("  media:\n{entries}")
+    };
+    let secret_keys = if secrets.is_empty() {
+        "  keys: []".to_string()
+    } else {
+        let keys = secrets
+            .keys()
+            .map(|name| format!("    - {name}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("  keys:\n{keys}")
+    };
+
+    format!(
+        r#"# Values the k8s-*.yaml manifests of this config set were rendered
+# from, laid out for a Helm chart.
+nameOverride: {APP_NAME}
+namespace: {namespace}
+replicaCount: 1
+image:
+  repository: {repository}
+  tag: {tag}
+  pullPolicy: IfNotPresent
+service:
+  type: ClusterIP
+  ports:
+{ports}
+ingress:
+  enabled: true
+  className: {ingress_class}
+  annotations:
+    nginx.ingress.kubernetes.io/proxy-body-size: {body_size}
+  hosts:
+{hosts}
+  tls:
+{tls}
+config:
+  configMapName: {CONFIG_MAP_NAME}
+  mountPath: {CONFIG_DIR}
+secrets:
+  secretName: {SECRET_NAME}
+  mountPath: {SECRET_MOUNT_DIR}
+  copiedTo: {SECRETS_DIR}
+{secret_keys}
+persistence:
+  data:
+    mountPath: {DATA_DIR}
+{media}
+"#,
+        namespace = settings.namespace,
+        ingress_class = settings.ingress_class,
+        body_size = yaml_string(&settings.client_max_body_size),
+        tls = settings.tls_entry(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli_wizard::template_payload::build_template_raw,
+        config::general::StreamMode, web::drafts::wizard_payload_from_raw,
+    };
+
+    use super::{
+        DATA_DIR, SECRET_MOUNT_DIR, SECRETS_DIR, literal_directory,
+        render_kubernetes_artifacts,
+    };
+
+    fn content<'a>(
+        artifacts: &'a [super::RenderedArtifact],
+        file_name: &str,
+    ) -> &'a str {
+        &artifacts
+            .iter()
+            .find(|artifact| artifact.file_name == file_name)
+            .unwrap_or_else(|| panic!("missing {file_name}"))
+            .content
+    }
+
+    #[test]
+    fn config_map_references_secrets_kept_in_the_secret() {
+        let mut raw = build_template_raw(StreamMode::Dual);
+        raw.emby.token = "emby-api-token".into();
+        let webdav_node = raw
+            .backend_nodes
+            .iter_mut()
+            .flatten()
+            .find(|node| node.webdav.is_some())
+            .expect("webdav node");
+        webdav_node.webdav.as_mut().expect("webdav").password =
+            "dav-password".into();
+        let payload = wizard_payload_from_raw(raw.clone());
+
+        let artifacts = render_kubernetes_artifacts(&raw, &payload);
+        let config_map = content(&artifacts, "k8s-configmap.yaml");
+        let secret = content(&artifacts, "k8s-secret.yaml");
+
+        for value in [
+            "emby-api-token",
+            "dav-password",
+            raw.general.encipher_key.as_str(),
+        ] {
+            assert!(!config_map.contains(value), "{value} leaked");
+            assert!(secret.contains(value), "{value} missing");
+        }
+        assert!(config_map.contains("token = \"${secret:emby_token}\""));
+        assert!(config_map.contains("provider = \"directory\""));
+        assert!(config_map.contains("directory = \"/data/secrets\""));
+        assert!(secret.contains("  emby_token: \"emby-api-token\""));
+        assert!(
+            !content(&artifacts, "k8s-values.yaml").contains("dav-password")
+        );
+    }
+
+    #[test]
+    fn ingress_routes_stream_path_and_carries_accel_locations() {
+        let raw = build_template_raw(StreamMode::Dual);
+        let backend = raw.backend.as_ref().expect("backend");
+        let stream_path =
+            format!("- path: /{}", backend.path.trim_matches('/'));
+        let payload = wizard_payload_from_raw(raw.clone());
+
+        let artifacts = render_kubernetes_artifacts(&raw, &payload);
+        let ingress = content(&artifacts, "k8s-ingress.yaml");
+        let deployment = content(&artifacts, "k8s-deployment.yaml");
+
+        assert!(ingress.contains(&stream_path));
+        assert!(ingress.contains("name: backend"));
+        assert!(ingress.contains("name: frontend"));
+        assert!(ingress.contains("secretName: embystream-tls"));
+        assert!(ingress.contains("nginx.ingress.kubernetes.io/server-snippet"));
+        assert!(ingress.contains("      location ^~ /_origin/webdav/"));
+        assert!(!ingress.contains("google_drive_ext"));
+        assert!(
+            deployment
+                .contains(&format!("containerPort: {}", backend.listen_port))
+        );
+        assert!(deployment.contains("containerPort: 6888"));
+        assert!(deployment.contains("mountPath: /run/secrets/embystream"));
+    }
+
+    #[test]
+    fn google_drive_token_secret_lives_on_the_writable_data_volume() {
+        let raw = build_template_raw(StreamMode::Dual);
+        let payload = wizard_payload_from_raw(raw.clone());
+
+        let artifacts = render_kubernetes_artifacts(&raw, &payload);
+        let config_map = content(&artifacts, "k8s-configmap.yaml");
+        let secret = content(&artifacts, "k8s-secret.yaml");
+        let deployment = content(&artifacts, "k8s-deployment.yaml");
+
+        let token_secret = "node_googledrivemedia_google_token";
+        assert!(
+            config_map.contains(&format!("token_secret = \"{token_secret}\""))
+        );
+        assert!(secret.contains(&format!("  {token_secret}: ")));
+        assert!(config_map.contains(&format!("directory = \"{SECRETS_DIR}\"")));
+        assert!(SECRETS_DIR.starts_with(&format!("{DATA_DIR}/")));
+
+        // The gateway only mounts the data volume, read-write; the
+        // read-only Secret mount belongs to the seeding init container.
+        let (init, main) = deployment
+            .split_once("      containers:")
+            .expect("containers");
+        assert!(init.contains("- name: seed-secrets"));
+        assert!(init.contains(&format!("mountPath: {SECRET_MOUNT_DIR}")));
+        assert!(init.contains(&format!(" {token_secret} ")));
+        assert!(!main.contains(SECRET_MOUNT_DIR));
+        let data_mount = main
+            .split("            - name: ")
+            .find(|mount| mount.starts_with("data\n"))
+            .expect("data mount");
+        assert!(data_mount.contains(&format!("mountPath: {DATA_DIR}")));
+        assert!(!data_mount.contains("readOnly"));
+    }
+
+    #[test]
+    fn disk_patterns_mount_their_literal_directory() {
+        assert_eq!(
+            literal_directory("^/mnt/media(/.*)$").as_deref(),
+            Some("/mnt/media")
+        );
+        assert_eq!(
+            literal_directory("/mnt/media/.*").as_deref(),
+            Some("/mnt/media")
+        );
+        assert_eq!(literal_directory("^(/.*)$"), None);
+    }
+}
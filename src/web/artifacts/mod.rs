@@ -1,3 +1,8 @@
+mod caddy;
+mod consistency;
+mod kubernetes;
+mod traefik;
+
 use axum::{
     Json, Router,
     extract::{Path, State},
@@ -8,7 +13,7 @@ use axum::{
 use axum_extra::extract::CookieJar;
 
 use crate::{
-    config::types::RawConfig,
+    config::{backend::GoogleDriveRoot, general::StreamMode, types::RawConfig},
     core::backend::webdav::{DEFAULT_QUERY_PARAM, MODE_PATH_JOIN},
     web::{
         api::WebAppState,
@@ -22,6 +27,8 @@ use crate::{
     },
 };
 
+pub use consistency::check_artifacts;
+
 #[derive(Debug, Clone)]
 pub struct RenderedArtifact {
     pub artifact_type: ArtifactType,
@@ -68,6 +75,7 @@ pub fn render_all(
     payload: &WizardPayload,
     config_toml: String,
 ) -> Vec<RenderedArtifact> {
+    let proxy_site = ProxySite::new(raw, payload);
     vec![
         RenderedArtifact {
             artifact_type: ArtifactType::ConfigToml,
@@ -81,6 +89,18 @@ pub fn render_all(
             language: "nginx".to_string(),
             content: render_nginx_conf(raw, payload),
         },
+        RenderedArtifact {
+            artifact_type: ArtifactType::Caddyfile,
+            file_name: "Caddyfile".to_string(),
+            language: "caddyfile".to_string(),
+            content: caddy::render_caddyfile(raw, &proxy_site),
+        },
+        RenderedArtifact {
+            artifact_type: ArtifactType::TraefikDynamic,
+            file_name: "traefik-dynamic.yaml".to_string(),
+            language: "yaml".to_string(),
+            content: traefik::render_traefik_dynamic(raw, &proxy_site),
+        },
         RenderedArtifact {
             artifact_type: ArtifactType::DockerCompose,
             file_name: "docker-compose.yaml".to_string(),
@@ -100,6 +120,9 @@ pub fn render_all(
             content: render_pm2_config(raw, payload),
         },
     ]
+    .into_iter()
+    .chain(kubernetes::render_kubernetes_artifacts(raw, payload))
+    .collect()
 }
 
 fn render_nginx_conf(raw: &RawConfig, payload: &WizardPayload) -> String {
@@ -232,7 +255,12 @@ log_format google_drive_ext
         ),
     );
 
-    let accel_blocks = render_accel_blocks(raw, payload);
+    let backend_google_access_log = normalize_or_default(
+        &backend_nginx.google_drive_access_log_path,
+        "/var/log/nginx/google_drive_access.log",
+    );
+    let accel_blocks =
+        render_accel_blocks(raw, Some(&backend_google_access_log));
 
     match raw.general.stream_mode {
         crate::config::general::StreamMode::Frontend => format!(
@@ -564,25 +592,133 @@ fn build_backend_resolver_line(
     }
 }
 
-fn render_accel_blocks(raw: &RawConfig, payload: &WizardPayload) -> String {
-    let mut accel_blocks = String::new();
-    let backend_google_access_log = normalize_or_default(
-        &payload.nginx.backend.google_drive_access_log_path,
-        "/var/log/nginx/google_drive_access.log",
-    );
+/// Site values the Caddy and Traefik artifacts share, resolved with the
+/// same defaults as `nginx.conf` for the draft's stream mode.
+struct ProxySite {
+    stream_mode: StreamMode,
+    server_name: String,
+    certificate: String,
+    private_key: String,
+    client_max_body_size: String,
+    /// Backend access log; only the backend mode writes one.
+    access_log: Option<String>,
+    frontend_port: u16,
+    backend_port: u16,
+    emby_port: String,
+    stream_path: String,
+    websocket_pattern: String,
+    static_pattern: String,
+}
 
+impl ProxySite {
+    fn new(raw: &RawConfig, payload: &WizardPayload) -> Self {
+        let frontend_port = raw
+            .frontend
+            .as_ref()
+            .map(|frontend| frontend.listen_port)
+            .unwrap_or(60001);
+        let backend_port = raw
+            .backend
+            .as_ref()
+            .map(|backend| backend.listen_port)
+            .unwrap_or(frontend_port);
+        let frontend_nginx = &payload.nginx.frontend;
+        let backend_nginx = &payload.nginx.backend;
+        let frontend_server_name = normalize_server_name(
+            &frontend_nginx.server_name,
+            "stream.example.com",
+        );
+        let (server_name, certificate, private_key, client_max_body_size) =
+            match raw.general.stream_mode {
+                StreamMode::Backend => (
+                    normalize_server_name(
+                        &backend_nginx.server_name,
+                        &frontend_server_name,
+                    ),
+                    &backend_nginx.ssl_certificate,
+                    &backend_nginx.ssl_certificate_key,
+                    normalize_or_default(
+                        &backend_nginx.client_max_body_size,
+                        "1G",
+                    ),
+                ),
+                StreamMode::Frontend | StreamMode::Dual => (
+                    frontend_server_name,
+                    &frontend_nginx.ssl_certificate,
+                    &frontend_nginx.ssl_certificate_key,
+                    normalize_or_default(
+                        &frontend_nginx.client_max_body_size,
+                        "100M",
+                    ),
+                ),
+            };
+
+        Self {
+            stream_mode: raw.general.stream_mode,
+            server_name,
+            certificate: certificate.trim().to_string(),
+            private_key: private_key.trim().to_string(),
+            client_max_body_size,
+            access_log: (raw.general.stream_mode == StreamMode::Backend).then(
+                || {
+                    normalize_or_default(
+                        &backend_nginx.access_log_path,
+                        "/var/log/nginx/embystream_access.log",
+                    )
+                },
+            ),
+            frontend_port,
+            backend_port,
+            emby_port: normalize_or_default(&raw.emby.port, "8096"),
+            stream_path: raw
+                .backend
+                .as_ref()
+                .map(|backend| backend.path.trim_matches('/'))
+                .filter(|path| !path.is_empty())
+                .unwrap_or("stream")
+                .to_string(),
+            websocket_pattern: normalize_or_default(
+                &frontend_nginx.websocket_location_pattern,
+                r"/(socket|embywebsocket)",
+            ),
+            static_pattern: normalize_or_default(
+                &frontend_nginx.static_location_pattern,
+                r"\.(webp|jpg|jpeg|png|gif|ico|css|js|html)$|Images|fonts",
+            ),
+        }
+    }
+}
+
+/// Internal location a node's `X-Accel-Redirect` responses point at.
+enum AccelTarget {
+    WebDav {
+        node_name: String,
+        node_uuid: String,
+        /// `base_url:port` without a trailing slash.
+        upstream: String,
+        /// Node path prefixed with `/`, or empty.
+        upstream_path: String,
+        host_header: String,
+    },
+    GoogleDrive {
+        node_name: String,
+        root: &'static str,
+        node_uuid: String,
+        query: &'static str,
+    },
+}
+
+fn accel_targets(raw: &RawConfig) -> Vec<AccelTarget> {
+    let mut targets = Vec::new();
     for node in raw.backend_nodes.as_deref().unwrap_or(&[]) {
+        if !node.proxy_mode.eq_ignore_ascii_case("accel_redirect") {
+            continue;
+        }
+
         if let Some(webdav) = node.webdav.as_ref() {
             let node_uuid = webdav.node_uuid.trim();
-            if !node_uuid.is_empty()
-                && node.proxy_mode.eq_ignore_ascii_case("accel_redirect")
-            {
+            if !node_uuid.is_empty() {
                 let path = node.path.trim_matches('/');
-                let upstream_path = if path.is_empty() {
-                    String::new()
-                } else {
-                    format!("/{path}")
-                };
                 let port = if node.port.is_empty() {
                     "80".to_string()
                 } else {
@@ -596,41 +732,93 @@ fn render_accel_blocks(raw: &RawConfig, payload: &WizardPayload) -> String {
                         .trim_end_matches('/'),
                     port
                 );
-                accel_blocks.push_str(&format!(
-                    r#"
+                targets.push(AccelTarget::WebDav {
+                    node_name: node.name.clone(),
+                    node_uuid: node_uuid.to_string(),
+                    upstream: format!(
+                        "{}:{port}",
+                        node.base_url.trim_end_matches('/')
+                    ),
+                    upstream_path: if path.is_empty() {
+                        String::new()
+                    } else {
+                        format!("/{path}")
+                    },
+                    host_header,
+                });
+            }
+        }
+
+        if let Some(google_drive) = node.google_drive.as_ref()
+            && !google_drive.node_uuid.trim().is_empty()
+        {
+            targets.push(AccelTarget::GoogleDrive {
+                node_name: node.name.clone(),
+                root: google_drive.root.as_str(),
+                node_uuid: google_drive.node_uuid.trim().to_string(),
+                // Only Shared Drive and folder roots can reach files that
+                // live outside the account's own My Drive.
+                query: if google_drive.root == GoogleDriveRoot::MyDrive {
+                    "alt=media&acknowledgeAbuse=true"
+                } else {
+                    "alt=media&supportsAllDrives=true&acknowledgeAbuse=true"
+                },
+            });
+        }
+    }
+    targets
+}
+
+/// Internal `accel_redirect` locations for every node that needs one.
+/// `google_drive_access_log` is only usable where the `google_drive_ext`
+/// log format is declared.
+fn render_accel_blocks(
+    raw: &RawConfig,
+    google_drive_access_log: Option<&str>,
+) -> String {
+    let mut accel_blocks = String::new();
+    let google_drive_access_log = google_drive_access_log
+        .map(|path| format!("\n        access_log {path} google_drive_ext;\n"))
+        .unwrap_or_default();
+
+    for target in accel_targets(raw) {
+        match target {
+            AccelTarget::WebDav {
+                node_uuid,
+                upstream,
+                upstream_path,
+                host_header,
+                ..
+            } => accel_blocks.push_str(&format!(
+                r#"
 
     location ^~ /_origin/webdav/{node_uuid}/ {{
         internal;
         rewrite ^/_origin/webdav/{node_uuid}/(.*)$ /$1 break;
-        proxy_pass {base_url}:{port}{upstream_path};
+        proxy_pass {upstream}{upstream_path};
         proxy_http_version 1.1;
         proxy_set_header Host {host_header};
         proxy_set_header Range $http_range;
         proxy_set_header If-Range $http_if_range;
         proxy_buffering off;
         proxy_request_buffering off;
-    }}"#,
-                    base_url = node.base_url.trim_end_matches('/'),
-                    host_header = host_header,
-                ));
-            }
-        }
-
-        if let Some(google_drive) = node.google_drive.as_ref() {
-            if !google_drive.node_uuid.trim().is_empty()
-                && node.proxy_mode.eq_ignore_ascii_case("accel_redirect")
-            {
-                accel_blocks.push_str(&format!(
-                    r#"
-
-    location ~ ^/_origin/google-drive/{node_uuid}/([^/]+)/([^/]+)$ {{
+    }}"#
+            )),
+            AccelTarget::GoogleDrive {
+                node_name,
+                root,
+                node_uuid,
+                query,
+            } => accel_blocks.push_str(&format!(
+                r#"
+
+    # googleDrive node '{node_name}' (root: {root})
+    location ~ ^/_origin/google-drive/{node_uuid}/([^/]+)$ {{
         internal;
-
-        access_log {backend_google_access_log} google_drive_ext;
-
-        set $google_node_uuid $1;
-        set $google_file_id $2;
-        set $google_drive_query "alt=media&supportsAllDrives=true&acknowledgeAbuse=true";
+{google_drive_access_log}
+        set $google_node_uuid {node_uuid};
+        set $google_file_id $1;
+        set $google_drive_query "{query}";
 
         proxy_pass https://www.googleapis.com/drive/v3/files/$google_file_id?$google_drive_query;
         proxy_http_version 1.1;
@@ -648,11 +836,8 @@ fn render_accel_blocks(raw: &RawConfig, payload: &WizardPayload) -> String {
         proxy_send_timeout 3600s;
         proxy_connect_timeout 60s;
         send_timeout 3600s;
-    }}"#,
-                    node_uuid = google_drive.node_uuid.trim(),
-                    backend_google_access_log = backend_google_access_log,
-                ));
-            }
+    }}"#
+            )),
         }
     }
 
@@ -660,23 +845,20 @@ fn render_accel_blocks(raw: &RawConfig, payload: &WizardPayload) -> String {
 }
 
 fn render_docker_compose(raw: &RawConfig) -> String {
-    let mut ports = vec!["      - \"6888:6888\"".to_string()];
-    if let Some(frontend) = raw.frontend.as_ref() {
-        if ports
-            .iter()
-            .all(|entry| !entry.contains(&frontend.listen_port.to_string()))
-        {
-            ports.push(format!("      - \"{0}:{0}\"", frontend.listen_port));
-        }
-    }
-    if let Some(backend) = raw.backend.as_ref() {
-        if ports
-            .iter()
-            .all(|entry| !entry.contains(&backend.listen_port.to_string()))
-        {
-            ports.push(format!("      - \"{0}:{0}\"", backend.listen_port));
+    let mut published = vec![6888];
+    let listen_ports = [
+        raw.frontend.as_ref().map(|frontend| frontend.listen_port),
+        raw.backend.as_ref().map(|backend| backend.listen_port),
+    ];
+    for port in listen_ports.into_iter().flatten() {
+        if !published.contains(&port) {
+            published.push(port);
         }
     }
+    let ports = published
+        .iter()
+        .map(|port| format!("      - \"{port}:{port}\""))
+        .collect::<Vec<_>>();
 
     format!(
         r#"services:
@@ -931,6 +1113,14 @@ fn parse_artifact_type_param(value: &str) -> Option<ArtifactType> {
         "docker_compose" => Some(ArtifactType::DockerCompose),
         "systemd_service" => Some(ArtifactType::SystemdService),
         "pm2_config" => Some(ArtifactType::Pm2Config),
+        "kubernetes_deployment" => Some(ArtifactType::KubernetesDeployment),
+        "kubernetes_service" => Some(ArtifactType::KubernetesService),
+        "kubernetes_config_map" => Some(ArtifactType::KubernetesConfigMap),
+        "kubernetes_secret" => Some(ArtifactType::KubernetesSecret),
+        "kubernetes_ingress" => Some(ArtifactType::KubernetesIngress),
+        "kubernetes_values" => Some(ArtifactType::KubernetesValues),
+        "caddyfile" => Some(ArtifactType::Caddyfile),
+        "traefik_dynamic" => Some(ArtifactType::TraefikDynamic),
         _ => None,
     }
 }
@@ -941,7 +1131,7 @@ mod tests {
         cli_wizard::{
             emit::emit_wizard_config_toml, template_payload::build_template_raw,
         },
-        config::general::StreamMode,
+        config::{backend::GoogleDriveRoot, general::StreamMode},
         web::drafts::wizard_payload_from_raw,
     };
 
@@ -957,7 +1147,7 @@ mod tests {
         let payload = wizard_payload_from_raw(raw.clone());
         let artifacts = render_all(&raw, &payload, config_toml);
 
-        assert_eq!(artifacts.len(), 5);
+        assert_eq!(artifacts.len(), 13);
         assert!(
             artifacts
                 .iter()
@@ -983,6 +1173,25 @@ mod tests {
                 .iter()
                 .any(|artifact| artifact.file_name == "pm2.config.cjs")
         );
+        assert!(artifacts.iter().any(|artifact| {
+            artifact.file_name == "Caddyfile"
+                && artifact.language == "caddyfile"
+        }));
+        for file_name in [
+            "traefik-dynamic.yaml",
+            "k8s-deployment.yaml",
+            "k8s-service.yaml",
+            "k8s-configmap.yaml",
+            "k8s-secret.yaml",
+            "k8s-ingress.yaml",
+            "k8s-values.yaml",
+        ] {
+            let artifact = artifacts
+                .iter()
+                .find(|artifact| artifact.file_name == file_name)
+                .unwrap_or_else(|| panic!("missing {file_name}"));
+            assert_eq!(artifact.language, "yaml");
+        }
     }
 
     #[test]
@@ -1064,6 +1273,34 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn backend_nginx_google_drive_accel_location_follows_node_root() {
+        let mut raw = build_template_raw(StreamMode::Backend);
+        let node = raw
+            .backend_nodes
+            .as_mut()
+            .and_then(|nodes| {
+                nodes.iter_mut().find(|node| node.google_drive.is_some())
+            })
+            .expect("google drive node");
+        node.proxy_mode = "accel_redirect".into();
+        let google_drive = node.google_drive.as_mut().expect("google drive");
+        google_drive.root = GoogleDriveRoot::MyDrive;
+        let node_uuid = google_drive.node_uuid.clone();
+        let payload = wizard_payload_from_raw(raw.clone());
+
+        let nginx = render_nginx_conf(&raw, &payload);
+
+        assert!(nginx.contains(&format!(
+            "location ~ ^/_origin/google-drive/{node_uuid}/([^/]+)$ {{"
+        )));
+        assert!(nginx.contains("(root: my_drive)"));
+        assert!(nginx.contains("set $google_file_id $1;"));
+        assert!(nginx.contains(
+            r#"set $google_drive_query "alt=media&acknowledgeAbuse=true";"#
+        ));
+    }
+
     #[test]
     fn systemd_and_pm2_use_new_default_paths_without_pilipili() {
         let raw = build_template_raw(StreamMode::Backend);
@@ -0,0 +1,272 @@
+//! Traefik v3 dynamic configuration counterpart of `nginx.conf`, loaded
+//! through the file provider. Traefik has no `X-Accel-Redirect` support, so
+//! nodes relying on it are listed instead of routed.
+
+use crate::config::{general::StreamMode, types::RawConfig};
+
+use super::{AccelTarget, ProxySite, accel_targets};
+
+struct Router {
+    name: &'static str,
+    rule: String,
+    middlewares: Vec<&'static str>,
+    service: &'static str,
+    priority: u32,
+}
+
+pub(super) fn render_traefik_dynamic(
+    raw: &RawConfig,
+    site: &ProxySite,
+) -> String {
+    let host = format!("Host(`{}`)", site.server_name);
+    let mut routers = Vec::new();
+    if site.stream_mode != StreamMode::Backend {
+        routers.push(Router {
+            name: "embystream-websocket",
+            rule: format!(
+                "{host} && PathRegexp(`(?i){}`)",
+                site.websocket_pattern
+            ),
+            middlewares: vec!["embystream-headers"],
+            service: "embystream-emby",
+            priority: 300,
+        });
+        routers.push(Router {
+            name: "embystream-static",
+            rule: format!(
+                "{host} && PathRegexp(`(?i){}`)",
+                site.static_pattern
+            ),
+            middlewares: vec!["embystream-headers", "embystream-static-cache"],
+            service: "embystream-frontend",
+            priority: 200,
+        });
+    }
+    match site.stream_mode {
+        StreamMode::Frontend => routers.push(Router {
+            name: "embystream-frontend",
+            rule: host.clone(),
+            middlewares: vec!["embystream-headers"],
+            service: "embystream-frontend",
+            priority: 1,
+        }),
+        StreamMode::Backend => routers.push(Router {
+            name: "embystream-backend",
+            rule: host.clone(),
+            middlewares: vec!["embystream-headers", "embystream-compress"],
+            service: "embystream-backend",
+            priority: 1,
+        }),
+        StreamMode::Dual => {
+            routers.push(Router {
+                name: "embystream-stream",
+                rule: format!("{host} && PathPrefix(`/{}`)", site.stream_path),
+                middlewares: vec!["embystream-headers"],
+                service: "embystream-backend",
+                priority: 100,
+            });
+            routers.push(Router {
+                name: "embystream-frontend",
+                rule: host.clone(),
+                middlewares: vec!["embystream-headers"],
+                service: "embystream-frontend",
+                priority: 1,
+            });
+        }
+    }
+    let routers = routers
+        .iter()
+        .map(|router| {
+            let middlewares = router
+                .middlewares
+                .iter()
+                .map(|middleware| format!("        - {middleware}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                r#"    {name}:
+      rule: {rule}
+      entryPoints:
+        - websecure
+      middlewares:
+{middlewares}
+      service: {service}
+      priority: {priority}
+      tls: {{}}"#,
+                name = router.name,
+                rule = yaml_single_quoted(&router.rule),
+                service = router.service,
+                priority = router.priority,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let (sts_include_subdomains, referrer_policy) = match site.stream_mode {
+        StreamMode::Frontend => (
+            "false",
+            "\n        referrerPolicy: origin-when-cross-origin",
+        ),
+        StreamMode::Backend => ("true", ""),
+        StreamMode::Dual => {
+            ("true", "\n        referrerPolicy: origin-when-cross-origin")
+        }
+    };
+    let sts_seconds = if site.stream_mode == StreamMode::Frontend {
+        15552000
+    } else {
+        31536000
+    };
+    let mut services = Vec::new();
+    if site.stream_mode != StreamMode::Backend {
+        services.push(render_service(
+            "embystream-emby",
+            &format!("http://127.0.0.1:{}", site.emby_port),
+            None,
+        ));
+        services.push(render_service(
+            "embystream-frontend",
+            &format!("http://127.0.0.1:{}", site.frontend_port),
+            None,
+        ));
+    }
+    if site.stream_mode != StreamMode::Frontend {
+        services.push(render_service(
+            "embystream-backend",
+            &format!("http://127.0.0.1:{}", site.backend_port),
+            Some("embystream-stream"),
+        ));
+    }
+    let tls = if site.certificate.is_empty() || site.private_key.is_empty() {
+        "# Routers use Traefik's default certificate; add `certResolver: <name>`\n# under each router's `tls` to issue one through ACME instead.\n".to_string()
+    } else {
+        format!(
+            "tls:\n  certificates:\n    - certFile: {}\n      keyFile: {}\n",
+            yaml_single_quoted(&site.certificate),
+            yaml_single_quoted(&site.private_key),
+        )
+    };
+
+    format!(
+        r#"# Entry points `web` (:80) and `websecure` (:443) come from Traefik's
+# static configuration. Request bodies are not capped by default, so
+# client_max_body_size ({body_size}) has no counterpart here.
+{accel_notice}http:
+  routers:
+    embystream-http:
+      rule: {host}
+      entryPoints:
+        - web
+      middlewares:
+        - embystream-https
+      service: noop@internal
+{routers}
+  middlewares:
+    embystream-https:
+      redirectScheme:
+        scheme: https
+        permanent: true
+    embystream-headers:
+      headers:
+        stsSeconds: {sts_seconds}
+        stsIncludeSubdomains: {sts_include_subdomains}
+        stsPreload: true
+        customFrameOptionsValue: SAMEORIGIN
+        contentTypeNosniff: true
+        browserXssFilter: true{referrer_policy}
+        customResponseHeaders:
+          X-Powered-By: ""
+    embystream-static-cache:
+      headers:
+        customResponseHeaders:
+          Cache-Control: "public, max-age=315360000"
+    embystream-compress:
+      compress: {{}}
+  services:
+{services}
+  serversTransports:
+    embystream-stream:
+      forwardingTimeouts:
+        dialTimeout: 120s
+        responseHeaderTimeout: 1200s
+{tls}"#,
+        body_size = site.client_max_body_size,
+        accel_notice = render_accel_notice(raw),
+        host = yaml_single_quoted(&host),
+        services = services.join("\n"),
+    )
+}
+
+fn render_service(
+    name: &str,
+    url: &str,
+    servers_transport: Option<&str>,
+) -> String {
+    let transport = servers_transport
+        .map(|transport| format!("\n        serversTransport: {transport}"))
+        .unwrap_or_default();
+    format!(
+        "    {name}:\n      loadBalancer:{transport}\n        servers:\n          - url: {}",
+        yaml_single_quoted(url)
+    )
+}
+
+/// Comment naming the nodes whose `accel_redirect` responses Traefik would
+/// hand to the client unresolved.
+fn render_accel_notice(raw: &RawConfig) -> String {
+    let targets = accel_targets(raw);
+    if targets.is_empty() {
+        return String::new();
+    }
+    let nodes = targets
+        .iter()
+        .map(|target| match target {
+            AccelTarget::WebDav { node_name, .. } => {
+                format!("#   - {node_name} (webdav)\n")
+            }
+            AccelTarget::GoogleDrive { node_name, .. } => {
+                format!("#   - {node_name} (googleDrive)\n")
+            }
+        })
+        .collect::<String>();
+    format!(
+        "#\n# Traefik cannot follow X-Accel-Redirect. Switch these nodes to\n# proxy_mode \"proxy\" or \"redirect\" before serving them through it:\n{nodes}"
+    )
+}
+
+/// YAML single-quoted scalar, which keeps regex backslashes literal.
+fn yaml_single_quoted(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli_wizard::template_payload::build_template_raw,
+        config::general::StreamMode,
+        web::{artifacts::ProxySite, drafts::wizard_payload_from_raw},
+    };
+
+    use super::render_traefik_dynamic;
+
+    #[test]
+    fn dual_mode_routes_stream_path_and_lists_accel_nodes() {
+        let raw = build_template_raw(StreamMode::Dual);
+        let mut payload = wizard_payload_from_raw(raw.clone());
+        payload.nginx.frontend.server_name = "media.example.org".into();
+        payload.nginx.frontend.ssl_certificate = "/etc/ssl/media.pem".into();
+        payload.nginx.frontend.ssl_certificate_key =
+            "/etc/ssl/media.key".into();
+
+        let dynamic =
+            render_traefik_dynamic(&raw, &ProxySite::new(&raw, &payload));
+
+        assert!(dynamic.contains(
+            "rule: 'Host(`media.example.org`) && PathPrefix(`/stream`)'"
+        ));
+        assert!(dynamic.contains("PathRegexp(`(?i)/(socket|embywebsocket)`)"));
+        assert!(dynamic.contains("          - url: 'http://127.0.0.1:8096'"));
+        assert!(dynamic.contains("#   - RcloneWebDav (webdav)"));
+        assert!(dynamic.contains("    - certFile: '/etc/ssl/media.pem'"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Admin browsing and export of the `audit_logs` table written by
+//! [`Database::write_audit_log`](super::db::Database::write_audit_log).
+
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::get,
+};
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    admin::require_admin,
+    api::WebAppState,
+    contracts::{AuditLogEntry, AuditLogListResponse},
+    db::AuditLogQuery,
+    error::WebError,
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 200;
+const MAX_EXPORT_ROWS: usize = 100_000;
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/", get(list_audit_logs))
+        .route("/export", get(export_audit_logs))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditLogQueryParams {
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    pub format: Option<String>,
+}
+
+impl AuditLogQueryParams {
+    fn to_query(&self, offset: usize, limit: usize) -> AuditLogQuery {
+        let filter = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(ToString::to_string)
+        };
+        AuditLogQuery {
+            user: filter(&self.user),
+            action: filter(&self.action),
+            target_type: filter(&self.target_type),
+            target_id: filter(&self.target_id),
+            from: self.from,
+            to: self.to,
+            offset,
+            limit,
+        }
+    }
+}
+
+async fn list_audit_logs(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Query(params): Query<AuditLogQueryParams>,
+) -> Result<Json<AuditLogListResponse>, WebError> {
+    let _admin = require_admin(&state, &jar).await?;
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params
+        .page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = (page as usize - 1) * page_size as usize;
+
+    let (items, total) = state
+        .db
+        .list_audit_logs(params.to_query(offset, page_size as usize))
+        .await?;
+    let (actions, target_types) = state.db.audit_log_facets().await?;
+
+    Ok(Json(AuditLogListResponse {
+        items,
+        total,
+        page,
+        page_size,
+        actions,
+        target_types,
+    }))
+}
+
+async fn export_audit_logs(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Query(params): Query<AuditLogQueryParams>,
+) -> Result<impl IntoResponse, WebError> {
+    let admin = require_admin(&state, &jar).await?;
+    let format = params.format.as_deref().unwrap_or("csv");
+    let (content_type, extension) = match format {
+        "csv" => ("text/csv; charset=utf-8", "csv"),
+        "json" => ("application/json", "json"),
+        _ => {
+            return Err(WebError::invalid_input(
+                "format",
+                "Export format must be csv or json.",
+            ));
+        }
+    };
+
+    let (items, total) = state
+        .db
+        .list_audit_logs(params.to_query(0, MAX_EXPORT_ROWS))
+        .await?;
+    let body = if extension == "csv" {
+        render_csv(&items)
+    } else {
+        serde_json::to_string_pretty(&items)
+            .map_err(|error| WebError::internal(error.to_string()))?
+    };
+
+    state
+        .db
+        .write_audit_log(
+            Some(admin.id),
+            "export_audit_logs",
+            "audit_log",
+            None,
+            json!({
+                "format": extension,
+                "rows": items.len(),
+                "truncated": total as usize > items.len(),
+            }),
+        )
+        .await?;
+
+    let file_name = format!(
+        "embystream-audit-{}.{extension}",
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        body,
+    ))
+}
+
+fn render_csv(items: &[AuditLogEntry]) -> String {
+    let mut out = String::from(
+        "created_at,user_id,username,action,target_type,target_id,detail\r\n",
+    );
+    for item in items {
+        let fields = [
+            item.created_at.to_rfc3339(),
+            item.user_id.clone().unwrap_or_default(),
+            item.username.clone().unwrap_or_default(),
+            item.action.clone(),
+            item.target_type.clone(),
+            item.target_id.clone().unwrap_or_default(),
+            item.detail.to_string(),
+        ];
+        let row = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&row);
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Quotes a CSV field when needed, and defuses values a spreadsheet would
+/// otherwise evaluate as a formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::csv_field;
+
+    #[test]
+    fn csv_field_quotes_and_defuses_formulas() {
+        assert_eq!(csv_field("login"), "login");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("{\"k\":\"v\"}"), "\"{\"\"k\"\":\"\"v\"\"}\"");
+        assert_eq!(csv_field("=HYPERLINK(1)"), "'=HYPERLINK(1)");
+    }
+}
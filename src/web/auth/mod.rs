@@ -18,11 +18,14 @@ use std::time::{Duration, Instant};
 
 use super::{
     api::WebAppState,
+    api_tokens::{api_token_user, bearer_token},
     contracts::{
         AuthResponse, ChangeOwnPasswordRequest, LoginRequest, LogoutResponse,
-        RegisterRequest, RegistrationSettingsResponse, SessionUser,
+        RegisterRequest, RegistrationSettingsResponse, SessionUser, UserRole,
     },
+    db::SessionMethod,
     error::WebError,
+    totp::{SecondFactor, verify_second_factor},
 };
 
 pub const SESSION_COOKIE_NAME: &str = "embystream_web_session";
@@ -38,6 +41,8 @@ pub fn routes() -> axum::Router<WebAppState> {
         .route("/logout", post(logout))
         .route("/password", patch(change_own_password))
         .route("/me", get(current_user))
+        .nest("/oidc", super::oidc::routes())
+        .nest("/totp", super::totp::routes())
 }
 
 async fn get_registration_settings(
@@ -55,7 +60,7 @@ pub fn hash_password(password: &str) -> Result<String, WebError> {
         .to_string())
 }
 
-fn verify_password(
+pub(crate) fn verify_password(
     password: &str,
     password_hash: &str,
 ) -> Result<bool, WebError> {
@@ -66,10 +71,32 @@ fn verify_password(
         .is_ok())
 }
 
+/// The signed-in user: the API token owner when the request carried a
+/// bearer token, otherwise the cookie session's user.
 pub async fn session_user_from_jar(
     state: &WebAppState,
     jar: &CookieJar,
 ) -> Result<SessionUser, WebError> {
+    let (user, totp_enrollment_required) =
+        enrolling_session_user(state, jar).await?;
+    if totp_enrollment_required {
+        return Err(WebError::EnrollmentRequired(
+            "Enable two-factor authentication to continue.",
+        ));
+    }
+    Ok(user)
+}
+
+/// Like [`session_user_from_jar`], but also admits admins who still have
+/// to enroll TOTP, so they can reach the enrollment endpoints.
+pub(crate) async fn enrolling_session_user(
+    state: &WebAppState,
+    jar: &CookieJar,
+) -> Result<(SessionUser, bool), WebError> {
+    if let Some(user) = api_token_user() {
+        return Ok((user, false));
+    }
+
     let session_cookie = jar
         .get(SESSION_COOKIE_NAME)
         .ok_or(WebError::Unauthorized("Session is required."))?;
@@ -80,14 +107,18 @@ pub async fn session_user_from_jar(
         .await?
         .ok_or(WebError::Unauthorized("Session is not valid."))?;
 
-    Ok(session.user)
+    Ok((session.user, session.totp_enrollment_required))
 }
 
 pub async fn enforce_same_origin(
     request: Request,
     next: Next,
 ) -> Result<Response, WebError> {
-    if !is_unsafe_method(request.method()) {
+    // Browsers cannot attach a bearer token cross-site on their own, and
+    // invalid tokens are rejected before any handler runs.
+    if !is_unsafe_method(request.method())
+        || bearer_token(request.headers()).is_some()
+    {
         return Ok(next.run(request).await);
     }
 
@@ -115,7 +146,7 @@ async fn register(
             payload.username.trim().to_string(),
             email,
             password_hash,
-            super::contracts::UserRole::User,
+            UserRole::User,
         )
         .await?;
 
@@ -130,7 +161,10 @@ async fn register(
         )
         .await?;
 
-    Ok(Json(AuthResponse { user }))
+    Ok(Json(AuthResponse {
+        user,
+        totp_enrollment_required: false,
+    }))
 }
 
 async fn login(
@@ -165,6 +199,32 @@ async fn login(
         return Err(WebError::Unauthorized("Invalid credentials."));
     }
 
+    let totp_secret = state.db.totp_state(user_row.id.clone()).await?.secret;
+    let second_factor = match (
+        totp_secret.as_deref(),
+        normalize_optional_string(payload.totp_code),
+    ) {
+        (None, _) => None,
+        (Some(_), None) => {
+            return Err(WebError::SecondFactorRequired(
+                "Enter the code from your authenticator app.",
+            ));
+        }
+        (Some(secret), Some(code)) => {
+            let Some(method) =
+                verify_second_factor(&state, &user_row.id, secret, &code)
+                    .await?
+            else {
+                record_login_failure(&state, &throttle_key);
+                return Err(WebError::Unauthorized("Invalid two-factor code."));
+            };
+            Some(method)
+        }
+    };
+    let totp_enrollment_required = user_row.role == UserRole::Admin
+        && totp_secret.is_none()
+        && state.db.totp_required_for_admins().await?;
+
     let user = SessionUser {
         id: user_row.id,
         username: user_row.username,
@@ -176,6 +236,7 @@ async fn login(
         .db
         .create_session(
             user.clone(),
+            SessionMethod::Password,
             headers
                 .get(axum::http::header::USER_AGENT)
                 .and_then(|value| value.to_str().ok())
@@ -191,13 +252,23 @@ async fn login(
             "login",
             "session",
             Some(session_id.clone()),
-            json!({ "username": user.username, "at": Utc::now() }),
+            json!({
+                "username": user.username,
+                "at": Utc::now(),
+                "second_factor": second_factor.map(SecondFactor::as_str),
+            }),
         )
         .await?;
 
     clear_login_failures(&state, &throttle_key);
     let cookie = build_session_cookie(&session_id, &headers);
-    Ok((jar.add(cookie), Json(AuthResponse { user })))
+    Ok((
+        jar.add(cookie),
+        Json(AuthResponse {
+            user,
+            totp_enrollment_required,
+        }),
+    ))
 }
 
 async fn logout(
@@ -240,8 +311,12 @@ async fn current_user(
     State(state): State<WebAppState>,
     jar: CookieJar,
 ) -> Result<Json<AuthResponse>, WebError> {
-    let user = session_user_from_jar(&state, &jar).await?;
-    Ok(Json(AuthResponse { user }))
+    let (user, totp_enrollment_required) =
+        enrolling_session_user(&state, &jar).await?;
+    Ok(Json(AuthResponse {
+        user,
+        totp_enrollment_required,
+    }))
 }
 
 async fn change_own_password(
@@ -326,7 +401,7 @@ fn normalize_optional_string(value: Option<String>) -> Option<String> {
         .filter(|item| !item.is_empty())
 }
 
-fn build_session_cookie(
+pub(crate) fn build_session_cookie(
     session_id: &str,
     headers: &HeaderMap,
 ) -> axum_extra::extract::cookie::Cookie<'static> {
@@ -395,7 +470,7 @@ fn validate_source_url(url: &str, expected_host: &str) -> Result<(), WebError> {
     Ok(())
 }
 
-fn request_uses_https(headers: &HeaderMap) -> bool {
+pub(crate) fn request_uses_https(headers: &HeaderMap) -> bool {
     if headers
         .get("x-forwarded-proto")
         .and_then(|value| value.to_str().ok())
@@ -427,6 +502,24 @@ fn request_uses_https(headers: &HeaderMap) -> bool {
         .unwrap_or(false)
 }
 
+/// `scheme://host` the browser used to reach the studio, honoring
+/// reverse proxy forwarding headers.
+pub(crate) fn request_origin(headers: &HeaderMap) -> Option<String> {
+    let host = headers
+        .get("x-forwarded-host")
+        .or_else(|| headers.get(header::HOST))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    let scheme = if request_uses_https(headers) {
+        "https"
+    } else {
+        "http"
+    };
+    Some(format!("{scheme}://{host}"))
+}
+
 fn login_throttle_key(headers: &HeaderMap) -> String {
     headers
         .get("x-forwarded-for")
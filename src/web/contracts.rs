@@ -6,8 +6,10 @@ use crate::config::{
     frontend::Frontend,
     general::{Emby, Log, StreamMode, UserAgent},
     http2::Http2,
+    secrets::SecretsConfig,
     types::FallbackConfig,
 };
+use crate::core::backend::resilience::BreakerSnapshot;
 use crate::oauthutil::OAuthToken;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -41,6 +43,21 @@ pub enum ArtifactType {
     DockerCompose,
     SystemdService,
     Pm2Config,
+    KubernetesDeployment,
+    KubernetesService,
+    KubernetesConfigMap,
+    KubernetesSecret,
+    KubernetesIngress,
+    KubernetesValues,
+    Caddyfile,
+    TraefikDynamic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentKind {
+    Deploy,
+    Rollback,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -73,6 +90,9 @@ pub struct UserAdminSummary {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub user: SessionUser,
+    /// Admin policy requires this user to enroll TOTP before anything else.
+    #[serde(default)]
+    pub totp_enrollment_required: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -80,6 +100,13 @@ pub struct RegistrationSettingsResponse {
     pub registration_enabled: bool,
 }
 
+/// Whether the login page offers single sign-on, and under which name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OidcProviderResponse {
+    pub enabled: bool,
+    pub display_name: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
@@ -91,6 +118,9 @@ pub struct RegisterRequest {
 pub struct LoginRequest {
     pub login: String,
     pub password: String,
+    /// TOTP or recovery code for accounts with two-factor authentication.
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -143,6 +173,27 @@ pub struct CreateDraftRequest {
     pub stream_mode: WizardStreamMode,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportDraftRequest {
+    #[serde(default)]
+    pub name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportRunningConfigRequest {
+    #[serde(default)]
+    pub name: String,
+}
+
+/// `warnings` lists the config keys the studio could not carry into the
+/// draft, such as `Disk` or `BackendNode[0].legacy_key`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportDraftResponse {
+    pub draft: DraftSummary,
+    pub warnings: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SaveDraftRequest {
     pub name: String,
@@ -162,6 +213,55 @@ pub struct DraftRevision {
     pub server_revision: u64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DraftRevisionSummary {
+    pub revision: u64,
+    pub name: String,
+    pub stream_mode: WizardStreamMode,
+    pub author: Option<String>,
+    pub restored_from: Option<u64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DraftRevisionListResponse {
+    pub current_revision: u64,
+    pub items: Vec<DraftRevisionSummary>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DraftChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One leaf value that differs; `path` is relative to its section, e.g.
+/// `path_rewrites[0].pattern`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DraftFieldChange {
+    pub path: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Changes within one config section. Backend nodes are their own sections,
+/// keyed by name as `BackendNode:<name>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DraftSectionDiff {
+    pub section: String,
+    pub change: DraftChangeKind,
+    pub fields: Vec<DraftFieldChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DraftRevisionDiffResponse {
+    pub from_revision: u64,
+    pub to_revision: u64,
+    pub sections: Vec<DraftSectionDiff>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConfigSetSummary {
     pub id: String,
@@ -176,6 +276,35 @@ pub struct ConfigSetListResponse {
     pub items: Vec<ConfigSetSummary>,
 }
 
+/// One write of the running gateway's `config.toml` from the Web Studio.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentSummary {
+    pub id: String,
+    pub kind: DeploymentKind,
+    pub config_set_id: Option<String>,
+    pub config_set_name: String,
+    pub config_path: String,
+    pub deployed_by: Option<String>,
+    pub has_backup: bool,
+    pub reloaded: bool,
+    pub restart_required: Vec<String>,
+    pub rollback_of: Option<String>,
+    pub rolled_back_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentEnvelope {
+    pub deployment: DeploymentSummary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentListResponse {
+    pub config_path: Option<String>,
+    pub live_reload: bool,
+    pub items: Vec<DeploymentSummary>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConfigSetEnvelope {
     pub config_set: ConfigSetSummary,
@@ -195,15 +324,49 @@ pub struct ArtifactDocument {
     pub content: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactIssueSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactIssueCode {
+    PortCollision,
+    PortNotPublished,
+    UpstreamPortMismatch,
+    EmbyNotLocal,
+    AccelLocationMissing,
+    AccelLocationStale,
+    AccelUnsupported,
+    TlsPathNotMounted,
+    StreamRelayLoop,
+}
+
+/// A place where one generated artifact disagrees with `config.toml` or
+/// with another artifact of the same config set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactIssue {
+    pub severity: ArtifactIssueSeverity,
+    pub code: ArtifactIssueCode,
+    pub artifact_type: ArtifactType,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ArtifactListResponse {
     pub items: Vec<ArtifactDocument>,
+    #[serde(default)]
+    pub issues: Vec<ArtifactIssue>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GenerateDraftResponse {
     pub config_set: ConfigSetSummary,
     pub artifacts: Vec<ArtifactSummary>,
+    pub issues: Vec<ArtifactIssue>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -235,6 +398,30 @@ pub struct LogListResponse {
     pub next_cursor: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub user_id: Option<String>,
+    /// `None` for CLI actions and users deleted since.
+    pub username: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub detail: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogListResponse {
+    pub items: Vec<AuditLogEntry>,
+    pub total: u64,
+    pub page: u32,
+    pub page_size: u32,
+    /// Distinct values recorded so far, for the filter pickers.
+    pub actions: Vec<String>,
+    pub target_types: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UserListResponse {
     pub items: Vec<UserAdminSummary>,
@@ -265,12 +452,125 @@ pub struct UpdateRegistrationSettingsRequest {
     pub registration_enabled: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwoFactorPolicyResponse {
+    pub required_for_admins: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateTwoFactorPolicyRequest {
+    pub required_for_admins: bool,
+}
+
+/// `None` keeps audit entries forever.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRetentionResponse {
+    pub retention_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateAuditRetentionRequest {
+    pub retention_days: Option<u32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ChangeOwnPasswordRequest {
     pub current_password: String,
     pub new_password: String,
 }
 
+/// What a personal API token may call. `admin` covers every other scope.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum ApiTokenScope {
+    #[serde(rename = "drafts:read")]
+    DraftsRead,
+    #[serde(rename = "drafts:write")]
+    DraftsWrite,
+    #[serde(rename = "logs:read")]
+    LogsRead,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiTokenScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DraftsRead => "drafts:read",
+            Self::DraftsWrite => "drafts:write",
+            Self::LogsRead => "logs:read",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiTokenSummary {
+    pub id: String,
+    pub name: String,
+    /// First characters of the secret, enough to tell tokens apart.
+    pub token_prefix: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiTokenListResponse {
+    pub items: Vec<ApiTokenSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<ApiTokenScope>,
+    /// Never expires when absent.
+    #[serde(default)]
+    pub expires_in_days: Option<u32>,
+}
+
+/// The secret is only returned here; the studio keeps its hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateApiTokenResponse {
+    pub token: ApiTokenSummary,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpStatusResponse {
+    pub enabled: bool,
+    /// Whether admin policy forbids turning it off for this account.
+    pub required: bool,
+    pub recovery_codes_remaining: u32,
+}
+
+/// A secret waiting for its first code; it replaces the active one only
+/// once confirmed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_svg: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisableTotpRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpRecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SystemMetricsResponse {
     pub cpu_usage_percent: f64,
@@ -284,6 +584,14 @@ pub struct SystemMetricsResponse {
     pub uptime_seconds: u64,
 }
 
+/// Circuit breaker state of upstream nodes. `available` is false when the
+/// stream service does not run in this process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreakerListResponse {
+    pub available: bool,
+    pub nodes: Vec<BreakerSnapshot>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ApiErrorDetail {
     pub code: String,
@@ -297,6 +605,18 @@ pub struct ApiErrorResponse {
     pub error: ApiErrorDetail,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoogleOAuthStartRequest {
+    pub draft_id: String,
+    pub node_uuid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoogleOAuthStartResponse {
+    pub authorize_url: String,
+    pub redirect_uri: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MetadataUpdateRequest {
     pub name: String,
@@ -317,6 +637,8 @@ pub struct WizardSharedPayload {
     pub user_agent: UserAgent,
     pub fallback: FallbackConfig,
     pub http2: Http2,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SecretsConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -347,6 +669,8 @@ pub struct WizardDeploymentPayload {
     pub systemd: WizardSystemdPayload,
     #[serde(default)]
     pub pm2: WizardPm2Payload,
+    #[serde(default)]
+    pub kubernetes: WizardKubernetesPayload,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -373,6 +697,18 @@ pub struct WizardPm2Payload {
     pub error_file: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WizardKubernetesPayload {
+    #[serde(default)]
+    pub namespace: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub ingress_class: String,
+    #[serde(default)]
+    pub tls_secret_name: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WizardFrontendNginxPayload {
     #[serde(default)]
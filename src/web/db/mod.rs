@@ -16,9 +16,11 @@ use crate::log_stream::global_log_stream;
 use super::{
     artifacts::RenderedArtifact,
     contracts::{
-        ArtifactListResponse, ConfigSetSummary, DraftDocument, DraftRevision,
-        DraftStatus, DraftSummary, GenerateDraftResponse, LogEntry,
-        LogListResponse, LoginBackgroundResponse, SessionUser,
+        ApiTokenScope, ApiTokenSummary, ArtifactIssue, ArtifactListResponse,
+        AuditLogEntry, ConfigSetSummary, DeploymentKind, DeploymentSummary,
+        DraftDocument, DraftRevision, DraftRevisionListResponse,
+        DraftRevisionSummary, DraftStatus, DraftSummary, GenerateDraftResponse,
+        LogEntry, LogListResponse, LoginBackgroundResponse, SessionUser,
         UserAdminSummary, UserRole, WizardPayload, WizardStreamMode,
     },
     error::WebError,
@@ -27,6 +29,9 @@ use super::{
 const DB_FILE_NAME: &str = "web-config-studio.sqlite3";
 const SESSION_TTL_HOURS: i64 = 24 * 14;
 const REGISTRATION_ENABLED_KEY: &str = "registration_enabled";
+const TOTP_REQUIRED_FOR_ADMINS_KEY: &str = "totp_required_for_admins";
+const AUDIT_RETENTION_DAYS_KEY: &str = "audit_retention_days";
+const DEPLOYMENTS_DIR_NAME: &str = "deployments";
 
 #[derive(Debug, Clone)]
 pub struct BootstrapAdmin {
@@ -55,6 +60,54 @@ pub struct UserRow {
 pub struct SessionRow {
     pub id: String,
     pub user: SessionUser,
+    /// An admin without TOTP while the admin policy requires it. SSO
+    /// sessions are exempt: the identity provider owns their second factor.
+    pub totp_enrollment_required: bool,
+}
+
+/// How a session was signed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionMethod {
+    Password,
+    Oidc,
+}
+
+impl SessionMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Password => "password",
+            Self::Oidc => "oidc",
+        }
+    }
+}
+
+/// Two-factor state of one account.
+#[derive(Debug, Clone, Default)]
+pub struct TotpState {
+    pub secret: Option<String>,
+    pub pending_secret: Option<String>,
+    pub recovery_codes_remaining: u32,
+}
+
+/// A live API token and the account it acts for.
+#[derive(Debug, Clone)]
+pub struct ApiTokenRow {
+    pub id: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub user: SessionUser,
+    /// Same policy as [`SessionRow::totp_enrollment_required`]; tokens of
+    /// accounts linked to an SSO identity are exempt.
+    pub totp_enrollment_required: bool,
+}
+
+#[derive(Debug)]
+pub struct CreateApiTokenInput {
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub token_hash: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone)]
@@ -72,6 +125,36 @@ pub struct PersistGeneratedConfigInput {
     pub stream_mode: WizardStreamMode,
     pub config_toml: String,
     pub artifacts: Vec<RenderedArtifact>,
+    pub issues: Vec<ArtifactIssue>,
+}
+
+#[derive(Debug)]
+pub struct RecordDeploymentInput {
+    pub id: String,
+    pub user_id: String,
+    pub kind: DeploymentKind,
+    pub config_set_id: Option<String>,
+    pub config_set_name: String,
+    pub config_path: String,
+    pub has_backup: bool,
+    pub reloaded: bool,
+    pub restart_required: Vec<String>,
+    pub rollback_of: Option<String>,
+}
+
+/// Filters for browsing `audit_logs`; `None` fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogQuery {
+    /// A user id or username.
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    /// Inclusive upper bound.
+    pub to: Option<DateTime<Utc>>,
+    pub offset: usize,
+    pub limit: usize,
 }
 
 impl Default for LogsQuery {
@@ -98,6 +181,13 @@ impl Database {
         self.data_dir().join(DB_FILE_NAME)
     }
 
+    /// Copy of the config file a deployment replaced.
+    pub fn deployment_backup_path(&self, deployment_id: &str) -> PathBuf {
+        self.data_dir()
+            .join(DEPLOYMENTS_DIR_NAME)
+            .join(format!("{deployment_id}.toml"))
+    }
+
     pub async fn initialize(&self) -> Result<Option<BootstrapAdmin>, WebError> {
         let db = self.clone();
         task::spawn_blocking(move || db.initialize_blocking()).await?
@@ -164,7 +254,7 @@ impl Database {
         let db = self.clone();
         task::spawn_blocking(move || {
             let conn = db.open_connection()?;
-            read_registration_enabled(&conn)
+            read_enabled_setting(&conn, REGISTRATION_ENABLED_KEY)
         })
         .await?
     }
@@ -176,22 +266,34 @@ impl Database {
         let db = self.clone();
         task::spawn_blocking(move || {
             let conn = db.open_connection()?;
-            conn.execute(
-                "INSERT INTO app_settings (key, value_json, updated_at)
-                 VALUES (?1, ?2, ?3)
-                 ON CONFLICT(key) DO UPDATE SET
-                    value_json = excluded.value_json,
-                    updated_at = excluded.updated_at",
-                params![
-                    REGISTRATION_ENABLED_KEY,
-                    serde_json::to_string(&json!({ "enabled": enabled }))
-                        .map_err(|error| WebError::internal(
-                            error.to_string()
-                        ))?,
-                    Utc::now().to_rfc3339()
-                ],
+            write_enabled_setting(&conn, REGISTRATION_ENABLED_KEY, enabled)?;
+            read_enabled_setting(&conn, REGISTRATION_ENABLED_KEY)
+        })
+        .await?
+    }
+
+    pub async fn totp_required_for_admins(&self) -> Result<bool, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            read_totp_required_for_admins(&conn)
+        })
+        .await?
+    }
+
+    pub async fn set_totp_required_for_admins(
+        &self,
+        required: bool,
+    ) -> Result<bool, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            write_enabled_setting(
+                &conn,
+                TOTP_REQUIRED_FOR_ADMINS_KEY,
+                required,
             )?;
-            read_registration_enabled(&conn)
+            read_totp_required_for_admins(&conn)
         })
         .await?
     }
@@ -265,6 +367,7 @@ impl Database {
     pub async fn create_session(
         &self,
         user: SessionUser,
+        method: SessionMethod,
         user_agent: Option<String>,
         ip_address: Option<String>,
     ) -> Result<String, WebError> {
@@ -278,8 +381,9 @@ impl Database {
 
             conn.execute(
                 "INSERT INTO sessions (
-                    id, user_id, expires_at, created_at, last_seen_at, user_agent, ip_address
-                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    id, user_id, expires_at, created_at, last_seen_at, user_agent, ip_address,
+                    auth_method
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
                 params![
                     session_id,
                     user.id,
@@ -287,7 +391,8 @@ impl Database {
                     now.to_rfc3339(),
                     now.to_rfc3339(),
                     user_agent,
-                    ip_address
+                    ip_address,
+                    method.as_str()
                 ],
             )?;
             Ok(session_id)
@@ -311,7 +416,8 @@ impl Database {
                         u.username,
                         u.email,
                         u.role,
-                        u.disabled
+                        u.disabled,
+                        u.totp_secret IS NOT NULL OR s.auth_method = 'oidc'
                      FROM sessions s
                      JOIN users u ON u.id = s.user_id
                      WHERE s.id = ?1
@@ -331,12 +437,15 @@ impl Database {
                                 )?,
                             },
                             row.get::<_, i64>(6)? != 0,
+                            row.get::<_, bool>(7)?,
                         ))
                     },
                 )
                 .optional()?;
 
-            let Some((session_id, expires_at, user, disabled)) = session else {
+            let Some((session_id, expires_at, user, disabled, second_factor)) =
+                session
+            else {
                 return Ok(None);
             };
 
@@ -365,9 +474,14 @@ impl Database {
                 params![session_id, Utc::now().to_rfc3339()],
             )?;
 
+            let totp_enrollment_required = user.role == UserRole::Admin
+                && !second_factor
+                && read_totp_required_for_admins(&conn)?;
+
             Ok(Some(SessionRow {
                 id: session_id,
                 user,
+                totp_enrollment_required,
             }))
         })
         .await?
@@ -570,10 +684,11 @@ impl Database {
         let db = self.clone();
         let user_id = user_id.to_string();
         task::spawn_blocking(move || {
-            let conn = db.open_connection()?;
+            let mut conn = db.open_connection()?;
+            let tx = conn.transaction()?;
             let draft_id = Uuid::new_v4().to_string();
             let now = Utc::now().to_rfc3339();
-            conn.execute(
+            tx.execute(
                 "INSERT INTO drafts (
                     id, user_id, name, status, stream_mode, wizard_version, payload_json,
                     generated_config_toml, created_at, updated_at, last_opened_at
@@ -588,6 +703,8 @@ impl Database {
                     now
                 ],
             )?;
+            snapshot_draft_revision(&tx, &draft_id, None)?;
+            tx.commit()?;
 
             Ok(DraftSummary {
                 id: draft_id,
@@ -711,9 +828,10 @@ impl Database {
         let user_id = user_id.to_string();
         let draft_id = draft_id.to_string();
         task::spawn_blocking(move || {
-            let conn = db.open_connection()?;
+            let mut conn = db.open_connection()?;
+            let tx = conn.transaction()?;
             let now = Utc::now().to_rfc3339();
-            let updated = conn.execute(
+            let updated = tx.execute(
                 "UPDATE drafts
                  SET name = ?3, payload_json = ?4, stream_mode = ?5,
                      updated_at = ?6, wizard_version = wizard_version + 1
@@ -734,24 +852,130 @@ impl Database {
                 return Err(WebError::NotFound("Draft was not found."));
             }
 
-            conn.query_row(
-                "SELECT updated_at, wizard_version
-                 FROM drafts
-                 WHERE id = ?1 AND user_id = ?2
-                 LIMIT 1",
-                params![draft_id, user_id],
-                |row| {
-                    Ok(DraftRevision {
-                        id: draft_id.clone(),
-                        updated_at: parse_rfc3339_to_utc(
-                            row.get::<_, String>(0)?,
-                            0,
-                        )?,
-                        server_revision: row.get::<_, u64>(1)?,
-                    })
-                },
-            )
-            .map_err(WebError::from)
+            let revision = snapshot_draft_revision(&tx, &draft_id, None)?;
+            tx.commit()?;
+            Ok(revision)
+        })
+        .await?
+    }
+
+    pub async fn list_draft_revisions(
+        &self,
+        user_id: &str,
+        draft_id: &str,
+    ) -> Result<Option<DraftRevisionListResponse>, WebError> {
+        let db = self.clone();
+        let user_id = user_id.to_string();
+        let draft_id = draft_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let current_revision = conn
+                .query_row(
+                    "SELECT wizard_version FROM drafts
+                     WHERE id = ?1 AND user_id = ?2
+                     LIMIT 1",
+                    params![draft_id, user_id],
+                    |row| row.get::<_, u64>(0),
+                )
+                .optional()?;
+            let Some(current_revision) = current_revision else {
+                return Ok(None);
+            };
+
+            let mut stmt = conn.prepare(
+                "SELECT r.revision, r.name, r.stream_mode, u.username,
+                        r.restored_from, r.created_at
+                 FROM draft_revisions r
+                 LEFT JOIN users u ON u.id = r.user_id
+                 WHERE r.draft_id = ?1
+                 ORDER BY r.revision DESC",
+            )?;
+            let rows = stmt.query_map(params![draft_id], |row| {
+                Ok(DraftRevisionSummary {
+                    revision: row.get(0)?,
+                    name: row.get(1)?,
+                    stream_mode: map_stream_mode_value(
+                        row.get::<_, String>(2)?,
+                        2,
+                    )?,
+                    author: row.get(3)?,
+                    restored_from: row.get(4)?,
+                    created_at: parse_rfc3339_to_utc(
+                        row.get::<_, String>(5)?,
+                        5,
+                    )?,
+                })
+            })?;
+
+            let mut items = Vec::new();
+            for row in rows {
+                items.push(row?);
+            }
+            Ok(Some(DraftRevisionListResponse {
+                current_revision,
+                items,
+            }))
+        })
+        .await?
+    }
+
+    /// Returns the name and payload a draft had at `revision`.
+    pub async fn get_draft_revision(
+        &self,
+        user_id: &str,
+        draft_id: &str,
+        revision: u64,
+    ) -> Result<Option<(String, WizardPayload)>, WebError> {
+        let db = self.clone();
+        let user_id = user_id.to_string();
+        let draft_id = draft_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            query_draft_revision(&conn, &user_id, &draft_id, revision)
+        })
+        .await?
+    }
+
+    /// Makes `revision` the draft's current document, recorded as a new
+    /// revision that points back at the one it restored.
+    pub async fn restore_draft_revision(
+        &self,
+        user_id: &str,
+        draft_id: &str,
+        revision: u64,
+    ) -> Result<Option<DraftRevision>, WebError> {
+        let db = self.clone();
+        let user_id = user_id.to_string();
+        let draft_id = draft_id.to_string();
+        task::spawn_blocking(move || {
+            let mut conn = db.open_connection()?;
+            let tx = conn.transaction()?;
+            let Some((name, payload)) =
+                query_draft_revision(&tx, &user_id, &draft_id, revision)?
+            else {
+                return Ok(None);
+            };
+
+            tx.execute(
+                "UPDATE drafts
+                 SET name = ?3, payload_json = ?4, stream_mode = ?5,
+                     updated_at = ?6, wizard_version = wizard_version + 1
+                 WHERE id = ?1 AND user_id = ?2",
+                params![
+                    draft_id,
+                    user_id,
+                    name,
+                    serde_json::to_string(&payload).map_err(|error| {
+                        WebError::internal(error.to_string())
+                    })?,
+                    payload.stream_mode.as_db_value(),
+                    Utc::now().to_rfc3339()
+                ],
+            )?;
+            let restored =
+                snapshot_draft_revision(&tx, &draft_id, Some(revision))?;
+            tx.commit()?;
+            Ok(Some(restored))
         })
         .await?
     }
@@ -841,12 +1065,14 @@ impl Database {
             let now = Utc::now().to_rfc3339();
             let payload_json = serde_json::to_string(&input.payload)
                 .map_err(|error| WebError::internal(error.to_string()))?;
+            let issues_json = serde_json::to_string(&input.issues)
+                .map_err(|error| WebError::internal(error.to_string()))?;
 
             tx.execute(
                 "INSERT INTO config_sets (
                     id, user_id, source_draft_id, name, stream_mode, payload_json,
-                    created_at, updated_at
-                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+                    issues_json, created_at, updated_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
                 params![
                     config_set_id,
                     input.user_id,
@@ -854,6 +1080,7 @@ impl Database {
                     input.draft_name,
                     input.stream_mode.as_db_value(),
                     payload_json,
+                    issues_json,
                     now
                 ],
             )?;
@@ -908,6 +1135,7 @@ impl Database {
                     .into_iter()
                     .map(|artifact| artifact.summary())
                     .collect(),
+                issues: input.issues,
             })
         })
         .await?
@@ -964,16 +1192,18 @@ impl Database {
         let config_set_id = config_set_id.to_string();
         task::spawn_blocking(move || {
             let conn = db.open_connection()?;
-            let exists = conn
+            let Some(issues_json) = conn
                 .query_row(
-                    "SELECT id FROM config_sets WHERE id = ?1 AND user_id = ?2 LIMIT 1",
+                    "SELECT issues_json FROM config_sets WHERE id = ?1 AND user_id = ?2 LIMIT 1",
                     params![config_set_id, user_id],
                     |row| row.get::<_, String>(0),
                 )
-                .optional()?;
-            if exists.is_none() {
+                .optional()?
+            else {
                 return Ok(None);
-            }
+            };
+            let issues = serde_json::from_str(&issues_json)
+                .map_err(|error| WebError::internal(error.to_string()))?;
 
             let mut stmt = conn.prepare(
                 "SELECT artifact_type, file_name, content
@@ -996,7 +1226,7 @@ impl Database {
             for row in rows {
                 items.push(row?);
             }
-            Ok(Some(ArtifactListResponse { items }))
+            Ok(Some(ArtifactListResponse { items, issues }))
         })
         .await?
     }
@@ -1010,7 +1240,7 @@ impl Database {
         let user_id = user_id.to_string();
         let config_set_id = config_set_id.to_string();
         task::spawn_blocking(move || {
-            let conn = db.open_connection()?;
+            let mut conn = db.open_connection()?;
             let source = conn
                 .query_row(
                     "SELECT name, stream_mode, payload_json
@@ -1047,7 +1277,8 @@ impl Database {
             let draft_id = Uuid::new_v4().to_string();
             let now = Utc::now().to_rfc3339();
             let draft_name = format!("{name} copy");
-            conn.execute(
+            let tx = conn.transaction()?;
+            tx.execute(
                 "INSERT INTO drafts (
                     id, user_id, name, status, stream_mode, wizard_version, payload_json,
                     generated_config_toml, created_at, updated_at, last_opened_at
@@ -1062,6 +1293,8 @@ impl Database {
                     now
                 ],
             )?;
+            snapshot_draft_revision(&tx, &draft_id, None)?;
+            tx.commit()?;
 
             Ok(Some(DraftSummary {
                 id: draft_id,
@@ -1245,82 +1478,714 @@ impl Database {
         .await?
     }
 
-    pub async fn list_logs(
+    pub async fn record_deployment(
         &self,
-        query: LogsQuery,
-    ) -> Result<LogListResponse, WebError> {
+        input: RecordDeploymentInput,
+    ) -> Result<DeploymentSummary, WebError> {
         let db = self.clone();
         task::spawn_blocking(move || {
-            let conn = db.open_connection()?;
-            let mut items = Vec::new();
+            let mut conn = db.open_connection()?;
+            let tx = conn.transaction()?;
+            let now = Utc::now().to_rfc3339();
+            let restart_required_json =
+                serde_json::to_string(&input.restart_required)
+                    .map_err(|error| WebError::internal(error.to_string()))?;
 
-            if query
-                .source
-                .as_deref()
-                .map(|value| value == "runtime")
-                .unwrap_or(false)
-            {
-                return Ok(LogListResponse {
-                    items,
-                    next_cursor: None,
-                });
+            tx.execute(
+                "INSERT INTO deployments (
+                    id, user_id, kind, config_set_id, config_set_name, config_path,
+                    has_backup, reloaded, restart_required_json, rollback_of,
+                    rolled_back_at, created_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, NULL, ?11)",
+                params![
+                    input.id,
+                    input.user_id,
+                    input.kind.as_db_value(),
+                    input.config_set_id,
+                    input.config_set_name,
+                    input.config_path,
+                    input.has_backup as i64,
+                    input.reloaded as i64,
+                    restart_required_json,
+                    input.rollback_of,
+                    now
+                ],
+            )?;
+
+            if let Some(rollback_of) = input.rollback_of.as_ref() {
+                tx.execute(
+                    "UPDATE deployments SET rolled_back_at = ?2 WHERE id = ?1",
+                    params![rollback_of, now],
+                )?;
             }
 
+            let summary = query_deployment(&tx, &input.id)?
+                .ok_or(WebError::internal("Deployment was not recorded."))?;
+            tx.commit()?;
+            Ok(summary)
+        })
+        .await?
+    }
+
+    /// Deletes the backups of all but the `keep` most recent deployments
+    /// that have one; those can no longer be rolled back.
+    pub async fn prune_deployment_backups(
+        &self,
+        keep: usize,
+    ) -> Result<usize, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
             let mut stmt = conn.prepare(
-                "SELECT created_at, action, target_type, detail_json
-                 FROM audit_logs
-                 ORDER BY created_at DESC
-                 LIMIT ?1",
+                "SELECT id FROM deployments WHERE has_backup = 1
+                 ORDER BY created_at DESC LIMIT -1 OFFSET ?1",
             )?;
-
-            let rows = stmt.query_map(params![query.limit as i64], |row| {
-                let detail_json: String = row.get(3)?;
-                let detail_json =
-                    serde_json::from_str::<serde_json::Value>(&detail_json)
-                        .unwrap_or_else(|_| json!({}));
-                let message = build_audit_log_message(
-                    &row.get::<_, String>(1)?,
-                    &row.get::<_, String>(2)?,
-                    &detail_json,
-                );
-                Ok(LogEntry {
-                    timestamp: DateTime::parse_from_rfc3339(
-                        &row.get::<_, String>(0)?,
-                    )
-                    .map_err(|error| {
-                        rusqlite::Error::FromSqlConversionFailure(
-                            0,
-                            rusqlite::types::Type::Text,
-                            Box::new(error),
-                        )
-                    })?
-                    .with_timezone(&Utc),
-                    level: "INFO".to_string(),
-                    source: "audit".to_string(),
-                    message,
-                })
-            })?;
-
-            for row in rows {
-                items.push(row?);
+            let expired = stmt
+                .query_map(params![keep as i64], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            for deployment_id in &expired {
+                let path = db.deployment_backup_path(deployment_id);
+                match std::fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(error)
+                        if error.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(error) => return Err(error.into()),
+                }
+                conn.execute(
+                    "UPDATE deployments SET has_backup = 0 WHERE id = ?1",
+                    params![deployment_id],
+                )?;
             }
-
-            Ok(LogListResponse {
-                items,
-                next_cursor: None,
-            })
+            Ok(expired.len())
         })
         .await?
     }
 
-    fn initialize_blocking(&self) -> Result<Option<BootstrapAdmin>, WebError> {
-        std::fs::create_dir_all(self.data_dir())?;
+    pub async fn get_deployment(
+        &self,
+        deployment_id: &str,
+    ) -> Result<Option<DeploymentSummary>, WebError> {
+        let db = self.clone();
+        let deployment_id = deployment_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            query_deployment(&conn, &deployment_id)
+        })
+        .await?
+    }
+
+    pub async fn create_api_token(
+        &self,
+        input: CreateApiTokenInput,
+    ) -> Result<ApiTokenSummary, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let token_id = Uuid::new_v4().to_string();
+            let scopes_json = serde_json::to_string(&input.scopes)
+                .map_err(|error| WebError::internal(error.to_string()))?;
+            conn.execute(
+                "INSERT INTO api_tokens (
+                    id, user_id, name, token_prefix, token_hash, scopes_json,
+                    created_at, expires_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    token_id,
+                    input.user_id,
+                    input.name,
+                    input.token_prefix,
+                    input.token_hash,
+                    scopes_json,
+                    Utc::now().to_rfc3339(),
+                    input.expires_at.map(|value| value.to_rfc3339())
+                ],
+            )?;
+            conn.query_row(
+                &format!("{API_TOKEN_SELECT} WHERE id = ?1 LIMIT 1"),
+                params![token_id],
+                map_api_token_row,
+            )
+            .map_err(WebError::from)
+        })
+        .await?
+    }
+
+    /// Unrevoked tokens of `user_id`, newest first; expired ones stay
+    /// listed until revoked.
+    pub async fn list_api_tokens(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<ApiTokenSummary>, WebError> {
+        let db = self.clone();
+        let user_id = user_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let mut stmt = conn.prepare(&format!(
+                "{API_TOKEN_SELECT}
+                 WHERE user_id = ?1 AND revoked_at IS NULL
+                 ORDER BY created_at DESC"
+            ))?;
+            let rows = stmt.query_map(params![user_id], map_api_token_row)?;
+            let mut items = Vec::new();
+            for row in rows {
+                items.push(row?);
+            }
+            Ok(items)
+        })
+        .await?
+    }
+
+    /// Returns whether a live token of `user_id` was revoked.
+    pub async fn revoke_api_token(
+        &self,
+        user_id: &str,
+        token_id: &str,
+    ) -> Result<bool, WebError> {
+        let db = self.clone();
+        let user_id = user_id.to_string();
+        let token_id = token_id.to_string();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let changed = conn.execute(
+                "UPDATE api_tokens SET revoked_at = ?3
+                 WHERE id = ?1 AND user_id = ?2 AND revoked_at IS NULL",
+                params![token_id, user_id, Utc::now().to_rfc3339()],
+            )?;
+            Ok(changed > 0)
+        })
+        .await?
+    }
+
+    /// Resolves a presented token by its hash. Revoked and expired tokens
+    /// and tokens of disabled users resolve to `None`.
+    pub async fn find_api_token(
+        &self,
+        token_hash: String,
+    ) -> Result<Option<ApiTokenRow>, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let token = conn
+                .query_row(
+                    "SELECT
+                        t.id,
+                        t.scopes_json,
+                        t.expires_at,
+                        u.id,
+                        u.username,
+                        u.email,
+                        u.role,
+                        u.disabled,
+                        u.totp_secret IS NOT NULL OR EXISTS (
+                            SELECT 1 FROM oidc_identities i WHERE i.user_id = u.id
+                        )
+                     FROM api_tokens t
+                     JOIN users u ON u.id = t.user_id
+                     WHERE t.token_hash = ?1 AND t.revoked_at IS NULL
+                     LIMIT 1",
+                    params![token_hash],
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, Option<String>>(2)?
+                                .map(|value| parse_rfc3339_to_utc(value, 2))
+                                .transpose()?,
+                            SessionUser {
+                                id: row.get(3)?,
+                                username: row.get(4)?,
+                                email: row.get(5)?,
+                                role: map_user_role_value(
+                                    row.get::<_, String>(6)?,
+                                    6,
+                                )?,
+                            },
+                            row.get::<_, i64>(7)? != 0,
+                            row.get::<_, bool>(8)?,
+                        ))
+                    },
+                )
+                .optional()?;
+
+            let Some((
+                token_id,
+                scopes_json,
+                expires_at,
+                user,
+                disabled,
+                second_factor,
+            )) = token
+            else {
+                return Ok(None);
+            };
+            if disabled
+                || expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+            {
+                return Ok(None);
+            }
+
+            let scopes = serde_json::from_str(&scopes_json)
+                .map_err(|error| WebError::internal(error.to_string()))?;
+            conn.execute(
+                "UPDATE api_tokens SET last_used_at = ?2 WHERE id = ?1",
+                params![token_id, Utc::now().to_rfc3339()],
+            )?;
+
+            let totp_enrollment_required = user.role == UserRole::Admin
+                && !second_factor
+                && read_totp_required_for_admins(&conn)?;
+
+            Ok(Some(ApiTokenRow {
+                id: token_id,
+                scopes,
+                user,
+                totp_enrollment_required,
+            }))
+        })
+        .await?
+    }
+
+    /// The local user an OIDC identity (`iss`, `sub`) signs in as.
+    pub async fn find_user_by_oidc_identity(
+        &self,
+        issuer: String,
+        subject: String,
+    ) -> Result<Option<UserRow>, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            conn.query_row(
+                "SELECT u.id, u.username, u.email, u.password_hash, u.role
+                 , u.disabled, u.created_at, u.updated_at
+                 FROM oidc_identities i
+                 JOIN users u ON u.id = i.user_id
+                 WHERE i.issuer = ?1 AND i.subject = ?2
+                 LIMIT 1",
+                params![issuer, subject],
+                map_user_row,
+            )
+            .optional()
+            .map_err(WebError::from)
+        })
+        .await?
+    }
+
+    pub async fn link_oidc_identity(
+        &self,
+        issuer: String,
+        subject: String,
+        user_id: String,
+    ) -> Result<(), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let result = conn.execute(
+                "INSERT INTO oidc_identities (issuer, subject, user_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![issuer, subject, user_id, Utc::now().to_rfc3339()],
+            );
+            match result {
+                Ok(_) => Ok(()),
+                Err(rusqlite::Error::SqliteFailure(error, _))
+                    if error.code == rusqlite::ErrorCode::ConstraintViolation =>
+                {
+                    Err(WebError::Conflict {
+                        message: "This SSO identity is already linked to an account.",
+                        field: None,
+                    })
+                }
+                Err(error) => Err(WebError::from(error)),
+            }
+        })
+        .await?
+    }
+
+    pub async fn totp_state(
+        &self,
+        user_id: String,
+    ) -> Result<TotpState, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let (secret, pending_secret) = conn
+                .query_row(
+                    "SELECT totp_secret, totp_pending_secret
+                     FROM users
+                     WHERE id = ?1
+                     LIMIT 1",
+                    params![user_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?
+                .ok_or(WebError::NotFound("User was not found."))?;
+            let recovery_codes_remaining = conn.query_row(
+                "SELECT COUNT(*) FROM totp_recovery_codes
+                 WHERE user_id = ?1 AND used_at IS NULL",
+                params![user_id],
+                |row| row.get::<_, u32>(0),
+            )?;
+            Ok(TotpState {
+                secret,
+                pending_secret,
+                recovery_codes_remaining,
+            })
+        })
+        .await?
+    }
+
+    pub async fn set_totp_pending_secret(
+        &self,
+        user_id: String,
+        secret: String,
+    ) -> Result<(), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            conn.execute(
+                "UPDATE users SET totp_pending_secret = ?2, updated_at = ?3
+                 WHERE id = ?1",
+                params![user_id, secret, Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Promotes the pending secret, remembering `step` so the enrolling
+    /// code cannot be replayed at login.
+    pub async fn enable_totp(
+        &self,
+        user_id: String,
+        secret: String,
+        step: u64,
+        recovery_code_hashes: Vec<String>,
+    ) -> Result<(), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let mut conn = db.open_connection()?;
+            let tx = conn.transaction()?;
+            let now = Utc::now().to_rfc3339();
+            tx.execute(
+                "UPDATE users
+                 SET totp_secret = ?2,
+                     totp_pending_secret = NULL,
+                     totp_enabled_at = ?3,
+                     totp_last_step = ?4,
+                     updated_at = ?3
+                 WHERE id = ?1",
+                params![user_id, secret, now, step as i64],
+            )?;
+            insert_recovery_codes(&tx, &user_id, &recovery_code_hashes)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Records a verified TOTP step. Returns `false` when the step (or a
+    /// later one) was already used, which rejects replayed codes.
+    pub async fn record_totp_step(
+        &self,
+        user_id: String,
+        step: u64,
+    ) -> Result<bool, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let changed = conn.execute(
+                "UPDATE users SET totp_last_step = ?2
+                 WHERE id = ?1
+                   AND (totp_last_step IS NULL OR totp_last_step < ?2)",
+                params![user_id, step as i64],
+            )?;
+            Ok(changed == 1)
+        })
+        .await?
+    }
+
+    /// Marks a matching unused recovery code as used.
+    pub async fn consume_recovery_code(
+        &self,
+        user_id: String,
+        code_hash: String,
+    ) -> Result<bool, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let changed = conn.execute(
+                "UPDATE totp_recovery_codes SET used_at = ?3
+                 WHERE id = (
+                    SELECT id FROM totp_recovery_codes
+                    WHERE user_id = ?1 AND code_hash = ?2 AND used_at IS NULL
+                    LIMIT 1
+                 )",
+                params![user_id, code_hash, Utc::now().to_rfc3339()],
+            )?;
+            Ok(changed == 1)
+        })
+        .await?
+    }
+
+    pub async fn replace_recovery_codes(
+        &self,
+        user_id: String,
+        recovery_code_hashes: Vec<String>,
+    ) -> Result<(), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let mut conn = db.open_connection()?;
+            let tx = conn.transaction()?;
+            insert_recovery_codes(&tx, &user_id, &recovery_code_hashes)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?
+    }
+
+    pub async fn disable_totp(&self, user_id: String) -> Result<(), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            clear_totp(&conn, &user_id)
+        })
+        .await?
+    }
+
+    /// Turns off two-factor for `username`, returning the user id. Used by
+    /// the CLI when an account has lost its authenticator.
+    pub async fn disable_totp_for_username(
+        &self,
+        username: String,
+    ) -> Result<String, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let user_id = conn
+                .query_row(
+                    "SELECT id FROM users WHERE username = ?1 LIMIT 1",
+                    params![username],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+                .ok_or(WebError::NotFound("User was not found."))?;
+            clear_totp(&conn, &user_id)?;
+            Ok(user_id)
+        })
+        .await?
+    }
+
+    pub async fn list_deployments(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<DeploymentSummary>, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let mut stmt = conn.prepare(&format!(
+                "{DEPLOYMENT_SELECT} ORDER BY d.created_at DESC LIMIT ?1"
+            ))?;
+            let rows =
+                stmt.query_map(params![limit as i64], map_deployment_row)?;
+            let mut items = Vec::new();
+            for row in rows {
+                items.push(row?);
+            }
+            Ok(items)
+        })
+        .await?
+    }
+
+    /// Matching audit entries, newest first, and how many match in total.
+    pub async fn list_audit_logs(
+        &self,
+        query: AuditLogQuery,
+    ) -> Result<(Vec<AuditLogEntry>, u64), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let filter = params![
+                query.user,
+                query.action,
+                query.target_type,
+                query.target_id,
+                query.from.map(|value| value.to_rfc3339()),
+                query.to.map(|value| value.to_rfc3339()),
+            ];
+            let total = conn.query_row(
+                &format!("SELECT COUNT(*) {AUDIT_LOG_FILTER}"),
+                filter,
+                |row| row.get::<_, i64>(0),
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT a.id, a.created_at, a.user_id, u.username, a.action,
+                        a.target_type, a.target_id, a.detail_json
+                 {AUDIT_LOG_FILTER}
+                 ORDER BY a.created_at DESC, a.rowid DESC
+                 LIMIT {} OFFSET {}",
+                query.limit, query.offset
+            ))?;
+            let rows = stmt.query_map(filter, map_audit_log_row)?;
+            let mut items = Vec::new();
+            for row in rows {
+                items.push(row?);
+            }
+            Ok((items, total as u64))
+        })
+        .await?
+    }
+
+    /// Distinct actions and target types recorded so far.
+    pub async fn audit_log_facets(
+        &self,
+    ) -> Result<(Vec<String>, Vec<String>), WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let mut facets = Vec::new();
+            for column in ["action", "target_type"] {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT DISTINCT {column} FROM audit_logs ORDER BY {column}"
+                ))?;
+                let values = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                facets.push(values);
+            }
+            let target_types = facets.pop().unwrap_or_default();
+            let actions = facets.pop().unwrap_or_default();
+            Ok((actions, target_types))
+        })
+        .await?
+    }
+
+    pub async fn audit_retention_days(&self) -> Result<Option<u32>, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            read_audit_retention_days(&conn)
+        })
+        .await?
+    }
+
+    pub async fn set_audit_retention_days(
+        &self,
+        days: Option<u32>,
+    ) -> Result<Option<u32>, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            conn.execute(
+                "INSERT INTO app_settings (key, value_json, updated_at)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) DO UPDATE SET
+                    value_json = excluded.value_json,
+                    updated_at = excluded.updated_at",
+                params![
+                    AUDIT_RETENTION_DAYS_KEY,
+                    json!({ "days": days }).to_string(),
+                    Utc::now().to_rfc3339()
+                ],
+            )?;
+            read_audit_retention_days(&conn)
+        })
+        .await?
+    }
+
+    /// Deletes audit entries older than the retention setting, returning
+    /// how many were removed.
+    pub async fn prune_audit_logs(&self) -> Result<usize, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let Some(days) = read_audit_retention_days(&conn)? else {
+                return Ok(0);
+            };
+            let cutoff = Utc::now() - chrono::Duration::days(i64::from(days));
+            Ok(conn.execute(
+                "DELETE FROM audit_logs WHERE created_at < ?1",
+                params![cutoff.to_rfc3339()],
+            )?)
+        })
+        .await?
+    }
 
-        for subdir in ["artifacts", "backgrounds"] {
+    pub async fn list_logs(
+        &self,
+        query: LogsQuery,
+    ) -> Result<LogListResponse, WebError> {
+        let db = self.clone();
+        task::spawn_blocking(move || {
+            let conn = db.open_connection()?;
+            let mut items = Vec::new();
+
+            if query
+                .source
+                .as_deref()
+                .map(|value| value == "runtime")
+                .unwrap_or(false)
+            {
+                return Ok(LogListResponse {
+                    items,
+                    next_cursor: None,
+                });
+            }
+
+            let mut stmt = conn.prepare(
+                "SELECT created_at, action, target_type, detail_json
+                 FROM audit_logs
+                 ORDER BY created_at DESC
+                 LIMIT ?1",
+            )?;
+
+            let rows = stmt.query_map(params![query.limit as i64], |row| {
+                let detail_json: String = row.get(3)?;
+                let detail_json =
+                    serde_json::from_str::<serde_json::Value>(&detail_json)
+                        .unwrap_or_else(|_| json!({}));
+                let message = build_audit_log_message(
+                    &row.get::<_, String>(1)?,
+                    &row.get::<_, String>(2)?,
+                    &detail_json,
+                );
+                Ok(LogEntry {
+                    timestamp: DateTime::parse_from_rfc3339(
+                        &row.get::<_, String>(0)?,
+                    )
+                    .map_err(|error| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            0,
+                            rusqlite::types::Type::Text,
+                            Box::new(error),
+                        )
+                    })?
+                    .with_timezone(&Utc),
+                    level: "INFO".to_string(),
+                    source: "audit".to_string(),
+                    message,
+                })
+            })?;
+
+            for row in rows {
+                items.push(row?);
+            }
+
+            Ok(LogListResponse {
+                items,
+                next_cursor: None,
+            })
+        })
+        .await?
+    }
+
+    fn initialize_blocking(&self) -> Result<Option<BootstrapAdmin>, WebError> {
+        std::fs::create_dir_all(self.data_dir())?;
+
+        for subdir in ["artifacts", "backgrounds", DEPLOYMENTS_DIR_NAME] {
             std::fs::create_dir_all(self.data_dir().join(subdir))?;
         }
 
-        let conn = self.open_connection()?;
+        let mut conn = self.open_connection()?;
         conn.execute_batch(
             r#"
             PRAGMA foreign_keys = ON;
@@ -1334,7 +2199,11 @@ impl Database {
                 disabled INTEGER NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
-                last_login_at TEXT
+                last_login_at TEXT,
+                totp_secret TEXT,
+                totp_pending_secret TEXT,
+                totp_enabled_at TEXT,
+                totp_last_step INTEGER
             );
 
             CREATE TABLE IF NOT EXISTS sessions (
@@ -1345,6 +2214,7 @@ impl Database {
                 last_seen_at TEXT NOT NULL,
                 user_agent TEXT,
                 ip_address TEXT,
+                auth_method TEXT NOT NULL DEFAULT 'password',
                 FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
             );
 
@@ -1363,6 +2233,20 @@ impl Database {
                 FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
             );
 
+            CREATE TABLE IF NOT EXISTS draft_revisions (
+                draft_id TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                user_id TEXT,
+                name TEXT NOT NULL,
+                stream_mode TEXT NOT NULL CHECK (stream_mode IN ('frontend', 'backend', 'dual')),
+                payload_json TEXT NOT NULL,
+                restored_from INTEGER,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY(draft_id, revision),
+                FOREIGN KEY(draft_id) REFERENCES drafts(id) ON DELETE CASCADE,
+                FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL
+            );
+
             CREATE TABLE IF NOT EXISTS config_sets (
                 id TEXT PRIMARY KEY,
                 user_id TEXT NOT NULL,
@@ -1370,28 +2254,13 @@ impl Database {
                 name TEXT NOT NULL,
                 stream_mode TEXT NOT NULL CHECK (stream_mode IN ('frontend', 'backend', 'dual')),
                 payload_json TEXT NOT NULL DEFAULT '{}',
+                issues_json TEXT NOT NULL DEFAULT '[]',
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
                 FOREIGN KEY(source_draft_id) REFERENCES drafts(id) ON DELETE SET NULL
             );
 
-            CREATE TABLE IF NOT EXISTS artifacts (
-                id TEXT PRIMARY KEY,
-                config_set_id TEXT NOT NULL,
-                artifact_type TEXT NOT NULL CHECK (artifact_type IN (
-                    'config_toml',
-                    'nginx_conf',
-                    'docker_compose',
-                    'systemd_service',
-                    'pm2_config'
-                )),
-                file_name TEXT NOT NULL,
-                content TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY(config_set_id) REFERENCES config_sets(id) ON DELETE CASCADE
-            );
-
             CREATE TABLE IF NOT EXISTS background_cache (
                 provider TEXT NOT NULL,
                 cache_key TEXT NOT NULL,
@@ -1412,6 +2281,55 @@ impl Database {
                 FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL
             );
 
+            CREATE TABLE IF NOT EXISTS deployments (
+                id TEXT PRIMARY KEY,
+                user_id TEXT,
+                kind TEXT NOT NULL CHECK (kind IN ('deploy', 'rollback')),
+                config_set_id TEXT,
+                config_set_name TEXT NOT NULL,
+                config_path TEXT NOT NULL,
+                has_backup INTEGER NOT NULL DEFAULT 0,
+                reloaded INTEGER NOT NULL DEFAULT 0,
+                restart_required_json TEXT NOT NULL DEFAULT '[]',
+                rollback_of TEXT,
+                rolled_back_at TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL,
+                FOREIGN KEY(config_set_id) REFERENCES config_sets(id) ON DELETE SET NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS api_tokens (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                token_prefix TEXT NOT NULL,
+                token_hash TEXT NOT NULL UNIQUE,
+                scopes_json TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                last_used_at TEXT,
+                expires_at TEXT,
+                revoked_at TEXT,
+                FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS oidc_identities (
+                issuer TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY(issuer, subject),
+                FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                code_hash TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                used_at TEXT,
+                FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+            );
+
             CREATE TABLE IF NOT EXISTS app_settings (
                 key TEXT PRIMARY KEY,
                 value_json TEXT NOT NULL,
@@ -1422,8 +2340,11 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_sessions_expires_at ON sessions(expires_at);
             CREATE INDEX IF NOT EXISTS idx_drafts_user_id ON drafts(user_id);
             CREATE INDEX IF NOT EXISTS idx_config_sets_user_id ON config_sets(user_id);
-            CREATE INDEX IF NOT EXISTS idx_artifacts_config_set_id ON artifacts(config_set_id);
             CREATE INDEX IF NOT EXISTS idx_audit_logs_created_at ON audit_logs(created_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_deployments_created_at ON deployments(created_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_api_tokens_user_id ON api_tokens(user_id);
+            CREATE INDEX IF NOT EXISTS idx_oidc_identities_user_id ON oidc_identities(user_id);
+            CREATE INDEX IF NOT EXISTS idx_totp_recovery_codes_user_id ON totp_recovery_codes(user_id);
             "#,
         )?;
 
@@ -1431,6 +2352,40 @@ impl Database {
             "ALTER TABLE users ADD COLUMN disabled INTEGER NOT NULL DEFAULT 0",
             [],
         );
+        let _ = conn.execute(
+            "ALTER TABLE config_sets ADD COLUMN issues_json TEXT NOT NULL DEFAULT '[]'",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE sessions ADD COLUMN auth_method TEXT NOT NULL DEFAULT 'password'",
+            [],
+        );
+        for column in [
+            "totp_secret TEXT",
+            "totp_pending_secret TEXT",
+            "totp_enabled_at TEXT",
+            "totp_last_step INTEGER",
+        ] {
+            let _ = conn
+                .execute(&format!("ALTER TABLE users ADD COLUMN {column}"), []);
+        }
+        ensure_artifacts_table(&mut conn)?;
+
+        // Drafts saved before revisions were kept start from their current
+        // document.
+        conn.execute(
+            "INSERT INTO draft_revisions (
+                draft_id, revision, user_id, name, stream_mode, payload_json,
+                restored_from, created_at
+             )
+             SELECT id, wizard_version, user_id, name, stream_mode, payload_json,
+                    NULL, updated_at
+             FROM drafts
+             WHERE NOT EXISTS (
+                SELECT 1 FROM draft_revisions r WHERE r.draft_id = drafts.id
+             )",
+            [],
+        )?;
 
         conn.execute(
             "INSERT OR IGNORE INTO app_settings (key, value_json, updated_at)
@@ -1494,6 +2449,181 @@ impl Database {
     }
 }
 
+/// Copies the draft's current row into `draft_revisions`.
+fn snapshot_draft_revision(
+    conn: &Connection,
+    draft_id: &str,
+    restored_from: Option<u64>,
+) -> Result<DraftRevision, WebError> {
+    conn.execute(
+        "INSERT INTO draft_revisions (
+            draft_id, revision, user_id, name, stream_mode, payload_json,
+            restored_from, created_at
+         )
+         SELECT id, wizard_version, user_id, name, stream_mode, payload_json,
+                ?2, updated_at
+         FROM drafts
+         WHERE id = ?1",
+        params![draft_id, restored_from],
+    )?;
+
+    conn.query_row(
+        "SELECT updated_at, wizard_version FROM drafts WHERE id = ?1 LIMIT 1",
+        params![draft_id],
+        |row| {
+            Ok(DraftRevision {
+                id: draft_id.to_string(),
+                updated_at: parse_rfc3339_to_utc(row.get::<_, String>(0)?, 0)?,
+                server_revision: row.get::<_, u64>(1)?,
+            })
+        },
+    )
+    .map_err(WebError::from)
+}
+
+fn query_draft_revision(
+    conn: &Connection,
+    user_id: &str,
+    draft_id: &str,
+    revision: u64,
+) -> Result<Option<(String, WizardPayload)>, WebError> {
+    conn.query_row(
+        "SELECT r.name, r.payload_json
+         FROM draft_revisions r
+         JOIN drafts d ON d.id = r.draft_id
+         WHERE r.draft_id = ?1 AND d.user_id = ?2 AND r.revision = ?3
+         LIMIT 1",
+        params![draft_id, user_id, revision],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                serde_json::from_str::<WizardPayload>(
+                    &row.get::<_, String>(1)?,
+                )
+                .map_err(|error| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        1,
+                        rusqlite::types::Type::Text,
+                        Box::new(error),
+                    )
+                })?,
+            ))
+        },
+    )
+    .optional()
+    .map_err(WebError::from)
+}
+
+/// `FROM ... WHERE` shared by audit log listing and counting; bind the
+/// [`AuditLogQuery`] filters as `?1` to `?6`.
+const AUDIT_LOG_FILTER: &str = "FROM audit_logs a
+     LEFT JOIN users u ON u.id = a.user_id
+     WHERE (?1 IS NULL OR a.user_id = ?1 OR u.username = ?1 COLLATE NOCASE)
+       AND (?2 IS NULL OR a.action = ?2)
+       AND (?3 IS NULL OR a.target_type = ?3)
+       AND (?4 IS NULL OR a.target_id = ?4)
+       AND (?5 IS NULL OR a.created_at >= ?5)
+       AND (?6 IS NULL OR a.created_at <= ?6)";
+
+const DEPLOYMENT_SELECT: &str = "SELECT d.id, d.kind, d.config_set_id, \
+     d.config_set_name, d.config_path, u.username, d.has_backup, d.reloaded, \
+     d.restart_required_json, d.rollback_of, d.rolled_back_at, d.created_at \
+     FROM deployments d LEFT JOIN users u ON u.id = d.user_id";
+
+const API_TOKEN_SELECT: &str = "SELECT id, name, token_prefix, scopes_json, \
+     created_at, last_used_at, expires_at FROM api_tokens";
+
+fn map_api_token_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<ApiTokenSummary> {
+    let scopes =
+        serde_json::from_str::<Vec<ApiTokenScope>>(&row.get::<_, String>(3)?)
+            .map_err(|error| {
+            rusqlite::Error::FromSqlConversionFailure(
+                3,
+                rusqlite::types::Type::Text,
+                Box::new(error),
+            )
+        })?;
+    Ok(ApiTokenSummary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        token_prefix: row.get(2)?,
+        scopes,
+        created_at: parse_rfc3339_to_utc(row.get::<_, String>(4)?, 4)?,
+        last_used_at: row
+            .get::<_, Option<String>>(5)?
+            .map(|value| parse_rfc3339_to_utc(value, 5))
+            .transpose()?,
+        expires_at: row
+            .get::<_, Option<String>>(6)?
+            .map(|value| parse_rfc3339_to_utc(value, 6))
+            .transpose()?,
+    })
+}
+
+fn query_deployment(
+    conn: &Connection,
+    deployment_id: &str,
+) -> Result<Option<DeploymentSummary>, WebError> {
+    conn.query_row(
+        &format!("{DEPLOYMENT_SELECT} WHERE d.id = ?1 LIMIT 1"),
+        params![deployment_id],
+        map_deployment_row,
+    )
+    .optional()
+    .map_err(WebError::from)
+}
+
+fn map_deployment_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<DeploymentSummary> {
+    let restart_required =
+        serde_json::from_str::<Vec<String>>(&row.get::<_, String>(8)?)
+            .map_err(|error| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    8,
+                    rusqlite::types::Type::Text,
+                    Box::new(error),
+                )
+            })?;
+    Ok(DeploymentSummary {
+        id: row.get(0)?,
+        kind: map_deployment_kind_value(row.get::<_, String>(1)?, 1)?,
+        config_set_id: row.get(2)?,
+        config_set_name: row.get(3)?,
+        config_path: row.get(4)?,
+        deployed_by: row.get(5)?,
+        has_backup: row.get::<_, i64>(6)? != 0,
+        reloaded: row.get::<_, i64>(7)? != 0,
+        restart_required,
+        rollback_of: row.get(9)?,
+        rolled_back_at: row
+            .get::<_, Option<String>>(10)?
+            .map(|value| parse_rfc3339_to_utc(value, 10))
+            .transpose()?,
+        created_at: parse_rfc3339_to_utc(row.get::<_, String>(11)?, 11)?,
+    })
+}
+
+fn map_audit_log_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<AuditLogEntry> {
+    let detail =
+        serde_json::from_str::<serde_json::Value>(&row.get::<_, String>(7)?)
+            .unwrap_or_else(|_| json!({}));
+    Ok(AuditLogEntry {
+        id: row.get(0)?,
+        created_at: parse_rfc3339_to_utc(row.get::<_, String>(1)?, 1)?,
+        user_id: row.get(2)?,
+        username: row.get(3)?,
+        action: row.get(4)?,
+        target_type: row.get(5)?,
+        target_id: row.get(6)?,
+        detail,
+    })
+}
+
 fn build_audit_log_message(
     action: &str,
     target_type: &str,
@@ -1506,11 +2636,99 @@ fn build_audit_log_message(
     }
 }
 
-fn read_registration_enabled(conn: &Connection) -> Result<bool, WebError> {
+/// Replaces every recovery code of `user_id` with `code_hashes`.
+fn insert_recovery_codes(
+    conn: &Connection,
+    user_id: &str,
+    code_hashes: &[String],
+) -> Result<(), WebError> {
+    conn.execute(
+        "DELETE FROM totp_recovery_codes WHERE user_id = ?1",
+        params![user_id],
+    )?;
+    let now = Utc::now().to_rfc3339();
+    for code_hash in code_hashes {
+        conn.execute(
+            "INSERT INTO totp_recovery_codes (id, user_id, code_hash, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![Uuid::new_v4().to_string(), user_id, code_hash, now],
+        )?;
+    }
+    Ok(())
+}
+
+fn clear_totp(conn: &Connection, user_id: &str) -> Result<(), WebError> {
+    conn.execute(
+        "UPDATE users
+         SET totp_secret = NULL,
+             totp_pending_secret = NULL,
+             totp_enabled_at = NULL,
+             totp_last_step = NULL,
+             updated_at = ?2
+         WHERE id = ?1",
+        params![user_id, Utc::now().to_rfc3339()],
+    )?;
+    conn.execute(
+        "DELETE FROM totp_recovery_codes WHERE user_id = ?1",
+        params![user_id],
+    )?;
+    Ok(())
+}
+
+fn read_audit_retention_days(
+    conn: &Connection,
+) -> Result<Option<u32>, WebError> {
+    let value_json = conn
+        .query_row(
+            "SELECT value_json FROM app_settings WHERE key = ?1 LIMIT 1",
+            params![AUDIT_RETENTION_DAYS_KEY],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+    let Some(value_json) = value_json else {
+        return Ok(None);
+    };
+    let value = serde_json::from_str::<serde_json::Value>(&value_json)
+        .map_err(|error| WebError::internal(error.to_string()))?;
+    Ok(value
+        .get("days")
+        .and_then(serde_json::Value::as_u64)
+        .and_then(|days| u32::try_from(days).ok()))
+}
+
+fn read_totp_required_for_admins(conn: &Connection) -> Result<bool, WebError> {
+    read_enabled_setting(conn, TOTP_REQUIRED_FOR_ADMINS_KEY)
+}
+
+fn write_enabled_setting(
+    conn: &Connection,
+    key: &str,
+    enabled: bool,
+) -> Result<(), WebError> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value_json, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET
+            value_json = excluded.value_json,
+            updated_at = excluded.updated_at",
+        params![
+            key,
+            serde_json::to_string(&json!({ "enabled": enabled }))
+                .map_err(|error| WebError::internal(error.to_string()))?,
+            Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+fn read_enabled_setting(
+    conn: &Connection,
+    key: &str,
+) -> Result<bool, WebError> {
     let value_json = conn
         .query_row(
             "SELECT value_json FROM app_settings WHERE key = ?1 LIMIT 1",
-            params![REGISTRATION_ENABLED_KEY],
+            params![key],
             |row| row.get::<_, String>(0),
         )
         .optional()?;
@@ -1616,7 +2834,32 @@ impl DraftStatus {
     }
 }
 
+impl DeploymentKind {
+    pub fn as_db_value(self) -> &'static str {
+        match self {
+            Self::Deploy => "deploy",
+            Self::Rollback => "rollback",
+        }
+    }
+}
+
 impl super::contracts::ArtifactType {
+    pub const ALL: [Self; 13] = [
+        Self::ConfigToml,
+        Self::NginxConf,
+        Self::DockerCompose,
+        Self::SystemdService,
+        Self::Pm2Config,
+        Self::KubernetesDeployment,
+        Self::KubernetesService,
+        Self::KubernetesConfigMap,
+        Self::KubernetesSecret,
+        Self::KubernetesIngress,
+        Self::KubernetesValues,
+        Self::Caddyfile,
+        Self::TraefikDynamic,
+    ];
+
     pub fn as_db_value(self) -> &'static str {
         match self {
             Self::ConfigToml => "config_toml",
@@ -1624,6 +2867,14 @@ impl super::contracts::ArtifactType {
             Self::DockerCompose => "docker_compose",
             Self::SystemdService => "systemd_service",
             Self::Pm2Config => "pm2_config",
+            Self::KubernetesDeployment => "kubernetes_deployment",
+            Self::KubernetesService => "kubernetes_service",
+            Self::KubernetesConfigMap => "kubernetes_config_map",
+            Self::KubernetesSecret => "kubernetes_secret",
+            Self::KubernetesIngress => "kubernetes_ingress",
+            Self::KubernetesValues => "kubernetes_values",
+            Self::Caddyfile => "caddyfile",
+            Self::TraefikDynamic => "traefik_dynamic",
         }
     }
 
@@ -1651,7 +2902,73 @@ fn artifact_language(
         super::contracts::ArtifactType::DockerCompose => "yaml",
         super::contracts::ArtifactType::SystemdService => "ini",
         super::contracts::ArtifactType::Pm2Config => "javascript",
+        super::contracts::ArtifactType::KubernetesDeployment
+        | super::contracts::ArtifactType::KubernetesService
+        | super::contracts::ArtifactType::KubernetesConfigMap
+        | super::contracts::ArtifactType::KubernetesSecret
+        | super::contracts::ArtifactType::KubernetesIngress
+        | super::contracts::ArtifactType::KubernetesValues
+        | super::contracts::ArtifactType::TraefikDynamic => "yaml",
+        super::contracts::ArtifactType::Caddyfile => "caddyfile",
+    }
+}
+
+/// Creates the artifacts table. Its CHECK constraint cannot be altered in
+/// place, so a table created before the current artifact types existed is
+/// rebuilt with its rows copied over.
+fn ensure_artifacts_table(conn: &mut Connection) -> Result<(), WebError> {
+    let allowed = super::contracts::ArtifactType::ALL
+        .iter()
+        .map(|artifact_type| format!("'{}'", artifact_type.as_db_value()))
+        .collect::<Vec<_>>();
+    let create = format!(
+        "CREATE TABLE artifacts (
+            id TEXT PRIMARY KEY,
+            config_set_id TEXT NOT NULL,
+            artifact_type TEXT NOT NULL CHECK (artifact_type IN ({})),
+            file_name TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(config_set_id) REFERENCES config_sets(id) ON DELETE CASCADE
+        );",
+        allowed.join(", ")
+    );
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master
+             WHERE type = 'table' AND name = 'artifacts'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match existing {
+        None => conn.execute_batch(&create)?,
+        Some(sql) if allowed.iter().all(|value| sql.contains(value)) => {}
+        Some(_) => {
+            let tx = conn.transaction()?;
+            tx.execute_batch(&format!(
+                "ALTER TABLE artifacts RENAME TO artifacts_legacy;
+                 {create}
+                 INSERT INTO artifacts (
+                    id, config_set_id, artifact_type, file_name, content,
+                    created_at
+                 )
+                 SELECT id, config_set_id, artifact_type, file_name, content,
+                        created_at
+                 FROM artifacts_legacy;
+                 DROP TABLE artifacts_legacy;"
+            ))?;
+            tx.commit()?;
+        }
     }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_artifacts_config_set_id
+         ON artifacts(config_set_id)",
+        [],
+    )?;
+    Ok(())
 }
 
 fn map_stream_mode_value(
@@ -1690,6 +3007,23 @@ fn map_draft_status_value(
     }
 }
 
+fn map_deployment_kind_value(
+    value: String,
+    index: usize,
+) -> rusqlite::Result<DeploymentKind> {
+    match value.as_str() {
+        "deploy" => Ok(DeploymentKind::Deploy),
+        "rollback" => Ok(DeploymentKind::Rollback),
+        _ => Err(rusqlite::Error::FromSqlConversionFailure(
+            index,
+            rusqlite::types::Type::Text,
+            Box::new(WebError::internal(format!(
+                "Unknown deployment kind '{value}'"
+            ))),
+        )),
+    }
+}
+
 fn map_artifact_type_value(
     value: String,
     index: usize,
@@ -1700,6 +3034,26 @@ fn map_artifact_type_value(
         "docker_compose" => Ok(super::contracts::ArtifactType::DockerCompose),
         "systemd_service" => Ok(super::contracts::ArtifactType::SystemdService),
         "pm2_config" => Ok(super::contracts::ArtifactType::Pm2Config),
+        "kubernetes_deployment" => {
+            Ok(super::contracts::ArtifactType::KubernetesDeployment)
+        }
+        "kubernetes_service" => {
+            Ok(super::contracts::ArtifactType::KubernetesService)
+        }
+        "kubernetes_config_map" => {
+            Ok(super::contracts::ArtifactType::KubernetesConfigMap)
+        }
+        "kubernetes_secret" => {
+            Ok(super::contracts::ArtifactType::KubernetesSecret)
+        }
+        "kubernetes_ingress" => {
+            Ok(super::contracts::ArtifactType::KubernetesIngress)
+        }
+        "kubernetes_values" => {
+            Ok(super::contracts::ArtifactType::KubernetesValues)
+        }
+        "caddyfile" => Ok(super::contracts::ArtifactType::Caddyfile),
+        "traefik_dynamic" => Ok(super::contracts::ArtifactType::TraefikDynamic),
         _ => Err(rusqlite::Error::FromSqlConversionFailure(
             index,
             rusqlite::types::Type::Text,
@@ -0,0 +1,242 @@
+use std::{
+    fs,
+    path::{Path as FsPath, PathBuf},
+};
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, post},
+};
+use axum_extra::extract::CookieJar;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    app::runtime_state,
+    config::core::{Config, write_atomic_config},
+    web::{
+        admin::require_admin,
+        api::WebAppState,
+        contracts::{
+            ArtifactType, DeploymentEnvelope, DeploymentKind,
+            DeploymentListResponse, DeploymentSummary, SessionUser,
+        },
+        db::RecordDeploymentInput,
+        error::WebError,
+    },
+};
+
+const DEPLOYMENT_LIST_LIMIT: usize = 50;
+/// Deployments whose replaced config stays on disk for rollback.
+const DEPLOYMENT_BACKUP_LIMIT: usize = 20;
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/", get(list_deployments))
+        .route("/{deployment_id}/rollback", post(rollback_deployment))
+}
+
+/// What a deployment writes and how it is recorded.
+struct DeploymentPlan {
+    kind: DeploymentKind,
+    config_set_id: Option<String>,
+    config_set_name: String,
+    rollback_of: Option<String>,
+}
+
+pub async fn deploy_config_set(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Path(config_set_id): Path<String>,
+) -> Result<Json<DeploymentEnvelope>, WebError> {
+    let admin = require_admin(&state, &jar).await?;
+    let config_path = deploy_target(&state)?;
+    let config_set = state
+        .db
+        .list_config_sets(&admin.id)
+        .await?
+        .into_iter()
+        .find(|config_set| config_set.id == config_set_id)
+        .ok_or(WebError::NotFound("Generated config set was not found."))?;
+    let content = state
+        .db
+        .list_config_set_artifacts(&admin.id, &config_set_id)
+        .await?
+        .and_then(|artifacts| {
+            artifacts.items.into_iter().find(|artifact| {
+                artifact.artifact_type == ArtifactType::ConfigToml
+            })
+        })
+        .ok_or(WebError::NotFound("Config set has no config.toml."))?
+        .content;
+
+    let deployment = apply_config(
+        &state,
+        admin,
+        &config_path,
+        &content,
+        DeploymentPlan {
+            kind: DeploymentKind::Deploy,
+            config_set_id: Some(config_set.id),
+            config_set_name: config_set.name,
+            rollback_of: None,
+        },
+    )
+    .await?;
+    Ok(Json(DeploymentEnvelope { deployment }))
+}
+
+async fn list_deployments(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<DeploymentListResponse>, WebError> {
+    let _admin = require_admin(&state, &jar).await?;
+    Ok(Json(DeploymentListResponse {
+        config_path: state
+            .config
+            .main_config_path
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        live_reload: runtime_state().is_some(),
+        items: state.db.list_deployments(DEPLOYMENT_LIST_LIMIT).await?,
+    }))
+}
+
+/// Restores the file a deployment replaced, recorded as a new deployment.
+async fn rollback_deployment(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Path(deployment_id): Path<String>,
+) -> Result<Json<DeploymentEnvelope>, WebError> {
+    let admin = require_admin(&state, &jar).await?;
+    let config_path = deploy_target(&state)?;
+    let target = state
+        .db
+        .get_deployment(&deployment_id)
+        .await?
+        .ok_or(WebError::NotFound("Deployment was not found."))?;
+    if target.rolled_back_at.is_some() {
+        return Err(WebError::Conflict {
+            message: "Deployment was already rolled back.",
+            field: None,
+        });
+    }
+    if !target.has_backup {
+        return Err(WebError::Conflict {
+            message: "Deployment has no previous version to restore.",
+            field: None,
+        });
+    }
+    if FsPath::new(&target.config_path) != config_path {
+        return Err(WebError::Conflict {
+            message: "Deployment targets a different config file.",
+            field: None,
+        });
+    }
+
+    let content =
+        fs::read_to_string(state.db.deployment_backup_path(&deployment_id))?;
+    let deployment = apply_config(
+        &state,
+        admin,
+        &config_path,
+        &content,
+        DeploymentPlan {
+            kind: DeploymentKind::Rollback,
+            config_set_id: target.config_set_id,
+            config_set_name: target.config_set_name,
+            rollback_of: Some(deployment_id),
+        },
+    )
+    .await?;
+    Ok(Json(DeploymentEnvelope { deployment }))
+}
+
+fn deploy_target(state: &WebAppState) -> Result<PathBuf, WebError> {
+    state.config.main_config_path.clone().ok_or(WebError::Conflict {
+        message: "Deploying needs the gateway config file; start the studio \
+                  with `embystream run --web`.",
+        field: None,
+    })
+}
+
+/// Validates `content`, backs up the current file, writes it atomically and
+/// reloads the in-process stream runtime when there is one. Backups older
+/// than the last [`DEPLOYMENT_BACKUP_LIMIT`] deployments are deleted.
+async fn apply_config(
+    state: &WebAppState,
+    user: SessionUser,
+    config_path: &FsPath,
+    content: &str,
+    plan: DeploymentPlan,
+) -> Result<DeploymentSummary, WebError> {
+    let _guard = state.deploy_lock.lock().await;
+    let config = Config::load_from_content(config_path, content)
+        .map_err(|error| WebError::ValidationFailed(error.to_string()))?;
+
+    let deployment_id = Uuid::new_v4().to_string();
+    let has_backup = config_path.exists();
+    if has_backup {
+        let previous = fs::read_to_string(config_path)?;
+        write_atomic_config(
+            &state.db.deployment_backup_path(&deployment_id),
+            &previous,
+        )
+        .map_err(|error| WebError::internal(error.to_string()))?;
+    }
+    write_atomic_config(config_path, content)
+        .map_err(|error| WebError::internal(error.to_string()))?;
+
+    let (reloaded, restart_required) = match runtime_state() {
+        Some(runtime)
+            if runtime.get_config().await.path.as_path() == config_path =>
+        {
+            let sections = runtime.reload_config(config).await;
+            (true, sections.into_iter().map(str::to_string).collect())
+        }
+        _ => (false, Vec::new()),
+    };
+
+    let action = match plan.kind {
+        DeploymentKind::Deploy => "deploy_config_set",
+        DeploymentKind::Rollback => "rollback_deployment",
+    };
+    let deployment = state
+        .db
+        .record_deployment(RecordDeploymentInput {
+            id: deployment_id.clone(),
+            user_id: user.id.clone(),
+            kind: plan.kind,
+            config_set_id: plan.config_set_id.clone(),
+            config_set_name: plan.config_set_name,
+            config_path: config_path.display().to_string(),
+            has_backup,
+            reloaded,
+            restart_required,
+            rollback_of: plan.rollback_of.clone(),
+        })
+        .await?;
+    state
+        .db
+        .prune_deployment_backups(DEPLOYMENT_BACKUP_LIMIT)
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id),
+            action,
+            "deployment",
+            Some(deployment_id),
+            json!({
+                "config_set_id": plan.config_set_id,
+                "config_path": deployment.config_path,
+                "reloaded": deployment.reloaded,
+                "restart_required": deployment.restart_required,
+                "rollback_of": plan.rollback_of,
+            }),
+        )
+        .await?;
+
+    Ok(deployment)
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{get, post},
+};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::web::{
+    api::WebAppState,
+    auth::session_user_from_jar,
+    contracts::{
+        DraftChangeKind, DraftFieldChange, DraftRevisionDiffResponse,
+        DraftRevisionListResponse, DraftSectionDiff, SaveDraftResponse,
+        WizardPayload,
+    },
+    error::WebError,
+};
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/{draft_id}/revisions", get(list_draft_revisions))
+        .route("/{draft_id}/revisions/diff", get(diff_draft_revisions))
+        .route(
+            "/{draft_id}/revisions/{revision}/restore",
+            post(restore_draft_revision),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffParams {
+    pub from: u64,
+    /// Defaults to the draft's current revision.
+    pub to: Option<u64>,
+}
+
+async fn list_draft_revisions(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Path(draft_id): Path<String>,
+) -> Result<Json<DraftRevisionListResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let revisions = state
+        .db
+        .list_draft_revisions(&user.id, &draft_id)
+        .await?
+        .ok_or(WebError::NotFound("Draft was not found."))?;
+    Ok(Json(revisions))
+}
+
+async fn diff_draft_revisions(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Path(draft_id): Path<String>,
+    Query(params): Query<RevisionDiffParams>,
+) -> Result<Json<DraftRevisionDiffResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let to_revision = match params.to {
+        Some(revision) => revision,
+        None => {
+            state
+                .db
+                .list_draft_revisions(&user.id, &draft_id)
+                .await?
+                .ok_or(WebError::NotFound("Draft was not found."))?
+                .current_revision
+        }
+    };
+
+    let (from_name, from_payload) = state
+        .db
+        .get_draft_revision(&user.id, &draft_id, params.from)
+        .await?
+        .ok_or(WebError::NotFound("Draft revision was not found."))?;
+    let (to_name, to_payload) = state
+        .db
+        .get_draft_revision(&user.id, &draft_id, to_revision)
+        .await?
+        .ok_or(WebError::NotFound("Draft revision was not found."))?;
+
+    Ok(Json(DraftRevisionDiffResponse {
+        from_revision: params.from,
+        to_revision,
+        sections: diff_payloads(
+            &from_name,
+            &from_payload,
+            &to_name,
+            &to_payload,
+        )?,
+    }))
+}
+
+async fn restore_draft_revision(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Path((draft_id, revision)): Path<(String, u64)>,
+) -> Result<Json<SaveDraftResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let draft = state
+        .db
+        .restore_draft_revision(&user.id, &draft_id, revision)
+        .await?
+        .ok_or(WebError::NotFound("Draft revision was not found."))?;
+    Ok(Json(SaveDraftResponse { draft }))
+}
+
+/// Compares two draft documents section by section. Backend nodes are
+/// matched by name so reordering them does not show up as a change.
+pub fn diff_payloads(
+    from_name: &str,
+    from: &WizardPayload,
+    to_name: &str,
+    to: &WizardPayload,
+) -> Result<Vec<DraftSectionDiff>, WebError> {
+    let from_sections = payload_sections(from_name, from)?;
+    let to_sections = payload_sections(to_name, to)?;
+    let from_lookup: HashMap<&str, &Value> = from_sections
+        .iter()
+        .map(|(section, value)| (section.as_str(), value))
+        .collect();
+    let to_lookup: HashMap<&str, &Value> = to_sections
+        .iter()
+        .map(|(section, value)| (section.as_str(), value))
+        .collect();
+
+    let mut sections = Vec::new();
+    for (section, value) in &to_sections {
+        let before = from_lookup.get(section.as_str()).copied();
+        let mut fields = Vec::new();
+        diff_values("", before, Some(value), &mut fields);
+        if fields.is_empty() {
+            continue;
+        }
+        sections.push(DraftSectionDiff {
+            section: section.clone(),
+            change: if before.is_some() {
+                DraftChangeKind::Modified
+            } else {
+                DraftChangeKind::Added
+            },
+            fields,
+        });
+    }
+    for (section, value) in &from_sections {
+        if to_lookup.contains_key(section.as_str()) {
+            continue;
+        }
+        let mut fields = Vec::new();
+        diff_values("", Some(value), None, &mut fields);
+        sections.push(DraftSectionDiff {
+            section: section.clone(),
+            change: DraftChangeKind::Removed,
+            fields,
+        });
+    }
+    Ok(sections)
+}
+
+/// Splits a draft into the sections it is edited as, named the way they
+/// appear in `config.toml`.
+fn payload_sections(
+    name: &str,
+    payload: &WizardPayload,
+) -> Result<Vec<(String, Value)>, WebError> {
+    let mut general = to_value(&payload.shared.general)?;
+    if let Value::Object(fields) = &mut general {
+        fields
+            .insert("stream_mode".to_string(), to_value(&payload.stream_mode)?);
+    }
+    let mut sections = vec![
+        ("Draft".to_string(), serde_json::json!({ "name": name })),
+        ("General".to_string(), general),
+        ("Log".to_string(), to_value(&payload.shared.log)?),
+        ("Emby".to_string(), to_value(&payload.shared.emby)?),
+        (
+            "UserAgent".to_string(),
+            to_value(&payload.shared.user_agent)?,
+        ),
+        ("Fallback".to_string(), to_value(&payload.shared.fallback)?),
+        ("Http2".to_string(), to_value(&payload.shared.http2)?),
+        ("Secrets".to_string(), to_value(&payload.shared.secrets)?),
+        ("Frontend".to_string(), to_value(&payload.frontend)?),
+        ("Backend".to_string(), to_value(&payload.backend)?),
+    ];
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for node in &payload.backend_nodes {
+        let count = seen.entry(node.name.as_str()).or_default();
+        *count += 1;
+        let section = if *count == 1 {
+            format!("BackendNode:{}", node.name)
+        } else {
+            format!("BackendNode:{}#{}", node.name, count)
+        };
+        sections.push((section, to_value(node)?));
+    }
+
+    sections.push(("Nginx".to_string(), to_value(&payload.nginx)?));
+    sections.push(("Deployment".to_string(), to_value(&payload.deployment)?));
+    Ok(sections)
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, WebError> {
+    serde_json::to_value(value)
+        .map_err(|error| WebError::internal(error.to_string()))
+}
+
+/// Pushes one change per differing leaf. Objects recurse by key and arrays
+/// by index; `null` counts as absent.
+fn diff_values(
+    path: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+    changes: &mut Vec<DraftFieldChange>,
+) {
+    let before = before.filter(|value| !value.is_null());
+    let after = after.filter(|value| !value.is_null());
+    match (before, after) {
+        (None, None) => {}
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            for (key, value) in before {
+                diff_values(
+                    &join_key(path, key),
+                    Some(value),
+                    after.get(key),
+                    changes,
+                );
+            }
+            for (key, value) in after {
+                if !before.contains_key(key) {
+                    diff_values(
+                        &join_key(path, key),
+                        None,
+                        Some(value),
+                        changes,
+                    );
+                }
+            }
+        }
+        (Some(Value::Object(before)), None) => {
+            for (key, value) in before {
+                diff_values(&join_key(path, key), Some(value), None, changes);
+            }
+        }
+        (None, Some(Value::Object(after))) => {
+            for (key, value) in after {
+                diff_values(&join_key(path, key), None, Some(value), changes);
+            }
+        }
+        (Some(Value::Array(before)), Some(Value::Array(after))) => {
+            for index in 0..before.len().max(after.len()) {
+                diff_values(
+                    &format!("{path}[{index}]"),
+                    before.get(index),
+                    after.get(index),
+                    changes,
+                );
+            }
+        }
+        (before, after) if before == after => {}
+        (before, after) => changes.push(DraftFieldChange {
+            path: path.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::diff_payloads;
+    use crate::{
+        cli_wizard::template_payload::build_template_raw,
+        config::general::StreamMode,
+        web::{contracts::DraftChangeKind, drafts::wizard_payload_from_raw},
+    };
+
+    #[test]
+    fn diff_reports_backend_node_changes_by_name() {
+        let before =
+            wizard_payload_from_raw(build_template_raw(StreamMode::Backend));
+        assert!(before.backend_nodes.len() >= 2, "template has nodes");
+
+        let mut after = before.clone();
+        after.backend_nodes.swap(0, 1);
+        assert!(
+            diff_payloads("Demo", &before, "Demo", &after)
+                .expect("diff")
+                .is_empty()
+        );
+
+        let renamed = after.backend_nodes[1].name.clone();
+        after.backend_nodes[0].path_rewrites[0].pattern = "^/new(/.*)$".into();
+        after.backend_nodes[1].name = format!("{renamed}-2");
+        after.shared.log.level = "debug".into();
+
+        let sections =
+            diff_payloads("Demo", &before, "Renamed", &after).expect("diff");
+        let by_section = |name: &str| {
+            sections
+                .iter()
+                .find(|section| section.section == name)
+                .unwrap_or_else(|| panic!("missing section {name}"))
+        };
+
+        assert_eq!(by_section("Draft").fields[0].after, Some(json!("Renamed")));
+        assert_eq!(by_section("Log").fields[0].path, "level");
+        let rewritten =
+            by_section(&format!("BackendNode:{}", after.backend_nodes[0].name));
+        assert_eq!(rewritten.change, DraftChangeKind::Modified);
+        assert_eq!(rewritten.fields[0].path, "path_rewrites[0].pattern");
+        assert_eq!(rewritten.fields[0].after, Some(json!("^/new(/.*)$")));
+        assert_eq!(
+            by_section(&format!("BackendNode:{renamed}-2")).change,
+            DraftChangeKind::Added
+        );
+        assert_eq!(
+            by_section(&format!("BackendNode:{renamed}")).change,
+            DraftChangeKind::Removed
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+mod history;
+
+use std::{fs, path::PathBuf};
 
 use axum::{
     Json, Router,
@@ -6,22 +8,33 @@ use axum::{
     routing::{get, patch, post},
 };
 use axum_extra::extract::CookieJar;
+use serde_json::json;
+use toml::Value as TomlValue;
 
 use crate::{
     cli_wizard::{
         emit::emit_wizard_config_toml, template_payload::build_template_raw,
     },
-    config::{core::finish_raw_config, general::General, types::RawConfig},
+    config::{
+        core::{
+            finish_raw_config, parse_raw_config_str, validate_raw_structure,
+        },
+        general::General,
+        types::RawConfig,
+    },
     web::{
+        admin::require_admin,
         api::WebAppState,
-        artifacts::render_all,
+        artifacts::{check_artifacts, render_all},
         auth::session_user_from_jar,
         contracts::{
             ConfigSetListResponse, CreateDraftRequest, DraftDocumentEnvelope,
             DraftEnvelope, DraftListResponse, GenerateDraftResponse,
-            MetadataUpdateRequest, SaveDraftRequest, SaveDraftResponse,
-            WizardBackendNginxPayload, WizardDeploymentPayload,
-            WizardFrontendNginxPayload, WizardNginxPayload, WizardPayload,
+            ImportDraftRequest, ImportDraftResponse,
+            ImportRunningConfigRequest, MetadataUpdateRequest,
+            SaveDraftRequest, SaveDraftResponse, WizardBackendNginxPayload,
+            WizardDeploymentPayload, WizardFrontendNginxPayload,
+            WizardKubernetesPayload, WizardNginxPayload, WizardPayload,
             WizardPm2Payload, WizardSharedGeneral, WizardSharedPayload,
             WizardStreamMode, WizardSystemdPayload, WizardTemplateResponse,
         },
@@ -34,12 +47,15 @@ pub fn routes() -> Router<WebAppState> {
     Router::new()
         .route("/templates/{stream_mode}", get(get_wizard_template))
         .route("/", post(create_draft).get(list_drafts))
+        .route("/import", post(import_draft))
+        .route("/import/running", post(import_running_config))
         .route(
             "/{draft_id}",
             get(get_draft).put(save_draft).delete(delete_draft),
         )
         .route("/{draft_id}/metadata", patch(update_draft_metadata))
         .route("/{draft_id}/generate", post(generate_from_draft))
+        .merge(history::routes())
 }
 
 pub async fn list_config_sets(
@@ -70,6 +86,67 @@ pub async fn create_draft(
     Ok(Json(DraftEnvelope { draft }))
 }
 
+/// Creates a draft from an uploaded `config.toml`.
+pub async fn import_draft(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<ImportDraftRequest>,
+) -> Result<Json<ImportDraftResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let (wizard_payload, warnings) = import_config_toml(&payload.content)?;
+    let stream_mode = wizard_payload.stream_mode;
+    let name = normalize_name(payload.name, stream_mode);
+
+    let draft = state
+        .db
+        .create_draft(&user.id, name, stream_mode, wizard_payload)
+        .await?;
+    Ok(Json(ImportDraftResponse { draft, warnings }))
+}
+
+/// Creates a draft from the config file the gateway was started with. The
+/// file holds live credentials, so only admins may import it.
+pub async fn import_running_config(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<ImportRunningConfigRequest>,
+) -> Result<Json<ImportDraftResponse>, WebError> {
+    let admin = require_admin(&state, &jar).await?;
+    let config_path = state.config.main_config_path.clone().ok_or(
+        WebError::Conflict {
+            message: "The studio was not started with a gateway config file.",
+            field: None,
+        },
+    )?;
+    if !config_path.exists() {
+        return Err(WebError::NotFound("Gateway config file was not found."));
+    }
+
+    let content = fs::read_to_string(&config_path)?;
+    let (wizard_payload, warnings) = import_config_toml(&content)?;
+    let stream_mode = wizard_payload.stream_mode;
+    let name = normalize_name(payload.name, stream_mode);
+
+    let draft = state
+        .db
+        .create_draft(&admin.id, name, stream_mode, wizard_payload)
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(admin.id),
+            "import_running_config",
+            "draft",
+            Some(draft.id.clone()),
+            json!({
+                "config_path": config_path.display().to_string(),
+                "warnings": warnings,
+            }),
+        )
+        .await?;
+    Ok(Json(ImportDraftResponse { draft, warnings }))
+}
+
 pub async fn get_wizard_template(
     State(state): State<WebAppState>,
     jar: CookieJar,
@@ -175,6 +252,7 @@ pub async fn generate_from_draft(
     let config_toml = emit_wizard_config_toml(&raw)
         .map_err(|error| WebError::internal(error.to_string()))?;
     let rendered = render_all(&raw, &draft.payload, config_toml.clone());
+    let issues = check_artifacts(&raw, &draft.payload, &rendered);
 
     let response = state
         .db
@@ -186,6 +264,7 @@ pub async fn generate_from_draft(
             stream_mode: draft.stream_mode,
             config_toml,
             artifacts: rendered,
+            issues,
         })
         .await?;
 
@@ -211,6 +290,7 @@ pub fn raw_from_wizard_payload(payload: &WizardPayload) -> RawConfig {
         open_list: None,
         direct_link: None,
         fallback: payload.shared.fallback.clone(),
+        secrets: payload.shared.secrets.clone(),
     }
 }
 
@@ -229,6 +309,7 @@ pub fn wizard_payload_from_raw(raw: RawConfig) -> WizardPayload {
             user_agent: raw.user_agent,
             fallback: raw.fallback,
             http2: raw.http2.unwrap_or_default(),
+            secrets: raw.secrets,
         },
         frontend: raw.frontend,
         backend: raw.backend,
@@ -238,6 +319,89 @@ pub fn wizard_payload_from_raw(raw: RawConfig) -> WizardPayload {
     }
 }
 
+/// Keys the config parser accepts under a second name.
+const IMPORT_KEY_ALIASES: &[(&str, &str)] = &[
+    ("type", "backend_type"),
+    ("PathRewrite", "path_rewrites"),
+    ("AntiReverseProxy", "anti_reverse_proxy"),
+    ("Disk", "disk"),
+    ("OpenList", "open_list"),
+    ("DirectLink", "direct_link"),
+    ("GoogleDrive", "google_drive"),
+    ("WebDav", "webdav"),
+    ("Smb", "smb"),
+    ("Resilience", "resilience"),
+];
+
+/// Parses `content` into a wizard payload and lists every key of the file
+/// the payload does not carry, so the UI can say what the draft dropped.
+fn import_config_toml(
+    content: &str,
+) -> Result<(WizardPayload, Vec<String>), WebError> {
+    let raw = parse_raw_config_str(content)
+        .map_err(|error| WebError::ValidationFailed(error.to_string()))?;
+    validate_raw_structure(&raw)
+        .map_err(|error| WebError::ValidationFailed(error.to_string()))?;
+    let original: TomlValue = toml::from_str(content)
+        .map_err(|error| WebError::ValidationFailed(error.to_string()))?;
+
+    let payload = wizard_payload_from_raw(raw);
+    let kept = TomlValue::try_from(raw_from_wizard_payload(&payload))
+        .map_err(|error| WebError::internal(error.to_string()))?;
+    let mut warnings = Vec::new();
+    collect_dropped_keys(&original, &kept, "", &mut warnings);
+    Ok((payload, warnings))
+}
+
+fn collect_dropped_keys(
+    original: &TomlValue,
+    kept: &TomlValue,
+    path: &str,
+    dropped: &mut Vec<String>,
+) {
+    match (original, kept) {
+        (TomlValue::Table(original), TomlValue::Table(kept)) => {
+            for (key, value) in original {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                let kept_value = kept.get(key).or_else(|| {
+                    IMPORT_KEY_ALIASES
+                        .iter()
+                        .find(|(alias, _)| alias == key)
+                        .and_then(|(_, name)| kept.get(*name))
+                });
+                match kept_value {
+                    Some(kept_value) => collect_dropped_keys(
+                        value,
+                        kept_value,
+                        &child_path,
+                        dropped,
+                    ),
+                    // Empty strings are skipped when serializing.
+                    None if value.as_str() == Some("") => {}
+                    None => dropped.push(child_path),
+                }
+            }
+        }
+        (TomlValue::Array(original), TomlValue::Array(kept)) => {
+            for (index, (value, kept_value)) in
+                original.iter().zip(kept).enumerate()
+            {
+                collect_dropped_keys(
+                    value,
+                    kept_value,
+                    &format!("{path}[{index}]"),
+                    dropped,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
 fn clear_default_backend_nodes(mut payload: WizardPayload) -> WizardPayload {
     if payload.stream_mode != WizardStreamMode::Frontend {
         payload.backend_nodes.clear();
@@ -311,6 +475,12 @@ fn default_deployment_payload(
             out_file: format!("{pm2_working_directory}/logs/pm2.out.log"),
             error_file: format!("{pm2_working_directory}/logs/pm2.err.log"),
         },
+        kubernetes: WizardKubernetesPayload {
+            namespace: "embystream".to_string(),
+            image: "openpilipili/embystream:latest".to_string(),
+            ingress_class: "nginx".to_string(),
+            tls_secret_name: "embystream-tls".to_string(),
+        },
     }
 }
 
@@ -337,12 +507,12 @@ mod tests {
             emit::emit_wizard_config_toml, template_payload::build_template_raw,
         },
         config::general::StreamMode,
-        web::contracts::SaveDraftRequest,
+        web::contracts::{SaveDraftRequest, WizardStreamMode},
     };
 
     use super::{
-        clear_default_backend_nodes, raw_from_wizard_payload,
-        wizard_payload_from_raw,
+        clear_default_backend_nodes, import_config_toml,
+        raw_from_wizard_payload, wizard_payload_from_raw,
     };
 
     #[test]
@@ -453,6 +623,89 @@ mod tests {
         assert!(node.disk.is_some());
     }
 
+    #[test]
+    fn import_keeps_backend_nodes_and_reports_dropped_keys() {
+        let content = r#"
+[Log]
+level = "info"
+prefix = ""
+root_path = "./logs"
+
+[General]
+memory_mode = "middle"
+stream_mode = "backend"
+encipher_key = "1234567890123456"
+encipher_iv = "1234567890123456"
+
+[Emby]
+url = "http://127.0.0.1"
+port = "8096"
+token = "tok"
+
+[UserAgent]
+mode = "allow"
+allow_ua = []
+deny_ua = []
+
+[Backend]
+listen_port = 60001
+base_url = "https://backend.example.com"
+port = "443"
+path = ""
+problematic_clients = []
+
+[Disk]
+description = "legacy"
+
+[[BackendNode]]
+name = "Movies"
+type = "Disk"
+pattern = "/mnt/movies/.*"
+legacy_flag = true
+
+[[BackendNode.PathRewrite]]
+enable = true
+pattern = "^/mnt(/.*)$"
+replacement = "/data$1"
+"#;
+
+        let (payload, warnings) =
+            import_config_toml(content).expect("import config");
+
+        assert_eq!(payload.stream_mode, WizardStreamMode::Backend);
+        assert_eq!(payload.backend_nodes.len(), 1);
+        assert_eq!(payload.backend_nodes[0].backend_type, "Disk");
+        assert_eq!(payload.backend_nodes[0].path_rewrites.len(), 1);
+        assert_eq!(
+            warnings,
+            vec!["BackendNode[0].legacy_flag".to_string(), "Disk".to_string()]
+        );
+    }
+
+    #[test]
+    fn import_of_generated_templates_has_no_warnings() {
+        for stream_mode in
+            [StreamMode::Frontend, StreamMode::Backend, StreamMode::Dual]
+        {
+            let content =
+                emit_wizard_config_toml(&build_template_raw(stream_mode))
+                    .expect("emit template");
+            let (_, warnings) =
+                import_config_toml(&content).expect("import template");
+            assert!(warnings.is_empty(), "{warnings:?}");
+        }
+    }
+
+    #[test]
+    fn import_rejects_config_missing_mode_section() {
+        let mut raw = build_template_raw(StreamMode::Frontend);
+        raw.general.stream_mode = StreamMode::Backend;
+        let content = toml::to_string(&raw).expect("serialize config");
+
+        assert!(import_config_toml(&content).is_err());
+        assert!(import_config_toml("[General]\nstream_mode = 1").is_err());
+    }
+
     #[test]
     fn wizard_templates_start_with_empty_backend_nodes() {
         let backend = clear_default_backend_nodes(wizard_payload_from_raw(
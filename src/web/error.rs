@@ -11,7 +11,11 @@ use super::contracts::{ApiErrorDetail, ApiErrorResponse};
 #[derive(Debug)]
 pub enum WebError {
     Unauthorized(&'static str),
+    /// The password was right; a TOTP or recovery code is still needed.
+    SecondFactorRequired(&'static str),
     Forbidden(&'static str),
+    /// Admin policy requires enrolling TOTP before using the studio.
+    EnrollmentRequired(&'static str),
     RateLimited(&'static str),
     NotFound(&'static str),
     Conflict {
@@ -40,8 +44,12 @@ impl WebError {
 
     fn status_code(&self) -> StatusCode {
         match self {
-            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
-            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Unauthorized(_) | Self::SecondFactorRequired(_) => {
+                StatusCode::UNAUTHORIZED
+            }
+            Self::Forbidden(_) | Self::EnrollmentRequired(_) => {
+                StatusCode::FORBIDDEN
+            }
             Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
             Self::Conflict { .. } => StatusCode::CONFLICT,
@@ -54,7 +62,9 @@ impl WebError {
     fn code(&self) -> &'static str {
         match self {
             Self::Unauthorized(_) => "unauthorized",
+            Self::SecondFactorRequired(_) => "totp_required",
             Self::Forbidden(_) => "forbidden",
+            Self::EnrollmentRequired(_) => "totp_enrollment_required",
             Self::RateLimited(_) => "rate_limited",
             Self::NotFound(_) => "not_found",
             Self::Conflict { .. } => "conflict",
@@ -67,7 +77,9 @@ impl WebError {
     fn message(&self) -> &str {
         match self {
             Self::Unauthorized(message)
+            | Self::SecondFactorRequired(message)
             | Self::Forbidden(message)
+            | Self::EnrollmentRequired(message)
             | Self::RateLimited(message)
             | Self::NotFound(message) => message,
             Self::Conflict { message, .. }
@@ -0,0 +1,408 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Redirect,
+    routing::{get, post},
+};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::{
+        backend::GoogleDriveConfig,
+        core::{Config, persist_google_drive_token},
+        error::ConfigError,
+    },
+    oauthutil::OAuthToken,
+};
+
+use super::{
+    api::WebAppState,
+    auth::{request_origin, session_user_from_jar},
+    contracts::{
+        GoogleOAuthStartRequest, GoogleOAuthStartResponse, UserRole,
+        WizardPayload,
+    },
+    error::WebError,
+};
+
+const CALLBACK_PATH: &str = "/api/google-oauth/callback";
+const PENDING_STATE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// An authorization started from the studio and waiting for Google to
+/// redirect back with a code.
+#[derive(Debug, Clone)]
+pub struct PendingGoogleOAuth {
+    user_id: String,
+    draft_id: String,
+    node_uuid: String,
+    redirect_uri: String,
+    created_at: Instant,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GoogleOAuthCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/start", post(start_google_oauth))
+        .route("/callback", get(google_oauth_callback))
+}
+
+async fn start_google_oauth(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    headers: HeaderMap,
+    Json(payload): Json<GoogleOAuthStartRequest>,
+) -> Result<Json<GoogleOAuthStartResponse>, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let draft = state
+        .db
+        .get_draft(&user.id, &payload.draft_id)
+        .await?
+        .ok_or(WebError::NotFound("Draft was not found."))?;
+    let cfg = find_google_drive_node(&draft.payload, &payload.node_uuid)
+        .ok_or(WebError::NotFound("Google Drive node was not found."))?;
+    if cfg.client_id.trim().is_empty() || cfg.client_secret.trim().is_empty() {
+        return Err(WebError::invalid_input(
+            "client_id",
+            "Save the node's OAuth client ID and secret first.",
+        ));
+    }
+
+    let redirect_uri = callback_redirect_uri(&headers)?;
+    let oauth_state = Uuid::new_v4().to_string();
+    prune_expired_states(&state);
+    state.google_oauth_states.insert(
+        oauth_state.clone(),
+        PendingGoogleOAuth {
+            user_id: user.id,
+            draft_id: payload.draft_id,
+            node_uuid: cfg.node_uuid.clone(),
+            redirect_uri: redirect_uri.clone(),
+            created_at: Instant::now(),
+        },
+    );
+
+    let authorize_url = state.google_drive_client.authorization_url(
+        &cfg.client_id,
+        &redirect_uri,
+        &oauth_state,
+    );
+    Ok(Json(GoogleOAuthStartResponse {
+        authorize_url,
+        redirect_uri,
+    }))
+}
+
+async fn google_oauth_callback(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Query(params): Query<GoogleOAuthCallbackParams>,
+) -> Result<Redirect, WebError> {
+    let user = session_user_from_jar(&state, &jar).await?;
+    let (_, pending) = params
+        .state
+        .as_deref()
+        .and_then(|value| state.google_oauth_states.remove(value))
+        .filter(|(_, pending)| {
+            pending.user_id == user.id
+                && pending.created_at.elapsed() < PENDING_STATE_TTL
+        })
+        .ok_or(WebError::Forbidden(
+            "Google authorization state is missing or expired.",
+        ))?;
+
+    if params.error.is_some() {
+        return Ok(studio_redirect(&pending.draft_id, "denied"));
+    }
+    let code = params.code.filter(|value| !value.trim().is_empty()).ok_or(
+        WebError::invalid_input(
+            "code",
+            "Google did not return an authorization code.",
+        ),
+    )?;
+
+    let draft = state
+        .db
+        .get_draft(&user.id, &pending.draft_id)
+        .await?
+        .ok_or(WebError::NotFound("Draft was not found."))?;
+    let cfg = find_google_drive_node(&draft.payload, &pending.node_uuid)
+        .ok_or(WebError::NotFound("Google Drive node was not found."))?;
+    let draft_client_id = cfg.client_id.clone();
+    let exchanged = state
+        .google_drive_client
+        .exchange_authorization_code(
+            &cfg.client_id,
+            &cfg.client_secret,
+            &code,
+            &pending.redirect_uri,
+        )
+        .await
+        .map_err(|error| WebError::ValidationFailed(error.to_string()))?;
+    if exchanged.refresh_token.trim().is_empty() {
+        return Ok(studio_redirect(&pending.draft_id, "no_refresh_token"));
+    }
+
+    let token = OAuthToken::from_refresh_parts(
+        exchanged.access_token,
+        exchanged.refresh_token,
+        exchanged.token_type,
+        exchanged
+            .expires_in
+            .map(|secs| Utc::now() + chrono::Duration::seconds(secs as i64)),
+    );
+    let mut payload = draft.payload;
+    apply_google_drive_token(&mut payload, &pending.node_uuid, &token);
+    state
+        .db
+        .save_draft(&user.id, &pending.draft_id, draft.name, payload, 0)
+        .await?;
+
+    // Admins also refresh the running config when the node is deployed.
+    let mut persisted_live = false;
+    if user.role == UserRole::Admin
+        && let Some(config_path) = state.config.main_config_path.as_deref()
+        && is_deployed(config_path, &pending.node_uuid, &draft_client_id)
+    {
+        match persist_google_drive_token(
+            config_path,
+            &pending.node_uuid,
+            &token,
+        ) {
+            Ok(()) => persisted_live = true,
+            Err(ConfigError::MissingConfig(_)) => {}
+            Err(error) => return Err(WebError::internal(error.to_string())),
+        }
+    }
+
+    state
+        .db
+        .write_audit_log(
+            Some(user.id),
+            "connect_google_drive",
+            "draft",
+            Some(pending.draft_id.clone()),
+            json!({
+                "node_uuid": pending.node_uuid,
+                "persisted_live": persisted_live,
+            }),
+        )
+        .await?;
+
+    Ok(studio_redirect(&pending.draft_id, "connected"))
+}
+
+fn find_google_drive_node<'a>(
+    payload: &'a WizardPayload,
+    node_uuid: &str,
+) -> Option<&'a GoogleDriveConfig> {
+    let node_uuid = node_uuid.trim();
+    if node_uuid.is_empty() {
+        return None;
+    }
+    payload
+        .backend_nodes
+        .iter()
+        .filter_map(|node| node.google_drive.as_ref())
+        .find(|cfg| cfg.node_uuid.trim().eq_ignore_ascii_case(node_uuid))
+}
+
+/// Whether the live config runs this node with the same OAuth client. A
+/// draft that only shares the `node_uuid` with a live node, or switched it
+/// to another client, must not hand its token to the running gateway.
+fn is_deployed(config_path: &Path, node_uuid: &str, client_id: &str) -> bool {
+    let Ok(live) = Config::load_from_path(config_path) else {
+        return false;
+    };
+    let node_uuid = node_uuid.trim();
+    live.backend_nodes
+        .iter()
+        .filter_map(|node| node.google_drive.as_ref())
+        .any(|cfg| {
+            cfg.node_uuid.trim().eq_ignore_ascii_case(node_uuid)
+                && cfg.client_id.trim() == client_id.trim()
+        })
+}
+
+fn apply_google_drive_token(
+    payload: &mut WizardPayload,
+    node_uuid: &str,
+    token: &OAuthToken,
+) -> bool {
+    let node_uuid = node_uuid.trim();
+    let Some(cfg) = payload
+        .backend_nodes
+        .iter_mut()
+        .filter_map(|node| node.google_drive.as_mut())
+        .find(|cfg| cfg.node_uuid.trim().eq_ignore_ascii_case(node_uuid))
+    else {
+        return false;
+    };
+    cfg.apply_token(token.clone());
+    true
+}
+
+/// The callback URL on the host the admin is browsing, which must also be
+/// registered as an authorized redirect URI of the OAuth client.
+fn callback_redirect_uri(headers: &HeaderMap) -> Result<String, WebError> {
+    let origin = request_origin(headers).ok_or(WebError::invalid_input(
+        "host",
+        "Request host is required to build the OAuth callback URL.",
+    ))?;
+    Ok(format!("{origin}{CALLBACK_PATH}"))
+}
+
+fn studio_redirect(draft_id: &str, outcome: &str) -> Redirect {
+    let query = serde_urlencoded::to_string([
+        ("draftId", draft_id),
+        ("googleOAuth", outcome),
+    ])
+    .unwrap_or_default();
+    Redirect::to(&format!("/wizard?{query}"))
+}
+
+fn prune_expired_states(state: &WebAppState) {
+    state
+        .google_oauth_states
+        .retain(|_, pending| pending.created_at.elapsed() < PENDING_STATE_TTL);
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderMap, HeaderValue, header};
+
+    use crate::{
+        cli_wizard::template_payload::build_template_raw,
+        config::{
+            backend::{BackendNode, GoogleDriveConfig},
+            general::StreamMode,
+        },
+        oauthutil::OAuthToken,
+        test_support::TestConfig,
+        web::drafts::wizard_payload_from_raw,
+    };
+
+    use super::{
+        apply_google_drive_token, callback_redirect_uri,
+        find_google_drive_node, is_deployed,
+    };
+
+    #[test]
+    fn callback_redirect_uri_follows_forwarded_scheme_and_host() {
+        let mut headers = HeaderMap::new();
+        headers
+            .insert(header::HOST, HeaderValue::from_static("127.0.0.1:17172"));
+        assert_eq!(
+            callback_redirect_uri(&headers).expect("redirect uri"),
+            "http://127.0.0.1:17172/api/google-oauth/callback"
+        );
+
+        headers.insert(
+            "x-forwarded-host",
+            HeaderValue::from_static("studio.example.com"),
+        );
+        headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        assert_eq!(
+            callback_redirect_uri(&headers).expect("redirect uri"),
+            "https://studio.example.com/api/google-oauth/callback"
+        );
+    }
+
+    #[test]
+    fn token_is_stored_on_matching_google_drive_node() {
+        let mut payload =
+            wizard_payload_from_raw(build_template_raw(StreamMode::Backend));
+        payload.backend_nodes = vec![BackendNode {
+            name: "GoogleDrive".to_string(),
+            backend_type: "googleDrive".to_string(),
+            pattern: String::new(),
+            pattern_regex: None,
+            base_url: String::new(),
+            port: String::new(),
+            path: String::new(),
+            priority: 0,
+            proxy_mode: "proxy".to_string(),
+            client_speed_limit_kbs: 0,
+            client_burst_speed_kbs: 0,
+            path_rewrites: vec![],
+            anti_reverse_proxy: Default::default(),
+            path_rewriter_cache: vec![],
+            uuid: String::new(),
+            disk: None,
+            open_list: None,
+            direct_link: None,
+            google_drive: Some(GoogleDriveConfig {
+                node_uuid: "Node-1".to_string(),
+                client_id: "client-id".to_string(),
+                client_secret: "secret".to_string(),
+                ..Default::default()
+            }),
+            webdav: None,
+            smb: None,
+            resilience: None,
+        }];
+        let token = OAuthToken::from_refresh_parts(
+            "access".to_string(),
+            "refresh".to_string(),
+            "Bearer".to_string(),
+            None,
+        );
+
+        assert!(!apply_google_drive_token(&mut payload, "node-2", &token));
+        assert!(apply_google_drive_token(&mut payload, "node-1", &token));
+
+        let cfg = find_google_drive_node(&payload, " node-1 ")
+            .expect("google drive node");
+        assert_eq!(cfg.refresh_token, "refresh");
+        assert_eq!(
+            cfg.token.as_ref().map(|token| token.access_token.as_str()),
+            Some("access")
+        );
+    }
+
+    #[test]
+    fn only_deployed_nodes_with_the_same_client_count_as_live() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let config_path = temp_dir.path().join("config.toml");
+        let live = TestConfig::new()
+            .stream_mode("backend")
+            .section(
+                "[Backend]\nlisten_port = 60002\n\
+                 base_url = \"https://emby.example.com\"\nport = \"443\"\n\
+                 path = \"\"\nproblematic_clients = []\n\n\
+                 [[BackendNode]]\nname = \"Drive\"\n\
+                 type = \"googleDrive\"\npattern = \"/gd/.*\"\n\n\
+                 [BackendNode.GoogleDrive]\nnode_uuid = \"node-1\"\n\
+                 client_id = \"client-id\"\n\
+                 client_secret = \"secret\"\n\
+                 drive_id = \"drive-1\"\n\
+                 refresh_token = \"refresh\"\n",
+            )
+            .toml();
+        std::fs::write(&config_path, live).expect("write config");
+
+        assert!(is_deployed(&config_path, " Node-1 ", "client-id"));
+        assert!(!is_deployed(&config_path, "node-2", "client-id"));
+        assert!(!is_deployed(&config_path, "node-1", "other-client"));
+        assert!(!is_deployed(
+            &temp_dir.path().join("missing.toml"),
+            "node-1",
+            "client-id"
+        ));
+    }
+}
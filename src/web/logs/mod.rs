@@ -547,7 +547,7 @@ mod tests {
         app::WebRuntimeConfig,
         auth::{self, SESSION_COOKIE_NAME},
         contracts::{LogEntry, SessionUser, UserRole},
-        db::Database,
+        db::{Database, SessionMethod},
         drafts,
     };
 
@@ -654,6 +654,7 @@ mod tests {
                 stream_log_dir,
                 executable_path: tempdir.path().join("embystream"),
                 main_config_path: Some(tempdir.path().join("config.toml")),
+                oidc: None,
             },
             live_logs.clone(),
         );
@@ -681,6 +682,7 @@ mod tests {
                     email: admin.email,
                     role: UserRole::Admin,
                 },
+                SessionMethod::Password,
                 None,
                 None,
             )
@@ -1,12 +1,18 @@
 pub mod admin;
 pub mod api;
+pub mod api_tokens;
 pub mod app;
 pub mod artifacts;
 pub mod assets;
+pub mod audit;
 pub mod auth;
 pub mod backgrounds;
 pub mod contracts;
 pub mod db;
+pub mod deployments;
 pub mod drafts;
 pub mod error;
+pub mod google_oauth;
 pub mod logs;
+pub mod oidc;
+pub mod totp;
@@ -0,0 +1,884 @@
+//! OpenID Connect sign-in next to local accounts: discovery, the
+//! authorization code flow with PKCE, and claim based role mapping.
+
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use aws_lc_rs::digest;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::{HeaderMap, header},
+    response::Redirect,
+    routing::get,
+};
+use axum_extra::extract::{
+    CookieJar,
+    cookie::{Cookie, SameSite},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use rand::{Rng, distributions::Alphanumeric};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+use super::{
+    api::WebAppState,
+    api_tokens::sha256_hex,
+    auth::{
+        build_session_cookie, hash_password, request_origin,
+        request_uses_https, session_user_from_jar,
+    },
+    contracts::{OidcProviderResponse, SessionUser, UserRole},
+    db::{SessionMethod, UserRow},
+    error::WebError,
+};
+
+const CALLBACK_PATH: &str = "/api/auth/oidc/callback";
+/// Holds a hash of the pending `state`, so only the browser that started a
+/// sign-in can complete it.
+const STATE_COOKIE_NAME: &str = "embystream_oidc_state";
+const PENDING_STATE_TTL: Duration = Duration::from_secs(10 * 60);
+const CLOCK_SKEW_SECS: i64 = 60;
+const DEFAULT_SCOPES: &str = "openid profile email";
+const DEFAULT_ROLE_CLAIM: &str = "groups";
+
+/// Provider settings read from `WEB_OIDC_*` environment variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcSettings {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: String,
+    pub redirect_uri: Option<String>,
+    pub display_name: String,
+    /// Claim holding group or role names; dots walk nested objects, as in
+    /// Keycloak's `realm_access.roles`.
+    pub role_claim: String,
+    pub admin_groups: Vec<String>,
+    /// When set, only members of these (or the admin) groups may sign in.
+    pub allowed_groups: Vec<String>,
+    pub auto_provision: bool,
+}
+
+impl OidcSettings {
+    pub fn from_env() -> Result<Option<Self>, WebError> {
+        let Some(issuer) = env_value("WEB_OIDC_ISSUER") else {
+            return Ok(None);
+        };
+        let client_id =
+            env_value("WEB_OIDC_CLIENT_ID").ok_or(WebError::invalid_input(
+                "WEB_OIDC_CLIENT_ID",
+                "OIDC client ID is required when WEB_OIDC_ISSUER is set.",
+            ))?;
+        let auto_provision = match env_value("WEB_OIDC_AUTO_PROVISION")
+            .map(|value| value.to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("0" | "false" | "no" | "off") => false,
+            Some("1" | "true" | "yes" | "on") => true,
+            Some(_) => {
+                return Err(WebError::invalid_input(
+                    "WEB_OIDC_AUTO_PROVISION",
+                    "Use true or false.",
+                ));
+            }
+        };
+
+        Ok(Some(Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: env_value("WEB_OIDC_CLIENT_SECRET"),
+            scopes: env_value("WEB_OIDC_SCOPES")
+                .unwrap_or_else(|| DEFAULT_SCOPES.to_string()),
+            redirect_uri: env_value("WEB_OIDC_REDIRECT_URI"),
+            display_name: env_value("WEB_OIDC_DISPLAY_NAME")
+                .unwrap_or_else(|| "SSO".to_string()),
+            role_claim: env_value("WEB_OIDC_ROLE_CLAIM")
+                .unwrap_or_else(|| DEFAULT_ROLE_CLAIM.to_string()),
+            admin_groups: env_list("WEB_OIDC_ADMIN_GROUPS"),
+            allowed_groups: env_list("WEB_OIDC_ALLOWED_GROUPS"),
+            auto_provision,
+        }))
+    }
+
+    /// Role granted by the identity's claims; `None` when the allowed
+    /// groups exclude it.
+    fn role_for(&self, claims: &Map<String, Value>) -> Option<UserRole> {
+        let groups = claim_values(claims, &self.role_claim);
+        let member =
+            |wanted: &[String]| wanted.iter().any(|name| groups.contains(name));
+        if member(&self.admin_groups) {
+            Some(UserRole::Admin)
+        } else if self.allowed_groups.is_empty() || member(&self.allowed_groups)
+        {
+            Some(UserRole::User)
+        } else {
+            None
+        }
+    }
+}
+
+/// A sign-in started from the studio and waiting for the provider to
+/// redirect back with a code.
+#[derive(Debug, Clone)]
+pub struct PendingOidcLogin {
+    nonce: String,
+    code_verifier: String,
+    redirect_uri: String,
+    provider: ProviderMetadata,
+    /// Set when a signed-in local user links the identity to their account.
+    link_user_id: Option<String>,
+    created_at: Instant,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcLoginParams {
+    #[serde(default)]
+    pub link: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/provider", get(get_provider))
+        .route("/login", get(start_login))
+        .route("/callback", get(login_callback))
+}
+
+async fn get_provider(
+    State(state): State<WebAppState>,
+) -> Json<OidcProviderResponse> {
+    let settings = state.config.oidc.as_ref();
+    Json(OidcProviderResponse {
+        enabled: settings.is_some(),
+        display_name: settings.map(|settings| settings.display_name.clone()),
+    })
+}
+
+async fn start_login(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    headers: HeaderMap,
+    Query(params): Query<OidcLoginParams>,
+) -> Result<(CookieJar, Redirect), WebError> {
+    let settings = state
+        .config
+        .oidc
+        .as_ref()
+        .ok_or(WebError::NotFound("SSO is not configured."))?;
+    let link_user_id = if params.link {
+        Some(session_user_from_jar(&state, &jar).await?.id)
+    } else {
+        None
+    };
+
+    let provider = discover(&state, settings).await?;
+    let redirect_uri = match settings.redirect_uri.clone() {
+        Some(uri) => uri,
+        None => {
+            let origin =
+                request_origin(&headers).ok_or(WebError::invalid_input(
+                    "host",
+                    "Request host is required to build the SSO callback URL.",
+                ))?;
+            format!("{origin}{CALLBACK_PATH}")
+        }
+    };
+    let oauth_state = Uuid::new_v4().to_string();
+    let nonce = random_string(32);
+    let code_verifier = random_string(64);
+    let mut authorize_url = reqwest::Url::parse(
+        &provider.authorization_endpoint,
+    )
+    .map_err(|_| {
+        WebError::ValidationFailed(
+            "SSO provider returned an invalid authorization endpoint."
+                .to_string(),
+        )
+    })?;
+    authorize_url
+        .query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &settings.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", &settings.scopes)
+        .append_pair("state", &oauth_state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &pkce_challenge(&code_verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    prune_expired_states(&state);
+    let state_cookie = state_cookie(sha256_hex(&oauth_state), &headers);
+    state.oidc_states.insert(
+        oauth_state,
+        PendingOidcLogin {
+            nonce,
+            code_verifier,
+            redirect_uri,
+            provider,
+            link_user_id,
+            created_at: Instant::now(),
+        },
+    );
+    Ok((jar.add(state_cookie), Redirect::to(authorize_url.as_str())))
+}
+
+async fn login_callback(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    headers: HeaderMap,
+    Query(params): Query<OidcCallbackParams>,
+) -> Result<(CookieJar, Redirect), WebError> {
+    let settings = state
+        .config
+        .oidc
+        .as_ref()
+        .ok_or(WebError::NotFound("SSO is not configured."))?;
+    let started_here = |value: &str| {
+        jar.get(STATE_COOKIE_NAME)
+            .is_some_and(|cookie| cookie.value() == sha256_hex(value))
+    };
+    let pending = params
+        .state
+        .as_deref()
+        .filter(|value| started_here(value))
+        .and_then(|value| state.oidc_states.remove(value))
+        .map(|(_, pending)| pending)
+        .filter(|pending| pending.created_at.elapsed() < PENDING_STATE_TTL);
+    let jar = jar.remove(state_cookie(String::new(), &headers));
+    let Some(pending) = pending else {
+        return Ok((jar, outcome_redirect(None, "expired")));
+    };
+    let link_user_id = pending.link_user_id.as_deref();
+    if let Some(user_id) = link_user_id {
+        let linking_user = session_user_from_jar(&state, &jar).await.ok();
+        if linking_user.is_none_or(|user| user.id != user_id) {
+            return Ok((jar, outcome_redirect(link_user_id, "expired")));
+        }
+    }
+    if params.error.is_some() {
+        return Ok((jar, outcome_redirect(link_user_id, "denied")));
+    }
+    let Some(code) = params.code.filter(|value| !value.trim().is_empty())
+    else {
+        return Ok((jar, outcome_redirect(link_user_id, "denied")));
+    };
+
+    let claims = match exchange_code(&state, settings, &pending, &code).await {
+        Ok(claims) => claims,
+        Err(error) => {
+            state
+                .db
+                .write_audit_log(
+                    pending.link_user_id.clone(),
+                    "oidc_login_failed",
+                    "session",
+                    None,
+                    json!({ "issuer": settings.issuer, "error": error.to_string() }),
+                )
+                .await?;
+            return Ok((jar, outcome_redirect(link_user_id, "failed")));
+        }
+    };
+    let subject = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    if let Some(user_id) = pending.link_user_id {
+        let outcome = match state
+            .db
+            .link_oidc_identity(
+                pending.provider.issuer.clone(),
+                subject.clone(),
+                user_id.clone(),
+            )
+            .await
+        {
+            Ok(()) => "linked",
+            Err(WebError::Conflict { .. }) => "conflict",
+            Err(error) => return Err(error),
+        };
+        if outcome == "linked" {
+            state
+                .db
+                .write_audit_log(
+                    Some(user_id.clone()),
+                    "link_oidc_identity",
+                    "user",
+                    Some(user_id.clone()),
+                    json!({ "issuer": pending.provider.issuer, "subject": subject }),
+                )
+                .await?;
+        }
+        return Ok((jar, outcome_redirect(Some(&user_id), outcome)));
+    }
+
+    let Some(role) = settings.role_for(&claims) else {
+        return Ok((jar, outcome_redirect(None, "forbidden")));
+    };
+    let user = match state
+        .db
+        .find_user_by_oidc_identity(
+            pending.provider.issuer.clone(),
+            subject.clone(),
+        )
+        .await?
+    {
+        Some(row) if row.disabled => {
+            return Ok((jar, outcome_redirect(None, "disabled")));
+        }
+        Some(row) => sync_role(&state, settings, row, role).await?,
+        None if settings.auto_provision => {
+            provision_user(&state, &pending.provider.issuer, &claims, role)
+                .await?
+        }
+        None => return Ok((jar, outcome_redirect(None, "not_provisioned"))),
+    };
+
+    let session_id = state
+        .db
+        .create_session(
+            user.clone(),
+            SessionMethod::Oidc,
+            headers
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(ToString::to_string),
+            None,
+        )
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id.clone()),
+            "login",
+            "session",
+            Some(session_id.clone()),
+            json!({
+                "username": user.username,
+                "method": "oidc",
+                "issuer": pending.provider.issuer,
+                "at": Utc::now(),
+            }),
+        )
+        .await?;
+
+    let cookie = build_session_cookie(&session_id, &headers);
+    Ok((jar.add(cookie), Redirect::to("/drafts")))
+}
+
+async fn discover(
+    state: &WebAppState,
+    settings: &OidcSettings,
+) -> Result<ProviderMetadata, WebError> {
+    let url = format!("{}/.well-known/openid-configuration", settings.issuer);
+    let provider = state
+        .http_client
+        .get(&url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|error| {
+            WebError::ValidationFailed(format!(
+                "SSO provider discovery failed: {error}"
+            ))
+        })?
+        .json::<ProviderMetadata>()
+        .await
+        .map_err(|error| {
+            WebError::ValidationFailed(format!(
+                "SSO provider discovery document is invalid: {error}"
+            ))
+        })?;
+    if provider.issuer.trim_end_matches('/') != settings.issuer {
+        return Err(WebError::ValidationFailed(format!(
+            "SSO provider reports issuer '{}' instead of '{}'.",
+            provider.issuer, settings.issuer
+        )));
+    }
+    Ok(provider)
+}
+
+/// Redeems the code and returns the ID token claims merged with userinfo.
+/// The ID token comes straight from the token endpoint over the
+/// provider's TLS connection, which OIDC Core 3.1.3.7 accepts in place of
+/// a signature check; issuer, audience, expiry and nonce are still
+/// verified.
+async fn exchange_code(
+    state: &WebAppState,
+    settings: &OidcSettings,
+    pending: &PendingOidcLogin,
+    code: &str,
+) -> Result<Map<String, Value>, WebError> {
+    let mut request = state.http_client.post(&pending.provider.token_endpoint);
+    request = match settings.client_secret.as_deref() {
+        Some(secret) => request.basic_auth(&settings.client_id, Some(secret)),
+        None => request,
+    };
+    let tokens = request
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", pending.redirect_uri.as_str()),
+            ("client_id", settings.client_id.as_str()),
+            ("code_verifier", pending.code_verifier.as_str()),
+        ])
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|error| {
+            WebError::ValidationFailed(format!(
+                "SSO token exchange failed: {error}"
+            ))
+        })?
+        .json::<TokenResponse>()
+        .await
+        .map_err(|error| {
+            WebError::ValidationFailed(format!(
+                "SSO token response is invalid: {error}"
+            ))
+        })?;
+
+    let mut claims = decode_id_token(&tokens.id_token)?;
+    validate_id_token_claims(
+        &claims,
+        &pending.provider.issuer,
+        &settings.client_id,
+        &pending.nonce,
+    )?;
+
+    if let Some(userinfo_endpoint) =
+        pending.provider.userinfo_endpoint.as_deref()
+    {
+        let userinfo = state
+            .http_client
+            .get(userinfo_endpoint)
+            .bearer_auth(&tokens.access_token)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|error| {
+                WebError::ValidationFailed(format!(
+                    "SSO userinfo request failed: {error}"
+                ))
+            })?
+            .json::<Map<String, Value>>()
+            .await
+            .map_err(|error| {
+                WebError::ValidationFailed(format!(
+                    "SSO userinfo response is invalid: {error}"
+                ))
+            })?;
+        if userinfo.get("sub") != claims.get("sub") {
+            return Err(WebError::ValidationFailed(
+                "SSO userinfo belongs to a different subject.".to_string(),
+            ));
+        }
+        for (key, value) in userinfo {
+            claims.entry(key).or_insert(value);
+        }
+    }
+    Ok(claims)
+}
+
+fn decode_id_token(id_token: &str) -> Result<Map<String, Value>, WebError> {
+    let invalid =
+        || WebError::ValidationFailed("SSO ID token is malformed.".to_string());
+    let payload = id_token.split('.').nth(1).ok_or_else(invalid)?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|_| invalid())?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid())
+}
+
+fn validate_id_token_claims(
+    claims: &Map<String, Value>,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+) -> Result<(), WebError> {
+    let fail = |message: &str| Err(WebError::ValidationFailed(message.into()));
+    if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+        return fail("SSO ID token has an unexpected issuer.");
+    }
+    let audience_matches = match claims.get("aud") {
+        Some(Value::String(audience)) => audience == client_id,
+        Some(Value::Array(audiences)) => audiences
+            .iter()
+            .any(|audience| audience.as_str() == Some(client_id)),
+        _ => false,
+    };
+    if !audience_matches {
+        return fail("SSO ID token was issued for another client.");
+    }
+    let expires_at = claims.get("exp").and_then(Value::as_i64).unwrap_or(0);
+    if expires_at + CLOCK_SKEW_SECS <= Utc::now().timestamp() {
+        return fail("SSO ID token has expired.");
+    }
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return fail("SSO ID token nonce does not match this sign-in.");
+    }
+    if claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .is_none_or(str::is_empty)
+    {
+        return fail("SSO ID token has no subject.");
+    }
+    Ok(())
+}
+
+/// Applies the mapped role to a returning user. Roles only follow the
+/// provider when admin groups are configured.
+async fn sync_role(
+    state: &WebAppState,
+    settings: &OidcSettings,
+    row: UserRow,
+    role: UserRole,
+) -> Result<SessionUser, WebError> {
+    let mut user = SessionUser {
+        id: row.id,
+        username: row.username,
+        email: row.email,
+        role: row.role,
+    };
+    if !settings.admin_groups.is_empty() && user.role != role {
+        state.db.update_user_role(&user.id, role).await?;
+        state
+            .db
+            .write_audit_log(
+                None,
+                "sync_oidc_role",
+                "user",
+                Some(user.id.clone()),
+                json!({ "username": user.username, "role": role.as_db_value() }),
+            )
+            .await?;
+        user.role = role;
+    }
+    Ok(user)
+}
+
+async fn provision_user(
+    state: &WebAppState,
+    issuer: &str,
+    claims: &Map<String, Value>,
+    role: UserRole,
+) -> Result<SessionUser, WebError> {
+    let subject = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let mut email = claims
+        .get("email")
+        .and_then(Value::as_str)
+        .filter(|_| claims.get("email_verified") != Some(&Value::Bool(false)))
+        .map(str::to_string);
+    // Nobody knows this password; the account signs in through SSO.
+    let password_hash = hash_password(&random_string(48))?;
+    let base = username_from_claims(claims);
+    let mut candidates = (1..=9)
+        .map(|index| match index {
+            1 => base.clone(),
+            _ => format!("{base}-{index}"),
+        })
+        .chain(std::iter::once(format!("{base}-{}", random_string(6))));
+
+    let mut username = candidates.next().unwrap_or_default();
+    let user = loop {
+        match state
+            .db
+            .create_user(
+                username.clone(),
+                email.clone(),
+                password_hash.clone(),
+                role,
+            )
+            .await
+        {
+            Ok(user) => break user,
+            Err(WebError::Conflict {
+                field: Some("email"),
+                ..
+            }) => email = None,
+            Err(WebError::Conflict {
+                field: Some("username"),
+                ..
+            }) => {
+                username = candidates.next().ok_or(WebError::Conflict {
+                    message: "No free username for this SSO identity.",
+                    field: Some("username"),
+                })?;
+            }
+            Err(error) => return Err(error),
+        }
+    };
+
+    state
+        .db
+        .link_oidc_identity(
+            issuer.to_string(),
+            subject.to_string(),
+            user.id.clone(),
+        )
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id.clone()),
+            "provision_oidc_user",
+            "user",
+            Some(user.id.clone()),
+            json!({
+                "username": user.username,
+                "issuer": issuer,
+                "subject": subject,
+                "role": user.role.as_db_value(),
+            }),
+        )
+        .await?;
+    Ok(user)
+}
+
+/// `preferred_username`, else the email's local part, else the subject,
+/// reduced to characters safe in a studio username.
+fn username_from_claims(claims: &Map<String, Value>) -> String {
+    let claim = |key: &str| {
+        claims
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let raw = claim("preferred_username")
+        .or_else(|| claim("email").and_then(|email| email.split('@').next()))
+        .or_else(|| claim("sub"))
+        .unwrap_or("sso");
+    let mut username = raw
+        .chars()
+        .filter(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+        .take(32)
+        .collect::<String>();
+    while username.chars().count() < 3 {
+        username.push('_');
+    }
+    username
+}
+
+/// Strings found at a dotted claim path: a single string, an array of
+/// strings, or a space separated list.
+fn claim_values(claims: &Map<String, Value>, path: &str) -> Vec<String> {
+    let mut segments = path.split('.');
+    let mut value = segments.next().and_then(|first| claims.get(first));
+    for segment in segments {
+        value = value.and_then(|value| value.get(segment));
+    }
+    match value {
+        Some(Value::String(value)) => {
+            value.split_whitespace().map(str::to_string).collect()
+        }
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD
+        .encode(digest::digest(&digest::SHA256, code_verifier.as_bytes()))
+}
+
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+/// Sends the browser back to the page that started the flow, with the
+/// outcome in `sso` for the UI to report.
+fn outcome_redirect(link_user_id: Option<&str>, outcome: &str) -> Redirect {
+    let page = if link_user_id.is_some() {
+        "/account"
+    } else {
+        "/login"
+    };
+    Redirect::to(&format!("{page}?sso={outcome}"))
+}
+
+fn state_cookie(value: String, headers: &HeaderMap) -> Cookie<'static> {
+    Cookie::build((STATE_COOKIE_NAME, value))
+        .path("/api/auth/oidc")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(request_uses_https(headers))
+        .max_age(
+            PENDING_STATE_TTL
+                .try_into()
+                .expect("pending state TTL fits a cookie max-age"),
+        )
+        .build()
+}
+
+fn prune_expired_states(state: &WebAppState) {
+    state
+        .oidc_states
+        .retain(|_, pending| pending.created_at.elapsed() < PENDING_STATE_TTL);
+}
+
+fn env_value(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .or_else(|| env::var(name.to_ascii_lowercase()).ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    env_value(name)
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, Value, json};
+
+    use super::{
+        OidcSettings, claim_values, pkce_challenge, username_from_claims,
+        validate_id_token_claims,
+    };
+    use crate::web::contracts::UserRole;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().expect("claims object")
+    }
+
+    fn settings() -> OidcSettings {
+        OidcSettings {
+            issuer: "https://auth.example.org".into(),
+            client_id: "studio".into(),
+            client_secret: None,
+            scopes: "openid".into(),
+            redirect_uri: None,
+            display_name: "SSO".into(),
+            role_claim: "realm_access.roles".into(),
+            admin_groups: vec!["studio-admins".into()],
+            allowed_groups: vec!["studio-users".into()],
+            auto_provision: true,
+        }
+    }
+
+    #[test]
+    fn roles_follow_nested_group_claims() {
+        let settings = settings();
+        let with_roles = |roles: Value| {
+            settings.role_for(&claims(json!({
+                "realm_access": { "roles": roles }
+            })))
+        };
+
+        assert_eq!(
+            with_roles(json!(["studio-users", "studio-admins"])),
+            Some(UserRole::Admin)
+        );
+        assert_eq!(with_roles(json!(["studio-users"])), Some(UserRole::User));
+        assert_eq!(with_roles(json!(["other"])), None);
+        assert_eq!(
+            claim_values(&claims(json!({ "groups": "a b" })), "groups"),
+            vec!["a".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn id_token_claims_must_match_issuer_audience_and_nonce() {
+        let valid = json!({
+            "iss": "https://auth.example.org",
+            "aud": ["studio", "other"],
+            "sub": "user-1",
+            "nonce": "n-1",
+            "exp": chrono::Utc::now().timestamp() + 300,
+        });
+        let check = |claims_value: &Value| {
+            validate_id_token_claims(
+                &claims(claims_value.clone()),
+                "https://auth.example.org",
+                "studio",
+                "n-1",
+            )
+        };
+
+        assert!(check(&valid).is_ok());
+        for (key, value) in [
+            ("iss", json!("https://evil.example")),
+            ("aud", json!("other")),
+            ("nonce", json!("n-2")),
+            ("exp", json!(1)),
+        ] {
+            let mut invalid = valid.clone();
+            invalid[key] = value;
+            assert!(check(&invalid).is_err(), "{key} should be rejected");
+        }
+    }
+
+    #[test]
+    fn usernames_and_pkce_are_derived_safely() {
+        assert_eq!(
+            username_from_claims(&claims(json!({
+                "preferred_username": "Jane Doe!",
+                "sub": "1",
+            }))),
+            "JaneDoe"
+        );
+        assert_eq!(
+            username_from_claims(&claims(json!({
+                "email": "jo@example.org",
+                "sub": "1",
+            }))),
+            "jo_"
+        );
+        // RFC 7636 appendix B.
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Optional TOTP two-factor authentication for local sign-in, with
+//! single-use recovery codes. Administrators can make it mandatory for
+//! admin accounts; `web admin disable-two-factor` is the offline fallback.
+
+use axum::{
+    Json, Router,
+    extract::State,
+    routing::{get, post},
+};
+use axum_extra::extract::CookieJar;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rand::Rng;
+use serde_json::json;
+
+use crate::util::Totp;
+
+use super::{
+    api::WebAppState,
+    api_tokens::sha256_hex,
+    auth::{enrolling_session_user, verify_password},
+    contracts::{
+        DisableTotpRequest, SessionUser, TotpCodeRequest,
+        TotpRecoveryCodesResponse, TotpSetupResponse, TotpStatusResponse,
+        UserRole,
+    },
+    error::WebError,
+};
+
+const ISSUER: &str = "EmbyStream";
+const SECRET_BYTES: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_HALF_LENGTH: usize = 5;
+/// No 0/o or 1/l, so codes survive being read off paper.
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+pub fn routes() -> Router<WebAppState> {
+    Router::new()
+        .route("/", get(get_status))
+        .route("/setup", post(setup))
+        .route("/enable", post(enable))
+        .route("/disable", post(disable))
+        .route("/recovery-codes", post(regenerate_recovery_codes))
+}
+
+/// How a second factor was proven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SecondFactor {
+    Totp,
+    RecoveryCode,
+}
+
+impl SecondFactor {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Totp => "totp",
+            Self::RecoveryCode => "recovery_code",
+        }
+    }
+}
+
+/// Checks `code` as a current TOTP code for `secret`, then as one of the
+/// user's unused recovery codes. Accepted codes cannot be used again.
+pub(crate) async fn verify_second_factor(
+    state: &WebAppState,
+    user_id: &str,
+    secret: &str,
+    code: &str,
+) -> Result<Option<SecondFactor>, WebError> {
+    if let Some(step) = Totp::verify(secret, code, Totp::now_secs()) {
+        let fresh =
+            state.db.record_totp_step(user_id.to_string(), step).await?;
+        return Ok(fresh.then_some(SecondFactor::Totp));
+    }
+
+    let normalized = normalize_recovery_code(code);
+    if normalized.len() != RECOVERY_CODE_HALF_LENGTH * 2 {
+        return Ok(None);
+    }
+    let consumed = state
+        .db
+        .consume_recovery_code(user_id.to_string(), sha256_hex(&normalized))
+        .await?;
+    Ok(consumed.then_some(SecondFactor::RecoveryCode))
+}
+
+async fn get_status(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<TotpStatusResponse>, WebError> {
+    let (user, _) = enrolling_session_user(&state, &jar).await?;
+    let totp = state.db.totp_state(user.id.clone()).await?;
+    Ok(Json(TotpStatusResponse {
+        enabled: totp.secret.is_some(),
+        required: required_for(&state, &user).await?,
+        recovery_codes_remaining: totp.recovery_codes_remaining,
+    }))
+}
+
+async fn setup(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+) -> Result<Json<TotpSetupResponse>, WebError> {
+    let (user, _) = enrolling_session_user(&state, &jar).await?;
+    if state.db.totp_state(user.id.clone()).await?.secret.is_some() {
+        return Err(WebError::Conflict {
+            message: "Two-factor authentication is already enabled.",
+            field: None,
+        });
+    }
+
+    let secret = Totp::encode_base32(&rand::random::<[u8; SECRET_BYTES]>());
+    state
+        .db
+        .set_totp_pending_secret(user.id.clone(), secret.clone())
+        .await?;
+
+    let otpauth_uri = otpauth_uri(&user.username, &secret);
+    Ok(Json(TotpSetupResponse {
+        qr_svg: qr_svg(&otpauth_uri)?,
+        secret,
+        otpauth_uri,
+    }))
+}
+
+async fn enable(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<TotpRecoveryCodesResponse>, WebError> {
+    let (user, _) = enrolling_session_user(&state, &jar).await?;
+    let totp = state.db.totp_state(user.id.clone()).await?;
+    if totp.secret.is_some() {
+        return Err(WebError::Conflict {
+            message: "Two-factor authentication is already enabled.",
+            field: None,
+        });
+    }
+    let Some(secret) = totp.pending_secret else {
+        return Err(WebError::invalid_input(
+            "code",
+            "Start two-factor setup first.",
+        ));
+    };
+    let Some(step) = Totp::verify(&secret, &payload.code, Totp::now_secs())
+    else {
+        return Err(WebError::invalid_input(
+            "code",
+            "The code does not match. Check the authenticator app's clock.",
+        ));
+    };
+
+    let recovery_codes = generate_recovery_codes();
+    state
+        .db
+        .enable_totp(
+            user.id.clone(),
+            secret,
+            step,
+            hash_recovery_codes(&recovery_codes),
+        )
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id.clone()),
+            "enable_totp",
+            "user",
+            Some(user.id),
+            json!({ "username": user.username }),
+        )
+        .await?;
+
+    Ok(Json(TotpRecoveryCodesResponse { recovery_codes }))
+}
+
+async fn disable(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<DisableTotpRequest>,
+) -> Result<Json<TotpStatusResponse>, WebError> {
+    let (user, _) = enrolling_session_user(&state, &jar).await?;
+    if required_for(&state, &user).await? {
+        return Err(WebError::Forbidden(
+            "Two-factor authentication is required for administrators.",
+        ));
+    }
+    let user_row = state
+        .db
+        .find_user_row_by_id(user.id.clone())
+        .await?
+        .ok_or(WebError::Unauthorized("Session is not valid."))?;
+    if !verify_password(&payload.password, &user_row.password_hash)? {
+        return Err(WebError::invalid_input(
+            "password",
+            "Current password is incorrect.",
+        ));
+    }
+    let Some(secret) = state.db.totp_state(user.id.clone()).await?.secret
+    else {
+        return Err(WebError::invalid_input(
+            "code",
+            "Two-factor authentication is not enabled.",
+        ));
+    };
+    let Some(method) =
+        verify_second_factor(&state, &user.id, &secret, &payload.code).await?
+    else {
+        return Err(WebError::invalid_input(
+            "code",
+            "Invalid two-factor code.",
+        ));
+    };
+
+    state.db.disable_totp(user.id.clone()).await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id.clone()),
+            "disable_totp",
+            "user",
+            Some(user.id),
+            json!({ "username": user.username, "method": method.as_str() }),
+        )
+        .await?;
+
+    Ok(Json(TotpStatusResponse {
+        enabled: false,
+        required: false,
+        recovery_codes_remaining: 0,
+    }))
+}
+
+async fn regenerate_recovery_codes(
+    State(state): State<WebAppState>,
+    jar: CookieJar,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<TotpRecoveryCodesResponse>, WebError> {
+    let (user, _) = enrolling_session_user(&state, &jar).await?;
+    let Some(secret) = state.db.totp_state(user.id.clone()).await?.secret
+    else {
+        return Err(WebError::invalid_input(
+            "code",
+            "Two-factor authentication is not enabled.",
+        ));
+    };
+    if verify_second_factor(&state, &user.id, &secret, &payload.code)
+        .await?
+        .is_none()
+    {
+        return Err(WebError::invalid_input(
+            "code",
+            "Invalid two-factor code.",
+        ));
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    state
+        .db
+        .replace_recovery_codes(
+            user.id.clone(),
+            hash_recovery_codes(&recovery_codes),
+        )
+        .await?;
+    state
+        .db
+        .write_audit_log(
+            Some(user.id.clone()),
+            "regenerate_recovery_codes",
+            "user",
+            Some(user.id),
+            json!({ "username": user.username }),
+        )
+        .await?;
+
+    Ok(Json(TotpRecoveryCodesResponse { recovery_codes }))
+}
+
+async fn required_for(
+    state: &WebAppState,
+    user: &SessionUser,
+) -> Result<bool, WebError> {
+    Ok(user.role == UserRole::Admin
+        && state.db.totp_required_for_admins().await?)
+}
+
+fn otpauth_uri(username: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{ISSUER}:{}?secret={secret}&issuer={ISSUER}",
+        utf8_percent_encode(username, NON_ALPHANUMERIC)
+    )
+}
+
+fn qr_svg(data: &str) -> Result<String, WebError> {
+    let code = qrcode::QrCode::new(data.as_bytes())
+        .map_err(|error| WebError::internal(error.to_string()))?;
+    Ok(code
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
+
+fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let mut half = || {
+        (0..RECOVERY_CODE_HALF_LENGTH)
+            .map(|_| {
+                char::from(
+                    RECOVERY_CODE_ALPHABET
+                        [rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())],
+                )
+            })
+            .collect::<String>()
+    };
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| format!("{}-{}", half(), half()))
+        .collect()
+}
+
+fn hash_recovery_codes(codes: &[String]) -> Vec<String> {
+    codes
+        .iter()
+        .map(|code| sha256_hex(&normalize_recovery_code(code)))
+        .collect()
+}
+
+/// Recovery codes are compared without dashes, spaces or case.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        RECOVERY_CODE_COUNT, generate_recovery_codes, normalize_recovery_code,
+        otpauth_uri,
+    };
+
+    #[test]
+    fn recovery_codes_normalize_from_display_form() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), 11);
+            assert_eq!(&code[5..6], "-");
+        }
+        assert_eq!(normalize_recovery_code(" AbCde-fGh23 "), "abcdefgh23");
+    }
+
+    #[test]
+    fn otpauth_uri_encodes_the_account_label() {
+        assert_eq!(
+            otpauth_uri("ops team", "JBSWY3DP"),
+            "otpauth://totp/EmbyStream:ops%20team?secret=JBSWY3DP&issuer=EmbyStream"
+        );
+    }
+}
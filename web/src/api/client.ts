@@ -1,30 +1,54 @@
 import type {
+  ApiTokenListResponse,
+  AuditLogFilters,
+  AuditLogListResponse,
+  AuditRetentionResponse,
+  CreateApiTokenRequest,
+  CreateApiTokenResponse,
   UserEnvelope,
   UserListResponse,
   ArtifactListResponse,
   AuthResponse,
+  CircuitBreakerListResponse,
   ConfigSetEnvelope,
   ConfigSetListResponse,
+  DeploymentEnvelope,
+  DeploymentListResponse,
   DraftDocumentEnvelope,
   DraftEnvelope,
   DraftListResponse,
+  DraftRevisionDiffResponse,
+  DraftRevisionListResponse,
   GenerateDraftResponse,
+  GoogleOAuthStartResponse,
+  ImportDraftResponse,
   LoginBackgroundResponse,
+  LoginRequest,
   LogListResponse,
   LogoutResponse,
+  OidcProviderResponse,
   RegistrationSettingsResponse,
   RegisterRequest,
   SaveDraftRequest,
   SystemMetricsResponse,
+  TotpRecoveryCodesResponse,
+  TotpSetupResponse,
+  TotpStatusResponse,
+  TwoFactorPolicyResponse,
   UpdateRegistrationSettingsRequest,
+  UpdateTwoFactorPolicyRequest,
   WizardTemplateResponse,
 } from "./types";
 import {
   ADMIN_API,
+  API_TOKENS_API,
+  AUDIT_API,
   AUTH_API,
   BACKGROUNDS_API,
   CONFIG_SETS_API,
+  DEPLOYMENTS_API,
   DRAFTS_API,
+  GOOGLE_OAUTH_API,
   LOGS_API,
   buildApiPath,
   buildWebSocketPath,
@@ -89,7 +113,7 @@ export function getRegistrationSettings() {
   );
 }
 
-export function login(payload: { login: string; password: string }) {
+export function login(payload: LoginRequest) {
   return request<AuthResponse>(AUTH_API.login(), {
     method: "POST",
     body: JSON.stringify(payload),
@@ -112,6 +136,73 @@ export function changeOwnPassword(payload: {
   });
 }
 
+export function listApiTokens() {
+  return request<ApiTokenListResponse>(API_TOKENS_API.list(), {
+    method: "GET",
+  });
+}
+
+export function createApiToken(payload: CreateApiTokenRequest) {
+  return request<CreateApiTokenResponse>(API_TOKENS_API.create(), {
+    method: "POST",
+    body: JSON.stringify(payload),
+  });
+}
+
+export function revokeApiToken(tokenId: string) {
+  return request<LogoutResponse>(API_TOKENS_API.revoke(tokenId), {
+    method: "DELETE",
+  });
+}
+
+export function getTotpStatus() {
+  return request<TotpStatusResponse>(AUTH_API.totpStatus(), {
+    method: "GET",
+  });
+}
+
+export function startTotpSetup() {
+  return request<TotpSetupResponse>(AUTH_API.totpSetup(), {
+    method: "POST",
+  });
+}
+
+export function enableTotp(code: string) {
+  return request<TotpRecoveryCodesResponse>(AUTH_API.totpEnable(), {
+    method: "POST",
+    body: JSON.stringify({ code }),
+  });
+}
+
+export function disableTotp(payload: { password: string; code: string }) {
+  return request<TotpStatusResponse>(AUTH_API.totpDisable(), {
+    method: "POST",
+    body: JSON.stringify(payload),
+  });
+}
+
+export function regenerateRecoveryCodes(code: string) {
+  return request<TotpRecoveryCodesResponse>(AUTH_API.totpRecoveryCodes(), {
+    method: "POST",
+    body: JSON.stringify({ code }),
+  });
+}
+
+export function getOidcProvider() {
+  return request<OidcProviderResponse>(AUTH_API.oidcProvider(), {
+    method: "GET",
+  });
+}
+
+/**
+ * Full-page URL that starts SSO; `link` attaches the identity to the
+ * signed-in account instead of signing in.
+ */
+export function oidcLoginUrl(link = false) {
+  const path = buildApiPath(AUTH_API.oidcLogin());
+  return link ? `${path}?link=true` : path;
+}
+
 export function getCurrentUser() {
   return request<AuthResponse>(AUTH_API.currentUser(), {
     method: "GET",
@@ -132,6 +223,20 @@ export function createDraft(payload: {
   });
 }
 
+export function importDraft(payload: { name: string; content: string }) {
+  return request<ImportDraftResponse>(DRAFTS_API.importConfig(), {
+    method: "POST",
+    body: JSON.stringify(payload),
+  });
+}
+
+export function importRunningConfig(payload: { name: string }) {
+  return request<ImportDraftResponse>(DRAFTS_API.importRunning(), {
+    method: "POST",
+    body: JSON.stringify(payload),
+  });
+}
+
 export function getDraftTemplate(streamMode: "frontend" | "backend" | "dual") {
   return request<WizardTemplateResponse>(DRAFTS_API.template(streamMode), {
     method: "GET",
@@ -159,12 +264,48 @@ export function saveDraft(draftId: string, payload: SaveDraftRequest) {
   });
 }
 
+export function listDraftRevisions(draftId: string) {
+  return request<DraftRevisionListResponse>(DRAFTS_API.revisions(draftId), {
+    method: "GET",
+  });
+}
+
+export function diffDraftRevisions(
+  draftId: string,
+  from: number,
+  to?: number,
+) {
+  const query = new URLSearchParams({ from: String(from) });
+  if (to !== undefined) {
+    query.set("to", String(to));
+  }
+  return request<DraftRevisionDiffResponse>(
+    `${DRAFTS_API.revisionDiff(draftId)}?${query.toString()}`,
+    { method: "GET" },
+  );
+}
+
+export function restoreDraftRevision(draftId: string, revision: number) {
+  return request<{
+    draft: { id: string; updated_at: string; server_revision: number };
+  }>(DRAFTS_API.restoreRevision(draftId, revision), {
+    method: "POST",
+  });
+}
+
 export function generateDraft(draftId: string) {
   return request<GenerateDraftResponse>(DRAFTS_API.generate(draftId), {
     method: "POST",
   });
 }
 
+export function startGoogleOAuth(draftId: string, nodeUuid: string) {
+  return request<GoogleOAuthStartResponse>(GOOGLE_OAUTH_API.start(), {
+    method: "POST",
+    body: JSON.stringify({ draft_id: draftId, node_uuid: nodeUuid }),
+  });
+}
+
 export function updateDraftMetadata(
   draftId: string,
   payload: { name: string },
@@ -199,6 +340,24 @@ export function deleteConfigSet(configSetId: string) {
   });
 }
 
+export function deployConfigSet(configSetId: string) {
+  return request<DeploymentEnvelope>(CONFIG_SETS_API.deploy(configSetId), {
+    method: "POST",
+  });
+}
+
+export function listDeployments() {
+  return request<DeploymentListResponse>(DEPLOYMENTS_API.list(), {
+    method: "GET",
+  });
+}
+
+export function rollbackDeployment(deploymentId: string) {
+  return request<DeploymentEnvelope>(DEPLOYMENTS_API.rollback(deploymentId), {
+    method: "POST",
+  });
+}
+
 export function updateConfigSetMetadata(
   configSetId: string,
   payload: { name: string },
@@ -248,6 +407,61 @@ export function getSystemMetrics() {
   });
 }
 
+export function listCircuitBreakers() {
+  return request<CircuitBreakerListResponse>(ADMIN_API.circuitBreakers(), {
+    method: "GET",
+  });
+}
+
+function auditQuery(filters: AuditLogFilters, extra: Record<string, string>) {
+  const query = new URLSearchParams(extra);
+  for (const [key, value] of Object.entries(filters)) {
+    if (value) {
+      query.set(key, value);
+    }
+  }
+  return query.toString();
+}
+
+export function listAuditLogs(
+  filters: AuditLogFilters,
+  page: number,
+  pageSize: number,
+) {
+  const query = auditQuery(filters, {
+    page: String(page),
+    page_size: String(pageSize),
+  });
+  return request<AuditLogListResponse>(`${AUDIT_API.list()}?${query}`, {
+    method: "GET",
+  });
+}
+
+/**
+ * Download URL for the filtered audit log; the browser sends the session
+ * cookie with the navigation.
+ */
+export function auditExportUrl(
+  filters: AuditLogFilters,
+  format: "csv" | "json",
+) {
+  const query = auditQuery(filters, { format });
+  return `${buildApiPath(AUDIT_API.export())}?${query}`;
+}
+
+export function getAuditRetention() {
+  return request<AuditRetentionResponse>(ADMIN_API.auditRetention(), {
+    method: "GET",
+  });
+}
+
+export function updateAuditRetention(retentionDays: number | null) {
+  return request<AuditRetentionResponse>(ADMIN_API.auditRetention(), {
+    method: "PATCH",
+    body: JSON.stringify({ retention_days: retentionDays }),
+  });
+}
+
 export function listUsers() {
   return request<UserListResponse>(ADMIN_API.users(), {
     method: "GET",
@@ -266,6 +480,19 @@ export function updateRegistrationSettings(
   );
 }
 
+export function getTwoFactorPolicy() {
+  return request<TwoFactorPolicyResponse>(ADMIN_API.twoFactorPolicy(), {
+    method: "GET",
+  });
+}
+
+export function updateTwoFactorPolicy(payload: UpdateTwoFactorPolicyRequest) {
+  return request<TwoFactorPolicyResponse>(ADMIN_API.twoFactorPolicy(), {
+    method: "PATCH",
+    body: JSON.stringify(payload),
+  });
+}
+
 export function updateUserRole(userId: string, role: "admin" | "user") {
   return request<UserEnvelope>(ADMIN_API.userRole(userId), {
     method: "PATCH",
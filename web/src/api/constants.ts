@@ -31,6 +31,22 @@ export const AUTH_API = {
   logout: () => "auth/logout",
   password: () => "auth/password",
   currentUser: () => "auth/me",
+  oidcProvider: () => "auth/oidc/provider",
+  oidcLogin: () => "auth/oidc/login",
+  totpStatus: () => "auth/totp",
+  totpSetup: () => "auth/totp/setup",
+  totpEnable: () => "auth/totp/enable",
+  totpDisable: () => "auth/totp/disable",
+  totpRecoveryCodes: () => "auth/totp/recovery-codes",
+} as const;
+
+/**
+ * Personal API tokens for scripting the studio.
+ */
+export const API_TOKENS_API = {
+  list: () => "tokens",
+  create: () => "tokens",
+  revoke: (tokenId: string) => `tokens/${tokenId}`,
 } as const;
 
 /**
@@ -39,6 +55,8 @@ export const AUTH_API = {
 export const DRAFTS_API = {
   list: () => "drafts",
   create: () => "drafts",
+  importConfig: () => "drafts/import",
+  importRunning: () => "drafts/import/running",
   template: (streamMode: "frontend" | "backend" | "dual") =>
     `drafts/templates/${streamMode}`,
   detail: (draftId: string) => `drafts/${draftId}`,
@@ -46,6 +64,17 @@ export const DRAFTS_API = {
   remove: (draftId: string) => `drafts/${draftId}`,
   generate: (draftId: string) => `drafts/${draftId}/generate`,
   metadata: (draftId: string) => `drafts/${draftId}/metadata`,
+  revisions: (draftId: string) => `drafts/${draftId}/revisions`,
+  revisionDiff: (draftId: string) => `drafts/${draftId}/revisions/diff`,
+  restoreRevision: (draftId: string, revision: number) =>
+    `drafts/${draftId}/revisions/${revision}/restore`,
+} as const;
+
+/**
+ * Google Drive authorization-code flow for draft nodes.
+ */
+export const GOOGLE_OAUTH_API = {
+  start: () => "google-oauth/start",
 } as const;
 
 /**
@@ -61,6 +90,12 @@ export const CONFIG_SETS_API = {
   duplicate: (configSetId: string) => `config-sets/${configSetId}/duplicate`,
   remove: (configSetId: string) => `config-sets/${configSetId}`,
   metadata: (configSetId: string) => `config-sets/${configSetId}/metadata`,
+  deploy: (configSetId: string) => `config-sets/${configSetId}/deploy`,
+} as const;
+
+export const DEPLOYMENTS_API = {
+  list: () => "deployments",
+  rollback: (deploymentId: string) => `deployments/${deploymentId}/rollback`,
 } as const;
 
 /**
@@ -78,13 +113,24 @@ export const LOGS_API = {
   stream: () => "logs/stream",
 } as const;
 
+/**
+ * Admin audit log browsing and export.
+ */
+export const AUDIT_API = {
+  list: () => "audit",
+  export: () => "audit/export",
+} as const;
+
 /**
  * Admin-only system and user management endpoints.
  */
 export const ADMIN_API = {
   system: () => "admin/system",
+  circuitBreakers: () => "admin/circuit-breakers",
   users: () => "admin/users",
   registrationSettings: () => "admin/settings/registration",
+  twoFactorPolicy: () => "admin/settings/two-factor",
+  auditRetention: () => "admin/settings/audit-retention",
   userRole: (userId: string) => `admin/users/${userId}/role`,
   userDisabled: (userId: string) => `admin/users/${userId}/disabled`,
   userPassword: (userId: string) => `admin/users/${userId}/password`,
@@ -6,7 +6,15 @@ export type ArtifactType =
   | "nginx_conf"
   | "docker_compose"
   | "systemd_service"
-  | "pm2_config";
+  | "pm2_config"
+  | "kubernetes_deployment"
+  | "kubernetes_service"
+  | "kubernetes_config_map"
+  | "kubernetes_secret"
+  | "kubernetes_ingress"
+  | "kubernetes_values"
+  | "caddyfile"
+  | "traefik_dynamic";
 
 export interface SessionUser {
   id: string;
@@ -27,6 +35,18 @@ export interface AdminUserSummary {
 
 export interface AuthResponse {
   user: SessionUser;
+  totp_enrollment_required: boolean;
+}
+
+export interface LoginRequest {
+  login: string;
+  password: string;
+  totp_code?: string | null;
+}
+
+export interface OidcProviderResponse {
+  enabled: boolean;
+  display_name: string | null;
 }
 
 export interface RegistrationSettingsResponse {
@@ -68,7 +88,8 @@ export type BackendNodeType =
   | "OpenList"
   | "DirectLink"
   | "googleDrive"
-  | "WebDav";
+  | "WebDav"
+  | "Smb";
 
 export interface DiskNodeConfig {
   description: string;
@@ -78,22 +99,44 @@ export interface OpenListNodeConfig {
   base_url: string;
   port: string;
   token: string;
+  username?: string;
+  password?: string;
+  otp_secret?: string;
 }
 
 export interface DirectLinkNodeConfig {
   user_agent: string;
 }
 
+export type GoogleDriveRoot = "shared_drive" | "my_drive" | "folder";
+
 export interface GoogleDriveNodeConfig {
   node_uuid: string;
   client_id: string;
   client_secret: string;
   drive_id: string;
   drive_name: string;
+  root?: GoogleDriveRoot;
+  root_folder_id?: string;
+  service_account_files?: string[];
+  service_account_subject?: string;
+  folder_index?: boolean;
+  token_secret?: string;
   access_token: string;
   refresh_token: string;
 }
 
+export type SecretsProvider = "encrypted_file" | "env" | "directory";
+
+export interface SecretsConfig {
+  provider?: SecretsProvider;
+  file?: string;
+  key_file?: string;
+  key_env?: string;
+  env_prefix?: string;
+  directory?: string;
+}
+
 export interface WebDavNodeConfig {
   url_mode: string;
   node_uuid: string;
@@ -104,6 +147,15 @@ export interface WebDavNodeConfig {
   user_agent: string;
 }
 
+export interface SmbNodeConfig {
+  share: string;
+  username: string;
+  password: string;
+  domain: string;
+  timeout_secs: number;
+  reconnect_attempts: number;
+}
+
 export interface BackendNodeConfig {
   name: string;
   backend_type: BackendNodeType | string;
@@ -122,6 +174,7 @@ export interface BackendNodeConfig {
   direct_link: DirectLinkNodeConfig | null;
   google_drive: GoogleDriveNodeConfig | null;
   webdav: WebDavNodeConfig | null;
+  smb: SmbNodeConfig | null;
 }
 
 export interface NginxFrontendConfig {
@@ -164,9 +217,17 @@ export interface Pm2DeploymentConfig {
   error_file: string;
 }
 
+export interface KubernetesDeploymentConfig {
+  namespace: string;
+  image: string;
+  ingress_class: string;
+  tls_secret_name: string;
+}
+
 export interface DeploymentConfigPayload {
   systemd: SystemdDeploymentConfig;
   pm2: Pm2DeploymentConfig;
+  kubernetes: KubernetesDeploymentConfig;
 }
 
 export interface WizardPayload {
@@ -199,6 +260,7 @@ export interface WizardPayload {
       ssl_cert_file: string;
       ssl_key_file: string;
     };
+    secrets?: SecretsConfig;
   };
   frontend: null | {
     listen_port: number;
@@ -229,6 +291,45 @@ export interface DraftEnvelope {
   draft: DraftSummary;
 }
 
+export interface ImportDraftResponse {
+  draft: DraftSummary;
+  warnings: string[];
+}
+
+export interface DraftRevisionSummary {
+  revision: number;
+  name: string;
+  stream_mode: StreamMode;
+  author: string | null;
+  restored_from: number | null;
+  created_at: string;
+}
+
+export interface DraftRevisionListResponse {
+  current_revision: number;
+  items: DraftRevisionSummary[];
+}
+
+export type DraftChangeKind = "added" | "removed" | "modified";
+
+export interface DraftFieldChange {
+  path: string;
+  before: unknown;
+  after: unknown;
+}
+
+export interface DraftSectionDiff {
+  section: string;
+  change: DraftChangeKind;
+  fields: DraftFieldChange[];
+}
+
+export interface DraftRevisionDiffResponse {
+  from_revision: number;
+  to_revision: number;
+  sections: DraftSectionDiff[];
+}
+
 export interface MetadataUpdateRequest {
   name: string;
 }
@@ -283,8 +384,29 @@ export interface ArtifactDocument {
   content: string;
 }
 
+export type ArtifactIssueSeverity = "warning" | "error";
+
+export type ArtifactIssueCode =
+  | "port_collision"
+  | "port_not_published"
+  | "upstream_port_mismatch"
+  | "emby_not_local"
+  | "accel_location_missing"
+  | "accel_location_stale"
+  | "accel_unsupported"
+  | "tls_path_not_mounted"
+  | "stream_relay_loop";
+
+export interface ArtifactIssue {
+  severity: ArtifactIssueSeverity;
+  code: ArtifactIssueCode;
+  artifact_type: ArtifactType;
+  message: string;
+}
+
 export interface ArtifactListResponse {
   items: ArtifactDocument[];
+  issues: ArtifactIssue[];
 }
 
 export interface GenerateDraftResponse {
@@ -293,6 +415,7 @@ export interface GenerateDraftResponse {
     artifact_type: ArtifactType;
     file_name: string;
   }>;
+  issues: ArtifactIssue[];
 }
 
 export interface LogListResponse {
@@ -305,6 +428,39 @@ export interface LogListResponse {
   next_cursor?: string | null;
 }
 
+export interface AuditLogEntry {
+  id: string;
+  created_at: string;
+  user_id: string | null;
+  username: string | null;
+  action: string;
+  target_type: string;
+  target_id: string | null;
+  detail: Record<string, unknown>;
+}
+
+export interface AuditLogListResponse {
+  items: AuditLogEntry[];
+  total: number;
+  page: number;
+  page_size: number;
+  actions: string[];
+  target_types: string[];
+}
+
+export interface AuditLogFilters {
+  user?: string;
+  action?: string;
+  target_type?: string;
+  target_id?: string;
+  from?: string;
+  to?: string;
+}
+
+export interface AuditRetentionResponse {
+  retention_days: number | null;
+}
+
 export interface LogStreamReplayMessage {
   kind: "replay";
   items: LogListResponse["items"];
@@ -329,6 +485,14 @@ export interface UpdateRegistrationSettingsRequest {
   registration_enabled: boolean;
 }
 
+export interface TwoFactorPolicyResponse {
+  required_for_admins: boolean;
+}
+
+export interface UpdateTwoFactorPolicyRequest {
+  required_for_admins: boolean;
+}
+
 export interface SystemMetricsResponse {
   cpu_usage_percent: number;
   cpu_core_count: number;
@@ -341,6 +505,103 @@ export interface SystemMetricsResponse {
   uptime_seconds: number;
 }
 
+export type DeploymentKind = "deploy" | "rollback";
+
+export interface DeploymentSummary {
+  id: string;
+  kind: DeploymentKind;
+  config_set_id: string | null;
+  config_set_name: string;
+  config_path: string;
+  deployed_by: string | null;
+  has_backup: boolean;
+  reloaded: boolean;
+  restart_required: string[];
+  rollback_of: string | null;
+  rolled_back_at: string | null;
+  created_at: string;
+}
+
+export interface DeploymentEnvelope {
+  deployment: DeploymentSummary;
+}
+
+export interface DeploymentListResponse {
+  config_path: string | null;
+  live_reload: boolean;
+  items: DeploymentSummary[];
+}
+
+export type CircuitBreakerState = "closed" | "open" | "half_open";
+
+export interface CircuitBreakerSnapshot {
+  node: string;
+  state: CircuitBreakerState;
+  consecutive_failures: number;
+  total_failures: number;
+  short_circuits: number;
+  opened_at: string | null;
+  retry_at: string | null;
+  last_failure: string | null;
+}
+
+export interface CircuitBreakerListResponse {
+  available: boolean;
+  nodes: CircuitBreakerSnapshot[];
+}
+
 export interface LogoutResponse {
   ok: boolean;
 }
+
+export type ApiTokenScope =
+  | "drafts:read"
+  | "drafts:write"
+  | "logs:read"
+  | "admin";
+
+export interface ApiTokenSummary {
+  id: string;
+  name: string;
+  token_prefix: string;
+  scopes: ApiTokenScope[];
+  created_at: string;
+  last_used_at: string | null;
+  expires_at: string | null;
+}
+
+export interface ApiTokenListResponse {
+  items: ApiTokenSummary[];
+}
+
+export interface CreateApiTokenRequest {
+  name: string;
+  scopes: ApiTokenScope[];
+  expires_in_days?: number | null;
+}
+
+export interface CreateApiTokenResponse {
+  token: ApiTokenSummary;
+  secret: string;
+}
+
+export interface TotpStatusResponse {
+  enabled: boolean;
+  required: boolean;
+  recovery_codes_remaining: number;
+}
+
+export interface TotpSetupResponse {
+  secret: string;
+  otpauth_uri: string;
+  qr_svg: string;
+}
+
+export interface TotpRecoveryCodesResponse {
+  recovery_codes: string[];
+}
+
+export interface GoogleOAuthStartResponse {
+  authorize_url: string;
+  redirect_uri: string;
+}
@@ -98,6 +98,12 @@ const router = createRouter({
       component: () => import("@/views/LogsView.vue"),
       meta: { requiresAuth: true, requiresAdmin: true },
     },
+    {
+      path: "/audit",
+      name: "audit",
+      component: () => import("@/views/AuditView.vue"),
+      meta: { requiresAuth: true, requiresAdmin: true },
+    },
   ],
 });
 
@@ -109,9 +115,17 @@ router.beforeEach(async (to, from) => {
     return { name: "login" };
   }
 
+  if (
+    sessionStore.totpEnrollmentRequired &&
+    to.meta.requiresAuth &&
+    to.name !== "account"
+  ) {
+    return { name: "account" };
+  }
+
   if (to.meta.requiresAdmin && !sessionStore.isAdmin) {
     if (
-      (to.name === "logs" || to.name === "users") &&
+      (to.name === "logs" || to.name === "users" || to.name === "audit") &&
       to.query.access === "forbidden"
     ) {
       return true;
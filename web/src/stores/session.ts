@@ -7,11 +7,13 @@ import {
   logout,
   register,
 } from "@/api/client";
-import type { SessionUser } from "@/api/types";
+import type { LoginRequest, SessionUser } from "@/api/types";
 
 export const useSessionStore = defineStore("session", {
   state: () => ({
     user: null as SessionUser | null,
+    /** Admin policy requires TOTP enrollment before anything else. */
+    totpEnrollmentRequired: false,
     bootstrapped: false,
   }),
   getters: {
@@ -26,6 +28,7 @@ export const useSessionStore = defineStore("session", {
       try {
         const response = await getCurrentUser();
         this.user = response.user;
+        this.totpEnrollmentRequired = response.totp_enrollment_required;
       } catch (error) {
         if (!(error instanceof ApiError) || error.status !== 401) {
           throw error;
@@ -35,9 +38,10 @@ export const useSessionStore = defineStore("session", {
         this.bootstrapped = true;
       }
     },
-    async signIn(payload: { login: string; password: string }) {
+    async signIn(payload: LoginRequest) {
       const response = await login(payload);
       this.user = response.user;
+      this.totpEnrollmentRequired = response.totp_enrollment_required;
       this.bootstrapped = true;
       return response.user;
     },
@@ -51,6 +55,7 @@ export const useSessionStore = defineStore("session", {
     async signOut() {
       await logout();
       this.user = null;
+      this.totpEnrollmentRequired = false;
       this.bootstrapped = true;
     },
   },